@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use juniper::meta::MetaType;
+use juniper::{
+	parse_document_source, Definition, OperationType, SchemaType, Selection, Spanning, Type,
+	Variables,
+};
+
+use crate::api::request::ApiRequest;
+use crate::api::schema::fields::find_property;
+use crate::api::schema::{get_type_data, AlchemyScalarValue, Schema};
+use crate::lib::CONFIG;
+
+type Fragments<'a> = HashMap<&'a str, (&'a str, &'a [Selection<'a, AlchemyScalarValue>])>;
+
+#[derive(Default, Debug)]
+pub struct QueryCost {
+	pub depth: u32,
+	pub complexity: u64,
+}
+
+/// Rejects requests whose depth or estimated cost exceed the configured limits
+pub fn check_query_limits(request: &ApiRequest, schema: &Schema) -> Result<(), String> {
+	let max_depth = CONFIG.get_max_query_depth();
+	let max_complexity = CONFIG.get_max_query_complexity();
+
+	if max_depth.is_none() && max_complexity.is_none() {
+		return Ok(());
+	}
+
+	// Invalid documents are reported by the executor itself
+	let cost = match analyze_query(request, schema) {
+		Some(cost) => cost,
+		None => return Ok(()),
+	};
+
+	if let Some(max_depth) = max_depth {
+		if cost.depth > max_depth {
+			return Err(format!(
+				"Query depth {} exceeds the maximum of {}",
+				cost.depth, max_depth
+			));
+		}
+	}
+
+	if let Some(max_complexity) = max_complexity {
+		if cost.complexity > max_complexity {
+			return Err(format!(
+				"Query complexity {} exceeds the maximum of {}",
+				cost.complexity, max_complexity
+			));
+		}
+	}
+
+	Ok(())
+}
+
+/// Rejects `__schema` and `__type` queries when introspection is disabled
+pub fn check_introspection(
+	request: &ApiRequest,
+	schema: &SchemaType<AlchemyScalarValue>,
+) -> Result<(), String> {
+	if CONFIG.is_introspection_enabled() {
+		return Ok(());
+	}
+
+	let document = match parse_document_source(request.query.as_str(), schema) {
+		Ok(document) => document,
+		Err(_) => return Ok(()),
+	};
+
+	let introspects = document.iter().any(|definition| match definition {
+		Definition::Operation(Spanning { item: op, .. }) => has_introspection(&op.selection_set),
+		Definition::Fragment(Spanning { item: f, .. }) => has_introspection(&f.selection_set),
+	});
+
+	match introspects {
+		true => Err("GraphQL introspection is not allowed".to_string()),
+		false => Ok(()),
+	}
+}
+
+fn has_introspection(selection_set: &[Selection<AlchemyScalarValue>]) -> bool {
+	selection_set.iter().any(|selection| match selection {
+		Selection::Field(Spanning { item: f, .. }) => {
+			f.name.item == "__schema"
+				|| f.name.item == "__type"
+				|| f.selection_set
+					.as_ref()
+					.map_or(false, |s| has_introspection(s))
+		}
+		Selection::InlineFragment(Spanning { item: f, .. }) => has_introspection(&f.selection_set),
+		Selection::FragmentSpread(_) => false,
+	})
+}
+
+/// Depth and complexity of the operation, each field counting for 1 unless its `@cost`
+/// directive sets another complexity
+pub fn analyze_query(request: &ApiRequest, schema: &Schema) -> Option<QueryCost> {
+	let document = parse_document_source(request.query.as_str(), &schema.schema).ok()?;
+
+	let mut fragments: Fragments = HashMap::new();
+	let mut operations = Vec::new();
+
+	for definition in &document {
+		match definition {
+			Definition::Operation(Spanning { item: op, .. }) => operations.push(op),
+			Definition::Fragment(Spanning { item: f, .. }) => {
+				fragments.insert(f.name.item, (f.type_condition.item, &f.selection_set[..]));
+			}
+		}
+	}
+
+	let operation = match &request.operation_name {
+		Some(name) => operations
+			.into_iter()
+			.find(|op| op.name.as_ref().map(|n| n.item) == Some(name.as_str()))?,
+		None if operations.len() == 1 => operations.pop()?,
+		None => return None,
+	};
+
+	let root_type = match operation.operation_type {
+		OperationType::Query => schema.schema.concrete_query_type(),
+		OperationType::Mutation => schema.schema.concrete_mutation_type()?,
+		OperationType::Subscription => return None,
+	};
+
+	let mut variables = request.get_variables();
+
+	// Variables left out of the request take the default of their definition
+	for (name, definition) in operation
+		.variable_definitions
+		.iter()
+		.flat_map(|d| d.item.items.iter())
+	{
+		if let Some(default) = &definition.default_value {
+			variables
+				.entry(name.item.to_string())
+				.or_insert_with(|| default.item.clone());
+		}
+	}
+
+	let analyzer = QueryAnalyzer {
+		schema,
+		fragments,
+		variables,
+		default_list_size: CONFIG.get_query_default_list_size(),
+	};
+
+	Some(analyzer.measure(&operation.selection_set, root_type, 1, &mut Vec::new()))
+}
+
+struct QueryAnalyzer<'a, 's> {
+	schema: &'s Schema,
+	fragments: Fragments<'a>,
+	variables: Variables<AlchemyScalarValue>,
+	default_list_size: u64,
+}
+
+impl<'a, 's> QueryAnalyzer<'a, 's> {
+	fn get_field_cost(&self, meta_type: &MetaType<AlchemyScalarValue>, name: &str) -> u64 {
+		meta_type
+			.name()
+			.and_then(|type_name| {
+				get_type_data(self.schema, type_name).and_then(|data| {
+					find_property(&data.entity, type_name, name).and_then(|p| p.directives.cost)
+				})
+			})
+			.unwrap_or(1)
+	}
+
+	fn measure(
+		&self,
+		selection_set: &'a [Selection<'a, AlchemyScalarValue>],
+		meta_type: &MetaType<AlchemyScalarValue>,
+		depth: u32,
+		spreads: &mut Vec<&'a str>,
+	) -> QueryCost {
+		let mut cost = QueryCost {
+			depth,
+			complexity: 0,
+		};
+
+		for selection in selection_set {
+			let inner = match selection {
+				Selection::Field(Spanning { item: f, .. }) => {
+					if f.name.item.starts_with("__") {
+						continue;
+					}
+
+					let meta_field = match meta_type.field_by_name(f.name.item) {
+						Some(field) => field,
+						None => continue,
+					};
+
+					let field_cost = self.get_field_cost(meta_type, f.name.item);
+
+					let inner_selection_set = match &f.selection_set {
+						Some(s) => s,
+						None => {
+							cost.complexity = cost.complexity.saturating_add(field_cost);
+							continue;
+						}
+					};
+
+					let inner_type = match self
+						.schema
+						.schema
+						.concrete_type_by_name(meta_field.field_type.innermost_name())
+					{
+						Some(t) => t,
+						None => continue,
+					};
+
+					let multiplier = match meta_field.field_type {
+						Type::List(_) | Type::NonNullList(_) => f
+							.arguments
+							.as_ref()
+							.and_then(|args| args.item.get("limit"))
+							.and_then(|limit| match &limit.item {
+								juniper::InputValue::Variable(name) => {
+									self.variables.get(name).and_then(|v| v.as_int_value())
+								}
+								value => value.as_int_value(),
+							})
+							.map(|limit| limit.max(0) as u64)
+							.unwrap_or(self.default_list_size),
+						_ => 1,
+					};
+
+					let inner = self.measure(inner_selection_set, inner_type, depth + 1, spreads);
+
+					QueryCost {
+						depth: inner.depth,
+						complexity: field_cost
+							.saturating_add(multiplier.saturating_mul(inner.complexity)),
+					}
+				}
+				Selection::FragmentSpread(Spanning { item: spread, .. }) => {
+					let name = spread.name.item;
+
+					// Cyclic fragments are rejected by validation, they are only skipped here
+					if spreads.contains(&name) {
+						continue;
+					}
+
+					let (type_condition, fragment_selection_set) = match self.fragments.get(name) {
+						Some(fragment) => *fragment,
+						None => continue,
+					};
+
+					let fragment_type = self
+						.schema
+						.schema
+						.concrete_type_by_name(type_condition)
+						.unwrap_or(meta_type);
+
+					spreads.push(name);
+					let inner = self.measure(fragment_selection_set, fragment_type, depth, spreads);
+					spreads.pop();
+
+					inner
+				}
+				Selection::InlineFragment(Spanning { item: inline, .. }) => {
+					let fragment_type = inline
+						.type_condition
+						.as_ref()
+						.and_then(|t| self.schema.schema.concrete_type_by_name(t.item))
+						.unwrap_or(meta_type);
+
+					self.measure(&inline.selection_set, fragment_type, depth, spreads)
+				}
+			};
+
+			cost.depth = cost.depth.max(inner.depth);
+			cost.complexity = cost.complexity.saturating_add(inner.complexity);
+		}
+
+		cost
+	}
+}
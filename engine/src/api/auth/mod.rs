@@ -0,0 +1,234 @@
+use actix_web::{http::header, HttpRequest as ActixRequest};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::api::schema::SchemaKind;
+use crate::lib::database::api::{DbAction, DbEntity, DbProperty};
+use crate::lib::schema::SchemaActionKind;
+use crate::lib::CONFIG;
+
+pub mod oidc;
+pub mod permissions;
+
+pub const ADMIN_ROLE: &str = "admin";
+pub const DEFAULT_ROLE: &str = "user";
+
+/// The claims Alchemy reads from a JWT, any other claim is kept in `extra`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Claims {
+	pub sub: Option<String>,
+	pub role: Option<String>,
+	pub exp: usize,
+	#[serde(flatten)]
+	pub extra: JsonMap<String, JsonValue>,
+}
+
+/// Signs the claims with the configured secret, `None` when authentication is disabled
+pub fn encode_token(claims: &Claims) -> Option<String> {
+	let secret = CONFIG.get_jwt_secret()?;
+
+	encode(
+		&Header::new(Algorithm::HS256),
+		claims,
+		&EncodingKey::from_secret(secret.as_bytes()),
+	)
+	.ok()
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+	InvalidToken,
+	MissingCredentials,
+}
+
+impl ToString for AuthError {
+	fn to_string(&self) -> String {
+		match self {
+			AuthError::InvalidToken => "Invalid authorization token",
+			AuthError::MissingCredentials => "Missing authorization credentials",
+		}
+		.to_string()
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Session {
+	pub role: String,
+	pub user_id: Option<String>,
+	pub claims: JsonMap<String, JsonValue>,
+	pub anonymous: bool,
+}
+
+impl Session {
+	/// Session used when authentication is disabled (no JWT secret configured)
+	pub fn admin() -> Self {
+		Self {
+			role: ADMIN_ROLE.to_string(),
+			user_id: None,
+			claims: JsonMap::new(),
+			anonymous: false,
+		}
+	}
+
+	pub fn anonymous(role: String) -> Self {
+		Self {
+			role,
+			user_id: None,
+			claims: JsonMap::new(),
+			anonymous: true,
+		}
+	}
+
+	pub fn from_claims(claims: Claims) -> Self {
+		Self {
+			role: claims.role.unwrap_or_else(|| DEFAULT_ROLE.to_string()),
+			user_id: claims.sub,
+			claims: claims.extra,
+			anonymous: false,
+		}
+	}
+
+	pub fn from_request(req: &ActixRequest) -> Result<Self, AuthError> {
+		Self::from_authorization(
+			req.headers()
+				.get(header::AUTHORIZATION)
+				.and_then(|h| h.to_str().ok()),
+		)
+	}
+
+	/// Session of the `Authorization` value, shared by the HTTP routes and the gRPC metadata
+	pub fn from_authorization(authorization: Option<&str>) -> Result<Self, AuthError> {
+		let secret = match CONFIG.get_jwt_secret() {
+			Some(secret) => secret,
+			None => return Ok(Self::admin()),
+		};
+
+		match authorization.and_then(|h| h.strip_prefix("Bearer ")) {
+			Some(token) => decode::<Claims>(
+				token,
+				&DecodingKey::from_secret(secret.as_bytes()),
+				&Validation::new(Algorithm::HS256),
+			)
+			.map(|data| Self::from_claims(data.claims))
+			.map_err(|_| AuthError::InvalidToken),
+			None => CONFIG
+				.get_anonymous_role()
+				.map(Self::anonymous)
+				.ok_or(AuthError::MissingCredentials),
+		}
+	}
+
+	/// Anonymous sessions can only run queries over the collections or operations
+	/// listed in the `ANONYMOUS_ACCESS` setting
+	pub fn can_execute(&self, operation_name: &str, entity: &DbEntity, kind: &SchemaKind) -> bool {
+		if !self.anonymous {
+			return true;
+		}
+
+		*kind == SchemaKind::Query
+			&& CONFIG
+				.get_anonymous_access()
+				.iter()
+				.any(|a| a == operation_name || *a == entity.collection_name)
+	}
+
+	/// Anonymous sessions can only run the query actions listed in `ANONYMOUS_ACCESS`,
+	/// others the actions allowing their role
+	pub fn can_execute_action(&self, action: &DbAction) -> bool {
+		if self.anonymous {
+			return action.kind == SchemaActionKind::Query
+				&& CONFIG
+					.get_anonymous_access()
+					.iter()
+					.any(|a| *a == action.name);
+		}
+
+		self.role == ADMIN_ROLE || action.roles.is_empty() || action.roles.contains(&self.role)
+	}
+
+	/// Sent to the actions and hooks handlers
+	pub fn to_json(&self) -> JsonValue {
+		serde_json::json!({
+			"role": self.role,
+			"userId": self.user_id,
+			"claims": self.claims,
+		})
+	}
+
+	/// Session variables exposed to AQL as `@session_{name}`
+	pub fn get_variables(&self) -> JsonMap<String, JsonValue> {
+		let mut variables = JsonMap::new();
+
+		for (k, v) in &self.claims {
+			if is_variable_name(k) {
+				variables.insert(k.clone(), v.clone());
+			}
+		}
+
+		variables.insert("role".to_string(), JsonValue::String(self.role.clone()));
+		variables.insert(
+			"user_id".to_string(),
+			self.user_id
+				.clone()
+				.map(JsonValue::String)
+				.unwrap_or(JsonValue::Null),
+		);
+
+		variables
+	}
+
+	/// Bind variables of the session variables used by a query, recorded while building its
+	/// nodes. ArangoDB rejects queries with unused bind variables so the rest are left out,
+	/// and the claims missing from the session are bound as `null`
+	pub fn get_bind_vars(&self, names: &[String]) -> Vec<(String, JsonValue)> {
+		let variables = self.get_variables();
+		let mut bind_vars: Vec<(String, JsonValue)> = Vec::new();
+
+		for name in names {
+			let key = format!("session_{}", name);
+
+			if bind_vars.iter().any(|(k, _)| *k == key) {
+				continue;
+			}
+
+			let value = variables.get(name).cloned().unwrap_or(JsonValue::Null);
+
+			bind_vars.push((key, value));
+		}
+
+		bind_vars
+	}
+
+	/// Drafts are read by the roles listed in `PREVIEW_ROLES`, by any authenticated role when
+	/// it's unset
+	pub fn can_preview(&self) -> bool {
+		if self.anonymous {
+			return false;
+		}
+
+		let roles = CONFIG.get_preview_roles();
+
+		self.role == ADMIN_ROLE || roles.is_empty() || roles.contains(&self.role)
+	}
+
+	pub fn can_read(&self, entity: &DbEntity) -> bool {
+		!self.anonymous
+			|| CONFIG
+				.get_anonymous_access()
+				.iter()
+				.any(|a| *a == entity.collection_name)
+	}
+
+	/// Fields with an `@auth` directive are only read by the roles it lists and the admin
+	pub fn can_read_field(&self, property: &DbProperty) -> bool {
+		match &property.directives.auth_roles {
+			Some(roles) => self.role == ADMIN_ROLE || roles.contains(&self.role),
+			None => true,
+		}
+	}
+}
+
+pub fn is_variable_name(name: &str) -> bool {
+	!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
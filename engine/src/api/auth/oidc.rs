@@ -0,0 +1,210 @@
+use actix_web::{
+	cookie::Cookie,
+	http::header,
+	web::{self, Query},
+	Error as ActixError, HttpRequest as ActixRequest, HttpResponse as ActixResponse,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use url::Url;
+
+use crate::api::auth::{encode_token, Claims, DEFAULT_ROLE};
+use crate::lib::CONFIG;
+
+const STATE_COOKIE: &str = "alchemy_oidc_state";
+
+/// Claims of the issued token set by Alchemy or validated with it, a provider can't set them
+/// through the user info
+const RESERVED_CLAIMS: [&str; 8] = ["sub", "role", "exp", "iat", "nbf", "iss", "aud", "jti"];
+
+/// Subset of the provider discovery document used by the authorization code flow
+#[derive(Deserialize)]
+struct ProviderMetadata {
+	authorization_endpoint: String,
+	token_endpoint: String,
+	userinfo_endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+	access_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+	code: String,
+	state: String,
+}
+
+pub fn configure_oidc(cfg: &mut web::ServiceConfig) {
+	if CONFIG.get_oidc_issuer().is_none() {
+		return;
+	}
+
+	cfg.service(web::resource("/auth/oidc/login").route(web::get().to(oidc_login_route)))
+		.service(web::resource("/auth/oidc/callback").route(web::get().to(oidc_callback_route)));
+}
+
+async fn get_provider_metadata() -> Result<ProviderMetadata, reqwest::Error> {
+	let issuer = CONFIG.get_oidc_issuer().unwrap();
+
+	reqwest::get(format!(
+		"{}/.well-known/openid-configuration",
+		issuer.trim_end_matches('/')
+	))
+	.await?
+	.json::<ProviderMetadata>()
+	.await
+}
+
+fn bad_gateway(message: &str) -> ActixResponse {
+	ActixResponse::BadGateway().json(serde_json::json!({ "error": message }))
+}
+
+pub async fn oidc_login_route() -> Result<ActixResponse, ActixError> {
+	let metadata = match get_provider_metadata().await {
+		Ok(metadata) => metadata,
+		Err(_) => return Ok(bad_gateway("Unable to reach the OIDC provider")),
+	};
+
+	let state: String = rand::thread_rng()
+		.sample_iter(&Alphanumeric)
+		.take(32)
+		.map(char::from)
+		.collect();
+
+	let url = Url::parse_with_params(
+		metadata.authorization_endpoint.as_str(),
+		&[
+			("response_type", "code"),
+			(
+				"client_id",
+				CONFIG.oidc_client_id.as_deref().unwrap_or_default(),
+			),
+			(
+				"redirect_uri",
+				CONFIG.oidc_redirect_url.as_deref().unwrap_or_default(),
+			),
+			("scope", CONFIG.get_oidc_scopes().as_str()),
+			("state", state.as_str()),
+		],
+	)
+	.map_err(actix_web::error::ErrorInternalServerError)?;
+
+	Ok(ActixResponse::Found()
+		.cookie(
+			Cookie::build(STATE_COOKIE, state)
+				.path("/auth/oidc")
+				.http_only(true)
+				.secure(CONFIG.is_production())
+				.finish(),
+		)
+		.insert_header((header::LOCATION, url.to_string()))
+		.finish())
+}
+
+pub async fn oidc_callback_route(
+	req: ActixRequest,
+	query: Query<CallbackQuery>,
+) -> Result<ActixResponse, ActixError> {
+	let expected_state = req.cookie(STATE_COOKIE).map(|c| c.value().to_string());
+
+	if expected_state.as_deref() != Some(query.state.as_str()) {
+		return Ok(ActixResponse::BadRequest().json(serde_json::json!({
+			"error": "Invalid OIDC state"
+		})));
+	}
+
+	let metadata = match get_provider_metadata().await {
+		Ok(metadata) => metadata,
+		Err(_) => return Ok(bad_gateway("Unable to reach the OIDC provider")),
+	};
+
+	let client = reqwest::Client::new();
+
+	let token = client
+		.post(metadata.token_endpoint.as_str())
+		.form(&[
+			("grant_type", "authorization_code"),
+			("code", query.code.as_str()),
+			(
+				"redirect_uri",
+				CONFIG.oidc_redirect_url.as_deref().unwrap_or_default(),
+			),
+			(
+				"client_id",
+				CONFIG.oidc_client_id.as_deref().unwrap_or_default(),
+			),
+			(
+				"client_secret",
+				CONFIG.oidc_client_secret.as_deref().unwrap_or_default(),
+			),
+		])
+		.send()
+		.await
+		.and_then(|r| r.error_for_status());
+
+	let token = match token {
+		Ok(response) => match response.json::<TokenResponse>().await {
+			Ok(token) => token,
+			Err(_) => return Ok(bad_gateway("Invalid OIDC token response")),
+		},
+		Err(_) => return Ok(bad_gateway("Unable to exchange the OIDC code")),
+	};
+
+	let user_info = client
+		.get(metadata.userinfo_endpoint.as_str())
+		.bearer_auth(token.access_token)
+		.send()
+		.await
+		.and_then(|r| r.error_for_status());
+
+	let mut user_info = match user_info {
+		Ok(response) => match response.json::<JsonMap<String, JsonValue>>().await {
+			Ok(info) => info,
+			Err(_) => return Ok(bad_gateway("Invalid OIDC user info response")),
+		},
+		Err(_) => return Ok(bad_gateway("Unable to fetch the OIDC user info")),
+	};
+
+	let sub = user_info
+		.remove("sub")
+		.and_then(|s| s.as_str().map(|s| s.to_string()));
+
+	// The tenant claim selects the database the token is valid for
+	user_info.remove(CONFIG.get_tenant_claim().as_str());
+
+	for claim in RESERVED_CLAIMS {
+		user_info.remove(claim);
+	}
+
+	let expires_in = CONFIG.get_jwt_expiration();
+
+	let claims = Claims {
+		sub,
+		role: Some(
+			CONFIG
+				.oidc_default_role
+				.clone()
+				.filter(|r| !r.is_empty())
+				.unwrap_or_else(|| DEFAULT_ROLE.to_string()),
+		),
+		exp: jsonwebtoken::get_current_timestamp() as usize + expires_in,
+		extra: user_info,
+	};
+
+	match encode_token(&claims) {
+		Some(token) => Ok(ActixResponse::Ok()
+			.cookie(Cookie::build(STATE_COOKIE, "").path("/auth/oidc").finish())
+			.json(serde_json::json!({
+				"token": token,
+				"expires_in": expires_in,
+			}))),
+		None => Ok(
+			ActixResponse::InternalServerError().json(serde_json::json!({
+				"error": "Unable to issue the Alchemy token"
+			})),
+		),
+	}
+}
@@ -0,0 +1,77 @@
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::api::auth::{is_variable_name, Session, ADMIN_ROLE};
+use crate::lib::database::api::{DbEntity, DbPermission};
+use crate::lib::database::aql::{
+	AQLFilterOperation, AQLLogicalFilter, AQLLogicalOperator, AQLNode, AQLOperation,
+	AQLQueryParameter, AQLSessionVariable,
+};
+
+fn get_permission<'a>(entity: &'a DbEntity, session: &Session) -> Option<&'a DbPermission> {
+	if session.role == ADMIN_ROLE {
+		return None;
+	}
+
+	entity.permissions.get(&session.role)
+}
+
+/// Filter restricting the documents of the entity to the ones the session can access
+pub fn get_permission_filter(entity: &DbEntity, session: &Session) -> Option<Box<dyn AQLNode>> {
+	let permission = get_permission(entity, session)?;
+
+	let mut nodes: Vec<Box<dyn AQLNode>> = Vec::new();
+
+	for (attribute, variable) in &permission.filter {
+		if !is_variable_name(variable) {
+			continue;
+		}
+
+		nodes.push(Box::new(AQLFilterOperation {
+			left_node: Box::new(AQLQueryParameter(attribute.clone())),
+			operation: AQLOperation::Equal,
+			right_node: Box::new(AQLSessionVariable(variable.clone())),
+		}));
+	}
+
+	if nodes.is_empty() {
+		None
+	} else {
+		Some(Box::new(AQLLogicalFilter {
+			nodes,
+			operation: AQLLogicalOperator::AND,
+		}))
+	}
+}
+
+/// Values of the session presets of the entity, `null` for the claims missing from the
+/// session so the attributes can't be given by the client instead
+pub fn get_preset_values(entity: &DbEntity, session: &Session) -> JsonMap<String, JsonValue> {
+	let presets = match get_permission(entity, session) {
+		Some(permission) => &permission.presets,
+		None => return JsonMap::new(),
+	};
+
+	let variables = session.get_variables();
+
+	presets
+		.iter()
+		.filter(|(_, variable)| is_variable_name(variable))
+		.map(|(attribute, variable)| {
+			let value = variables.get(variable).cloned().unwrap_or(JsonValue::Null);
+
+			(attribute.clone(), value)
+		})
+		.collect()
+}
+
+/// Merges the session presets of the entity into the AQL object of a new document. Their
+/// values are inlined as the attributes are, so no session variable has to be bound
+pub fn apply_presets(attributes: String, entity: &DbEntity, session: &Session) -> String {
+	let presets = get_preset_values(entity, session);
+
+	if presets.is_empty() {
+		return attributes;
+	}
+
+	format!("MERGE({}, {})", attributes, JsonValue::Object(presets))
+}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use futures_util::StreamExt;
+use lazy_static::lazy_static;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::api::schema::cache::apply_results_invalidation;
+use crate::api::schema::{get_loaded_schema_version, reload_schema, Schema};
+use crate::lib::schema::get_schema_version;
+use crate::lib::CONFIG;
+
+/// Channel the instances sharing the Redis server publish their messages to
+const CHANNEL: &str = "alchemy:cluster";
+
+/// Delay before subscribing again once the subscription is lost
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Delays before connecting again after a failed attempt, doubled after each one
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// State change of an instance, applied by the others
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ClusterMessage {
+	/// The collection tagged with its database was written, the query results read from it are
+	/// dropped
+	InvalidateResults { tag: String },
+	/// The schema was changed and its version bumped
+	ReloadSchema { version: u64 },
+}
+
+/// Connection to Redis, reconnecting by itself once established. Until then the attempts are
+/// spaced out so an unreachable server doesn't slow every request down
+struct Connector {
+	connection: Option<ConnectionManager>,
+	retry_at: Option<Instant>,
+	delay: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+	/// Instance publishing the message, it skips its own messages
+	instance: String,
+	message: ClusterMessage,
+}
+
+lazy_static! {
+	static ref CLIENT: Option<redis::Client> = CONFIG.get_redis_url().and_then(|url| {
+		redis::Client::open(url.as_str())
+			.map_err(|e| tracing::error!("Error opening the Redis client: {}", e))
+			.ok()
+	});
+	static ref CONNECTOR: Mutex<Connector> = Mutex::new(Connector {
+		connection: None,
+		retry_at: None,
+		delay: MIN_RECONNECT_DELAY,
+	});
+	static ref INSTANCE_ID: String = uuid::Uuid::new_v4().to_string();
+}
+
+/// Whether state is shared with the other instances through Redis
+pub fn is_redis_enabled() -> bool {
+	CLIENT.is_some()
+}
+
+/// Connection shared by the response cache, the rate limiter and the published messages.
+/// `None` when Redis isn't configured or can't be reached, the callers fall back to the state
+/// of the instance
+pub async fn get_redis_connection() -> Option<ConnectionManager> {
+	let client = CLIENT.as_ref()?;
+	let mut connector = CONNECTOR.lock().await;
+
+	if let Some(connection) = &connector.connection {
+		return Some(connection.clone());
+	}
+
+	if connector.retry_at.map_or(false, |at| Instant::now() < at) {
+		return None;
+	}
+
+	match ConnectionManager::new(client.clone()).await {
+		Ok(connection) => {
+			connector.connection = Some(connection.clone());
+			connector.retry_at = None;
+			connector.delay = MIN_RECONNECT_DELAY;
+
+			Some(connection)
+		}
+		Err(e) => {
+			tracing::error!(
+				"Error connecting to Redis, retrying in {:?}: {}",
+				connector.delay,
+				e
+			);
+
+			connector.retry_at = Some(Instant::now() + connector.delay);
+			connector.delay = (connector.delay * 2).min(MAX_RECONNECT_DELAY);
+
+			None
+		}
+	}
+}
+
+/// Publishes the message to the other instances, nothing is sent without Redis
+pub async fn publish(message: ClusterMessage) {
+	let mut connection = match get_redis_connection().await {
+		Some(connection) => connection,
+		None => return,
+	};
+
+	let payload = serde_json::to_string(&Envelope {
+		instance: INSTANCE_ID.clone(),
+		message,
+	})
+	.unwrap();
+
+	let result = redis::cmd("PUBLISH")
+		.arg(CHANNEL)
+		.arg(payload)
+		.query_async::<_, ()>(&mut connection)
+		.await;
+
+	if let Err(e) = result {
+		tracing::error!("Error publishing to the other instances: {}", e);
+	}
+}
+
+/// Applies the messages of the other instances, subscribing again when the connection is lost
+pub async fn run_cluster_subscriber(schema: Arc<ArcSwap<Schema>>) {
+	let client = match CLIENT.as_ref() {
+		Some(client) => client,
+		None => return,
+	};
+
+	loop {
+		if let Err(e) = subscribe(client, &schema).await {
+			tracing::error!("Error receiving the messages of the other instances: {}", e);
+		}
+
+		tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+	}
+}
+
+async fn subscribe(client: &redis::Client, schema: &ArcSwap<Schema>) -> redis::RedisResult<()> {
+	let mut pubsub = client.get_async_connection().await?.into_pubsub();
+
+	pubsub.subscribe(CHANNEL).await?;
+
+	let mut messages = pubsub.on_message();
+
+	while let Some(message) = messages.next().await {
+		let payload: String = message.get_payload()?;
+
+		match serde_json::from_str::<Envelope>(&payload) {
+			Ok(envelope) if envelope.instance != *INSTANCE_ID => {
+				apply(envelope.message, schema).await
+			}
+			Ok(_) => {}
+			Err(e) => tracing::warn!("Invalid message from another instance: {}", e),
+		}
+	}
+
+	Ok(())
+}
+
+async fn apply(message: ClusterMessage, schema: &ArcSwap<Schema>) {
+	tracing::debug!(?message, "Applying message of another instance");
+
+	match message {
+		ClusterMessage::InvalidateResults { tag } => apply_results_invalidation(&tag),
+		ClusterMessage::ReloadSchema { version } => {
+			if version > get_loaded_schema_version() {
+				tracing::info!("Reloading the schema changed by another instance");
+
+				reload_schema(schema).await;
+			}
+		}
+	}
+}
+
+/// Reloads the schema once its version changed, so the instances sharing the database serve
+/// the same schema even when the change notifications are missed
+pub async fn run_schema_watcher(schema: Arc<ArcSwap<Schema>>) {
+	let interval = match CONFIG.get_schema_poll_interval() {
+		Some(interval) => interval,
+		None => return,
+	};
+
+	loop {
+		tokio::time::sleep(interval).await;
+
+		match get_schema_version().await {
+			Ok(version) if version != get_loaded_schema_version() => {
+				tracing::info!("Reloading the schema, its version changed to {}", version);
+
+				reload_schema(&schema).await;
+			}
+			Ok(_) => {}
+			Err(e) => tracing::error!("Error reading the schema version: {}", e),
+		}
+	}
+}
+
+/// Round trip to Redis for the readiness probe, `None` when it isn't configured
+pub async fn check_redis() -> Option<Result<(), String>> {
+	if !is_redis_enabled() {
+		return None;
+	}
+
+	let mut connection = match get_redis_connection().await {
+		Some(connection) => connection,
+		None => return Some(Err("Connection failed".to_string())),
+	};
+
+	Some(
+		redis::cmd("PING")
+			.query_async::<_, String>(&mut connection)
+			.await
+			.map(|_| ())
+			.map_err(|e| e.to_string()),
+	)
+}
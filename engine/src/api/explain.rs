@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+
+use juniper::Variables;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::api::auth::Session;
+use crate::api::schema::{get_query_operation_entity, Context, Schema};
+use crate::lib::database::tenants::get_current_database_name;
+use crate::lib::database::DATABASE;
+use crate::lib::CONFIG;
+
+tokio::task_local! {
+	/// Queries of the operation being explained, collected instead of being sent to ArangoDB
+	static EXPLAINED_QUERIES: RefCell<Vec<(String, Vec<(String, JsonValue)>)>>;
+}
+
+/// AQL an operation generates with ArangoDB's plan for it
+pub struct ExplainedQuery {
+	pub aql: String,
+	pub bind_vars: JsonValue,
+	/// Response of the explain API, with the `plan` and its `warnings`
+	pub explain: JsonValue,
+}
+
+/// Collects the query when an operation is being explained, in which case it mustn't be sent
+pub fn capture_query(aql: &str, bind_vars: &[(String, JsonValue)]) -> bool {
+	EXPLAINED_QUERIES
+		.try_with(|queries| {
+			queries
+				.borrow_mut()
+				.push((aql.to_string(), bind_vars.to_vec()))
+		})
+		.is_ok()
+}
+
+async fn explain_query(aql: &str, bind_vars: &JsonValue) -> Result<JsonValue, String> {
+	let host = DATABASE.get().await.host().to_string();
+
+	let response = reqwest::Client::new()
+		.post(format!(
+			"{}/_db/{}/_api/explain",
+			host.trim_end_matches('/'),
+			get_current_database_name()
+		))
+		.basic_auth(&CONFIG.db_user, Some(&CONFIG.db_pass))
+		.json(&json!({ "query": aql, "bindVars": bind_vars }))
+		.send()
+		.await
+		.map_err(|e| e.to_string())?;
+
+	let status = response.status();
+	let body: JsonValue = response.json().await.map_err(|e| e.to_string())?;
+
+	match status.is_success() {
+		true => Ok(body),
+		false => Err(body["errorMessage"]
+			.as_str()
+			.map(String::from)
+			.unwrap_or_else(|| status.to_string())),
+	}
+}
+
+/// Runs the query operation `operation` with the arguments and selection without sending its
+/// queries to ArangoDB, which explains them instead. `arguments` and `selection` are written
+/// as in a GraphQL document, e.g. `where: { age: { gt: 18 } }` and `{ name posts { title } }`
+pub async fn explain_operation(
+	schema: &Schema,
+	operation: &str,
+	arguments: Option<&str>,
+	selection: Option<&str>,
+) -> Result<Vec<ExplainedQuery>, String> {
+	// Other fields of the root type, like actions, would be executed
+	if get_query_operation_entity(schema, operation).is_none() {
+		return Err(format!("Query operation {} not found", operation));
+	}
+
+	let document = format!(
+		"query {{ {}{} {} }}",
+		operation,
+		arguments
+			.filter(|a| !a.trim().is_empty())
+			.map(|a| format!("({})", a))
+			.unwrap_or_default(),
+		selection.unwrap_or("{ _key }")
+	);
+
+	let context = Context::new(Session::admin());
+
+	let (result, queries) = EXPLAINED_QUERIES
+		.scope(RefCell::new(Vec::new()), async {
+			let result =
+				juniper::execute(&document, None, schema, &Variables::new(), &context).await;
+
+			(result, EXPLAINED_QUERIES.with(|q| q.take()))
+		})
+		.await;
+
+	// Errors of the null results returned instead of the documents are left out
+	match result {
+		Err(e) => return Err(e.to_string()),
+		Ok((_, errors)) if queries.is_empty() => {
+			if let Some(error) = errors.first() {
+				return Err(error.error().message().to_string());
+			}
+		}
+		Ok(_) => (),
+	}
+
+	let mut explained = Vec::new();
+
+	for (aql, bind_vars) in queries {
+		let bind_vars = JsonValue::Object(bind_vars.into_iter().collect::<JsonMap<_, _>>());
+		let explain = explain_query(&aql, &bind_vars).await?;
+
+		explained.push(ExplainedQuery {
+			aql,
+			bind_vars,
+			explain,
+		});
+	}
+
+	Ok(explained)
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_web::{
+	http::header,
+	web::{Bytes, Path, Query},
+	Error as ActixError, HttpRequest as ActixRequest, HttpResponse as ActixResponse,
+};
+use convert_case::Casing;
+use juniper::validation::validate_input_values;
+use juniper::{parse_document_source, Definition, FromInputValue, InputValue, Variables};
+use rust_arango::{AqlQuery, Database};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::auth::Session;
+use crate::api::import::DataFormat;
+use crate::api::rate_limit::RATE_LIMITER;
+use crate::api::schema::input::filter::{
+	check_filter_access, check_filter_values, get_aql_filter_from_entity_filter,
+	get_property_types, EntityFilter,
+};
+use crate::api::schema::operations::publish::get_published_filter;
+use crate::api::schema::{get_collection_entity, AlchemyScalarValue, Schema};
+use crate::api::server::{error_response, rate_limited_response};
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::api::{DbEntity, DbScalarType};
+use crate::lib::database::aql::get_session_variables;
+use crate::lib::database::DATABASE;
+
+/// Documents fetched from ArangoDB at once, the next batch is only fetched once the client
+/// has read the previous one
+const EXPORT_BATCH_SIZE: u32 = 1000;
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+	/// `csv` or `ndjson`, read from the `Accept` header when omitted
+	format: Option<String>,
+	/// JSON filter with the same shape as the `where` argument of the API
+	r#where: Option<String>,
+	/// Comma separated fields to export, every field of the entity when omitted
+	fields: Option<String>,
+	limit: Option<u32>,
+}
+
+/// Checks the filter against the `where` argument type of the schema, so invalid filters are
+/// rejected like they are on the API
+fn parse_filter(
+	schema: &Schema,
+	entity: &DbEntity,
+	filter: &str,
+) -> Result<EntityFilter<'static, AlchemyScalarValue>, String> {
+	let value: InputValue<AlchemyScalarValue> =
+		serde_json::from_str(filter).map_err(|e| format!("Invalid filter: {}", e))?;
+
+	let source = format!(
+		"query($where: {}BoolExp) {{ __typename }}",
+		entity.name.to_case(convert_case::Case::Pascal)
+	);
+
+	let document = parse_document_source(&source, &schema.schema)
+		.map_err(|e| format!("Invalid filter: {}", e))?;
+
+	let operation = document
+		.iter()
+		.find_map(|d| match d {
+			Definition::Operation(operation) => Some(operation),
+			_ => None,
+		})
+		.ok_or_else(|| "Invalid filter".to_string())?;
+
+	let mut variables = Variables::new();
+
+	variables.insert("where".to_string(), value.clone());
+
+	if let Some(error) = validate_input_values(&variables, operation, &schema.schema).first() {
+		return Err(error.message().to_string());
+	}
+
+	EntityFilter::from_input_value(&value).ok_or_else(|| "Invalid filter".to_string())
+}
+
+/// Fields exported, the requested ones or all of them, leaving out the fields with an `@auth`
+/// directive the session can't read
+fn get_fields(
+	entity: &DbEntity,
+	fields: &Option<String>,
+	session: &Session,
+) -> Result<Vec<String>, String> {
+	let properties: Vec<String> = std::iter::once("_key".to_string())
+		.chain(
+			entity
+				.properties
+				.iter()
+				.chain(entity.get_variant_properties())
+				.filter(|p| session.can_read_field(p))
+				.map(|p| p.name.clone()),
+		)
+		.collect();
+
+	let fields = match fields.as_deref().filter(|f| !f.is_empty()) {
+		Some(fields) => fields.split(',').map(|f| f.trim().to_string()).collect(),
+		None => return Ok(properties),
+	};
+
+	match fields.iter().find(|f| !properties.contains(f)) {
+		Some(field) => Err(format!("Field {} not found in {}", field, entity.name)),
+		None => Ok(fields),
+	}
+}
+
+/// AQL returning the fields of the documents the session can read, matching the filter, with
+/// the session variables it uses
+fn get_export_aql(
+	entity: &DbEntity,
+	session: &Session,
+	filter: Option<EntityFilter<AlchemyScalarValue>>,
+	limit: Option<u32>,
+) -> (String, Vec<String>) {
+	// Filters on values that can't be compared are left out, like on the API
+	let properties: HashMap<String, DbScalarType> = entity
+		.properties
+		.iter()
+		.filter(|p| match &p.scalar_type {
+			DbScalarType::Object | DbScalarType::Bytes => false,
+			DbScalarType::Array(item) => item.get_embedded().is_some(),
+			_ => true,
+		})
+		.map(|p| (p.name.clone(), p.scalar_type.clone()))
+		.collect();
+
+	let mut filters = Vec::new();
+
+	if let Some(filter) =
+		filter.and_then(|f| get_aql_filter_from_entity_filter(&f.filter_arguments, &properties))
+	{
+		filters.push(filter);
+	}
+
+	if let Some(filter) = get_permission_filter(entity, session) {
+		filters.push(filter);
+	}
+
+	if let Some(filter) = get_published_filter(entity) {
+		filters.push(filter);
+	}
+
+	let aql = format!(
+		"FOR i_0 IN @@collection {} {} RETURN KEEP(i_0, @fields)",
+		filters
+			.iter()
+			.map(|f| format!("FILTER {}", f.describe(0)))
+			.collect::<Vec<String>>()
+			.join(" "),
+		limit.map(|l| format!("LIMIT {}", l)).unwrap_or_default()
+	);
+
+	(
+		aql,
+		get_session_variables(filters.iter().map(|f| f.as_ref())),
+	)
+}
+
+fn to_csv_value(value: Option<&JsonValue>) -> String {
+	let value = match value {
+		None | Some(JsonValue::Null) => return String::new(),
+		Some(JsonValue::String(s)) => s.clone(),
+		Some(value) => value.to_string(),
+	};
+
+	if value.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+		format!("\"{}\"", value.replace('"', "\"\""))
+	} else {
+		value
+	}
+}
+
+fn to_csv_row(values: impl Iterator<Item = String>) -> String {
+	let mut row = values.collect::<Vec<String>>().join(",");
+
+	row.push_str("\r\n");
+
+	row
+}
+
+/// Encodes the batches of documents as they're read from the cursor
+struct ExportCursor {
+	database: Database,
+	format: DataFormat,
+	fields: Vec<String>,
+	/// CSV header, sent with the first batch
+	header: Option<String>,
+	/// Batch read but not sent yet, the first one comes with the cursor
+	batch: Option<Vec<JsonValue>>,
+	cursor_id: Option<String>,
+}
+
+impl ExportCursor {
+	fn encode(&mut self, documents: Vec<JsonValue>) -> Bytes {
+		let mut out = self.header.take().unwrap_or_default();
+
+		for document in documents {
+			match self.format {
+				DataFormat::Csv => out.push_str(&to_csv_row(
+					self.fields.iter().map(|f| to_csv_value(document.get(f))),
+				)),
+				DataFormat::Ndjson => {
+					out.push_str(&document.to_string());
+					out.push('\n');
+				}
+			}
+		}
+
+		Bytes::from(out)
+	}
+
+	async fn next_chunk(mut self) -> Option<(Result<Bytes, ActixError>, Self)> {
+		let documents = match self.batch.take() {
+			Some(documents) => documents,
+			None => {
+				let cursor_id = self.cursor_id.take()?;
+
+				match self.database.aql_next_batch(cursor_id.as_str()).await {
+					Ok(cursor) => {
+						self.cursor_id = cursor.id.filter(|_| cursor.more);
+
+						cursor.result
+					}
+					Err(e) => {
+						return Some((Err(actix_web::error::ErrorInternalServerError(e)), self))
+					}
+				}
+			}
+		};
+
+		Some((Ok(self.encode(documents)), self))
+	}
+}
+
+/// Streams the documents of a collection the caller can read as CSV or newline delimited
+/// JSON, filtered like the `get` queries of the API
+pub async fn export_route(
+	req: ActixRequest,
+	collection: Path<String>,
+	params: Query<ExportParams>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let session = match Session::from_request(&req) {
+		Ok(session) => session,
+		Err(e) => return Ok(error_response(ActixResponse::Unauthorized(), e.to_string())),
+	};
+
+	if let Err(retry_after) = RATE_LIMITER.check(&req, &session).await {
+		return Ok(rate_limited_response(retry_after));
+	}
+
+	let accept = req
+		.headers()
+		.get(header::ACCEPT)
+		.and_then(|h| h.to_str().ok())
+		.and_then(|h| h.split(',').find_map(|t| DataFormat::parse(t.trim())));
+
+	let format = match params.format.as_deref() {
+		Some(format) => match DataFormat::parse(format) {
+			Some(format) => format,
+			None => {
+				return Ok(error_response(
+					ActixResponse::BadRequest(),
+					format!("Unsupported format {}", format),
+				))
+			}
+		},
+		None => accept.unwrap_or(DataFormat::Ndjson),
+	};
+
+	let schema = schema.load_full();
+
+	let entity: Arc<DbEntity> = match get_collection_entity(&schema, &collection) {
+		Some(entity) if session.can_read(&entity) => entity,
+		_ => {
+			return Ok(error_response(
+				ActixResponse::NotFound(),
+				format!("Collection {} not found", collection),
+			))
+		}
+	};
+
+	let filter = match params.r#where.as_deref().filter(|w| !w.is_empty()) {
+		Some(filter) => match parse_filter(&schema, &entity, filter) {
+			Ok(filter) => Some(filter),
+			Err(e) => return Ok(error_response(ActixResponse::BadRequest(), e)),
+		},
+		None => None,
+	};
+
+	if let Some(filter) = &filter {
+		if let Err(e) = check_filter_access(&filter.filter_arguments, &entity, &session) {
+			return Ok(error_response(ActixResponse::Forbidden(), e.to_string()));
+		}
+
+		let properties = get_property_types(&entity.properties);
+
+		if let Err(e) = check_filter_values(&filter.filter_arguments, &properties) {
+			return Ok(error_response(ActixResponse::BadRequest(), e.to_string()));
+		}
+	}
+
+	let fields = match get_fields(&entity, &params.fields, &session) {
+		Ok(fields) => fields,
+		Err(e) => return Ok(error_response(ActixResponse::BadRequest(), e)),
+	};
+
+	let (aql, session_variables) = get_export_aql(&entity, &session, filter, params.limit);
+
+	let mut query = AqlQuery::builder()
+		.query(aql.as_str())
+		.bind_var("@collection", entity.collection_name.as_str())
+		.bind_var("fields", fields.clone());
+
+	for (k, v) in session.get_bind_vars(&session_variables) {
+		query = query.bind_var(k, v);
+	}
+
+	let database = DATABASE.get().await.database();
+
+	let cursor = database
+		.aql_query_batch(query.batch_size(EXPORT_BATCH_SIZE).build())
+		.await
+		.map_err(actix_web::error::ErrorInternalServerError)?;
+
+	let (content_type, header) = match format {
+		DataFormat::Csv => ("text/csv", Some(to_csv_row(fields.iter().cloned()))),
+		DataFormat::Ndjson => ("application/x-ndjson", None),
+	};
+
+	let export = ExportCursor {
+		database,
+		format,
+		fields,
+		header,
+		batch: Some(cursor.result),
+		cursor_id: cursor.id.filter(|_| cursor.more),
+	};
+
+	Ok(ActixResponse::Ok()
+		.content_type(content_type)
+		.streaming(futures_util::stream::unfold(
+			export,
+			ExportCursor::next_chunk,
+		)))
+}
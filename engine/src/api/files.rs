@@ -0,0 +1,630 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use actix_multipart::Multipart;
+use actix_web::{
+	error::JsonPayloadError,
+	http::header,
+	web::{Bytes, Json, Path, Query},
+	Error as ActixError, HttpRequest as ActixRequest, HttpResponse as ActixResponse,
+};
+use futures_util::StreamExt;
+use lazy_static::lazy_static;
+use rust_arango::AqlQuery;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::auth::Session;
+use crate::api::rate_limit::RATE_LIMITER;
+use crate::api::request::ApiBatchRequest;
+use crate::api::schema::operations::publish::get_published_filter;
+use crate::api::schema::{get_collection_entity, get_mutation_operation_entity, SchemaKind};
+use crate::api::server::{error_response, execute_api_request, rate_limited_response};
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::api::{DbEntity, DbScalarType};
+use crate::lib::database::aql::get_session_variables;
+use crate::lib::database::DATABASE;
+use crate::lib::images::ImageTransform;
+use crate::lib::naming::get_operation_name;
+use crate::lib::storage::{
+	create_upload_url, get_derivative, get_file, get_storage, read_file, verify_local_signature,
+	write_file, DEFAULT_STORAGE,
+};
+use crate::lib::CONFIG;
+
+/// Prefix of the values replacing the files in the variables of multipart requests
+const UPLOAD_TOKEN_PREFIX: &str = "upload:";
+
+#[derive(Clone)]
+pub struct PendingUpload {
+	pub name: String,
+	pub mime: String,
+	pub content: Vec<u8>,
+}
+
+lazy_static! {
+	/// Files of the multipart requests being executed, by the token replacing them
+	static ref PENDING_UPLOADS: Mutex<HashMap<String, PendingUpload>> = Mutex::new(HashMap::new());
+}
+
+/// File of a multipart request being executed, if the value is the token replacing it
+pub fn take_upload(value: &str) -> Option<PendingUpload> {
+	if !value.starts_with(UPLOAD_TOKEN_PREFIX) {
+		return None;
+	}
+
+	PENDING_UPLOADS.lock().unwrap().get(value).cloned()
+}
+
+/// Value at a dotted path of the operations, e.g. `variables.file` or `0.variables.files.1`
+fn get_path_mut<'a>(value: &'a mut JsonValue, path: &str) -> Option<&'a mut JsonValue> {
+	path.split('.')
+		.try_fold(value, |value, segment| match value {
+			JsonValue::Array(items) => items.get_mut(segment.parse::<usize>().ok()?),
+			JsonValue::Object(object) => object.get_mut(segment),
+			_ => None,
+		})
+}
+
+/// Executes a request following the GraphQL multipart request spec, the `operations` field
+/// holds the request, `map` the variable paths of each file field
+pub async fn graphql_upload_route(
+	req: ActixRequest,
+	mut payload: Multipart,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let session = match Session::from_request(&req) {
+		Ok(session) => session,
+		Err(e) => return Ok(error_response(ActixResponse::Unauthorized(), e.to_string())),
+	};
+
+	if let Err(retry_after) = RATE_LIMITER.check(&req, &session).await {
+		return Ok(rate_limited_response(retry_after));
+	}
+
+	let max_size = CONFIG.get_upload_max_size();
+	let max_files = CONFIG.get_upload_max_files();
+
+	let mut operations: Option<JsonValue> = None;
+	let mut map: HashMap<String, Vec<String>> = HashMap::new();
+	let mut files: HashMap<String, PendingUpload> = HashMap::new();
+	let mut fields = 0;
+
+	while let Some(field) = payload.next().await {
+		let mut field = field?;
+
+		let disposition = field.content_disposition();
+		let name = disposition.get_name().unwrap_or_default().to_string();
+		let file_name = disposition.get_filename().map(String::from);
+		let mime = field.content_type().to_string();
+
+		// Each field is buffered, their number is bounded along with their size: the files and
+		// the `operations` and `map` fields
+		fields += 1;
+
+		if fields > max_files + 2 {
+			return Ok(error_response(
+				ActixResponse::PayloadTooLarge(),
+				format!("More than {} files uploaded", max_files),
+			));
+		}
+
+		let mut content = Vec::new();
+
+		while let Some(chunk) = field.next().await {
+			content.extend_from_slice(&chunk?);
+
+			if content.len() > max_size {
+				return Ok(error_response(
+					ActixResponse::PayloadTooLarge(),
+					format!("Field {} is larger than {} bytes", name, max_size),
+				));
+			}
+		}
+
+		match name.as_str() {
+			"operations" => {
+				operations =
+					Some(serde_json::from_slice(&content).map_err(JsonPayloadError::Deserialize)?)
+			}
+			"map" => {
+				map = serde_json::from_slice(&content).map_err(JsonPayloadError::Deserialize)?
+			}
+			_ => {
+				files.insert(
+					name.clone(),
+					PendingUpload {
+						name: file_name.unwrap_or(name),
+						mime,
+						content,
+					},
+				);
+			}
+		}
+	}
+
+	let mut operations = match operations {
+		Some(operations) => operations,
+		None => {
+			return Ok(error_response(
+				ActixResponse::BadRequest(),
+				"Missing operations field".to_string(),
+			))
+		}
+	};
+
+	let mut uploads = HashMap::new();
+
+	for (key, paths) in map {
+		let upload = match files.remove(&key) {
+			Some(upload) => upload,
+			None => {
+				return Ok(error_response(
+					ActixResponse::BadRequest(),
+					format!("File {} is missing", key),
+				))
+			}
+		};
+
+		let token = format!("{}{}", UPLOAD_TOKEN_PREFIX, uuid::Uuid::new_v4().simple());
+
+		for path in paths {
+			match get_path_mut(&mut operations, &path) {
+				Some(value) => *value = JsonValue::String(token.clone()),
+				None => {
+					return Ok(error_response(
+						ActixResponse::BadRequest(),
+						format!("Invalid path {} of file {}", path, key),
+					))
+				}
+			}
+		}
+
+		uploads.insert(token, upload);
+	}
+
+	let request: ApiBatchRequest =
+		serde_json::from_value(operations).map_err(JsonPayloadError::Deserialize)?;
+
+	// The first operation was counted before reading the files
+	if request.operation_count() > 1 {
+		let tokens = (request.operation_count() - 1).min(u32::MAX as usize) as u32;
+
+		if let Err(retry_after) = RATE_LIMITER.check_tokens(&req, &session, tokens).await {
+			return Ok(rate_limited_response(retry_after));
+		}
+	}
+
+	let tokens: Vec<String> = uploads.keys().cloned().collect();
+
+	PENDING_UPLOADS.lock().unwrap().extend(uploads);
+
+	let response = execute_api_request(session, request, &schema, None).await;
+
+	// Files the operations didn't store are dropped with the request
+	let mut pending = PENDING_UPLOADS.lock().unwrap();
+
+	for token in tokens {
+		pending.remove(&token);
+	}
+
+	response
+}
+
+#[derive(Deserialize)]
+pub struct SignedFileParams {
+	expires: Option<i64>,
+	signature: Option<String>,
+}
+
+/// Seconds clients and proxies cache the files of public storages
+const PUBLIC_FILE_MAX_AGE: u32 = 86400;
+
+/// Metadata of the file at the path or with the id, if the request is allowed to access it
+/// with the method. Files of private storages and uploads need a signed URL
+async fn get_signed_file(
+	path: &str,
+	method: &str,
+	params: &SignedFileParams,
+) -> Result<JsonValue, ActixResponse> {
+	let id = path.split('.').next().unwrap_or_default();
+
+	let file = match get_file(id).await {
+		Some(file) if file["path"] == path || file["id"] == path => file,
+		_ => {
+			return Err(error_response(
+				ActixResponse::NotFound(),
+				format!("File {} not found", path),
+			))
+		}
+	};
+
+	let is_private = get_storage(file["storage"].as_str().unwrap_or(DEFAULT_STORAGE))
+		.map(|s| s.is_private())
+		.unwrap_or(true);
+
+	if !is_private && method == "GET" {
+		return Ok(file);
+	}
+
+	let is_signed = match (params.expires, &params.signature) {
+		(Some(expires), Some(signature)) => verify_local_signature(
+			file["path"].as_str().unwrap_or_default(),
+			method,
+			expires,
+			signature,
+		),
+		_ => false,
+	};
+
+	match is_signed {
+		true => Ok(file),
+		false => Err(error_response(
+			ActixResponse::Forbidden(),
+			"Invalid or expired signature".to_string(),
+		)),
+	}
+}
+
+/// Serves the content of a file, for local storages without a public URL. Images are
+/// resized and converted with the `w`, `h`, `fit` and `format` parameters
+pub async fn file_route(
+	req: ActixRequest,
+	path: Path<String>,
+	params: Query<SignedFileParams>,
+	transform: Query<ImageTransform>,
+) -> Result<ActixResponse, ActixError> {
+	let file = match get_signed_file(&path, "GET", &params).await {
+		Ok(file) => file,
+		Err(response) => return Ok(response),
+	};
+
+	let mime = file["mime"].as_str().unwrap_or("application/octet-stream");
+
+	if !transform.is_empty() && !mime.starts_with("image/") {
+		return Ok(error_response(
+			ActixResponse::BadRequest(),
+			format!("File {} isn't an image", path),
+		));
+	}
+
+	if let Err(e) = transform.validate() {
+		return Ok(error_response(ActixResponse::BadRequest(), e.to_string()));
+	}
+
+	// Uploading through a signed URL again changes the size of the file
+	let etag = match transform.is_empty() {
+		true => format!(
+			"\"{}-{}\"",
+			file["id"].as_str().unwrap_or_default(),
+			file["size"]
+		),
+		false => format!(
+			"\"{}-{}-{}\"",
+			file["id"].as_str().unwrap_or_default(),
+			file["size"],
+			transform.get_variant(mime)
+		),
+	};
+
+	let cache_control = match params.signature {
+		Some(_) => "private, no-cache".to_string(),
+		None => format!("public, max-age={}", PUBLIC_FILE_MAX_AGE),
+	};
+
+	let is_fresh = req
+		.headers()
+		.get(header::IF_NONE_MATCH)
+		.and_then(|v| v.to_str().ok())
+		.map_or(false, |v| v.split(',').any(|t| t.trim() == etag));
+
+	if is_fresh {
+		return Ok(ActixResponse::NotModified()
+			.insert_header((header::ETAG, etag))
+			.insert_header((header::CACHE_CONTROL, cache_control))
+			.finish());
+	}
+
+	let result = match transform.is_empty() {
+		true => read_file(&file).await.map(|content| (content, mime)),
+		false => get_derivative(&file, &transform).await,
+	};
+
+	let (content, content_type) = match result {
+		Ok(result) => result,
+		Err(e) => return Ok(error_response(ActixResponse::NotFound(), e.to_string())),
+	};
+
+	Ok(ActixResponse::Ok()
+		.content_type(content_type)
+		.insert_header((header::ETAG, etag))
+		.insert_header((header::CACHE_CONTROL, cache_control))
+		.insert_header((
+			header::CONTENT_DISPOSITION,
+			format!(
+				"inline; filename={}",
+				serde_json::to_string(file["name"].as_str().unwrap_or_default()).unwrap()
+			),
+		))
+		.body(content))
+}
+
+/// Stores the content of a file through a signed upload URL of a local storage
+pub async fn file_upload_route(
+	path: Path<String>,
+	params: Query<SignedFileParams>,
+	body: Bytes,
+) -> Result<ActixResponse, ActixError> {
+	let file = match get_signed_file(&path, "PUT", &params).await {
+		Ok(file) => file,
+		Err(response) => return Ok(response),
+	};
+
+	match write_file(&file, &body).await {
+		Ok(_) => Ok(ActixResponse::Ok().finish()),
+		Err(e) => Ok(error_response(
+			ActixResponse::InternalServerError(),
+			e.to_string(),
+		)),
+	}
+}
+
+#[derive(Deserialize)]
+pub struct UploadUrlRequest {
+	collection: String,
+	property: String,
+	name: String,
+	mime: String,
+}
+
+/// Creates a file for a `File` property of a collection, responding with its `id` and the
+/// signed `url` the client uploads its content to with `PUT`, before setting the property.
+/// The session must be allowed to create documents of the collection
+pub async fn upload_url_route(
+	req: ActixRequest,
+	body: Json<UploadUrlRequest>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let session = match Session::from_request(&req) {
+		Ok(session) => session,
+		Err(e) => return Ok(error_response(ActixResponse::Unauthorized(), e.to_string())),
+	};
+
+	if let Err(retry_after) = RATE_LIMITER.check(&req, &session).await {
+		return Ok(rate_limited_response(retry_after));
+	}
+
+	let schema = schema.load();
+	let entity = get_collection_entity(&schema, &body.collection);
+
+	if let Some(entity) = &entity {
+		let operation_name = get_operation_name("create", &entity.name, false);
+
+		let can_create = get_mutation_operation_entity(&schema, &operation_name)
+			.map_or(false, |entity| {
+				session.can_execute(&operation_name, &entity, &SchemaKind::Mutation)
+			});
+
+		if !can_create {
+			return Ok(error_response(
+				ActixResponse::Forbidden(),
+				format!(
+					"Role {} is not allowed to execute {}",
+					session.role, operation_name
+				),
+			));
+		}
+	}
+
+	let storage = entity.and_then(|entity| {
+		entity.properties.iter().find_map(|p| match p.scalar_type {
+			DbScalarType::File(ref storage) if p.name == body.property => Some(storage.clone()),
+			_ => None,
+		})
+	});
+
+	// The schema isn't held while the file is created
+	drop(schema);
+
+	let storage = match storage {
+		Some(storage) => storage,
+		None => {
+			return Ok(error_response(
+				ActixResponse::NotFound(),
+				format!(
+					"File property {}.{} not found",
+					body.collection, body.property
+				),
+			))
+		}
+	};
+
+	match create_upload_url(&storage, &body.collection, &body.name, &body.mime).await {
+		Ok((id, url)) => Ok(ActixResponse::Ok().json(serde_json::json!({
+			"id": id,
+			"url": url,
+			"method": "PUT",
+		}))),
+		Err(e) => Ok(error_response(
+			ActixResponse::InternalServerError(),
+			e.to_string(),
+		)),
+	}
+}
+
+/// Whether the session can read a document holding the file in one of its `File` properties,
+/// filtered like the exports by the permissions of the session and the publication
+async fn can_read_file(entity: &DbEntity, session: &Session, id: &str) -> bool {
+	if !session.can_read(entity) {
+		return false;
+	}
+
+	let properties: Vec<&str> = entity
+		.properties
+		.iter()
+		.filter(|p| matches!(p.scalar_type, DbScalarType::File(_)))
+		.map(|p| p.name.as_str())
+		.collect();
+
+	let mut filters = Vec::new();
+
+	if let Some(filter) = get_permission_filter(entity, session) {
+		filters.push(filter);
+	}
+
+	// Drafts are only read by the roles previewing them
+	if !session.can_preview() {
+		if let Some(filter) = get_published_filter(entity) {
+			filters.push(filter);
+		}
+	}
+
+	let aql = format!(
+		"FOR i_0 IN @@collection FILTER @id IN VALUES(KEEP(i_0, @properties)) {} LIMIT 1 RETURN true",
+		filters
+			.iter()
+			.map(|f| format!("FILTER {}", f.describe(0)))
+			.collect::<Vec<String>>()
+			.join(" ")
+	);
+
+	let mut query = AqlQuery::builder()
+		.query(aql.as_str())
+		.bind_var("@collection", entity.collection_name.as_str())
+		.bind_var("id", id)
+		.bind_var("properties", properties);
+
+	let session_variables = get_session_variables(filters.iter().map(|f| f.as_ref()));
+
+	for (k, v) in session.get_bind_vars(&session_variables) {
+		query = query.bind_var(k, v);
+	}
+
+	let documents: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(query.build())
+		.await
+		.unwrap_or_default();
+
+	!documents.is_empty()
+}
+
+/// Streams the content of a file from its storage, for deployments not exposing their
+/// buckets. Supports single byte ranges, the session must be able to read a document holding
+/// the file
+pub async fn file_download_route(
+	req: ActixRequest,
+	id: Path<String>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let session = match Session::from_request(&req) {
+		Ok(session) => session,
+		Err(e) => return Ok(error_response(ActixResponse::Unauthorized(), e.to_string())),
+	};
+
+	if let Err(retry_after) = RATE_LIMITER.check(&req, &session).await {
+		return Ok(rate_limited_response(retry_after));
+	}
+
+	let file = get_file(&id).await;
+
+	let collection = file.as_ref().map(|file| file["collection"].as_str());
+	let entity = collection
+		.flatten()
+		.and_then(|collection| get_collection_entity(&schema.load(), collection));
+
+	// Files stored before being linked to a collection need an authenticated session
+	let is_readable = match (collection, entity) {
+		(Some(Some(_)), Some(entity)) => can_read_file(&entity, &session, &id).await,
+		(Some(None), _) => !session.anonymous,
+		_ => false,
+	};
+
+	let file = match file.filter(|_| is_readable) {
+		Some(file) => file,
+		None => {
+			return Ok(error_response(
+				ActixResponse::NotFound(),
+				format!("File {} not found", id),
+			))
+		}
+	};
+
+	let storage = match get_storage(file["storage"].as_str().unwrap_or(DEFAULT_STORAGE)) {
+		Ok(storage) => storage,
+		Err(e) => return Ok(error_response(ActixResponse::NotFound(), e.to_string())),
+	};
+
+	let path = file["path"].as_str().unwrap_or_default();
+
+	let size = match storage.size(path).await {
+		Ok(size) => size,
+		Err(e) => return Ok(error_response(ActixResponse::NotFound(), e.to_string())),
+	};
+
+	// Multiple ranges aren't supported, the whole file is sent instead
+	let range = req
+		.headers()
+		.get(header::RANGE)
+		.and_then(|h| h.to_str().ok())
+		.and_then(|h| h.parse::<header::Range>().ok())
+		.and_then(|range| match range {
+			header::Range::Bytes(specs) if specs.len() == 1 => Some(specs[0].clone()),
+			_ => None,
+		});
+
+	let (mut response, start, end) = match range {
+		Some(spec) => match spec.to_satisfiable_range(size) {
+			Some((start, end)) => {
+				let mut response = ActixResponse::PartialContent();
+
+				response.insert_header((
+					header::CONTENT_RANGE,
+					format!("bytes {}-{}/{}", start, end, size),
+				));
+
+				(response, start, end)
+			}
+			None => {
+				return Ok(ActixResponse::RangeNotSatisfiable()
+					.insert_header((header::CONTENT_RANGE, format!("bytes */{}", size)))
+					.finish())
+			}
+		},
+		None => (ActixResponse::Ok(), 0, size.saturating_sub(1)),
+	};
+
+	if size == 0 {
+		return Ok(response
+			.content_type(file["mime"].as_str().unwrap_or("application/octet-stream"))
+			.finish());
+	}
+
+	let content = match storage.stream(path, start, end).await {
+		Ok(content) => content,
+		Err(e) => {
+			return Ok(error_response(
+				ActixResponse::InternalServerError(),
+				e.to_string(),
+			))
+		}
+	};
+
+	Ok(response
+		.content_type(file["mime"].as_str().unwrap_or("application/octet-stream"))
+		.insert_header((header::ACCEPT_RANGES, "bytes"))
+		// Ranges are of the stored bytes, the compression middleware must leave them as is
+		.insert_header((header::CONTENT_ENCODING, "identity"))
+		.insert_header((
+			header::CONTENT_DISPOSITION,
+			format!(
+				"attachment; filename={}",
+				serde_json::to_string(file["name"].as_str().unwrap_or_default()).unwrap()
+			),
+		))
+		.no_chunking(end + 1 - start)
+		.streaming(content))
+}
@@ -0,0 +1,312 @@
+use std::convert::Infallible;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+
+use actix_web::{Error as ActixError, HttpResponse as ActixResponse};
+use arc_swap::{ArcSwap, ArcSwapOption};
+use futures_util::future::BoxFuture;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use lazy_static::lazy_static;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use serde_json::{json, Value as JsonValue};
+use tonic::body::BoxBody;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::server::{Grpc, UnaryService};
+use tonic::Status;
+
+use crate::api::auth::Session;
+use crate::api::grpc::proto::{
+	from_proto_input, get_method_operation, get_service_name, to_proto_document, ProtoFile,
+	PROTO_PACKAGE,
+};
+use crate::api::rate_limit::RATE_LIMITER;
+use crate::api::rest::{execute_operation, CrudOperation, OperationError};
+use crate::api::schema::{get_document_entities, Schema};
+use crate::api::server::error_response;
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::api::DbEntity;
+use crate::lib::CONFIG;
+
+pub mod proto;
+
+lazy_static! {
+	/// Descriptors of the messages of the schema they were generated from, generated again
+	/// once the schema is reloaded
+	static ref DESCRIPTORS: ArcSwapOption<(Arc<Schema>, DescriptorPool)> = ArcSwapOption::empty();
+}
+
+async fn get_descriptors(schema: &Arc<Schema>) -> Result<DescriptorPool, Status> {
+	if let Some((cached, pool)) = DESCRIPTORS.load().as_deref() {
+		if Arc::ptr_eq(cached, schema) {
+			return Ok(pool.clone());
+		}
+	}
+
+	let file = ProtoFile::from_entities(&get_document_entities(schema))
+		.await
+		.map_err(|e| Status::internal(format!("Error numbering the fields: {}", e)))?
+		.to_descriptor();
+
+	let pool = DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+		file: vec![file],
+	})
+	.map_err(|e| Status::internal(format!("Error generating the descriptors: {}", e)))?;
+
+	DESCRIPTORS.store(Some(Arc::new((schema.clone(), pool.clone()))));
+
+	Ok(pool)
+}
+
+/// Decodes the requests and encodes the responses of a method from and to the JSON mapping
+/// of their messages
+struct JsonCodec {
+	input: MessageDescriptor,
+	output: MessageDescriptor,
+}
+
+struct JsonEncoder(MessageDescriptor);
+
+struct JsonDecoder(MessageDescriptor);
+
+impl Codec for JsonCodec {
+	type Encode = JsonValue;
+	type Decode = JsonValue;
+	type Encoder = JsonEncoder;
+	type Decoder = JsonDecoder;
+
+	fn encoder(&mut self) -> Self::Encoder {
+		JsonEncoder(self.output.clone())
+	}
+
+	fn decoder(&mut self) -> Self::Decoder {
+		JsonDecoder(self.input.clone())
+	}
+}
+
+impl Encoder for JsonEncoder {
+	type Item = JsonValue;
+	type Error = Status;
+
+	fn encode(&mut self, item: JsonValue, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+		DynamicMessage::deserialize(self.0.clone(), item)
+			.map_err(|e| Status::internal(format!("Error encoding the response: {}", e)))?
+			.encode(dst)
+			.map_err(|e| Status::internal(format!("Error encoding the response: {}", e)))
+	}
+}
+
+impl Decoder for JsonDecoder {
+	type Item = JsonValue;
+	type Error = Status;
+
+	fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<JsonValue>, Status> {
+		let message = DynamicMessage::decode(self.0.clone(), src)
+			.map_err(|e| Status::invalid_argument(format!("Invalid request: {}", e)))?;
+
+		serde_json::to_value(&message)
+			.map(Some)
+			.map_err(|e| Status::invalid_argument(format!("Invalid request: {}", e)))
+	}
+}
+
+/// Method of the service of a collection, executing the operation like the REST routes
+struct CrudMethod {
+	schema: Arc<Schema>,
+	entity: Arc<DbEntity>,
+	operation: CrudOperation,
+	address: String,
+}
+
+impl UnaryService<JsonValue> for CrudMethod {
+	type Response = JsonValue;
+	type Future = BoxFuture<'static, Result<tonic::Response<JsonValue>, Status>>;
+
+	fn call(&mut self, request: tonic::Request<JsonValue>) -> Self::Future {
+		let schema = self.schema.clone();
+		let entity = self.entity.clone();
+		let operation = self.operation;
+		let address = self.address.clone();
+
+		Box::pin(async move {
+			let session = Session::from_authorization(
+				request
+					.metadata()
+					.get("authorization")
+					.and_then(|v| v.to_str().ok()),
+			)
+			.map_err(|e| Status::unauthenticated(e.to_string()))?;
+
+			if let Err(retry_after) = RATE_LIMITER.check_address(&address, &session).await {
+				return Err(Status::resource_exhausted(format!(
+					"Rate limit exceeded, retry in {} seconds",
+					retry_after
+				)));
+			}
+
+			let input = request.into_inner();
+
+			let variables = match operation {
+				CrudOperation::List => {
+					let filter = match input["where"].as_str().filter(|w| !w.is_empty()) {
+						Some(filter) => {
+							Some(serde_json::from_str::<JsonValue>(filter).map_err(|e| {
+								Status::invalid_argument(format!("Invalid filter: {}", e))
+							})?)
+						}
+						None => None,
+					};
+
+					json!({ "where": filter, "limit": input["limit"] })
+				}
+				CrudOperation::Get | CrudOperation::Remove => json!({ "key": input["key"] }),
+				CrudOperation::Create => json!({
+					"object": { "attributes": from_proto_input(&input, &entity) },
+				}),
+				CrudOperation::Update => json!({
+					"key": input["key"],
+					"set": from_proto_input(&input["set"], &entity),
+				}),
+			};
+
+			let result = execute_operation(session, &schema, &entity, operation, variables)
+				.await
+				.map_err(|e| match e {
+					OperationError::Forbidden(message) => Status::permission_denied(message),
+					OperationError::NotFound(message) => Status::not_found(message),
+					OperationError::Invalid(message) => Status::invalid_argument(message),
+					OperationError::Timeout(message) => Status::deadline_exceeded(message),
+				})?;
+
+			Ok(tonic::Response::new(match operation {
+				CrudOperation::List => json!({
+					"items": result
+						.as_array()
+						.into_iter()
+						.flatten()
+						.map(|document| to_proto_document(document, &entity))
+						.collect::<Vec<JsonValue>>(),
+				}),
+				_ => to_proto_document(&result, &entity),
+			}))
+		})
+	}
+}
+
+/// Routes `/alchemy.{Entity}Service/{Method}` requests to the method of the collection
+async fn handle_grpc_request(
+	req: Request<Body>,
+	schema: Arc<ArcSwap<Schema>>,
+	address: String,
+) -> Result<Response<BoxBody>, Infallible> {
+	// The schema in use for the whole request, even if it is reloaded meanwhile
+	let schema = schema.load_full();
+
+	let (service, method) = match req.uri().path().trim_start_matches('/').split_once('/') {
+		Some((service, method)) => (service.to_string(), method.to_string()),
+		None => return Ok(Status::unimplemented("Unknown method").to_http()),
+	};
+
+	let entity = get_document_entities(&schema)
+		.into_iter()
+		.find(|e| format!("{}.{}", PROTO_PACKAGE, get_service_name(e)) == service);
+
+	let (entity, operation) = match (entity, get_method_operation(&method)) {
+		(Some(entity), Some(operation)) => (entity, operation),
+		_ => {
+			return Ok(
+				Status::unimplemented(format!("Method {}/{} not found", service, method)).to_http(),
+			)
+		}
+	};
+
+	let descriptor = get_descriptors(&schema).await.and_then(|pool| {
+		pool.get_service_by_name(&service)
+			.and_then(|s| s.methods().find(|m| m.name() == method))
+			.ok_or_else(|| {
+				Status::unimplemented(format!("Method {}/{} not found", service, method))
+			})
+	});
+
+	let descriptor = match descriptor {
+		Ok(descriptor) => descriptor,
+		Err(status) => return Ok(status.to_http()),
+	};
+
+	let mut grpc = Grpc::new(JsonCodec {
+		input: descriptor.input(),
+		output: descriptor.output(),
+	});
+
+	let method = CrudMethod {
+		schema,
+		entity,
+		operation,
+		address,
+	};
+
+	Ok(grpc.unary(method, req).await)
+}
+
+/// Serves the CRUD services of the collections over gRPC, their messages are generated from
+/// the schema and described by `/api/grpc/alchemy.proto`
+pub async fn run_grpc_server(host: String, port: u16, schema: Arc<ArcSwap<Schema>>) {
+	let make_service = make_service_fn(move |conn: &AddrStream| {
+		let schema = schema.clone();
+		let address = conn.remote_addr().ip().to_string();
+
+		async move {
+			Ok::<_, Infallible>(service_fn(move |req| {
+				handle_grpc_request(req, schema.clone(), address.clone())
+			}))
+		}
+	});
+
+	let address = match (host.as_str(), port)
+		.to_socket_addrs()
+		.map(|mut a| a.next())
+	{
+		Ok(Some(address)) => address,
+		_ => {
+			tracing::error!("Invalid gRPC server address {}:{}", host, port);
+
+			return;
+		}
+	};
+
+	let server = match Server::try_bind(&address) {
+		Ok(server) => server.http2_only(true).serve(make_service),
+		Err(e) => {
+			tracing::error!("Error binding gRPC server to {}: {}", address, e);
+
+			return;
+		}
+	};
+
+	if let Err(e) = server.await {
+		tracing::error!("Error running gRPC server: {}", e);
+	}
+}
+
+/// Serves the `.proto` definitions of the current schema, it describes the collections like
+/// the GraphQL introspection so it's only available when introspection is enabled
+pub async fn proto_route(schema: TenantSchema) -> Result<ActixResponse, ActixError> {
+	if !CONFIG.is_introspection_enabled() {
+		return Ok(error_response(
+			ActixResponse::NotFound(),
+			"Protobuf definitions are not available".to_string(),
+		));
+	}
+
+	match ProtoFile::from_entities(&get_document_entities(&schema.load())).await {
+		Ok(file) => Ok(ActixResponse::Ok()
+			.content_type("text/plain; charset=utf-8")
+			.body(file.to_string())),
+		Err(e) => Ok(error_response(
+			ActixResponse::InternalServerError(),
+			format!("Error numbering the fields: {}", e),
+		)),
+	}
+}
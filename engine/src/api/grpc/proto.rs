@@ -0,0 +1,538 @@
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Error;
+use convert_case::Casing;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+	DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+	OneofDescriptorProto, ServiceDescriptorProto,
+};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::api::rest::CrudOperation;
+use crate::api::schema::files::FILE_TYPE;
+use crate::lib::database::api::{DbEntity, DbProperty, DbScalarType};
+use crate::lib::schema::get_field_numbers;
+
+pub const PROTO_PACKAGE: &str = "alchemy";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ProtoType {
+	String,
+	Int32,
+	Int64,
+	Double,
+	Bool,
+	Bytes,
+	Message(String),
+}
+
+impl ProtoType {
+	fn get_name(&self) -> &str {
+		match self {
+			ProtoType::String => "string",
+			ProtoType::Int32 => "int32",
+			ProtoType::Int64 => "int64",
+			ProtoType::Double => "double",
+			ProtoType::Bool => "bool",
+			ProtoType::Bytes => "bytes",
+			ProtoType::Message(name) => name,
+		}
+	}
+}
+
+pub struct ProtoField {
+	pub name: String,
+	/// Name of the property the field holds, used in the JSON mapping of the message
+	pub json_name: String,
+	pub field_type: ProtoType,
+	pub repeated: bool,
+	/// Persisted by the name of the property, see `get_field_numbers`
+	pub number: i32,
+}
+
+impl ProtoField {
+	fn new(name: &str, field_type: ProtoType, repeated: bool) -> Self {
+		// Identifiers can't start with an underscore, like the `_key` property
+		let field_name = name.trim_start_matches('_');
+
+		Self {
+			name: match field_name.is_empty() {
+				true => "value".to_string(),
+				false => field_name.to_string(),
+			},
+			json_name: name.to_string(),
+			field_type,
+			repeated,
+			number: 0,
+		}
+	}
+
+	/// Scalars have presence so zero values are told apart from unset ones
+	fn is_optional(&self) -> bool {
+		!self.repeated && !matches!(self.field_type, ProtoType::Message(_))
+	}
+}
+
+pub struct ProtoMessage {
+	pub name: String,
+	/// In the order of the properties, their numbers are kept once assigned whatever the order
+	pub fields: Vec<ProtoField>,
+}
+
+impl ProtoMessage {
+	fn new(name: String, mut fields: Vec<ProtoField>) -> Self {
+		// Names without their leading underscores may be taken, like `key` by `_key`: the
+		// property of the name keeps it and the other one is suffixed
+		for i in 0..fields.len() {
+			if fields[i].name == fields[i].json_name {
+				continue;
+			}
+
+			while fields
+				.iter()
+				.enumerate()
+				.any(|(j, f)| j != i && f.name == fields[i].name)
+			{
+				fields[i].name.push('_');
+			}
+		}
+
+		Self { name, fields }
+	}
+}
+
+pub struct ProtoMethod {
+	pub name: String,
+	pub input: String,
+	pub output: String,
+}
+
+pub struct ProtoService {
+	pub name: String,
+	pub methods: Vec<ProtoMethod>,
+}
+
+/// Protobuf definitions of the CRUD services of the collections, each collection has a
+/// `{Entity}Service` with `Get`, `List`, `Create`, `Update` and `Delete` methods
+#[derive(Default)]
+pub struct ProtoFile {
+	pub messages: Vec<ProtoMessage>,
+	pub services: Vec<ProtoService>,
+}
+
+pub fn get_message_name(entity: &DbEntity) -> String {
+	entity.name.to_case(convert_case::Case::Pascal)
+}
+
+pub fn get_service_name(entity: &DbEntity) -> String {
+	format!("{}Service", get_message_name(entity))
+}
+
+/// Operation of a method of the services
+pub fn get_method_operation(method: &str) -> Option<CrudOperation> {
+	match method {
+		"Get" => Some(CrudOperation::Get),
+		"List" => Some(CrudOperation::List),
+		"Create" => Some(CrudOperation::Create),
+		"Update" => Some(CrudOperation::Update),
+		"Delete" => Some(CrudOperation::Remove),
+		_ => None,
+	}
+}
+
+/// Properties of the documents of the entity, those of the variants and the computed ones
+/// included. Computed properties can't be set
+pub fn get_entity_properties(entity: &DbEntity, input: bool) -> Vec<&DbProperty> {
+	let mut properties: Vec<&DbProperty> = entity.properties.iter().collect();
+
+	properties.extend(entity.get_variant_properties());
+
+	if !input {
+		properties.extend(entity.computed.iter());
+	}
+
+	properties
+}
+
+impl ProtoFile {
+	/// Definitions of the collections, with the persisted numbers of their fields
+	pub async fn from_entities(entities: &[Arc<DbEntity>]) -> Result<Self, Error> {
+		let mut file = Self::default();
+
+		for entity in entities {
+			file.add_entity(entity);
+		}
+
+		file.number_fields().await?;
+
+		Ok(file)
+	}
+
+	async fn number_fields(&mut self) -> Result<(), Error> {
+		let messages: Vec<(String, Vec<String>)> = self
+			.messages
+			.iter()
+			.map(|m| {
+				let fields = m.fields.iter().map(|f| f.json_name.clone()).collect();
+
+				(m.name.clone(), fields)
+			})
+			.collect();
+
+		let numbers = get_field_numbers(&messages).await?;
+
+		for message in &mut self.messages {
+			for field in &mut message.fields {
+				field.number = numbers[&message.name][&field.json_name];
+			}
+		}
+
+		Ok(())
+	}
+
+	fn add_entity(&mut self, entity: &DbEntity) {
+		let name = get_message_name(entity);
+
+		self.add_message(&name, get_entity_properties(entity, false), false);
+		self.add_message(
+			&format!("{}Input", name),
+			get_entity_properties(entity, true),
+			true,
+		);
+
+		self.messages.push(ProtoMessage::new(
+			format!("{}KeyRequest", name),
+			vec![ProtoField::new("key", ProtoType::String, false)],
+		));
+
+		self.messages.push(ProtoMessage::new(
+			format!("{}ListRequest", name),
+			vec![
+				ProtoField::new("limit", ProtoType::Int32, false),
+				// JSON filter, with the same shape as the `where` argument of the GraphQL API
+				ProtoField::new("where", ProtoType::String, false),
+			],
+		));
+
+		self.messages.push(ProtoMessage::new(
+			format!("{}ListResponse", name),
+			vec![ProtoField::new(
+				"items",
+				ProtoType::Message(name.clone()),
+				true,
+			)],
+		));
+
+		self.messages.push(ProtoMessage::new(
+			format!("{}UpdateRequest", name),
+			vec![
+				ProtoField::new("key", ProtoType::String, false),
+				ProtoField::new("set", ProtoType::Message(format!("{}Input", name)), false),
+			],
+		));
+
+		let method = |method: &str, input: &str, output: &str| ProtoMethod {
+			name: method.to_string(),
+			input: format!("{}{}", name, input),
+			output: format!("{}{}", name, output),
+		};
+
+		self.services.push(ProtoService {
+			name: get_service_name(entity),
+			methods: vec![
+				method("Get", "KeyRequest", ""),
+				method("List", "ListRequest", "ListResponse"),
+				method("Create", "Input", ""),
+				method("Update", "UpdateRequest", ""),
+				method("Delete", "KeyRequest", ""),
+			],
+		});
+	}
+
+	fn add_message(&mut self, name: &str, properties: Vec<&DbProperty>, input: bool) {
+		if self.messages.iter().any(|m| m.name == name) {
+			return;
+		}
+
+		// Added before its fields so recursive types refer to it instead of being added again
+		let index = self.messages.len();
+
+		self.messages
+			.push(ProtoMessage::new(name.to_string(), Vec::new()));
+
+		let fields = properties
+			.into_iter()
+			.map(|p| {
+				let (field_type, repeated) = self.get_field_type(&p.scalar_type, input);
+
+				ProtoField::new(&p.name, field_type, repeated)
+			})
+			.collect();
+
+		self.messages[index] = ProtoMessage::new(name.to_string(), fields);
+	}
+
+	/// Type of the field holding the values, values without a protobuf type are JSON strings
+	fn get_field_type(&mut self, scalar_type: &DbScalarType, input: bool) -> (ProtoType, bool) {
+		let field_type = match scalar_type {
+			DbScalarType::Array(item) if !matches!(**item, DbScalarType::Array(_)) => {
+				return (self.get_field_type(item, input).0, true)
+			}
+			DbScalarType::Embedded(embedded) => {
+				let name = match input {
+					true => format!("{}Input", embedded.name),
+					false => embedded.name.clone(),
+				};
+
+				self.add_message(&name, embedded.properties.iter().collect(), input);
+
+				ProtoType::Message(name)
+			}
+			// Files are set with the id of an upload and read with their metadata
+			DbScalarType::File(_) if !input => {
+				self.add_message(
+					&FILE_TYPE.name,
+					FILE_TYPE.properties.iter().collect(),
+					false,
+				);
+
+				ProtoType::Message(FILE_TYPE.name.clone())
+			}
+			DbScalarType::Float => ProtoType::Double,
+			DbScalarType::Int => ProtoType::Int32,
+			DbScalarType::BigInt => ProtoType::Int64,
+			DbScalarType::Boolean => ProtoType::Bool,
+			DbScalarType::Bytes => ProtoType::Bytes,
+			_ => ProtoType::String,
+		};
+
+		(field_type, false)
+	}
+
+	pub fn to_descriptor(&self) -> FileDescriptorProto {
+		let message_type = self
+			.messages
+			.iter()
+			.map(|message| {
+				let mut fields = Vec::new();
+				let mut oneofs = Vec::new();
+
+				for field in &message.fields {
+					let (field_type, type_name) = match &field.field_type {
+						ProtoType::String => (Type::String, None),
+						ProtoType::Int32 => (Type::Int32, None),
+						ProtoType::Int64 => (Type::Int64, None),
+						ProtoType::Double => (Type::Double, None),
+						ProtoType::Bool => (Type::Bool, None),
+						ProtoType::Bytes => (Type::Bytes, None),
+						ProtoType::Message(name) => {
+							(Type::Message, Some(format!(".{}.{}", PROTO_PACKAGE, name)))
+						}
+					};
+
+					// Optional fields of proto3 are in a oneof of their own
+					let oneof_index = field.is_optional().then(|| {
+						oneofs.push(OneofDescriptorProto {
+							name: Some(format!("_{}", field.name)),
+							..Default::default()
+						});
+
+						oneofs.len() as i32 - 1
+					});
+
+					fields.push(FieldDescriptorProto {
+						name: Some(field.name.clone()),
+						number: Some(field.number),
+						label: Some(match field.repeated {
+							true => Label::Repeated as i32,
+							false => Label::Optional as i32,
+						}),
+						r#type: Some(field_type as i32),
+						type_name,
+						json_name: Some(field.json_name.clone()),
+						oneof_index,
+						proto3_optional: field.is_optional().then(|| true),
+						..Default::default()
+					});
+				}
+
+				DescriptorProto {
+					name: Some(message.name.clone()),
+					field: fields,
+					oneof_decl: oneofs,
+					..Default::default()
+				}
+			})
+			.collect();
+
+		let service = self
+			.services
+			.iter()
+			.map(|service| ServiceDescriptorProto {
+				name: Some(service.name.clone()),
+				method: service
+					.methods
+					.iter()
+					.map(|method| MethodDescriptorProto {
+						name: Some(method.name.clone()),
+						input_type: Some(format!(".{}.{}", PROTO_PACKAGE, method.input)),
+						output_type: Some(format!(".{}.{}", PROTO_PACKAGE, method.output)),
+						..Default::default()
+					})
+					.collect(),
+				..Default::default()
+			})
+			.collect();
+
+		FileDescriptorProto {
+			name: Some(format!("{}.proto", PROTO_PACKAGE)),
+			package: Some(PROTO_PACKAGE.to_string()),
+			message_type,
+			service,
+			syntax: Some("proto3".to_string()),
+			..Default::default()
+		}
+	}
+}
+
+/// `.proto` source of the definitions, for the code generators of the clients
+impl fmt::Display for ProtoFile {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "syntax = \"proto3\";\n\npackage {};", PROTO_PACKAGE)?;
+
+		for message in &self.messages {
+			writeln!(f, "\nmessage {} {{", message.name)?;
+
+			for field in &message.fields {
+				let label = match (field.repeated, field.is_optional()) {
+					(true, _) => "repeated ",
+					(_, true) => "optional ",
+					_ => "",
+				};
+
+				writeln!(
+					f,
+					"  {}{} {} = {} [json_name = \"{}\"];",
+					label,
+					field.field_type.get_name(),
+					field.name,
+					field.number,
+					field.json_name
+				)?;
+			}
+
+			writeln!(f, "}}")?;
+		}
+
+		for service in &self.services {
+			writeln!(f, "\nservice {} {{", service.name)?;
+
+			for method in &service.methods {
+				writeln!(
+					f,
+					"  rpc {}({}) returns ({});",
+					method.name, method.input, method.output
+				)?;
+			}
+
+			writeln!(f, "}}")?;
+		}
+
+		Ok(())
+	}
+}
+
+fn convert_properties(
+	value: &JsonValue,
+	properties: &[&DbProperty],
+	convert: fn(&JsonValue, &DbScalarType) -> JsonValue,
+) -> JsonValue {
+	let mut object = JsonMap::new();
+
+	for property in properties {
+		match value.get(&property.name) {
+			None | Some(JsonValue::Null) => {}
+			Some(v) => {
+				object.insert(property.name.clone(), convert(v, &property.scalar_type));
+			}
+		}
+	}
+
+	JsonValue::Object(object)
+}
+
+/// JSON mapping of a value in its message, JSON values are encoded as strings
+fn to_proto_value(value: &JsonValue, scalar_type: &DbScalarType) -> JsonValue {
+	match scalar_type {
+		DbScalarType::Object => JsonValue::String(value.to_string()),
+		DbScalarType::Array(item) => match **item {
+			DbScalarType::Array(_) => JsonValue::String(value.to_string()),
+			_ => JsonValue::Array(
+				value
+					.as_array()
+					.into_iter()
+					.flatten()
+					.map(|v| to_proto_value(v, item))
+					.collect(),
+			),
+		},
+		DbScalarType::Embedded(embedded) => convert_properties(
+			value,
+			&embedded.properties.iter().collect::<Vec<&DbProperty>>(),
+			to_proto_value,
+		),
+		_ => value.clone(),
+	}
+}
+
+/// Value of the API from the JSON mapping of its message, which has 64-bit integers as strings
+fn from_proto_value(value: &JsonValue, scalar_type: &DbScalarType) -> JsonValue {
+	match (scalar_type, value) {
+		(DbScalarType::Object, JsonValue::String(s)) => {
+			serde_json::from_str(s).unwrap_or_else(|_| value.clone())
+		}
+		(DbScalarType::BigInt, JsonValue::String(s)) => s
+			.parse::<i64>()
+			.map(JsonValue::from)
+			.unwrap_or_else(|_| value.clone()),
+		(DbScalarType::Array(item), _) => match (&**item, value) {
+			(DbScalarType::Array(_), JsonValue::String(s)) => {
+				serde_json::from_str(s).unwrap_or_else(|_| value.clone())
+			}
+			_ => JsonValue::Array(
+				value
+					.as_array()
+					.into_iter()
+					.flatten()
+					.map(|v| from_proto_value(v, item))
+					.collect(),
+			),
+		},
+		(DbScalarType::Embedded(embedded), _) => convert_properties(
+			value,
+			&embedded.properties.iter().collect::<Vec<&DbProperty>>(),
+			from_proto_value,
+		),
+		_ => value.clone(),
+	}
+}
+
+/// JSON mapping of the `{Entity}` message of a document
+pub fn to_proto_document(document: &JsonValue, entity: &DbEntity) -> JsonValue {
+	convert_properties(
+		document,
+		&get_entity_properties(entity, false),
+		to_proto_value,
+	)
+}
+
+/// Attributes of the API from the JSON mapping of an `{Entity}Input` message
+pub fn from_proto_input(input: &JsonValue, entity: &DbEntity) -> JsonValue {
+	convert_properties(
+		input,
+		&get_entity_properties(entity, true),
+		from_proto_value,
+	)
+}
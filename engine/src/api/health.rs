@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use actix_web::HttpResponse as ActixResponse;
+use rust_arango::AqlQuery;
+use serde_json::{json, Value as JsonValue};
+
+use crate::api::cluster::check_redis;
+use crate::api::schema::get_entities;
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::DATABASE;
+use crate::lib::CONFIG;
+
+/// Changes of the schema file left unapplied at startup, set when `SCHEMA_APPLY` is disabled
+pub static PENDING_SCHEMA_CHANGES: AtomicUsize = AtomicUsize::new(0);
+
+/// Liveness of the process, answered without touching the database
+pub async fn healthz_route() -> ActixResponse {
+	ActixResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+/// Sends a query without retries, a probe failing once is retried by the orchestrator
+async fn check_database() -> Result<(), String> {
+	let database = DATABASE.get().await.database();
+	let query = AqlQuery::builder().query("RETURN 1").build();
+
+	let result = match CONFIG.get_db_query_timeout() {
+		Some(timeout) => tokio::time::timeout(timeout, database.aql_query::<JsonValue>(query))
+			.await
+			.map_err(|_| format!("Request timed out after {:?}", timeout))?,
+		None => database.aql_query::<JsonValue>(query).await,
+	};
+
+	result.map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Readiness to serve requests: ArangoDB answers queries, the API schema is loaded and the
+/// schema file has no pending changes. Answers 503 with the failing checks otherwise. Redis is
+/// reported without failing the probe, the instance falling back to its own state without it
+pub async fn readyz_route(schema: TenantSchema) -> ActixResponse {
+	let database = check_database().await;
+	let entities = get_entities(&schema.load()).len();
+	let pending = PENDING_SCHEMA_CHANGES.load(Ordering::Relaxed);
+	let redis = check_redis().await;
+
+	let ready = database.is_ok() && pending == 0;
+
+	let body = json!({
+		"status": if ready { "ok" } else { "unavailable" },
+		"checks": {
+			"database": match &database {
+				Ok(_) => json!({ "status": "ok" }),
+				Err(e) => json!({ "status": "unavailable", "error": e }),
+			},
+			// The server only starts once the schema is loaded
+			"schema": {
+				"status": "ok",
+				"entities": entities,
+			},
+			"redis": match &redis {
+				Some(Ok(_)) => json!({ "status": "ok" }),
+				Some(Err(e)) => json!({ "status": "unavailable", "error": e }),
+				None => json!({ "status": "disabled" }),
+			},
+			"migrations": {
+				"status": if pending == 0 { "ok" } else { "pending" },
+				"file": CONFIG.get_schema_file(),
+				"pending": pending,
+			},
+		},
+	});
+
+	match ready {
+		true => ActixResponse::Ok().json(body),
+		false => ActixResponse::ServiceUnavailable().json(body),
+	}
+}
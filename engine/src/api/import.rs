@@ -0,0 +1,543 @@
+use std::collections::HashMap;
+
+use actix_web::{
+	http::header,
+	web::{Path, Payload as ActixPayload, Query},
+	Error as ActixError, HttpRequest as ActixRequest, HttpResponse as ActixResponse,
+};
+use chrono::{Duration, SecondsFormat, Utc};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::api::auth::permissions::get_preset_values;
+use crate::api::auth::Session;
+use crate::api::rate_limit::RATE_LIMITER;
+use crate::api::response_cache::RESPONSE_CACHE;
+use crate::api::schema::cache::invalidate_results;
+use crate::api::schema::get_collection_entity;
+use crate::api::schema::hooks::has_hooks;
+use crate::api::schema::operations::create::generate_values;
+use crate::api::schema::scalars::datetime::parse_datetime;
+use crate::api::schema::scalars::decimal::is_decimal;
+use crate::api::server::{error_response, rate_limited_response};
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::api::{DbEntity, DbScalarType};
+use crate::lib::database::tenants::get_current_database_name;
+use crate::lib::events::EventOperation;
+use crate::lib::CONFIG;
+
+/// Formats documents are imported from and exported to
+#[derive(Clone, Copy, PartialEq)]
+pub enum DataFormat {
+	Csv,
+	Ndjson,
+}
+
+impl DataFormat {
+	pub fn parse(value: &str) -> Option<Self> {
+		match value {
+			"csv" | "text/csv" => Some(Self::Csv),
+			"ndjson" | "jsonl" | "application/x-ndjson" | "application/jsonl" => Some(Self::Ndjson),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportParams {
+	/// `csv` or `ndjson`, read from the `Content-Type` when omitted
+	format: Option<String>,
+	batch_size: Option<usize>,
+	/// Comma separated `column:field` pairs, columns are mapped to the field of their name
+	/// otherwise
+	map: Option<String>,
+	delimiter: Option<char>,
+}
+
+#[derive(Serialize)]
+struct RowError {
+	/// Position of the row in the data, starting at 1 after the CSV header
+	row: usize,
+	message: String,
+}
+
+#[derive(Serialize, Default)]
+struct ImportReport {
+	inserted: usize,
+	errors: Vec<RowError>,
+}
+
+/// Splits the request body into records as it's received, newlines in quoted CSV values
+/// don't end a record
+struct RecordReader {
+	buffer: Vec<u8>,
+	position: usize,
+	in_quotes: bool,
+	quoted: bool,
+}
+
+impl RecordReader {
+	fn new(format: DataFormat) -> Self {
+		Self {
+			buffer: Vec::new(),
+			position: 0,
+			in_quotes: false,
+			quoted: format == DataFormat::Csv,
+		}
+	}
+
+	fn push(&mut self, chunk: &[u8]) {
+		self.buffer.extend_from_slice(chunk);
+	}
+
+	fn next_record(&mut self) -> Option<Result<String, String>> {
+		while self.position < self.buffer.len() {
+			match self.buffer[self.position] {
+				b'"' if self.quoted => self.in_quotes = !self.in_quotes,
+				b'\n' if !self.in_quotes => {
+					let record: Vec<u8> = self.buffer.drain(..=self.position).collect();
+
+					self.position = 0;
+
+					return Some(to_record(&record[..record.len() - 1]));
+				}
+				_ => {}
+			}
+
+			self.position += 1;
+		}
+
+		None
+	}
+
+	/// The last record, when the body doesn't end with a newline
+	fn finish(&mut self) -> Option<Result<String, String>> {
+		if self.buffer.is_empty() {
+			return None;
+		}
+
+		let record = std::mem::take(&mut self.buffer);
+
+		Some(to_record(&record))
+	}
+}
+
+fn to_record(bytes: &[u8]) -> Result<String, String> {
+	let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+
+	String::from_utf8(bytes.to_vec()).map_err(|_| "Invalid UTF-8".to_string())
+}
+
+/// Values of a CSV record, quotes are escaped by doubling them
+fn parse_csv_record(record: &str, delimiter: char) -> Result<Vec<String>, String> {
+	let mut values = Vec::new();
+	let mut value = String::new();
+	let mut in_quotes = false;
+	let mut chars = record.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'"' if in_quotes => {
+				if chars.peek() == Some(&'"') {
+					value.push('"');
+					chars.next();
+				} else {
+					in_quotes = false;
+				}
+			}
+			'"' if value.is_empty() => in_quotes = true,
+			c if c == delimiter && !in_quotes => values.push(std::mem::take(&mut value)),
+			c => value.push(c),
+		}
+	}
+
+	if in_quotes {
+		return Err("Unterminated quoted value".to_string());
+	}
+
+	values.push(value);
+
+	Ok(values)
+}
+
+fn find_property_type<'a>(entity: &'a DbEntity, field: &str) -> Option<&'a DbScalarType> {
+	entity
+		.properties
+		.iter()
+		.chain(entity.get_variant_properties())
+		.find(|p| p.name == field)
+		.map(|p| &p.scalar_type)
+}
+
+/// Converts a CSV value to the type of its field, empty values are left out
+fn coerce_value(value: &str, scalar_type: &DbScalarType) -> Result<Option<JsonValue>, String> {
+	if value.is_empty() {
+		return Ok(None);
+	}
+
+	let invalid = |type_name: &str| format!("Invalid {} value {:?}", type_name, value);
+
+	let coerced = match scalar_type {
+		DbScalarType::Int | DbScalarType::BigInt => value
+			.parse::<i64>()
+			.map(JsonValue::from)
+			.map_err(|_| invalid("integer"))?,
+		DbScalarType::Float => value
+			.parse::<f64>()
+			.ok()
+			.and_then(serde_json::Number::from_f64)
+			.map(JsonValue::Number)
+			.ok_or_else(|| invalid("float"))?,
+		DbScalarType::Boolean => match value.to_lowercase().as_str() {
+			"true" | "1" => JsonValue::Bool(true),
+			"false" | "0" => JsonValue::Bool(false),
+			_ => return Err(invalid("boolean")),
+		},
+		DbScalarType::DateTime => parse_datetime(value)
+			.map(|d| JsonValue::String(d.to_rfc3339()))
+			.ok_or_else(|| invalid("date time"))?,
+		DbScalarType::Decimal if !is_decimal(value) => return Err(invalid("decimal")),
+		DbScalarType::Uuid if uuid::Uuid::parse_str(value).is_err() => return Err(invalid("UUID")),
+		DbScalarType::Bytes => match base64::decode(value) {
+			Ok(bytes) if bytes.len() <= CONFIG.get_bytes_max_size() => value.into(),
+			_ => return Err(invalid("base64")),
+		},
+		DbScalarType::Enum(values) if !values.iter().any(|v| v == value) => {
+			return Err(invalid("enum"))
+		}
+		DbScalarType::Array(_) | DbScalarType::Object | DbScalarType::Embedded(_) => {
+			serde_json::from_str(value).map_err(|_| invalid("JSON"))?
+		}
+		_ => value.into(),
+	};
+
+	Ok(Some(coerced))
+}
+
+/// Maps the CSV header to the fields of the entity, failing on columns without one
+fn get_columns(
+	header: Vec<String>,
+	entity: &DbEntity,
+	map: &HashMap<String, String>,
+) -> Result<Vec<(String, DbScalarType)>, String> {
+	header
+		.into_iter()
+		.map(|column| {
+			let field = map.get(&column).cloned().unwrap_or(column);
+
+			match field.as_str() {
+				"_key" => Ok((field, DbScalarType::String)),
+				_ => find_property_type(entity, &field)
+					.map(|t| (field.clone(), t.clone()))
+					.ok_or_else(|| format!("Column {} has no field in {}", field, entity.name)),
+			}
+		})
+		.collect()
+}
+
+fn parse_map(map: &Option<String>) -> HashMap<String, String> {
+	map.as_deref()
+		.unwrap_or_default()
+		.split(',')
+		.filter_map(|pair| {
+			let (column, field) = pair.split_once(':')?;
+
+			Some((column.trim().to_string(), field.trim().to_string()))
+		})
+		.collect()
+}
+
+/// Builds the document of a record, filling in generated values, presets and the TTL
+struct DocumentBuilder<'a> {
+	format: DataFormat,
+	delimiter: char,
+	entity: &'a DbEntity,
+	map: HashMap<String, String>,
+	presets: JsonMap<String, JsonValue>,
+	columns: Option<Vec<(String, DbScalarType)>>,
+}
+
+impl<'a> DocumentBuilder<'a> {
+	fn build(&self, record: &str) -> Result<JsonValue, String> {
+		let mut document = match &self.columns {
+			Some(columns) => {
+				let values = parse_csv_record(record, self.delimiter)?;
+
+				if values.len() != columns.len() {
+					return Err(format!(
+						"Expected {} values, got {}",
+						columns.len(),
+						values.len()
+					));
+				}
+
+				let mut document = JsonMap::new();
+
+				for ((field, scalar_type), value) in columns.iter().zip(values) {
+					if let Some(value) = coerce_value(&value, scalar_type)? {
+						document.insert(field.clone(), value);
+					}
+				}
+
+				document
+			}
+			None => match serde_json::from_str(record) {
+				Ok(JsonValue::Object(object)) => object
+					.into_iter()
+					.map(|(k, v)| (self.map.get(&k).cloned().unwrap_or(k), v))
+					.collect(),
+				Ok(_) => return Err("Expected a JSON object".to_string()),
+				Err(e) => return Err(e.to_string()),
+			},
+		};
+
+		document.extend(self.presets.clone());
+
+		generate_values(&mut document, self.entity);
+
+		if let Some(ttl) = &self.entity.ttl {
+			let expires_at = Utc::now() + Duration::seconds(ttl.expire_after as i64);
+
+			document.insert(
+				ttl.field.clone(),
+				JsonValue::String(expires_at.to_rfc3339_opts(SecondsFormat::Millis, true)),
+			);
+		}
+
+		Ok(JsonValue::Object(document))
+	}
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct BulkImportResult {
+	created: usize,
+	details: Vec<String>,
+}
+
+/// Inserts the documents with the ArangoDB bulk import, one per row in `rows`. Rejected
+/// documents are reported by their position in the batch, which is mapped back to the row
+async fn import_batch(
+	client: &reqwest::Client,
+	collection: &str,
+	documents: Vec<JsonValue>,
+	rows: &[usize],
+	report: &mut ImportReport,
+) -> Result<(), reqwest::Error> {
+	let result: BulkImportResult = client
+		.post(format!(
+			"{}/_db/{}/_api/import",
+			CONFIG.db_host.trim_end_matches('/'),
+			get_current_database_name()
+		))
+		.basic_auth(&CONFIG.db_user, Some(&CONFIG.db_pass))
+		.query(&[
+			("collection", collection),
+			("type", "list"),
+			("details", "true"),
+		])
+		.json(&documents)
+		.send()
+		.await?
+		.error_for_status()?
+		.json()
+		.await?;
+
+	report.inserted += result.created;
+
+	RESPONSE_CACHE.invalidate(collection).await;
+	invalidate_results(collection).await;
+
+	for detail in result.details {
+		let row = detail
+			.strip_prefix("at position ")
+			.and_then(|d| d.split(':').next())
+			.and_then(|p| p.parse::<usize>().ok())
+			.and_then(|p| rows.get(p).copied())
+			.unwrap_or_default();
+
+		report.errors.push(RowError {
+			row,
+			message: detail,
+		});
+	}
+
+	Ok(())
+}
+
+/// Bulk loads CSV (with a header row) or newline delimited JSON into a collection. Rows are
+/// inserted as they're received, rows that fail don't stop the import and are reported.
+/// Collections with hooks on create are refused, their documents go through the API
+pub async fn import_route(
+	req: ActixRequest,
+	collection: Path<String>,
+	params: Query<ImportParams>,
+	mut payload: ActixPayload,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let session = match Session::from_request(&req) {
+		Ok(session) => session,
+		Err(e) => return Ok(error_response(ActixResponse::Unauthorized(), e.to_string())),
+	};
+
+	if session.anonymous {
+		return Ok(error_response(
+			ActixResponse::Forbidden(),
+			"Importing requires authentication".to_string(),
+		));
+	}
+
+	if let Err(retry_after) = RATE_LIMITER.check(&req, &session).await {
+		return Ok(rate_limited_response(retry_after));
+	}
+
+	let content_type = req
+		.headers()
+		.get(header::CONTENT_TYPE)
+		.and_then(|h| h.to_str().ok())
+		.and_then(|h| h.split(';').next())
+		.map(|h| h.trim().to_string());
+
+	let format = match params
+		.format
+		.as_deref()
+		.or(content_type.as_deref())
+		.and_then(DataFormat::parse)
+	{
+		Some(format) => format,
+		None => {
+			return Ok(error_response(
+				ActixResponse::UnsupportedMediaType(),
+				"Expected CSV or NDJSON data".to_string(),
+			))
+		}
+	};
+
+	// Singletons hold a single document, updated by their mutation
+	let entity = match get_collection_entity(&schema.load(), &collection)
+		.filter(|e| e.singleton.is_none())
+	{
+		Some(entity) => entity,
+		None => {
+			return Ok(error_response(
+				ActixResponse::NotFound(),
+				format!("Collection {} not found", collection),
+			))
+		}
+	};
+
+	// The bulk import can't run the hooks on each document, so they'd be bypassed
+	if has_hooks(&entity, EventOperation::Create) {
+		return Ok(error_response(
+			ActixResponse::Conflict(),
+			format!(
+				"Collection {} has hooks on create, its documents can't be imported",
+				collection
+			),
+		));
+	}
+
+	let batch_size = params
+		.batch_size
+		.filter(|s| *s > 0)
+		.unwrap_or_else(|| CONFIG.get_import_batch_size());
+
+	let mut builder = DocumentBuilder {
+		format,
+		delimiter: params.delimiter.unwrap_or(','),
+		entity: &entity,
+		map: parse_map(&params.map),
+		presets: get_preset_values(&entity, &session),
+		columns: None,
+	};
+
+	let client = reqwest::Client::new();
+	let mut reader = RecordReader::new(format);
+	let mut report = ImportReport::default();
+
+	let mut documents = Vec::new();
+	let mut rows = Vec::new();
+	let mut row = 0;
+	let mut finished = false;
+
+	while !finished {
+		match payload.next().await {
+			Some(chunk) => reader.push(&chunk?),
+			None => finished = true,
+		}
+
+		loop {
+			let record = match reader.next_record() {
+				Some(record) => record,
+				None if finished => match reader.finish() {
+					Some(record) => record,
+					None => break,
+				},
+				None => break,
+			};
+
+			let record = match record {
+				Ok(record) if record.trim().is_empty() => continue,
+				Ok(record) => record,
+				Err(message) => {
+					row += 1;
+					report.errors.push(RowError { row, message });
+
+					continue;
+				}
+			};
+
+			if builder.format == DataFormat::Csv && builder.columns.is_none() {
+				let columns = parse_csv_record(&record, builder.delimiter)
+					.and_then(|header| get_columns(header, &entity, &builder.map));
+
+				match columns {
+					Ok(columns) => builder.columns = Some(columns),
+					Err(e) => return Ok(error_response(ActixResponse::BadRequest(), e)),
+				}
+
+				continue;
+			}
+
+			row += 1;
+
+			match builder.build(&record) {
+				Ok(document) => {
+					documents.push(document);
+					rows.push(row);
+				}
+				Err(message) => report.errors.push(RowError { row, message }),
+			}
+
+			if documents.len() >= batch_size {
+				import_batch(
+					&client,
+					&entity.collection_name,
+					std::mem::take(&mut documents),
+					&std::mem::take(&mut rows),
+					&mut report,
+				)
+				.await
+				.map_err(actix_web::error::ErrorBadGateway)?;
+			}
+		}
+	}
+
+	if !documents.is_empty() {
+		import_batch(
+			&client,
+			&entity.collection_name,
+			documents,
+			&rows,
+			&mut report,
+		)
+		.await
+		.map_err(actix_web::error::ErrorBadGateway)?;
+	}
+
+	report.errors.sort_by_key(|e| e.row);
+
+	Ok(ActixResponse::Ok().json(report))
+}
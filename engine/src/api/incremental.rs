@@ -0,0 +1,1167 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use actix_web::{
+	http::header, web::Bytes, Error as ActixError, HttpRequest as ActixRequest,
+	HttpResponse as ActixResponse,
+};
+use futures_util::stream::{self, FuturesUnordered};
+use futures_util::{future, StreamExt};
+use graphql_parser::query::{
+	parse_query, Definition, Directive, Document, Field, FragmentDefinition, InlineFragment,
+	OperationDefinition, Selection, SelectionSet, TypeCondition, Value,
+};
+use juniper::http::GraphQLRequest;
+use juniper::meta::{Field as MetaField, MetaType};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use tokio::task::JoinHandle;
+
+use crate::api::auth::Session;
+use crate::api::request::ApiRequest;
+use crate::api::schema::{AlchemyScalarValue, Context, Schema};
+use crate::lib::database::tenants::in_current_database;
+
+type QueryDocument = Document<'static, String>;
+type QuerySelectionSet = SelectionSet<'static, String>;
+
+/// How the payloads of an incremental response are sent, chosen from the `Accept` header
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IncrementalDelivery {
+	Multipart,
+	EventStream,
+}
+
+impl IncrementalDelivery {
+	pub fn from_request(req: &ActixRequest) -> Option<Self> {
+		let accept = req.headers().get(header::ACCEPT)?.to_str().ok()?;
+
+		if accept.contains("multipart/mixed") {
+			Some(IncrementalDelivery::Multipart)
+		} else if accept.contains("text/event-stream") {
+			Some(IncrementalDelivery::EventStream)
+		} else {
+			None
+		}
+	}
+
+	fn get_content_type(&self) -> &'static str {
+		match self {
+			IncrementalDelivery::Multipart => "multipart/mixed; boundary=\"-\"",
+			IncrementalDelivery::EventStream => "text/event-stream",
+		}
+	}
+
+	fn format_payload(&self, payload: &JsonValue) -> Bytes {
+		Bytes::from(match self {
+			IncrementalDelivery::Multipart => format!(
+				"\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n{}",
+				payload
+			),
+			IncrementalDelivery::EventStream => format!("event: next\ndata: {}\n\n", payload),
+		})
+	}
+
+	fn format_end(&self) -> Bytes {
+		Bytes::from_static(match self {
+			IncrementalDelivery::Multipart => b"\r\n-----\r\n",
+			IncrementalDelivery::EventStream => b"event: complete\ndata:\n\n",
+		})
+	}
+}
+
+/// Fragment marked with `@defer`, its fields are sent once the query selecting them under their
+/// path is resolved
+struct DeferredFragment {
+	label: Option<String>,
+	/// Response keys of the fields leading to the objects the fragment is spread in
+	path: Vec<String>,
+	/// Response keys of the fields of the fragment
+	keys: Vec<String>,
+	/// Fields only selected by the fragment, left out of the initial payload
+	hidden_keys: Vec<String>,
+	/// Query selecting the fragment and the fields leading to it
+	query: String,
+}
+
+/// List field marked with `@stream`, the items after `initialCount` are sent once the query
+/// selecting the whole list is resolved
+struct StreamedField {
+	label: Option<String>,
+	/// Response keys of the fields leading to the objects the list is selected in
+	path: Vec<String>,
+	key: String,
+	initial_count: usize,
+	/// Query selecting the list and the fields leading to it
+	query: String,
+}
+
+/// The queries an operation with `@defer` or `@stream` directives is split into
+pub struct IncrementalPlan {
+	/// Query of the initial payload, without the deferred fragments and with the streamed lists
+	/// limited to their `initialCount`. Mutations aren't split as they can only be executed
+	/// once, their payloads are taken from the whole result
+	initial_query: Option<String>,
+	/// Whole query, without the directives
+	query: String,
+	/// Paths of the selections only left with a `__typename` once split, removed from the
+	/// initial payload
+	placeholder_paths: Vec<Vec<String>>,
+	deferred: Vec<DeferredFragment>,
+	streams: Vec<StreamedField>,
+}
+
+fn is_incremental_directive(directive: &Directive<'static, String>) -> bool {
+	directive.name == "defer" || directive.name == "stream"
+}
+
+fn get_argument(
+	directive: &Directive<'static, String>,
+	name: &str,
+	variables: &JsonValue,
+) -> Option<JsonValue> {
+	let value = directive
+		.arguments
+		.iter()
+		.find(|(argument, _)| argument == name)
+		.map(|(_, value)| value)?;
+
+	match value {
+		Value::Variable(variable) => variables.get(variable).cloned(),
+		Value::Int(value) => value.as_i64().map(|v| json!(v)),
+		Value::Boolean(value) => Some(json!(value)),
+		Value::String(value) => Some(json!(value)),
+		_ => None,
+	}
+}
+
+/// Finds the directive, unless it's disabled with its `if` argument
+fn get_enabled_directive<'a>(
+	directives: &'a [Directive<'static, String>],
+	name: &str,
+	variables: &JsonValue,
+) -> Option<&'a Directive<'static, String>> {
+	directives.iter().find(|d| {
+		d.name == name
+			&& get_argument(d, "if", variables)
+				.and_then(|v| v.as_bool())
+				.unwrap_or(true)
+	})
+}
+
+fn get_label(directive: &Directive<'static, String>, variables: &JsonValue) -> Option<String> {
+	get_argument(directive, "label", variables).and_then(|v| v.as_str().map(|l| l.to_string()))
+}
+
+fn get_response_key(field: &Field<'static, String>) -> &str {
+	field.alias.as_ref().unwrap_or(&field.name)
+}
+
+fn has_incremental_directives(selection_set: &QuerySelectionSet) -> bool {
+	selection_set.items.iter().any(|item| match item {
+		Selection::Field(field) => {
+			field.directives.iter().any(is_incremental_directive)
+				|| has_incremental_directives(&field.selection_set)
+		}
+		Selection::InlineFragment(fragment) => {
+			fragment.directives.iter().any(is_incremental_directive)
+				|| has_incremental_directives(&fragment.selection_set)
+		}
+		Selection::FragmentSpread(spread) => spread.directives.iter().any(is_incremental_directive),
+	})
+}
+
+fn strip_selection_set(selection_set: &mut QuerySelectionSet) {
+	for item in selection_set.items.iter_mut() {
+		match item {
+			Selection::Field(field) => {
+				field.directives.retain(|d| !is_incremental_directive(d));
+				strip_selection_set(&mut field.selection_set);
+			}
+			Selection::InlineFragment(fragment) => {
+				fragment.directives.retain(|d| !is_incremental_directive(d));
+				strip_selection_set(&mut fragment.selection_set);
+			}
+			Selection::FragmentSpread(spread) => {
+				spread.directives.retain(|d| !is_incremental_directive(d));
+			}
+		}
+	}
+}
+
+fn get_selection_sets_mut(document: &mut QueryDocument) -> Vec<&mut QuerySelectionSet> {
+	document
+		.definitions
+		.iter_mut()
+		.map(|definition| match definition {
+			Definition::Operation(OperationDefinition::SelectionSet(s)) => s,
+			Definition::Operation(OperationDefinition::Query(q)) => &mut q.selection_set,
+			Definition::Operation(OperationDefinition::Mutation(m)) => &mut m.selection_set,
+			Definition::Operation(OperationDefinition::Subscription(s)) => &mut s.selection_set,
+			Definition::Fragment(f) => &mut f.selection_set,
+		})
+		.collect()
+}
+
+fn strip_document(document: &mut QueryDocument) {
+	for selection_set in get_selection_sets_mut(document) {
+		strip_selection_set(selection_set);
+	}
+}
+
+/// Removes the `@defer` and `@stream` directives of the request, so they're executed in a
+/// single response when the client can't receive incremental payloads
+pub fn strip_incremental_directives(request: &mut ApiRequest) {
+	let mut document = match parse_query::<String>(&request.query) {
+		Ok(document) => document.into_static(),
+		// Invalid documents are reported by the executor itself
+		Err(_) => return,
+	};
+
+	if !get_selection_sets_mut(&mut document)
+		.into_iter()
+		.any(|s| has_incremental_directives(s))
+	{
+		return;
+	}
+
+	strip_document(&mut document);
+
+	request.query = document.to_string();
+}
+
+pub(crate) fn get_fragment<'a>(
+	fragments: &'a [FragmentDefinition<'static, String>],
+	name: &str,
+) -> Option<&'a FragmentDefinition<'static, String>> {
+	fragments.iter().find(|f| f.name == name)
+}
+
+/// Response keys of the fields selected in the object, those of the fragments included
+fn get_selection_keys(
+	selection_set: &QuerySelectionSet,
+	fragments: &[FragmentDefinition<'static, String>],
+	visited: &mut HashSet<String>,
+	keys: &mut Vec<String>,
+) {
+	for item in &selection_set.items {
+		match item {
+			Selection::Field(field) => {
+				let key = get_response_key(field).to_string();
+
+				if !keys.contains(&key) {
+					keys.push(key);
+				}
+			}
+			Selection::InlineFragment(fragment) => {
+				get_selection_keys(&fragment.selection_set, fragments, visited, keys)
+			}
+			Selection::FragmentSpread(spread) => {
+				// Fragment cycles are reported by the executor itself
+				if !visited.insert(spread.fragment_name.clone()) {
+					continue;
+				}
+
+				if let Some(fragment) = get_fragment(fragments, &spread.fragment_name) {
+					get_selection_keys(&fragment.selection_set, fragments, visited, keys);
+				}
+			}
+		}
+	}
+}
+
+/// Keys selected by the fragment or by the spread fragment
+fn get_item_keys(
+	item: &Selection<'static, String>,
+	fragments: &[FragmentDefinition<'static, String>],
+) -> Vec<String> {
+	let mut keys = Vec::new();
+	let selection_set = match item {
+		Selection::Field(field) => return vec![get_response_key(field).to_string()],
+		Selection::InlineFragment(fragment) => &fragment.selection_set,
+		Selection::FragmentSpread(spread) => match get_fragment(fragments, &spread.fragment_name) {
+			Some(fragment) => &fragment.selection_set,
+			None => return keys,
+		},
+	};
+
+	get_selection_keys(selection_set, fragments, &mut HashSet::new(), &mut keys);
+
+	keys
+}
+
+fn get_deferred_directive<'a>(
+	item: &'a Selection<'static, String>,
+	variables: &JsonValue,
+) -> Option<&'a Directive<'static, String>> {
+	match item {
+		Selection::Field(_) => None,
+		Selection::InlineFragment(fragment) => {
+			get_enabled_directive(&fragment.directives, "defer", variables)
+		}
+		Selection::FragmentSpread(spread) => {
+			get_enabled_directive(&spread.directives, "defer", variables)
+		}
+	}
+}
+
+fn get_empty_selection_set() -> QuerySelectionSet {
+	SelectionSet {
+		span: Default::default(),
+		items: Vec::new(),
+	}
+}
+
+fn has_limit_argument(field: &MetaField<AlchemyScalarValue>) -> bool {
+	field.arguments.as_ref().map_or(false, |arguments| {
+		arguments.iter().any(|a| a.name == "limit")
+	})
+}
+
+/// Limits the list to its first `count` items, unless it's limited to fewer already
+fn limit_list(field: &mut Field<'static, String>, count: usize, variables: &JsonValue) {
+	let limit = Value::Int((count.min(i32::MAX as usize) as i32).into());
+
+	match field.arguments.iter_mut().find(|(name, _)| name == "limit") {
+		Some((_, value)) => {
+			let current = match value {
+				Value::Variable(variable) => variables.get(variable).and_then(|v| v.as_i64()),
+				Value::Int(current) => current.as_i64(),
+				_ => None,
+			};
+
+			if current.map_or(true, |current| current > count as i64) {
+				*value = limit;
+			}
+		}
+		None => field.arguments.push(("limit".to_string(), limit)),
+	}
+}
+
+struct PlanBuilder<'a> {
+	schema: &'a Schema,
+	document: &'a QueryDocument,
+	operation_index: usize,
+	variables: &'a JsonValue,
+	fragments: Vec<FragmentDefinition<'static, String>>,
+	deferred: Vec<DeferredFragment>,
+	streams: Vec<StreamedField>,
+	placeholder_paths: Vec<Vec<String>>,
+}
+
+impl<'a> PlanBuilder<'a> {
+	/// Query of the operation selecting only the item, under the fields and inline fragments
+	/// leading to it
+	fn get_partial_query(
+		&self,
+		ancestors: &[Selection<'static, String>],
+		item: Selection<'static, String>,
+	) -> String {
+		let mut selection_set = SelectionSet {
+			span: Default::default(),
+			items: vec![item],
+		};
+
+		for ancestor in ancestors.iter().rev() {
+			let mut ancestor = ancestor.clone();
+
+			match &mut ancestor {
+				Selection::Field(field) => field.selection_set = selection_set,
+				Selection::InlineFragment(fragment) => fragment.selection_set = selection_set,
+				Selection::FragmentSpread(_) => (),
+			}
+
+			selection_set = SelectionSet {
+				span: Default::default(),
+				items: vec![ancestor],
+			};
+		}
+
+		let mut document = self.document.clone();
+
+		if let Some(operation) = get_selection_sets_mut(&mut document)
+			.into_iter()
+			.nth(self.operation_index)
+		{
+			*operation = selection_set;
+		}
+
+		strip_document(&mut document);
+		remove_unused_definitions(&mut document, self.operation_index);
+
+		document.to_string()
+	}
+
+	/// Removes the deferred fragments of the operation and limits its streamed lists, collecting
+	/// the queries of their payloads. The directives of the named fragments are ignored as their
+	/// fields can be selected at several paths, those within streamed lists as the rest of the
+	/// lists is sent whole
+	fn split_selection_set(
+		&mut self,
+		selection_set: &mut QuerySelectionSet,
+		path: &[String],
+		ancestors: &[Selection<'static, String>],
+		meta_type: Option<&'a MetaType<'static, AlchemyScalarValue>>,
+	) {
+		let schema = self.schema;
+		let mut keys = Vec::new();
+
+		for item in &selection_set.items {
+			if get_deferred_directive(item, self.variables).is_none() {
+				keys.extend(get_item_keys(item, &self.fragments));
+			}
+		}
+
+		let mut items = Vec::new();
+		let mut is_split = false;
+
+		for mut item in selection_set.items.drain(..) {
+			if let Some(directive) = get_deferred_directive(&item, self.variables) {
+				let fragment_keys = get_item_keys(&item, &self.fragments);
+				let query = self.get_partial_query(ancestors, item.clone());
+
+				self.deferred.push(DeferredFragment {
+					label: get_label(directive, self.variables),
+					path: path.to_vec(),
+					hidden_keys: fragment_keys
+						.iter()
+						.filter(|k| !keys.contains(k))
+						.cloned()
+						.collect(),
+					keys: fragment_keys,
+					query,
+				});
+
+				is_split = true;
+
+				continue;
+			}
+
+			match &mut item {
+				Selection::Field(field) => {
+					let key = get_response_key(field).to_string();
+					let meta_field = meta_type.and_then(|t| t.field_by_name(&field.name));
+
+					let stream = get_enabled_directive(&field.directives, "stream", self.variables)
+						.map(|directive| {
+							(
+								get_label(directive, self.variables),
+								get_argument(directive, "initialCount", self.variables)
+									.and_then(|v| v.as_u64())
+									.unwrap_or(0) as usize,
+							)
+						});
+
+					if let Some((label, initial_count)) = stream {
+						let query =
+							self.get_partial_query(ancestors, Selection::Field(field.clone()));
+
+						self.streams.push(StreamedField {
+							label,
+							path: path.to_vec(),
+							key,
+							initial_count,
+							query,
+						});
+
+						// Lists without a limit are cut once resolved
+						if meta_field.map_or(false, has_limit_argument) {
+							limit_list(field, initial_count, self.variables);
+						}
+					} else {
+						let mut field_ancestors = ancestors.to_vec();
+						let mut field_path = path.to_vec();
+
+						field_ancestors.push(Selection::Field(Field {
+							position: field.position,
+							alias: field.alias.clone(),
+							name: field.name.clone(),
+							arguments: field.arguments.clone(),
+							directives: field.directives.clone(),
+							selection_set: get_empty_selection_set(),
+						}));
+						field_path.push(key);
+
+						let field_type = meta_field.and_then(|f| {
+							schema
+								.schema
+								.concrete_type_by_name(f.field_type.innermost_name())
+						});
+
+						self.split_selection_set(
+							&mut field.selection_set,
+							&field_path,
+							&field_ancestors,
+							field_type,
+						);
+					}
+				}
+				Selection::InlineFragment(fragment) => {
+					let mut fragment_ancestors = ancestors.to_vec();
+
+					fragment_ancestors.push(Selection::InlineFragment(InlineFragment {
+						position: fragment.position,
+						type_condition: fragment.type_condition.clone(),
+						directives: fragment.directives.clone(),
+						selection_set: get_empty_selection_set(),
+					}));
+
+					let fragment_type = match &fragment.type_condition {
+						Some(TypeCondition::On(name)) => schema.schema.concrete_type_by_name(name),
+						None => meta_type,
+					};
+
+					self.split_selection_set(
+						&mut fragment.selection_set,
+						path,
+						&fragment_ancestors,
+						fragment_type,
+					);
+				}
+				Selection::FragmentSpread(_) => (),
+			}
+
+			items.push(item);
+		}
+
+		// Selections left empty by the deferred fragments still need a field
+		if items.is_empty() && is_split {
+			self.placeholder_paths.push(path.to_vec());
+			items.push(Selection::Field(Field {
+				position: selection_set.span.0,
+				alias: None,
+				name: "__typename".to_string(),
+				arguments: Vec::new(),
+				directives: Vec::new(),
+				selection_set: get_empty_selection_set(),
+			}));
+		}
+
+		selection_set.items = items;
+	}
+}
+
+fn collect_spreads(selection_set: &QuerySelectionSet, names: &mut Vec<String>) {
+	for item in &selection_set.items {
+		match item {
+			Selection::Field(field) => collect_spreads(&field.selection_set, names),
+			Selection::InlineFragment(fragment) => collect_spreads(&fragment.selection_set, names),
+			Selection::FragmentSpread(spread) => names.push(spread.fragment_name.clone()),
+		}
+	}
+}
+
+fn collect_value_variables(value: &Value<'static, String>, variables: &mut HashSet<String>) {
+	match value {
+		Value::Variable(name) => {
+			variables.insert(name.clone());
+		}
+		Value::List(values) => {
+			for value in values {
+				collect_value_variables(value, variables);
+			}
+		}
+		Value::Object(values) => {
+			for value in values.values() {
+				collect_value_variables(value, variables);
+			}
+		}
+		_ => (),
+	}
+}
+
+fn collect_directive_variables(
+	directives: &[Directive<'static, String>],
+	variables: &mut HashSet<String>,
+) {
+	for directive in directives {
+		for (_, value) in &directive.arguments {
+			collect_value_variables(value, variables);
+		}
+	}
+}
+
+fn collect_variables(selection_set: &QuerySelectionSet, variables: &mut HashSet<String>) {
+	for item in &selection_set.items {
+		match item {
+			Selection::Field(field) => {
+				for (_, value) in &field.arguments {
+					collect_value_variables(value, variables);
+				}
+
+				collect_directive_variables(&field.directives, variables);
+				collect_variables(&field.selection_set, variables);
+			}
+			Selection::InlineFragment(fragment) => {
+				collect_directive_variables(&fragment.directives, variables);
+				collect_variables(&fragment.selection_set, variables);
+			}
+			Selection::FragmentSpread(spread) => {
+				collect_directive_variables(&spread.directives, variables)
+			}
+		}
+	}
+}
+
+/// Names of the fragments spread in the selection, those spread by the fragments included
+pub(crate) fn get_reachable_fragments(
+	selection_sets: &[&QuerySelectionSet],
+	fragments: &[FragmentDefinition<'static, String>],
+) -> HashSet<String> {
+	let mut names = Vec::new();
+	let mut reachable = HashSet::new();
+
+	for selection_set in selection_sets {
+		collect_spreads(selection_set, &mut names);
+	}
+
+	while let Some(name) = names.pop() {
+		if !reachable.insert(name.clone()) {
+			continue;
+		}
+
+		if let Some(fragment) = get_fragment(fragments, &name) {
+			collect_spreads(&fragment.selection_set, &mut names);
+		}
+	}
+
+	reachable
+}
+
+/// Removes the fragments and the variables the selections left out were the only ones to
+/// use, the executor rejects unused ones
+fn remove_unused_definitions(document: &mut QueryDocument, operation_index: usize) {
+	let fragments: Vec<FragmentDefinition<'static, String>> = document
+		.definitions
+		.iter()
+		.filter_map(|d| match d {
+			Definition::Fragment(f) => Some(f.clone()),
+			_ => None,
+		})
+		.collect();
+
+	let operations: Vec<&QuerySelectionSet> = document
+		.definitions
+		.iter()
+		.filter_map(|d| match d {
+			Definition::Operation(OperationDefinition::SelectionSet(s)) => Some(s),
+			Definition::Operation(OperationDefinition::Query(q)) => Some(&q.selection_set),
+			Definition::Operation(OperationDefinition::Mutation(m)) => Some(&m.selection_set),
+			Definition::Operation(OperationDefinition::Subscription(s)) => Some(&s.selection_set),
+			Definition::Fragment(_) => None,
+		})
+		.collect();
+
+	let reachable = get_reachable_fragments(&operations, &fragments);
+
+	let mut variables = HashSet::new();
+
+	if let Some(Definition::Operation(operation)) = document.definitions.get(operation_index) {
+		let selection_set = match operation {
+			OperationDefinition::SelectionSet(s) => s,
+			OperationDefinition::Query(q) => &q.selection_set,
+			OperationDefinition::Mutation(m) => &m.selection_set,
+			OperationDefinition::Subscription(s) => &s.selection_set,
+		};
+
+		collect_variables(selection_set, &mut variables);
+
+		for name in get_reachable_fragments(&[selection_set], &fragments) {
+			if let Some(fragment) = get_fragment(&fragments, &name) {
+				collect_variables(&fragment.selection_set, &mut variables);
+			}
+		}
+	}
+
+	if let Some(Definition::Operation(operation)) = document.definitions.get_mut(operation_index) {
+		let definitions = match operation {
+			OperationDefinition::Query(q) => Some((&mut q.variable_definitions, &q.directives)),
+			OperationDefinition::Mutation(m) => Some((&mut m.variable_definitions, &m.directives)),
+			_ => None,
+		};
+
+		if let Some((definitions, directives)) = definitions {
+			collect_directive_variables(directives, &mut variables);
+
+			definitions.retain(|d| variables.contains(&d.name));
+		}
+	}
+
+	document.definitions.retain(|d| match d {
+		Definition::Fragment(f) => reachable.contains(&f.name),
+		_ => true,
+	});
+}
+
+/// Index of the executed operation, the one named by the request or the only one
+pub(crate) fn get_operation_index(
+	document: &QueryDocument,
+	operation_name: Option<&str>,
+) -> Option<usize> {
+	let operations: Vec<(usize, Option<&String>)> = document
+		.definitions
+		.iter()
+		.enumerate()
+		.filter_map(|(i, d)| match d {
+			Definition::Operation(OperationDefinition::SelectionSet(_)) => Some((i, None)),
+			Definition::Operation(OperationDefinition::Query(q)) => Some((i, q.name.as_ref())),
+			Definition::Operation(OperationDefinition::Mutation(m)) => Some((i, m.name.as_ref())),
+			Definition::Operation(OperationDefinition::Subscription(s)) => {
+				Some((i, s.name.as_ref()))
+			}
+			Definition::Fragment(_) => None,
+		})
+		.collect();
+
+	match operation_name {
+		Some(name) => operations
+			.iter()
+			.find(|(_, n)| n.map(|n| n == name).unwrap_or(false))
+			.map(|(i, _)| *i),
+		None if operations.len() == 1 => Some(operations[0].0),
+		None => None,
+	}
+}
+
+/// Splits the operation of the request into its initial query and its deferred parts, none
+/// when it has no `@defer` or `@stream` directives
+pub fn get_incremental_plan(request: &ApiRequest, schema: &Schema) -> Option<IncrementalPlan> {
+	let mut document = parse_query::<String>(&request.query).ok()?.into_static();
+	let index = get_operation_index(&document, request.operation_name.as_deref())?;
+
+	let variables = serde_json::to_value(&request.variables).unwrap_or_default();
+	let mut initial = document.clone();
+
+	let mut builder = PlanBuilder {
+		schema,
+		document: &document,
+		operation_index: index,
+		variables: &variables,
+		fragments: document
+			.definitions
+			.iter()
+			.filter_map(|d| match d {
+				Definition::Fragment(f) => Some(f.clone()),
+				_ => None,
+			})
+			.collect(),
+		deferred: Vec::new(),
+		streams: Vec::new(),
+		placeholder_paths: Vec::new(),
+	};
+
+	let query_type = Some(schema.schema.concrete_query_type());
+
+	let is_mutation = match &mut initial.definitions[index] {
+		Definition::Operation(OperationDefinition::SelectionSet(s)) => {
+			builder.split_selection_set(s, &[], &[], query_type);
+			false
+		}
+		Definition::Operation(OperationDefinition::Query(q)) => {
+			builder.split_selection_set(&mut q.selection_set, &[], &[], query_type);
+			false
+		}
+		Definition::Operation(OperationDefinition::Mutation(m)) => {
+			let mutation_type = schema.schema.concrete_mutation_type();
+
+			builder.split_selection_set(&mut m.selection_set, &[], &[], mutation_type);
+			true
+		}
+		_ => return None,
+	};
+
+	if builder.deferred.is_empty() && builder.streams.is_empty() {
+		return None;
+	}
+
+	let PlanBuilder {
+		deferred,
+		streams,
+		placeholder_paths,
+		..
+	} = builder;
+
+	strip_document(&mut document);
+	strip_document(&mut initial);
+	remove_unused_definitions(&mut initial, index);
+
+	Some(IncrementalPlan {
+		initial_query: match is_mutation {
+			true => None,
+			false => Some(initial.to_string()),
+		},
+		query: document.to_string(),
+		placeholder_paths,
+		deferred,
+		streams,
+	})
+}
+
+/// Objects at the path of response keys with their path in the response, lists are walked
+/// through with the indices of their items
+fn get_objects<'a>(
+	value: &'a JsonValue,
+	keys: &[String],
+	path: Vec<JsonValue>,
+	objects: &mut Vec<(Vec<JsonValue>, &'a JsonMap<String, JsonValue>)>,
+) {
+	match value {
+		JsonValue::Array(items) => {
+			for (i, item) in items.iter().enumerate() {
+				let mut path = path.clone();
+
+				path.push(json!(i));
+				get_objects(item, keys, path, objects);
+			}
+		}
+		JsonValue::Object(object) => match keys.split_first() {
+			Some((key, keys)) => {
+				if let Some(value) = object.get(key) {
+					let mut path = path.clone();
+
+					path.push(json!(key));
+					get_objects(value, keys, path, objects);
+				}
+			}
+			None => objects.push((path, object)),
+		},
+		_ => (),
+	}
+}
+
+fn for_each_object_mut(
+	value: &mut JsonValue,
+	keys: &[String],
+	f: &mut dyn FnMut(&mut JsonMap<String, JsonValue>),
+) {
+	match value {
+		JsonValue::Array(items) => {
+			for item in items {
+				for_each_object_mut(item, keys, f);
+			}
+		}
+		JsonValue::Object(object) => match keys.split_first() {
+			Some((key, keys)) => {
+				if let Some(value) = object.get_mut(key) {
+					for_each_object_mut(value, keys, f);
+				}
+			}
+			None => f(object),
+		},
+		_ => (),
+	}
+}
+
+impl DeferredFragment {
+	/// Results of the fragment for each object at its path
+	fn get_results(&self, data: &JsonValue) -> Vec<JsonValue> {
+		let mut objects = Vec::new();
+
+		get_objects(data, &self.path, Vec::new(), &mut objects);
+
+		objects
+			.into_iter()
+			.filter_map(|(path, object)| {
+				let fields: JsonMap<String, JsonValue> = self
+					.keys
+					.iter()
+					.filter_map(|k| object.get(k).map(|v| (k.clone(), v.clone())))
+					.collect();
+
+				match fields.is_empty() {
+					true => None,
+					false => Some(get_incremental_result(
+						json!({ "data": fields }),
+						path,
+						&self.label,
+					)),
+				}
+			})
+			.collect()
+	}
+}
+
+impl StreamedField {
+	/// Results of the items after `initialCount` for each list at its path
+	fn get_results(&self, data: &JsonValue) -> Vec<JsonValue> {
+		let mut objects = Vec::new();
+
+		get_objects(data, &self.path, Vec::new(), &mut objects);
+
+		objects
+			.into_iter()
+			.filter_map(|(mut path, object)| {
+				let items = match object.get(&self.key) {
+					Some(JsonValue::Array(items)) if items.len() > self.initial_count => {
+						items[self.initial_count..].to_vec()
+					}
+					_ => return None,
+				};
+
+				path.push(json!(self.key));
+				path.push(json!(self.initial_count));
+
+				Some(get_incremental_result(
+					json!({ "items": items }),
+					path,
+					&self.label,
+				))
+			})
+			.collect()
+	}
+}
+
+/// Whether the error path is under the path of the result. The last index of the path of
+/// streamed items is the first one, the errors of the items after it are theirs as well
+fn is_result_error(error_path: &[JsonValue], path: &[JsonValue], is_items: bool) -> bool {
+	let (last, parent) = match path.split_last() {
+		Some(split) => split,
+		None => return true,
+	};
+
+	if error_path.len() < path.len() || !error_path.starts_with(parent) {
+		return false;
+	}
+
+	let index = &error_path[parent.len()];
+
+	match is_items {
+		true => index
+			.as_u64()
+			.zip(last.as_u64())
+			.map_or(false, |(i, first)| i >= first),
+		false => index == last,
+	}
+}
+
+impl IncrementalPlan {
+	fn get_initial_payload(&self, mut response: JsonValue) -> JsonValue {
+		if let Some(data) = response.get_mut("data") {
+			for path in &self.placeholder_paths {
+				for_each_object_mut(data, path, &mut |object| {
+					object.remove("__typename");
+				});
+			}
+
+			for fragment in &self.deferred {
+				for_each_object_mut(data, &fragment.path, &mut |object| {
+					for key in &fragment.hidden_keys {
+						object.remove(key);
+					}
+				});
+			}
+
+			for field in &self.streams {
+				for_each_object_mut(data, &field.path, &mut |object| {
+					if let Some(JsonValue::Array(items)) = object.get_mut(&field.key) {
+						items.truncate(field.initial_count);
+					}
+				});
+			}
+		}
+
+		response["hasNext"] = json!(true);
+
+		response
+	}
+
+	/// Queries of the deferred fragments then of the streamed lists, the payload of each one
+	/// is sent once it's resolved
+	fn get_part_queries(&self) -> Vec<String> {
+		self.deferred
+			.iter()
+			.map(|f| f.query.clone())
+			.chain(self.streams.iter().map(|f| f.query.clone()))
+			.collect()
+	}
+
+	/// Payload of the deferred fragment or the streamed list at the index of its query, each
+	/// result carrying the errors under its path
+	fn get_part_payload(&self, part: usize, mut response: JsonValue, has_next: bool) -> JsonValue {
+		let data = &response["data"];
+
+		let (results, is_items) = match part.checked_sub(self.deferred.len()) {
+			None => (self.deferred[part].get_results(data), false),
+			Some(stream) => (self.streams[stream].get_results(data), true),
+		};
+
+		let mut errors: Vec<JsonValue> = match response.get_mut("errors").map(JsonValue::take) {
+			Some(JsonValue::Array(errors)) => errors,
+			_ => Vec::new(),
+		};
+
+		let incremental: Vec<JsonValue> = results
+			.into_iter()
+			.map(|mut result| {
+				let path = result["path"].as_array().cloned().unwrap_or_default();
+
+				let (result_errors, rest): (Vec<JsonValue>, Vec<JsonValue>) =
+					errors.drain(..).partition(|error| {
+						error["path"]
+							.as_array()
+							.map_or(false, |e| is_result_error(e, &path, is_items))
+					});
+
+				errors = rest;
+
+				if !result_errors.is_empty() {
+					result["errors"] = json!(result_errors);
+				}
+
+				result
+			})
+			.collect();
+
+		let mut payload = json!({ "incremental": incremental, "hasNext": has_next });
+
+		if !errors.is_empty() {
+			payload["errors"] = json!(errors);
+		}
+
+		payload
+	}
+
+	/// Payload of the deferred fragments and the rest of the streamed lists taken from the
+	/// whole result, their errors were already sent with the initial payload
+	fn get_incremental_payload(&self, response: &JsonValue) -> JsonValue {
+		let data = &response["data"];
+
+		let incremental: Vec<JsonValue> = self
+			.deferred
+			.iter()
+			.flat_map(|fragment| fragment.get_results(data))
+			.chain(
+				self.streams
+					.iter()
+					.flat_map(|field| field.get_results(data)),
+			)
+			.collect();
+
+		json!({ "incremental": incremental, "hasNext": false })
+	}
+}
+
+fn get_incremental_result(
+	mut result: JsonValue,
+	path: Vec<JsonValue>,
+	label: &Option<String>,
+) -> JsonValue {
+	result["path"] = json!(path);
+
+	if let Some(label) = label {
+		result["label"] = json!(label);
+	}
+
+	result
+}
+
+async fn execute_query(
+	schema: Arc<Schema>,
+	session: Session,
+	request: ApiRequest,
+	query: String,
+) -> JsonValue {
+	let context = Context::new(session);
+	let request = GraphQLRequest::new(query, request.operation_name, request.variables);
+	let response = request.execute(&schema, &context).await;
+
+	serde_json::to_value(&response)
+		.unwrap_or_else(|e| json!({ "errors": [{ "message": e.to_string() }] }))
+}
+
+/// Executes the query in the database of the request, the payloads are streamed once the
+/// request is handled, outside of its database scope
+fn spawn_query(
+	schema: &Arc<Schema>,
+	session: &Session,
+	request: &ApiRequest,
+	query: String,
+) -> JoinHandle<JsonValue> {
+	actix_web::rt::spawn(in_current_database(execute_query(
+		schema.clone(),
+		session.clone(),
+		request.clone(),
+		query,
+	)))
+}
+
+async fn get_query_response(query: JoinHandle<JsonValue>) -> JsonValue {
+	query.await.unwrap_or_else(
+		|e| json!({ "errors": [{ "message": format!("Error executing the query: {}", e) }] }),
+	)
+}
+
+/// Sends the initial payload, then the deferred fragments and the rest of the streamed lists
+/// as the queries selecting them are resolved. Mutations are executed once and their result
+/// is split into the payloads
+pub fn incremental_response(
+	session: Session,
+	request: ApiRequest,
+	plan: IncrementalPlan,
+	schema: Arc<Schema>,
+	delivery: IncrementalDelivery,
+) -> ActixResponse {
+	let payloads = match plan.initial_query.clone() {
+		Some(initial_query) => {
+			let initial = spawn_query(&schema, &session, &request, initial_query);
+
+			let parts: FuturesUnordered<_> = plan
+				.get_part_queries()
+				.into_iter()
+				.enumerate()
+				.map(|(i, query)| {
+					let query = spawn_query(&schema, &session, &request, query);
+
+					async move { (i, get_query_response(query).await) }
+				})
+				.collect();
+
+			let count = parts.len();
+			let plan = Arc::new(plan);
+			let mut parts = Some(parts);
+
+			stream::once(get_query_response(initial))
+				.flat_map(move |mut response| {
+					let parts = parts.take().unwrap_or_default();
+
+					// Errors without data, the parts can't be sent under it
+					if !response["data"].is_object() {
+						response["hasNext"] = json!(false);
+
+						return stream::once(future::ready(response)).boxed();
+					}
+
+					let plan = plan.clone();
+
+					stream::once(future::ready(plan.get_initial_payload(response)))
+						.chain(parts.enumerate().map(move |(sent, (part, response))| {
+							plan.get_part_payload(part, response, sent + 1 < count)
+						}))
+						.boxed()
+				})
+				.boxed()
+		}
+		None => {
+			let query = spawn_query(&schema, &session, &request, plan.query.clone());
+
+			stream::once(get_query_response(query))
+				.flat_map(move |response| {
+					stream::iter([
+						plan.get_initial_payload(response.clone()),
+						plan.get_incremental_payload(&response),
+					])
+				})
+				.boxed()
+		}
+	};
+
+	let payloads = payloads
+		.map(move |payload| Ok::<_, ActixError>(delivery.format_payload(&payload)))
+		.chain(stream::once(async move {
+			Ok::<_, ActixError>(delivery.format_end())
+		}));
+
+	ActixResponse::Ok()
+		.content_type(delivery.get_content_type())
+		.insert_header((header::CACHE_CONTROL, "no-cache"))
+		.streaming(payloads)
+}
@@ -1,2 +1,22 @@
+pub mod analysis;
+pub mod auth;
+pub mod cluster;
+pub mod explain;
+pub mod export;
+pub mod files;
+pub mod grpc;
+pub mod health;
+pub mod import;
+pub mod incremental;
+pub mod openapi;
+pub mod persisted;
+pub mod rate_limit;
+pub mod request;
+pub mod response_cache;
+pub mod rest;
+pub mod root_fields;
+pub mod scheduler;
 pub mod schema;
 pub mod server;
+pub mod stats;
+pub mod tenants;
@@ -0,0 +1,350 @@
+use actix_web::{Error as ActixError, HttpResponse as ActixResponse};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::api::schema::files::FILE_TYPE;
+use crate::api::schema::{get_document_entities, Schema};
+use crate::api::server::error_response;
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::api::{DbEmbedded, DbEntity, DbProperty, DbScalarType};
+use crate::lib::CONFIG;
+
+fn get_ref(name: &str) -> JsonValue {
+	json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+/// JSON schema of a property value, embedded types are added to the components
+fn get_type_schema(
+	scalar_type: &DbScalarType,
+	input: bool,
+	components: &mut JsonMap<String, JsonValue>,
+) -> JsonValue {
+	match scalar_type {
+		DbScalarType::Array(item) => json!({
+			"type": "array",
+			"items": get_type_schema(item, input, components),
+		}),
+		DbScalarType::Enum(values) => json!({ "type": "string", "enum": values }),
+		DbScalarType::Embedded(embedded) => {
+			add_embedded_schema(embedded, input, components);
+
+			get_ref(&get_embedded_name(embedded, input))
+		}
+		DbScalarType::String => json!({ "type": "string" }),
+		DbScalarType::Object => json!({ "type": "object" }),
+		DbScalarType::Float => json!({ "type": "number" }),
+		DbScalarType::Int => json!({ "type": "integer" }),
+		DbScalarType::BigInt => json!({ "type": "string", "format": "int64" }),
+		DbScalarType::Boolean => json!({ "type": "boolean" }),
+		DbScalarType::Bytes => json!({ "type": "string", "format": "byte" }),
+		DbScalarType::DateTime => json!({ "type": "string", "format": "date-time" }),
+		DbScalarType::Decimal => json!({ "type": "string", "format": "decimal" }),
+		DbScalarType::Uuid => json!({ "type": "string", "format": "uuid" }),
+		// Files are set with the id of an upload and read with their metadata
+		DbScalarType::File(_) if input => json!({ "type": "string", "description": "File id" }),
+		DbScalarType::File(_) => {
+			add_embedded_schema(&FILE_TYPE, false, components);
+
+			get_ref(&FILE_TYPE.name)
+		}
+	}
+}
+
+fn get_embedded_name(embedded: &DbEmbedded, input: bool) -> String {
+	match input {
+		true => format!("{}Input", embedded.name),
+		false => embedded.name.clone(),
+	}
+}
+
+fn add_embedded_schema(
+	embedded: &DbEmbedded,
+	input: bool,
+	components: &mut JsonMap<String, JsonValue>,
+) {
+	let name = get_embedded_name(embedded, input);
+
+	if components.contains_key(&name) {
+		return;
+	}
+
+	// Inserted first so recursive types refer to it instead of being expanded again
+	components.insert(name.clone(), JsonValue::Null);
+
+	let schema = get_object_schema(embedded.properties.iter(), input, components);
+
+	components.insert(name, schema);
+}
+
+fn get_object_schema<'a>(
+	properties: impl Iterator<Item = &'a DbProperty>,
+	input: bool,
+	components: &mut JsonMap<String, JsonValue>,
+) -> JsonValue {
+	let mut schemas = JsonMap::new();
+	let mut required = Vec::new();
+
+	for property in properties {
+		let is_required = match input {
+			true => property.required && property.generator.is_none() && !property.is_key,
+			false => property.required,
+		};
+
+		if is_required {
+			required.push(property.name.clone());
+		}
+
+		let mut property_schema = get_type_schema(&property.scalar_type, input, components);
+
+		if let Some(reason) = &property.deprecation_reason {
+			property_schema["deprecated"] = json!(true);
+			property_schema["description"] = json!(reason);
+		}
+
+		schemas.insert(property.name.clone(), property_schema);
+	}
+
+	let mut schema = json!({ "type": "object", "properties": schemas });
+
+	if !required.is_empty() {
+		schema["required"] = json!(required);
+	}
+
+	schema
+}
+
+/// Schemas of the documents of the entity, of the attributes they're created with and of
+/// those they're updated with, all optional. Properties of the variants are always optional
+fn add_entity_schemas(entity: &DbEntity, components: &mut JsonMap<String, JsonValue>) {
+	let mut output = get_object_schema(entity.properties.iter(), false, components);
+	let mut input = get_object_schema(entity.properties.iter(), true, components);
+
+	for property in entity.get_variant_properties() {
+		output["properties"][&property.name] =
+			get_type_schema(&property.scalar_type, false, components);
+		input["properties"][&property.name] =
+			get_type_schema(&property.scalar_type, true, components);
+	}
+
+	for property in &entity.computed {
+		let mut schema = get_type_schema(&property.scalar_type, false, components);
+
+		schema["readOnly"] = json!(true);
+		output["properties"][&property.name] = schema;
+	}
+
+	let mut set = input.clone();
+
+	if let Some(set) = set.as_object_mut() {
+		set.remove("required");
+	}
+
+	components.insert(entity.name.clone(), output);
+	components.insert(format!("{}Input", entity.name), input);
+	components.insert(format!("{}Set", entity.name), set);
+}
+
+fn get_json_response(description: &str, schema: JsonValue) -> JsonValue {
+	json!({
+		"description": description,
+		"content": { "application/json": { "schema": schema } },
+	})
+}
+
+/// Parameters filtering the listed documents, properties with values that can't be written in
+/// a query string are only filtered with `where`
+fn get_filter_parameters(entity: &DbEntity) -> Vec<JsonValue> {
+	let mut parameters = vec![
+		json!({
+			"name": "limit",
+			"in": "query",
+			"schema": { "type": "integer" },
+		}),
+		json!({
+			"name": "where",
+			"in": "query",
+			"description": "JSON filter, with the same shape as the `where` argument of the GraphQL API",
+			"schema": { "type": "string" },
+		}),
+	];
+
+	let properties = entity
+		.properties
+		.iter()
+		.chain(entity.get_variant_properties())
+		.filter(|p| {
+			!matches!(
+				p.scalar_type,
+				DbScalarType::Array(_)
+					| DbScalarType::Embedded(_)
+					| DbScalarType::Object
+					| DbScalarType::Bytes
+			)
+		});
+
+	for property in properties {
+		parameters.push(json!({
+			"name": property.name,
+			"in": "query",
+			"description": format!(
+				"Equal to the value, `{0}[_neq]`, `{0}[_gt]`, `{0}[_gte]`, `{0}[_lt]`, `{0}[_lte]`, `{0}[_in]` and `{0}[_nin]` for the other operators",
+				property.name
+			),
+			"schema": match property.scalar_type {
+				DbScalarType::File(_) => json!({ "type": "string" }),
+				ref scalar_type => get_type_schema(scalar_type, true, &mut JsonMap::new()),
+			},
+		}));
+	}
+
+	parameters
+}
+
+/// Marks the operations of the paths deprecated, for deprecated collections
+fn set_paths_deprecated(path: &mut JsonValue) {
+	for (method, operation) in path.as_object_mut().into_iter().flatten() {
+		if method != "parameters" {
+			operation["deprecated"] = json!(true);
+		}
+	}
+}
+
+fn get_entity_paths(entity: &DbEntity, paths: &mut JsonMap<String, JsonValue>) {
+	let document = get_ref(&entity.name);
+	let input = get_ref(&format!("{}Input", entity.name));
+	let set = get_ref(&format!("{}Set", entity.name));
+	let error = get_ref("Error");
+
+	let key_parameter = json!({
+		"name": "key",
+		"in": "path",
+		"required": true,
+		"schema": { "type": "string" },
+	});
+
+	paths.insert(
+		format!("/api/rest/{}", entity.collection_name),
+		json!({
+			"get": {
+				"tags": [entity.name],
+				"operationId": format!("list{}", entity.name),
+				"parameters": get_filter_parameters(entity),
+				"responses": {
+					"200": get_json_response("Documents", json!({ "type": "array", "items": document })),
+					"400": get_json_response("Invalid filter", error),
+				},
+			},
+			"post": {
+				"tags": [entity.name],
+				"operationId": format!("create{}", entity.name),
+				"requestBody": {
+					"required": true,
+					"content": { "application/json": { "schema": input } },
+				},
+				"responses": {
+					"201": get_json_response("Created document", document),
+					"400": get_json_response("Invalid document", error),
+				},
+			},
+		}),
+	);
+
+	paths.insert(
+		format!("/api/rest/{}/{{key}}", entity.collection_name),
+		json!({
+			"parameters": [key_parameter],
+			"get": {
+				"tags": [entity.name],
+				"operationId": format!("get{}", entity.name),
+				"responses": {
+					"200": get_json_response("Document", document),
+					"404": get_json_response("Document not found", error),
+				},
+			},
+			"patch": {
+				"tags": [entity.name],
+				"operationId": format!("update{}", entity.name),
+				"requestBody": {
+					"required": true,
+					"content": { "application/json": { "schema": set } },
+				},
+				"responses": {
+					"200": get_json_response("Updated document", document),
+					"404": get_json_response("Document not found", error),
+				},
+			},
+			"delete": {
+				"tags": [entity.name],
+				"operationId": format!("remove{}", entity.name),
+				"responses": {
+					"200": get_json_response("Removed document", document),
+					"404": get_json_response("Document not found", error),
+				},
+			},
+		}),
+	);
+
+	if entity.deprecation_reason.is_some() {
+		for path in [
+			format!("/api/rest/{}", entity.collection_name),
+			format!("/api/rest/{}/{{key}}", entity.collection_name),
+		] {
+			set_paths_deprecated(&mut paths[&path]);
+		}
+	}
+}
+
+/// OpenAPI 3 specification of the REST routes of the collections
+pub fn get_openapi_spec(schema: &Schema) -> JsonValue {
+	let mut paths = JsonMap::new();
+	let mut components = JsonMap::new();
+
+	components.insert(
+		"Error".to_string(),
+		json!({
+			"type": "object",
+			"properties": {
+				"errors": {
+					"type": "array",
+					"items": {
+						"type": "object",
+						"properties": { "message": { "type": "string" } },
+					},
+				},
+			},
+		}),
+	);
+
+	for entity in get_document_entities(schema) {
+		add_entity_schemas(&entity, &mut components);
+		get_entity_paths(&entity, &mut paths);
+	}
+
+	json!({
+		"openapi": "3.0.3",
+		"info": {
+			"title": "Alchemy",
+			"version": env!("CARGO_PKG_VERSION"),
+		},
+		"paths": paths,
+		"components": {
+			"schemas": components,
+			"securitySchemes": {
+				"bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+			},
+		},
+		"security": [{ "bearerAuth": [] }],
+	})
+}
+
+/// Serves the specification of the current schema, it describes the collections like the
+/// GraphQL introspection so it's only available when introspection is enabled
+pub async fn openapi_route(schema: TenantSchema) -> Result<ActixResponse, ActixError> {
+	if !CONFIG.is_introspection_enabled() {
+		return Ok(error_response(
+			ActixResponse::NotFound(),
+			"OpenAPI specification is not available".to_string(),
+		));
+	}
+
+	Ok(ActixResponse::Ok().json(get_openapi_spec(&schema.load())))
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+use crate::api::request::ApiRequest;
+use crate::lib::CONFIG;
+
+/// Amount of automatically persisted queries kept in memory
+const MAX_CACHED_QUERIES: usize = 10_000;
+
+pub const PERSISTED_QUERY_NOT_FOUND: &str = "PersistedQueryNotFound";
+
+/// Queries registered by hash, either from the persisted queries file or the meta API
+pub struct PersistedQueries {
+	registered: RwLock<HashMap<String, String>>,
+	cached: RwLock<HashMap<String, String>>,
+}
+
+impl PersistedQueries {
+	pub fn load() -> Self {
+		let registered =
+			read_registered().unwrap_or_else(|e| panic!("Invalid persisted queries file: {}", e));
+
+		Self {
+			registered: RwLock::new(registered),
+			cached: RwLock::new(HashMap::new()),
+		}
+	}
+
+	/// Reads the persisted queries file again, keeping the registered queries when it's invalid
+	pub fn reload(&self) {
+		match read_registered() {
+			Ok(registered) => *self.registered.write().unwrap() = registered,
+			Err(e) => tracing::error!("Invalid persisted queries file: {}", e),
+		}
+	}
+
+	pub fn get(&self, hash: &str) -> Option<String> {
+		if let Some(query) = self.registered.read().unwrap().get(hash) {
+			return Some(query.clone());
+		}
+
+		self.cached.read().unwrap().get(hash).cloned()
+	}
+
+	pub fn is_registered(&self, hash: &str) -> bool {
+		self.registered.read().unwrap().contains_key(hash)
+	}
+
+	pub fn get_all(&self) -> Vec<(String, String)> {
+		self.registered
+			.read()
+			.unwrap()
+			.iter()
+			.map(|(hash, query)| (hash.clone(), query.clone()))
+			.collect()
+	}
+
+	/// Adds a query to the allowlist and returns its hash
+	pub fn register(&self, query: String) -> String {
+		let hash = hash_query(&query);
+
+		let mut registered = self.registered.write().unwrap();
+
+		registered.insert(hash.clone(), query);
+		save(&registered);
+
+		hash
+	}
+
+	pub fn remove(&self, hash: &str) -> bool {
+		let mut registered = self.registered.write().unwrap();
+
+		let removed = registered.remove(hash).is_some();
+
+		if removed {
+			save(&registered);
+		}
+
+		removed
+	}
+
+	/// Remembers a query sent by a client so it can later be referenced by hash only
+	fn cache(&self, hash: String, query: String) {
+		let mut cached = self.cached.write().unwrap();
+
+		if cached.len() >= MAX_CACHED_QUERIES {
+			cached.clear();
+		}
+
+		cached.insert(hash, query);
+	}
+}
+
+fn read_registered() -> Result<HashMap<String, String>, String> {
+	let path = match CONFIG.get_persisted_queries_file() {
+		Some(path) => path,
+		None => return Ok(HashMap::new()),
+	};
+
+	match fs::read_to_string(path) {
+		Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path, e)),
+		Err(_) => Ok(HashMap::new()),
+	}
+}
+
+fn save(registered: &HashMap<String, String>) {
+	if let Some(path) = CONFIG.get_persisted_queries_file() {
+		let contents = serde_json::to_string_pretty(registered).unwrap();
+
+		if let Err(e) = fs::write(path, contents) {
+			tracing::error!("Error saving persisted queries to {}: {}", path, e);
+		}
+	}
+}
+
+pub fn hash_query(query: &str) -> String {
+	format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+/// Fills in the query of requests that reference a persisted query by hash.
+/// With `PERSISTED_QUERIES_ONLY` enabled, anything not registered is rejected.
+pub fn resolve_persisted_query(request: &mut ApiRequest) -> Result<(), String> {
+	let allowlist_only = CONFIG.get_persisted_queries_only();
+
+	match request.get_persisted_query_hash().map(str::to_string) {
+		Some(hash) => {
+			if let Some(query) = PERSISTED_QUERIES.get(&hash) {
+				if allowlist_only && !PERSISTED_QUERIES.is_registered(&hash) {
+					return Err("Only registered queries are allowed".to_string());
+				}
+
+				request.query = query;
+
+				return Ok(());
+			}
+
+			if allowlist_only {
+				return Err("Only registered queries are allowed".to_string());
+			}
+
+			if request.query.is_empty() {
+				return Err(PERSISTED_QUERY_NOT_FOUND.to_string());
+			}
+
+			if hash_query(&request.query) != hash {
+				return Err("Provided sha256Hash does not match query".to_string());
+			}
+
+			PERSISTED_QUERIES.cache(hash, request.query.clone());
+
+			Ok(())
+		}
+		None => {
+			if request.query.is_empty() {
+				return Err("No query provided".to_string());
+			}
+
+			if allowlist_only && !PERSISTED_QUERIES.is_registered(&hash_query(&request.query)) {
+				return Err("Only registered queries are allowed".to_string());
+			}
+
+			Ok(())
+		}
+	}
+}
+
+lazy_static! {
+	pub static ref PERSISTED_QUERIES: PersistedQueries = PersistedQueries::load();
+}
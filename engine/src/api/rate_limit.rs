@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use actix_web::{http::header::HeaderName, HttpRequest as ActixRequest};
+use lazy_static::lazy_static;
+
+use crate::api::auth::Session;
+use crate::api::cluster::get_redis_connection;
+use crate::lib::CONFIG;
+
+/// Amount of buckets kept before idle ones are pruned
+const MAX_BUCKETS: usize = 10_000;
+
+struct Bucket {
+	tokens: f64,
+	updated_at: Instant,
+}
+
+/// Token bucket rate limiter, each role gets its own requests per minute limit. With Redis the
+/// instances share a counter per minute instead, the buckets being used when it's unreachable
+pub struct RateLimiter {
+	buckets: Mutex<HashMap<String, Bucket>>,
+	limits: HashMap<String, u32>,
+	default_limit: Option<u32>,
+	trusted_proxies: Vec<IpAddr>,
+}
+
+impl RateLimiter {
+	pub fn new(
+		limits: HashMap<String, u32>,
+		default_limit: Option<u32>,
+		trusted_proxies: Vec<IpAddr>,
+	) -> Self {
+		Self {
+			buckets: Mutex::new(HashMap::new()),
+			limits,
+			default_limit,
+			trusted_proxies,
+		}
+	}
+
+	/// Address the request comes from. Behind trusted proxies it's the last one of
+	/// `X-Forwarded-For` they didn't add themselves, the rest of the header can be sent by the
+	/// client
+	fn get_address(&self, req: &ActixRequest) -> String {
+		let peer = match req.peer_addr() {
+			Some(address) => address.ip(),
+			None => return "unknown".to_string(),
+		};
+
+		if !self.trusted_proxies.contains(&peer) {
+			return peer.to_string();
+		}
+
+		let forwarded: Vec<IpAddr> = req
+			.headers()
+			.get_all(HeaderName::from_static("x-forwarded-for"))
+			.filter_map(|h| h.to_str().ok())
+			.flat_map(|h| h.split(','))
+			.filter_map(|a| a.trim().parse::<IpAddr>().ok())
+			.collect();
+
+		forwarded
+			.into_iter()
+			.rev()
+			.find(|a| !self.trusted_proxies.contains(a))
+			.unwrap_or(peer)
+			.to_string()
+	}
+
+	/// Takes a token for the request, returns the seconds to wait when none is left
+	pub async fn check(&self, req: &ActixRequest, session: &Session) -> Result<(), u64> {
+		self.check_tokens(req, session, 1).await
+	}
+
+	/// Takes a token for each operation of the request, e.g. those of a batch
+	pub async fn check_tokens(
+		&self,
+		req: &ActixRequest,
+		session: &Session,
+		tokens: u32,
+	) -> Result<(), u64> {
+		let address = self.get_address(req);
+
+		self.take_tokens(&address, session, tokens).await
+	}
+
+	/// Takes a token for the session, anonymous ones are limited by their address
+	pub async fn check_address(&self, address: &str, session: &Session) -> Result<(), u64> {
+		self.take_tokens(address, session, 1).await
+	}
+
+	async fn take_tokens(&self, address: &str, session: &Session, tokens: u32) -> Result<(), u64> {
+		let limit = match self
+			.limits
+			.get(&session.role)
+			.or(self.default_limit.as_ref())
+		{
+			Some(limit) => *limit,
+			None => return Ok(()),
+		};
+
+		let key = match &session.user_id {
+			Some(id) => format!("user:{}", id),
+			None => format!("ip:{}", address),
+		};
+		let key = format!("{}:{}", session.role, key);
+
+		match Self::check_shared(&key, limit, tokens).await {
+			Some(result) => result,
+			None => self.check_bucket(key, limit as f64, tokens as f64),
+		}
+	}
+
+	/// Counts the operations in the Redis counter of the current minute, `None` without Redis
+	async fn check_shared(key: &str, limit: u32, tokens: u32) -> Option<Result<(), u64>> {
+		let mut connection = get_redis_connection().await?;
+
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_secs();
+		let counter = format!("alchemy:rate:{}:{}", key, now / 60);
+
+		let result = redis::pipe()
+			.cmd("INCRBY")
+			.arg(&counter)
+			.arg(tokens)
+			.cmd("EXPIRE")
+			.arg(&counter)
+			.arg(60)
+			.ignore()
+			.query_async::<_, (u32,)>(&mut connection)
+			.await;
+
+		match result {
+			Ok((count,)) if count > limit => Some(Err(60 - now % 60)),
+			Ok(_) => Some(Ok(())),
+			Err(e) => {
+				tracing::warn!("Error counting the request in Redis: {}", e);
+
+				None
+			}
+		}
+	}
+
+	fn check_bucket(&self, key: String, limit: f64, tokens: f64) -> Result<(), u64> {
+		let rate = limit / 60.0;
+		let now = Instant::now();
+
+		let mut buckets = self.buckets.lock().unwrap();
+
+		if buckets.len() > MAX_BUCKETS {
+			buckets.retain(|_, b| now.duration_since(b.updated_at).as_secs() < 60);
+		}
+
+		let bucket = buckets.entry(key).or_insert(Bucket {
+			tokens: limit,
+			updated_at: now,
+		});
+
+		let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+
+		bucket.tokens = (bucket.tokens + elapsed * rate).min(limit);
+		bucket.updated_at = now;
+
+		if bucket.tokens >= tokens {
+			bucket.tokens -= tokens;
+
+			Ok(())
+		} else {
+			Err(((tokens - bucket.tokens) / rate).ceil() as u64)
+		}
+	}
+}
+
+lazy_static! {
+	pub static ref RATE_LIMITER: RateLimiter = RateLimiter::new(
+		CONFIG.get_rate_limits(),
+		CONFIG.get_rate_limit_default(),
+		CONFIG.get_trusted_proxies(),
+	);
+}
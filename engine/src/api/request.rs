@@ -0,0 +1,153 @@
+use actix_web::{
+	error::JsonPayloadError,
+	web::{Payload as ActixPayload, Query},
+	Error as ActixError, FromRequest, HttpMessage, HttpRequest as ActixRequest,
+};
+use juniper::http::{GraphQLBatchRequest, GraphQLRequest};
+use juniper::InputValue;
+use serde::Deserialize;
+
+use crate::api::schema::AlchemyScalarValue;
+
+/// A single GraphQL operation as sent by the client
+#[derive(Deserialize, Clone, Debug)]
+pub struct ApiRequest {
+	/// Empty when the client only sends a persisted query hash
+	#[serde(default)]
+	pub query: String,
+	#[serde(rename = "operationName")]
+	pub operation_name: Option<String>,
+	pub variables: Option<InputValue<AlchemyScalarValue>>,
+	pub extensions: Option<ApiRequestExtensions>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ApiRequestExtensions {
+	#[serde(rename = "persistedQuery")]
+	pub persisted_query: Option<PersistedQueryExtension>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PersistedQueryExtension {
+	#[serde(rename = "sha256Hash")]
+	pub sha256_hash: String,
+}
+
+impl ApiRequest {
+	pub fn get_persisted_query_hash(&self) -> Option<&str> {
+		self.extensions
+			.as_ref()
+			.and_then(|e| e.persisted_query.as_ref())
+			.map(|p| p.sha256_hash.as_str())
+	}
+
+	pub fn get_variables(&self) -> juniper::Variables<AlchemyScalarValue> {
+		self.variables
+			.as_ref()
+			.and_then(|v| {
+				v.to_object_value().map(|o| {
+					o.into_iter()
+						.map(|(k, v)| (k.to_string(), v.clone()))
+						.collect()
+				})
+			})
+			.unwrap_or_default()
+	}
+}
+
+impl From<ApiRequest> for GraphQLRequest<AlchemyScalarValue> {
+	fn from(req: ApiRequest) -> Self {
+		GraphQLRequest::new(req.query, req.operation_name, req.variables)
+	}
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ApiBatchRequest {
+	Single(ApiRequest),
+	Batch(Vec<ApiRequest>),
+}
+
+#[derive(Deserialize)]
+struct GetApiRequest {
+	#[serde(default)]
+	query: String,
+	#[serde(rename = "operationName")]
+	operation_name: Option<String>,
+	variables: Option<String>,
+	extensions: Option<String>,
+}
+
+impl ApiBatchRequest {
+	/// Number of operations of the request
+	pub fn operation_count(&self) -> usize {
+		match self {
+			Self::Single(_) => 1,
+			Self::Batch(requests) => requests.len(),
+		}
+	}
+
+	/// Reads the GraphQL request from the query string on GET or from the body on POST
+	pub async fn from_http(req: &ActixRequest, payload: ActixPayload) -> Result<Self, ActixError> {
+		if req.method() == actix_web::http::Method::GET {
+			let get_req = Query::<GetApiRequest>::from_query(req.query_string())?.into_inner();
+
+			let variables = match get_req.variables {
+				Some(v) => Some(serde_json::from_str(&v).map_err(JsonPayloadError::Deserialize)?),
+				None => None,
+			};
+
+			let extensions = match get_req.extensions {
+				Some(e) => Some(serde_json::from_str(&e).map_err(JsonPayloadError::Deserialize)?),
+				None => None,
+			};
+
+			return Ok(Self::Single(ApiRequest {
+				query: get_req.query,
+				operation_name: get_req.operation_name,
+				variables,
+				extensions,
+			}));
+		}
+
+		let body = String::from_request(req, &mut payload.into_inner()).await?;
+
+		match req.content_type() {
+			"application/json" => {
+				Ok(serde_json::from_str::<Self>(&body).map_err(JsonPayloadError::Deserialize)?)
+			}
+			"application/graphql" => Ok(Self::Single(ApiRequest {
+				query: body,
+				operation_name: None,
+				variables: None,
+				extensions: None,
+			})),
+			_ => Err(JsonPayloadError::ContentType.into()),
+		}
+	}
+
+	pub fn requests(&self) -> Vec<&ApiRequest> {
+		match self {
+			Self::Single(req) => vec![req],
+			Self::Batch(reqs) => reqs.iter().collect(),
+		}
+	}
+
+	pub fn requests_mut(&mut self) -> Vec<&mut ApiRequest> {
+		match self {
+			Self::Single(req) => vec![req],
+			Self::Batch(reqs) => reqs.iter_mut().collect(),
+		}
+	}
+}
+
+impl From<ApiBatchRequest> for GraphQLBatchRequest<AlchemyScalarValue> {
+	fn from(req: ApiBatchRequest) -> Self {
+		match req {
+			ApiBatchRequest::Single(req) => GraphQLBatchRequest::Single(req.into()),
+			ApiBatchRequest::Batch(reqs) => {
+				GraphQLBatchRequest::Batch(reqs.into_iter().map(|r| r.into()).collect())
+			}
+		}
+	}
+}
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use juniper::meta::MetaType;
+use juniper::{parse_document_source, Definition, OperationType, Selection, Spanning};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::api::auth::Session;
+use crate::api::cluster::{get_redis_connection, is_redis_enabled};
+use crate::api::request::ApiRequest;
+use crate::api::schema::fields::find_property;
+use crate::api::schema::files::FILE_TYPE;
+use crate::api::schema::{
+	get_entities, get_query_operation_entity, get_type_data, AlchemyScalarValue, Schema,
+};
+use crate::lib::database::api::DbEntity;
+use crate::lib::database::tenants::{get_current_database_name, is_default_database};
+use crate::lib::CONFIG;
+
+/// Invalidates every cached response when the schema is reloaded
+pub const SCHEMA_TAG: &str = "alchemy_schema";
+
+/// Tag of a collection in the cache, prefixed with the database of the tenant so the tenants
+/// don't invalidate each other's responses
+fn get_cache_tag(collection: &str) -> String {
+	match is_default_database() {
+		true => collection.to_string(),
+		false => format!("{}/{}", get_current_database_name(), collection),
+	}
+}
+
+type Fragments<'a> = HashMap<&'a str, (&'a str, &'a [Selection<'a, AlchemyScalarValue>])>;
+
+/// How the response of a query is cached: for how long, and the collections whose writes
+/// invalidate it
+pub struct CachePolicy {
+	key: String,
+	max_age: u32,
+	collections: Vec<String>,
+	public: bool,
+}
+
+impl CachePolicy {
+	/// `Cache-Control` header of the response, private unless it's for anonymous sessions
+	pub fn get_cache_control(&self) -> String {
+		format!(
+			"{}, max-age={}",
+			if self.public { "public" } else { "private" },
+			self.max_age
+		)
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+	/// Versions of the collections when the response was executed
+	versions: Vec<u64>,
+	body: String,
+}
+
+enum CacheBackend {
+	Memory {
+		responses: Mutex<HashMap<String, (String, Instant)>>,
+		versions: Mutex<HashMap<String, u64>>,
+		size: usize,
+	},
+	/// Shared by every instance
+	Redis,
+}
+
+/// Responses of the queries reading only collections with a cache max age. Each collection
+/// has a version bumped by its writes, a response is only served while the versions of its
+/// collections are those it was executed with
+pub struct ResponseCache {
+	backend: CacheBackend,
+}
+
+impl ResponseCache {
+	fn new() -> Self {
+		let backend = match is_redis_enabled() {
+			true => CacheBackend::Redis,
+			false => CacheBackend::Memory {
+				responses: Mutex::new(HashMap::new()),
+				versions: Mutex::new(HashMap::new()),
+				size: CONFIG.get_response_cache_size(),
+			},
+		};
+
+		Self { backend }
+	}
+
+	/// Current versions of the collections of the policy, read before the query is executed
+	/// so writes happening meanwhile invalidate its response
+	pub async fn get_versions(&self, policy: &CachePolicy) -> Vec<u64> {
+		match &self.backend {
+			CacheBackend::Memory { versions, .. } => {
+				let versions = versions.lock().unwrap();
+
+				policy
+					.collections
+					.iter()
+					.map(|c| versions.get(c).copied().unwrap_or_default())
+					.collect()
+			}
+			CacheBackend::Redis => {
+				let mut connection = match get_redis_connection().await {
+					Some(connection) => connection,
+					None => return Vec::new(),
+				};
+
+				let keys: Vec<String> = policy
+					.collections
+					.iter()
+					.map(|c| format!("alchemy:collection:{}", c))
+					.collect();
+
+				redis::cmd("MGET")
+					.arg(keys)
+					.query_async::<_, Vec<Option<u64>>>(&mut connection)
+					.await
+					.map(|versions| {
+						versions
+							.into_iter()
+							.map(Option::unwrap_or_default)
+							.collect()
+					})
+					.unwrap_or_default()
+			}
+		}
+	}
+
+	/// Response cached for the policy, when the versions of its collections haven't changed
+	pub async fn get(&self, policy: &CachePolicy, versions: &[u64]) -> Option<String> {
+		let cached = match &self.backend {
+			CacheBackend::Memory { responses, .. } => {
+				let responses = responses.lock().unwrap();
+
+				let cached = responses
+					.get(&policy.key)
+					.filter(|(_, expires_at)| *expires_at > Instant::now())
+					.map(|(cached, _)| cached.clone());
+
+				cached
+			}
+			CacheBackend::Redis => {
+				let mut connection = get_redis_connection().await?;
+
+				redis::cmd("GET")
+					.arg(format!("alchemy:response:{}", policy.key))
+					.query_async::<_, Option<String>>(&mut connection)
+					.await
+					.ok()
+					.flatten()
+			}
+		}?;
+
+		let cached: CachedResponse = serde_json::from_str(&cached).ok()?;
+
+		match cached.versions == versions {
+			true => Some(cached.body),
+			false => None,
+		}
+	}
+
+	/// Caches the response of the policy, executed with the versions of its collections
+	pub async fn set(&self, policy: &CachePolicy, versions: Vec<u64>, body: String) {
+		if versions.len() != policy.collections.len() {
+			return;
+		}
+
+		let cached = serde_json::to_string(&CachedResponse { versions, body }).unwrap();
+
+		match &self.backend {
+			CacheBackend::Memory {
+				responses, size, ..
+			} => {
+				let mut responses = responses.lock().unwrap();
+				let now = Instant::now();
+
+				if responses.len() >= *size {
+					responses.retain(|_, (_, expires_at)| *expires_at > now);
+				}
+
+				if responses.len() >= *size {
+					responses.clear();
+				}
+
+				if *size > 0 {
+					responses.insert(
+						policy.key.clone(),
+						(cached, now + Duration::from_secs(policy.max_age as u64)),
+					);
+				}
+			}
+			CacheBackend::Redis => {
+				let mut connection = match get_redis_connection().await {
+					Some(connection) => connection,
+					None => return,
+				};
+
+				let result = redis::cmd("SET")
+					.arg(format!("alchemy:response:{}", policy.key))
+					.arg(cached)
+					.arg("EX")
+					.arg(policy.max_age)
+					.query_async::<_, ()>(&mut connection)
+					.await;
+
+				if let Err(e) = result {
+					tracing::warn!("Error caching the response: {}", e);
+				}
+			}
+		}
+	}
+
+	/// Invalidates the responses reading the collection, once it's written
+	pub async fn invalidate(&self, collection: &str) {
+		let collection = get_cache_tag(collection);
+
+		match &self.backend {
+			CacheBackend::Memory { versions, .. } => {
+				*versions.lock().unwrap().entry(collection).or_default() += 1;
+			}
+			CacheBackend::Redis => {
+				let mut connection = match get_redis_connection().await {
+					Some(connection) => connection,
+					None => return,
+				};
+
+				let result = redis::cmd("INCR")
+					.arg(format!("alchemy:collection:{}", collection))
+					.query_async::<_, u64>(&mut connection)
+					.await;
+
+				if let Err(e) = result {
+					tracing::error!("Error invalidating the responses of {}: {}", collection, e);
+				}
+			}
+		}
+	}
+}
+
+lazy_static! {
+	pub static ref RESPONSE_CACHE: ResponseCache = ResponseCache::new();
+}
+
+/// Walks the selection of a query, collecting the collections it reads
+struct PolicyBuilder<'a, 's> {
+	schema: &'s Schema,
+	fragments: Fragments<'a>,
+	max_age: Option<u32>,
+	collections: Vec<String>,
+}
+
+impl<'a, 's> PolicyBuilder<'a, 's> {
+	fn add_collection(&mut self, collection: &str) {
+		if !self.collections.iter().any(|c| c == collection) {
+			self.collections.push(collection.to_string());
+		}
+	}
+
+	/// Responses reading an entity without a cache max age aren't cached
+	fn add_entity(&mut self, entity: &DbEntity) -> bool {
+		let max_age = match entity.cache_max_age {
+			Some(max_age) if max_age > 0 => max_age,
+			_ => return false,
+		};
+
+		self.max_age = Some(self.max_age.map_or(max_age, |m| m.min(max_age)));
+		self.add_collection(&entity.collection_name);
+
+		true
+	}
+
+	/// Adds the entity of the type, or those of the union
+	fn add_type(&mut self, type_name: &str) -> bool {
+		if let Some(data) = get_type_data(self.schema, type_name) {
+			return self.add_entity(&data.entity);
+		}
+
+		let schema = self.schema;
+
+		match schema.schema.concrete_type_by_name(type_name) {
+			Some(MetaType::Union(union)) => union.of_type_names.iter().all(|t| self.add_type(t)),
+			_ => true,
+		}
+	}
+
+	fn visit(
+		&mut self,
+		selection_set: &'a [Selection<'a, AlchemyScalarValue>],
+		meta_type: &MetaType<AlchemyScalarValue>,
+		is_root: bool,
+		spreads: &mut Vec<&'a str>,
+	) -> bool {
+		let schema = self.schema;
+		let type_data = meta_type.name().and_then(|n| get_type_data(schema, n));
+
+		for selection in selection_set {
+			let is_cacheable = match selection {
+				Selection::Field(Spanning { item: f, .. }) => {
+					if f.name.item == "__typename" {
+						continue;
+					}
+
+					// Actions and shortest paths may read anything
+					if is_root {
+						match get_query_operation_entity(schema, f.name.item) {
+							Some(entity) if self.add_entity(&entity) => {}
+							_ => return false,
+						}
+					}
+
+					let meta_field = match meta_type.field_by_name(f.name.item) {
+						Some(field) => field,
+						None => return false,
+					};
+
+					// Fields with a `@cache` directive lower the max age of the response
+					let field_max_age = type_data
+						.as_ref()
+						.zip(meta_type.name())
+						.and_then(|(data, type_name)| {
+							find_property(&data.entity, type_name, f.name.item)
+						})
+						.and_then(|p| p.directives.cache_max_age);
+
+					match field_max_age {
+						Some(0) => return false,
+						Some(max_age) => {
+							self.max_age = Some(self.max_age.map_or(max_age, |m| m.min(max_age)))
+						}
+						None => {}
+					}
+
+					if let Some(data) = &type_data {
+						for relationship in data.relationships.iter().filter(|r| {
+							r.name == f.name.item || r.get_edges_field_name() == f.name.item
+						}) {
+							self.add_collection(&relationship.edge);
+						}
+					}
+
+					let inner_type_name = meta_field.field_type.innermost_name();
+
+					// Signed file URLs expire and graph traversals reach any of their collections
+					if inner_type_name == FILE_TYPE.name
+						|| inner_type_name == "GraphNeighbor"
+						|| inner_type_name == "GraphPathStep"
+					{
+						return false;
+					}
+
+					match (
+						&f.selection_set,
+						schema.schema.concrete_type_by_name(inner_type_name),
+					) {
+						(Some(inner_selection_set), Some(inner_type)) => {
+							self.add_type(inner_type_name)
+								&& self.visit(inner_selection_set, inner_type, false, spreads)
+						}
+						_ => true,
+					}
+				}
+				Selection::FragmentSpread(Spanning { item: spread, .. }) => {
+					let name = spread.name.item;
+
+					if spreads.contains(&name) {
+						continue;
+					}
+
+					let (type_condition, fragment_selection_set) = match self.fragments.get(name) {
+						Some(fragment) => *fragment,
+						None => return false,
+					};
+
+					let fragment_type = schema
+						.schema
+						.concrete_type_by_name(type_condition)
+						.unwrap_or(meta_type);
+
+					spreads.push(name);
+					let is_cacheable =
+						self.visit(fragment_selection_set, fragment_type, is_root, spreads);
+					spreads.pop();
+
+					is_cacheable
+				}
+				Selection::InlineFragment(Spanning { item: inline, .. }) => {
+					let fragment_type = inline
+						.type_condition
+						.as_ref()
+						.and_then(|t| schema.schema.concrete_type_by_name(t.item))
+						.unwrap_or(meta_type);
+
+					self.visit(&inline.selection_set, fragment_type, is_root, spreads)
+				}
+			};
+
+			if !is_cacheable {
+				return false;
+			}
+		}
+
+		true
+	}
+}
+
+/// Cache policy of a query, `None` unless every collection it reads has a cache max age.
+/// Responses are cached by the normalized query, its variables and the session
+pub fn get_cache_policy(
+	request: &ApiRequest,
+	schema: &Schema,
+	session: &Session,
+) -> Option<CachePolicy> {
+	if get_entities(schema)
+		.iter()
+		.all(|e| e.cache_max_age.is_none())
+	{
+		return None;
+	}
+
+	let document = parse_document_source(request.query.as_str(), &schema.schema).ok()?;
+
+	let mut fragments: Fragments = HashMap::new();
+	let mut operations = Vec::new();
+
+	for definition in &document {
+		match definition {
+			Definition::Operation(Spanning { item: op, .. }) => operations.push(op),
+			Definition::Fragment(Spanning { item: f, .. }) => {
+				fragments.insert(f.name.item, (f.type_condition.item, &f.selection_set[..]));
+			}
+		}
+	}
+
+	let operation = match &request.operation_name {
+		Some(name) => operations
+			.into_iter()
+			.find(|op| op.name.as_ref().map(|n| n.item) == Some(name.as_str()))?,
+		None if operations.len() == 1 => operations.pop()?,
+		None => return None,
+	};
+
+	if !matches!(operation.operation_type, OperationType::Query) {
+		return None;
+	}
+
+	let mut builder = PolicyBuilder {
+		schema,
+		fragments,
+		max_age: None,
+		collections: vec![SCHEMA_TAG.to_string()],
+	};
+
+	let root_type = schema.schema.concrete_query_type();
+
+	if !builder.visit(&operation.selection_set, root_type, true, &mut Vec::new()) {
+		return None;
+	}
+
+	let query = graphql_parser::parse_query::<&str>(&request.query)
+		.map(|document| document.to_string())
+		.unwrap_or_else(|_| request.query.clone());
+
+	let key = json!({
+		"query": query,
+		"operationName": request.operation_name,
+		"variables": request.variables,
+		"session": session.get_variables(),
+		"anonymous": session.anonymous,
+		"database": get_current_database_name(),
+	});
+
+	Some(CachePolicy {
+		key: format!("{:x}", Sha256::digest(key.to_string().as_bytes())),
+		max_age: builder.max_age?,
+		collections: builder
+			.collections
+			.iter()
+			.map(|c| get_cache_tag(c))
+			.collect(),
+		public: session.anonymous,
+	})
+}
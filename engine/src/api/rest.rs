@@ -0,0 +1,469 @@
+use std::sync::Arc;
+
+use actix_web::{
+	web::{Json, Path, Query},
+	Error as ActixError, HttpRequest as ActixRequest, HttpResponse as ActixResponse,
+	HttpResponseBuilder,
+};
+use convert_case::Casing;
+use juniper::{InputValue, Variables};
+use serde_json::{json, Value as JsonValue};
+
+use crate::api::auth::Session;
+use crate::api::rate_limit::RATE_LIMITER;
+use crate::api::schema::errors::ErrorCode;
+use crate::api::schema::files::FILE_TYPE;
+use crate::api::schema::{get_collection_entity, AlchemyScalarValue, Context, Schema};
+use crate::api::server::{error_response, rate_limited_response};
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::api::{DbEntity, DbProperty, DbScalarType};
+use crate::lib::naming::get_operation_name;
+use crate::lib::CONFIG;
+
+/// Query string parameters that aren't property filters
+const RESERVED_PARAMS: [&str; 2] = ["limit", "where"];
+
+fn get_property_selection(name: &str, scalar_type: &DbScalarType, session: &Session) -> String {
+	match scalar_type {
+		DbScalarType::Array(item) => get_property_selection(name, item, session),
+		DbScalarType::Embedded(embedded) => {
+			format!(
+				"{} {{ {} }}",
+				name,
+				get_selection(&embedded.properties, session)
+			)
+		}
+		DbScalarType::File(_) => format!(
+			"{} {{ {} }}",
+			name,
+			get_selection(&FILE_TYPE.properties, session)
+		),
+		_ => name.to_string(),
+	}
+}
+
+/// Fields with an `@auth` directive not allowing the role are left out
+fn get_selection(properties: &[DbProperty], session: &Session) -> String {
+	properties
+		.iter()
+		.filter(|p| session.can_read_field(p))
+		.map(|p| get_property_selection(&p.name, &p.scalar_type, session))
+		.collect::<Vec<String>>()
+		.join(" ")
+}
+
+/// Selects every property of the entity, the REST routes respond with whole documents
+fn get_entity_selection(entity: &DbEntity, session: &Session) -> String {
+	let mut selection = get_selection(&entity.properties, session);
+
+	if !entity.computed.is_empty() {
+		selection = format!("{} {}", selection, get_selection(&entity.computed, session));
+	}
+
+	for variant in &entity.variants {
+		selection = format!(
+			"{} ... on {} {{ {} }}",
+			selection,
+			variant.name,
+			get_selection(&variant.properties, session)
+		);
+	}
+
+	selection
+}
+
+/// Value of a filter parameter, typed after the property it filters
+fn parse_param_value(value: &str, scalar_type: &DbScalarType) -> Result<JsonValue, String> {
+	let parsed = match scalar_type {
+		DbScalarType::Int | DbScalarType::Float => serde_json::from_str(value)
+			.ok()
+			.filter(|v: &JsonValue| v.is_number()),
+		DbScalarType::Boolean => value.parse::<bool>().ok().map(JsonValue::Bool),
+		_ => Some(JsonValue::String(value.to_string())),
+	};
+
+	parsed.ok_or_else(|| format!("Invalid value {}", value))
+}
+
+/// Filter of the `where` parameter, holding a JSON filter like the API argument, and of the
+/// property parameters: `name=value` for equality and `name[op]=value` for the other
+/// operators, `_in` and `_nin` taking comma separated values
+fn get_filter(entity: &DbEntity, params: &[(String, String)]) -> Result<Option<JsonValue>, String> {
+	let mut filters = Vec::new();
+
+	for (key, value) in params {
+		if key == "where" {
+			filters
+				.push(serde_json::from_str(value).map_err(|e| format!("Invalid filter: {}", e))?);
+			continue;
+		}
+
+		if RESERVED_PARAMS.contains(&key.as_str()) {
+			continue;
+		}
+
+		let (name, operator) = match key.split_once('[') {
+			Some((name, operator)) => {
+				(name, operator.trim_end_matches(']').trim_start_matches('_'))
+			}
+			None => (key.as_str(), "eq"),
+		};
+
+		let scalar_type = entity
+			.properties
+			.iter()
+			.chain(entity.get_variant_properties())
+			.find(|p| p.name == name)
+			.map(|p| &p.scalar_type)
+			.ok_or_else(|| format!("Field {} not found in {}", name, entity.name))?;
+
+		let value = match operator {
+			"in" | "nin" => JsonValue::Array(
+				value
+					.split(',')
+					.map(|v| parse_param_value(v, scalar_type))
+					.collect::<Result<Vec<JsonValue>, String>>()?,
+			),
+			_ => parse_param_value(value, scalar_type)?,
+		};
+
+		filters.push(json!({ name: { format!("_{}", operator): value } }));
+	}
+
+	Ok(match filters.len() {
+		0 => None,
+		1 => filters.pop(),
+		_ => Some(json!({ "_and": filters })),
+	})
+}
+
+/// Operation on a collection outside of GraphQL, executed as the GraphQL operation of the
+/// entity so it goes through the same permissions, hooks and events
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CrudOperation {
+	/// Variables `where` and `limit`
+	List,
+	/// Variable `key`
+	Get,
+	/// Variable `object`, holding the `attributes` of the document
+	Create,
+	/// Variables `key` and `set`
+	Update,
+	/// Variable `key`
+	Remove,
+}
+
+impl CrudOperation {
+	/// Name of the GraphQL field of the operation and the document executing it, selecting
+	/// whole documents
+	fn get_document(&self, entity: &DbEntity, session: &Session) -> (String, String) {
+		let selection = get_entity_selection(entity, session);
+
+		let field = match self {
+			CrudOperation::List => get_operation_name(CONFIG.get_query_verb(), &entity.name, true),
+			CrudOperation::Get => get_operation_name(CONFIG.get_query_verb(), &entity.name, false),
+			CrudOperation::Create => get_operation_name("create", &entity.name, false),
+			CrudOperation::Update => get_operation_name("update", &entity.name, false),
+			CrudOperation::Remove => get_operation_name("remove", &entity.name, false),
+		};
+
+		let document = match self {
+			CrudOperation::List => format!(
+				"query($where: {}BoolExp, $limit: Int) {{ {}(where: $where, limit: $limit) {{ {} }} }}",
+				entity.name.to_case(convert_case::Case::Pascal),
+				field,
+				selection
+			),
+			CrudOperation::Get => format!(
+				"query($key: ID!) {{ {}(where: {{ _key: $key }}) {{ {} }} }}",
+				field, selection
+			),
+			CrudOperation::Create => format!(
+				"mutation($object: {}Insert!) {{ {}(object: $object) {{ {} }} }}",
+				entity.name, field, selection
+			),
+			CrudOperation::Update => format!(
+				"mutation($key: ID!, $set: {}Set!) {{ {}(where: {{ _key: $key }}, _set: $set) {{ {} }} }}",
+				entity.name, field, selection
+			),
+			CrudOperation::Remove => format!(
+				"mutation($key: ID!) {{ {}(where: {{ _key: $key }}) {{ {} }} }}",
+				field, selection
+			),
+		};
+
+		(field, document)
+	}
+}
+
+pub enum OperationError {
+	Forbidden(String),
+	NotFound(String),
+	Invalid(String),
+	Timeout(String),
+}
+
+/// Executes the operation on the documents of the entity, returning its result
+pub async fn execute_operation(
+	session: Session,
+	schema: &Schema,
+	entity: &DbEntity,
+	operation: CrudOperation,
+	variables: JsonValue,
+) -> Result<JsonValue, OperationError> {
+	let mut input = Variables::new();
+
+	for (k, v) in variables.as_object().into_iter().flatten() {
+		let value: InputValue<AlchemyScalarValue> = serde_json::from_value(v.clone())
+			.map_err(|e| OperationError::Invalid(e.to_string()))?;
+
+		input.insert(k.clone(), value);
+	}
+
+	let (field, document) = operation.get_document(entity, &session);
+	let context = Context::new(session);
+
+	let (value, errors) = juniper::execute(&document, None, schema, &input, &context)
+		.await
+		.map_err(|e| OperationError::Invalid(e.to_string()))?;
+
+	if let Some(error) = errors.first() {
+		let message = error.error().message().to_string();
+		let code = error
+			.error()
+			.extensions()
+			.as_object_value()
+			.and_then(|e| e.get_field_value("code"))
+			.and_then(|c| c.as_string_value());
+
+		return Err(match code {
+			Some(c) if c == ErrorCode::Forbidden.as_str() => OperationError::Forbidden(message),
+			Some(c) if c == ErrorCode::NotFound.as_str() => OperationError::NotFound(message),
+			Some(c) if c == ErrorCode::Timeout.as_str() => OperationError::Timeout(message),
+			_ => OperationError::Invalid(message),
+		});
+	}
+
+	let result = serde_json::to_value(&value)
+		.unwrap_or_default()
+		.get_mut(&field)
+		.map(JsonValue::take)
+		.unwrap_or_default();
+
+	match result.is_null() {
+		true => Err(OperationError::NotFound(format!(
+			"{} not found",
+			entity.name
+		))),
+		false => Ok(result),
+	}
+}
+
+/// Checks the session like the GraphQL route, then the collection exists in the schema
+async fn get_rest_entity(
+	req: &ActixRequest,
+	schema: &Schema,
+	collection: &str,
+) -> Result<(Session, Arc<DbEntity>), ActixResponse> {
+	let session = match Session::from_request(req) {
+		Ok(session) => session,
+		Err(e) => return Err(error_response(ActixResponse::Unauthorized(), e.to_string())),
+	};
+
+	if let Err(retry_after) = RATE_LIMITER.check(req, &session).await {
+		return Err(rate_limited_response(retry_after));
+	}
+
+	match get_collection_entity(schema, collection).filter(|e| e.singleton.is_none()) {
+		Some(entity) => Ok((session, entity)),
+		None => Err(error_response(
+			ActixResponse::NotFound(),
+			format!("Collection {} not found", collection),
+		)),
+	}
+}
+
+async fn execute_rest_operation(
+	session: Session,
+	schema: &Schema,
+	entity: &DbEntity,
+	operation: CrudOperation,
+	variables: JsonValue,
+	mut response: HttpResponseBuilder,
+) -> Result<ActixResponse, ActixError> {
+	Ok(
+		match execute_operation(session, schema, entity, operation, variables).await {
+			Ok(result) => response.json(result),
+			Err(OperationError::Forbidden(message)) => {
+				error_response(ActixResponse::Forbidden(), message)
+			}
+			Err(OperationError::NotFound(message)) => {
+				error_response(ActixResponse::NotFound(), message)
+			}
+			Err(OperationError::Invalid(message)) => {
+				error_response(ActixResponse::BadRequest(), message)
+			}
+			Err(OperationError::Timeout(message)) => {
+				error_response(ActixResponse::GatewayTimeout(), message)
+			}
+		},
+	)
+}
+
+/// Lists the documents of the collection, filtered with the query string
+pub async fn rest_list_route(
+	req: ActixRequest,
+	collection: Path<String>,
+	params: Query<Vec<(String, String)>>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let schema = schema.load_full();
+
+	let (session, entity) = match get_rest_entity(&req, &schema, &collection).await {
+		Ok(result) => result,
+		Err(response) => return Ok(response),
+	};
+
+	let filter = match get_filter(&entity, &params) {
+		Ok(filter) => filter,
+		Err(e) => return Ok(error_response(ActixResponse::BadRequest(), e)),
+	};
+
+	let limit = match params.iter().find(|(k, _)| k == "limit") {
+		Some((_, limit)) => match limit.parse::<i32>() {
+			Ok(limit) => Some(limit),
+			Err(_) => {
+				return Ok(error_response(
+					ActixResponse::BadRequest(),
+					format!("Invalid limit {}", limit),
+				))
+			}
+		},
+		None => None,
+	};
+
+	execute_rest_operation(
+		session,
+		&schema,
+		&entity,
+		CrudOperation::List,
+		json!({ "where": filter, "limit": limit }),
+		ActixResponse::Ok(),
+	)
+	.await
+}
+
+/// Creates a document in the collection from the JSON body holding its attributes
+pub async fn rest_create_route(
+	req: ActixRequest,
+	collection: Path<String>,
+	body: Json<JsonValue>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let schema = schema.load_full();
+
+	let (session, entity) = match get_rest_entity(&req, &schema, &collection).await {
+		Ok(result) => result,
+		Err(response) => return Ok(response),
+	};
+
+	if !body.is_object() {
+		return Ok(error_response(
+			ActixResponse::BadRequest(),
+			"The body must be a JSON object".to_string(),
+		));
+	}
+
+	execute_rest_operation(
+		session,
+		&schema,
+		&entity,
+		CrudOperation::Create,
+		json!({ "object": { "attributes": body.into_inner() } }),
+		ActixResponse::Created(),
+	)
+	.await
+}
+
+/// Gets the document of the collection with the key
+pub async fn rest_get_route(
+	req: ActixRequest,
+	path: Path<(String, String)>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let schema = schema.load_full();
+	let (collection, key) = path.into_inner();
+
+	let (session, entity) = match get_rest_entity(&req, &schema, &collection).await {
+		Ok(result) => result,
+		Err(response) => return Ok(response),
+	};
+
+	execute_rest_operation(
+		session,
+		&schema,
+		&entity,
+		CrudOperation::Get,
+		json!({ "key": key }),
+		ActixResponse::Ok(),
+	)
+	.await
+}
+
+/// Sets the attributes of the JSON body on the document of the collection with the key
+pub async fn rest_update_route(
+	req: ActixRequest,
+	path: Path<(String, String)>,
+	body: Json<JsonValue>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let schema = schema.load_full();
+	let (collection, key) = path.into_inner();
+
+	let (session, entity) = match get_rest_entity(&req, &schema, &collection).await {
+		Ok(result) => result,
+		Err(response) => return Ok(response),
+	};
+
+	if !body.is_object() {
+		return Ok(error_response(
+			ActixResponse::BadRequest(),
+			"The body must be a JSON object".to_string(),
+		));
+	}
+
+	execute_rest_operation(
+		session,
+		&schema,
+		&entity,
+		CrudOperation::Update,
+		json!({ "key": key, "set": body.into_inner() }),
+		ActixResponse::Ok(),
+	)
+	.await
+}
+
+/// Removes the document of the collection with the key, responding with it
+pub async fn rest_delete_route(
+	req: ActixRequest,
+	path: Path<(String, String)>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let schema = schema.load_full();
+	let (collection, key) = path.into_inner();
+
+	let (session, entity) = match get_rest_entity(&req, &schema, &collection).await {
+		Ok(result) => result,
+		Err(response) => return Ok(response),
+	};
+
+	execute_rest_operation(
+		session,
+		&schema,
+		&entity,
+		CrudOperation::Remove,
+		json!({ "key": key }),
+		ActixResponse::Ok(),
+	)
+	.await
+}
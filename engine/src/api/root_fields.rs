@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use graphql_parser::query::{
+	parse_query, Definition, Document, FragmentDefinition, OperationDefinition, Selection,
+	SelectionSet, TypeCondition,
+};
+
+use crate::api::incremental::{get_fragment, get_operation_index, get_reachable_fragments};
+use crate::api::request::ApiRequest;
+
+type QueryDocument = Document<'static, String>;
+type QuerySelectionSet = SelectionSet<'static, String>;
+
+/// Name of the root type of the queries in the schema
+const QUERY_TYPE: &str = "Query";
+
+fn is_query_type(type_condition: &TypeCondition<'static, String>) -> bool {
+	match type_condition {
+		TypeCondition::On(name) => name == QUERY_TYPE,
+	}
+}
+
+/// Moves the selections of the fragments on the root type into the selection set. Fragments
+/// with directives are kept, and so are the spreads of a fragment already being inlined since
+/// the executor rejects those cycles
+fn inline_fragments(
+	selection_set: &mut QuerySelectionSet,
+	fragments: &[FragmentDefinition<'static, String>],
+	visited: &mut Vec<String>,
+	inlined: &mut HashSet<String>,
+) -> bool {
+	let mut changed = false;
+	let mut items = Vec::with_capacity(selection_set.items.len());
+
+	for item in selection_set.items.drain(..) {
+		let selection = match &item {
+			Selection::InlineFragment(fragment)
+				if fragment.directives.is_empty()
+					&& fragment.type_condition.as_ref().map_or(true, is_query_type) =>
+			{
+				Some((None, fragment.selection_set.clone()))
+			}
+			Selection::FragmentSpread(spread)
+				if spread.directives.is_empty() && !visited.contains(&spread.fragment_name) =>
+			{
+				get_fragment(fragments, &spread.fragment_name)
+					.filter(|f| is_query_type(&f.type_condition))
+					.map(|f| (Some(f.name.clone()), f.selection_set.clone()))
+			}
+			_ => None,
+		};
+
+		let (name, mut selection_set) = match selection {
+			Some(selection) => selection,
+			None => {
+				items.push(item);
+				continue;
+			}
+		};
+
+		if let Some(name) = &name {
+			visited.push(name.clone());
+			inlined.insert(name.clone());
+		}
+
+		inline_fragments(&mut selection_set, fragments, visited, inlined);
+
+		if name.is_some() {
+			visited.pop();
+		}
+
+		items.extend(selection_set.items);
+		changed = true;
+	}
+
+	selection_set.items = items;
+
+	changed
+}
+
+/// Removes the inlined fragments no operation spreads anymore, the executor rejects unused ones
+fn remove_inlined_fragments(document: &mut QueryDocument, inlined: &HashSet<String>) {
+	let fragments: Vec<FragmentDefinition<'static, String>> = document
+		.definitions
+		.iter()
+		.filter_map(|d| match d {
+			Definition::Fragment(f) => Some(f.clone()),
+			_ => None,
+		})
+		.collect();
+
+	let operations: Vec<&QuerySelectionSet> = document
+		.definitions
+		.iter()
+		.filter_map(|d| match d {
+			Definition::Operation(OperationDefinition::SelectionSet(s)) => Some(s),
+			Definition::Operation(OperationDefinition::Query(q)) => Some(&q.selection_set),
+			Definition::Operation(OperationDefinition::Mutation(m)) => Some(&m.selection_set),
+			Definition::Operation(OperationDefinition::Subscription(s)) => Some(&s.selection_set),
+			Definition::Fragment(_) => None,
+		})
+		.collect();
+
+	let reachable = get_reachable_fragments(&operations, &fragments);
+
+	document.definitions.retain(|d| match d {
+		Definition::Fragment(f) => !inlined.contains(&f.name) || reachable.contains(&f.name),
+		_ => true,
+	});
+}
+
+/// Inlines the fragments of the root selection of a query. The executor resolves the fields
+/// of a root fragment after the fields before it are resolved, while the fields of the root
+/// selection are resolved concurrently, so their AQL queries are sent together
+pub fn inline_root_fragments(request: &mut ApiRequest) {
+	let mut document = match parse_query::<String>(&request.query) {
+		Ok(document) => document.into_static(),
+		// Invalid documents are reported by the executor itself
+		Err(_) => return,
+	};
+
+	let index = match get_operation_index(&document, request.operation_name.as_deref()) {
+		Some(index) => index,
+		None => return,
+	};
+
+	let fragments: Vec<FragmentDefinition<'static, String>> = document
+		.definitions
+		.iter()
+		.filter_map(|d| match d {
+			Definition::Fragment(f) => Some(f.clone()),
+			_ => None,
+		})
+		.collect();
+
+	let selection_set = match &mut document.definitions[index] {
+		Definition::Operation(OperationDefinition::SelectionSet(s)) => s,
+		Definition::Operation(OperationDefinition::Query(q)) => &mut q.selection_set,
+		// Mutation fields have to be executed one after another
+		_ => return,
+	};
+
+	let mut inlined = HashSet::new();
+
+	if !inline_fragments(selection_set, &fragments, &mut Vec::new(), &mut inlined) {
+		return;
+	}
+
+	remove_inlined_fragments(&mut document, &inlined);
+
+	request.query = document.to_string();
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Error};
+use arc_swap::ArcSwap;
+use juniper::{InputValue, Variables};
+use rust_arango::AqlQuery;
+use serde_json::{json, value::Value as JsonValue};
+
+use crate::api::auth::Session;
+use crate::api::schema::{AlchemyScalarValue, Context, Schema};
+use crate::lib::database::DATABASE;
+use crate::lib::scheduler::{
+	claim_due_cron_jobs, finish_cron_run, start_cron_run, AlchemyCronJob, CronAction,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest output kept in the run history
+const MAX_OUTPUT_LENGTH: usize = 4096;
+
+async fn call_webhook(job: &AlchemyCronJob, scheduled_at: &str) -> Result<String, Error> {
+	let mut request = reqwest::Client::new()
+		.post(&job.target)
+		.timeout(WEBHOOK_TIMEOUT)
+		.json(&json!({ "job": job.name, "scheduledAt": scheduled_at }));
+
+	for (name, value) in &job.headers {
+		request = request.header(name, value);
+	}
+
+	let response = request.send().await?;
+	let status = response.status();
+	let body = response.text().await.unwrap_or_default();
+
+	match status.is_success() {
+		true => Ok(body),
+		false => Err(anyhow!("Webhook responded with {}: {}", status, body)),
+	}
+}
+
+async fn execute_aql(job: &AlchemyCronJob) -> Result<String, Error> {
+	let mut query = AqlQuery::builder().query(job.target.as_str());
+
+	for (name, value) in &job.variables {
+		query = query.bind_var(name.as_str(), value.clone());
+	}
+
+	let result: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(query.build())
+		.await?;
+
+	Ok(JsonValue::Array(result).to_string())
+}
+
+async fn execute_graphql(schema: &ArcSwap<Schema>, job: &AlchemyCronJob) -> Result<String, Error> {
+	let mut variables = Variables::new();
+
+	for (name, value) in &job.variables {
+		let value: InputValue<AlchemyScalarValue> = serde_json::from_value(value.clone())?;
+
+		variables.insert(name.clone(), value);
+	}
+
+	let schema = schema.load_full();
+	let context = Context::new(Session::admin());
+
+	let (data, errors) = juniper::execute(&job.target, None, &schema, &variables, &context)
+		.await
+		.map_err(|e| anyhow!("{}", e))?;
+
+	if !errors.is_empty() {
+		return Err(anyhow!(
+			"{}",
+			errors
+				.iter()
+				.map(|e| e.error().message().to_string())
+				.collect::<Vec<String>>()
+				.join(", ")
+		));
+	}
+
+	Ok(serde_json::to_string(&data)?)
+}
+
+/// Executes the job, recording its output or error in the run
+pub async fn execute_cron_run(
+	schema: Arc<ArcSwap<Schema>>,
+	job: AlchemyCronJob,
+	scheduled_at: String,
+	key: String,
+) {
+	let result = match job.action {
+		CronAction::Webhook => call_webhook(&job, &scheduled_at).await,
+		CronAction::Aql => execute_aql(&job).await,
+		CronAction::Graphql => execute_graphql(&schema, &job).await,
+	};
+
+	let result = result.map(|mut output| {
+		if output.len() > MAX_OUTPUT_LENGTH {
+			let mut end = MAX_OUTPUT_LENGTH;
+
+			while !output.is_char_boundary(end) {
+				end -= 1;
+			}
+
+			output.truncate(end);
+		}
+
+		output
+	});
+
+	finish_cron_run(&key, result).await;
+}
+
+async fn run_due_jobs(schema: &Arc<ArcSwap<Schema>>) -> Result<(), Error> {
+	for (job, scheduled_at) in claim_due_cron_jobs().await? {
+		let key = start_cron_run(&job.name, &scheduled_at).await?;
+
+		actix_web::rt::spawn(execute_cron_run(schema.clone(), job, scheduled_at, key));
+	}
+
+	Ok(())
+}
+
+/// Runs the cron jobs as they're due, each in the background so slow ones don't delay others
+pub async fn run_scheduler(schema: Arc<ArcSwap<Schema>>) {
+	loop {
+		if let Err(e) = run_due_jobs(&schema).await {
+			tracing::error!("Error running cron jobs: {}", e);
+		}
+
+		tokio::time::sleep(POLL_INTERVAL).await;
+	}
+}
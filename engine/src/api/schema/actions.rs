@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use juniper::meta::Field;
+use juniper::{Arguments, ExecutionResult, IntoFieldError, Registry, Selection, Spanning};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::api::auth::Session;
+use crate::api::schema::errors::ForbiddenError;
+use crate::api::schema::fields::build_field_from_property;
+use crate::api::schema::handlers::call_handler;
+use crate::api::schema::utils::{convert_json_value_to_juniper_value, RawInput};
+use crate::api::schema::{build_argument_from_property, AsyncScalarValue};
+use crate::lib::database::api::{DbAction, DbScalarType};
+
+const ACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn build_action_field<'r, S>(registry: &mut Registry<'r, S>, action: &DbAction) -> Field<'r, S>
+where
+	S: AsyncScalarValue + 'r,
+{
+	let mut field =
+		build_field_from_property(registry, &action.output, &action.output.scalar_type, true);
+
+	for argument in &action.arguments {
+		field = field.argument(build_argument_from_property(
+			registry,
+			argument,
+			&argument.scalar_type,
+			argument.required,
+		));
+	}
+
+	field
+}
+
+/// Keeps the selected fields of the handler result, under their aliases
+fn project_selection<S>(
+	value: &JsonValue,
+	selection_set: &[Selection<S>],
+	scalar_type: &DbScalarType,
+) -> JsonValue
+where
+	S: AsyncScalarValue,
+{
+	let embedded = match scalar_type {
+		DbScalarType::Array(item) => {
+			return match value {
+				JsonValue::Array(items) => JsonValue::Array(
+					items
+						.iter()
+						.map(|i| project_selection(i, selection_set, item))
+						.collect(),
+				),
+				_ => JsonValue::Null,
+			}
+		}
+		DbScalarType::Embedded(embedded) => embedded,
+		_ => return value.clone(),
+	};
+
+	if !value.is_object() {
+		return JsonValue::Null;
+	}
+
+	let mut object = JsonMap::new();
+
+	for selection in selection_set {
+		match *selection {
+			Selection::Field(Spanning { item: ref f, .. }) => {
+				let response_name = f.alias.as_ref().unwrap_or(&f.name).item.to_string();
+
+				let field_value = match embedded.properties.iter().find(|p| p.name == f.name.item) {
+					Some(property) => project_selection(
+						&value[f.name.item],
+						f.selection_set.as_deref().unwrap_or_default(),
+						&property.scalar_type,
+					),
+					None if f.name.item == "__typename" => json!(embedded.name),
+					None => JsonValue::Null,
+				};
+
+				object.insert(response_name, field_value);
+			}
+			Selection::InlineFragment(Spanning { item: ref f, .. }) => {
+				if let JsonValue::Object(fields) =
+					project_selection(value, &f.selection_set, scalar_type)
+				{
+					object.extend(fields);
+				}
+			}
+			_ => {}
+		}
+	}
+
+	JsonValue::Object(object)
+}
+
+/// Resolves the action by calling its handler with the arguments and the session, which
+/// responds with the result or an error `message`
+pub async fn resolve_action<S>(
+	action: &DbAction,
+	arguments: &Arguments<'_, S>,
+	selection_set: &[Selection<'_, S>],
+	session: &Session,
+) -> ExecutionResult<S>
+where
+	S: AsyncScalarValue,
+{
+	if !session.can_execute_action(action) {
+		return Err(ForbiddenError::new(format!(
+			"Role {} is not allowed to execute {}",
+			session.role, action.name
+		))
+		.into_field_error());
+	}
+
+	let mut input = JsonMap::new();
+
+	for argument in &action.arguments {
+		if let Some(value) = arguments.get::<RawInput<S>>(&argument.name) {
+			input.insert(
+				argument.name.clone(),
+				serde_json::to_value(&value.0).unwrap_or_default(),
+			);
+		}
+	}
+
+	let payload = json!({
+		"action": action.name,
+		"input": input,
+		"session": session.to_json(),
+	});
+
+	let body = call_handler(
+		&action.handler,
+		&format!("action {}", action.name),
+		&payload,
+		ACTION_TIMEOUT,
+	)
+	.await
+	.map_err(|e| e.into_field_error())?;
+
+	Ok(convert_json_value_to_juniper_value(project_selection(
+		&body,
+		selection_set,
+		&action.output.scalar_type,
+	)))
+}
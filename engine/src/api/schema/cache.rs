@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use juniper::Value;
+use lazy_static::lazy_static;
+use tokio::sync::OnceCell;
+
+use crate::api::cluster::{publish, ClusterMessage};
+use crate::lib::database::tenants::get_current_database_name;
+use crate::lib::CONFIG;
+
+/// AQL compiled for the query operations of a schema, by the shape of their selection and
+/// arguments. It's dropped with the schema once it's reloaded
+#[derive(Default)]
+pub struct QueryCache {
+	queries: Mutex<HashMap<String, Arc<OnceCell<(String, Vec<String>)>>>>,
+}
+
+impl QueryCache {
+	/// Cell holding the AQL of the operation and its session variables, filled once it's
+	/// compiled. The cache is emptied when it's full and disabled with a size of `0`
+	pub fn get(&self, key: String) -> Option<Arc<OnceCell<(String, Vec<String>)>>> {
+		let size = CONFIG.get_query_cache_size();
+
+		if size == 0 {
+			return None;
+		}
+
+		let mut queries = self.queries.lock().unwrap();
+
+		if queries.len() >= size && !queries.contains_key(&key) {
+			queries.clear();
+		}
+
+		Some(queries.entry(key).or_default().clone())
+	}
+}
+
+lazy_static! {
+	/// Versions of the collections of each tenant, bumped when the API writes to them
+	static ref COLLECTION_VERSIONS: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+}
+
+fn get_collection_tag(collection: &str) -> String {
+	format!("{}/{}", get_current_database_name(), collection)
+}
+
+fn get_collection_version(tag: &str) -> u64 {
+	COLLECTION_VERSIONS
+		.lock()
+		.unwrap()
+		.get(tag)
+		.copied()
+		.unwrap_or_default()
+}
+
+/// Drops the cached results of the query operations reading the collection, once it's written.
+/// The other instances sharing the Redis server drop theirs too
+pub async fn invalidate_results(collection: &str) {
+	let tag = get_collection_tag(collection);
+
+	apply_results_invalidation(&tag);
+
+	publish(ClusterMessage::InvalidateResults { tag }).await;
+}
+
+/// Drops the cached results of the collection tagged with its database
+pub fn apply_results_invalidation(tag: &str) {
+	*COLLECTION_VERSIONS
+		.lock()
+		.unwrap()
+		.entry(tag.to_string())
+		.or_default() += 1;
+}
+
+struct CachedResult<S> {
+	value: Value<S>,
+	version: u64,
+	expires_at: Instant,
+}
+
+/// Results of the query operations of collections with a cache max age, by collection and by
+/// the shape of the operation. A result is served until it expires or the collection is
+/// written, it's dropped with the schema once it's reloaded
+pub struct ResultCache<S> {
+	results: Mutex<HashMap<String, CachedResult<S>>>,
+}
+
+impl<S> Default for ResultCache<S> {
+	fn default() -> Self {
+		Self {
+			results: Mutex::new(HashMap::new()),
+		}
+	}
+}
+
+impl<S> ResultCache<S>
+where
+	S: Clone,
+{
+	pub fn get(&self, collection: &str, key: &str) -> Option<Value<S>> {
+		let tag = get_collection_tag(collection);
+		let version = get_collection_version(&tag);
+
+		self.results
+			.lock()
+			.unwrap()
+			.get(&format!("{}:{}", tag, key))
+			.filter(|r| r.version == version && r.expires_at > Instant::now())
+			.map(|r| r.value.clone())
+	}
+
+	/// Caches the result for `max_age` seconds, with the version of the collection read
+	/// before the operation was executed. The cache is emptied when it's full
+	pub fn set(&self, collection: &str, key: &str, version: u64, value: Value<S>, max_age: u32) {
+		let size = CONFIG.get_response_cache_size();
+
+		if size == 0 {
+			return;
+		}
+
+		let mut results = self.results.lock().unwrap();
+		let now = Instant::now();
+
+		if results.len() >= size {
+			results.retain(|_, r| r.expires_at > now);
+		}
+
+		if results.len() >= size {
+			results.clear();
+		}
+
+		results.insert(
+			format!("{}:{}", get_collection_tag(collection), key),
+			CachedResult {
+				value,
+				version,
+				expires_at: now + Duration::from_secs(max_age as u64),
+			},
+		);
+	}
+
+	/// Version of the collection a result executed now is cached with
+	pub fn get_version(&self, collection: &str) -> u64 {
+		get_collection_version(&get_collection_tag(collection))
+	}
+}
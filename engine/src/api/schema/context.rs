@@ -0,0 +1,19 @@
+use crate::api::auth::Session;
+use crate::api::schema::loader::RelationshipLoader;
+
+pub struct Context {
+	pub session: Session,
+	/// Batches the relationship lookups of the custom resolvers of the request
+	pub loader: RelationshipLoader,
+}
+
+impl Context {
+	pub fn new(session: Session) -> Context {
+		Context {
+			session,
+			loader: RelationshipLoader::new(),
+		}
+	}
+}
+
+impl juniper::Context for Context {}
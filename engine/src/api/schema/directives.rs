@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::api::schema::fields::find_property;
+use crate::api::schema::{get_entities, Schema};
+use crate::lib::database::api::{DbEntity, DbFieldDirectives};
+
+/// Definitions of the directives the fields of collections can be annotated with
+const DIRECTIVE_DEFINITIONS: &str = "directive @auth(roles: [String!]!) on FIELD_DEFINITION
+
+directive @cache(maxAge: Int!) on FIELD_DEFINITION
+
+directive @cost(complexity: Int!) on FIELD_DEFINITION
+";
+
+/// SDL of the schema with the `@auth`, `@cache` and `@cost` directives of the fields. Juniper
+/// doesn't print custom directives, they are appended to the lines of their fields
+pub fn print_schema(schema: &Schema) -> String {
+	let sdl = schema.as_schema_language();
+
+	// Entity of each object and interface type, variants are looked up in their collection
+	let mut types: HashMap<String, Arc<DbEntity>> = HashMap::new();
+
+	for entity in get_entities(schema) {
+		for variant in &entity.variants {
+			types.insert(variant.name.clone(), entity.clone());
+		}
+
+		types.insert(entity.name.clone(), entity);
+	}
+
+	let mut lines: Vec<String> = Vec::new();
+	let mut current_type: Option<String> = None;
+	let mut in_description = false;
+
+	for line in sdl.lines() {
+		let mut line = line.to_string();
+
+		// Descriptions spanning several lines are block strings
+		if line.matches("\"\"\"").count() % 2 == 1 {
+			in_description = !in_description;
+		}
+
+		if line.starts_with("type ") || line.starts_with("interface ") {
+			current_type = line.split_whitespace().nth(1).map(str::to_string);
+		} else if line == "}" {
+			current_type = None;
+		} else if let Some(type_name) = &current_type {
+			let field = line.trim_start();
+
+			if !in_description && !field.starts_with('"') {
+				let name = field
+					.split(|c| c == '(' || c == ':')
+					.next()
+					.unwrap_or_default();
+
+				if let Some(property) = types
+					.get(type_name)
+					.and_then(|entity| find_property(entity, type_name, name.trim()))
+				{
+					line.push_str(&format_directives(&property.directives));
+				}
+			}
+		}
+
+		lines.push(line);
+	}
+
+	format!("{}\n{}", DIRECTIVE_DEFINITIONS, lines.join("\n"))
+}
+
+fn format_directives(directives: &DbFieldDirectives) -> String {
+	let mut formatted = String::new();
+
+	if let Some(roles) = &directives.auth_roles {
+		formatted.push_str(&format!(
+			" @auth(roles: {})",
+			serde_json::to_string(roles).unwrap()
+		));
+	}
+
+	if let Some(max_age) = directives.cache_max_age {
+		formatted.push_str(&format!(" @cache(maxAge: {})", max_age));
+	}
+
+	if let Some(cost) = directives.cost {
+		formatted.push_str(&format!(" @cost(complexity: {})", cost));
+	}
+
+	formatted
+}
@@ -0,0 +1,131 @@
+use std::marker::PhantomData;
+
+use juniper::meta::MetaType;
+use juniper::{FromInputValue, GraphQLType, GraphQLValue, InputValue, Registry, ScalarValue};
+
+use crate::api::schema::fields::build_field_from_property;
+use crate::api::schema::{build_argument_from_property, build_set_argument_from_property};
+use crate::lib::database::api::DbEmbedded;
+
+/// Output type of a structured sub-document, resolved together with its entity
+pub struct EmbeddedObject;
+
+impl<S> GraphQLValue<S> for EmbeddedObject
+where
+	S: ScalarValue,
+{
+	type Context = ();
+	type TypeInfo = DbEmbedded;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+impl<S> GraphQLType<S> for EmbeddedObject
+where
+	S: ScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let fields = info
+			.properties
+			.iter()
+			.map(|p| build_field_from_property(registry, p, &p.scalar_type, true))
+			.collect::<Vec<_>>();
+
+		registry
+			.build_object_type::<EmbeddedObject>(info, &fields)
+			.into_meta()
+	}
+}
+
+pub struct EmbeddedInputData<'a> {
+	pub name: String,
+	pub embedded: &'a DbEmbedded,
+	pub partial: bool,
+}
+
+impl<'a> EmbeddedInputData<'a> {
+	pub fn new(embedded: &'a DbEmbedded) -> Self {
+		Self {
+			name: format!("{}Input", embedded.name),
+			embedded,
+			partial: false,
+		}
+	}
+
+	/// Input of a sub-document merged into the stored one, none of its fields are required
+	pub fn partial(embedded: &'a DbEmbedded) -> Self {
+		Self {
+			name: format!("{}Set", embedded.name),
+			embedded,
+			partial: true,
+		}
+	}
+}
+
+/// Input type of a structured sub-document, it's stored as part of the entity attributes
+pub struct EmbeddedInput<'a> {
+	_marker: PhantomData<&'a ()>,
+}
+
+impl<'a, S> GraphQLValue<S> for EmbeddedInput<'a>
+where
+	S: ScalarValue,
+{
+	type Context = ();
+	type TypeInfo = EmbeddedInputData<'a>;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+impl<'a, S> GraphQLType<S> for EmbeddedInput<'a>
+where
+	S: ScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let args = info
+			.embedded
+			.properties
+			.iter()
+			.map(|p| {
+				if info.partial {
+					build_set_argument_from_property(registry, p)
+				} else {
+					build_argument_from_property(registry, p, &p.scalar_type, p.required)
+				}
+			})
+			.collect::<Vec<_>>();
+
+		registry
+			.build_input_object_type::<EmbeddedInput>(info, &args)
+			.into_meta()
+	}
+}
+
+impl<'a, S> FromInputValue<S> for EmbeddedInput<'a>
+where
+	S: ScalarValue,
+{
+	fn from_input_value(_: &InputValue<S>) -> Option<Self> {
+		Some(Self {
+			_marker: Default::default(),
+		})
+	}
+}
@@ -1,13 +1,125 @@
+use std::collections::HashMap;
+
+use convert_case::Casing;
 use juniper::meta::{EnumValue, MetaType};
 use juniper::{FromInputValue, GraphQLType, GraphQLValue, InputValue, Registry, ScalarValue};
+use serde_json::Value as JsonValue;
+
+use crate::lib::database::api::{DbEntity, DbProperty, DbScalarType};
 
 pub struct DbEnumInfo {
 	pub(crate) name: String,
 	pub(crate) properties: Vec<String>,
+	/// Descriptions of the values, by stored value
+	pub(crate) descriptions: HashMap<String, String>,
 }
 
 pub struct GraphQLEnum(String);
 
+/// Name of the GraphQL enum value for a value stored in the database
+pub fn get_enum_value_name(value: &str) -> String {
+	value.to_case(convert_case::Case::UpperSnake)
+}
+
+/// Stored value for a GraphQL enum value name
+pub fn get_enum_db_value<'a>(values: &'a [String], name: &str) -> Option<&'a String> {
+	values.iter().find(|v| get_enum_value_name(v) == name)
+}
+
+/// Pairs of stored value and GraphQL enum value name, used to translate query results
+pub fn get_enum_translations(values: &[String]) -> Vec<(String, String)> {
+	values
+		.iter()
+		.map(|v| (v.clone(), get_enum_value_name(v)))
+		.collect()
+}
+
+/// Values of an enum property, also when it's a list of enum values
+pub fn get_enum_values(scalar_type: &DbScalarType) -> Option<&Vec<String>> {
+	match scalar_type {
+		DbScalarType::Enum(values) => Some(values),
+		DbScalarType::Array(t) => get_enum_values(t),
+		_ => None,
+	}
+}
+
+fn has_enum_properties(properties: &[DbProperty]) -> bool {
+	properties
+		.iter()
+		.any(|p| match p.scalar_type.get_embedded() {
+			Some(embedded) => has_enum_properties(&embedded.properties),
+			None => get_enum_values(&p.scalar_type).is_some(),
+		})
+}
+
+fn map_enum_value(value: &mut JsonValue, values: &[String]) {
+	match value {
+		JsonValue::String(name) => {
+			if let Some(db_value) = get_enum_db_value(values, name) {
+				*name = db_value.clone();
+			}
+		}
+		JsonValue::Array(items) => {
+			for item in items {
+				map_enum_value(item, values);
+			}
+		}
+		_ => {}
+	}
+}
+
+fn map_enum_properties(value: &mut JsonValue, properties: &[DbProperty]) {
+	match value {
+		JsonValue::Object(object) => {
+			for property in properties {
+				if let Some(value) = object.get_mut(&property.name) {
+					match property.scalar_type.get_embedded() {
+						Some(embedded) => map_enum_properties(value, &embedded.properties),
+						None => {
+							if let Some(values) = get_enum_values(&property.scalar_type) {
+								map_enum_value(value, values);
+							}
+						}
+					}
+				}
+			}
+		}
+		// Lists of sub-documents
+		JsonValue::Array(items) => {
+			for item in items {
+				map_enum_properties(item, properties);
+			}
+		}
+		_ => {}
+	}
+}
+
+/// Replaces GraphQL enum value names in a document with the values stored in the database
+pub fn map_enum_input_values(attributes: String, entity: &DbEntity) -> String {
+	let has_enums = has_enum_properties(&entity.properties)
+		|| entity
+			.variants
+			.iter()
+			.any(|v| has_enum_properties(&v.properties));
+
+	if !has_enums {
+		return attributes;
+	}
+
+	let mut document: JsonValue = match serde_json::from_str(&attributes) {
+		Ok(document) => document,
+		Err(_) => return attributes,
+	};
+
+	map_enum_properties(&mut document, &entity.properties);
+
+	for variant in &entity.variants {
+		map_enum_properties(&mut document, &variant.properties);
+	}
+
+	document.to_string()
+}
+
 impl<S> GraphQLValue<S> for GraphQLEnum
 where
 	S: ScalarValue,
@@ -42,7 +154,14 @@ where
 				&info
 					.properties
 					.iter()
-					.map(|p| EnumValue::new(p))
+					.map(|p| {
+						let value = EnumValue::new(&get_enum_value_name(p));
+
+						match info.descriptions.get(p) {
+							Some(description) => value.description(description),
+							None => value,
+						}
+					})
 					.collect::<Vec<EnumValue>>()
 					.as_slice(),
 			)
@@ -1,4 +1,71 @@
-use juniper::{FieldError, IntoFieldError, ScalarValue, Value};
+use juniper::{FieldError, IntoFieldError, Object, ScalarValue, Value};
+use rust_arango::ClientError;
+use std::any::Any;
+use std::time::Duration;
+
+use crate::lib::CONFIG;
+
+/// ArangoDB error numbers reported to clients as their own codes
+const UNIQUE_CONSTRAINT_VIOLATED: u16 = 1210;
+const SCHEMA_VALIDATION_FAILED: u16 = 1620;
+/// Query killed, e.g. by its `maxRuntime`
+const QUERY_KILLED: u16 = 1500;
+/// Query over its `memoryLimit`
+const RESOURCE_LIMIT: u16 = 32;
+
+/// Code of an error, exposed in its `extensions` so clients can handle it without parsing the
+/// message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorCode {
+	NotFound,
+	ValidationFailed,
+	Forbidden,
+	Conflict,
+	DbError,
+	RateLimited,
+	Timeout,
+	ResourceLimit,
+	InternalError,
+}
+
+impl ErrorCode {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			ErrorCode::NotFound => "NOT_FOUND",
+			ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+			ErrorCode::Forbidden => "FORBIDDEN",
+			ErrorCode::Conflict => "CONFLICT",
+			ErrorCode::DbError => "DB_ERROR",
+			ErrorCode::RateLimited => "RATE_LIMITED",
+			ErrorCode::Timeout => "TIMEOUT",
+			ErrorCode::ResourceLimit => "RESOURCE_LIMIT",
+			ErrorCode::InternalError => "INTERNAL_ERROR",
+		}
+	}
+}
+
+/// Error with the `extensions { code, field, details }` of the response, `field` being the
+/// attribute the error is about
+fn build_field_error<S: ScalarValue>(
+	message: String,
+	code: &str,
+	field: Option<String>,
+	details: Option<Value<S>>,
+) -> FieldError<S> {
+	let mut extensions = Object::with_capacity(3);
+
+	extensions.add_field("code", Value::scalar(code.to_string()));
+
+	if let Some(field) = field {
+		extensions.add_field("field", Value::scalar(field));
+	}
+
+	if let Some(details) = details {
+		extensions.add_field("details", details);
+	}
+
+	FieldError::new(message, Value::Object(extensions))
+}
 
 pub struct NotFoundError {
 	model: String,
@@ -12,22 +79,272 @@ impl NotFoundError {
 
 impl<S: ScalarValue> IntoFieldError<S> for NotFoundError {
 	fn into_field_error(self) -> FieldError<S> {
-		FieldError::new(format!("{} not found", self.model), Value::Null)
+		build_field_error(
+			format!("{} not found", self.model),
+			ErrorCode::NotFound.as_str(),
+			None,
+			None,
+		)
 	}
 }
 
+/// Error of ArangoDB, only its message is hidden from clients unless `API_DEBUG_ERRORS` is
+/// enabled since it can describe the queries and the collections
 pub struct DatabaseError {
 	message: String,
+	error_num: Option<u16>,
 }
 
 impl DatabaseError {
+	/// Attributes of the index a unique constraint violation is reported for, e.g.
+	/// `... of type persistent over 'email'; conflicting key: 123`
+	fn get_conflicting_field(&self) -> Option<String> {
+		let (_, fields) = self.message.split_once("over '")?;
+
+		fields
+			.split_once('\'')
+			.map(|(fields, _)| fields.to_string())
+	}
+
+	fn get_details<S: ScalarValue>(&self) -> Option<Value<S>> {
+		CONFIG.is_debug_errors_enabled().then(|| {
+			let mut details = Object::with_capacity(1);
+
+			details.add_field("message", Value::scalar(self.message.clone()));
+
+			Value::Object(details)
+		})
+	}
+}
+
+impl From<ClientError> for DatabaseError {
+	fn from(error: ClientError) -> Self {
+		let error_num = match &error {
+			ClientError::Arango(e) => Some(e.error_num()),
+			_ => None,
+		};
+
+		Self {
+			message: error.to_string(),
+			error_num,
+		}
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for DatabaseError {
+	fn into_field_error(self) -> FieldError<S> {
+		match self.error_num {
+			Some(UNIQUE_CONSTRAINT_VIOLATED) => build_field_error(
+				"A document with the same value already exists".to_string(),
+				ErrorCode::Conflict.as_str(),
+				self.get_conflicting_field(),
+				self.get_details(),
+			),
+			Some(QUERY_KILLED) => build_field_error(
+				"Query timed out".to_string(),
+				ErrorCode::Timeout.as_str(),
+				None,
+				self.get_details(),
+			),
+			Some(RESOURCE_LIMIT) => build_field_error(
+				"Query exceeded its memory limit".to_string(),
+				ErrorCode::ResourceLimit.as_str(),
+				None,
+				self.get_details(),
+			),
+			// The message describes the rule of the collection schema the document breaks
+			Some(SCHEMA_VALIDATION_FAILED) => build_field_error(
+				self.message,
+				ErrorCode::ValidationFailed.as_str(),
+				None,
+				None,
+			),
+			_ => {
+				tracing::error!("Database error: {}", self.message);
+
+				build_field_error(
+					"Database error".to_string(),
+					ErrorCode::DbError.as_str(),
+					None,
+					self.get_details(),
+				)
+			}
+		}
+	}
+}
+
+/// An operation ran longer than `OPERATION_TIMEOUT` or the timeout of its role
+pub struct TimeoutError {
+	timeout: Duration,
+}
+
+impl TimeoutError {
+	pub fn new(timeout: Duration) -> Self {
+		Self { timeout }
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for TimeoutError {
+	fn into_field_error(self) -> FieldError<S> {
+		build_field_error(
+			format!("Operation timed out after {:?}", self.timeout),
+			ErrorCode::Timeout.as_str(),
+			None,
+			None,
+		)
+	}
+}
+
+pub struct ForbiddenError {
+	message: String,
+}
+
+impl ForbiddenError {
 	pub fn new(message: String) -> Self {
 		Self { message }
 	}
 }
 
-impl<S: ScalarValue> IntoFieldError<S> for DatabaseError {
+impl std::fmt::Display for ForbiddenError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for ForbiddenError {
+	fn into_field_error(self) -> FieldError<S> {
+		build_field_error(self.message, ErrorCode::Forbidden.as_str(), None, None)
+	}
+}
+
+/// Input rejected before anything is executed, e.g. a relationship nested too deep
+pub struct ValidationError {
+	message: String,
+	field: Option<String>,
+}
+
+impl ValidationError {
+	pub fn new(message: String, field: Option<String>) -> Self {
+		Self { message, field }
+	}
+}
+
+impl std::fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for ValidationError {
+	fn into_field_error(self) -> FieldError<S> {
+		build_field_error(
+			self.message,
+			ErrorCode::ValidationFailed.as_str(),
+			self.field,
+			None,
+		)
+	}
+}
+
+pub struct ResolverError {
+	message: String,
+}
+
+impl ResolverError {
+	pub fn new(message: String) -> Self {
+		Self { message }
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for ResolverError {
+	fn into_field_error(self) -> FieldError<S> {
+		build_field_error(self.message, ErrorCode::InternalError.as_str(), None, None)
+	}
+}
+
+/// Error of the handler of a hook or an action, `code` is exposed in the error extensions
+pub struct HandlerError {
+	message: String,
+	code: Option<String>,
+}
+
+impl HandlerError {
+	pub fn new(message: String, code: Option<String>) -> Self {
+		Self { message, code }
+	}
+
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for HandlerError {
+	fn into_field_error(self) -> FieldError<S> {
+		let code = self
+			.code
+			.unwrap_or_else(|| ErrorCode::InternalError.as_str().to_string());
+
+		build_field_error(self.message, &code, None, None)
+	}
+}
+
+/// A resolver panicked, the panic is logged while clients only get the id of the request to
+/// report it
+pub struct InternalError {
+	request_id: Option<String>,
+}
+
+impl InternalError {
+	pub fn new(request_id: Option<String>) -> Self {
+		Self { request_id }
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for InternalError {
+	fn into_field_error(self) -> FieldError<S> {
+		let mut extensions = Object::with_capacity(2);
+
+		extensions.add_field(
+			"code",
+			Value::scalar(ErrorCode::InternalError.as_str().to_string()),
+		);
+
+		if let Some(request_id) = &self.request_id {
+			extensions.add_field("requestId", Value::scalar(request_id.clone()));
+		}
+
+		let message = match &self.request_id {
+			Some(request_id) => format!("Internal error, request id {}", request_id),
+			None => "Internal error".to_string(),
+		};
+
+		FieldError::new(message, Value::Object(extensions))
+	}
+}
+
+/// Message of a caught panic, panics usually carry a string
+pub fn get_panic_message(panic: &(dyn Any + Send)) -> &str {
+	panic
+		.downcast_ref::<&str>()
+		.copied()
+		.or_else(|| panic.downcast_ref::<String>().map(|m| m.as_str()))
+		.unwrap_or("unknown panic")
+}
+
+/// A relationship constraint the change would break
+pub struct ConstraintError {
+	message: String,
+	field: Option<String>,
+}
+
+impl ConstraintError {
+	pub fn new(message: String, field: Option<String>) -> Self {
+		Self { message, field }
+	}
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for ConstraintError {
 	fn into_field_error(self) -> FieldError<S> {
-		FieldError::new(self.message, Value::Null)
+		build_field_error(self.message, ErrorCode::Conflict.as_str(), self.field, None)
 	}
 }
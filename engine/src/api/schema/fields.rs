@@ -1,16 +1,64 @@
+use futures_util::future::try_join_all;
+use futures_util::FutureExt;
 use juniper::meta::{Field, MetaType};
 use juniper::{
-	Arguments, BoxFuture, ExecutionResult, Executor, GraphQLType, GraphQLValue, GraphQLValueAsync,
-	Registry, ScalarValue, Selection, Spanning, Value,
+	Arguments, BoxFuture, ExecutionResult, Executor, FieldError, GraphQLType, GraphQLValue,
+	GraphQLValueAsync, IntoFieldError, LookAheadMethods, Object, Registry, ScalarValue, Selection,
+	Spanning, Value, Variables,
 };
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
 
-use crate::api::schema::enums::{DbEnumInfo, GraphQLEnum};
-use crate::api::schema::input::filter::{get_aql_filter_from_args, EntityFilter, EntityFilterData};
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::auth::Session;
+use crate::api::schema::actions::resolve_action;
+use crate::api::schema::embedded::EmbeddedObject;
+use crate::api::schema::enums::{get_enum_translations, get_enum_values, DbEnumInfo, GraphQLEnum};
+use crate::api::schema::errors::{
+	get_panic_message, ForbiddenError, InternalError, ValidationError,
+};
+use crate::api::schema::files::{sign_file_urls, FILE_TYPE};
+use crate::api::schema::graph::{
+	check_graph_access, get_neighbors_query, resolve_shortest_path, GraphNeighbor,
+	TraversalDirection,
+};
+use crate::api::schema::input::filter::{
+	check_filter_args_access, get_aql_filter_from_args, EntityFilter, EntityFilterData,
+};
+use crate::api::schema::operations::publish::{get_published_filter, PREVIEW_ARGUMENT};
 use crate::api::schema::operations::{OperationData, OperationEntry, OperationRegistry};
-use crate::api::schema::{AsyncScalarValue, SchemaData};
-use crate::lib::database::api::{DbProperty, DbRelationship, DbScalarType};
-use crate::lib::database::aql::{AQLProperty, AQLQuery, AQLQueryRelationship};
+use crate::api::schema::resolvers::ResolverContext;
+use crate::api::schema::revisions::{
+	build_revisions_field, get_revisions_property, has_revisions_field, resolve_revisions,
+	REVISIONS_FIELD,
+};
+use crate::api::schema::scalars::bigint::BigIntScalar;
+use crate::api::schema::scalars::bytes::BytesScalar;
+use crate::api::schema::scalars::datetime::DateTimeScalar;
+use crate::api::schema::scalars::decimal::DecimalScalar;
+use crate::api::schema::scalars::json::JsonScalar;
+use crate::api::schema::scalars::uuid::UuidScalar;
+use crate::api::schema::utils::convert_json_value_to_juniper_value;
+use crate::api::schema::{AsyncScalarValue, Context, SchemaData, SchemaKind};
+use crate::api::server::get_current_request_id;
+use crate::api::stats::OPERATION_STATS;
+use crate::lib::database::api::{
+	DbEmbedded, DbEntity, DbProperty, DbRelationship, DbScalarType, DbUnion, DbVariant,
+};
+use crate::lib::database::aql::{
+	get_attribute_accessor, AQLCollectionFilter, AQLEdgeProjection, AQLFragment,
+	AQLFragmentCondition, AQLLogicalFilter, AQLLogicalOperator, AQLNode, AQLProperty, AQLQuery,
+	AQLQueryRelationship,
+};
+use crate::lib::locales::{get_locale_chain, get_translation_expression};
+use crate::lib::logging::log_deprecated_usage;
+use crate::lib::storage::FILES_COLLECTION;
+use crate::lib::CONFIG;
+
+/// Argument of the operations of entities with translatable fields, the locale their values are
+/// read and written in
+pub const LOCALE_ARGUMENT: &str = "locale";
 
 pub struct SchemaFieldFactory;
 
@@ -31,6 +79,26 @@ impl SchemaFieldFactory {
 			field = field.argument(arg);
 		}
 
+		// Translatable fields are read and written in the requested locale
+		if operation
+			.data
+			.entity
+			.properties
+			.iter()
+			.any(|p| p.translatable)
+		{
+			field = field.argument(registry.arg::<Option<String>>(LOCALE_ARGUMENT, &()));
+		}
+
+		// Drafts of entities with publication are only read when asked for
+		if operation.kind == SchemaKind::Query && operation.data.entity.publication {
+			field = field.argument(registry.arg::<Option<bool>>(PREVIEW_ARGUMENT, &()));
+		}
+
+		if let Some(reason) = &operation.data.entity.deprecation_reason {
+			field = field.deprecated(Some(reason));
+		}
+
 		field
 	}
 
@@ -60,7 +128,7 @@ pub struct Entity<'a> {
 	_marker: PhantomData<&'a ()>,
 }
 
-fn build_field_from_property<'r, S>(
+pub fn build_field_from_property<'r, S>(
 	registry: &mut Registry<'r, S>,
 	property: &DbProperty,
 	scalar_type: &DbScalarType,
@@ -83,10 +151,20 @@ where
 	{
 		let is_array = matches!(property.scalar_type, DbScalarType::Array(_));
 
-		if required && !is_array {
+		let field = if required && !is_array {
 			registry.field::<T>(property.name.as_str(), info)
 		} else {
 			registry.field::<Option<T>>(property.name.as_str(), info)
+		};
+
+		let field = match &property.description {
+			Some(description) => field.description(description),
+			None => field,
+		};
+
+		match &property.deprecation_reason {
+			Some(reason) => field.deprecated(Some(reason)),
+			None => field,
 		}
 	}
 
@@ -110,13 +188,27 @@ where
 			&DbEnumInfo {
 				name: property.associated_type.clone().unwrap(),
 				properties: values.clone(),
+				descriptions: property.enum_descriptions.clone(),
 			},
 		),
+		DbScalarType::Embedded(embedded) => {
+			build_field::<EmbeddedObject, S>(registry, property, required, embedded)
+		}
 		DbScalarType::String => build_field::<String, S>(registry, property, required, &()),
-		DbScalarType::Object => build_field::<String, S>(registry, property, required, &()),
+		DbScalarType::Object => build_field::<JsonScalar, S>(registry, property, required, &()),
 		DbScalarType::Float => build_field::<f64, S>(registry, property, required, &()),
 		DbScalarType::Int => build_field::<i32, S>(registry, property, required, &()),
+		DbScalarType::BigInt => build_field::<BigIntScalar, S>(registry, property, required, &()),
 		DbScalarType::Boolean => build_field::<bool, S>(registry, property, required, &()),
+		DbScalarType::Bytes => build_field::<BytesScalar, S>(registry, property, required, &()),
+		DbScalarType::Decimal => build_field::<DecimalScalar, S>(registry, property, required, &()),
+		DbScalarType::DateTime => {
+			build_field::<DateTimeScalar, S>(registry, property, required, &())
+		}
+		DbScalarType::Uuid => build_field::<UuidScalar, S>(registry, property, required, &()),
+		DbScalarType::File(_) => {
+			build_field::<EmbeddedObject, S>(registry, property, required, &FILE_TYPE)
+		}
 	}
 }
 
@@ -136,7 +228,12 @@ where
 		registry.field::<Entity>(relationship.name.as_str(), info)
 	};
 
-	if returns_array {
+	let field = match &relationship.description {
+		Some(description) => field.description(description),
+		None => field,
+	};
+
+	let field = if returns_array {
 		field
 			.argument(
 				registry.arg::<Option<EntityFilter<S>>>("where", &EntityFilterData::new(info.data)),
@@ -144,9 +241,146 @@ where
 			.argument(registry.arg::<Option<i32>>("limit", &()))
 	} else {
 		field
+	};
+
+	// Self-referential relationships can be traversed recursively, returning a tree
+	if relationship.from.name == relationship.to.name {
+		field.argument(registry.arg::<Option<i32>>("depth", &()))
+	} else {
+		field
 	}
 }
 
+fn build_edges_field_from_relationship<'r, S>(
+	registry: &mut Registry<'r, S>,
+	relationship: &DbRelationship,
+	info: &RelationshipEdgeData<S>,
+) -> Field<'r, S>
+where
+	S: AsyncScalarValue,
+{
+	let name = relationship.get_edges_field_name();
+
+	if relationship.relationship_type.returns_array() {
+		registry
+			.field::<Vec<RelationshipEdge>>(name.as_str(), info)
+			.argument(
+				registry.arg::<Option<EntityFilter<S>>>(
+					"where",
+					&EntityFilterData::new(info.node.data),
+				),
+			)
+			.argument(registry.arg::<Option<i32>>("limit", &()))
+	} else {
+		registry.field::<RelationshipEdge>(name.as_str(), info)
+	}
+}
+
+fn build_entity_fields<'r, S>(
+	info: &EntityData<S>,
+	registry: &mut Registry<'r, S>,
+) -> Vec<Field<'r, S>>
+where
+	S: AsyncScalarValue + 'r,
+{
+	let mut fields = Vec::new();
+
+	for property in &info.data.entity.properties {
+		let field = build_field_from_property(registry, &property, &property.scalar_type, true);
+
+		fields.push(field);
+	}
+
+	// Computed properties are read-only and resolved by their AQL expression
+	for property in &info.data.entity.computed {
+		fields.push(build_field_from_property(
+			registry,
+			property,
+			&property.scalar_type,
+			true,
+		));
+	}
+
+	if has_revisions_field(&info.data.entity) {
+		fields.push(build_revisions_field(registry));
+	}
+
+	if let Some(resolvers) = info.registry.get_resolvers(&info.data.entity.name) {
+		for (name, resolver) in resolvers {
+			let property = DbProperty {
+				name: name.clone(),
+				scalar_type: resolver.scalar_type(),
+				..Default::default()
+			};
+
+			fields.push(build_field_from_property(
+				registry,
+				&property,
+				&property.scalar_type,
+				true,
+			));
+		}
+	}
+
+	for graph in &info.data.graphs {
+		fields.push(
+			registry
+				.field::<Vec<GraphNeighbor>>(graph.get_neighbors_field_name().as_str(), &())
+				.argument(registry.arg::<Option<i32>>("depth", &()))
+				.argument(registry.arg::<Option<TraversalDirection>>("direction", &()))
+				.argument(registry.arg::<Option<i32>>("limit", &())),
+		);
+	}
+
+	for relationship in &*info.data.relationships {
+		// Polymorphic relationships return their union, documents can't be filtered across types
+		if let Some(ref union) = relationship.union {
+			let union_info = &EntityUnionData {
+				union,
+				registry: info.registry,
+			};
+
+			fields.push(if relationship.relationship_type.returns_array() {
+				registry
+					.field::<Vec<EntityUnion>>(relationship.name.as_str(), union_info)
+					.argument(registry.arg::<Option<i32>>("limit", &()))
+			} else {
+				registry.field::<EntityUnion>(relationship.name.as_str(), union_info)
+			});
+
+			continue;
+		}
+
+		let rel_info = &EntityData {
+			data: &*info
+				.registry
+				.get_operation_data(&relationship.to.name)
+				.expect("Relationship entity operation data not found"),
+			registry: info.registry,
+		};
+
+		let field = build_field_from_relationship(registry, relationship, rel_info);
+
+		fields.push(field);
+
+		if let Some(ref edge) = relationship.edge_properties {
+			fields.push(build_edges_field_from_relationship(
+				registry,
+				relationship,
+				&RelationshipEdgeData {
+					node: EntityData {
+						data: rel_info.data,
+						registry: rel_info.registry,
+					},
+					edge,
+				},
+			));
+		}
+	}
+
+	fields
+}
+
 impl<'a, S> GraphQLType<S> for Entity<'a>
 where
 	S: AsyncScalarValue,
@@ -159,40 +393,223 @@ where
 	where
 		S: 'r,
 	{
-		let mut fields = Vec::new();
+		let fields = build_entity_fields(info, registry);
+
+		// Polymorphic collections are interfaces implemented by each variant
+		if !info.data.entity.variants.is_empty() {
+			for variant in &info.data.entity.variants {
+				registry.get_type::<EntityVariant>(&EntityVariantData {
+					entity: EntityData {
+						data: info.data,
+						registry: info.registry,
+					},
+					variant,
+				});
+			}
+
+			let mut meta = registry.build_interface_type::<Entity>(info, &fields);
+
+			if let Some(description) = &info.data.entity.description {
+				meta = meta.description(description);
+			}
+
+			return meta.into_meta();
+		}
+
+		let mut meta = registry.build_object_type::<Entity>(info, &fields);
+
+		if let Some(description) = &info.data.entity.description {
+			meta = meta.description(description);
+		}
+
+		meta.into_meta()
+	}
+}
+
+impl<'a, S> GraphQLValue<S> for Entity<'a>
+where
+	S: AsyncScalarValue,
+{
+	type Context = ();
+	type TypeInfo = EntityData<'a, S>;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+pub struct EntityVariantData<'a, S>
+where
+	S: AsyncScalarValue,
+{
+	pub entity: EntityData<'a, S>,
+	pub variant: &'a DbVariant,
+}
+
+/// Object type of a variant of a polymorphic collection
+pub struct EntityVariant<'a> {
+	_marker: PhantomData<&'a ()>,
+}
+
+impl<'a, S> GraphQLType<S> for EntityVariant<'a>
+where
+	S: AsyncScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.variant.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let mut fields = build_entity_fields(&info.entity, registry);
 
-		for property in &info.data.entity.properties {
+		for property in &info.variant.properties {
 			let field = build_field_from_property(registry, &property, &property.scalar_type, true);
 
 			fields.push(field);
 		}
 
-		for relationship in &*info.data.relationships {
-			let rel_info = &EntityData {
-				data: &*info
-					.registry
-					.get_operation_data(&relationship.to.name)
-					.expect("Relationship entity operation data not found"),
+		let interface = registry.get_type::<Entity>(&info.entity);
+
+		registry
+			.build_object_type::<EntityVariant>(info, &fields)
+			.interfaces(&[interface])
+			.into_meta()
+	}
+}
+
+impl<'a, S> GraphQLValue<S> for EntityVariant<'a>
+where
+	S: AsyncScalarValue,
+{
+	type Context = ();
+	type TypeInfo = EntityVariantData<'a, S>;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+pub struct EntityUnionData<'a, S>
+where
+	S: AsyncScalarValue,
+{
+	pub union: &'a DbUnion,
+	pub registry: &'a OperationRegistry<S>,
+}
+
+/// Union of the entities related by a polymorphic relationship
+pub struct EntityUnion<'a> {
+	_marker: PhantomData<&'a ()>,
+}
+
+impl<'a, S> GraphQLType<S> for EntityUnion<'a>
+where
+	S: AsyncScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.union.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let mut types = Vec::new();
+
+		for entity in &info.union.entities {
+			let data = info
+				.registry
+				.get_operation_data(&entity.name)
+				.expect("Union entity operation data not found");
+
+			let entity_data = EntityData {
+				data: &data,
 				registry: info.registry,
 			};
 
-			let field = build_field_from_relationship(registry, relationship, rel_info);
-
-			fields.push(field);
+			// Members of unions are object types, so polymorphic collections add their variants
+			if entity.variants.is_empty() {
+				types.push(registry.get_type::<Entity>(&entity_data));
+			} else {
+				for variant in &entity.variants {
+					types.push(registry.get_type::<EntityVariant>(&EntityVariantData {
+						entity: EntityData {
+							data: &data,
+							registry: info.registry,
+						},
+						variant,
+					}));
+				}
+			}
 		}
 
 		registry
-			.build_object_type::<Entity>(info, &fields)
+			.build_union_type::<EntityUnion>(info, &types)
 			.into_meta()
 	}
 }
 
-impl<'a, S> GraphQLValue<S> for Entity<'a>
+impl<'a, S> GraphQLValue<S> for EntityUnion<'a>
 where
 	S: AsyncScalarValue,
 {
 	type Context = ();
-	type TypeInfo = EntityData<'a, S>;
+	type TypeInfo = EntityUnionData<'a, S>;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+pub struct RelationshipEdgeData<'a, S>
+where
+	S: AsyncScalarValue,
+{
+	pub node: EntityData<'a, S>,
+	pub edge: &'a DbEmbedded,
+}
+
+/// Edge of a relationship with its attributes and the related document as `node`
+pub struct RelationshipEdge<'a> {
+	_marker: PhantomData<&'a ()>,
+}
+
+impl<'a, S> GraphQLType<S> for RelationshipEdge<'a>
+where
+	S: AsyncScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.edge.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let mut fields = info
+			.edge
+			.properties
+			.iter()
+			.map(|p| build_field_from_property(registry, p, &p.scalar_type, true))
+			.collect::<Vec<_>>();
+
+		fields.push(registry.field::<Entity>("node", &info.node));
+
+		registry
+			.build_object_type::<RelationshipEdge>(info, &fields)
+			.into_meta()
+	}
+}
+
+impl<'a, S> GraphQLValue<S> for RelationshipEdge<'a>
+where
+	S: AsyncScalarValue,
+{
+	type Context = ();
+	type TypeInfo = RelationshipEdgeData<'a, S>;
 
 	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
 		<Self as GraphQLType<S>>::name(info)
@@ -213,7 +630,7 @@ impl<'a, S> GraphQLValue<S> for SchemaFieldResolver<'a, S>
 where
 	S: AsyncScalarValue,
 {
-	type Context = ();
+	type Context = Context;
 	type TypeInfo = SchemaData<S>;
 
 	fn type_name<'i>(&self, _: &'i Self::TypeInfo) -> Option<&'i str> {
@@ -231,13 +648,29 @@ where
 		selection_set: Option<&'b [Selection<S>]>,
 		executor: &'b Executor<Self::Context, S>,
 	) -> BoxFuture<'b, ExecutionResult<S>> {
-		Box::pin(resolve_graphql_field(
-			info,
-			self.field_name,
-			self.arguments,
-			selection_set.unwrap(),
-			executor,
-		))
+		let field_name = self.field_name;
+
+		// A panicking operation fails its own field instead of the whole request
+		Box::pin(async move {
+			AssertUnwindSafe(resolve_graphql_field(
+				info,
+				field_name,
+				self.arguments,
+				selection_set.unwrap_or_default(),
+				executor,
+			))
+			.catch_unwind()
+			.await
+			.unwrap_or_else(|panic| {
+				tracing::error!(
+					"Resolver of {} panicked: {}",
+					field_name,
+					get_panic_message(panic.as_ref())
+				);
+
+				Err(InternalError::new(get_current_request_id()).into_field_error())
+			})
+		})
 	}
 }
 
@@ -252,22 +685,417 @@ where
 	S: AsyncScalarValue,
 {
 	if let Some(entry) = info.operation_registry.get_operation(field_name) {
-		let query =
-			get_query_from_graphql(selection_set, &entry.data.entity.name, info, None, executor);
+		let session = &executor.context().session;
+
+		if !session.can_execute(field_name, &entry.data.entity, &entry.kind) {
+			return Err(ForbiddenError::new(format!(
+				"Role {} is not allowed to execute {}",
+				session.role, field_name
+			))
+			.into_field_error());
+		}
+
+		if CONFIG.is_deprecation_log_enabled() {
+			log_deprecated_selection(field_name, &entry.data.entity, executor);
+		}
+
+		// Queries are compiled once for operations of the same shape and role
+		let compiled = match entry.kind {
+			SchemaKind::Query => info
+				.query_cache
+				.get(get_query_cache_key(arguments, executor)),
+			SchemaKind::Mutation => None,
+		};
+
+		// Results of collections with a cache max age are served until the collection is written
+		let result_key = match entry.kind {
+			SchemaKind::Query => {
+				get_result_cache_key(&entry.data.entity, info, arguments, executor)
+			}
+			SchemaKind::Mutation => None,
+		};
 
-		(entry.closure)(&entry.data, arguments, query).await
+		let collection = &entry.data.entity.collection_name;
+		let mut result_version = 0;
+
+		if let Some(key) = &result_key {
+			if let Some(value) = info.result_cache.get(collection, key) {
+				return Ok(value);
+			}
+
+			result_version = info.result_cache.get_version(collection);
+		}
+
+		let locales = get_locale_chain(arguments.get::<String>(LOCALE_ARGUMENT).as_deref());
+
+		let query = match compiled {
+			Some(compiled) if compiled.get().is_some() => {
+				let mut query = AQLQuery::new(1);
+
+				query.compiled = Some(compiled);
+				query
+			}
+			compiled => {
+				let mut query = get_query_from_graphql(
+					selection_set,
+					&entry.data.entity.name,
+					info,
+					None,
+					&locales,
+					executor,
+				)?;
+
+				query.compiled = compiled;
+				query
+			}
+		};
+
+		let time = std::time::Instant::now();
+		let result = (entry.closure)(&entry.data, arguments, query, session).await;
+
+		OPERATION_STATS.record(field_name, time.elapsed(), &result);
+
+		let mut value = result?;
+
+		resolve_custom_fields(
+			&mut value,
+			selection_set,
+			&entry.data.entity.name,
+			info,
+			executor,
+		)
+		.await?;
+
+		if let (Some(key), Some(max_age)) = (result_key, entry.data.entity.cache_max_age) {
+			info.result_cache
+				.set(collection, &key, result_version, value.clone(), max_age);
+		}
+
+		Ok(value)
+	} else if let Some(action) = info.get_action(field_name) {
+		resolve_action(
+			action,
+			arguments,
+			selection_set,
+			&executor.context().session,
+		)
+		.await
+	} else if let Some(entity) = info.get_revisions_entity(field_name) {
+		resolve_revisions(
+			&entity,
+			arguments,
+			selection_set,
+			&executor.context().session,
+		)
+		.await
+	} else if field_name == "shortestPath" {
+		resolve_shortest_path(
+			&info.graphs,
+			arguments,
+			selection_set,
+			&executor.context().session,
+		)
+		.await
 	} else {
 		Ok(Value::null())
 	}
 }
 
+/// Logs the deprecated operation and the deprecated fields of its entity it selects
+fn log_deprecated_selection<'a, S>(
+	field_name: &str,
+	entity: &DbEntity,
+	executor: &'a Executor<'a, 'a, Context, S>,
+) where
+	S: ScalarValue,
+{
+	let session = &executor.context().session;
+
+	if let Some(reason) = &entity.deprecation_reason {
+		log_deprecated_usage(field_name, field_name, reason, &session.role);
+	}
+
+	for child in executor.look_ahead().children() {
+		let property = entity
+			.properties
+			.iter()
+			.find(|p| p.name == child.field_name());
+
+		if let Some(reason) = property.and_then(|p| p.deprecation_reason.as_ref()) {
+			log_deprecated_usage(
+				field_name,
+				&format!("{}.{}", entity.name, child.field_name()),
+				reason,
+				&session.role,
+			);
+		}
+	}
+}
+
+/// Operations with the same key compile to the same AQL, the look ahead holds the selection
+/// with the fragments expanded, the skipped fields left out and the variables replaced
+fn get_query_cache_key<'a, S>(
+	arguments: &Arguments<S>,
+	executor: &'a Executor<'a, 'a, Context, S>,
+) -> String
+where
+	S: ScalarValue,
+{
+	let session = &executor.context().session;
+
+	format!(
+		"{}:{}:{:?}:{:?}",
+		session.role,
+		session.anonymous,
+		arguments,
+		executor.look_ahead()
+	)
+}
+
+/// Key of the result of a query operation of a collection with a cache max age, by the shape
+/// of the operation and the session. Operations selecting relationships, files or resolved
+/// fields read more than the collection and are not cached
+fn get_result_cache_key<'a, S>(
+	entity: &DbEntity,
+	data: &SchemaData<S>,
+	arguments: &Arguments<S>,
+	executor: &'a Executor<'a, 'a, Context, S>,
+) -> Option<String>
+where
+	S: AsyncScalarValue,
+{
+	if !entity.cache_max_age.map_or(false, |max_age| max_age > 0) {
+		return None;
+	}
+
+	let cacheable = executor.look_ahead().children().iter().all(|child| {
+		let name = child.field_name();
+		let type_name = child.applies_for().unwrap_or(&entity.name);
+
+		if name == "__typename" {
+			return true;
+		}
+
+		data.operation_registry
+			.get_resolver(&entity.name, name)
+			.is_none()
+			&& find_property(entity, type_name, name)
+				.map_or(false, |p| !is_file_type(&p.scalar_type))
+	});
+
+	if !cacheable {
+		return None;
+	}
+
+	Some(format!(
+		"{}:{}",
+		get_query_cache_key(arguments, executor),
+		serde_json::Value::Object(executor.context().session.get_variables())
+	))
+}
+
+fn is_file_type(scalar_type: &DbScalarType) -> bool {
+	match scalar_type {
+		DbScalarType::File(_) => true,
+		DbScalarType::Array(t) => is_file_type(t),
+		_ => false,
+	}
+}
+
+/// Replaces the documents projected for custom resolved fields with the resolver output
+fn resolve_custom_fields<'a, S>(
+	value: &'a mut Value<S>,
+	selection_set: &'a [Selection<'a, S>],
+	type_name: &'a str,
+	data: &'a SchemaData<S>,
+	executor: &'a Executor<'a, 'a, Context, S>,
+) -> BoxFuture<'a, Result<(), FieldError<S>>>
+where
+	S: AsyncScalarValue,
+{
+	Box::pin(async move {
+		let object = match value {
+			// Resolved together so the relationship lookups of the items are batched
+			Value::List(items) => {
+				try_join_all(items.iter_mut().map(|item| {
+					resolve_custom_fields(item, selection_set, type_name, data, executor)
+				}))
+				.await?;
+
+				return Ok(());
+			}
+			Value::Object(object) => object,
+			_ => return Ok(()),
+		};
+
+		let entity_data = data.operation_registry.get_operation_data(type_name);
+		let context = executor.context();
+
+		for selection in selection_set {
+			if is_excluded(selection, executor.variables()) {
+				continue;
+			}
+
+			match *selection {
+				Selection::Field(Spanning { item: ref f, .. }) => {
+					let response_name = f.alias.as_ref().unwrap_or(&f.name).item;
+
+					// Fields of fragments for other variants are not in the document
+					let field_value = match object.get_mut_field_value(response_name) {
+						Some(field_value) => field_value,
+						None => continue,
+					};
+
+					let resolver = entity_data.as_ref().and_then(|d| {
+						data.operation_registry
+							.get_resolver(&d.entity.name, f.name.item)
+							.map(|resolver| (d, resolver))
+					});
+
+					if let Some((entity_data, resolver)) = resolver {
+						let parent = serde_json::to_value(&*field_value).unwrap();
+
+						let resolver_context = ResolverContext {
+							entity: &entity_data.entity,
+							session: &context.session,
+							relationships: &entity_data.relationships,
+							loader: &context.loader,
+						};
+
+						let resolved = resolver
+							.resolve(&parent, &resolver_context)
+							.await
+							.map_err(|e| e.into_field_error())?;
+
+						*field_value = convert_json_value_to_juniper_value(resolved);
+					} else if let Some(ref inner_selection_set) = f.selection_set {
+						let inner_type_name = executor
+							.schema()
+							.concrete_type_by_name(type_name)
+							.and_then(|t| t.field_by_name(f.name.item))
+							.map(|field| field.field_type.innermost_name());
+
+						if inner_type_name == Some(FILE_TYPE.name.as_str()) {
+							sign_file_urls(field_value, inner_selection_set);
+						} else if let Some(inner_type_name) = inner_type_name {
+							resolve_custom_fields(
+								field_value,
+								inner_selection_set,
+								inner_type_name,
+								data,
+								executor,
+							)
+							.await?;
+						}
+					}
+				}
+				Selection::InlineFragment(_) | Selection::FragmentSpread(_) => {
+					let (type_condition, fragment_selection_set) =
+						match get_fragment_selection(selection, executor) {
+							Some(fragment) => fragment,
+							None => continue,
+						};
+
+					let mut fragment_value =
+						Value::Object(std::mem::replace(object, Object::with_capacity(0)));
+
+					resolve_custom_fields(
+						&mut fragment_value,
+						fragment_selection_set,
+						type_condition.unwrap_or(type_name),
+						data,
+						executor,
+					)
+					.await?;
+
+					if let Value::Object(fragment_object) = fragment_value {
+						*object = fragment_object;
+					}
+				}
+			}
+		}
+
+		Ok(())
+	})
+}
+
+/// Document passed to a resolver, only with the attributes it reads when they're known
+fn get_resolver_parent_expression(attributes: Option<Vec<String>>, variable: &str) -> String {
+	match attributes {
+		Some(mut attributes) => {
+			attributes.extend(["_id".to_string(), "_key".to_string()]);
+
+			format!(
+				"KEEP({}, {})",
+				variable,
+				serde_json::to_string(&attributes).unwrap()
+			)
+		}
+		None => variable.to_string(),
+	}
+}
+
+/// Fields and fragments left out with `@skip` or `@include`
+fn is_excluded<S>(selection: &Selection<S>, variables: &Variables<S>) -> bool
+where
+	S: ScalarValue,
+{
+	let directives = match *selection {
+		Selection::Field(Spanning { item: ref f, .. }) => &f.directives,
+		Selection::InlineFragment(Spanning { item: ref f, .. }) => &f.directives,
+		Selection::FragmentSpread(Spanning { item: ref f, .. }) => &f.directives,
+	};
+
+	directives.iter().flatten().any(|directive| {
+		let condition = directive
+			.item
+			.arguments
+			.as_ref()
+			.and_then(|a| a.item.get("if"))
+			.and_then(|v| v.item.clone().into_const(variables).convert::<bool>());
+
+		match (directive.item.name.item, condition) {
+			("skip", Some(condition)) => condition,
+			("include", Some(condition)) => !condition,
+			_ => false,
+		}
+	})
+}
+
+/// Type condition and selections of an inline fragment or of the named fragment spread
+fn get_fragment_selection<'a, S>(
+	selection: &'a Selection<'a, S>,
+	executor: &'a Executor<'a, 'a, Context, S>,
+) -> Option<(Option<&'a str>, &'a [Selection<'a, S>])>
+where
+	S: AsyncScalarValue,
+{
+	match *selection {
+		Selection::InlineFragment(Spanning {
+			item: ref fragment, ..
+		}) => Some((
+			fragment.type_condition.as_ref().map(|t| t.item),
+			fragment.selection_set.as_slice(),
+		)),
+		Selection::FragmentSpread(Spanning {
+			item: ref spread, ..
+		}) => executor
+			.fragment_by_name(spread.name.item)
+			.map(|f| (Some(f.type_condition.item), f.selection_set.as_slice())),
+		Selection::Field(_) => None,
+	}
+}
+
+/// Compiles the selections into a single AQL query, relationships and graph traversals are
+/// nested as subqueries of the documents they're selected on
 fn get_query_from_graphql<'a, S>(
 	selection_set: &'a [Selection<'a, S>],
 	entity_name: &'a str,
 	data: &'a SchemaData<S>,
 	query_id: Option<u32>,
+	locales: &'a [String],
 	executor: &'a Executor<'a, 'a, <SchemaFieldResolver<'a, S> as GraphQLValue<S>>::Context, S>,
-) -> AQLQuery
+) -> Result<AQLQuery, FieldError<S>>
 where
 	S: AsyncScalarValue,
 {
@@ -278,26 +1106,72 @@ where
 		.concrete_type_by_name(entity_name.as_ref())
 		.expect("Type not found in schema");
 
+	let entity_data = data.operation_registry.get_operation_data(entity_name);
+
 	for selection in selection_set {
+		if is_excluded(selection, executor.variables()) {
+			continue;
+		}
+
 		match *selection {
 			Selection::Field(Spanning { item: ref f, .. }) => {
 				let response_name = f.alias.as_ref().unwrap_or(&f.name).item;
 
 				if f.name.item == "__typename" {
+					if let Some(entity_data) = &entity_data {
+						query.properties.push(get_typename_property(
+							response_name.to_string(),
+							&entity_data.entity,
+							&query.get_variable_name(),
+						));
+					} else if let Some(union) = find_union(&data.relationships, entity_name) {
+						query.properties.push(get_union_typename_property(
+							response_name.to_string(),
+							union,
+							&query.get_variable_name(),
+						));
+					}
+
 					continue;
 				}
 
 				let response_name = response_name.to_string();
 
-				if let Some(inner_selection_set) = &f.selection_set {
-					let mut inner_query = get_query_from_graphql(
-						inner_selection_set,
-						entity_name,
-						data,
-						Some(query.id + 1),
-						executor,
-					);
+				// Resolved fields receive the document they read, replaced by the resolver output
+				if let Some(entity_data) = &entity_data {
+					if let Some(resolver) = data
+						.operation_registry
+						.get_resolver(&entity_data.entity.name, f.name.item)
+					{
+						let mut property = AQLProperty::new(response_name);
+						property.expression = Some(get_resolver_parent_expression(
+							resolver.attributes(),
+							&query.get_variable_name(),
+						));
+
+						query.properties.push(property);
 
+						continue;
+					}
+				}
+
+				let property = entity_data
+					.as_ref()
+					.and_then(|d| find_property(&d.entity, entity_name, f.name.item));
+
+				if let Some(property) = property {
+					let session = &executor.context().session;
+
+					if !session.can_read_field(property) {
+						return Err(ForbiddenError::new(format!(
+							"Role {} is not allowed to read {}.{}",
+							session.role, entity_name, f.name.item
+						))
+						.into_field_error());
+					}
+				}
+
+				if let (None, Some(inner_selection_set)) = (property, &f.selection_set) {
 					let meta_field = meta_type.field_by_name(f.name.item).unwrap_or_else(|| {
 						panic!(
 							"Field {} not found on type {:?}",
@@ -318,37 +1192,508 @@ where
 						&meta_field.arguments,
 					);
 
+					let session = &executor.context().session;
+
+					let versioned = entity_data.as_ref().filter(|d| {
+						f.name.item == REVISIONS_FIELD && has_revisions_field(&d.entity)
+					});
+
+					if let Some(entity_data) = versioned {
+						query.properties.push(get_revisions_property(
+							inner_selection_set,
+							&entity_data.entity,
+							&args,
+							response_name,
+							&query.get_variable_name(),
+							session,
+						));
+
+						continue;
+					}
+
+					let graph = entity_data.as_ref().and_then(|d| {
+						d.graphs
+							.iter()
+							.find(|g| g.get_neighbors_field_name() == f.name.item)
+					});
+
+					if let Some(graph) = graph {
+						check_graph_access(graph, session)?;
+
+						let inner_query = get_neighbors_query(
+							inner_selection_set,
+							graph,
+							&args,
+							query.id + 1,
+							query.get_variable_name(),
+							get_traversal_depth(&args)?,
+						);
+
+						query.relations.insert(response_name, inner_query);
+
+						continue;
+					}
+
+					let relationship = entity_data.as_ref().and_then(|d| {
+						data.relationships.iter().find(|r| {
+							r.from.name == d.entity.name
+								&& (r.name == f.name.item
+									|| r.get_edges_field_name() == f.name.item)
+						})
+					});
+
+					let relationship = match relationship {
+						Some(relationship) => relationship,
+						None => continue,
+					};
+
+					if let Some(ref union) = relationship.union {
+						let mut inner_query = get_query_from_graphql(
+							inner_selection_set,
+							&union.name,
+							data,
+							Some(query.id + 1),
+							locales,
+							executor,
+						)?;
+
+						inner_query.limit = args.get::<i32>("limit");
+						inner_query.filter = Some(get_union_filter(union, session)?);
+						inner_query.relationship = Some(AQLQueryRelationship {
+							edge: relationship.edge.clone(),
+							variable_name: query.get_variable_name(),
+							direction: relationship.direction.clone(),
+							relationship_type: relationship.relationship_type.clone(),
+							edge_projection: None,
+							depth: None,
+						});
+
+						query.relations.insert(response_name, inner_query);
+
+						continue;
+					}
+
 					let operation_data = data
 						.operation_registry
-						.get_operation_data(meta_field.field_type.innermost_name())
+						.get_operation_data(&relationship.to.name)
 						.unwrap();
 
-					inner_query.limit = args.get::<i32>("limit");
-					inner_query.filter = get_aql_filter_from_args(&args, &operation_data);
+					if !session.can_read(&operation_data.entity) {
+						return Err(ForbiddenError::new(format!(
+							"Role {} is not allowed to read {}",
+							session.role, operation_data.entity.name
+						))
+						.into_field_error());
+					}
 
-					for relationship in &*data.relationships {
-						if relationship.name == response_name {
-							inner_query.relationship = Some(AQLQueryRelationship {
-								edge: relationship.edge.clone(),
-								variable_name: query.get_variable_name(),
-								direction: relationship.direction.clone(),
-								relationship_type: relationship.relationship_type.clone(),
-							});
+					let depth = get_traversal_depth(&args)?;
 
-							query.relations.insert(response_name.clone(), inner_query);
+					// Recursive traversals nest the same query once per level, from the deepest
+					let mut child_query: Option<AQLQuery> = None;
 
-							break;
+					for level in (1..=depth).rev() {
+						let query_id = query.id + level;
+
+						// `{name}_edges` fields traverse the same edges, returning their attributes
+						let (mut inner_query, edge_projection) = if relationship.name == f.name.item
+						{
+							let inner_query = get_query_from_graphql(
+								inner_selection_set,
+								meta_field.field_type.innermost_name(),
+								data,
+								Some(query_id),
+								locales,
+								executor,
+							)?;
+
+							(inner_query, None)
+						} else {
+							let (inner_query, edge_projection) = get_edges_query(
+								inner_selection_set,
+								relationship,
+								data,
+								query_id,
+								locales,
+								executor,
+							)?;
+
+							(inner_query, Some(edge_projection))
+						};
+
+						inner_query.limit = args.get::<i32>("limit");
+						check_filter_args_access(&args, &operation_data.entity, session)?;
+
+						inner_query.filter = get_aql_filter_from_args(&args, &operation_data);
+
+						if let Some(permission_filter) =
+							get_permission_filter(&operation_data.entity, session)
+						{
+							inner_query.add_filter(permission_filter);
+						}
+
+						if let Some(published_filter) = get_published_filter(&operation_data.entity)
+						{
+							inner_query.add_filter(published_filter);
 						}
+
+						inner_query.relationship = Some(AQLQueryRelationship {
+							edge: relationship.edge.clone(),
+							variable_name: AQLQuery::new(query_id - 1).get_variable_name(),
+							direction: relationship.direction.clone(),
+							relationship_type: relationship.relationship_type.clone(),
+							edge_projection,
+							depth: None,
+						});
+
+						if let Some(child_query) = child_query.take() {
+							inner_query
+								.relations
+								.insert(response_name.clone(), child_query);
+						}
+
+						child_query = Some(inner_query);
+					}
+
+					if let Some(inner_query) = child_query {
+						query.relations.insert(response_name, inner_query);
 					}
 				} else {
-					query.properties.push(AQLProperty {
-						name: response_name,
+					let mut aql_property =
+						get_aql_property(response_name, f.selection_set.as_deref(), property);
+
+					if let Some(expression) = property.and_then(|p| p.expression.as_ref()) {
+						aql_property.expression = Some(format!(
+							"FIRST(LET doc = {} RETURN ({}))",
+							query.get_variable_name(),
+							expression
+						));
+					}
+
+					if let Some(property) = property.filter(|p| p.translatable) {
+						aql_property.expression = Some(get_translation_expression(
+							&query.get_variable_name(),
+							&property.name,
+							locales,
+						));
+					}
+
+					query.properties.push(aql_property);
+				}
+			}
+			Selection::InlineFragment(_) | Selection::FragmentSpread(_) => {
+				let (type_condition, fragment_selection_set) =
+					match get_fragment_selection(selection, executor) {
+						Some(fragment) => fragment,
+						None => continue,
+					};
+
+				let type_name = type_condition.unwrap_or(entity_name);
+
+				let inner_query = get_query_from_graphql(
+					fragment_selection_set,
+					type_name,
+					data,
+					Some(query.id),
+					locales,
+					executor,
+				)?;
+
+				// Members of a union are told apart by their collection
+				let union_member = find_union(&data.relationships, entity_name).and_then(|u| {
+					u.entities.iter().find(|e| {
+						e.name == type_name || e.variants.iter().any(|v| v.name == type_name)
+					})
+				});
+
+				if let Some(member) = union_member {
+					query.fragments.push(AQLFragment {
+						condition: AQLFragmentCondition::Collection(member.collection_name.clone()),
+						properties: inner_query.properties,
+						relations: inner_query.relations,
 					});
+					query.fragments.extend(inner_query.fragments);
+
+					continue;
+				}
+
+				let variant = entity_data.as_ref().and_then(|d| {
+					let variant = d.entity.variants.iter().find(|v| v.name == type_name)?;
+
+					Some((d.entity.discriminator.clone()?, variant.value.clone()))
+				});
+
+				match variant {
+					Some((attribute, value)) => query.fragments.push(AQLFragment {
+						condition: AQLFragmentCondition::Variant(attribute, value),
+						properties: inner_query.properties,
+						relations: HashMap::new(),
+					}),
+					None => query.properties.extend(inner_query.properties),
+				}
+
+				query.relations.extend(inner_query.relations);
+				query.fragments.extend(inner_query.fragments);
+			}
+		}
+	}
+
+	Ok(query)
+}
+
+/// Levels of a recursive traversal given by the `depth` argument, capped by the configuration
+fn get_traversal_depth<S>(args: &Arguments<S>) -> Result<u32, FieldError<S>>
+where
+	S: ScalarValue,
+{
+	let depth = args.get::<i32>("depth").unwrap_or(1).max(1) as u32;
+	let max_depth = CONFIG.get_max_traversal_depth();
+
+	if depth > max_depth {
+		return Err(ValidationError::new(
+			format!("Depth {} exceeds the maximum of {}", depth, max_depth),
+			Some("depth".to_string()),
+		)
+		.into_field_error());
+	}
+
+	Ok(depth)
+}
+
+/// Query of a `{name}_edges` field, the edge attributes are projected apart from the `node`
+fn get_edges_query<'a, S>(
+	selection_set: &'a [Selection<'a, S>],
+	relationship: &'a DbRelationship,
+	data: &'a SchemaData<S>,
+	query_id: u32,
+	locales: &'a [String],
+	executor: &'a Executor<'a, 'a, <SchemaFieldResolver<'a, S> as GraphQLValue<S>>::Context, S>,
+) -> Result<(AQLQuery, AQLEdgeProjection), FieldError<S>>
+where
+	S: AsyncScalarValue,
+{
+	let mut query = AQLQuery::new(query_id);
+	let mut projection = AQLEdgeProjection {
+		properties: Vec::new(),
+		node: None,
+	};
+
+	let edge = relationship.edge_properties.as_ref();
+
+	for selection in selection_set {
+		if is_excluded(selection, executor.variables()) {
+			continue;
+		}
+
+		if let Selection::Field(Spanning { item: ref f, .. }) = *selection {
+			let response_name = f.alias.as_ref().unwrap_or(&f.name).item.to_string();
+
+			match f.name.item {
+				"node" => {
+					if let Some(ref node_selection_set) = f.selection_set {
+						query = get_query_from_graphql(
+							node_selection_set,
+							&relationship.to.name,
+							data,
+							Some(query_id),
+							locales,
+							executor,
+						)?;
+
+						projection.node = Some(response_name);
+					}
+				}
+				"__typename" => {
+					let mut property = AQLProperty::new(response_name);
+					property.expression = edge.map(|e| serde_json::to_string(&e.name).unwrap());
+
+					projection.properties.push(property);
+				}
+				name => projection.properties.push(get_aql_property(
+					response_name,
+					f.selection_set.as_deref(),
+					edge.and_then(|e| e.properties.iter().find(|p| p.name == name)),
+				)),
+			}
+		}
+	}
+
+	Ok((query, projection))
+}
+
+/// Finds a property of the entity, including computed properties and the properties of the
+/// variant named `type_name`
+pub fn find_property<'e>(
+	entity: &'e DbEntity,
+	type_name: &str,
+	name: &str,
+) -> Option<&'e DbProperty> {
+	entity
+		.properties
+		.iter()
+		.chain(entity.computed.iter())
+		.chain(
+			entity
+				.variants
+				.iter()
+				.filter(|v| v.name == type_name)
+				.flat_map(|v| v.properties.iter()),
+		)
+		.find(|p| p.name == name)
+}
+
+fn find_union<'d>(relationships: &'d [DbRelationship], name: &str) -> Option<&'d DbUnion> {
+	relationships
+		.iter()
+		.filter_map(|r| r.union.as_deref())
+		.find(|u| u.name == name)
+}
+
+/// Documents of a polymorphic relationship must be of one of its collections, readable with
+/// the permissions of their entity and published when it has publication
+fn get_union_filter<S>(
+	union: &DbUnion,
+	session: &Session,
+) -> Result<Box<dyn AQLNode>, FieldError<S>>
+where
+	S: ScalarValue,
+{
+	let mut nodes: Vec<Box<dyn AQLNode>> = Vec::new();
+
+	for entity in &union.entities {
+		if !session.can_read(entity) {
+			return Err(ForbiddenError::new(format!(
+				"Role {} is not allowed to read {}",
+				session.role, entity.name
+			))
+			.into_field_error());
+		}
+
+		let mut member_nodes: Vec<Box<dyn AQLNode>> = vec![Box::new(AQLCollectionFilter(
+			entity.collection_name.clone(),
+		))];
+
+		if let Some(permission_filter) = get_permission_filter(entity, session) {
+			member_nodes.push(permission_filter);
+		}
+
+		if let Some(published_filter) = get_published_filter(entity) {
+			member_nodes.push(published_filter);
+		}
+
+		nodes.push(Box::new(AQLLogicalFilter {
+			nodes: member_nodes,
+			operation: AQLLogicalOperator::AND,
+		}));
+	}
+
+	Ok(Box::new(AQLLogicalFilter {
+		nodes,
+		operation: AQLLogicalOperator::OR,
+	}))
+}
+
+/// `__typename` of a member of a union, from the collection of the document
+fn get_union_typename_property(
+	response_name: String,
+	union: &DbUnion,
+	variable: &str,
+) -> AQLProperty {
+	let mut property = AQLProperty::new(response_name.clone());
+
+	property.expression = Some(union.entities.iter().rev().fold(
+		"null".to_string(),
+		|otherwise, entity| {
+			format!(
+				"(IS_SAME_COLLECTION({}, {}) ? {} : {})",
+				serde_json::to_string(&entity.collection_name).unwrap(),
+				variable,
+				get_typename_property(response_name.clone(), entity, variable)
+					.expression
+					.unwrap(),
+				otherwise
+			)
+		},
+	));
+
+	property
+}
+
+/// `__typename` of a document, taken from the discriminator for polymorphic collections
+fn get_typename_property(response_name: String, entity: &DbEntity, variable: &str) -> AQLProperty {
+	let mut property = AQLProperty::new(response_name);
+
+	let type_name = serde_json::to_string(&entity.name).unwrap();
+
+	property.expression = Some(match entity.discriminator {
+		Some(ref discriminator) if !entity.variants.is_empty() => {
+			let variants = entity
+				.variants
+				.iter()
+				.map(|v| (v.value.clone(), serde_json::Value::String(v.name.clone())))
+				.collect::<serde_json::Map<String, serde_json::Value>>();
+
+			format!(
+				"TRANSLATE({}, {}, {})",
+				get_attribute_accessor(variable, discriminator),
+				serde_json::Value::Object(variants),
+				type_name
+			)
+		}
+		_ => type_name,
+	});
+
+	property
+}
+
+/// Projection of a selected property, sub-documents only return their selected attributes
+fn get_aql_property<S>(
+	response_name: String,
+	selection_set: Option<&[Selection<S>]>,
+	property: Option<&DbProperty>,
+) -> AQLProperty
+where
+	S: ScalarValue,
+{
+	let mut aql_property = AQLProperty::new(response_name);
+
+	let property = match property {
+		Some(property) => property,
+		None => return aql_property,
+	};
+
+	aql_property.translations =
+		get_enum_values(&property.scalar_type).map(|values| get_enum_translations(values));
+
+	// Files are returned from their metadata, the document only holds their id
+	let embedded = match property.scalar_type {
+		DbScalarType::File(_) => {
+			aql_property.lookup = Some(FILES_COLLECTION.to_string());
+
+			Some(&*FILE_TYPE)
+		}
+		_ => property.scalar_type.get_embedded(),
+	};
+
+	if let (Some(embedded), Some(selection_set)) = (embedded, selection_set) {
+		let mut fields = Vec::new();
+
+		for selection in selection_set {
+			if let Selection::Field(Spanning { item: ref f, .. }) = *selection {
+				if f.name.item == "__typename" {
+					continue;
 				}
+
+				fields.push(get_aql_property(
+					f.alias.as_ref().unwrap_or(&f.name).item.to_string(),
+					f.selection_set.as_deref(),
+					embedded.properties.iter().find(|p| p.name == f.name.item),
+				));
 			}
-			_ => unreachable!(),
 		}
+
+		aql_property.fields = Some(fields);
+		aql_property.is_list = matches!(property.scalar_type, DbScalarType::Array(_));
 	}
 
-	query
+	aql_property
 }
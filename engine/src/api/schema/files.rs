@@ -0,0 +1,193 @@
+use juniper::{FieldError, IntoFieldError, ScalarValue, Selection, Spanning, Value};
+use lazy_static::lazy_static;
+use rust_arango::AqlQuery;
+use serde_json::Value as JsonValue;
+
+use crate::api::files::take_upload;
+use crate::api::schema::errors::{DatabaseError, ResolverError, ValidationError};
+use crate::api::schema::AsyncScalarValue;
+use crate::lib::database::api::{DbEmbedded, DbEntity, DbProperty, DbScalarType};
+use crate::lib::database::DATABASE;
+use crate::lib::storage::{get_file, save_file, sign_private_url, DEFAULT_STORAGE};
+
+fn file_property(name: &str, scalar_type: DbScalarType) -> DbProperty {
+	DbProperty {
+		name: name.to_string(),
+		scalar_type,
+		required: true,
+		..Default::default()
+	}
+}
+
+lazy_static! {
+	/// Output type of `File` properties, resolved from the metadata of the file they hold the
+	/// id of
+	pub static ref FILE_TYPE: DbEmbedded = DbEmbedded {
+		name: "File".to_string(),
+		properties: vec![
+			file_property("id", DbScalarType::String),
+			file_property("name", DbScalarType::String),
+			file_property("url", DbScalarType::String),
+			file_property("mime", DbScalarType::String),
+			// Unknown until files created with a signed upload URL are uploaded
+			DbProperty {
+				required: false,
+				..file_property("size", DbScalarType::Int)
+			},
+			file_property("createdAt", DbScalarType::DateTime),
+		],
+	};
+}
+
+/// Replaces the URLs of the files of private storages with signed ones, valid for a while
+pub fn sign_file_urls<S>(value: &mut Value<S>, selection_set: &[Selection<S>])
+where
+	S: ScalarValue,
+{
+	let object = match value {
+		Value::List(items) => {
+			for item in items {
+				sign_file_urls(item, selection_set);
+			}
+
+			return;
+		}
+		Value::Object(object) => object,
+		_ => return,
+	};
+
+	for selection in selection_set {
+		if let Selection::Field(Spanning { item: ref f, .. }) = *selection {
+			if f.name.item != "url" {
+				continue;
+			}
+
+			let response_name = f.alias.as_ref().unwrap_or(&f.name).item;
+
+			if let Some(url) = object.get_mut_field_value(response_name) {
+				if let Some(signed) = url.as_string_value().and_then(sign_private_url) {
+					*url = Value::scalar(signed);
+				}
+			}
+		}
+	}
+}
+
+/// Checks the id of a file given instead of an upload: it must be stored for the collection in
+/// the storage of the property, and not be held by documents other than the written ones, those
+/// matching `owner`. Otherwise any file id could be attached to a document to read it back
+async fn check_file_id<S>(
+	entity: &DbEntity,
+	name: &str,
+	storage: &str,
+	id: &str,
+	owner: Option<&JsonValue>,
+) -> Result<(), FieldError<S>>
+where
+	S: AsyncScalarValue,
+{
+	let is_valid = get_file(id).await.map_or(false, |file| {
+		file["collection"] == entity.collection_name.as_str()
+			&& file["storage"].as_str().unwrap_or(DEFAULT_STORAGE) == storage
+	});
+
+	if !is_valid {
+		return Err(
+			ValidationError::new(format!("File {} not found", id), Some(name.to_string()))
+				.into_field_error(),
+		);
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR d IN @@collection
+				FILTER d[@property] == @id
+				FILTER @owner == null || !MATCHES(d, @owner)
+				LIMIT 1
+				RETURN d._key",
+		)
+		.bind_var("@collection", entity.collection_name.as_str())
+		.bind_var("property", name)
+		.bind_var("id", id)
+		.bind_var("owner", owner.cloned().unwrap_or_default())
+		.build();
+
+	let holders: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(query)
+		.await
+		.map_err(|e| DatabaseError::from(e).into_field_error())?;
+
+	match holders.is_empty() {
+		true => Ok(()),
+		false => Err(ValidationError::new(
+			format!("File {} belongs to another document", id),
+			Some(name.to_string()),
+		)
+		.into_field_error()),
+	}
+}
+
+/// Stores the files uploaded for the `File` properties of the attributes in the storage of
+/// each property, replacing them with the ids of the stored files. Ids of stored files are only
+/// accepted once checked, `owner` matching the documents written which can keep their files
+pub async fn store_uploads<S>(
+	entity: &DbEntity,
+	attributes: String,
+	owner: Option<&JsonValue>,
+) -> Result<String, FieldError<S>>
+where
+	S: AsyncScalarValue,
+{
+	let properties: Vec<(&String, &String)> = entity
+		.properties
+		.iter()
+		.filter_map(|p| match p.scalar_type {
+			DbScalarType::File(ref storage) => Some((&p.name, storage)),
+			_ => None,
+		})
+		.collect();
+
+	if properties.is_empty() {
+		return Ok(attributes);
+	}
+
+	let mut document: JsonValue = match serde_json::from_str(&attributes) {
+		Ok(document) => document,
+		Err(_) => return Ok(attributes),
+	};
+
+	for (name, storage) in properties {
+		let value = match document[name].as_str() {
+			Some(value) => value.to_string(),
+			None => continue,
+		};
+
+		let upload = match take_upload(&value) {
+			Some(upload) => upload,
+			None => {
+				check_file_id::<S>(entity, name, storage, &value, owner).await?;
+
+				continue;
+			}
+		};
+
+		let id = save_file(
+			storage,
+			&entity.collection_name,
+			&upload.name,
+			&upload.mime,
+			&upload.content,
+		)
+		.await
+		.map_err(|e| {
+			ResolverError::new(format!("Error storing {}: {}", upload.name, e)).into_field_error()
+		})?;
+
+		document[name] = JsonValue::String(id);
+	}
+
+	Ok(document.to_string())
+}
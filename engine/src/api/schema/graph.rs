@@ -0,0 +1,247 @@
+use juniper::{
+	Arguments, ExecutionResult, FieldError, IntoFieldError, ScalarValue, Selection, Spanning, Value,
+};
+use rust_arango::{AqlQuery, ClientError};
+use serde_json::Value as JsonValue;
+use tracing::Instrument;
+
+use crate::api::auth::Session;
+use crate::api::schema::errors::{DatabaseError, ForbiddenError, NotFoundError};
+use crate::api::schema::operations::publish::get_published_filter;
+use crate::api::schema::scalars::json::JsonScalar;
+use crate::api::schema::utils::JuniperValue;
+use crate::lib::database::api::{DbGraph, DbRelationshipDirection, DbRelationshipType};
+use crate::lib::database::aql::{
+	AQLCollectionFilter, AQLEdgeProjection, AQLLogicalFilter, AQLLogicalOperator, AQLNode,
+	AQLProperty, AQLQuery, AQLQueryRelationship,
+};
+use crate::lib::database::DATABASE;
+use crate::lib::logging::{log_slow_query, query_span};
+
+/// Direction in which the edges of a graph are followed
+#[derive(GraphQLEnum, Clone)]
+pub enum TraversalDirection {
+	Outbound,
+	Inbound,
+	Any,
+}
+
+impl From<TraversalDirection> for DbRelationshipDirection {
+	fn from(direction: TraversalDirection) -> Self {
+		match direction {
+			TraversalDirection::Outbound => DbRelationshipDirection::Outbound,
+			TraversalDirection::Inbound => DbRelationshipDirection::Inbound,
+			TraversalDirection::Any => DbRelationshipDirection::Any,
+		}
+	}
+}
+
+/// Document reached by a traversal with the edge leading to it, resolved with its parent
+#[derive(GraphQLObject)]
+pub struct GraphNeighbor {
+	pub vertex: JsonScalar,
+	pub edge: JsonScalar,
+	/// Number of edges from the traversed document
+	pub depth: i32,
+}
+
+/// Document of a shortest path, the edge leading to it is null for the first one
+#[derive(GraphQLObject)]
+pub struct GraphPathStep {
+	pub vertex: JsonScalar,
+	pub edge: Option<JsonScalar>,
+}
+
+/// Documents of any collection of the graph can be returned, so all of them must be readable
+pub fn check_graph_access<S>(graph: &DbGraph, session: &Session) -> Result<(), FieldError<S>>
+where
+	S: ScalarValue,
+{
+	match graph.entities.iter().find(|e| !session.can_read(e)) {
+		Some(entity) => Err(ForbiddenError::new(format!(
+			"Role {} is not allowed to read {} of graph {}",
+			session.role, entity.name, graph.name
+		))
+		.into_field_error()),
+		None => Ok(()),
+	}
+}
+
+/// Documents reached in the collections of entities with publication must be published, `None`
+/// when none of the entities of the graph has publication
+fn get_published_vertex_filter(graph: &DbGraph) -> Option<Box<dyn AQLNode>> {
+	if !graph.entities.iter().any(|e| e.publication) {
+		return None;
+	}
+
+	let nodes: Vec<Box<dyn AQLNode>> = graph
+		.entities
+		.iter()
+		.map(|entity| {
+			let mut member_nodes: Vec<Box<dyn AQLNode>> = vec![Box::new(AQLCollectionFilter(
+				entity.collection_name.clone(),
+			))];
+
+			if let Some(published_filter) = get_published_filter(entity) {
+				member_nodes.push(published_filter);
+			}
+
+			Box::new(AQLLogicalFilter {
+				nodes: member_nodes,
+				operation: AQLLogicalOperator::AND,
+			}) as Box<dyn AQLNode>
+		})
+		.collect();
+
+	Some(Box::new(AQLLogicalFilter {
+		nodes,
+		operation: AQLLogicalOperator::OR,
+	}))
+}
+
+fn get_path_property(f_name: &str, response_name: String, query: &AQLQuery) -> AQLProperty {
+	let mut property = AQLProperty::new(response_name);
+
+	property.expression = Some(match f_name {
+		"vertex" => query.get_variable_name(),
+		"edge" => query.get_edge_variable_name(),
+		"depth" => format!("LENGTH({}.edges)", query.get_path_variable_name()),
+		_ => "\"GraphNeighbor\"".to_string(),
+	});
+
+	property
+}
+
+/// Query of a `{graph}_neighbors` field, traversing `1..depth` edges from the document
+pub fn get_neighbors_query<S>(
+	selection_set: &[Selection<S>],
+	graph: &DbGraph,
+	args: &Arguments<S>,
+	query_id: u32,
+	variable_name: String,
+	depth: u32,
+) -> AQLQuery
+where
+	S: ScalarValue,
+{
+	let mut query = AQLQuery::new(query_id);
+	let mut properties = Vec::new();
+
+	for selection in selection_set {
+		if let Selection::Field(Spanning { item: ref f, .. }) = *selection {
+			let response_name = f.alias.as_ref().unwrap_or(&f.name).item.to_string();
+
+			properties.push(get_path_property(f.name.item, response_name, &query));
+		}
+	}
+
+	query.limit = args.get::<i32>("limit");
+	query.filter = get_published_vertex_filter(graph);
+	query.relationship = Some(AQLQueryRelationship {
+		edge: format!("GRAPH {}", serde_json::to_string(&graph.name).unwrap()),
+		variable_name,
+		direction: args
+			.get::<TraversalDirection>("direction")
+			.unwrap_or(TraversalDirection::Any)
+			.into(),
+		relationship_type: DbRelationshipType::ManyToMany,
+		edge_projection: Some(AQLEdgeProjection {
+			properties,
+			node: None,
+		}),
+		depth: Some(depth),
+	});
+
+	query
+}
+
+/// Resolves the `shortestPath` query between two documents of a graph
+pub async fn resolve_shortest_path<S>(
+	graphs: &[DbGraph],
+	arguments: &Arguments<'_, S>,
+	selection_set: &[Selection<'_, S>],
+	session: &Session,
+) -> ExecutionResult<S>
+where
+	S: ScalarValue,
+{
+	let graph_name = arguments.get::<String>("graph").unwrap_or_default();
+
+	let graph = match graphs.iter().find(|g| g.name == graph_name) {
+		Some(graph) => graph,
+		None => return Err(NotFoundError::new(format!("Graph {}", graph_name)).into_field_error()),
+	};
+
+	check_graph_access(graph, session)?;
+
+	let direction: DbRelationshipDirection = arguments
+		.get::<TraversalDirection>("direction")
+		.unwrap_or(TraversalDirection::Any)
+		.into();
+
+	let query = AQLQuery::new(1);
+
+	let properties = selection_set
+		.iter()
+		.filter_map(|selection| match *selection {
+			Selection::Field(Spanning { item: ref f, .. }) => {
+				let response_name = f.alias.as_ref().unwrap_or(&f.name).item.to_string();
+
+				let mut property = get_path_property(f.name.item, response_name, &query);
+
+				if f.name.item == "__typename" {
+					property.expression = Some("\"GraphPathStep\"".to_string());
+				}
+
+				Some(property.describe(&query.get_variable_name()))
+			}
+			_ => None,
+		})
+		.collect::<Vec<String>>()
+		.join(",");
+
+	let aql = format!(
+		"FOR {}, {} IN {} SHORTEST_PATH @from TO @to GRAPH @graph RETURN {{{}}}",
+		query.get_variable_name(),
+		query.get_edge_variable_name(),
+		direction.to_string(),
+		properties
+	);
+
+	let mut bind_vars: Vec<(String, JsonValue)> = vec![
+		(
+			"from".to_string(),
+			arguments.get::<String>("from").unwrap_or_default().into(),
+		),
+		(
+			"to".to_string(),
+			arguments.get::<String>("to").unwrap_or_default().into(),
+		),
+		("graph".to_string(), graph.name.clone().into()),
+	];
+
+	let span = query_span(&aql, &bind_vars);
+	let time = std::time::Instant::now();
+
+	let steps: Result<Vec<JuniperValue<S>>, ClientError> = DATABASE
+		.get()
+		.await
+		.run(|database| {
+			let mut shortest_path_query = AqlQuery::builder().query(&aql);
+
+			for (k, v) in &bind_vars {
+				shortest_path_query = shortest_path_query.bind_var(k.clone(), v.clone());
+			}
+
+			async move { database.aql_query(shortest_path_query.build()).await }
+		})
+		.instrument(span)
+		.await;
+
+	log_slow_query(&aql, &bind_vars, time.elapsed());
+
+	match steps {
+		Ok(steps) => Ok(Value::list(steps.into_iter().map(|s| s.0).collect())),
+		Err(e) => Err(DatabaseError::from(e).into_field_error()),
+	}
+}
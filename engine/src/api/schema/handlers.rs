@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use serde_json::Value as JsonValue;
+
+use crate::api::schema::errors::HandlerError;
+use crate::lib::database::api::DbHandler;
+use crate::lib::plugins::call_plugin;
+
+fn error_from_body(body: &JsonValue) -> Option<HandlerError> {
+	body["message"].as_str().map(|message| {
+		HandlerError::new(message.to_string(), body["code"].as_str().map(String::from))
+	})
+}
+
+/// Calls the handler of the hook or action `name` with the payload, returning its JSON result.
+/// Endpoints fail with a non-2xx status and plugins with an `error` object, both holding a
+/// `message` and an optional `code`
+pub async fn call_handler(
+	handler: &DbHandler,
+	name: &str,
+	payload: &JsonValue,
+	timeout: Duration,
+) -> Result<JsonValue, HandlerError> {
+	match handler {
+		DbHandler::Http { url, headers } => {
+			let mut request = reqwest::Client::new()
+				.post(url)
+				.timeout(timeout)
+				.json(payload);
+
+			for (header, value) in headers {
+				request = request.header(header, value);
+			}
+
+			let response = request
+				.send()
+				.await
+				.map_err(|e| HandlerError::new(format!("Error calling {}: {}", name, e), None))?;
+
+			let status = response.status();
+			let body: JsonValue = response.json().await.unwrap_or(JsonValue::Null);
+
+			if !status.is_success() {
+				return Err(error_from_body(&body).unwrap_or_else(|| {
+					HandlerError::new(format!("{} responded with {}", name, status), None)
+				}));
+			}
+
+			Ok(body)
+		}
+		DbHandler::Plugin { name: plugin, hash } => {
+			let body = call_plugin(plugin, hash, payload)
+				.await
+				.map_err(|e| HandlerError::new(format!("Error calling {}: {}", name, e), None))?;
+
+			if body["error"].is_object() {
+				return Err(error_from_body(&body["error"]).unwrap_or_else(|| {
+					HandlerError::new(format!("Plugin {} failed", plugin), None)
+				}));
+			}
+
+			Ok(body)
+		}
+	}
+}
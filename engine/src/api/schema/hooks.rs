@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use juniper::{FieldError, IntoFieldError};
+use serde_json::{json, Value as JsonValue};
+
+use crate::api::auth::Session;
+use crate::api::schema::errors::HandlerError;
+use crate::api::schema::handlers::call_handler;
+use crate::api::schema::AsyncScalarValue;
+use crate::lib::database::api::{DbEntity, DbHook};
+use crate::lib::events::EventOperation;
+use crate::lib::schema::HookStage;
+
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Calls the handler of the hook, returning the JSON it responded with, if any
+async fn call_hook(
+	hook: &DbHook,
+	entity: &DbEntity,
+	operation: EventOperation,
+	mut payload: JsonValue,
+	session: &Session,
+) -> Result<JsonValue, HandlerError> {
+	payload["hook"] = json!(hook.name);
+	payload["collection"] = json!(entity.collection_name);
+	payload["operation"] = json!(operation);
+	payload["stage"] = json!(hook.stage);
+	payload["session"] = session.to_json();
+
+	call_handler(
+		&hook.handler,
+		&format!("hook {}", hook.name),
+		&payload,
+		HOOK_TIMEOUT,
+	)
+	.await
+}
+
+/// Runs the `BEFORE` hooks of the operation one after the other, each receiving the JSON
+/// input returned by the previous one. Any of them can reject the mutation by failing
+pub async fn run_before_hooks<S>(
+	entity: &DbEntity,
+	operation: EventOperation,
+	input: Option<String>,
+	r#where: Option<JsonValue>,
+	session: &Session,
+) -> Result<Option<String>, FieldError<S>>
+where
+	S: AsyncScalarValue,
+{
+	let hooks = entity.get_hooks(HookStage::Before, operation);
+
+	if hooks.is_empty() {
+		return Ok(input);
+	}
+
+	let mut input: Option<JsonValue> = input.and_then(|i| serde_json::from_str(&i).ok());
+
+	for hook in hooks {
+		let body = call_hook(
+			hook,
+			entity,
+			operation,
+			json!({ "input": input, "where": r#where }),
+			session,
+		)
+		.await
+		.map_err(|e| e.into_field_error())?;
+
+		if let Some(transformed) = body.get("input").filter(|i| i.is_object()) {
+			input = Some(transformed.clone());
+		}
+	}
+
+	Ok(input.map(|i| i.to_string()))
+}
+
+pub fn has_after_hooks(entity: &DbEntity, operation: EventOperation) -> bool {
+	!entity.get_hooks(HookStage::After, operation).is_empty()
+}
+
+/// Whether the operation runs hooks at any stage
+pub fn has_hooks(entity: &DbEntity, operation: EventOperation) -> bool {
+	entity
+		.hooks
+		.iter()
+		.any(|h| h.operations.contains(&operation))
+}
+
+/// Runs the `AFTER` hooks of the operation with the changed documents, `old` and `new`. The
+/// mutation is already applied so their failures are only logged
+pub async fn run_after_hooks(
+	entity: &DbEntity,
+	operation: EventOperation,
+	changes: &[JsonValue],
+	session: &Session,
+) {
+	if changes.is_empty() {
+		return;
+	}
+
+	for hook in entity.get_hooks(HookStage::After, operation) {
+		if let Err(e) = call_hook(
+			hook,
+			entity,
+			operation,
+			json!({ "documents": changes }),
+			session,
+		)
+		.await
+		{
+			tracing::error!(
+				hook = hook.name.as_str(),
+				"Error running hook: {}",
+				e.message()
+			);
+		}
+	}
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use juniper::meta::MetaType;
+use juniper::{FromInputValue, GraphQLType, GraphQLValue, InputValue, Registry, ScalarValue};
+
+use crate::api::schema::enums::{get_enum_db_value, DbEnumInfo, GraphQLEnum};
+use crate::lib::database::aql::{
+	AQLFilterInOperation, AQLFilterOperation, AQLLogicalFilter, AQLLogicalOperator, AQLNode,
+	AQLNotFilter, AQLOperation, AQLQueryParameter, AQLQueryRaw, AQLQueryValue,
+};
+
+pub struct EnumFilterData {
+	pub name: String,
+	pub enum_info: DbEnumInfo,
+}
+
+impl EnumFilterData {
+	pub fn new(name: String, values: Vec<String>, descriptions: HashMap<String, String>) -> Self {
+		Self {
+			name: format!("{}ComparisonExp", name),
+			enum_info: DbEnumInfo {
+				name,
+				properties: values,
+				descriptions,
+			},
+		}
+	}
+}
+
+pub struct EnumFilter<'a, S: 'a> {
+	_marker: PhantomData<&'a S>,
+}
+
+impl<'a, S> GraphQLValue<S> for EnumFilter<'a, S>
+where
+	S: ScalarValue,
+{
+	type Context = ();
+	type TypeInfo = EnumFilterData;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+impl<'a, S> GraphQLType<S> for EnumFilter<'a, S>
+where
+	S: ScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let args = vec![
+			registry.arg::<Option<GraphQLEnum>>("_eq", &info.enum_info),
+			registry.arg::<Option<GraphQLEnum>>("_neq", &info.enum_info),
+			registry.arg::<Option<Vec<GraphQLEnum>>>("_in", &info.enum_info),
+			registry.arg::<Option<Vec<GraphQLEnum>>>("_nin", &info.enum_info),
+		];
+
+		registry
+			.build_input_object_type::<Self>(info, &args)
+			.into_meta()
+	}
+}
+
+impl<'a, S> FromInputValue<S> for EnumFilter<'a, S>
+where
+	S: ScalarValue,
+{
+	fn from_input_value(_: &InputValue<S>) -> Option<Self> {
+		Some(Self {
+			_marker: Default::default(),
+		})
+	}
+}
+
+impl<'a, S> EnumFilter<'a, S>
+where
+	S: ScalarValue,
+{
+	/// Builds the filter comparing against the stored values of the given enum value names
+	pub fn get_aql_filter_node(
+		attribute: String,
+		value: &InputValue<S>,
+		values: &[String],
+	) -> impl AQLNode {
+		let mut node = AQLLogicalFilter {
+			nodes: Vec::new(),
+			operation: AQLLogicalOperator::AND,
+		};
+
+		let get_node = |value: &InputValue<S>| -> Box<dyn AQLNode> {
+			match value
+				.as_enum_value()
+				.or_else(|| value.as_string_value())
+				.and_then(|name| get_enum_db_value(values, name))
+			{
+				Some(v) => Box::new(AQLQueryValue(serde_json::to_string(v).unwrap())),
+				None => Box::new(AQLQueryRaw("null".to_string())),
+			}
+		};
+
+		let get_list_nodes = |value: &InputValue<S>| -> Vec<Box<dyn AQLNode>> {
+			value
+				.to_list_value()
+				.map(|list| list.into_iter().map(&get_node).collect())
+				.unwrap_or_default()
+		};
+
+		if let InputValue::Object(items) = value {
+			for (key, value) in items {
+				let attr = || Box::new(AQLQueryParameter(attribute.clone()));
+
+				let filter: Box<dyn AQLNode> = match key.item.as_str() {
+					"_eq" => Box::new(AQLFilterOperation {
+						left_node: attr(),
+						operation: AQLOperation::Equal,
+						right_node: get_node(&value.item),
+					}),
+					"_neq" => Box::new(AQLFilterOperation {
+						left_node: attr(),
+						operation: AQLOperation::NotEqual,
+						right_node: get_node(&value.item),
+					}),
+					"_in" => Box::new(AQLFilterInOperation {
+						left_node: attr(),
+						vec: get_list_nodes(&value.item),
+					}),
+					"_nin" => Box::new(AQLNotFilter(Box::new(AQLFilterInOperation {
+						left_node: attr(),
+						vec: get_list_nodes(&value.item),
+					}))),
+					_ => unreachable!(),
+				};
+
+				node.nodes.push(filter);
+			}
+		}
+
+		node
+	}
+}
@@ -1,15 +1,21 @@
 use convert_case::Casing;
 use juniper::meta::{Argument, MetaType};
 use juniper::{
-	Arguments, FromInputValue, GraphQLType, GraphQLValue, InputValue, Registry, ScalarValue, ID,
+	Arguments, FieldError, FromInputValue, GraphQLType, GraphQLValue, InputValue, IntoFieldError,
+	Registry, ScalarValue, ID,
 };
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+use crate::api::auth::Session;
+use crate::api::schema::errors::{ForbiddenError, ValidationError};
 use crate::api::schema::input;
 use crate::api::schema::operations::OperationData;
-use crate::lib::database::api::DbScalarType;
-use crate::lib::database::aql::{AQLFilter, AQLLogicalFilter, AQLLogicalOperator, AQLNode};
+use crate::lib::database::api::{DbEmbedded, DbEntity, DbProperty, DbScalarType};
+use crate::lib::database::aql::{
+	AQLArrayFilter, AQLArrayQuantifier, AQLFilter, AQLLogicalFilter, AQLLogicalOperator, AQLNode,
+	AQLQueryRaw,
+};
 
 pub trait FilterOperation<S>
 where
@@ -24,6 +30,7 @@ where
 {
 	pub name: String,
 	pub operation_data: &'a OperationData<S>,
+	pub properties: &'a [DbProperty],
 }
 
 impl<'a, S> EntityFilterData<'a, S>
@@ -37,6 +44,16 @@ where
 				data.entity.name.to_case(convert_case::Case::Pascal)
 			),
 			operation_data: data,
+			properties: &data.entity.properties,
+		}
+	}
+
+	/// Filter on the properties of a structured sub-document
+	pub fn embedded(data: &'a OperationData<S>, embedded: &'a DbEmbedded) -> Self {
+		Self {
+			name: format!("{}BoolExp", embedded.name),
+			operation_data: data,
+			properties: &embedded.properties,
 		}
 	}
 }
@@ -94,14 +111,22 @@ where
 
 		args.extend([and, not, or]);
 
-		for property in &info.operation_data.entity.properties {
+		for property in info.properties.iter() {
 			let arg = match property.scalar_type {
-				DbScalarType::String | DbScalarType::Enum(_) => {
-					registry.arg::<Option<input::str::Filter<'a, S>>>(
+				DbScalarType::String | DbScalarType::Uuid | DbScalarType::File(_) => registry
+					.arg::<Option<input::str::Filter<'a, S>>>(
 						property.name.as_str(),
 						&input::str::FilterData::from(info),
-					)
-				}
+					),
+				DbScalarType::Enum(ref values) => registry
+					.arg::<Option<input::enums::EnumFilter<'a, S>>>(
+						property.name.as_str(),
+						&input::enums::EnumFilterData::new(
+							property.associated_type.clone().unwrap(),
+							values.clone(),
+							property.enum_descriptions.clone(),
+						),
+					),
 				DbScalarType::Float => registry.arg::<Option<input::float::Filter<'a, S>>>(
 					property.name.as_str(),
 					&input::float::FilterData::from(info),
@@ -110,11 +135,36 @@ where
 					property.name.as_str(),
 					&input::int::FilterData::from(info),
 				),
+				DbScalarType::BigInt => registry.arg::<Option<input::bigint::Filter<'a, S>>>(
+					property.name.as_str(),
+					&input::bigint::FilterData::from(info),
+				),
 				DbScalarType::Boolean => registry.arg::<Option<input::bool::Filter<'a, S>>>(
 					property.name.as_str(),
 					&input::bool::FilterData::from(info),
 				),
-				_ => registry.arg::<Option<i32>>(property.name.as_str(), &()),
+				DbScalarType::DateTime => registry.arg::<Option<input::datetime::Filter<'a, S>>>(
+					property.name.as_str(),
+					&input::datetime::FilterData::from(info),
+				),
+				DbScalarType::Decimal => registry.arg::<Option<input::decimal::Filter<'a, S>>>(
+					property.name.as_str(),
+					&input::decimal::FilterData::from(info),
+				),
+				DbScalarType::Embedded(ref embedded) => registry.arg::<Option<Self>>(
+					property.name.as_str(),
+					&EntityFilterData::embedded(info.operation_data, embedded),
+				),
+				DbScalarType::Array(ref item) => match item.get_embedded() {
+					Some(embedded) => registry.arg::<Option<EmbeddedListFilter<'a, S>>>(
+						property.name.as_str(),
+						&EmbeddedListFilterData::new(info.operation_data, embedded),
+					),
+					// Lists of scalars have no filter
+					None => continue,
+				},
+				// Binary data can't be compared and JSON values have no filter
+				DbScalarType::Bytes | DbScalarType::Object => continue,
 			};
 
 			args.push(arg)
@@ -189,6 +239,151 @@ where
 	arr
 }
 
+/// Fields with an `@auth` directive can only be filtered on by the roles reading them, their
+/// values would be found out from the matched documents otherwise
+pub fn check_filter_access<S>(
+	filter: &FilterAttributes<S>,
+	entity: &DbEntity,
+	session: &Session,
+) -> Result<(), ForbiddenError>
+where
+	S: ScalarValue,
+{
+	let restricted = entity
+		.properties
+		.iter()
+		.find(|p| !session.can_read_field(p) && filter.attributes.contains_key(&p.name));
+
+	if let Some(property) = restricted {
+		return Err(ForbiddenError::new(format!(
+			"Role {} is not allowed to filter on {}.{}",
+			session.role, entity.name, property.name
+		)));
+	}
+
+	let nested = filter
+		.and
+		.iter()
+		.chain(filter.or.iter())
+		.flatten()
+		.chain((*filter.not).as_ref());
+
+	for filter in nested {
+		check_filter_access(filter, entity, session)?;
+	}
+
+	Ok(())
+}
+
+pub fn get_property_types(properties: &[DbProperty]) -> HashMap<String, DbScalarType> {
+	properties
+		.iter()
+		.map(|p| (p.name.clone(), p.scalar_type.clone()))
+		.collect()
+}
+
+/// Decimals and dates that can't be parsed would be compared as `null`, matching the documents
+/// without a value, so they are rejected
+pub fn check_filter_values<S>(
+	filter: &FilterAttributes<S>,
+	properties: &HashMap<String, DbScalarType>,
+) -> Result<(), ValidationError>
+where
+	S: ScalarValue,
+{
+	for (name, value) in &filter.attributes {
+		let scalar = match properties.get(name) {
+			Some(scalar) => scalar,
+			None => continue,
+		};
+
+		match scalar {
+			DbScalarType::Decimal | DbScalarType::DateTime => {
+				let operators = match value {
+					InputValue::Object(operators) => operators,
+					_ => continue,
+				};
+
+				let is_valid = |v: &InputValue<S>| match scalar {
+					DbScalarType::Decimal => input::to_decimal(v).is_some(),
+					_ => input::to_datetime(v).is_some(),
+				};
+
+				for (_, value) in operators {
+					let values = match &value.item {
+						InputValue::List(items) => items.iter().map(|i| &i.item).collect(),
+						item => vec![item],
+					};
+
+					if let Some(invalid) = values.into_iter().find(|v| !v.is_null() && !is_valid(v))
+					{
+						return Err(ValidationError::new(
+							format!(
+								"Invalid {:?} value {} for {}",
+								scalar,
+								invalid.as_string_value().map_or_else(
+									|| "of another type".to_string(),
+									|v| v.to_string()
+								),
+								name
+							),
+							Some(name.clone()),
+						));
+					}
+				}
+			}
+			DbScalarType::Embedded(embedded) => check_filter_values(
+				&parse_filter_attributes(value),
+				&get_property_types(&embedded.properties),
+			)?,
+			DbScalarType::Array(item) if item.get_embedded().is_some() => {
+				let properties = get_property_types(&item.get_embedded().unwrap().properties);
+
+				if let InputValue::Object(items) = value {
+					for (_, value) in items {
+						check_filter_values(&parse_filter_attributes(&value.item), &properties)?;
+					}
+				}
+			}
+			_ => (),
+		}
+	}
+
+	let nested = filter
+		.and
+		.iter()
+		.chain(filter.or.iter())
+		.flatten()
+		.chain((*filter.not).as_ref());
+
+	for filter in nested {
+		check_filter_values(filter, properties)?;
+	}
+
+	Ok(())
+}
+
+/// Checks the `where` argument with `check_filter_access` and `check_filter_values`
+pub fn check_filter_args_access<S>(
+	args: &Arguments<S>,
+	entity: &DbEntity,
+	session: &Session,
+) -> Result<(), FieldError<S>>
+where
+	S: ScalarValue,
+{
+	match args.get::<EntityFilter<S>>("where") {
+		Some(entity_filter) => {
+			let filter = &entity_filter.filter_arguments;
+
+			check_filter_access(filter, entity, session).map_err(|e| e.into_field_error())?;
+			check_filter_values(filter, &get_property_types(&entity.properties))
+				.map_err(|e| e.into_field_error())
+		}
+		None => Ok(()),
+	}
+}
+
 pub fn get_aql_filter_from_args<S>(
 	args: &Arguments<S>,
 	data: &OperationData<S>,
@@ -197,12 +392,7 @@ where
 	S: ScalarValue,
 {
 	if let Some(entity_filter) = args.get::<EntityFilter<S>>("where") {
-		let properties: HashMap<String, DbScalarType> = data
-			.entity
-			.properties
-			.iter()
-			.map(|p| (p.name.clone(), p.scalar_type.clone()))
-			.collect();
+		let properties = get_property_types(&data.entity.properties);
 
 		get_aql_filter_from_entity_filter(&entity_filter.filter_arguments, &properties)
 	} else {
@@ -217,7 +407,19 @@ pub fn get_aql_filter_from_entity_filter<S>(
 where
 	S: ScalarValue,
 {
-	let attr_node = Box::new(create_aql_node_from_attributes(&filter, &properties));
+	get_aql_filter_from_attributes(filter, properties, "")
+}
+
+/// Builds the filter with every attribute name prefixed by `path`, used for sub-documents
+fn get_aql_filter_from_attributes<S>(
+	filter: &FilterAttributes<S>,
+	properties: &HashMap<String, DbScalarType>,
+	path: &str,
+) -> Option<Box<dyn AQLNode>>
+where
+	S: ScalarValue,
+{
+	let attr_node = Box::new(create_aql_node_from_attributes(&filter, &properties, path));
 
 	let mut and_node = None;
 	let mut or_node = None;
@@ -227,6 +429,7 @@ where
 		filters: &Vec<FilterAttributes<S>>,
 		operation: AQLLogicalOperator,
 		properties: &HashMap<String, DbScalarType>,
+		path: &str,
 	) -> Option<Box<dyn AQLNode>> {
 		let mut n = AQLLogicalFilter {
 			nodes: Vec::new(),
@@ -234,7 +437,7 @@ where
 		};
 
 		for a in filters {
-			if let Some(f) = get_aql_filter_from_entity_filter(a, properties) {
+			if let Some(f) = get_aql_filter_from_attributes(a, properties, path) {
 				n.nodes.push(f);
 			}
 		}
@@ -247,15 +450,15 @@ where
 	}
 
 	if let Some(and) = &filter.and {
-		and_node = collect_logical_node(and, AQLLogicalOperator::AND, properties);
+		and_node = collect_logical_node(and, AQLLogicalOperator::AND, properties, path);
 	}
 
 	if let Some(not) = &*filter.not {
-		not_node = get_aql_filter_from_entity_filter(&not, properties);
+		not_node = get_aql_filter_from_attributes(&not, properties, path);
 	}
 
 	if let Some(or) = &filter.or {
-		or_node = collect_logical_node(or, AQLLogicalOperator::OR, properties);
+		or_node = collect_logical_node(or, AQLLogicalOperator::OR, properties, path);
 	}
 
 	let node = AQLFilter {
@@ -275,6 +478,7 @@ where
 fn create_aql_node_from_attributes<S>(
 	filter: &FilterAttributes<S>,
 	properties: &HashMap<String, DbScalarType>,
+	path: &str,
 ) -> impl AQLNode
 where
 	S: ScalarValue,
@@ -286,11 +490,13 @@ where
 
 	for (name, value) in &filter.attributes {
 		if let Some(scalar) = properties.get(name) {
-			node.nodes.push(create_aql_node_from_attribute(
-				name.to_string(),
-				value,
-				scalar,
-			));
+			let attribute_node =
+				create_aql_node_from_attribute(format!("{}{}", path, name), value, scalar);
+
+			// Null and empty comparison expressions don't filter anything
+			if attribute_node.valid() {
+				node.nodes.push(attribute_node);
+			}
 		}
 	}
 
@@ -306,13 +512,144 @@ where
 	S: ScalarValue,
 {
 	match scalar {
-		DbScalarType::String | DbScalarType::Enum(_) => {
+		DbScalarType::String | DbScalarType::Uuid | DbScalarType::File(_) => {
 			Box::new(input::str::Filter::get_aql_filter_node(name, value))
 		}
+		DbScalarType::Enum(values) => Box::new(input::enums::EnumFilter::get_aql_filter_node(
+			name, value, values,
+		)),
 		DbScalarType::Float => Box::new(input::float::Filter::get_aql_filter_node(name, value)),
 		DbScalarType::Int => Box::new(input::int::Filter::get_aql_filter_node(name, value)),
+		DbScalarType::BigInt => Box::new(input::bigint::Filter::get_aql_filter_node(name, value)),
 		DbScalarType::Boolean => Box::new(input::bool::Filter::get_aql_filter_node(name, value)),
-		_ => todo!(),
+		DbScalarType::DateTime => {
+			Box::new(input::datetime::Filter::get_aql_filter_node(name, value))
+		}
+		DbScalarType::Decimal => Box::new(input::decimal::Filter::get_aql_filter_node(name, value)),
+		DbScalarType::Embedded(embedded) => {
+			get_embedded_filter_node(value, embedded, &format!("{}.", name))
+		}
+		DbScalarType::Array(item) if item.get_embedded().is_some() => {
+			let embedded = item.get_embedded().unwrap();
+
+			let mut node = AQLLogicalFilter {
+				nodes: Vec::new(),
+				operation: AQLLogicalOperator::AND,
+			};
+
+			if let InputValue::Object(items) = value {
+				for (key, value) in items {
+					let quantifier = match key.item.as_str() {
+						"_any" => AQLArrayQuantifier::Any,
+						"_all" => AQLArrayQuantifier::All,
+						"_none" => AQLArrayQuantifier::None,
+						_ => unreachable!(),
+					};
+
+					node.nodes.push(Box::new(AQLArrayFilter {
+						attribute: name.clone(),
+						quantifier,
+						filter: get_embedded_filter_node(&value.item, embedded, ""),
+					}));
+				}
+			}
+
+			Box::new(node)
+		}
+		// Properties without a filter input, the empty node is left out of the filter
+		_ => Box::new(AQLLogicalFilter {
+			nodes: Vec::new(),
+			operation: AQLLogicalOperator::AND,
+		}),
+	}
+}
+
+fn get_embedded_filter_node<S>(
+	value: &InputValue<S>,
+	embedded: &DbEmbedded,
+	path: &str,
+) -> Box<dyn AQLNode>
+where
+	S: ScalarValue,
+{
+	let properties = get_property_types(&embedded.properties);
+
+	get_aql_filter_from_attributes(&parse_filter_attributes(value), &properties, path)
+		.unwrap_or_else(|| Box::new(AQLQueryRaw("true".to_string())))
+}
+
+pub struct EmbeddedListFilterData<'a, S>
+where
+	S: ScalarValue,
+{
+	pub name: String,
+	pub item: EntityFilterData<'a, S>,
+}
+
+impl<'a, S> EmbeddedListFilterData<'a, S>
+where
+	S: ScalarValue,
+{
+	pub fn new(data: &'a OperationData<S>, embedded: &'a DbEmbedded) -> Self {
+		Self {
+			name: format!("{}ListExp", embedded.name),
+			item: EntityFilterData::embedded(data, embedded),
+		}
+	}
+}
+
+/// Filters on the items of a list of sub-documents
+pub struct EmbeddedListFilter<'a, S>
+where
+	S: ScalarValue + 'a,
+{
+	_marker: PhantomData<&'a S>,
+}
+
+impl<'a, S> GraphQLValue<S> for EmbeddedListFilter<'a, S>
+where
+	S: ScalarValue,
+{
+	type Context = ();
+	type TypeInfo = EmbeddedListFilterData<'a, S>;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+impl<'a, S> GraphQLType<S> for EmbeddedListFilter<'a, S>
+where
+	S: ScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let args = vec![
+			registry.arg::<Option<EntityFilter<'a, S>>>("_any", &info.item),
+			registry.arg::<Option<EntityFilter<'a, S>>>("_all", &info.item),
+			registry.arg::<Option<EntityFilter<'a, S>>>("_none", &info.item),
+		];
+
+		registry
+			.build_input_object_type::<Self>(info, &args)
+			.into_meta()
+	}
+}
+
+impl<'a, S> FromInputValue<S> for EmbeddedListFilter<'a, S>
+where
+	S: ScalarValue,
+{
+	fn from_input_value(_: &InputValue<S>) -> Option<Self> {
+		Some(Self {
+			_marker: Default::default(),
+		})
 	}
 }
 
@@ -412,3 +749,394 @@ where
 
 	attributes
 }
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use serde_json::{json, Value as JsonValue};
+
+	use super::*;
+	use crate::api::schema::AlchemyScalarValue;
+
+	const FIELD: &str = "i_0[\"field\"]";
+
+	/// AQL of the `where` filter on a single property named `field`
+	fn describe_filter(scalar: DbScalarType, filter: JsonValue) -> Option<String> {
+		let value: InputValue<AlchemyScalarValue> =
+			serde_json::from_value(json!({ "field": filter })).unwrap();
+		let properties = HashMap::from([("field".to_string(), scalar)]);
+
+		get_aql_filter_from_entity_filter(&parse_filter_attributes(&value), &properties)
+			.map(|node| node.describe(0))
+	}
+
+	fn assert_filters(scalar: DbScalarType, cases: Vec<(JsonValue, String)>) {
+		for (filter, expected) in cases {
+			assert_eq!(
+				describe_filter(scalar.clone(), filter.clone()),
+				Some(expected),
+				"{:?} filter {}",
+				scalar,
+				filter
+			);
+		}
+	}
+
+	#[test]
+	fn string_operators() {
+		let like = |function: &str| format!("{}({},\"a%\")", function, FIELD);
+
+		assert_filters(
+			DbScalarType::String,
+			vec![
+				(json!({ "_eq": "a" }), format!("({} == \"a\")", FIELD)),
+				(json!({ "_neq": "a" }), format!("({} != \"a\")", FIELD)),
+				(json!({ "_gt": "a" }), format!("({} > \"a\")", FIELD)),
+				(json!({ "_gte": "a" }), format!("({} >= \"a\")", FIELD)),
+				(json!({ "_lt": "a" }), format!("({} < \"a\")", FIELD)),
+				(json!({ "_lte": "a" }), format!("({} <= \"a\")", FIELD)),
+				(json!({ "_regex": "^a" }), format!("({} =~ \"^a\")", FIELD)),
+				(json!({ "_nregex": "^a" }), format!("({} !~ \"^a\")", FIELD)),
+				(
+					json!({ "_in": ["a", "b"] }),
+					format!("({} IN [\"a\",\"b\"])", FIELD),
+				),
+				(
+					json!({ "_nin": ["a", "b"] }),
+					format!("(NOT ({} IN [\"a\",\"b\"]))", FIELD),
+				),
+				(json!({ "_like": "a%" }), like("LIKE")),
+				(json!({ "_nlike": "a%" }), format!("(NOT {})", like("LIKE"))),
+				(
+					json!({ "_ilike": "a%" }),
+					format!("LIKE({},\"a%\",true)", FIELD),
+				),
+				(
+					json!({ "_nilike": "a%" }),
+					format!("(NOT LIKE({},\"a%\",true))", FIELD),
+				),
+			],
+		);
+	}
+
+	#[test]
+	fn string_values_are_escaped() {
+		assert_filters(
+			DbScalarType::String,
+			vec![(
+				json!({ "_eq": "a\" || true || \"" }),
+				format!("({} == \"a\\\" || true || \\\"\")", FIELD),
+			)],
+		);
+	}
+
+	#[test]
+	fn uuid_and_file_operators() {
+		for scalar in [DbScalarType::Uuid, DbScalarType::File("local".to_string())] {
+			assert_filters(
+				scalar,
+				vec![
+					(json!({ "_eq": "a" }), format!("({} == \"a\")", FIELD)),
+					(json!({ "_in": ["a"] }), format!("({} IN [\"a\"])", FIELD)),
+				],
+			);
+		}
+	}
+
+	#[test]
+	fn int_operators() {
+		assert_filters(
+			DbScalarType::Int,
+			vec![
+				(json!({ "_eq": 1 }), format!("({} == 1)", FIELD)),
+				(json!({ "_eq": 0 }), format!("({} == 0)", FIELD)),
+				(json!({ "_neq": 1 }), format!("({} != 1)", FIELD)),
+				(json!({ "_gt": 1 }), format!("({} > 1)", FIELD)),
+				(json!({ "_gte": 1 }), format!("({} >= 1)", FIELD)),
+				(json!({ "_lt": -1 }), format!("({} < -1)", FIELD)),
+				(json!({ "_lte": 1 }), format!("({} <= 1)", FIELD)),
+				(json!({ "_in": [1, 2] }), format!("({} IN [1,2])", FIELD)),
+				(
+					json!({ "_nin": [1, 2] }),
+					format!("(NOT ({} IN [1,2]))", FIELD),
+				),
+			],
+		);
+	}
+
+	#[test]
+	fn float_operators() {
+		assert_filters(
+			DbScalarType::Float,
+			vec![
+				(json!({ "_eq": 1.5 }), format!("({} == 1.5)", FIELD)),
+				(json!({ "_neq": 1.5 }), format!("({} != 1.5)", FIELD)),
+				(json!({ "_gt": 1.5 }), format!("({} > 1.5)", FIELD)),
+				(json!({ "_gte": 1.5 }), format!("({} >= 1.5)", FIELD)),
+				(json!({ "_lt": 1.5 }), format!("({} < 1.5)", FIELD)),
+				(json!({ "_lte": 1.5 }), format!("({} <= 1.5)", FIELD)),
+				(
+					json!({ "_in": [1.5, 2.5] }),
+					format!("({} IN [1.5,2.5])", FIELD),
+				),
+				(
+					json!({ "_nin": [1.5, 2.5] }),
+					format!("(NOT ({} IN [1.5,2.5]))", FIELD),
+				),
+			],
+		);
+	}
+
+	#[test]
+	fn bigint_operators() {
+		let big = 9007199254740993i64;
+
+		assert_filters(
+			DbScalarType::BigInt,
+			vec![
+				(json!({ "_eq": big }), format!("({} == {})", FIELD, big)),
+				(json!({ "_eq": 1 }), format!("({} == 1)", FIELD)),
+				(json!({ "_neq": big }), format!("({} != {})", FIELD, big)),
+				(json!({ "_gt": big }), format!("({} > {})", FIELD, big)),
+				(json!({ "_gte": big }), format!("({} >= {})", FIELD, big)),
+				(json!({ "_lt": big }), format!("({} < {})", FIELD, big)),
+				(json!({ "_lte": big }), format!("({} <= {})", FIELD, big)),
+				(
+					json!({ "_in": [big, 1] }),
+					format!("({} IN [{},1])", FIELD, big),
+				),
+				(
+					json!({ "_nin": [big, 1] }),
+					format!("(NOT ({} IN [{},1]))", FIELD, big),
+				),
+			],
+		);
+	}
+
+	#[test]
+	fn bool_operators() {
+		assert_filters(
+			DbScalarType::Boolean,
+			vec![
+				(json!({ "_eq": true }), format!("({} == true)", FIELD)),
+				(json!({ "_eq": false }), format!("({} == false)", FIELD)),
+				(json!({ "_neq": true }), format!("({} != true)", FIELD)),
+				(json!({ "_neq": false }), format!("({} != false)", FIELD)),
+			],
+		);
+	}
+
+	#[test]
+	fn null_comparisons() {
+		for scalar in [
+			DbScalarType::String,
+			DbScalarType::Int,
+			DbScalarType::Float,
+			DbScalarType::BigInt,
+			DbScalarType::Boolean,
+			DbScalarType::Decimal,
+			DbScalarType::DateTime,
+		] {
+			assert_filters(
+				scalar,
+				vec![
+					(json!({ "_eq": null }), format!("({} == null)", FIELD)),
+					(json!({ "_neq": null }), format!("({} != null)", FIELD)),
+				],
+			);
+		}
+	}
+
+	#[test]
+	fn empty_comparisons_are_skipped() {
+		assert_eq!(
+			describe_filter(DbScalarType::Boolean, JsonValue::Null),
+			None
+		);
+		assert_eq!(describe_filter(DbScalarType::Int, json!({})), None);
+	}
+
+	#[test]
+	fn properties_without_filter_are_skipped() {
+		for scalar in [
+			DbScalarType::Object,
+			DbScalarType::Bytes,
+			DbScalarType::Array(Box::new(DbScalarType::String)),
+		] {
+			assert_eq!(describe_filter(scalar, json!({ "_eq": 1 })), None);
+		}
+	}
+
+	#[test]
+	fn combined_operators() {
+		assert_filters(
+			DbScalarType::Int,
+			vec![(
+				json!({ "_gt": 1, "_lt": 5 }),
+				format!("(({} > 1) AND ({} < 5))", FIELD, FIELD),
+			)],
+		);
+	}
+
+	#[test]
+	fn enum_operators() {
+		let scalar = DbScalarType::Enum(vec!["draft".to_string(), "in review".to_string()]);
+
+		assert_filters(
+			scalar,
+			vec![
+				(
+					json!({ "_eq": "IN_REVIEW" }),
+					format!("({} == \"in review\")", FIELD),
+				),
+				(
+					json!({ "_neq": "DRAFT" }),
+					format!("({} != \"draft\")", FIELD),
+				),
+				(
+					json!({ "_in": ["DRAFT", "IN_REVIEW"] }),
+					format!("({} IN [\"draft\",\"in review\"])", FIELD),
+				),
+				(
+					json!({ "_nin": ["DRAFT"] }),
+					format!("(NOT ({} IN [\"draft\"]))", FIELD),
+				),
+				(json!({ "_eq": "UNKNOWN" }), format!("({} == null)", FIELD)),
+				(json!({ "_eq": null }), format!("({} == null)", FIELD)),
+			],
+		);
+	}
+
+	#[test]
+	fn datetime_operators() {
+		let compare = |operation: &str| {
+			format!(
+				"(DATE_TIMESTAMP({}) {} DATE_TIMESTAMP(\"2024-01-02T01:04:05+00:00\"))",
+				FIELD, operation
+			)
+		};
+		let date = "2024-01-02T03:04:05+02:00";
+
+		assert_filters(
+			DbScalarType::DateTime,
+			vec![
+				(json!({ "_eq": date }), compare("==")),
+				(json!({ "_neq": date }), compare("!=")),
+				(json!({ "_before": date }), compare("<")),
+				(json!({ "_after": date }), compare(">")),
+				(
+					json!({ "_between": [date, date] }),
+					format!("({} AND {})", compare(">="), compare("<=")),
+				),
+				(json!({ "_between": [date] }), "false".to_string()),
+			],
+		);
+	}
+
+	#[test]
+	fn decimal_operators() {
+		let compare =
+			|operation: &str| format!("(TO_NUMBER({}) {} TO_NUMBER(\"1.50\"))", FIELD, operation);
+
+		assert_filters(
+			DbScalarType::Decimal,
+			vec![
+				(json!({ "_eq": "1.50" }), compare("==")),
+				(json!({ "_neq": "1.50" }), compare("!=")),
+				(json!({ "_gt": "1.50" }), compare(">")),
+				(json!({ "_gte": "1.50" }), compare(">=")),
+				(json!({ "_lt": "1.50" }), compare("<")),
+				(json!({ "_lte": "1.50" }), compare("<=")),
+			],
+		);
+	}
+
+	fn check_values(scalar: DbScalarType, filter: JsonValue) -> bool {
+		let value: InputValue<AlchemyScalarValue> =
+			serde_json::from_value(json!({ "field": filter })).unwrap();
+		let properties = HashMap::from([("field".to_string(), scalar)]);
+
+		check_filter_values(&parse_filter_attributes(&value), &properties).is_ok()
+	}
+
+	#[test]
+	fn invalid_values_are_rejected() {
+		assert!(check_values(
+			DbScalarType::Decimal,
+			json!({ "_eq": "1.50" })
+		));
+		assert!(check_values(DbScalarType::Decimal, json!({ "_eq": null })));
+		assert!(!check_values(
+			DbScalarType::Decimal,
+			json!({ "_eq": "1e3" })
+		));
+		assert!(!check_values(DbScalarType::Decimal, json!({ "_gt": 1 })));
+
+		let date = "2024-01-02T03:04:05+02:00";
+
+		assert!(check_values(
+			DbScalarType::DateTime,
+			json!({ "_between": [date, date] })
+		));
+		assert!(!check_values(
+			DbScalarType::DateTime,
+			json!({ "_eq": "not a date" })
+		));
+		assert!(!check_values(
+			DbScalarType::DateTime,
+			json!({ "_between": [date, "not a date"] })
+		));
+	}
+
+	fn get_embedded() -> Arc<DbEmbedded> {
+		Arc::new(DbEmbedded {
+			name: "Address".to_string(),
+			properties: vec![DbProperty {
+				name: "city".to_string(),
+				scalar_type: DbScalarType::String,
+				..Default::default()
+			}],
+		})
+	}
+
+	#[test]
+	fn embedded_operators() {
+		assert_filters(
+			DbScalarType::Embedded(get_embedded()),
+			vec![(
+				json!({ "city": { "_eq": "a" } }),
+				"(i_0[\"field\"][\"city\"] == \"a\")".to_string(),
+			)],
+		);
+	}
+
+	#[test]
+	fn embedded_list_operators() {
+		let quantified = |filter: &str, comparison: &str| {
+			format!(
+				"(LENGTH(FOR i_1 IN ({} || []) FILTER {} LIMIT 1 RETURN true) {})",
+				FIELD, filter, comparison
+			)
+		};
+		let item_filter = "(i_1[\"city\"] == \"a\")";
+
+		assert_filters(
+			DbScalarType::Array(Box::new(DbScalarType::Embedded(get_embedded()))),
+			vec![
+				(
+					json!({ "_any": { "city": { "_eq": "a" } } }),
+					quantified(item_filter, "> 0"),
+				),
+				(
+					json!({ "_all": { "city": { "_eq": "a" } } }),
+					quantified(&format!("NOT ({})", item_filter), "== 0"),
+				),
+				(
+					json!({ "_none": { "city": { "_eq": "a" } } }),
+					quantified(item_filter, "== 0"),
+				),
+			],
+		);
+	}
+}
@@ -1,15 +1,17 @@
+use convert_case::Casing;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use juniper::meta::{Argument, MetaType};
 use juniper::{FromInputValue, GraphQLType, GraphQLValue, InputValue, Registry};
 
+use crate::api::schema::embedded::{EmbeddedInput, EmbeddedInputData};
 use crate::api::schema::input::filter::{
 	parse_indices_attributes, EntityIndicesFilter, EntityIndicesFilterData,
 };
 use crate::api::schema::operations::{OperationData, OperationRegistry};
 use crate::api::schema::{build_argument_from_property, input_value_to_string, AsyncScalarValue};
-use crate::lib::database::api::DbRelationship;
+use crate::lib::database::api::{DbEmbedded, DbRelationship};
 
 fn build_insert_arg_from_relationship<'r, S>(
 	registry: &mut Registry<'r, S>,
@@ -26,13 +28,21 @@ where
 	}
 }
 
+/// Relationship to insert with the attributes of its edge, if any
 #[derive(Debug)]
 pub enum EntityInsertRelationship<S>
 where
 	S: AsyncScalarValue,
 {
-	Existing(String, HashMap<String, InputValue<S>>),
-	New(String, InputValue<S>),
+	Existing(String, HashMap<String, InputValue<S>>, Option<String>),
+	New(String, InputValue<S>, Option<String>),
+	/// Existing document of the named target of a polymorphic relationship
+	Target(
+		String,
+		String,
+		HashMap<String, InputValue<S>>,
+		Option<String>,
+	),
 }
 
 pub struct EntityInsert<'a, S>
@@ -129,19 +139,31 @@ where
 			} else if k == "relationships" {
 				for (rel_key, rel_object) in object.to_object_value().unwrap() {
 					let mut parse_object = |object: &InputValue<S>| {
-						for (rel_type, rel_data) in object.to_object_value().unwrap() {
+						let object = object.to_object_value().unwrap();
+
+						let edge = object.get("edge").map(|edge| input_value_to_string(edge));
+
+						for (rel_type, rel_data) in object {
 							match rel_type {
 								"addExisting" => {
 									relationships.push(EntityInsertRelationship::Existing(
 										rel_key.to_string(),
 										parse_indices_attributes(rel_data),
+										edge.clone(),
 									))
 								}
 								"addNew" => relationships.push(EntityInsertRelationship::New(
 									rel_key.to_string(),
 									rel_data.clone(),
+									edge.clone(),
+								)),
+								"edge" => {}
+								target => relationships.push(EntityInsertRelationship::Target(
+									rel_key.to_string(),
+									target.to_string(),
+									parse_indices_attributes(rel_data),
+									edge.clone(),
 								)),
-								&_ => {}
 							}
 						}
 					};
@@ -215,16 +237,27 @@ where
 				continue;
 			}
 
+			// Generated values can be omitted, the server fills them in
 			let arg = build_argument_from_property(
 				registry,
 				&property,
 				&property.scalar_type,
-				property.required,
+				property.required && property.generator.is_none(),
 			);
 
 			args.push(arg);
 		}
 
+		// Variant properties are only required by documents of their variant
+		for property in info.data.entity.get_variant_properties() {
+			args.push(build_argument_from_property(
+				registry,
+				property,
+				&property.scalar_type,
+				false,
+			));
+		}
+
 		registry
 			.build_input_object_type::<EntityAttributesInsert>(info, &args)
 			.into_meta()
@@ -299,12 +332,42 @@ where
 		let mut args = Vec::new();
 
 		for relationship in &*info.data.relationships {
+			if relationship.union.is_some() {
+				let rel_info = &EntityUnionRelationshipInsertData {
+					name: format!(
+						"{}{}Insert",
+						relationship.from.name,
+						relationship.name.to_case(convert_case::Case::Pascal)
+					),
+					relationship,
+					registry: info.registry,
+				};
+
+				args.push(if relationship.relationship_type.returns_array() {
+					registry.arg::<Option<Vec<EntityUnionRelationshipInsert>>>(
+						relationship.name.as_str(),
+						rel_info,
+					)
+				} else {
+					registry.arg::<Option<EntityUnionRelationshipInsert>>(
+						relationship.name.as_str(),
+						rel_info,
+					)
+				});
+
+				continue;
+			}
+
 			let rel_data = &info
 				.registry
 				.get_operation_data(&relationship.to.name)
 				.expect("Relationship entity operation data not found");
 
-			let rel_info = &EntityRelationshipInsertData::new(rel_data, &info.registry);
+			let rel_info = &EntityRelationshipInsertData::new(
+				rel_data,
+				&info.registry,
+				relationship.edge_properties.as_deref(),
+			);
 
 			let arg = build_insert_arg_from_relationship(registry, relationship, rel_info);
 
@@ -355,17 +418,27 @@ where
 	pub name: String,
 	pub data: &'a OperationData<S>,
 	pub registry: &'a OperationRegistry<S>,
+	/// Attributes of the edge, given with the related document
+	pub edge: Option<&'a DbEmbedded>,
 }
 
 impl<'a, S> EntityRelationshipInsertData<'a, S>
 where
 	S: AsyncScalarValue,
 {
-	pub fn new(data: &'a OperationData<S>, registry: &'a OperationRegistry<S>) -> Self {
+	pub fn new(
+		data: &'a OperationData<S>,
+		registry: &'a OperationRegistry<S>,
+		edge: Option<&'a DbEmbedded>,
+	) -> Self {
 		Self {
-			name: format!("{}RelationshipInsert", data.entity.name.as_str()),
+			name: match edge {
+				Some(edge) => format!("{}Insert", edge.name),
+				None => format!("{}RelationshipInsert", data.entity.name.as_str()),
+			},
 			data,
 			registry,
+			edge,
 		}
 	}
 }
@@ -391,8 +464,14 @@ where
 			&EntityIndicesFilterData::new(info.data),
 		);
 
+		let mut args = vec![new, existing];
+
+		if let Some(edge) = info.edge {
+			args.push(registry.arg::<Option<EmbeddedInput>>("edge", &EmbeddedInputData::new(edge)));
+		}
+
 		registry
-			.build_input_object_type::<EntityRelationshipInsert>(info, &vec![new, existing])
+			.build_input_object_type::<EntityRelationshipInsert>(info, &args)
 			.into_meta()
 	}
 }
@@ -421,3 +500,81 @@ where
 		})
 	}
 }
+
+/// Existing document related by a polymorphic relationship, given by the indices of one
+/// of its collections
+pub struct EntityUnionRelationshipInsert<'a> {
+	pub data: String,
+
+	_marker: PhantomData<&'a ()>,
+}
+
+pub struct EntityUnionRelationshipInsertData<'a, S>
+where
+	S: AsyncScalarValue,
+{
+	pub name: String,
+	pub relationship: &'a DbRelationship,
+	pub registry: &'a OperationRegistry<S>,
+}
+
+impl<'a, S> GraphQLType<S> for EntityUnionRelationshipInsert<'a>
+where
+	S: AsyncScalarValue,
+{
+	fn name(info: &Self::TypeInfo) -> Option<&str> {
+		Some(info.name.as_str())
+	}
+
+	fn meta<'r>(info: &Self::TypeInfo, registry: &mut Registry<'r, S>) -> MetaType<'r, S>
+	where
+		S: 'r,
+	{
+		let mut args = Vec::new();
+
+		for target in info.relationship.get_targets() {
+			let target_data = info
+				.registry
+				.get_operation_data(&target.name)
+				.expect("Relationship entity operation data not found");
+
+			args.push(registry.arg::<Option<EntityIndicesFilter<S>>>(
+				target.name.to_case(convert_case::Case::Snake).as_str(),
+				&EntityIndicesFilterData::new(&target_data),
+			));
+		}
+
+		if let Some(ref edge) = info.relationship.edge_properties {
+			args.push(registry.arg::<Option<EmbeddedInput>>("edge", &EmbeddedInputData::new(edge)));
+		}
+
+		registry
+			.build_input_object_type::<EntityUnionRelationshipInsert>(info, &args)
+			.into_meta()
+	}
+}
+
+impl<'a, S> GraphQLValue<S> for EntityUnionRelationshipInsert<'a>
+where
+	S: AsyncScalarValue,
+{
+	type Context = ();
+	type TypeInfo = EntityUnionRelationshipInsertData<'a, S>;
+
+	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
+		<Self as GraphQLType<S>>::name(info)
+	}
+}
+
+impl<'a, S> FromInputValue<S> for EntityUnionRelationshipInsert<'a>
+where
+	S: AsyncScalarValue,
+{
+	fn from_input_value(data: &InputValue<S>) -> Option<Self> {
+		Some(Self {
+			data: input_value_to_string(data),
+
+			_marker: Default::default(),
+		})
+	}
+}
@@ -1,9 +1,15 @@
-use std::fmt::Debug;
-
 use juniper::{InputValue, ScalarValue};
+use serde::Serialize;
 
-use crate::lib::database::aql::{AQLNode, AQLQueryRaw, AQLQueryValue};
+use crate::api::schema::scalars::datetime::parse_datetime;
+use crate::api::schema::scalars::decimal::is_decimal;
+use crate::api::schema::scalars::value::scalar_to_i64;
+use crate::lib::database::aql::{
+	AQLFilterOperation, AQLFunctionCall, AQLNode, AQLOperation, AQLQueryParameter, AQLQueryRaw,
+	AQLQueryValue,
+};
 
+pub mod enums;
 pub mod filter;
 pub mod insert;
 pub mod set;
@@ -12,14 +18,16 @@ mod utils;
 
 type MutatorFn<S, R> = fn(&InputValue<S>) -> Option<R>;
 
+/// AQL literal of the value, `null` when it's null or not of the type of the mutator. Values
+/// are written as JSON so strings are escaped and booleans compare as `true` and `false`
 pub fn get_node<S, R>(value: &InputValue<S>, mutator: MutatorFn<S, R>) -> Box<dyn AQLNode>
 where
 	S: ScalarValue,
-	R: Debug,
+	R: Serialize,
 {
 	match mutator(value) {
 		None => Box::new(AQLQueryRaw("null".to_string())),
-		Some(v) => Box::new(AQLQueryValue(format!("{:?}", v))),
+		Some(v) => Box::new(AQLQueryValue(serde_json::to_string(&v).unwrap())),
 	}
 }
 
@@ -29,7 +37,7 @@ pub fn get_list_nodes<S, R>(
 ) -> Vec<Box<dyn AQLNode>>
 where
 	S: ScalarValue,
-	R: Debug,
+	R: Serialize,
 {
 	let mut nodes: Vec<Box<dyn AQLNode>> = Vec::new();
 
@@ -220,13 +228,223 @@ utils::define_type_filter!(int, i32, "IntComparisonExp", to_int {
 	};
 });
 
+pub fn to_bigint<S>(v: &InputValue<S>) -> Option<i64>
+where
+	S: ScalarValue,
+{
+	v.as_scalar().and_then(scalar_to_i64)
+}
+
+utils::define_type_filter!(bigint, crate::api::schema::scalars::bigint::BigIntScalar, "BigIntComparisonExp", to_bigint {
+	BigIntEqual, "_eq", Equal;
+	BigIntGreaterThan, "_gt", GreaterThan;
+	BigIntGreaterOrEqualThan, "_gte", GreaterOrEqualThan;
+	BigIntLessThan, "_lt", LessThan;
+	BigIntLessOrEqualThan, "_lte", LessOrEqualThan;
+	BigIntNotEqual, "_neq", NotEqual;
+
+	* BigIntInArray, "_in", Vec<crate::api::schema::scalars::bigint::BigIntScalar>, (attr, val) -> {
+		use crate::api::schema::input::{get_list_nodes, to_bigint};
+		use crate::lib::database::aql::{AQLFilterInOperation, AQLQueryParameter};
+
+		let nodes = get_list_nodes(val, to_bigint);
+
+		Box::new(AQLFilterInOperation {
+			left_node: Box::new(AQLQueryParameter(attr.to_string())),
+			vec: nodes,
+		})
+	};
+	* BigIntNotInArray, "_nin", Vec<crate::api::schema::scalars::bigint::BigIntScalar>, (attr, val) -> {
+		use crate::api::schema::input::{get_list_nodes, to_bigint};
+		use crate::lib::database::aql::{AQLFilterInOperation, AQLQueryParameter, AQLNotFilter};
+
+		let nodes = get_list_nodes(val, to_bigint);
+
+		Box::new(AQLNotFilter(Box::new(AQLFilterInOperation {
+			left_node: Box::new(AQLQueryParameter(attr.to_string())),
+			vec: nodes,
+		})))
+	};
+});
+
 pub fn to_bool<S>(v: &InputValue<S>) -> Option<bool>
 where
 	S: ScalarValue,
 {
-	v.as_scalar().map(|i| i.as_boolean().unwrap())
+	v.as_scalar().and_then(|i| i.as_boolean())
 }
 
 utils::define_type_filter!(bool, bool, "BoolComparisonExp", to_bool {
 	BoolEqual, "_eq", Equal;
+	BoolNotEqual, "_neq", NotEqual;
+});
+
+pub fn to_datetime<S>(v: &InputValue<S>) -> Option<String>
+where
+	S: ScalarValue,
+{
+	v.as_string_value()
+		.and_then(parse_datetime)
+		.map(|d| d.to_rfc3339())
+}
+
+/// Compares the attribute to `null` as it is, the functions comparing the values would turn
+/// it into a number matching other values
+fn get_null_comparison_node(attribute: &str, operation: AQLOperation) -> Box<dyn AQLNode> {
+	Box::new(AQLFilterOperation {
+		left_node: Box::new(AQLQueryParameter(attribute.to_string())),
+		operation,
+		right_node: Box::new(AQLQueryRaw("null".to_string())),
+	})
+}
+
+/// Compares dates by timestamp so values stored with different offsets still match
+pub fn get_date_comparison_node<S>(
+	attribute: &str,
+	value: &InputValue<S>,
+	operation: AQLOperation,
+) -> Box<dyn AQLNode>
+where
+	S: ScalarValue,
+{
+	if value.is_null() {
+		return get_null_comparison_node(attribute, operation);
+	}
+
+	let date_timestamp = |node: Box<dyn AQLNode>| -> Box<dyn AQLNode> {
+		Box::new(AQLFunctionCall {
+			name: "DATE_TIMESTAMP".to_string(),
+			parameters: vec![node],
+		})
+	};
+
+	Box::new(AQLFilterOperation {
+		left_node: date_timestamp(Box::new(AQLQueryParameter(attribute.to_string()))),
+		operation,
+		right_node: date_timestamp(get_node(value, to_datetime)),
+	})
+}
+
+utils::define_type_filter!(datetime, String, "DateTimeComparisonExp", to_datetime {
+	* DateTimeEqual, "_eq", crate::api::schema::scalars::datetime::DateTimeScalar, (attr, val) -> {
+		use crate::api::schema::input::get_date_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_date_comparison_node(attr, val, AQLOperation::Equal)
+	};
+	* DateTimeNotEqual, "_neq", crate::api::schema::scalars::datetime::DateTimeScalar, (attr, val) -> {
+		use crate::api::schema::input::get_date_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_date_comparison_node(attr, val, AQLOperation::NotEqual)
+	};
+	* DateTimeBefore, "_before", crate::api::schema::scalars::datetime::DateTimeScalar, (attr, val) -> {
+		use crate::api::schema::input::get_date_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_date_comparison_node(attr, val, AQLOperation::LessThan)
+	};
+	* DateTimeAfter, "_after", crate::api::schema::scalars::datetime::DateTimeScalar, (attr, val) -> {
+		use crate::api::schema::input::get_date_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_date_comparison_node(attr, val, AQLOperation::GreaterThan)
+	};
+	* DateTimeBetween, "_between", Vec<crate::api::schema::scalars::datetime::DateTimeScalar>, (attr, val) -> {
+		use crate::api::schema::input::get_date_comparison_node;
+		use crate::lib::database::aql::{AQLLogicalFilter, AQLLogicalOperator, AQLOperation, AQLQueryRaw};
+
+		use crate::lib::database::aql::AQLNode;
+
+		let bounds = val.to_list_value().unwrap_or_default();
+
+		let node: Box<dyn AQLNode> = match bounds.as_slice() {
+			[from, to] => Box::new(AQLLogicalFilter {
+				nodes: vec![
+					get_date_comparison_node(attr, from, AQLOperation::GreaterOrEqualThan),
+					get_date_comparison_node(attr, to, AQLOperation::LessOrEqualThan),
+				],
+				operation: AQLLogicalOperator::AND,
+			}),
+			_ => Box::new(AQLQueryRaw("false".to_string())),
+		};
+
+		node
+	};
+});
+
+pub fn to_decimal<S>(v: &InputValue<S>) -> Option<String>
+where
+	S: ScalarValue,
+{
+	v.as_string_value()
+		.filter(|v| is_decimal(v))
+		.map(|v| v.to_string())
+}
+
+/// Decimals are stored as strings, so they are compared numerically instead of lexicographically.
+/// Comparisons happen at double precision, stored values are never rounded.
+pub fn get_decimal_comparison_node<S>(
+	attribute: &str,
+	value: &InputValue<S>,
+	operation: AQLOperation,
+) -> Box<dyn AQLNode>
+where
+	S: ScalarValue,
+{
+	if value.is_null() {
+		return get_null_comparison_node(attribute, operation);
+	}
+
+	let to_number = |node: Box<dyn AQLNode>| -> Box<dyn AQLNode> {
+		Box::new(AQLFunctionCall {
+			name: "TO_NUMBER".to_string(),
+			parameters: vec![node],
+		})
+	};
+
+	Box::new(AQLFilterOperation {
+		left_node: to_number(Box::new(AQLQueryParameter(attribute.to_string()))),
+		operation,
+		right_node: to_number(get_node(value, to_decimal)),
+	})
+}
+
+utils::define_type_filter!(decimal, String, "DecimalComparisonExp", to_decimal {
+	* DecimalEqual, "_eq", crate::api::schema::scalars::decimal::DecimalScalar, (attr, val) -> {
+		use crate::api::schema::input::get_decimal_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_decimal_comparison_node(attr, val, AQLOperation::Equal)
+	};
+	* DecimalGreaterThan, "_gt", crate::api::schema::scalars::decimal::DecimalScalar, (attr, val) -> {
+		use crate::api::schema::input::get_decimal_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_decimal_comparison_node(attr, val, AQLOperation::GreaterThan)
+	};
+	* DecimalGreaterOrEqualThan, "_gte", crate::api::schema::scalars::decimal::DecimalScalar, (attr, val) -> {
+		use crate::api::schema::input::get_decimal_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_decimal_comparison_node(attr, val, AQLOperation::GreaterOrEqualThan)
+	};
+	* DecimalLessThan, "_lt", crate::api::schema::scalars::decimal::DecimalScalar, (attr, val) -> {
+		use crate::api::schema::input::get_decimal_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_decimal_comparison_node(attr, val, AQLOperation::LessThan)
+	};
+	* DecimalLessOrEqualThan, "_lte", crate::api::schema::scalars::decimal::DecimalScalar, (attr, val) -> {
+		use crate::api::schema::input::get_decimal_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_decimal_comparison_node(attr, val, AQLOperation::LessOrEqualThan)
+	};
+	* DecimalNotEqual, "_neq", crate::api::schema::scalars::decimal::DecimalScalar, (attr, val) -> {
+		use crate::api::schema::input::get_decimal_comparison_node;
+		use crate::lib::database::aql::AQLOperation;
+
+		get_decimal_comparison_node(attr, val, AQLOperation::NotEqual)
+	};
 });
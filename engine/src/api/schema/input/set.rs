@@ -4,7 +4,9 @@ use juniper::meta::MetaType;
 use juniper::{FromInputValue, GraphQLType, GraphQLValue, InputValue, Registry, ScalarValue};
 
 use crate::api::schema::operations::OperationData;
-use crate::api::schema::{build_argument_from_property, input_value_to_string, AsyncScalarValue};
+use crate::api::schema::{
+	build_set_argument_from_property, input_value_to_string, AsyncScalarValue,
+};
 
 pub struct EntitySet<'a> {
 	pub data: String,
@@ -51,10 +53,12 @@ where
 				continue;
 			}
 
-			let arg =
-				build_argument_from_property(registry, &property, &property.scalar_type, false);
+			args.push(build_set_argument_from_property(registry, property));
+		}
 
-			args.push(arg);
+		// Variant properties are only required by documents of their variant
+		for property in info.data.entity.get_variant_properties() {
+			args.push(build_set_argument_from_property(registry, property));
 		}
 
 		registry
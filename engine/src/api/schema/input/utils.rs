@@ -26,14 +26,10 @@ macro_rules! define_simple_filter {
 						attribute.to_string(),
 					)),
 					operation: crate::lib::database::aql::AQLOperation::$operation,
-					right_node: match crate::api::schema::input::$fn(value) {
-						None => {
-							Box::new(crate::lib::database::aql::AQLQueryRaw("null".to_string()))
-						}
-						Some(v) => {
-							Box::new(crate::lib::database::aql::AQLQueryValue(format!("{:?}", v)))
-						}
-					},
+					right_node: crate::api::schema::input::get_node(
+						value,
+						crate::api::schema::input::$fn,
+					),
 				})
 			}
 		}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rust_arango::{AqlQuery, ClientError};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tokio::sync::OnceCell;
+use tracing::Instrument;
+
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::auth::Session;
+use crate::api::schema::errors::ResolverError;
+use crate::api::schema::operations::publish::get_published_filter;
+use crate::lib::database::api::{DbRelationship, DbRelationshipDirection};
+use crate::lib::database::aql::{
+	get_session_variables, AQLCollectionFilter, AQLLogicalFilter, AQLLogicalOperator, AQLNode,
+};
+use crate::lib::database::{fetch_batches, DATABASE};
+use crate::lib::logging::{log_slow_query, query_span};
+use crate::lib::CONFIG;
+
+#[derive(Deserialize)]
+struct RelatedDocument {
+	parent: String,
+	document: JsonValue,
+}
+
+/// Lookups of a relationship waiting to be sent together
+struct Batch {
+	ids: Mutex<Vec<String>>,
+	results: OnceCell<Result<HashMap<String, Vec<JsonValue>>, String>>,
+}
+
+/// Loads the documents related to other documents for the resolvers of a request. Lookups
+/// of the same relationship started together are sent as one query filtering the edges by the
+/// ids of all of their parents, and the results are kept for the rest of the request
+#[derive(Default)]
+pub struct RelationshipLoader {
+	batches: Mutex<HashMap<String, Arc<Batch>>>,
+	cache: Mutex<HashMap<(String, String), Vec<JsonValue>>>,
+}
+
+impl RelationshipLoader {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Documents related to the document `id` by the relationship, readable by the session
+	pub async fn load(
+		&self,
+		relationship: &DbRelationship,
+		id: &str,
+		session: &Session,
+	) -> Result<Vec<JsonValue>, ResolverError> {
+		let key = format!("{}.{}", relationship.from.name, relationship.name);
+
+		if let Some(documents) = self
+			.cache
+			.lock()
+			.unwrap()
+			.get(&(key.clone(), id.to_string()))
+		{
+			return Ok(documents.clone());
+		}
+
+		let batch = {
+			let mut batches = self.batches.lock().unwrap();
+
+			let batch = batches.entry(key.clone()).or_insert_with(|| {
+				Arc::new(Batch {
+					ids: Mutex::new(Vec::new()),
+					results: OnceCell::new(),
+				})
+			});
+
+			batch.ids.lock().unwrap().push(id.to_string());
+			batch.clone()
+		};
+
+		let results = batch
+			.results
+			.get_or_init(|| async {
+				// Lets the other lookups of the same tick join the batch before it's sent
+				tokio::task::yield_now().await;
+
+				{
+					let mut batches = self.batches.lock().unwrap();
+
+					if batches.get(&key).map_or(false, |b| Arc::ptr_eq(b, &batch)) {
+						batches.remove(&key);
+					}
+				}
+
+				let ids = std::mem::take(&mut *batch.ids.lock().unwrap());
+
+				execute_batch(relationship, ids, session).await
+			})
+			.await;
+
+		match results {
+			Ok(results) => {
+				let documents = results.get(id).cloned().unwrap_or_default();
+
+				self.cache
+					.lock()
+					.unwrap()
+					.insert((key, id.to_string()), documents.clone());
+
+				Ok(documents)
+			}
+			Err(e) => Err(ResolverError::new(e.clone())),
+		}
+	}
+}
+
+/// Documents of the collections of the relationship must be readable with the permissions of
+/// their entity, and published when it has publication
+fn get_target_filter(
+	relationship: &DbRelationship,
+	session: &Session,
+) -> Result<Option<Box<dyn AQLNode>>, String> {
+	let targets = relationship.get_targets();
+
+	if let Some(entity) = targets.iter().find(|e| !session.can_read(e)) {
+		return Err(format!(
+			"Role {} is not allowed to read {}",
+			session.role, entity.name
+		));
+	}
+
+	if relationship.union.is_none() {
+		let nodes: Vec<Box<dyn AQLNode>> = get_permission_filter(&relationship.to, session)
+			.into_iter()
+			.chain(get_published_filter(&relationship.to))
+			.collect();
+
+		return Ok(match nodes.len() {
+			0 => None,
+			1 => nodes.into_iter().next(),
+			_ => Some(Box::new(AQLLogicalFilter {
+				nodes,
+				operation: AQLLogicalOperator::AND,
+			})),
+		});
+	}
+
+	let nodes: Vec<Box<dyn AQLNode>> = targets
+		.into_iter()
+		.map(|entity| {
+			let mut member_nodes: Vec<Box<dyn AQLNode>> = vec![Box::new(AQLCollectionFilter(
+				entity.collection_name.clone(),
+			))];
+
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				member_nodes.push(permission_filter);
+			}
+
+			if let Some(published_filter) = get_published_filter(entity) {
+				member_nodes.push(published_filter);
+			}
+
+			Box::new(AQLLogicalFilter {
+				nodes: member_nodes,
+				operation: AQLLogicalOperator::AND,
+			}) as Box<dyn AQLNode>
+		})
+		.collect();
+
+	Ok(Some(Box::new(AQLLogicalFilter {
+		nodes,
+		operation: AQLLogicalOperator::OR,
+	})))
+}
+
+/// AQL of a batch with the session variables it uses, the edges are followed from the end of
+/// the parents
+fn get_batch_aql(
+	relationship: &DbRelationship,
+	session: &Session,
+) -> Result<(String, Vec<String>), String> {
+	let edges = match relationship.direction {
+		DbRelationshipDirection::Outbound => {
+			"FOR e IN @@edge FILTER e._from IN @ids LET parent = e._from LET related = e._to"
+		}
+		DbRelationshipDirection::Inbound => {
+			"FOR e IN @@edge FILTER e._to IN @ids LET parent = e._to LET related = e._from"
+		}
+		DbRelationshipDirection::Any => {
+			"FOR e IN @@edge FILTER e._from IN @ids OR e._to IN @ids \
+			FOR parent IN INTERSECTION([e._from, e._to], @ids) \
+			LET related = parent == e._from ? e._to : e._from"
+		}
+	};
+
+	let filter = get_target_filter(relationship, session)?;
+	let session_variables = get_session_variables(filter.as_deref());
+
+	let filter = match filter {
+		Some(filter) => format!(" FILTER {}", filter.describe(1)),
+		None => String::new(),
+	};
+
+	let aql = format!(
+		"{} LET i_1 = DOCUMENT(related) FILTER i_1 != null{} RETURN {{ parent, document: i_1 }}",
+		edges, filter
+	);
+
+	Ok((aql, session_variables))
+}
+
+async fn execute_batch(
+	relationship: &DbRelationship,
+	ids: Vec<String>,
+	session: &Session,
+) -> Result<HashMap<String, Vec<JsonValue>>, String> {
+	let (aql, session_variables) = get_batch_aql(relationship, session)?;
+
+	let mut bind_vars: Vec<(String, JsonValue)> = vec![
+		("@edge".to_string(), relationship.edge.clone().into()),
+		("ids".to_string(), ids.into()),
+	];
+
+	bind_vars.extend(session.get_bind_vars(&session_variables));
+
+	let span = query_span(&aql, &bind_vars);
+	let time = std::time::Instant::now();
+
+	// Documents with many edges are paged through, each batch being grouped by parent before
+	// the next one is fetched
+	let results: Result<HashMap<String, Vec<JsonValue>>, ClientError> = DATABASE
+		.get()
+		.await
+		.run(|database| {
+			let mut query = AqlQuery::builder().query(&aql);
+
+			for (k, v) in &bind_vars {
+				query = query.bind_var(k.clone(), v.clone());
+			}
+
+			async move {
+				let mut results: HashMap<String, Vec<JsonValue>> = HashMap::new();
+
+				fetch_batches(
+					&database,
+					query.batch_size(CONFIG.get_internal_batch_size()).build(),
+					|batch: Vec<RelatedDocument>| {
+						for related in batch {
+							results
+								.entry(related.parent)
+								.or_default()
+								.push(related.document);
+						}
+					},
+				)
+				.await?;
+
+				Ok(results)
+			}
+		})
+		.instrument(span)
+		.await;
+
+	log_slow_query(&aql, &bind_vars, time.elapsed());
+
+	results.map_err(|e| format!("{}", e))
+}
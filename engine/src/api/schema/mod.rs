@@ -1,51 +1,215 @@
+pub mod actions;
+pub mod cache;
+pub mod context;
+pub mod directives;
+pub mod embedded;
 pub mod enums;
 pub mod errors;
 pub mod fields;
+pub mod files;
+pub mod graph;
+pub mod handlers;
+pub mod hooks;
 pub mod input;
+pub mod loader;
 pub mod operations;
+pub mod resolvers;
+pub mod revisions;
 pub mod scalars;
 mod utils;
 
+use crate::api::cluster::{publish, ClusterMessage};
+use crate::api::response_cache::{RESPONSE_CACHE, SCHEMA_TAG};
+use crate::api::schema::actions::build_action_field;
+use crate::api::schema::cache::{QueryCache, ResultCache};
+pub use crate::api::schema::context::Context;
+use crate::api::schema::embedded::{EmbeddedInput, EmbeddedInputData};
 use crate::api::schema::enums::{DbEnumInfo, GraphQLEnum};
 use crate::api::schema::fields::SchemaFieldFactory;
-use crate::api::schema::operations::OperationRegistry;
+use crate::api::schema::graph::{GraphPathStep, TraversalDirection};
+use crate::api::schema::operations::{OperationData, OperationRegistry};
+use crate::api::schema::resolvers::get_field_resolvers;
+use crate::api::schema::revisions::{build_revisions_query, get_revisions_query_name};
+use crate::api::schema::scalars::bigint::BigIntScalar;
+use crate::api::schema::scalars::bytes::BytesScalar;
+use crate::api::schema::scalars::datetime::DateTimeScalar;
+use crate::api::schema::scalars::decimal::DecimalScalar;
+use crate::api::schema::scalars::json::JsonScalar;
+use crate::api::schema::scalars::upload::UploadScalar;
+use crate::api::schema::scalars::uuid::UuidScalar;
+pub use crate::api::schema::scalars::value::AlchemyScalarValue;
+use arc_swap::ArcSwap;
 use juniper::meta::{Argument, MetaType};
 use juniper::{
 	Arguments, BoxFuture, EmptySubscription, ExecutionResult, Executor, FromInputValue,
 	GraphQLType, GraphQLValue, GraphQLValueAsync, InputValue, Registry, RootNode, ScalarValue,
 };
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use crate::lib::database::api::*;
+use crate::lib::database::reads::read_from_leader;
+use crate::lib::schema::{bump_schema_version, get_schema_version, SchemaActionKind};
 
-pub type Schema = RootNode<'static, SchemaType, SchemaType, EmptySubscription>;
+pub type Schema =
+	RootNode<'static, SchemaType, SchemaType, EmptySubscription<Context>, AlchemyScalarValue>;
 
 pub trait AsyncScalarValue = ScalarValue + Send + Sync;
 
+/// Version of the schema the API schema was last built from
+static LOADED_SCHEMA_VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// Version of the schema read before building the API schema on startup
+pub async fn load_schema_version() {
+	LOADED_SCHEMA_VERSION.store(read_schema_version().await, Ordering::Relaxed);
+}
+
+pub fn get_loaded_schema_version() -> u64 {
+	LOADED_SCHEMA_VERSION.load(Ordering::Relaxed)
+}
+
+async fn read_schema_version() -> u64 {
+	get_schema_version().await.unwrap_or_else(|e| {
+		tracing::error!("Error reading the schema version: {}", e);
+
+		get_loaded_schema_version()
+	})
+}
+
+/// Rebuilds the schema from the database, it is swapped atomically so requests being executed
+/// finish with the previous one
+pub async fn reload_schema(current: &ArcSwap<Schema>) {
+	// Read first, so a change made while the schema is built is reloaded again
+	let version = read_schema_version().await;
+	let map = generate_sdl().await;
+
+	current.store(Arc::new(schema(map)));
+	LOADED_SCHEMA_VERSION.store(version, Ordering::Relaxed);
+
+	RESPONSE_CACHE.invalidate(SCHEMA_TAG).await;
+}
+
+/// Reloads the schema once it was changed here, bumping its version so the other instances
+/// sharing the database reload theirs. They are told right away when they share Redis too
+pub async fn apply_schema_change(current: &ArcSwap<Schema>) {
+	let version = match bump_schema_version().await {
+		Ok(version) => version,
+		Err(e) => {
+			tracing::error!("Error bumping the schema version: {}", e);
+
+			return reload_schema(current).await;
+		}
+	};
+
+	reload_schema(current).await;
+
+	publish(ClusterMessage::ReloadSchema { version }).await;
+}
+
+/// Entity of the schema stored in the collection
+pub fn get_collection_entity(schema: &Schema, collection: &str) -> Option<Arc<DbEntity>> {
+	schema
+		.query_info
+		.operation_registry
+		.get_entity_by_collection(collection)
+}
+
+pub fn get_entities(schema: &Schema) -> Vec<Arc<DbEntity>> {
+	schema.query_info.operation_registry.get_entities()
+}
+
+/// Entities with operations on their documents, singletons only being read and updated whole
+pub fn get_document_entities(schema: &Schema) -> Vec<Arc<DbEntity>> {
+	get_entities(schema)
+		.into_iter()
+		.filter(|e| e.singleton.is_none())
+		.collect()
+}
+
+/// Data of the entity of a type, variants share the data of their collection
+pub fn get_type_data(
+	schema: &Schema,
+	type_name: &str,
+) -> Option<Arc<OperationData<AlchemyScalarValue>>> {
+	schema
+		.query_info
+		.operation_registry
+		.get_operation_data(type_name)
+}
+
+/// Entity read by a query operation of the root type
+pub fn get_query_operation_entity(schema: &Schema, name: &str) -> Option<Arc<DbEntity>> {
+	schema
+		.query_info
+		.operation_registry
+		.get_operation(name)
+		.filter(|o| o.kind == SchemaKind::Query)
+		.map(|o| o.data.entity.clone())
+}
+
+/// Entity written by a mutation operation of the root type
+pub fn get_mutation_operation_entity(schema: &Schema, name: &str) -> Option<Arc<DbEntity>> {
+	schema
+		.mutation_info
+		.operation_registry
+		.get_operation(name)
+		.filter(|o| o.kind == SchemaKind::Mutation)
+		.map(|o| o.data.entity.clone())
+}
+
 pub fn schema(map: DbMap) -> Schema {
 	let mut operation_registry = OperationRegistry::new();
+	let all_relationships = Arc::new(map.relationships.clone());
+	// Relationships with collections left out of the API are only followed on delete
+	let relationships: Arc<Vec<DbRelationship>> = Arc::new(
+		map.relationships
+			.iter()
+			.filter(|r| r.is_exposed())
+			.cloned()
+			.collect(),
+	);
+
+	for entry in get_field_resolvers() {
+		operation_registry.register_resolver(entry);
+	}
 
 	for p in map.primitives {
 		match p {
 			DbPrimitive::Entity(t) => {
 				let mut relationships = Vec::new();
 
-				for relationship in &map.relationships {
+				for relationship in relationships.iter() {
 					if relationship.from.name == t.name {
 						relationships.push(relationship.clone())
 					}
 				}
 
-				operation_registry.register_entity(t, relationships);
+				let graphs = map
+					.graphs
+					.iter()
+					.filter(|g| g.has_entity(&t.name))
+					.cloned()
+					.collect();
+
+				operation_registry.register_entity(
+					t,
+					relationships,
+					all_relationships.clone(),
+					graphs,
+				);
 			}
+			// Enum types are registered by the fields, filters and inputs using them
 			DbPrimitive::Enum(_) => {}
 		}
 	}
 
-	let relationships = Arc::new(map.relationships.clone());
 	let query_info = SchemaData {
 		operation_registry: Arc::new(operation_registry),
 		relationships,
+		graphs: Arc::new(map.graphs.clone()),
+		actions: Arc::new(map.actions.clone()),
+		query_cache: Arc::new(QueryCache::default()),
+		result_cache: Arc::new(ResultCache::default()),
 		kind: SchemaKind::Query,
 	};
 
@@ -57,7 +221,7 @@ pub fn schema(map: DbMap) -> Schema {
 	RootNode::new_with_info(
 		SchemaType,
 		SchemaType,
-		EmptySubscription::new(),
+		EmptySubscription::<Context>::new(),
 		query_info,
 		mutation_info,
 		(),
@@ -78,6 +242,42 @@ where
 	kind: SchemaKind,
 	operation_registry: Arc<OperationRegistry<S>>,
 	relationships: Arc<Vec<DbRelationship>>,
+	graphs: Arc<Vec<DbGraph>>,
+	actions: Arc<Vec<Arc<DbAction>>>,
+	query_cache: Arc<QueryCache>,
+	result_cache: Arc<ResultCache<S>>,
+}
+
+impl<S> SchemaData<S>
+where
+	S: AsyncScalarValue,
+{
+	/// Action of the root type, operations of the entities take precedence on name conflicts
+	pub fn get_action(&self, name: &str) -> Option<&DbAction> {
+		let kind = match self.kind {
+			SchemaKind::Query => SchemaActionKind::Query,
+			SchemaKind::Mutation => SchemaActionKind::Mutation,
+		};
+
+		self.actions
+			.iter()
+			.find(|a| a.kind == kind && a.output.name == name)
+			.filter(|_| self.operation_registry.get_operation(name).is_none())
+			.map(|a| a.as_ref())
+	}
+
+	/// Versioned entity listing its revisions by key under the name, operations taking precedence
+	pub fn get_revisions_entity(&self, name: &str) -> Option<Arc<DbEntity>> {
+		if self.kind != SchemaKind::Query || self.operation_registry.get_operation(name).is_some() {
+			return None;
+		}
+
+		self.operation_registry
+			.get_entities()
+			.into_iter()
+			.filter(|e| e.versioning.is_some() && e.singleton.is_none())
+			.find(|e| get_revisions_query_name(e) == name)
+	}
 }
 
 pub struct SchemaType;
@@ -108,6 +308,32 @@ where
 			));
 		}
 
+		for entity in info.operation_registry.get_entities() {
+			if info
+				.get_revisions_entity(&get_revisions_query_name(&entity))
+				.is_some()
+			{
+				queries.push(build_revisions_query(registry, &entity));
+			}
+		}
+
+		if info.kind == SchemaKind::Query && !info.graphs.is_empty() {
+			queries.push(
+				registry
+					.field::<Vec<GraphPathStep>>("shortestPath", &())
+					.argument(registry.arg::<String>("graph", &()))
+					.argument(registry.arg::<String>("from", &()))
+					.argument(registry.arg::<String>("to", &()))
+					.argument(registry.arg::<Option<TraversalDirection>>("direction", &())),
+			);
+		}
+
+		for action in info.actions.iter() {
+			if info.get_action(&action.output.name).is_some() {
+				queries.push(build_action_field(registry, action));
+			}
+		}
+
 		registry
 			.build_object_type::<SchemaType>(info, &queries)
 			.into_meta()
@@ -118,7 +344,7 @@ impl<S> GraphQLValue<S> for SchemaType
 where
 	S: AsyncScalarValue,
 {
-	type Context = ();
+	type Context = Context;
 	type TypeInfo = SchemaData<S>;
 
 	fn type_name<'i>(&self, info: &'i Self::TypeInfo) -> Option<&'i str> {
@@ -137,6 +363,11 @@ where
 		arguments: &'b Arguments<S>,
 		executor: &'b Executor<Self::Context, S>,
 	) -> BoxFuture<'b, ExecutionResult<S>> {
+		// Replicas may not have the changes of a mutation yet, including those it returns
+		if info.kind == SchemaKind::Mutation {
+			read_from_leader();
+		}
+
 		Box::pin(async move {
 			executor
 				.resolve_async(
@@ -155,6 +386,31 @@ where
 	serde_json::to_string(data).unwrap()
 }
 
+/// Argument of a property in update inputs, it's always optional. Sub-documents are merged on
+/// update so their fields are optional too
+pub fn build_set_argument_from_property<'r, S>(
+	registry: &mut Registry<'r, S>,
+	property: &DbProperty,
+) -> Argument<'r, S>
+where
+	S: ScalarValue,
+{
+	match &property.scalar_type {
+		DbScalarType::Embedded(embedded) => {
+			let argument = registry.arg::<Option<EmbeddedInput>>(
+				property.name.as_str(),
+				&EmbeddedInputData::partial(embedded),
+			);
+
+			match &property.description {
+				Some(description) => argument.description(description),
+				None => argument,
+			}
+		}
+		scalar_type => build_argument_from_property(registry, property, scalar_type, false),
+	}
+}
+
 pub fn build_argument_from_property<'r, S>(
 	registry: &mut Registry<'r, S>,
 	property: &DbProperty,
@@ -174,16 +430,22 @@ where
 		S: ScalarValue + 'r,
 		T: GraphQLType<S, Context = ()> + FromInputValue<S>,
 	{
-		if required {
+		let argument = if required {
 			registry.arg::<T>(property.name.as_str(), info)
 		} else {
 			registry.arg::<Option<T>>(property.name.as_str(), info)
+		};
+
+		match &property.description {
+			Some(description) => argument.description(description),
+			None => argument,
 		}
 	}
 
 	match scalar_type {
+		// The items of lists are never null, only the list itself is optional
 		DbScalarType::Array(t) => {
-			let mut argument = build_argument_from_property(registry, property, &t, required);
+			let mut argument = build_argument_from_property(registry, property, &t, true);
 
 			if required {
 				argument.arg_type = juniper::Type::NonNullList(Box::new(argument.arg_type));
@@ -201,12 +463,33 @@ where
 			&DbEnumInfo {
 				name: property.associated_type.clone().unwrap(),
 				properties: values.clone(),
+				descriptions: property.enum_descriptions.clone(),
 			},
 		),
+		DbScalarType::Embedded(embedded) => build_argument::<EmbeddedInput, S>(
+			registry,
+			property,
+			required,
+			&EmbeddedInputData::new(embedded),
+		),
 		DbScalarType::String => build_argument::<String, S>(registry, property, required, &()),
-		DbScalarType::Object => build_argument::<String, S>(registry, property, required, &()),
+		DbScalarType::Object => build_argument::<JsonScalar, S>(registry, property, required, &()),
 		DbScalarType::Float => build_argument::<f64, S>(registry, property, required, &()),
 		DbScalarType::Int => build_argument::<i32, S>(registry, property, required, &()),
+		DbScalarType::BigInt => {
+			build_argument::<BigIntScalar, S>(registry, property, required, &())
+		}
 		DbScalarType::Boolean => build_argument::<bool, S>(registry, property, required, &()),
+		DbScalarType::Bytes => build_argument::<BytesScalar, S>(registry, property, required, &()),
+		DbScalarType::Decimal => {
+			build_argument::<DecimalScalar, S>(registry, property, required, &())
+		}
+		DbScalarType::DateTime => {
+			build_argument::<DateTimeScalar, S>(registry, property, required, &())
+		}
+		DbScalarType::Uuid => build_argument::<UuidScalar, S>(registry, property, required, &()),
+		DbScalarType::File(_) => {
+			build_argument::<UploadScalar, S>(registry, property, required, &())
+		}
 	}
 }
@@ -1,131 +1,320 @@
 use std::collections::HashMap;
 
 use convert_case::Casing;
-use juniper::InputValue;
+use juniper::{FieldError, InputValue, IntoFieldError};
+use serde_json::{Map as JsonMap, Value as JsonValue};
 
+use crate::api::auth::permissions::apply_presets;
+use crate::api::auth::Session;
+use crate::api::schema::enums::map_enum_input_values;
+use crate::api::schema::errors::ConstraintError;
 use crate::api::schema::fields::Entity;
+use crate::api::schema::files::store_uploads;
+use crate::api::schema::hooks::{has_after_hooks, run_after_hooks, run_before_hooks};
 use crate::api::schema::input::insert::{EntityInsert, EntityInsertData, EntityInsertRelationship};
 use crate::api::schema::operations::{
-	execute_internal_query, execute_query, get_filter_by_indices_attributes, get_filter_by_key,
-	OperationData, QueryReturnType,
+	apply_locale, execute_internal_query, execute_query, get_changed_key,
+	get_filter_by_indices_attributes, get_filter_by_key, OperationData, QueryReturnType,
 };
 use crate::api::schema::{input_value_to_string, AsyncScalarValue};
-use crate::lib::database::api::DbRelationship;
+use crate::lib::database::api::{DbEntity, DbRelationship};
 use crate::lib::database::aql::{AQLProperty, AQLQuery, AQLQueryMethod};
+use crate::lib::events::EventOperation;
+use crate::lib::naming::get_operation_name;
+use crate::lib::publication::{DRAFT_STATUS, STATUS_FIELD};
 
-fn get_relationship_data(relationships: &Vec<DbRelationship>, name: String) -> (&str, &str, &str) {
-	let mut edge = "";
-	let mut from_collection = "";
-	let mut to_collection = "";
+fn get_relationship<'a>(relationships: &'a Vec<DbRelationship>, name: &str) -> &'a DbRelationship {
+	relationships
+		.iter()
+		.find(|r| r.name == name)
+		.expect("Relationship not found")
+}
 
-	for relationship in relationships {
-		if relationship.name == name {
-			edge = &relationship.edge;
-			from_collection = &relationship.from.collection_name;
-			to_collection = &relationship.to.collection_name;
+/// Fills in omitted generated values of a document, also setting `_key` from the property
+/// used as key. Documents of entities with publication are drafts unless given a status
+pub fn generate_values(object: &mut JsonMap<String, JsonValue>, entity: &DbEntity) {
+	if entity.publication && object.get(STATUS_FIELD).map_or(true, |s| s.is_null()) {
+		object.insert(
+			STATUS_FIELD.to_string(),
+			JsonValue::String(DRAFT_STATUS.to_string()),
+		);
+	}
+
+	for property in entity.properties.iter().filter(|p| p.generator.is_some()) {
+		let value = match object.get(&property.name) {
+			Some(value) if !value.is_null() => value.clone(),
+			_ => {
+				let value = JsonValue::String(property.generator.as_ref().unwrap().generate());
+
+				object.insert(property.name.clone(), value.clone());
+
+				value
+			}
+		};
+
+		if property.is_key {
+			object.insert("_key".to_string(), value);
 		}
 	}
+}
+
+pub fn apply_generated_values(attributes: String, entity: &DbEntity) -> String {
+	if !entity.publication && !entity.properties.iter().any(|p| p.generator.is_some()) {
+		return attributes;
+	}
+
+	let mut document: JsonValue = match serde_json::from_str(&attributes) {
+		Ok(document) => document,
+		Err(_) => return attributes,
+	};
+
+	if let Some(object) = document.as_object_mut() {
+		generate_values(object, entity);
+	}
 
-	(edge, from_collection, to_collection)
+	document.to_string()
 }
 
+/// Sets when the new document expires, for entities with a TTL
+fn apply_ttl(attributes: String, entity: &DbEntity) -> String {
+	match &entity.ttl {
+		Some(ttl) => format!(
+			"MERGE({}, {{{}: DATE_ISO8601(DATE_NOW() + {})}})",
+			attributes,
+			serde_json::to_string(&ttl.field).unwrap(),
+			ttl.expire_after * 1000
+		),
+		None => attributes,
+	}
+}
+
+fn get_create_relationship_method(
+	relationship: &DbRelationship,
+	inner: Option<Box<AQLQuery>>,
+) -> AQLQueryMethod {
+	AQLQueryMethod::CreateRelationship(
+		inner,
+		relationship.direction.clone(),
+		relationship.relationship_type.clone(),
+		relationship
+			.get_targets()
+			.iter()
+			.map(|e| e.collection_name.clone())
+			.collect(),
+	)
+}
+
+/// Inserts an edge to an existing document of `to_entity`, found by its indices
+async fn insert_existing_relationship<S>(
+	relationship: &DbRelationship,
+	to_entity: &DbEntity,
+	attributes: HashMap<String, InputValue<S>>,
+	edge_attributes: Option<String>,
+	key: &str,
+	session: &Session,
+) -> Result<(), FieldError<S>>
+where
+	S: AsyncScalarValue,
+{
+	let mut query = AQLQuery::new(0);
+
+	query.filter = Some(get_filter_by_indices_attributes(&attributes));
+	query.properties = vec![AQLProperty::new("_id".to_string())];
+	query.limit = Some(1);
+
+	let mut insert_query = AQLQuery::new(0);
+
+	insert_query.method = get_create_relationship_method(relationship, Some(Box::new(query)));
+
+	if let Some(edge_attributes) = edge_attributes {
+		insert_query.creates = edge_attributes;
+	}
+
+	let mut attrs = HashMap::new();
+
+	// Add edge and from attributes
+	attrs.insert(
+		"@inner_collection".to_string(),
+		InputValue::scalar(to_entity.collection_name.clone()),
+	);
+	attrs.insert(
+		"__from".to_string(),
+		InputValue::scalar(format!("{}/{}", relationship.from.collection_name, key)),
+	);
+
+	let edge_data =
+		execute_internal_query::<S>(insert_query, &relationship.edge, attributes, attrs, session)
+			.await?;
+
+	check_cardinality(edge_data, relationship)
+}
+
+/// Inserts the edges of the relationships, failing on those breaking their cardinality
 async fn insert_relationships<S>(
 	relationships: Vec<EntityInsertRelationship<S>>,
 	key: &str,
 	data: &OperationData<S>,
-) where
+	session: &Session,
+) -> Result<(), FieldError<S>>
+where
 	S: AsyncScalarValue,
 {
 	for relationship in relationships {
 		match relationship {
-			EntityInsertRelationship::Existing(k, attributes) => {
-				let mut query = AQLQuery::new(0);
-
-				query.filter = Some(get_filter_by_indices_attributes(&attributes));
-				query.properties = vec![AQLProperty {
-					name: "_id".to_string(),
-				}];
-				query.limit = Some(1);
-
-				let (edge, from_collection, to_collection) =
-					get_relationship_data(&data.relationships, k);
+			EntityInsertRelationship::Existing(k, attributes, edge_attributes) => {
+				let relationship = get_relationship(&data.relationships, &k);
 
-				let mut insert_query = AQLQuery::new(0);
-
-				insert_query.method = AQLQueryMethod::CreateRelationship(Some(Box::new(query)));
+				insert_existing_relationship(
+					relationship,
+					&relationship.to,
+					attributes,
+					edge_attributes,
+					key,
+					session,
+				)
+				.await?;
+			}
+			EntityInsertRelationship::Target(k, target, attributes, edge_attributes) => {
+				let relationship = get_relationship(&data.relationships, &k);
 
-				let mut attrs = HashMap::new();
+				let to_entity = relationship
+					.get_targets()
+					.into_iter()
+					.find(|e| e.name.to_case(convert_case::Case::Snake) == target)
+					.expect("Relationship target not found");
 
-				// Add edge and from attributes
-				attrs.insert(
-					"@inner_collection".to_string(),
-					InputValue::scalar(to_collection.to_string()),
-				);
-				attrs.insert(
-					"__from".to_string(),
-					InputValue::scalar(format!("{}/{}", from_collection, key)),
-				);
-
-				execute_internal_query::<S>(insert_query, edge, attributes, attrs).await;
+				insert_existing_relationship(
+					relationship,
+					to_entity,
+					attributes,
+					edge_attributes,
+					key,
+					session,
+				)
+				.await?;
 			}
-			EntityInsertRelationship::New(k, new_attributes) => {
-				let (edge, from_collection, to_collection) =
-					get_relationship_data(&data.relationships, k);
+			EntityInsertRelationship::New(k, new_attributes, edge_attributes) => {
+				let relationship = get_relationship(&data.relationships, &k);
+				let to_entity = &relationship.to;
 
 				let mut create_instance_query = AQLQuery::new(0);
 
 				create_instance_query.method = AQLQueryMethod::Create;
 				create_instance_query.creates = input_value_to_string(&new_attributes);
 
+				create_instance_query.creates = apply_ttl(
+					apply_presets(
+						apply_generated_values(
+							map_enum_input_values(create_instance_query.creates, to_entity),
+							to_entity,
+						),
+						to_entity,
+						session,
+					),
+					to_entity,
+				);
+
 				let instance_data = execute_internal_query::<S>(
 					create_instance_query,
-					to_collection,
+					&to_entity.collection_name,
 					HashMap::new(),
 					HashMap::new(),
+					session,
 				)
-				.await;
-				let inserted_key = instance_data[0]["_key"].as_str().unwrap().to_string();
+				.await?;
+				let inserted_key = get_changed_key(&instance_data, &to_entity.name)?;
 
 				let mut insert_query = AQLQuery::new(0);
 
-				insert_query.method = AQLQueryMethod::CreateRelationship(None);
+				insert_query.method = get_create_relationship_method(relationship, None);
+
+				if let Some(edge_attributes) = edge_attributes {
+					insert_query.creates = edge_attributes;
+				}
 
 				let mut attrs = HashMap::new();
 
 				// Add edge and from attributes
 				attrs.insert(
 					"__from".to_string(),
-					InputValue::scalar(format!("{}/{}", from_collection, key)),
+					InputValue::scalar(format!("{}/{}", relationship.from.collection_name, key)),
 				);
 				attrs.insert(
 					"__to".to_string(),
-					InputValue::scalar(format!("{}/{}", to_collection, inserted_key)),
+					InputValue::scalar(format!("{}/{}", to_entity.collection_name, inserted_key)),
 				);
 
-				execute_internal_query::<S>(insert_query, edge, HashMap::new(), attrs).await;
+				let edge_data = execute_internal_query::<S>(
+					insert_query,
+					&relationship.edge,
+					HashMap::new(),
+					attrs,
+					session,
+				)
+				.await?;
+
+				check_cardinality(edge_data, relationship)?;
 			}
 		}
 	}
+
+	Ok(())
+}
+
+/// No edge is returned when inserting it would break the cardinality of the relationship
+fn check_cardinality<S>(
+	edge_data: Vec<JsonValue>,
+	relationship: &DbRelationship,
+) -> Result<(), FieldError<S>>
+where
+	S: AsyncScalarValue,
+{
+	if edge_data.is_empty() {
+		return Err(ConstraintError::new(
+			format!(
+				"Relationship {} of {} is {:?}, the related document is already taken",
+				relationship.name, relationship.from.name, relationship.relationship_type
+			),
+			Some(relationship.name.clone()),
+		)
+		.into_field_error());
+	}
+
+	Ok(())
 }
 
 crate::api::schema::operations::utils::define_operation!(
 	Create {
-		on_call(data, arguments, query) -> {
+		on_call(data, arguments, query, session) -> {
 			let entity = &data.entity;
 			let collection = &entity.collection_name;
 
 			let mut insert_query = AQLQuery::new(0);
 			let object = arguments.get::<EntityInsert<S>>("object").unwrap();
+			let attributes = apply_generated_values(
+				apply_locale(map_enum_input_values(object.attributes, entity), entity, arguments),
+				entity,
+			);
 
 			insert_query.method = AQLQueryMethod::Create;
-			insert_query.creates = object.attributes;
+			insert_query.returns_changes = has_after_hooks(entity, EventOperation::Create);
 
 			Box::pin(async move {
-				let create_data = execute_internal_query::<S>(insert_query, collection, HashMap::new(), HashMap::new()).await;
-				let inserted_key = create_data[0]["_key"].as_str().unwrap().to_string();
+				let attributes = run_before_hooks::<S>(entity, EventOperation::Create, Some(attributes), None, session)
+					.await?
+					.unwrap_or_default();
+				let attributes = store_uploads::<S>(entity, attributes, None).await?;
+
+				insert_query.creates = apply_ttl(apply_presets(attributes, entity, session), entity);
+
+				let create_data = execute_internal_query::<S>(insert_query, collection, HashMap::new(), HashMap::new(), session).await?;
+
+				run_after_hooks(entity, EventOperation::Create, &create_data, session).await;
+
+				let inserted_key = get_changed_key(&create_data, &entity.name)?;
 
 				if !object.relationships.is_empty() {
-					insert_relationships(object.relationships, &inserted_key, data).await;
+					insert_relationships(object.relationships, &inserted_key, data, session).await?;
 				}
 
 				query.filter = Some(get_filter_by_key());
@@ -140,22 +329,13 @@ crate::api::schema::operations::utils::define_operation!(
 					collection,
 					QueryReturnType::Single,
 					args,
-					HashMap::<String, String>::new()
+					HashMap::<String, String>::new(),
+					session
 				).await
 			})
 		},
 		name(data) -> {
-			format!(
-				"create{}",
-				pluralizer::pluralize(
-					data.entity
-						.name
-						.to_case(convert_case::Case::Pascal)
-						.as_str(),
-					1,
-					false,
-				)
-			)
+			get_operation_name("create", &data.entity.name, false)
 		},
 		arguments(data, registry) {
 			object EntityInsert<S> => &EntityInsertData::new(data, registry)
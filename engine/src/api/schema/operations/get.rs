@@ -1,16 +1,20 @@
 use std::collections::HashMap;
 
-use convert_case::Casing;
+use juniper::IntoFieldError;
 
+use crate::api::auth::permissions::get_permission_filter;
 use crate::api::schema::fields::Entity;
 use crate::api::schema::input::filter::{EntityIndicesFilter, EntityIndicesFilterData};
+use crate::api::schema::operations::publish::get_publication_filter;
 use crate::api::schema::operations::{
 	execute_query, get_filter_by_indices_attributes, QueryReturnType,
 };
+use crate::lib::naming::get_operation_name;
+use crate::lib::CONFIG;
 
 crate::api::schema::operations::utils::define_operation!(
 	Get {
-		on_call(data, args, query) -> {
+		on_call(data, args, query, session) -> {
 			let entity = &data.entity;
 			let collection = &entity.collection_name;
 
@@ -19,29 +23,30 @@ crate::api::schema::operations::utils::define_operation!(
 			query.filter = Some(get_filter_by_indices_attributes(&indices_filter));
 			query.limit = Some(1);
 
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				query.add_filter(permission_filter);
+			}
+
+			let publication_filter = get_publication_filter(entity, args, session);
+
 			Box::pin(async move {
+				if let Some(publication_filter) = publication_filter.map_err(|e| e.into_field_error())? {
+					query.add_filter(publication_filter);
+				}
+
 				execute_query(
 					query,
 					entity,
 					collection,
 					QueryReturnType::Single,
 					indices_filter,
-					HashMap::<String, String>::new()
+					HashMap::<String, String>::new(),
+					session
 				).await
 			})
 		},
 		name(data) -> {
-			format!(
-				"get{}",
-				pluralizer::pluralize(
-					data.entity
-						.name
-						.to_case(convert_case::Case::Pascal)
-						.as_str(),
-					1,
-					false,
-				)
-			)
+			get_operation_name(CONFIG.get_query_verb(), &data.entity.name, false)
 		},
 		arguments(data, _registry) {
 			where EntityIndicesFilter<S> => &EntityIndicesFilterData::<S>::new(data)
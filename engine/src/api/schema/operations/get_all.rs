@@ -1,14 +1,20 @@
 use std::collections::HashMap;
 
-use convert_case::Casing;
+use juniper::IntoFieldError;
 
+use crate::api::auth::permissions::get_permission_filter;
 use crate::api::schema::fields::Entity;
-use crate::api::schema::input::filter::{get_aql_filter_from_args, EntityFilter, EntityFilterData};
+use crate::api::schema::input::filter::{
+	check_filter_args_access, get_aql_filter_from_args, EntityFilter, EntityFilterData,
+};
+use crate::api::schema::operations::publish::get_publication_filter;
 use crate::api::schema::operations::{execute_query, QueryReturnType};
+use crate::lib::naming::get_operation_name;
+use crate::lib::CONFIG;
 
 crate::api::schema::operations::utils::define_operation!(
 	GetAll {
-		on_call(data, args, query) -> {
+		on_call(data, args, query, session) -> {
 			let time = std::time::Instant::now();
 
 			let entity = &data.entity;
@@ -17,31 +23,35 @@ crate::api::schema::operations::utils::define_operation!(
 			query.limit = args.get::<i32>("limit");
 			query.filter = get_aql_filter_from_args(args, data);
 
-			println!("Query AQL Filter generation: {:?}", time.elapsed());
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				query.add_filter(permission_filter);
+			}
+
+			let publication_filter = get_publication_filter(entity, args, session);
+			let filter_access = check_filter_args_access(args, entity, session);
+
+			tracing::debug!(elapsed = ?time.elapsed(), "Generated the query filter");
 
 			Box::pin(async move {
+				filter_access?;
+
+				if let Some(publication_filter) = publication_filter.map_err(|e| e.into_field_error())? {
+					query.add_filter(publication_filter);
+				}
+
 				execute_query(
 					query,
 					entity,
 					collection,
 					QueryReturnType::Multiple,
 					HashMap::new(),
-					HashMap::<String, String>::new()
+					HashMap::<String, String>::new(),
+					session
 				).await
 			})
 		},
 		name(data) -> {
-			format!(
-				"get{}",
-				pluralizer::pluralize(
-					data.entity
-						.name
-						.to_case(convert_case::Case::Pascal)
-						.as_str(),
-					2,
-					false,
-				)
-			)
+			get_operation_name(CONFIG.get_query_verb(), &data.entity.name, true)
 		},
 		arguments(data, _registry) {
 			limit Option<i32> => &()
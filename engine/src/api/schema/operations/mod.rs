@@ -1,38 +1,58 @@
 use convert_case::Casing;
 use juniper::meta::{Argument, Field};
 use juniper::{
-	Arguments, BoxFuture, ExecutionResult, InputValue, IntoFieldError, Registry, ScalarValue, Value,
+	Arguments, BoxFuture, ExecutionResult, FieldError, InputValue, IntoFieldError, Registry,
+	ScalarValue, Value,
 };
-use rust_arango::{AqlQuery, ClientError};
+use rust_arango::{AqlOptions, AqlQuery, ClientError, Database as ArangoDatabase};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::sync::Arc;
-
-use crate::api::schema::errors::{DatabaseError, NotFoundError};
+use tracing::{Instrument, Span};
+
+use crate::api::auth::Session;
+use crate::api::explain::capture_query;
+use crate::api::response_cache::RESPONSE_CACHE;
+use crate::api::schema::cache::invalidate_results;
+use crate::api::schema::errors::{DatabaseError, NotFoundError, TimeoutError};
+use crate::api::schema::fields::LOCALE_ARGUMENT;
 use crate::api::schema::operations::create::Create;
 use crate::api::schema::operations::get::Get;
 use crate::api::schema::operations::get_all::GetAll;
+use crate::api::schema::operations::publish::{Publish, Unpublish};
 use crate::api::schema::operations::remove::Remove;
 use crate::api::schema::operations::remove_all::RemoveAll;
+use crate::api::schema::operations::revert::Revert;
+use crate::api::schema::operations::singleton::{Singleton, UpdateSingleton};
 use crate::api::schema::operations::update::Update;
 use crate::api::schema::operations::update_all::UpdateAll;
-use crate::api::schema::utils::convert_json_to_juniper_value;
+use crate::api::schema::resolvers::{FieldResolver, FieldResolverEntry};
+use crate::api::schema::utils::JuniperValue;
 use crate::api::schema::{AsyncScalarValue, SchemaKind};
-use crate::lib::database::api::{DbEntity, DbRelationship};
+use crate::lib::audit::record_mutations;
+use crate::lib::database::api::{DbEntity, DbGraph, DbRelationship};
 use crate::lib::database::aql::{
 	AQLFilterOperation, AQLLogicalFilter, AQLLogicalOperator, AQLNode, AQLOperation, AQLQuery,
-	AQLQueryBind, AQLQueryParameter,
+	AQLQueryBind, AQLQueryMethod, AQLQueryParameter,
 };
-use crate::lib::database::DATABASE;
+use crate::lib::database::{fetch_batches, DATABASE};
+use crate::lib::events::{enqueue_events, has_webhooks, EventOperation};
+use crate::lib::locales::localize_values;
+use crate::lib::logging::{log_slow_query, query_span};
+use crate::lib::revisions::record_revisions;
+use crate::lib::CONFIG;
 
 pub mod utils;
 
 pub mod create;
 pub mod get;
 pub mod get_all;
+pub mod publish;
 pub mod remove;
 pub mod remove_all;
+pub mod revert;
+pub mod singleton;
 pub mod update;
 pub mod update_all;
 
@@ -44,13 +64,20 @@ where
 {
 	operation_data: HashMap<String, Arc<OperationData<S>>>,
 	operations: HashMap<String, OperationEntry<S>>,
+	/// Custom field resolvers by entity and field name
+	resolvers: HashMap<String, HashMap<String, Arc<dyn FieldResolver>>>,
 }
 
 pub struct OperationEntry<S>
 where
 	S: AsyncScalarValue,
 {
-	pub closure: for<'a> fn(&'a OperationData<S>, &'a Arguments<S>, AQLQuery) -> FutureType<'a, S>,
+	pub closure: for<'a> fn(
+		&'a OperationData<S>,
+		&'a Arguments<S>,
+		AQLQuery,
+		&'a Session,
+	) -> FutureType<'a, S>,
 	pub arguments_closure: for<'a> fn(
 		&mut Registry<'a, S>,
 		data: &OperationData<S>,
@@ -75,6 +102,7 @@ where
 		OperationRegistry {
 			operation_data: HashMap::new(),
 			operations: HashMap::new(),
+			resolvers: HashMap::new(),
 		}
 	}
 
@@ -83,10 +111,11 @@ where
 		key: &str,
 		arguments: &'b Arguments<S>,
 		query: AQLQuery,
+		session: &'b Session,
 	) -> Option<FutureType<'b, S>> {
 		self.operations
 			.get(key)
-			.map(|o| (o.closure)(&o.data, arguments, query))
+			.map(|o| (o.closure)(&o.data, arguments, query, session))
 	}
 
 	pub fn get_operations(&self, kind: SchemaKind) -> HashMap<&String, &OperationEntry<S>> {
@@ -104,10 +133,58 @@ where
 		self.operation_data.get(key).map(|e| e.clone())
 	}
 
-	pub fn register_entity(&mut self, entity: Arc<DbEntity>, relationships: Vec<DbRelationship>) {
+	/// Entity stored in the collection, variants share the data of their collection
+	pub fn get_entity_by_collection(&self, collection: &str) -> Option<Arc<DbEntity>> {
+		self.operation_data
+			.values()
+			.find(|d| d.entity.collection_name == collection)
+			.map(|d| d.entity.clone())
+	}
+
+	/// Entities of the collections, sorted by name
+	pub fn get_entities(&self) -> Vec<Arc<DbEntity>> {
+		let mut entities: Vec<Arc<DbEntity>> = self
+			.operation_data
+			.iter()
+			.filter(|(name, d)| **name == d.entity.name)
+			.map(|(_, d)| d.entity.clone())
+			.collect();
+
+		entities.sort_by(|a, b| a.name.cmp(&b.name));
+
+		entities
+	}
+
+	pub fn get_resolvers(&self, entity: &str) -> Option<&HashMap<String, Arc<dyn FieldResolver>>> {
+		self.resolvers.get(entity)
+	}
+
+	pub fn get_resolver(&self, entity: &str, field: &str) -> Option<Arc<dyn FieldResolver>> {
+		self.resolvers
+			.get(entity)
+			.and_then(|r| r.get(field))
+			.cloned()
+	}
+
+	pub fn register_resolver(&mut self, entry: FieldResolverEntry) {
+		self.resolvers
+			.entry(entry.entity)
+			.or_default()
+			.insert(entry.field, entry.resolver);
+	}
+
+	pub fn register_entity(
+		&mut self,
+		entity: Arc<DbEntity>,
+		relationships: Vec<DbRelationship>,
+		all_relationships: Arc<Vec<DbRelationship>>,
+		graphs: Vec<DbGraph>,
+	) {
 		let data = Arc::new(OperationData {
 			entity: entity.clone(),
 			relationships,
+			all_relationships,
+			graphs,
 
 			_phantom: Default::default(),
 		});
@@ -115,6 +192,20 @@ where
 		self.operation_data
 			.insert(entity.name.clone(), data.clone());
 
+		// Variant types are resolved with the data of their collection
+		for variant in &entity.variants {
+			self.operation_data
+				.insert(variant.name.clone(), data.clone());
+		}
+
+		// Singletons are read and updated as a whole, their document is created by the first update
+		if entity.singleton.is_some() {
+			self.register::<Singleton>(data.clone(), SchemaKind::Query);
+			self.register::<UpdateSingleton>(data, SchemaKind::Mutation);
+
+			return;
+		}
+
 		vec![
 			self.register::<Get>(data.clone(), SchemaKind::Query),
 			self.register::<GetAll>(data.clone(), SchemaKind::Query),
@@ -124,6 +215,15 @@ where
 			self.register::<RemoveAll>(data.clone(), SchemaKind::Mutation),
 			self.register::<Create>(data.clone(), SchemaKind::Mutation),
 		];
+
+		if entity.versioning.is_some() {
+			self.register::<Revert>(data.clone(), SchemaKind::Mutation);
+		}
+
+		if entity.publication {
+			self.register::<Publish>(data.clone(), SchemaKind::Mutation);
+			self.register::<Unpublish>(data, SchemaKind::Mutation);
+		}
 	}
 
 	fn register<T: 'static>(&mut self, data: Arc<OperationData<S>>, kind: SchemaKind) -> String
@@ -153,6 +253,10 @@ where
 {
 	pub entity: Arc<DbEntity>,
 	pub relationships: Vec<DbRelationship>,
+	/// Relationships of every entity, followed when removing documents
+	pub all_relationships: Arc<Vec<DbRelationship>>,
+	/// Named graphs with the entity collection as a vertex collection
+	pub graphs: Vec<DbGraph>,
 
 	_phantom: PhantomData<S>,
 }
@@ -166,6 +270,7 @@ where
 		data: &'b OperationData<S>,
 		arguments: &'b Arguments<S>,
 		query: AQLQuery,
+		session: &'b Session,
 	) -> FutureType<'b, S>;
 
 	fn get_operation_name(data: &OperationData<S>) -> String;
@@ -239,7 +344,7 @@ fn get_filter_in_keys() -> Box<dyn AQLNode> {
 }
 
 fn get_single_entry<S>(
-	entries: Result<Vec<JsonValue>, ClientError>,
+	entries: Result<Vec<JuniperValue<S>>, ClientError>,
 	entity_name: String,
 ) -> ExecutionResult<S>
 where
@@ -249,50 +354,61 @@ where
 
 	return match entries {
 		Ok(data) => {
-			if let Some(first) = data.first() {
-				let time = std::time::Instant::now();
-
-				let ret = Ok(convert_json_to_juniper_value(first.as_object().unwrap()));
-
-				println!("Conversion: {:?}", time.elapsed());
-
-				return ret;
+			if let Some(JuniperValue(first)) = data.into_iter().next() {
+				return Ok(first);
 			}
 
 			Err(not_found_error)
 		}
-		Err(e) => {
-			let message = format!("{}", e);
-
-			Err(DatabaseError::new(message).into_field_error())
-		}
+		Err(e) => Err(DatabaseError::from(e).into_field_error()),
 	};
 }
 
-fn get_multiple_entries<S>(entries: Result<Vec<JsonValue>, ClientError>) -> ExecutionResult<S>
+/// Key of the first document changed by an internal query, the entity isn't found when the
+/// query changed none
+fn get_changed_key<S>(entries: &[JsonValue], entity_name: &str) -> Result<String, FieldError<S>>
 where
 	S: AsyncScalarValue,
 {
-	return match entries {
-		Ok(data) => {
-			let mut output = Vec::<Value<S>>::new();
+	entries
+		.first()
+		.and_then(|e| e["_key"].as_str())
+		.map(String::from)
+		.ok_or_else(|| NotFoundError::new(entity_name.to_string()).into_field_error())
+}
 
-			let time = std::time::Instant::now();
+/// Collects the documents of a cursor batch by batch, fetching the next batch once the
+/// previous one is deserialized so the JSON of only one batch is held at once
+async fn get_multiple_entries<S>(
+	cursor: Result<(Vec<JuniperValue<S>>, Option<String>, ArangoDatabase), ClientError>,
+) -> ExecutionResult<S>
+where
+	S: AsyncScalarValue,
+{
+	let into_field_error = |e: ClientError| DatabaseError::from(e).into_field_error();
 
-			for datum in data {
-				output.push(convert_json_to_juniper_value(datum.as_object().unwrap()));
-			}
+	let (mut batch, mut cursor_id, database) = cursor.map_err(into_field_error)?;
 
-			println!("Output conversion: {:?}", time.elapsed());
+	let mut output = Vec::<Value<S>>::new();
 
-			Ok(Value::list(output))
-		}
-		Err(e) => {
-			let message = format!("{}", e);
+	loop {
+		output.extend(batch.drain(..).map(|JuniperValue(datum)| datum));
 
-			Err(DatabaseError::new(message).into_field_error())
-		}
-	};
+		let id = match cursor_id.take() {
+			Some(id) => id,
+			None => break,
+		};
+
+		let cursor = database
+			.aql_next_batch(id.as_str())
+			.await
+			.map_err(into_field_error)?;
+
+		batch = cursor.result;
+		cursor_id = cursor.id.filter(|_| cursor.more);
+	}
+
+	Ok(Value::list(output))
 }
 
 pub enum QueryReturnType {
@@ -300,43 +416,184 @@ pub enum QueryReturnType {
 	Multiple,
 }
 
+/// Binds the arguments of an internal query to its AQL, with the span it's executed in and the
+/// bind variables logged when it's slow
+fn build_internal_query<'a, S>(
+	query: &AQLQuery,
+	aql: &'a str,
+	collection: &str,
+	query_arguments: HashMap<String, InputValue<S>>,
+	query_hardcoded_arguments: HashMap<String, InputValue<S>>,
+	session: &Session,
+) -> (AqlQuery<'a>, Span, Vec<(String, JsonValue)>)
+where
+	S: AsyncScalarValue,
+{
+	let mut bind_vars: Vec<(String, JsonValue)> =
+		vec![("@collection".to_string(), collection.into())];
+
+	utils::assign_parameters!(query_arguments, (key, v) -> {
+		bind_vars.push((query.get_argument_key(key.as_str()), v));
+	});
+
+	utils::assign_parameters!(query_hardcoded_arguments, (key, v) -> {
+		bind_vars.push((key, v));
+	});
+
+	bind_vars.extend(session.get_bind_vars(&query.get_session_variables()));
+
+	let span = query_span(aql, &bind_vars);
+
+	let mut entries_query = AqlQuery::builder().query(aql);
+
+	for (k, v) in &bind_vars {
+		entries_query = entries_query.bind_var(k.clone(), v.clone());
+	}
+
+	(
+		entries_query
+			.batch_size(CONFIG.get_internal_batch_size())
+			.build(),
+		span,
+		bind_vars,
+	)
+}
+
+/// Records the changes of a mutation into the audit log, a failure is only logged since the
+/// mutation is already applied
+async fn record_audit_log(
+	collection: &str,
+	operation: EventOperation,
+	changes: &[JsonValue],
+	session: &Session,
+) {
+	let user = session.user_id.as_deref();
+
+	if let Err(e) = record_mutations(collection, operation, changes, user, &session.role).await {
+		tracing::error!(collection, "Error recording audit log: {}", e);
+	}
+}
+
+/// Records the prior versions of the documents changed by a mutation of a versioned entity,
+/// a failure is only logged since the mutation is already applied
+async fn record_entity_revisions(
+	entity: &DbEntity,
+	operation: EventOperation,
+	changes: &[JsonValue],
+	session: &Session,
+) {
+	let versioning = match &entity.versioning {
+		Some(versioning) => versioning,
+		None => return,
+	};
+
+	let collection = entity.collection_name.as_str();
+	let user = session.user_id.as_deref();
+
+	if let Err(e) = record_revisions(collection, versioning, operation, changes, user).await {
+		tracing::error!(collection, "Error recording revisions: {}", e);
+	}
+}
+
+/// Stores the values given for the translatable fields of an entity under the `locale`
+/// argument, or the default locale when it's not given
+fn apply_locale<S>(attributes: String, entity: &DbEntity, arguments: &Arguments<S>) -> String
+where
+	S: ScalarValue,
+{
+	let fields: Vec<&str> = entity
+		.properties
+		.iter()
+		.filter(|p| p.translatable)
+		.map(|p| p.name.as_str())
+		.collect();
+
+	if fields.is_empty() {
+		return attributes;
+	}
+
+	let mut document: JsonValue = match serde_json::from_str(&attributes) {
+		Ok(document) => document,
+		Err(_) => return attributes,
+	};
+
+	let locale = arguments
+		.get::<String>(LOCALE_ARGUMENT)
+		.filter(|l| !l.is_empty())
+		.unwrap_or_else(|| CONFIG.get_default_locale().to_string());
+
+	if let Some(object) = document.as_object_mut() {
+		localize_values(object, &fields, &locale);
+	}
+
+	document.to_string()
+}
+
 async fn execute_internal_query<S>(
-	query: AQLQuery,
+	mut query: AQLQuery,
 	collection: &str,
 	query_arguments: HashMap<String, InputValue<S>>,
 	query_hardcoded_arguments: HashMap<String, InputValue<S>>,
-) -> Vec<JsonValue>
+	session: &Session,
+) -> Result<Vec<JsonValue>, FieldError<S>>
 where
 	S: AsyncScalarValue,
 {
 	let time = std::time::Instant::now();
 
+	let operation = match query.method {
+		AQLQueryMethod::Create => Some(EventOperation::Create),
+		AQLQueryMethod::Update(_) => Some(EventOperation::Update),
+		_ => None,
+	};
+
+	let audited = operation.filter(|_| CONFIG.is_audit_log_enabled());
+	let delivered = operation.filter(|o| has_webhooks(collection, *o));
+
+	query.returns_changes |= audited.is_some() || delivered.is_some();
+
 	let aql = query.to_aql();
 
-	println!("Internal Query: {}", &aql);
+	let (entries_query, span, bind_vars) = build_internal_query(
+		&query,
+		&aql,
+		collection,
+		query_arguments,
+		query_hardcoded_arguments,
+		session,
+	);
 
-	let mut entries_query = AqlQuery::builder()
-		.query(&aql)
-		.bind_var("@collection".to_string(), collection.clone());
+	// Mutations may return many documents, they are fetched in batches
+	let mut entries = Vec::new();
 
-	utils::assign_parameters!(query_arguments, (key, v) -> {
-		entries_query = entries_query.bind_var(query.get_argument_key(key.as_str()), v);
-	});
+	let fetched = fetch_batches(
+		&DATABASE.get().await.database(),
+		entries_query,
+		|batch: Vec<JsonValue>| entries.extend(batch),
+	)
+	.instrument(span)
+	.await;
 
-	utils::assign_parameters!(query_hardcoded_arguments, (key, v) -> {
-		entries_query = entries_query.bind_var(key.as_str(), v);
-	});
+	tracing::debug!(collection, elapsed = ?time.elapsed(), "Executed internal query");
 
-	let entries: Result<Vec<JsonValue>, ClientError> = DATABASE
-		.get()
-		.await
-		.database
-		.aql_query(entries_query.build())
-		.await;
+	log_slow_query(&aql, &bind_vars, time.elapsed());
 
-	println!("Internal Query AQL: {:?}", time.elapsed());
+	fetched.map_err(|e| DatabaseError::from(e).into_field_error())?;
 
-	entries.unwrap()
+	RESPONSE_CACHE.invalidate(collection).await;
+	invalidate_results(collection).await;
+
+	if let Some(operation) = audited {
+		record_audit_log(collection, operation, &entries, session).await;
+	}
+
+	if let Some(operation) = delivered {
+		if let Err(e) = enqueue_events(collection, operation, &entries).await {
+			tracing::error!(collection, "Error queueing events: {}", e);
+		}
+	}
+
+	Ok(entries)
 }
 
 async fn execute_query<'a, S, T>(
@@ -346,6 +603,7 @@ async fn execute_query<'a, S, T>(
 	return_type: QueryReturnType,
 	query_arguments: HashMap<String, InputValue<S>>,
 	raw_arguments: HashMap<String, T>,
+	session: &'a Session,
 ) -> ExecutionResult<S>
 where
 	S: AsyncScalarValue,
@@ -357,31 +615,105 @@ where
 
 	let query_str = query.to_aql();
 
-	println!("{}", &query_str);
-
-	let mut entries_query = AqlQuery::builder()
-		.query(&query_str)
-		.bind_var("@collection".to_string(), collection.clone());
+	let mut bind_vars: Vec<(String, JsonValue)> =
+		vec![("@collection".to_string(), collection.into())];
 
 	utils::assign_parameters!(query_arguments, (key, v) -> {
-		entries_query = entries_query.bind_var(query.get_argument_key(key.as_str()), v);
+		bind_vars.push((query.get_argument_key(key.as_str()), v));
 	});
 
 	for (k, v) in raw_arguments {
-		entries_query = entries_query.bind_var(query.get_argument_key(k.as_str()), v);
+		bind_vars.push((query.get_argument_key(k.as_str()), v.into()));
 	}
 
-	let entries: Result<Vec<JsonValue>, ClientError> = DATABASE
-		.get()
-		.await
-		.database
-		.aql_query(entries_query.build())
-		.await;
+	bind_vars.extend(session.get_bind_vars(&query.get_session_variables()));
+
+	if capture_query(&query_str, &bind_vars) {
+		return match return_type {
+			QueryReturnType::Single => Ok(Value::null()),
+			QueryReturnType::Multiple => Ok(Value::list(Vec::new())),
+		};
+	}
 
-	println!("SQL: {:?}", time.elapsed());
+	let span = query_span(&query_str, &bind_vars);
 
-	match return_type {
-		QueryReturnType::Single => get_single_entry(entries, entity.name.clone()),
-		QueryReturnType::Multiple => get_multiple_entries(entries),
+	let timeout = CONFIG.get_operation_timeout(&session.role);
+	let memory_limit = CONFIG.get_operation_memory_limit(&session.role);
+
+	// ArangoDB kills the queries over their limits, 0 leaving them unlimited
+	let build_query = || {
+		let mut entries_query = AqlQuery::builder()
+			.query(&query_str)
+			.memory_limit(memory_limit.unwrap_or(0))
+			.options(
+				AqlOptions::builder()
+					.max_runtime(timeout.map(|t| t.as_secs_f64()).unwrap_or(0.0))
+					.build(),
+			);
+
+		for (k, v) in &bind_vars {
+			entries_query = entries_query.bind_var(k.clone(), v.clone());
+		}
+
+		entries_query
+	};
+
+	let execution = async {
+		match return_type {
+			QueryReturnType::Single => {
+				let entries: Result<Vec<JuniperValue<S>>, ClientError> = DATABASE
+					.get()
+					.await
+					.run(|database| {
+						let entries_query = build_query().build();
+
+						async move { database.aql_query(entries_query).await }
+					})
+					.await;
+
+				tracing::debug!(collection, elapsed = ?time.elapsed(), "Executed query");
+
+				log_slow_query(&query_str, &bind_vars, time.elapsed());
+
+				get_single_entry(entries, entity.name.clone())
+			}
+			QueryReturnType::Multiple => {
+				// The rest of the batches are fetched from the connection the cursor was opened on
+				let cursor = DATABASE
+					.get()
+					.await
+					.run(|database| {
+						let entries_query = build_query()
+							.batch_size(CONFIG.get_query_batch_size())
+							.build();
+
+						async move {
+							let cursor = database.aql_query_batch(entries_query).await?;
+
+							Ok::<_, ClientError>((
+								cursor.result,
+								cursor.id.filter(|_| cursor.more),
+								database,
+							))
+						}
+					})
+					.await;
+
+				tracing::debug!(collection, elapsed = ?time.elapsed(), "Executed query");
+
+				log_slow_query(&query_str, &bind_vars, time.elapsed());
+
+				get_multiple_entries(cursor).await
+			}
+		}
+	}
+	.instrument(span);
+
+	// The remaining batches of a cursor are fetched within the timeout as well
+	match timeout {
+		Some(timeout) => tokio::time::timeout(timeout, execution)
+			.await
+			.unwrap_or_else(|_| Err(TimeoutError::new(timeout).into_field_error())),
+		None => execution.await,
 	}
 }
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use juniper::{Arguments, InputValue};
+
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::auth::Session;
+use crate::api::schema::errors::ForbiddenError;
+use crate::api::schema::fields::Entity;
+use crate::api::schema::hooks::{has_after_hooks, run_after_hooks, run_before_hooks};
+use crate::api::schema::input::filter::{EntityIndicesFilter, EntityIndicesFilterData};
+use crate::api::schema::operations::{
+	execute_internal_query, execute_query, get_changed_key, get_filter_by_indices_attributes,
+	get_filter_by_key, record_entity_revisions, FutureType, OperationData, QueryReturnType,
+};
+use crate::api::schema::AsyncScalarValue;
+use crate::lib::database::api::DbEntity;
+use crate::lib::database::aql::{
+	AQLFilterOperation, AQLNode, AQLOperation, AQLQuery, AQLQueryMethod, AQLQueryParameter,
+	AQLQueryValue,
+};
+use crate::lib::events::EventOperation;
+use crate::lib::naming::get_operation_name;
+use crate::lib::publication::{DRAFT_STATUS, PUBLISHED_STATUS, STATUS_FIELD};
+
+/// Argument of the queries of entities with publication, reading their drafts too
+pub const PREVIEW_ARGUMENT: &str = "preview";
+
+/// Filter restricting the documents of entities with publication to the published ones, for
+/// the documents read without the `preview` argument: through relationships, graphs or exports
+pub fn get_published_filter(entity: &DbEntity) -> Option<Box<dyn AQLNode>> {
+	if !entity.publication {
+		return None;
+	}
+
+	Some(Box::new(AQLFilterOperation {
+		left_node: Box::new(AQLQueryParameter(STATUS_FIELD.to_string())),
+		operation: AQLOperation::Equal,
+		right_node: Box::new(AQLQueryValue(
+			serde_json::to_string(PUBLISHED_STATUS).unwrap(),
+		)),
+	}))
+}
+
+/// Filter restricting the documents of entities with publication to the published ones, unless
+/// the query previews them
+pub fn get_publication_filter<S>(
+	entity: &DbEntity,
+	arguments: &Arguments<S>,
+	session: &Session,
+) -> Result<Option<Box<dyn AQLNode>>, ForbiddenError>
+where
+	S: AsyncScalarValue,
+{
+	if !entity.publication {
+		return Ok(None);
+	}
+
+	if arguments.get::<bool>(PREVIEW_ARGUMENT).unwrap_or(false) {
+		if !session.can_preview() {
+			return Err(ForbiddenError::new(format!(
+				"Role {} can't preview {}",
+				session.role, entity.name
+			)));
+		}
+
+		return Ok(None);
+	}
+
+	Ok(get_published_filter(entity))
+}
+
+/// Sets the status of the document matched by `where`, as an update running its hooks. The
+/// updated document is then read by `query`
+fn set_status<'b, S>(
+	data: &'b OperationData<S>,
+	arguments: &'b Arguments<S>,
+	query: AQLQuery,
+	session: &'b Session,
+	status: &'static str,
+) -> FutureType<'b, S>
+where
+	S: AsyncScalarValue,
+{
+	let entity = &data.entity;
+	let collection = &entity.collection_name;
+
+	let indices_filter = arguments
+		.get::<EntityIndicesFilter<S>>("where")
+		.unwrap()
+		.indices_arguments;
+
+	let mut status_query = AQLQuery::new(0);
+
+	status_query.returns_changes =
+		has_after_hooks(entity, EventOperation::Update) || entity.versioning.is_some();
+	status_query.filter = Some(get_filter_by_indices_attributes(&indices_filter));
+
+	if let Some(permission_filter) = get_permission_filter(entity, session) {
+		status_query.add_filter(permission_filter);
+	}
+
+	let changes = serde_json::json!({ STATUS_FIELD: status }).to_string();
+
+	Box::pin(async move {
+		let changes = run_before_hooks::<S>(
+			entity,
+			EventOperation::Update,
+			Some(changes),
+			serde_json::to_value(&indices_filter).ok(),
+			session,
+		)
+		.await?
+		.unwrap_or_default();
+
+		status_query.method = AQLQueryMethod::Update(changes);
+
+		let status_data = execute_internal_query::<S>(
+			status_query,
+			collection,
+			indices_filter,
+			HashMap::new(),
+			session,
+		)
+		.await?;
+
+		record_entity_revisions(entity, EventOperation::Update, &status_data, session).await;
+		run_after_hooks(entity, EventOperation::Update, &status_data, session).await;
+
+		let updated_key = get_changed_key(&status_data, &entity.name)?;
+
+		let mut args = HashMap::new();
+
+		args.insert("_key".to_string(), InputValue::scalar(updated_key));
+
+		execute_query(
+			query,
+			entity,
+			collection,
+			QueryReturnType::Single,
+			args,
+			HashMap::<String, String>::new(),
+			session,
+		)
+		.await
+	})
+}
+
+crate::api::schema::operations::utils::define_operation!(
+	Publish {
+		on_call(data, arguments, query, session) -> {
+			query.filter = Some(get_filter_by_key());
+
+			set_status(data, arguments, query, session, PUBLISHED_STATUS)
+		},
+		name(data) -> {
+			get_operation_name("publish", &data.entity.name, false)
+		},
+		arguments(data, _registry) {
+			where EntityIndicesFilter<S> => &EntityIndicesFilterData::<S>::new(data)
+		},
+		return_type -> Entity
+	}
+);
+
+crate::api::schema::operations::utils::define_operation!(
+	Unpublish {
+		on_call(data, arguments, query, session) -> {
+			query.filter = Some(get_filter_by_key());
+
+			set_status(data, arguments, query, session, DRAFT_STATUS)
+		},
+		name(data) -> {
+			get_operation_name("unpublish", &data.entity.name, false)
+		},
+		arguments(data, _registry) {
+			where EntityIndicesFilter<S> => &EntityIndicesFilterData::<S>::new(data)
+		},
+		return_type -> Entity
+	}
+);
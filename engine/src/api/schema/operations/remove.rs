@@ -1,17 +1,35 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use convert_case::Casing;
+use juniper::{FieldError, InputValue, IntoFieldError};
+use rust_arango::transaction::{TransactionCollections, TransactionSettings};
+use rust_arango::{AqlQuery, ClientError};
+use serde_json::Value as JsonValue;
+use tracing::Instrument;
 
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::auth::Session;
+use crate::api::response_cache::RESPONSE_CACHE;
+use crate::api::schema::cache::invalidate_results;
+use crate::api::schema::errors::{ConstraintError, DatabaseError};
 use crate::api::schema::fields::Entity;
+use crate::api::schema::hooks::{has_after_hooks, run_after_hooks, run_before_hooks};
 use crate::api::schema::input::filter::{EntityIndicesFilter, EntityIndicesFilterData};
 use crate::api::schema::operations::{
-	execute_internal_query, execute_query, get_filter_by_indices_attributes, QueryReturnType,
+	build_internal_query, execute_query, get_filter_by_indices_attributes, record_audit_log,
+	record_entity_revisions, OperationData, QueryReturnType,
 };
+use crate::api::schema::AsyncScalarValue;
+use crate::lib::database::api::{DbOnDelete, DbRelationship, DbRelationshipDirection};
 use crate::lib::database::aql::{AQLQuery, AQLQueryMethod};
+use crate::lib::database::DATABASE;
+use crate::lib::events::{enqueue_events, has_webhooks, EventOperation};
+use crate::lib::logging::log_slow_query;
+use crate::lib::naming::get_operation_name;
+use crate::lib::CONFIG;
 
 crate::api::schema::operations::utils::define_operation!(
 	Remove {
-		on_call(data, arguments, query) -> {
+		on_call(data, arguments, query, session) -> {
 			let entity = &data.entity;
 			let collection = &entity.collection_name;
 
@@ -19,14 +37,21 @@ crate::api::schema::operations::utils::define_operation!(
 
 			query.filter = Some(get_filter_by_indices_attributes(&indices_filter));
 
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				query.add_filter(permission_filter);
+			}
+
 			Box::pin(async move {
+				run_before_hooks::<S>(entity, EventOperation::Delete, None, serde_json::to_value(&indices_filter).ok(), session).await?;
+
 				let result = execute_query(
 					query,
 					entity,
 					collection,
 					QueryReturnType::Single,
 					indices_filter.clone(),
-					HashMap::<String, String>::new()
+					HashMap::<String, String>::new(),
+					session
 				).await;
 
 				let mut remove_query = AQLQuery::new(0);
@@ -34,23 +59,17 @@ crate::api::schema::operations::utils::define_operation!(
 				remove_query.method = AQLQueryMethod::Remove;
 				remove_query.filter = Some(get_filter_by_indices_attributes(&indices_filter));
 
-				execute_internal_query::<S>(remove_query, collection, indices_filter, HashMap::new()).await;
+				if let Some(permission_filter) = get_permission_filter(entity, session) {
+					remove_query.add_filter(permission_filter);
+				}
+
+				remove_with_relationships(remove_query, data, indices_filter, session).await?;
 
 				result
 			})
 		},
 		name(data) -> {
-			format!(
-				"remove{}",
-				pluralizer::pluralize(
-					data.entity
-						.name
-						.to_case(convert_case::Case::Pascal)
-						.as_str(),
-					1,
-					false,
-				)
-			)
+			get_operation_name("remove", &data.entity.name, false)
 		},
 		arguments(data, _registry) {
 			where EntityIndicesFilter<S> => &EntityIndicesFilterData::<S>::new(data)
@@ -58,3 +77,297 @@ crate::api::schema::operations::utils::define_operation!(
 		return_type -> Entity
 	}
 );
+
+fn into_database_error<S>(e: ClientError) -> FieldError<S>
+where
+	S: AsyncScalarValue,
+{
+	DatabaseError::from(e).into_field_error()
+}
+
+/// Attributes of the edges holding the current document and the related one
+fn get_edge_sides(relationship: &DbRelationship) -> (&str, &str) {
+	match relationship.direction {
+		DbRelationshipDirection::Inbound => ("_to", "_from"),
+		_ => ("_from", "_to"),
+	}
+}
+
+/// Collections written when removing documents of the entity, following cascades
+fn get_write_collections(collection: &str, relationships: &[DbRelationship]) -> Vec<String> {
+	let mut collections = vec![collection.to_string()];
+	let mut pending = vec![collection.to_string()];
+
+	while let Some(current) = pending.pop() {
+		for relationship in relationships {
+			let is_from = relationship.from.collection_name == current;
+			let targets = relationship.get_targets();
+
+			if !is_from && !targets.iter().any(|t| t.collection_name == current) {
+				continue;
+			}
+
+			if !collections.contains(&relationship.edge) {
+				collections.push(relationship.edge.clone());
+			}
+
+			if !is_from || relationship.on_delete != DbOnDelete::Cascade {
+				continue;
+			}
+
+			for target in targets {
+				if !collections.contains(&target.collection_name) {
+					collections.push(target.collection_name.clone());
+					pending.push(target.collection_name.clone());
+				}
+			}
+		}
+	}
+
+	collections
+}
+
+/// Removes the documents matched by the query in a transaction, with the edges holding them.
+/// Related documents are removed as well for `CASCADE` relationships, while `RESTRICT`
+/// ones abort the removal when there are any
+pub async fn remove_with_relationships<S>(
+	mut remove_query: AQLQuery,
+	data: &OperationData<S>,
+	query_arguments: HashMap<String, InputValue<S>>,
+	session: &Session,
+) -> Result<(), FieldError<S>>
+where
+	S: AsyncScalarValue,
+{
+	let collection = &data.entity.collection_name;
+	let relationships = &data.all_relationships;
+
+	let audited = CONFIG.is_audit_log_enabled();
+
+	// Only the documents removed by the query are delivered and versioned, not the cascaded
+	// ones, while the audit log records both
+	remove_query.returns_changes = audited
+		|| has_webhooks(collection, EventOperation::Delete)
+		|| has_after_hooks(&data.entity, EventOperation::Delete)
+		|| data.entity.versioning.is_some();
+
+	let cascade_aql = match audited {
+		true => "FOR id IN @ids REMOVE PARSE_IDENTIFIER(id).key IN @@collection RETURN { _key: OLD._key, old: OLD }",
+		false => "FOR id IN @ids REMOVE PARSE_IDENTIFIER(id).key IN @@collection",
+	};
+
+	let write_collections = get_write_collections(collection, relationships);
+
+	let settings = TransactionSettings::builder()
+		.collections(
+			TransactionCollections::builder()
+				.write(write_collections.clone())
+				.build(),
+		)
+		.build();
+
+	let transaction = DATABASE
+		.get()
+		.await
+		.database()
+		.begin_transaction(settings)
+		.await
+		.map_err(into_database_error)?;
+
+	let result: Result<(Vec<JsonValue>, Vec<(String, Vec<JsonValue>)>), FieldError<S>> = async {
+		let aql = remove_query.to_aql();
+
+		let time = std::time::Instant::now();
+
+		let (remove_aql_query, span, bind_vars) = build_internal_query(
+			&remove_query,
+			&aql,
+			collection,
+			query_arguments,
+			HashMap::new(),
+			session,
+		);
+
+		let removed: Vec<JsonValue> = transaction
+			.aql_query(remove_aql_query)
+			.instrument(span)
+			.await
+			.map_err(into_database_error)?;
+
+		log_slow_query(&aql, &bind_vars, time.elapsed());
+
+		let ids: Vec<String> = removed
+			.iter()
+			.filter_map(|d| d["_key"].as_str())
+			.map(|k| format!("{}/{}", collection, k))
+			.collect();
+
+		let mut cascaded = Vec::new();
+		let mut visited: HashSet<String> = ids.iter().cloned().collect();
+		let mut pending = vec![(collection.clone(), ids)];
+
+		while let Some((current, ids)) = pending.pop() {
+			if ids.is_empty() {
+				continue;
+			}
+
+			for relationship in relationships.iter() {
+				if relationship.from.collection_name != current
+					|| relationship.on_delete == DbOnDelete::SetNull
+				{
+					continue;
+				}
+
+				let (side, other_side) = get_edge_sides(relationship);
+
+				let target_collections: Vec<String> = relationship
+					.get_targets()
+					.iter()
+					.map(|t| t.collection_name.clone())
+					.collect();
+
+				let related_aql = format!(
+					"FOR e IN @@edge FILTER e.{side} IN @ids AND PARSE_IDENTIFIER(e.{other_side}).collection IN @collections RETURN DISTINCT e.{other_side}",
+					side = side,
+					other_side = other_side
+				);
+
+				let related: Vec<String> = transaction
+					.aql_query(
+						AqlQuery::builder()
+							.query(&related_aql)
+							.bind_var("@edge", relationship.edge.clone())
+							.bind_var("ids", ids.clone())
+							.bind_var("collections", target_collections.clone())
+							.build(),
+					)
+					.await
+					.map_err(into_database_error)?;
+
+				let related: Vec<String> = related
+					.into_iter()
+					.filter(|id| !visited.contains(id))
+					.collect();
+
+				if related.is_empty() {
+					continue;
+				}
+
+				if relationship.on_delete == DbOnDelete::Restrict {
+					return Err(ConstraintError::new(
+						format!(
+							"Relationship {} of {} restricts removing documents with related documents",
+							relationship.name, relationship.from.name
+						),
+						Some(relationship.name.clone()),
+					)
+					.into_field_error());
+				}
+
+				visited.extend(related.iter().cloned());
+
+				// Related documents of polymorphic relationships can be of several collections
+				for target_collection in target_collections {
+					let prefix = format!("{}/", target_collection);
+
+					let target_ids: Vec<String> = related
+						.iter()
+						.filter(|id| id.starts_with(&prefix))
+						.cloned()
+						.collect();
+
+					if target_ids.is_empty() {
+						continue;
+					}
+
+					let removed_targets: Vec<JsonValue> = transaction
+						.aql_query(
+							AqlQuery::builder()
+								.query(cascade_aql)
+								.bind_var("@collection", target_collection.clone())
+								.bind_var("ids", target_ids.clone())
+								.build(),
+						)
+						.await
+						.map_err(into_database_error)?;
+
+					cascaded.push((target_collection.clone(), removed_targets));
+					pending.push((target_collection, target_ids));
+				}
+			}
+
+			// Edges of any relationship holding the documents, declared from either collection
+			let mut edges: Vec<&String> = relationships
+				.iter()
+				.filter(|r| {
+					r.from.collection_name == current
+						|| r.get_targets().iter().any(|t| t.collection_name == current)
+				})
+				.map(|r| &r.edge)
+				.collect();
+
+			edges.sort();
+			edges.dedup();
+
+			for edge in edges {
+				let _: Vec<JsonValue> = transaction
+					.aql_query(
+						AqlQuery::builder()
+							.query(
+								"FOR e IN @@edge FILTER e._from IN @ids OR e._to IN @ids REMOVE e IN @@edge",
+							)
+							.bind_var("@edge", edge.clone())
+							.bind_var("ids", ids.clone())
+							.build(),
+					)
+					.await
+					.map_err(into_database_error)?;
+			}
+		}
+
+		Ok((removed, cascaded))
+	}
+	.await;
+
+	match result {
+		Ok((removed, cascaded)) => {
+			transaction.commit().await.map_err(into_database_error)?;
+
+			for write_collection in &write_collections {
+				RESPONSE_CACHE.invalidate(write_collection).await;
+				invalidate_results(write_collection).await;
+			}
+
+			if audited {
+				record_audit_log(collection, EventOperation::Delete, &removed, session).await;
+
+				for (target_collection, removed_targets) in &cascaded {
+					record_audit_log(
+						target_collection,
+						EventOperation::Delete,
+						removed_targets,
+						session,
+					)
+					.await;
+				}
+			}
+
+			record_entity_revisions(&data.entity, EventOperation::Delete, &removed, session).await;
+
+			if remove_query.returns_changes {
+				if let Err(e) = enqueue_events(collection, EventOperation::Delete, &removed).await {
+					tracing::error!(collection = %collection, "Error queueing events: {}", e);
+				}
+			}
+
+			run_after_hooks(&data.entity, EventOperation::Delete, &removed, session).await;
+
+			Ok(())
+		}
+		Err(e) => {
+			let _ = transaction.abort().await;
+
+			Err(e)
+		}
+	}
+}
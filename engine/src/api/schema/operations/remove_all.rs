@@ -1,29 +1,48 @@
 use std::collections::HashMap;
 
-use convert_case::Casing;
-
+use crate::api::auth::permissions::get_permission_filter;
 use crate::api::schema::fields::Entity;
-use crate::api::schema::input::filter::{get_aql_filter_from_args, EntityFilter, EntityFilterData};
-use crate::api::schema::operations::{execute_internal_query, execute_query, QueryReturnType};
+use crate::api::schema::hooks::run_before_hooks;
+use crate::api::schema::input::filter::{
+	check_filter_args_access, get_aql_filter_from_args, EntityFilter, EntityFilterData,
+};
+use crate::api::schema::operations::remove::remove_with_relationships;
+use crate::api::schema::operations::{execute_query, QueryReturnType};
+use crate::api::schema::utils::RawInput;
 use crate::lib::database::aql::{AQLQuery, AQLQueryMethod};
+use crate::lib::events::EventOperation;
+use crate::lib::naming::get_operation_name;
 
 crate::api::schema::operations::utils::define_operation!(
 	RemoveAll {
-		on_call(data, arguments, query) -> {
+		on_call(data, arguments, query, session) -> {
 			let entity = &data.entity;
 			let collection = &entity.collection_name;
 
 			query.filter = get_aql_filter_from_args(arguments, data);
 			query.limit = arguments.get::<i32>("limit");
 
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				query.add_filter(permission_filter);
+			}
+
+			let filter_access = check_filter_args_access(arguments, entity, session);
+
 			Box::pin(async move {
+				filter_access?;
+
+				let filter = arguments.get::<RawInput<S>>("where").and_then(|w| serde_json::to_value(&w.0).ok());
+
+				run_before_hooks::<S>(entity, EventOperation::Delete, None, filter, session).await?;
+
 				let result = execute_query(
 					query,
 					entity,
 					collection,
 					QueryReturnType::Multiple,
 					HashMap::new(),
-					HashMap::<String, String>::new()
+					HashMap::<String, String>::new(),
+					session
 				).await;
 
 				let mut remove_query = AQLQuery::new(0);
@@ -32,23 +51,17 @@ crate::api::schema::operations::utils::define_operation!(
 				remove_query.filter = get_aql_filter_from_args(arguments, data);
 				remove_query.limit = arguments.get::<i32>("limit");
 
-				execute_internal_query::<S>(remove_query, collection, HashMap::new(), HashMap::new()).await;
+				if let Some(permission_filter) = get_permission_filter(entity, session) {
+					remove_query.add_filter(permission_filter);
+				}
+
+				remove_with_relationships(remove_query, data, HashMap::new(), session).await?;
 
 				result
 			})
 		},
 		name(data) -> {
-			format!(
-				"remove{}",
-				pluralizer::pluralize(
-					data.entity
-						.name
-						.to_case(convert_case::Case::Pascal)
-						.as_str(),
-					2,
-					false,
-				)
-			)
+			get_operation_name("remove", &data.entity.name, true)
 		},
 		arguments(data, _registry) {
 			where EntityFilter<S> => &EntityFilterData::new(data)
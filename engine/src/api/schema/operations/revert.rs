@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use juniper::{InputValue, IntoFieldError};
+
+use crate::api::auth::permissions::{apply_presets, get_permission_filter};
+use crate::api::schema::errors::{DatabaseError, NotFoundError};
+use crate::api::schema::fields::Entity;
+use crate::api::schema::hooks::{has_after_hooks, run_after_hooks, run_before_hooks};
+use crate::api::schema::input::filter::{EntityIndicesFilter, EntityIndicesFilterData};
+use crate::api::schema::operations::{
+	execute_internal_query, execute_query, get_changed_key, get_filter_by_indices_attributes,
+	get_filter_by_key, record_entity_revisions, QueryReturnType,
+};
+use crate::lib::database::aql::{
+	AQLFilterOperation, AQLOperation, AQLQuery, AQLQueryBind, AQLQueryMethod, AQLQueryParameter,
+};
+use crate::lib::events::EventOperation;
+use crate::lib::naming::get_operation_name;
+use crate::lib::revisions::{get_revert_changes, RevertChanges};
+
+crate::api::schema::operations::utils::define_operation!(
+	Revert {
+		on_call(data, arguments, query, session) -> {
+			let entity = &data.entity;
+			let collection = &entity.collection_name;
+
+			let mut indices_filter = arguments.get::<EntityIndicesFilter<S>>("where").unwrap().indices_arguments;
+			let revision = arguments.get::<String>("revision").unwrap();
+			let filter = serde_json::to_value(&indices_filter).ok();
+
+			let mut revert_query = AQLQuery::new(0);
+
+			revert_query.returns_changes = true;
+			revert_query.filter = Some(get_filter_by_indices_attributes(&indices_filter));
+
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				revert_query.add_filter(permission_filter);
+			}
+
+			// The revision has to be of the document matched by `where`
+			revert_query.add_filter(Box::new(AQLFilterOperation {
+				left_node: Box::new(AQLQueryParameter("_key".to_string())),
+				operation: AQLOperation::Equal,
+				right_node: Box::new(AQLQueryBind("__revision_document".to_string())),
+			}));
+
+			Box::pin(async move {
+				let revert_changes = get_revert_changes(collection, &revision)
+					.await
+					.map_err(|e| DatabaseError::from(e).into_field_error())?;
+
+				let reverted_key = match revert_changes {
+					Some(RevertChanges::Update(document, changes)) => {
+						indices_filter.insert("__revision_document".to_string(), InputValue::scalar(document));
+
+						let changes = run_before_hooks::<S>(entity, EventOperation::Update, Some(changes.to_string()), filter, session)
+							.await?
+							.unwrap_or_default();
+
+						revert_query.method = AQLQueryMethod::Update(changes);
+
+						let revert_data = execute_internal_query::<S>(revert_query, collection, indices_filter, HashMap::new(), session).await?;
+
+						record_entity_revisions(entity, EventOperation::Update, &revert_data, session).await;
+
+						if has_after_hooks(entity, EventOperation::Update) {
+							run_after_hooks(entity, EventOperation::Update, &revert_data, session).await;
+						}
+
+						get_changed_key(&revert_data, &entity.name)?
+					}
+					// A removed document is created again, `where` matching the key it had
+					Some(RevertChanges::Insert(document, attributes))
+						if indices_filter.get("_key").and_then(|k| k.as_string_value()) == Some(document.as_str()) =>
+					{
+						let attributes = run_before_hooks::<S>(entity, EventOperation::Create, Some(attributes.to_string()), None, session)
+							.await?
+							.unwrap_or_default();
+
+						let mut insert_query = AQLQuery::new(0);
+
+						insert_query.method = AQLQueryMethod::Create;
+						insert_query.returns_changes = has_after_hooks(entity, EventOperation::Create);
+						insert_query.creates = apply_presets(attributes, entity, session);
+
+						let insert_data = execute_internal_query::<S>(insert_query, collection, HashMap::new(), HashMap::new(), session).await?;
+
+						run_after_hooks(entity, EventOperation::Create, &insert_data, session).await;
+
+						get_changed_key(&insert_data, &entity.name)?
+					}
+					_ => return Err(NotFoundError::new(format!("Revision {}", revision)).into_field_error()),
+				};
+
+				query.filter = Some(get_filter_by_key());
+
+				let mut args = HashMap::new();
+
+				args.insert("_key".to_string(), InputValue::scalar(reverted_key));
+
+				execute_query(
+					query,
+					entity,
+					collection,
+					QueryReturnType::Single,
+					args,
+					HashMap::<String, String>::new(),
+					session
+				).await
+			})
+		},
+		name(data) -> {
+			get_operation_name("revert", &data.entity.name, false)
+		},
+		arguments(data, _registry) {
+			where EntityIndicesFilter<S> => &EntityIndicesFilterData::<S>::new(data)
+			revision String => &()
+		},
+		return_type -> Entity
+	}
+);
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use convert_case::Casing;
+use juniper::InputValue;
+
+use crate::api::auth::permissions::{apply_presets, get_permission_filter};
+use crate::api::schema::enums::map_enum_input_values;
+use crate::api::schema::fields::Entity;
+use crate::api::schema::files::store_uploads;
+use crate::api::schema::hooks::{has_after_hooks, run_after_hooks, run_before_hooks};
+use crate::api::schema::input::set::{EntitySet, EntitySetData};
+use crate::api::schema::operations::create::apply_generated_values;
+use crate::api::schema::operations::{
+	apply_locale, execute_internal_query, execute_query, get_filter_by_key,
+	record_entity_revisions, OperationData, QueryReturnType,
+};
+use crate::api::schema::AsyncScalarValue;
+use crate::lib::database::aql::{AQLQuery, AQLQueryMethod};
+use crate::lib::events::EventOperation;
+use crate::lib::naming::format_operation_name;
+use crate::lib::CONFIG;
+
+/// Singletons are named after their collection, e.g. `settings` and `updateSettings`
+fn get_singleton_name<S>(verb: &str, data: &OperationData<S>) -> String
+where
+	S: AsyncScalarValue,
+{
+	format_operation_name(
+		verb,
+		&format!(
+			"{}{}",
+			CONFIG.get_schema_prefix(),
+			data.entity
+				.collection_name
+				.to_case(convert_case::Case::Pascal)
+		),
+	)
+}
+
+/// Arguments matching the document of the singleton
+fn get_singleton_arguments<S>(data: &OperationData<S>) -> HashMap<String, InputValue<S>>
+where
+	S: AsyncScalarValue,
+{
+	let mut args = HashMap::new();
+
+	args.insert(
+		"_key".to_string(),
+		InputValue::scalar(data.entity.singleton.clone().unwrap_or_default()),
+	);
+
+	args
+}
+
+crate::api::schema::operations::utils::define_operation!(
+	Singleton {
+		on_call(data, _arguments, query, session) -> {
+			let entity = &data.entity;
+			let collection = &entity.collection_name;
+
+			query.filter = Some(get_filter_by_key());
+			query.limit = Some(1);
+
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				query.add_filter(permission_filter);
+			}
+
+			Box::pin(async move {
+				execute_query(
+					query,
+					entity,
+					collection,
+					QueryReturnType::Single,
+					get_singleton_arguments(data),
+					HashMap::<String, String>::new(),
+					session
+				).await
+			})
+		},
+		name(data) -> {
+			get_singleton_name("", data)
+		},
+		arguments(_data, _registry) {},
+		return_type -> Option<Entity>
+	}
+);
+
+crate::api::schema::operations::utils::define_operation!(
+	UpdateSingleton {
+		on_call(data, arguments, query, session) -> {
+			let entity = &data.entity;
+			let collection = &entity.collection_name;
+
+			let mut update_query = AQLQuery::new(0);
+
+			let changes = apply_locale(
+				map_enum_input_values(arguments.get::<EntitySet>("_set").unwrap().data, entity),
+				entity,
+				arguments,
+			);
+
+			update_query.returns_changes = has_after_hooks(entity, EventOperation::Update) || entity.versioning.is_some();
+			update_query.filter = Some(get_filter_by_key());
+
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				update_query.add_filter(permission_filter);
+			}
+
+			query.filter = Some(get_filter_by_key());
+
+			Box::pin(async move {
+				let changes = run_before_hooks::<S>(entity, EventOperation::Update, Some(changes), None, session)
+					.await?
+					.unwrap_or_default();
+				let changes = store_uploads::<S>(entity, changes, Some(&serde_json::json!({ "_key": entity.singleton }))).await?;
+
+				update_query.method = AQLQueryMethod::Update(changes.clone());
+
+				let update_data = execute_internal_query::<S>(update_query, collection, get_singleton_arguments(data), HashMap::new(), session).await?;
+
+				// The document is created by the first update, under the key of the singleton
+				if update_data.is_empty() {
+					let mut insert_query = AQLQuery::new(0);
+
+					insert_query.method = AQLQueryMethod::Create;
+					insert_query.returns_changes = has_after_hooks(entity, EventOperation::Create);
+					insert_query.creates = apply_presets(
+						format!(
+							"MERGE({}, {{ _key: {} }})",
+							apply_generated_values(changes, entity),
+							serde_json::to_string(&entity.singleton).unwrap()
+						),
+						entity,
+						session,
+					);
+
+					let create_data = execute_internal_query::<S>(insert_query, collection, HashMap::new(), HashMap::new(), session).await?;
+
+					run_after_hooks(entity, EventOperation::Create, &create_data, session).await;
+				} else {
+					record_entity_revisions(entity, EventOperation::Update, &update_data, session).await;
+					run_after_hooks(entity, EventOperation::Update, &update_data, session).await;
+				}
+
+				execute_query(
+					query,
+					entity,
+					collection,
+					QueryReturnType::Single,
+					get_singleton_arguments(data),
+					HashMap::<String, String>::new(),
+					session
+				).await
+			})
+		},
+		name(data) -> {
+			get_singleton_name("update", data)
+		},
+		arguments(data, _registry) {
+			_set EntitySet => &EntitySetData::new(data)
+		},
+		return_type -> Entity
+	}
+);
@@ -1,20 +1,25 @@
 use std::collections::HashMap;
 
-use convert_case::Casing;
 use juniper::InputValue;
 
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::schema::enums::map_enum_input_values;
 use crate::api::schema::fields::Entity;
+use crate::api::schema::files::store_uploads;
+use crate::api::schema::hooks::{has_after_hooks, run_after_hooks, run_before_hooks};
 use crate::api::schema::input::filter::{EntityIndicesFilter, EntityIndicesFilterData};
 use crate::api::schema::input::set::{EntitySet, EntitySetData};
 use crate::api::schema::operations::{
-	execute_internal_query, execute_query, get_filter_by_indices_attributes, get_filter_by_key,
-	QueryReturnType,
+	apply_locale, execute_internal_query, execute_query, get_changed_key,
+	get_filter_by_indices_attributes, get_filter_by_key, record_entity_revisions, QueryReturnType,
 };
 use crate::lib::database::aql::{AQLQuery, AQLQueryMethod};
+use crate::lib::events::EventOperation;
+use crate::lib::naming::get_operation_name;
 
 crate::api::schema::operations::utils::define_operation!(
 	Update {
-		on_call(data, arguments, query) -> {
+		on_call(data, arguments, query, session) -> {
 			let entity = &data.entity;
 			let collection = &entity.collection_name;
 
@@ -22,12 +27,33 @@ crate::api::schema::operations::utils::define_operation!(
 
 			let mut update_query = AQLQuery::new(0);
 
-			update_query.method = AQLQueryMethod::Update(arguments.get::<EntitySet>("_set").unwrap().data);
+			let changes = apply_locale(
+				map_enum_input_values(arguments.get::<EntitySet>("_set").unwrap().data, entity),
+				entity,
+				arguments,
+			);
+
+			update_query.returns_changes = has_after_hooks(entity, EventOperation::Update) || entity.versioning.is_some();
 			update_query.filter = Some(get_filter_by_indices_attributes(&indices_filter));
 
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				update_query.add_filter(permission_filter);
+			}
+
 			Box::pin(async move {
-				let create_data = execute_internal_query::<S>(update_query, collection, indices_filter, HashMap::new()).await;
-				let inserted_key = create_data[0]["_key"].as_str().unwrap().to_string();
+				let changes = run_before_hooks::<S>(entity, EventOperation::Update, Some(changes), serde_json::to_value(&indices_filter).ok(), session)
+					.await?
+					.unwrap_or_default();
+				let changes = store_uploads::<S>(entity, changes, serde_json::to_value(&indices_filter).ok().as_ref()).await?;
+
+				update_query.method = AQLQueryMethod::Update(changes);
+
+				let create_data = execute_internal_query::<S>(update_query, collection, indices_filter, HashMap::new(), session).await?;
+
+				record_entity_revisions(entity, EventOperation::Update, &create_data, session).await;
+				run_after_hooks(entity, EventOperation::Update, &create_data, session).await;
+
+				let inserted_key = get_changed_key(&create_data, &entity.name)?;
 
 				query.filter = Some(get_filter_by_key());
 
@@ -41,22 +67,13 @@ crate::api::schema::operations::utils::define_operation!(
 					collection,
 					QueryReturnType::Single,
 					args,
-					HashMap::<String, String>::new()
+					HashMap::<String, String>::new(),
+					session
 				).await
 			})
 		},
 		name(data) -> {
-			format!(
-				"update{}",
-				pluralizer::pluralize(
-					data.entity
-						.name
-						.to_case(convert_case::Case::Pascal)
-						.as_str(),
-					1,
-					false,
-				)
-			)
+			get_operation_name("update", &data.entity.name, false)
 		},
 		arguments(data, _registry) {
 			where EntityIndicesFilter<S> => &EntityIndicesFilterData::<S>::new(data)
@@ -1,29 +1,63 @@
 use std::collections::HashMap;
 
-use convert_case::Casing;
-
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::schema::enums::map_enum_input_values;
 use crate::api::schema::fields::Entity;
-use crate::api::schema::input::filter::{get_aql_filter_from_args, EntityFilter, EntityFilterData};
+use crate::api::schema::files::store_uploads;
+use crate::api::schema::hooks::{has_after_hooks, run_after_hooks, run_before_hooks};
+use crate::api::schema::input::filter::{
+	check_filter_args_access, get_aql_filter_from_args, EntityFilter, EntityFilterData,
+};
 use crate::api::schema::input::set::{EntitySet, EntitySetData};
 use crate::api::schema::operations::{
-	execute_internal_query, execute_query, get_filter_in_keys, QueryReturnType,
+	apply_locale, execute_internal_query, execute_query, get_filter_in_keys,
+	record_entity_revisions, QueryReturnType,
 };
+use crate::api::schema::utils::RawInput;
 use crate::lib::database::aql::{AQLQuery, AQLQueryMethod};
+use crate::lib::events::EventOperation;
+use crate::lib::naming::get_operation_name;
 
 crate::api::schema::operations::utils::define_operation!(
 	UpdateAll {
-		on_call(data, arguments, query) -> {
+		on_call(data, arguments, query, session) -> {
 			let entity = &data.entity;
 			let collection = &entity.collection_name;
 
 			let mut update_query = AQLQuery::new(0);
 
-			update_query.method = AQLQueryMethod::Update(arguments.get::<EntitySet>("_set").unwrap().data);
+			let changes = apply_locale(
+				map_enum_input_values(arguments.get::<EntitySet>("_set").unwrap().data, entity),
+				entity,
+				arguments,
+			);
+			let filter = arguments.get::<RawInput<S>>("where").and_then(|w| serde_json::to_value(&w.0).ok());
+
+			update_query.returns_changes = has_after_hooks(entity, EventOperation::Update) || entity.versioning.is_some();
 			update_query.filter = get_aql_filter_from_args(arguments, data);
 			update_query.limit = arguments.get::<i32>("limit");
 
+			if let Some(permission_filter) = get_permission_filter(entity, session) {
+				update_query.add_filter(permission_filter);
+			}
+
+			let filter_access = check_filter_args_access(arguments, entity, session);
+
 			Box::pin(async move {
-				let create_data = execute_internal_query::<S>(update_query, collection, HashMap::new(), HashMap::new()).await;
+				filter_access?;
+
+				let changes = run_before_hooks::<S>(entity, EventOperation::Update, Some(changes), filter, session)
+					.await?
+					.unwrap_or_default();
+				let changes = store_uploads::<S>(entity, changes, None).await?;
+
+				update_query.method = AQLQueryMethod::Update(changes);
+
+				let create_data = execute_internal_query::<S>(update_query, collection, HashMap::new(), HashMap::new(), session).await?;
+
+				record_entity_revisions(entity, EventOperation::Update, &create_data, session).await;
+				run_after_hooks(entity, EventOperation::Update, &create_data, session).await;
+
 				let mut keys = Vec::new();
 
 				for row in create_data {
@@ -42,22 +76,13 @@ crate::api::schema::operations::utils::define_operation!(
 					collection,
 					QueryReturnType::Multiple,
 					HashMap::new(),
-					args
+					args,
+					session
 				).await
 			})
 		},
 		name(data) -> {
-			format!(
-				"update{}",
-				pluralizer::pluralize(
-					data.entity
-						.name
-						.to_case(convert_case::Case::Pascal)
-						.as_str(),
-					2,
-					false,
-				)
-			)
+			get_operation_name("update", &data.entity.name, true)
 		},
 		arguments(data, _registry) {
 			limit Option<i32> => &()
@@ -1,7 +1,7 @@
 macro_rules! define_operation {
 	(
 		$name:ident {
-			on_call($data:ident , $arguments:ident , $query:ident) -> $call_body:block,
+			on_call($data:ident , $arguments:ident , $query:ident , $session:ident) -> $call_body:block,
 			name($name_data:ident) -> $name_body:block,
 			arguments($args_data:ident, $args_registry:ident) {
 				$(
@@ -21,10 +21,12 @@ macro_rules! define_operation {
 				data: &'b crate::api::schema::operations::OperationData<S>,
 				arguments: &'b ::juniper::Arguments<S>,
 				query: crate::lib::database::aql::AQLQuery,
+				session: &'b crate::api::auth::Session,
 			) -> crate::api::schema::operations::FutureType<'b, S> {
 				let $data = data;
 				let $arguments = arguments;
 				let mut $query = query;
+				let $session = session;
 
 				$call_body
 			}
@@ -72,31 +74,9 @@ macro_rules! assign_parameters {
 	($args:expr, ($k:ident, $v:ident) -> $closure:tt) => {
 		for (key, value) in $args {
 			let $k = key.clone();
+			let $v = crate::api::schema::utils::convert_input_value_to_json_value(&value);
 
-			match value {
-				::juniper::InputValue::Scalar(s) => {
-					if let Some(int) = s.as_int() {
-						let $v = int;
-
-						$closure
-					} else if let Some(float) = s.as_float() {
-						let $v = float;
-
-						$closure
-					} else if let Some(str) = s.as_string() {
-						let $v = str;
-
-						$closure
-					}
-				}
-				_ => {
-					println!(
-						"WARN: Using non-scalar for query arguments ({}, {})",
-						key,
-						value.to_string()
-					)
-				}
-			}
+			$closure
 		}
 	};
 }
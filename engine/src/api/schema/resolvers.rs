@@ -0,0 +1,75 @@
+use juniper::BoxFuture;
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+
+use crate::api::auth::Session;
+use crate::api::schema::errors::ResolverError;
+use crate::api::schema::loader::RelationshipLoader;
+use crate::lib::database::api::{DbEntity, DbRelationship, DbScalarType};
+
+/// Resolves a field of an entity with Rust code instead of a stored attribute,
+/// e.g. to call an external service
+pub trait FieldResolver: Send + Sync {
+	/// Type of the resolved value
+	fn scalar_type(&self) -> DbScalarType;
+
+	/// Attributes of the parent document read by the resolver, only those are fetched along
+	/// with `_id` and `_key`. The whole document is fetched when `None`
+	fn attributes(&self) -> Option<Vec<String>> {
+		None
+	}
+
+	/// Resolves the field from the parent document, with the attributes it reads
+	fn resolve<'a>(
+		&'a self,
+		parent: &'a JsonValue,
+		context: &'a ResolverContext<'a>,
+	) -> BoxFuture<'a, Result<JsonValue, ResolverError>>;
+}
+
+/// Request a field is resolved for
+pub struct ResolverContext<'a> {
+	pub entity: &'a DbEntity,
+	pub session: &'a Session,
+	pub(crate) relationships: &'a [DbRelationship],
+	pub(crate) loader: &'a RelationshipLoader,
+}
+
+impl<'a> ResolverContext<'a> {
+	/// Documents related to the parent by the relationship `name` of the entity. The lookups
+	/// of the parents resolved together are batched into a single query
+	pub async fn load_related(
+		&self,
+		parent: &JsonValue,
+		name: &str,
+	) -> Result<Vec<JsonValue>, ResolverError> {
+		let relationship = self
+			.relationships
+			.iter()
+			.find(|r| r.name == name)
+			.ok_or_else(|| {
+				ResolverError::new(format!(
+					"Relationship {} of {} not found",
+					name, self.entity.name
+				))
+			})?;
+
+		let id = parent["_id"]
+			.as_str()
+			.ok_or_else(|| ResolverError::new(format!("Parent {} has no _id", self.entity.name)))?;
+
+		self.loader.load(relationship, id, self.session).await
+	}
+}
+
+/// A resolver registered for the field `field` of the entity `entity`
+pub struct FieldResolverEntry {
+	pub entity: String,
+	pub field: String,
+	pub resolver: Arc<dyn FieldResolver>,
+}
+
+/// Custom field resolvers added to the schema, register plugins here
+pub fn get_field_resolvers() -> Vec<FieldResolverEntry> {
+	Vec::new()
+}
@@ -0,0 +1,239 @@
+use juniper::meta::Field;
+use juniper::{
+	Arguments, ExecutionResult, IntoFieldError, Registry, ScalarValue, Selection, Spanning, Value,
+	ID,
+};
+use rust_arango::{AqlQuery, ClientError};
+use serde_json::Value as JsonValue;
+use tracing::Instrument;
+
+use crate::api::auth::permissions::get_permission_filter;
+use crate::api::auth::Session;
+use crate::api::schema::errors::{DatabaseError, ForbiddenError};
+use crate::api::schema::scalars::json::JsonScalar;
+use crate::api::schema::utils::JuniperValue;
+use crate::lib::database::api::DbEntity;
+use crate::lib::database::aql::{get_session_variables, AQLNode, AQLProperty};
+use crate::lib::database::DATABASE;
+use crate::lib::logging::{log_slow_query, query_span};
+use crate::lib::naming::format_operation_name;
+use crate::lib::revisions::get_revisions_collection;
+
+/// Field listing the prior versions of the documents of versioned entities
+pub const REVISIONS_FIELD: &str = "revisions";
+
+/// Version of a document before one of its updates or its removal
+#[derive(GraphQLObject)]
+pub struct EntityRevision {
+	/// Passed to `revert{Entity}` to bring the document back to this version
+	pub id: String,
+	pub operation: String,
+	pub user: Option<String>,
+	pub timestamp: String,
+	/// Attributes of the document at this version
+	pub data: JsonScalar,
+}
+
+/// Whether the entity has the `revisions` field, a property of the same name taking precedence
+pub fn has_revisions_field(entity: &DbEntity) -> bool {
+	entity.versioning.is_some() && !entity.properties.iter().any(|p| p.name == REVISIONS_FIELD)
+}
+
+pub fn build_revisions_field<'r, S>(registry: &mut Registry<'r, S>) -> Field<'r, S>
+where
+	S: ScalarValue + 'r,
+{
+	registry
+		.field::<Vec<EntityRevision>>(REVISIONS_FIELD, &())
+		.argument(registry.arg::<Option<i32>>("limit", &()))
+}
+
+/// Expression of the attributes of a revision, without the fields the session can't read
+pub fn get_revision_data(entity: &DbEntity, session: &Session) -> String {
+	let restricted: Vec<&String> = entity
+		.properties
+		.iter()
+		.chain(entity.get_variant_properties())
+		.filter(|p| !session.can_read_field(p))
+		.map(|p| &p.name)
+		.collect();
+
+	match restricted.is_empty() {
+		true => "r.data".to_string(),
+		false => format!(
+			"UNSET(r.data, {})",
+			serde_json::to_string(&restricted).unwrap()
+		),
+	}
+}
+
+/// AQL object of the selected fields of the revision `r`
+fn get_revision_object<S>(
+	selection_set: &[Selection<S>],
+	entity: &DbEntity,
+	session: &Session,
+) -> String
+where
+	S: ScalarValue,
+{
+	let mut fields = Vec::new();
+	let data = get_revision_data(entity, session);
+
+	for selection in selection_set {
+		if let Selection::Field(Spanning { item: ref f, .. }) = *selection {
+			let response_name = f.alias.as_ref().unwrap_or(&f.name).item;
+
+			let value = match f.name.item {
+				"id" => "r._key",
+				"operation" => "r.operation",
+				"user" => "r.user",
+				"timestamp" => "r.timestamp",
+				"data" => data.as_str(),
+				_ => "\"EntityRevision\"",
+			};
+
+			fields.push(format!(
+				"{}: {}",
+				serde_json::to_string(response_name).unwrap(),
+				value
+			));
+		}
+	}
+
+	format!("{{ {} }}", fields.join(", "))
+}
+
+fn get_limit<S>(args: &Arguments<S>) -> String
+where
+	S: ScalarValue,
+{
+	args.get::<i32>("limit")
+		.map(|l| format!("LIMIT {}", l.max(0)))
+		.unwrap_or_default()
+}
+
+/// Revisions of the document held by `variable_name`, the latest first
+pub fn get_revisions_property<S>(
+	selection_set: &[Selection<S>],
+	entity: &DbEntity,
+	args: &Arguments<S>,
+	response_name: String,
+	variable_name: &str,
+	session: &Session,
+) -> AQLProperty
+where
+	S: ScalarValue,
+{
+	let mut property = AQLProperty::new(response_name);
+
+	property.expression = Some(format!(
+		"(FOR r IN `{}` FILTER r.document == {}._key SORT r.timestamp DESC {} RETURN {})",
+		get_revisions_collection(&entity.collection_name),
+		variable_name,
+		get_limit(args),
+		get_revision_object(selection_set, entity, session)
+	));
+
+	property
+}
+
+/// Query listing the revisions of a document by its key, including those of removed documents
+/// which the `revisions` field can't reach
+pub fn get_revisions_query_name(entity: &DbEntity) -> String {
+	format_operation_name("get", &format!("{}Revisions", entity.name))
+}
+
+pub fn build_revisions_query<'r, S>(
+	registry: &mut Registry<'r, S>,
+	entity: &DbEntity,
+) -> Field<'r, S>
+where
+	S: ScalarValue + 'r,
+{
+	registry
+		.field::<Vec<EntityRevision>>(&get_revisions_query_name(entity), &())
+		.argument(registry.arg::<ID>("_key", &()))
+		.argument(registry.arg::<Option<i32>>("limit", &()))
+}
+
+/// Resolves the revisions query of the entity, the latest first. Only the revisions at which
+/// the document matched the permission filter of the session are listed
+pub async fn resolve_revisions<S>(
+	entity: &DbEntity,
+	arguments: &Arguments<'_, S>,
+	selection_set: &[Selection<'_, S>],
+	session: &Session,
+) -> ExecutionResult<S>
+where
+	S: ScalarValue,
+{
+	if !session.can_read(entity) {
+		return Err(ForbiddenError::new(format!(
+			"Role {} is not allowed to read {}",
+			session.role, entity.name
+		))
+		.into_field_error());
+	}
+
+	let filter = get_permission_filter(entity, session);
+	let session_variables = get_session_variables(filter.as_deref());
+
+	// The permission filter applies to the document as it was at the revision
+	let permission_filter = filter
+		.map(|filter| {
+			format!(
+				"LET i_1 = MERGE(r.data, {{ _key: r.document }}) FILTER {}",
+				filter.describe(1)
+			)
+		})
+		.unwrap_or_default();
+
+	let aql = format!(
+		"FOR r IN @@revisions FILTER r.document == @key {} SORT r.timestamp DESC {} RETURN {}",
+		permission_filter,
+		get_limit(arguments),
+		get_revision_object(selection_set, entity, session)
+	);
+
+	let mut bind_vars: Vec<(String, JsonValue)> = vec![
+		(
+			"@revisions".to_string(),
+			get_revisions_collection(&entity.collection_name).into(),
+		),
+		(
+			"key".to_string(),
+			arguments
+				.get::<ID>("_key")
+				.map(|key| key.to_string())
+				.unwrap_or_default()
+				.into(),
+		),
+	];
+
+	bind_vars.extend(session.get_bind_vars(&session_variables));
+
+	let span = query_span(&aql, &bind_vars);
+	let time = std::time::Instant::now();
+
+	let revisions: Result<Vec<JuniperValue<S>>, ClientError> = DATABASE
+		.get()
+		.await
+		.run(|database| {
+			let mut revisions_query = AqlQuery::builder().query(&aql);
+
+			for (k, v) in &bind_vars {
+				revisions_query = revisions_query.bind_var(k.clone(), v.clone());
+			}
+
+			async move { database.aql_query(revisions_query.build()).await }
+		})
+		.instrument(span)
+		.await;
+
+	log_slow_query(&aql, &bind_vars, time.elapsed());
+
+	match revisions {
+		Ok(revisions) => Ok(Value::list(revisions.into_iter().map(|r| r.0).collect())),
+		Err(e) => Err(DatabaseError::from(e).into_field_error()),
+	}
+}
@@ -0,0 +1,31 @@
+use juniper::parser::{ParseError, ScalarToken, Token};
+use juniper::{InputValue, ParseScalarResult, ScalarValue, Value};
+
+use crate::api::schema::scalars::value::{scalar_from_i64, scalar_to_i64};
+
+/// 64-bit integer for values that don't fit in a GraphQL `Int`
+pub struct BigIntScalar(pub i64);
+
+#[juniper::graphql_scalar(name = "BigInt", description = "64-bit signed integer")]
+impl<S> GraphQLScalar for BigIntScalar
+where
+	S: ScalarValue,
+{
+	fn resolve(&self) -> Value {
+		Value::Scalar(scalar_from_i64(self.0))
+	}
+
+	fn from_input_value(value: &InputValue<S>) -> Option<BigIntScalar> {
+		value.as_scalar().and_then(scalar_to_i64).map(BigIntScalar)
+	}
+
+	fn from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+		if let ScalarToken::Int(v) = value {
+			v.parse::<i64>()
+				.map(scalar_from_i64)
+				.map_err(|_| ParseError::UnexpectedToken(Token::Scalar(value)))
+		} else {
+			Err(ParseError::UnexpectedToken(Token::Scalar(value)))
+		}
+	}
+}
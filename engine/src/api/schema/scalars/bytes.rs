@@ -0,0 +1,33 @@
+use juniper::parser::{ParseError, ScalarToken, Token};
+use juniper::{InputValue, ParseScalarResult, ScalarValue, Value};
+
+use crate::lib::CONFIG;
+
+/// Binary data encoded as standard base64, limited to `BYTES_MAX_SIZE` decoded bytes
+pub struct BytesScalar(pub Vec<u8>);
+
+#[juniper::graphql_scalar(name = "Bytes", description = "Base64 encoded binary data")]
+impl<S> GraphQLScalar for BytesScalar
+where
+	S: ScalarValue,
+{
+	fn resolve(&self) -> Value {
+		Value::scalar(base64::encode(&self.0))
+	}
+
+	fn from_input_value(value: &InputValue<S>) -> Option<BytesScalar> {
+		value
+			.as_string_value()
+			.and_then(|v| base64::decode(v).ok())
+			.filter(|bytes| bytes.len() <= CONFIG.get_bytes_max_size())
+			.map(BytesScalar)
+	}
+
+	fn from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+		if let ScalarToken::String(v) = value {
+			Ok(S::from(v.to_owned()))
+		} else {
+			Err(ParseError::UnexpectedToken(Token::Scalar(value)))
+		}
+	}
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use juniper::{InputValue, ScalarValue, Value};
+
+/// RFC 3339 date and time, inputs with any offset are normalized to UTC
+pub struct DateTimeScalar(pub DateTime<Utc>);
+
+#[juniper::graphql_scalar(name = "DateTime", description = "RFC 3339 date and time")]
+impl<S> GraphQLScalar for DateTimeScalar
+where
+	S: ScalarValue,
+{
+	fn resolve(&self) -> Value {
+		Value::scalar(self.0.to_rfc3339())
+	}
+
+	fn from_input_value(value: &InputValue<S>) -> Option<DateTimeScalar> {
+		value
+			.as_string_value()
+			.and_then(parse_datetime)
+			.map(DateTimeScalar)
+	}
+
+	fn from_str(value: juniper::ScalarToken) -> juniper::ParseScalarResult<S> {
+		<String as juniper::ParseScalarValue<S>>::from_str(value)
+	}
+}
+
+pub fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+	DateTime::parse_from_rfc3339(value)
+		.ok()
+		.map(|d| d.with_timezone(&Utc))
+}
@@ -0,0 +1,47 @@
+use juniper::parser::{ParseError, ScalarToken, Token};
+use juniper::{InputValue, ParseScalarResult, ScalarValue, Value};
+
+/// Arbitrary precision decimal kept as a string so it never goes through a float
+pub struct DecimalScalar(pub String);
+
+#[juniper::graphql_scalar(
+	name = "Decimal",
+	description = "Arbitrary precision decimal number encoded as a string"
+)]
+impl<S> GraphQLScalar for DecimalScalar
+where
+	S: ScalarValue,
+{
+	fn resolve(&self) -> Value {
+		Value::scalar(self.0.clone())
+	}
+
+	fn from_input_value(value: &InputValue<S>) -> Option<DecimalScalar> {
+		value
+			.as_string_value()
+			.filter(|v| is_decimal(v))
+			.map(|v| DecimalScalar(v.to_string()))
+	}
+
+	fn from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+		if let ScalarToken::String(v) = value {
+			Ok(S::from(v.to_owned()))
+		} else {
+			Err(ParseError::UnexpectedToken(Token::Scalar(value)))
+		}
+	}
+}
+
+/// Checks for an optionally signed number with an optional fractional part, e.g. `-12.50`
+pub fn is_decimal(value: &str) -> bool {
+	let digits = value.strip_prefix('-').unwrap_or(value);
+
+	let (integer, fraction) = match digits.split_once('.') {
+		Some((integer, fraction)) => (integer, Some(fraction)),
+		None => (digits, None),
+	};
+
+	let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+	is_digits(integer) && fraction.map_or(true, is_digits)
+}
@@ -1 +1,8 @@
+pub mod bigint;
+pub mod bytes;
+pub mod datetime;
+pub mod decimal;
 pub mod json;
+pub mod upload;
+pub mod uuid;
+pub mod value;
@@ -0,0 +1,26 @@
+use juniper::{InputValue, ScalarValue, Value};
+
+/// File sent along a multipart request, replaced by a token in its variables, or the id of a
+/// file uploaded before
+pub struct UploadScalar(pub String);
+
+#[juniper::graphql_scalar(
+	name = "Upload",
+	description = "File of a multipart request, or the id of an uploaded file"
+)]
+impl<S> GraphQLScalar for UploadScalar
+where
+	S: ScalarValue,
+{
+	fn resolve(&self) -> Value {
+		Value::scalar(self.0.clone())
+	}
+
+	fn from_input_value(value: &InputValue<S>) -> Option<UploadScalar> {
+		value.as_string_value().map(|v| UploadScalar(v.to_string()))
+	}
+
+	fn from_str(value: juniper::ScalarToken) -> juniper::ParseScalarResult<S> {
+		<String as juniper::ParseScalarValue<S>>::from_str(value)
+	}
+}
@@ -0,0 +1,25 @@
+use juniper::{InputValue, ScalarValue, Value};
+use uuid::Uuid;
+
+pub struct UuidScalar(pub Uuid);
+
+#[juniper::graphql_scalar(name = "UUID", description = "Hyphenated UUID string")]
+impl<S> GraphQLScalar for UuidScalar
+where
+	S: ScalarValue,
+{
+	fn resolve(&self) -> Value {
+		Value::scalar(self.0.hyphenated().to_string())
+	}
+
+	fn from_input_value(value: &InputValue<S>) -> Option<UuidScalar> {
+		value
+			.as_string_value()
+			.and_then(|v| Uuid::parse_str(v).ok())
+			.map(UuidScalar)
+	}
+
+	fn from_str(value: juniper::ScalarToken) -> juniper::ParseScalarResult<S> {
+		<String as juniper::ParseScalarValue<S>>::from_str(value)
+	}
+}
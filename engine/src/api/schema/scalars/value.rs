@@ -0,0 +1,149 @@
+use std::fmt;
+
+use juniper::ScalarValue;
+use serde::de;
+
+/// Scalar values of the API schema, 64-bit integers outside of the `Int` range are kept as `BigInt`
+#[derive(Debug, PartialEq, Clone, GraphQLScalarValue)]
+pub enum AlchemyScalarValue {
+	Int(i32),
+	BigInt(i64),
+	Float(f64),
+	String(String),
+	Boolean(bool),
+}
+
+impl ScalarValue for AlchemyScalarValue {
+	type Visitor = AlchemyScalarValueVisitor;
+
+	fn as_int(&self) -> Option<i32> {
+		match *self {
+			Self::Int(ref i) => Some(*i),
+			_ => None,
+		}
+	}
+
+	fn as_float(&self) -> Option<f64> {
+		match *self {
+			Self::Int(ref i) => Some(*i as f64),
+			Self::BigInt(ref i) => Some(*i as f64),
+			Self::Float(ref f) => Some(*f),
+			_ => None,
+		}
+	}
+
+	fn as_str(&self) -> Option<&str> {
+		match *self {
+			Self::String(ref s) => Some(s.as_str()),
+			_ => None,
+		}
+	}
+
+	fn as_string(&self) -> Option<String> {
+		match *self {
+			Self::String(ref s) => Some(s.clone()),
+			_ => None,
+		}
+	}
+
+	fn into_string(self) -> Option<String> {
+		match self {
+			Self::String(s) => Some(s),
+			_ => None,
+		}
+	}
+
+	fn as_boolean(&self) -> Option<bool> {
+		match *self {
+			Self::Boolean(ref b) => Some(*b),
+			_ => None,
+		}
+	}
+
+	fn into_another<S: ScalarValue>(self) -> S {
+		match self {
+			Self::Int(i) => S::from(i),
+			Self::BigInt(i) => scalar_from_i64(i),
+			Self::Float(f) => S::from(f),
+			Self::String(s) => S::from(s),
+			Self::Boolean(b) => S::from(b),
+		}
+	}
+}
+
+impl<'a> From<&'a str> for AlchemyScalarValue {
+	fn from(s: &'a str) -> Self {
+		Self::String(s.into())
+	}
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct AlchemyScalarValueVisitor;
+
+impl<'de> de::Visitor<'de> for AlchemyScalarValueVisitor {
+	type Value = AlchemyScalarValue;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a valid input value")
+	}
+
+	fn visit_bool<E>(self, value: bool) -> Result<AlchemyScalarValue, E> {
+		Ok(AlchemyScalarValue::Boolean(value))
+	}
+
+	fn visit_i64<E>(self, value: i64) -> Result<AlchemyScalarValue, E>
+	where
+		E: de::Error,
+	{
+		match i32::try_from(value) {
+			Ok(value) => Ok(AlchemyScalarValue::Int(value)),
+			Err(_) => Ok(AlchemyScalarValue::BigInt(value)),
+		}
+	}
+
+	fn visit_u64<E>(self, value: u64) -> Result<AlchemyScalarValue, E>
+	where
+		E: de::Error,
+	{
+		match i64::try_from(value) {
+			Ok(value) => self.visit_i64(value),
+			Err(_) => Ok(AlchemyScalarValue::Float(value as f64)),
+		}
+	}
+
+	fn visit_f64<E>(self, value: f64) -> Result<AlchemyScalarValue, E> {
+		Ok(AlchemyScalarValue::Float(value))
+	}
+
+	fn visit_str<E>(self, value: &str) -> Result<AlchemyScalarValue, E>
+	where
+		E: de::Error,
+	{
+		self.visit_string(value.into())
+	}
+
+	fn visit_string<E>(self, value: String) -> Result<AlchemyScalarValue, E> {
+		Ok(AlchemyScalarValue::String(value))
+	}
+}
+
+/// Builds a scalar from a 64-bit integer through its visitor, so scalar values
+/// without a 64-bit representation fall back to whatever they deserialize into
+pub fn scalar_from_i64<S>(value: i64) -> S
+where
+	S: ScalarValue,
+{
+	de::Visitor::visit_i64::<de::value::Error>(S::Visitor::default(), value)
+		.expect("Scalar value visitor rejected an integer")
+}
+
+/// Reads a 64-bit integer from any scalar value representing one
+pub fn scalar_to_i64<S>(value: &S) -> Option<i64>
+where
+	S: ScalarValue,
+{
+	match value.as_int() {
+		Some(i) => Some(i as i64),
+		None => serde_json::to_value(value).ok()?.as_i64(),
+	}
+}
@@ -1,34 +1,39 @@
-use juniper::{Object, ScalarValue, Value};
+use juniper::{FromInputValue, InputValue, Object, ScalarValue, Value};
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
 use serde_json::{Map as JsonMap, Number as JsonNumber, Value as JsonValue};
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::api::schema::scalars::value::{scalar_from_i64, scalar_to_i64};
+
+/// Any argument value, e.g. to send it to an external handler as JSON
+pub struct RawInput<S>(pub InputValue<S>);
+
+impl<S> FromInputValue<S> for RawInput<S>
+where
+	S: ScalarValue,
+{
+	fn from_input_value(value: &InputValue<S>) -> Option<Self> {
+		Some(RawInput(value.clone()))
+	}
+}
+
+fn convert_integer<S>(v: i64) -> Value<S>
+where
+	S: ScalarValue,
+{
+	match i32::try_from(v) {
+		Ok(v) => Value::scalar(v),
+		Err(_) => Value::Scalar(scalar_from_i64(v)),
+	}
+}
 
 fn convert_number<S>(n: &JsonNumber) -> Value<S>
 where
 	S: ScalarValue,
 {
-	return if n.is_i64() {
-		let v = n.as_i64().unwrap();
-
-		let res = if v > i32::MAX as i64 {
-			i32::MAX
-		} else if v < i32::MIN as i64 {
-			i32::MIN
-		} else {
-			v as i32
-		};
-
-		Value::scalar(res)
-	} else if n.is_u64() {
-		let v = n.as_u64().unwrap();
-
-		let res = if v > i32::MAX as u64 {
-			i32::MAX
-		} else if v < i32::MIN as u64 {
-			i32::MIN
-		} else {
-			v as i32
-		};
-
-		Value::scalar(res)
+	return if let Some(v) = n.as_i64() {
+		convert_integer(v)
 	} else {
 		let v = n.as_f64().unwrap();
 
@@ -36,29 +41,168 @@ where
 	};
 }
 
-pub fn convert_json_to_juniper_value<S>(data: &JsonMap<String, JsonValue>) -> Value<S>
+/// Moves the strings and keys of the JSON value into the GraphQL one instead of copying them
+pub fn convert_json_value_to_juniper_value<S>(val: JsonValue) -> Value<S>
+where
+	S: ScalarValue,
+{
+	match val {
+		JsonValue::Null => Value::null(),
+		JsonValue::Bool(v) => Value::scalar(v),
+		JsonValue::Number(n) => convert_number(&n),
+		JsonValue::String(s) => Value::scalar(s),
+		JsonValue::Array(a) => Value::list(
+			a.into_iter()
+				.map(convert_json_value_to_juniper_value)
+				.collect(),
+		),
+		JsonValue::Object(o) => convert_json_to_juniper_value(o),
+	}
+}
+
+pub fn convert_json_to_juniper_value<S>(data: JsonMap<String, JsonValue>) -> Value<S>
 where
 	S: ScalarValue,
 {
 	let mut object = Object::<S>::with_capacity(data.len());
 
-	fn convert<S>(val: &JsonValue) -> Value<S>
+	for (key, val) in data {
+		object.add_field(key, convert_json_value_to_juniper_value(val));
+	}
+
+	Value::Object(object)
+}
+
+/// JSON value of an argument, e.g. to bind it to a query. Enums are given by their name
+pub fn convert_input_value_to_json_value<S>(value: &InputValue<S>) -> JsonValue
+where
+	S: ScalarValue,
+{
+	match value {
+		InputValue::Null => JsonValue::Null,
+		InputValue::Scalar(ref s) => {
+			if let Some(i) = scalar_to_i64(s) {
+				JsonValue::Number(i.into())
+			} else if let Some(f) = s.as_float() {
+				JsonValue::Number(JsonNumber::from_f64(f).unwrap_or(0i8.into()))
+			} else if let Some(str) = s.as_string() {
+				JsonValue::String(str)
+			} else if let Some(b) = s.as_boolean() {
+				JsonValue::Bool(b)
+			} else {
+				JsonValue::Null
+			}
+		}
+		InputValue::Enum(v) | InputValue::Variable(v) => JsonValue::String(v.to_string()),
+		InputValue::List(l) => JsonValue::Array(
+			l.iter()
+				.map(|i| convert_input_value_to_json_value(&i.item))
+				.collect(),
+		),
+		InputValue::Object(ref o) => JsonValue::Object(
+			o.iter()
+				.map(|(k, v)| (k.item.clone(), convert_input_value_to_json_value(&v.item)))
+				.collect(),
+		),
+	}
+}
+
+/// GraphQL value deserialized straight from the JSON returned by ArangoDB, so query results
+/// don't go through a `JsonValue` that has to be converted afterwards
+pub struct JuniperValue<S>(pub Value<S>);
+
+impl<'de, S> Deserialize<'de> for JuniperValue<S>
+where
+	S: ScalarValue,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
 	where
-		S: ScalarValue,
+		D: Deserializer<'de>,
 	{
-		match val {
-			JsonValue::Null => Value::null(),
-			JsonValue::Bool(v) => Value::scalar(v.to_owned()),
-			JsonValue::Number(n) => convert_number(n),
-			JsonValue::String(s) => Value::scalar(s.to_owned()),
-			JsonValue::Array(a) => Value::list(a.iter().map(|i| convert(i)).collect()),
-			JsonValue::Object(ref o) => convert_json_to_juniper_value(o),
-		}
+		deserializer
+			.deserialize_any(JuniperValueVisitor(PhantomData))
+			.map(JuniperValue)
 	}
+}
 
-	for (key, val) in data {
-		object.add_field(key, convert(val));
+struct JuniperValueVisitor<S>(PhantomData<S>);
+
+impl<'de, S> Visitor<'de> for JuniperValueVisitor<S>
+where
+	S: ScalarValue,
+{
+	type Value = Value<S>;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a JSON value")
 	}
 
-	Value::Object(object)
+	fn visit_unit<E>(self) -> Result<Value<S>, E> {
+		Ok(Value::null())
+	}
+
+	fn visit_none<E>(self) -> Result<Value<S>, E> {
+		Ok(Value::null())
+	}
+
+	fn visit_some<D>(self, deserializer: D) -> Result<Value<S>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_any(self)
+	}
+
+	fn visit_bool<E>(self, v: bool) -> Result<Value<S>, E> {
+		Ok(Value::scalar(v))
+	}
+
+	fn visit_i64<E>(self, v: i64) -> Result<Value<S>, E> {
+		Ok(convert_integer(v))
+	}
+
+	// Like `JsonNumber::as_i64`, integers too large for an `i64` are kept as floats
+	fn visit_u64<E>(self, v: u64) -> Result<Value<S>, E> {
+		Ok(match i64::try_from(v) {
+			Ok(v) => convert_integer(v),
+			Err(_) => Value::scalar(v as f64),
+		})
+	}
+
+	fn visit_f64<E>(self, v: f64) -> Result<Value<S>, E> {
+		Ok(Value::scalar(v))
+	}
+
+	fn visit_str<E>(self, v: &str) -> Result<Value<S>, E> {
+		Ok(Value::scalar(v.to_string()))
+	}
+
+	fn visit_string<E>(self, v: String) -> Result<Value<S>, E> {
+		Ok(Value::scalar(v))
+	}
+
+	fn visit_seq<A>(self, mut seq: A) -> Result<Value<S>, A::Error>
+	where
+		A: SeqAccess<'de>,
+	{
+		let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+		while let Some(JuniperValue(value)) = seq.next_element()? {
+			list.push(value);
+		}
+
+		Ok(Value::list(list))
+	}
+
+	fn visit_map<A>(self, mut map: A) -> Result<Value<S>, A::Error>
+	where
+		A: MapAccess<'de>,
+	{
+		let mut object = Object::with_capacity(map.size_hint().unwrap_or(0));
+
+		while let Some((key, JuniperValue(value))) = map.next_entry::<String, JuniperValue<S>>()? {
+			object.add_field(key, value);
+		}
+
+		Ok(Value::Object(object))
+	}
 }
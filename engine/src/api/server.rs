@@ -1,21 +1,394 @@
+use std::future::Future;
+use std::sync::Arc;
+
 use actix_web::{
-	web::{Data, Payload as ActixPayload},
+	dev::{Service, ServiceRequest, ServiceResponse},
+	http::header::{self, HeaderName, HeaderValue},
+	web::Payload as ActixPayload,
 	Error as ActixError, HttpRequest as ActixRequest, HttpResponse as ActixResponse,
 };
-use std::sync::Mutex;
+use futures_util::future::join_all;
+use futures_util::FutureExt;
+use juniper::http::GraphQLRequest;
+use std::panic::AssertUnwindSafe;
+use tracing::Instrument;
+
+use juniper_actix::playground_handler;
+
+use crate::api::analysis::{check_introspection, check_query_limits};
+use crate::api::auth::Session;
+use crate::api::incremental::{
+	get_incremental_plan, incremental_response, strip_incremental_directives, IncrementalDelivery,
+};
+use crate::api::persisted::resolve_persisted_query;
+use crate::api::rate_limit::RATE_LIMITER;
+use crate::api::request::{ApiBatchRequest, ApiRequest};
+use crate::api::response_cache::{get_cache_policy, RESPONSE_CACHE};
+use crate::api::root_fields::inline_root_fragments;
+use crate::api::schema::directives::print_schema;
+use crate::api::schema::errors::{get_panic_message, ErrorCode};
+use crate::api::schema::{AlchemyScalarValue, Context, Schema};
+use crate::api::tenants::TenantSchema;
+use crate::lib::database::reads::scope_reads;
+use crate::lib::logging::set_remote_parent;
+use crate::lib::CONFIG;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Set to `true` to read from the leader instead of the replicas, e.g. right after a write
+/// made by another request
+pub const READ_LEADER_HEADER: &str = "x-alchemy-read-leader";
 
-use juniper_actix::{graphql_handler, playground_handler};
+tokio::task_local! {
+	/// Id of the API request being handled, reported with the errors of its panics
+	static REQUEST_ID: String;
+}
 
-use crate::api::schema::Schema;
+/// Id of the API request being handled by the current task
+pub fn get_current_request_id() -> Option<String> {
+	REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+pub fn error_response(
+	mut response: actix_web::HttpResponseBuilder,
+	message: String,
+) -> ActixResponse {
+	response.json(serde_json::json!({
+		"errors": [{ "message": message }]
+	}))
+}
+
+fn get_coded_error(code: ErrorCode, message: String) -> serde_json::Value {
+	serde_json::json!({ "message": message, "extensions": { "code": code.as_str() } })
+}
 
+/// Error response with the code of the error in its `extensions`
+pub fn coded_error_response(
+	mut response: actix_web::HttpResponseBuilder,
+	code: ErrorCode,
+	message: String,
+) -> ActixResponse {
+	response.json(serde_json::json!({
+		"errors": [get_coded_error(code, message)]
+	}))
+}
+
+/// Response of a request over the rate limit of its role, which can be sent again after
+/// `retry_after` seconds
+pub fn rate_limited_response(retry_after: u64) -> ActixResponse {
+	let mut response = ActixResponse::TooManyRequests();
+
+	response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+
+	coded_error_response(
+		response,
+		ErrorCode::RateLimited,
+		"Rate limit exceeded".to_string(),
+	)
+}
+
+/// Middleware sending the reads of a request to the replicas, or to the leader when asked to
+/// by the `x-alchemy-read-leader` header. They are sent to the leader once it has written
+pub fn scope_request_reads<S, B>(
+	req: ServiceRequest,
+	srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, ActixError>>
+where
+	S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+{
+	let leader = req
+		.headers()
+		.get(READ_LEADER_HEADER)
+		.and_then(|h| h.to_str().ok())
+		.map(|h| h.eq_ignore_ascii_case("true"))
+		.unwrap_or(false);
+
+	scope_reads(leader, srv.call(req))
+}
+
+/// Id of the request sent by the client or a new one, logged with everything done for it
+fn get_request_id(req: &ActixRequest) -> String {
+	req.headers()
+		.get(REQUEST_ID_HEADER)
+		.and_then(|h| h.to_str().ok())
+		.filter(|h| !h.is_empty())
+		.map(|h| h.to_string())
+		.unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Handles the API request in a span holding its id, operation and user, the id is sent back
+/// in the `x-request-id` header. The span continues the trace of the `traceparent` header.
+/// A panic while handling it is answered with an error instead of dropping the connection
 pub async fn graphql_api_route(
 	req: ActixRequest,
 	payload: ActixPayload,
-	schema: Data<Mutex<Schema>>,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let request_id = get_request_id(&req);
+
+	let span = tracing::info_span!(
+		"request",
+		otel.kind = "server",
+		http.method = %req.method(),
+		http.target = req.path(),
+		request_id = request_id.as_str(),
+		operation = tracing::field::Empty,
+		role = tracing::field::Empty,
+		user = tracing::field::Empty,
+	);
+
+	set_remote_parent(&span, req.headers());
+
+	let time = std::time::Instant::now();
+
+	let handled = AssertUnwindSafe(REQUEST_ID.scope(
+		request_id.clone(),
+		handle_api_request(req, payload, schema).instrument(span.clone()),
+	))
+	.catch_unwind()
+	.await;
+
+	let mut response = match handled {
+		Ok(response) => response?,
+		Err(panic) => {
+			span.in_scope(|| {
+				tracing::error!("Request panicked: {}", get_panic_message(panic.as_ref()))
+			});
+
+			coded_error_response(
+				ActixResponse::InternalServerError(),
+				ErrorCode::InternalError,
+				format!("Internal error, request id {}", request_id),
+			)
+		}
+	};
+
+	span.in_scope(|| {
+		tracing::info!(
+			status = response.status().as_u16(),
+			elapsed = ?time.elapsed(),
+			"Handled request"
+		)
+	});
+
+	if let Ok(value) = HeaderValue::from_str(&request_id) {
+		response
+			.headers_mut()
+			.insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+	}
+
+	Ok(response)
+}
+
+async fn handle_api_request(
+	req: ActixRequest,
+	payload: ActixPayload,
+	schema: TenantSchema,
+) -> Result<ActixResponse, ActixError> {
+	let session = match Session::from_request(&req) {
+		Ok(session) => session,
+		Err(e) => return Ok(error_response(ActixResponse::Unauthorized(), e.to_string())),
+	};
+
+	let span = tracing::Span::current();
+
+	span.record("role", session.role.as_str());
+
+	if let Some(user_id) = &session.user_id {
+		span.record("user", user_id.as_str());
+	}
+
+	let request = ApiBatchRequest::from_http(&req, payload).await?;
+
+	// Each operation of a batch is counted
+	let tokens = request.operation_count().min(u32::MAX as usize) as u32;
+
+	if let Err(retry_after) = RATE_LIMITER.check_tokens(&req, &session, tokens).await {
+		return Ok(rate_limited_response(retry_after));
+	}
+
+	execute_api_request(
+		session,
+		request,
+		&schema,
+		IncrementalDelivery::from_request(&req),
+	)
+	.await
+}
+
+/// Resolves the persisted query of the operation and checks it before it's executed
+fn prepare_api_request(request: &mut ApiRequest, schema: &Schema) -> Result<(), String> {
+	resolve_persisted_query(request)?;
+	check_introspection(request, &schema.schema)?;
+	check_query_limits(request, schema)
+}
+
+/// Executes the operations of the request, once the session is checked. Operations with
+/// `@defer` or `@stream` are delivered incrementally when the client accepts it
+pub async fn execute_api_request(
+	session: Session,
+	request: ApiBatchRequest,
+	schema: &TenantSchema,
+	delivery: Option<IncrementalDelivery>,
 ) -> Result<ActixResponse, ActixError> {
-	graphql_handler(&schema.lock().unwrap(), &(), req, payload).await
+	// The schema in use for the whole request, even if it is reloaded meanwhile
+	let schema = schema.load_full();
+
+	let mut request = match request {
+		ApiBatchRequest::Single(request) => request,
+		ApiBatchRequest::Batch(requests) => {
+			return execute_api_batch(session, requests, schema).await
+		}
+	};
+
+	if let Some(operation_name) = &request.operation_name {
+		tracing::Span::current().record("operation", operation_name.as_str());
+	}
+
+	let prepared = tracing::info_span!("graphql.validate")
+		.in_scope(|| prepare_api_request(&mut request, &schema));
+
+	if let Err(e) = prepared {
+		return Ok(coded_error_response(
+			ActixResponse::BadRequest(),
+			ErrorCode::ValidationFailed,
+			e,
+		));
+	}
+
+	if let Some(delivery) = delivery {
+		if let Some(plan) = get_incremental_plan(&request, &schema) {
+			return Ok(incremental_response(
+				session, request, plan, schema, delivery,
+			));
+		}
+	}
+
+	strip_incremental_directives(&mut request);
+	inline_root_fragments(&mut request);
+
+	let cache_policy = get_cache_policy(&request, &schema, &session);
+
+	let versions = match cache_policy {
+		Some(ref policy) => {
+			let versions = RESPONSE_CACHE.get_versions(policy).await;
+
+			if let Some(body) = RESPONSE_CACHE.get(policy, &versions).await {
+				return Ok(ActixResponse::Ok()
+					.content_type("application/json")
+					.insert_header((header::CACHE_CONTROL, policy.get_cache_control()))
+					.body(body));
+			}
+
+			versions
+		}
+		None => Vec::new(),
+	};
+
+	let context = Context::new(session);
+
+	let request: GraphQLRequest<AlchemyScalarValue> = request.into();
+	let response = request
+		.execute(&schema, &context)
+		.instrument(tracing::info_span!("graphql.execute"))
+		.await;
+
+	let body = serde_json::to_string(&response)?;
+
+	if !response.is_ok() {
+		return Ok(ActixResponse::BadRequest()
+			.content_type("application/json")
+			.body(body));
+	}
+
+	let mut builder = ActixResponse::Ok();
+
+	// Responses with field errors aren't cached, they may be transient
+	let cache_policy = cache_policy
+		.filter(|_| serde_json::to_value(&response).map_or(false, |r| r.get("errors").is_none()));
+
+	if let Some(policy) = cache_policy {
+		RESPONSE_CACHE.set(&policy, versions, body.clone()).await;
+
+		builder.insert_header((header::CACHE_CONTROL, policy.get_cache_control()));
+	}
+
+	Ok(builder.content_type("application/json").body(body))
+}
+
+/// Executes the operations of a batch concurrently, responding with their results in the
+/// same order. Operations failing their checks only fail their own result
+async fn execute_api_batch(
+	session: Session,
+	requests: Vec<ApiRequest>,
+	schema: Arc<Schema>,
+) -> Result<ActixResponse, ActixError> {
+	if requests.is_empty() {
+		return Ok(error_response(
+			ActixResponse::BadRequest(),
+			"Batch has no operations".to_string(),
+		));
+	}
+
+	if let Some(max_batch_size) = CONFIG.get_max_batch_size() {
+		if requests.len() > max_batch_size {
+			return Ok(error_response(
+				ActixResponse::BadRequest(),
+				format!(
+					"Batch of {} operations exceeds the maximum of {}",
+					requests.len(),
+					max_batch_size
+				),
+			));
+		}
+	}
+
+	let context = Context::new(session);
+
+	let responses = join_all(requests.into_iter().map(|mut request| {
+		let schema = &schema;
+		let context = &context;
+
+		async move {
+			let prepared = tracing::info_span!("graphql.validate")
+				.in_scope(|| prepare_api_request(&mut request, schema));
+
+			if let Err(e) = prepared {
+				return serde_json::json!({ "errors": [get_coded_error(ErrorCode::ValidationFailed, e)] });
+			}
+
+			strip_incremental_directives(&mut request);
+			inline_root_fragments(&mut request);
+
+			let request: GraphQLRequest<AlchemyScalarValue> = request.into();
+			let response = request
+				.execute(schema, context)
+				.instrument(tracing::info_span!("graphql.execute"))
+				.await;
+
+			serde_json::to_value(&response)
+				.unwrap_or_else(|e| serde_json::json!({ "errors": [{ "message": e.to_string() }] }))
+		}
+	}))
+	.await;
+
+	Ok(ActixResponse::Ok().json(responses))
 }
 
 pub async fn playground_api_route() -> Result<ActixResponse, ActixError> {
 	playground_handler("/api/graphql", Some("/api/graphql_subscriptions")).await
 }
+
+/// Serves the SDL of the current schema for code generators, it describes the API like the
+/// GraphQL introspection so it's only available when introspection is enabled
+pub async fn sdl_route(schema: TenantSchema) -> Result<ActixResponse, ActixError> {
+	if !CONFIG.is_introspection_enabled() {
+		return Ok(error_response(
+			ActixResponse::NotFound(),
+			"GraphQL schema is not available".to_string(),
+		));
+	}
+
+	Ok(ActixResponse::Ok()
+		.content_type("application/graphql; charset=utf-8")
+		.body(print_schema(&schema.load())))
+}
@@ -0,0 +1,108 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use juniper::{ExecutionResult, ScalarValue, Value};
+use lazy_static::lazy_static;
+
+/// Latencies kept per operation for the percentiles, the oldest being dropped first
+const LATENCY_WINDOW: usize = 1000;
+
+#[derive(Default)]
+struct OperationCounters {
+	count: usize,
+	errors: usize,
+	rows: usize,
+	latencies: VecDeque<Duration>,
+}
+
+/// Executions of an operation since startup, the percentiles being of the latest ones
+pub struct OperationStatsSnapshot {
+	pub name: String,
+	pub count: usize,
+	pub errors: usize,
+	pub rows: usize,
+	pub p50: Duration,
+	pub p95: Duration,
+}
+
+/// Execution stats of the registered operations, kept in memory
+pub struct OperationStats {
+	operations: Mutex<HashMap<String, OperationCounters>>,
+}
+
+impl OperationStats {
+	fn new() -> OperationStats {
+		OperationStats {
+			operations: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Counts an execution of the operation, with the documents it returned
+	pub fn record<S: ScalarValue>(
+		&self,
+		name: &str,
+		elapsed: Duration,
+		result: &ExecutionResult<S>,
+	) {
+		let mut operations = self.operations.lock().unwrap();
+		let counters = operations.entry(name.to_string()).or_default();
+
+		counters.count += 1;
+
+		match result {
+			Ok(Value::List(documents)) => counters.rows += documents.len(),
+			Ok(Value::Null) => (),
+			Ok(_) => counters.rows += 1,
+			Err(_) => counters.errors += 1,
+		}
+
+		if counters.latencies.len() == LATENCY_WINDOW {
+			counters.latencies.pop_front();
+		}
+
+		counters.latencies.push_back(elapsed);
+	}
+
+	/// Stats of every executed operation, sorted by name
+	pub fn get_snapshots(&self) -> Vec<OperationStatsSnapshot> {
+		let operations = self.operations.lock().unwrap();
+
+		let mut snapshots: Vec<OperationStatsSnapshot> = operations
+			.iter()
+			.map(|(name, counters)| {
+				let mut latencies: Vec<Duration> = counters.latencies.iter().cloned().collect();
+
+				latencies.sort();
+
+				OperationStatsSnapshot {
+					name: name.clone(),
+					count: counters.count,
+					errors: counters.errors,
+					rows: counters.rows,
+					p50: get_percentile(&latencies, 50),
+					p95: get_percentile(&latencies, 95),
+				}
+			})
+			.collect();
+
+		snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+
+		snapshots
+	}
+}
+
+/// Nearest-rank percentile of sorted latencies
+fn get_percentile(sorted: &[Duration], percentile: usize) -> Duration {
+	if sorted.is_empty() {
+		return Duration::ZERO;
+	}
+
+	let rank = (sorted.len() * percentile + 99) / 100;
+
+	sorted[rank.max(1) - 1]
+}
+
+lazy_static! {
+	pub static ref OPERATION_STATS: OperationStats = OperationStats::new();
+}
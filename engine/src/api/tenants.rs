@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse};
+use actix_web::error::{ErrorInternalServerError, ErrorServiceUnavailable, InternalError};
+use actix_web::web::Data;
+use actix_web::{
+	Error as ActixError, FromRequest, HttpMessage, HttpRequest as ActixRequest,
+	HttpResponse as ActixResponse,
+};
+use arc_swap::ArcSwap;
+use futures_util::future::LocalBoxFuture;
+use futures_util::FutureExt;
+use lazy_static::lazy_static;
+use rust_arango::ClientError;
+use tokio::sync::OnceCell;
+
+use crate::api::auth::Session;
+use crate::api::response_cache::{RESPONSE_CACHE, SCHEMA_TAG};
+use crate::api::schema::{schema, Schema};
+use crate::api::server::error_response;
+use crate::lib::database::generate_sdl;
+use crate::lib::database::tenants::{
+	get_current_database_name, is_default_database, scope_database,
+};
+use crate::lib::database::DATABASE;
+use crate::lib::CONFIG;
+
+/// Why the tenant of a request couldn't be selected, reported by the routes using its schema
+#[derive(Clone)]
+struct TenantError(String);
+
+/// Host of the request without the port, e.g. `acme` of `acme.api.example.com` when
+/// `TENANT_DOMAIN` is `api.example.com`
+fn get_subdomain(req: &ActixRequest) -> Option<String> {
+	let domain = CONFIG.get_tenant_domain()?;
+	let host = req.connection_info().host().to_string();
+	let host = host.split(':').next().unwrap_or_default();
+
+	host.strip_suffix(domain.as_str())?
+		.strip_suffix('.')
+		.filter(|s| !s.is_empty() && !s.contains('.'))
+		.map(String::from)
+}
+
+/// Tokens are only valid for the database of their tenant claim, or `DB_NAME` without one, so
+/// a header or host selecting another tenant can't reuse them. Without a JWT secret there are
+/// no tokens to check
+fn check_token_tenant(req: &ActixRequest, database: &str) -> Result<(), String> {
+	if CONFIG.get_jwt_secret().is_none() {
+		return Ok(());
+	}
+
+	let session = match Session::from_request(req) {
+		Ok(session) if !session.anonymous => session,
+		// Invalid tokens are rejected by the routes
+		_ => return Ok(()),
+	};
+
+	let claim = session
+		.claims
+		.get(CONFIG.get_tenant_claim().as_str())
+		.and_then(|c| c.as_str())
+		.unwrap_or(CONFIG.db_name.as_str());
+
+	match claim == database {
+		true => Ok(()),
+		false => Err(format!("Token is not valid for tenant {}", database)),
+	}
+}
+
+/// Database the request is for, selected by `TENANT_SOURCE`. Requests without a tenant use
+/// `DB_NAME`, while the tenants outside of `TENANTS` and the ones the token isn't issued for
+/// are rejected
+fn get_request_database(req: &ActixRequest) -> Result<String, String> {
+	let tenant = match CONFIG.get_tenant_source() {
+		Some("header") => req
+			.headers()
+			.get(CONFIG.get_tenant_header().as_str())
+			.and_then(|h| h.to_str().ok())
+			.map(String::from),
+		Some("subdomain") => get_subdomain(req),
+		// Only a valid token can select a tenant
+		Some("claim") => Session::from_request(req).ok().and_then(|session| {
+			session
+				.claims
+				.get(CONFIG.get_tenant_claim().as_str())
+				.and_then(|c| c.as_str())
+				.map(String::from)
+		}),
+		_ => None,
+	};
+
+	let tenant = match tenant.filter(|t| !t.is_empty()) {
+		Some(tenant) => tenant,
+		None => CONFIG.db_name.clone(),
+	};
+
+	if tenant != CONFIG.db_name && !CONFIG.get_tenants().contains(&tenant) {
+		return Err(format!("Tenant {} not found", tenant));
+	}
+
+	check_token_tenant(req, &tenant)?;
+
+	Ok(tenant)
+}
+
+/// Middleware sending the queries of a request to the database of its tenant. The meta API
+/// and the admin console always manage `DB_NAME`
+pub fn scope_request<S, B>(
+	req: ServiceRequest,
+	srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, ActixError>>
+where
+	S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+{
+	let is_meta = req.path().starts_with("/meta/") || req.path().starts_with("/admin");
+
+	let database = match is_meta {
+		true => CONFIG.db_name.clone(),
+		false => get_request_database(req.request()).unwrap_or_else(|e| {
+			req.extensions_mut().insert(TenantError(e));
+
+			// No database is open under this name, so nothing can be queried
+			String::new()
+		}),
+	};
+
+	scope_database(database, srv.call(req))
+}
+
+/// API schemas of the tenants, built from their databases the first time they are requested.
+/// Each tenant has its own cell so building one doesn't hold the requests of the others
+pub struct TenantSchemas {
+	schemas: Mutex<HashMap<String, Arc<OnceCell<Arc<ArcSwap<Schema>>>>>>,
+}
+
+impl TenantSchemas {
+	fn new() -> TenantSchemas {
+		TenantSchemas {
+			schemas: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Schema of the database of the current request
+	async fn get(&self) -> Result<Arc<ArcSwap<Schema>>, ClientError> {
+		let name = get_current_database_name();
+		let cell = self
+			.schemas
+			.lock()
+			.unwrap()
+			.entry(name.clone())
+			.or_default()
+			.clone();
+
+		// Concurrent requests of the tenant wait for the same build, a failed one is tried
+		// again by the next request
+		let schema = cell
+			.get_or_try_init(|| async {
+				DATABASE.get().await.open_database(&name).await?;
+
+				tracing::info!("Building the schema of tenant {}", name);
+
+				let schema = Arc::new(ArcSwap::from_pointee(schema(generate_sdl().await)));
+
+				// Responses cached with a previous schema of the tenant
+				RESPONSE_CACHE.invalidate(SCHEMA_TAG).await;
+
+				Ok::<_, ClientError>(schema)
+			})
+			.await?;
+
+		Ok(schema.clone())
+	}
+
+	/// Drops the schemas so they are built again with their changes
+	pub async fn clear(&self) {
+		self.schemas.lock().unwrap().clear();
+	}
+}
+
+lazy_static! {
+	pub static ref TENANT_SCHEMAS: TenantSchemas = TenantSchemas::new();
+}
+
+/// API schema of the tenant of the request, extracted like `Data<ArcSwap<Schema>>` which it
+/// replaces in the routes
+#[derive(Clone)]
+pub struct TenantSchema(Arc<ArcSwap<Schema>>);
+
+impl TenantSchema {
+	pub fn into_inner(self) -> Arc<ArcSwap<Schema>> {
+		self.0
+	}
+}
+
+impl Deref for TenantSchema {
+	type Target = ArcSwap<Schema>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl FromRequest for TenantSchema {
+	type Error = ActixError;
+	type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+	fn from_request(req: &ActixRequest, _: &mut Payload) -> Self::Future {
+		let error = req.extensions().get::<TenantError>().cloned();
+		let default = req
+			.app_data::<Data<ArcSwap<Schema>>>()
+			.map(|schema| schema.clone().into_inner());
+
+		async move {
+			if let Some(TenantError(message)) = error {
+				let response = error_response(ActixResponse::NotFound(), message.clone());
+
+				return Err(InternalError::from_response(message, response).into());
+			}
+
+			if is_default_database() {
+				return default
+					.map(TenantSchema)
+					.ok_or_else(|| ErrorInternalServerError("API schema is not configured"));
+			}
+
+			TENANT_SCHEMAS.get().await.map(TenantSchema).map_err(|e| {
+				tracing::error!("Error opening the database of the tenant: {}", e);
+
+				ErrorServiceUnavailable("Database of the tenant is unavailable")
+			})
+		}
+		.boxed_local()
+	}
+}
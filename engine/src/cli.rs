@@ -0,0 +1,177 @@
+use anyhow::{anyhow, Error};
+use clap::{Parser, Subcommand};
+
+use crate::api;
+use crate::lib::database::generate_sdl;
+use crate::lib::schema::{self, declarative::SchemaChange};
+use crate::lib::CONFIG;
+
+/// Settings can be given as flags before the command, e.g. `alchemy --db-host localhost serve`
+#[derive(Parser)]
+#[clap(name = "alchemy", version, about)]
+pub struct Cli {
+	#[clap(subcommand)]
+	pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+	/// Runs the API servers, the default command
+	Serve,
+	/// Prints the API schema, or exports, compares or applies the stored schema
+	#[clap(subcommand)]
+	Schema(SchemaCommand),
+	/// Applies or rolls back the schema migrations
+	#[clap(subcommand)]
+	Migrate(MigrateCommand),
+	/// Upserts the seed files of SEEDS_DIR, or only those of the given collections
+	Seed {
+		#[clap(long)]
+		dir: Option<String>,
+		collections: Vec<String>,
+	},
+	/// Checks the configuration, exiting with the missing and invalid settings
+	ValidateConfig,
+}
+
+#[derive(Subcommand)]
+pub enum SchemaCommand {
+	/// Writes the SDL of the GraphQL API generated from the stored schema, to stdout unless an
+	/// output file is given
+	Print {
+		#[clap(short, long)]
+		output: Option<String>,
+	},
+	/// Writes the stored schema as JSON, to stdout unless an output file is given
+	Export {
+		#[clap(short, long)]
+		output: Option<String>,
+	},
+	/// Prints the changes bringing the stored schema to the schema file
+	Diff {
+		/// Schema file, SCHEMA_FILE by default
+		file: Option<String>,
+		/// Also removes what the schema file doesn't declare
+		#[clap(long)]
+		prune: bool,
+	},
+	/// Applies the changes bringing the stored schema to the schema file
+	Apply {
+		/// Schema file, SCHEMA_FILE by default
+		file: Option<String>,
+		/// Also removes what the schema file doesn't declare
+		#[clap(long)]
+		prune: bool,
+	},
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+	/// Applies the pending changes of SCHEMA_FILE, each one recorded as a migration
+	Up,
+	/// Rolls back the latest migrations
+	Down {
+		#[clap(long, default_value = "1")]
+		count: u32,
+	},
+}
+
+async fn get_schema_changes(file: Option<String>, prune: bool) -> Result<Vec<SchemaChange>, Error> {
+	let path = file
+		.or_else(|| CONFIG.get_schema_file().cloned())
+		.ok_or_else(|| anyhow!("No schema file given and SCHEMA_FILE isn't set"))?;
+
+	let definition = std::fs::read_to_string(&path)
+		.map_err(|e| anyhow!("Error reading schema file {}: {}", path, e))?;
+
+	Ok(schema::diff_schema(&schema::parse_schema(&definition)?, prune).await)
+}
+
+async fn apply_schema(file: Option<String>, prune: bool) -> Result<(), Error> {
+	let author = file.clone().or_else(|| CONFIG.get_schema_file().cloned());
+	let changes = get_schema_changes(file, prune).await?;
+
+	if changes.is_empty() {
+		println!("Schema is up to date");
+
+		return Ok(());
+	}
+
+	for change in &changes {
+		println!("{}", change);
+	}
+
+	schema::apply_schema_changes(changes, author).await
+}
+
+async fn run_command(command: Command) -> Result<(), Error> {
+	match command {
+		Command::Serve => unreachable!("Served by main"),
+		Command::Schema(SchemaCommand::Print { output }) => {
+			let sdl =
+				api::schema::directives::print_schema(&api::schema::schema(generate_sdl().await));
+
+			match output {
+				Some(path) => std::fs::write(path, sdl)?,
+				None => println!("{}", sdl),
+			}
+		}
+		Command::Schema(SchemaCommand::Export { output }) => {
+			let export = serde_json::to_string_pretty(&schema::export_schema().await?)?;
+
+			match output {
+				Some(path) => std::fs::write(path, export)?,
+				None => println!("{}", export),
+			}
+		}
+		Command::Schema(SchemaCommand::Diff { file, prune }) => {
+			let changes = get_schema_changes(file, prune).await?;
+
+			if changes.is_empty() {
+				println!("Schema is up to date");
+			}
+
+			for change in changes {
+				println!("{}", change);
+			}
+		}
+		Command::Schema(SchemaCommand::Apply { file, prune }) => apply_schema(file, prune).await?,
+		Command::Migrate(MigrateCommand::Up) => {
+			if CONFIG.get_schema_file().is_none() {
+				return Err(anyhow!("SCHEMA_FILE isn't set"));
+			}
+
+			apply_schema(None, false).await?
+		}
+		Command::Migrate(MigrateCommand::Down { count }) => {
+			for name in schema::rollback_migrations(count).await? {
+				println!("Rolled back {}", name);
+			}
+		}
+		Command::Seed { dir, collections } => {
+			let dir = dir
+				.or_else(|| CONFIG.get_seeds_dir().cloned())
+				.ok_or_else(|| anyhow!("No seeds directory given and SEEDS_DIR isn't set"))?;
+
+			let collections = (!collections.is_empty()).then(|| collections);
+
+			for report in schema::run_seeds(&dir, collections.as_deref()).await? {
+				println!("Seeded {}", report);
+			}
+		}
+		// Invalid settings have made Alchemy exit while loading the config
+		Command::ValidateConfig => println!("Configuration is valid"),
+	}
+
+	Ok(())
+}
+
+/// Runs an operational command without starting the servers, exiting with an error code when
+/// it fails
+pub async fn run(command: Command) {
+	if let Err(e) = run_command(command).await {
+		eprintln!("{}", e);
+
+		std::process::exit(1);
+	}
+}
@@ -0,0 +1,169 @@
+use anyhow::Error;
+use chrono::{SecondsFormat, Utc};
+use rust_arango::index::{Index, IndexSettings};
+use rust_arango::AqlQuery;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::lib::database::DATABASE;
+use crate::lib::events::EventOperation;
+use crate::lib::CONFIG;
+
+const AUDIT_LOG_COLLECTION: &str = "alchemy_audit_log";
+
+/// Filters of the audit log entries returned by `get_audit_log`, `since` and `until` being
+/// RFC 3339 times
+pub struct AuditLogFilter {
+	pub collection: Option<String>,
+	pub key: Option<String>,
+	pub user: Option<String>,
+	pub operation: Option<EventOperation>,
+	pub since: Option<String>,
+	pub until: Option<String>,
+	pub limit: Option<u32>,
+}
+
+async fn ensure_collection(name: &str) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(name).await.is_err() {
+		db.create_collection(name).await?;
+	}
+
+	Ok(())
+}
+
+/// Creates the audit log collection, with an expiry index removing the entries older than
+/// `AUDIT_LOG_RETENTION_DAYS` when it's set
+pub async fn init_audit_log() -> Result<(), Error> {
+	ensure_collection(AUDIT_LOG_COLLECTION).await?;
+
+	let db = DATABASE.get().await.database();
+
+	for index in db.indexes(AUDIT_LOG_COLLECTION).await?.indexes {
+		if let IndexSettings::Ttl { .. } = index.settings {
+			db.delete_index(&index.id).await?;
+		}
+	}
+
+	if let Some(days) = CONFIG.get_audit_log_retention_days() {
+		let index = Index::builder()
+			.fields(vec!["timestamp".to_string()])
+			.settings(IndexSettings::Ttl {
+				expire_after: days * 24 * 60 * 60,
+			})
+			.build();
+
+		db.create_index(AUDIT_LOG_COLLECTION, &index).await?;
+	}
+
+	Ok(())
+}
+
+/// Attributes whose values differ before and after the change, with both values
+fn get_diff(old: &JsonValue, new: &JsonValue) -> JsonValue {
+	let empty = JsonMap::new();
+	let old = old.as_object().unwrap_or(&empty);
+	let new = new.as_object().unwrap_or(&empty);
+
+	let mut diff = JsonMap::new();
+
+	for key in old
+		.keys()
+		.chain(new.keys().filter(|k| !old.contains_key(*k)))
+	{
+		if key == "_rev" {
+			continue;
+		}
+
+		let before = old.get(key).unwrap_or(&JsonValue::Null);
+		let after = new.get(key).unwrap_or(&JsonValue::Null);
+
+		if before != after {
+			diff.insert(key.clone(), json!({ "old": before, "new": after }));
+		}
+	}
+
+	JsonValue::Object(diff)
+}
+
+/// Records the changes of a mutation, `changes` being the `old` and `new` documents returned
+/// by it, along with the user who made it
+pub async fn record_mutations(
+	collection: &str,
+	operation: EventOperation,
+	changes: &[JsonValue],
+	user: Option<&str>,
+	role: &str,
+) -> Result<(), Error> {
+	if changes.is_empty() {
+		return Ok(());
+	}
+
+	let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+
+	let entries: Vec<JsonValue> = changes
+		.iter()
+		.map(|change| {
+			let old = change.get("old").cloned().unwrap_or(JsonValue::Null);
+			let new = change.get("new").cloned().unwrap_or(JsonValue::Null);
+
+			json!({
+				"collection": collection,
+				"key": change["_key"],
+				"operation": operation,
+				"user": user,
+				"role": role,
+				"timestamp": timestamp,
+				"diff": get_diff(&old, &new),
+				"old": old,
+				"new": new,
+			})
+		})
+		.collect();
+
+	ensure_collection(AUDIT_LOG_COLLECTION).await?;
+
+	let query = AqlQuery::builder()
+		.query("FOR e IN @entries INSERT e INTO @@collection")
+		.bind_var("@collection", AUDIT_LOG_COLLECTION)
+		.bind_var("entries", JsonValue::Array(entries))
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(())
+}
+
+/// Entries of the audit log matching the filter, the latest first
+pub async fn get_audit_log(filter: AuditLogFilter) -> Result<Vec<JsonValue>, Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(AUDIT_LOG_COLLECTION).await.is_err() {
+		return Ok(Vec::new());
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR e IN @@collection
+				FILTER @collection == null OR e.collection == @collection
+				FILTER @key == null OR e.key == @key
+				FILTER @user == null OR e.user == @user
+				FILTER @operation == null OR e.operation == @operation
+				FILTER @since == null OR DATE_TIMESTAMP(e.timestamp) >= DATE_TIMESTAMP(@since)
+				FILTER @until == null OR DATE_TIMESTAMP(e.timestamp) < DATE_TIMESTAMP(@until)
+				SORT e.timestamp DESC
+				LIMIT @limit
+				RETURN e",
+		)
+		.bind_var("@collection", AUDIT_LOG_COLLECTION)
+		.bind_var("collection", filter.collection)
+		.bind_var("key", filter.key)
+		.bind_var("user", filter.user)
+		.bind_var("operation", serde_json::to_value(filter.operation)?)
+		.bind_var("since", filter.since)
+		.bind_var("until", filter.until)
+		.bind_var("limit", filter.limit.unwrap_or(u32::MAX))
+		.build();
+
+	Ok(db.aql_query(query).await?)
+}
@@ -1,6 +1,6 @@
-use std::{fs::File, io::Read};
+use std::{collections::HashMap, net::IpAddr, path::Path, str::FromStr, time::Duration};
 
-use anyhow::Result;
+use convert_case::Case;
 use lazy_static::lazy_static;
 use serde::Deserialize;
 
@@ -10,10 +10,231 @@ pub struct Config {
 	pub db_user: String,
 	pub db_pass: String,
 	pub db_name: String,
+	/// Connections to ArangoDB kept open, 8 by default. `DB_HOST` can list several comma
+	/// separated hosts to fail over to
+	pub db_pool_size: Option<String>,
+	/// Seconds to wait for a connection to be established, 10 by default
+	pub db_connect_timeout: Option<String>,
+	/// Seconds a read can take before it's abandoned, unlimited when unset
+	pub db_query_timeout: Option<String>,
+	/// Times a read failing with a transient error is retried, 3 by default
+	pub db_retries: Option<String>,
+	/// Milliseconds before the first retry, doubled on each of the next ones, 100 by default
+	pub db_retry_backoff: Option<String>,
+	/// Comma separated hosts of read replicas the reads of the API are spread over, the writes
+	/// and the reads of a request after it has written go to `DB_HOST`
+	pub db_replica_hosts: Option<String>,
 
 	pub rust_env: String,
 
 	pub app_port: String,
+
+	/// Filter of the logs printed, e.g. `info` or `alchemy=debug` to log the AQL queries
+	pub log_level: Option<String>,
+	/// `json` to print one JSON object per line, `pretty` to print text, JSON by default in
+	/// production
+	pub log_format: Option<String>,
+	/// OTLP gRPC endpoint the traces are exported to, e.g. `http://localhost:4317`, traces
+	/// aren't exported when unset
+	pub otel_exporter_otlp_endpoint: Option<String>,
+	/// Service the traces are reported for, `alchemy` by default
+	pub otel_service_name: Option<String>,
+
+	/// Secret required in the `x-alchemy-admin-secret` header to access the meta API
+	pub admin_secret: String,
+
+	/// Directory of the built admin console, served under `/admin` along with the meta API
+	pub admin_ui_dir: Option<String>,
+
+	/// Optional separate address and port to serve the meta API from
+	pub meta_host: Option<String>,
+	pub meta_port: Option<String>,
+
+	/// Unix sockets to serve the API and the meta API from instead of their ports, so they
+	/// can be restricted to local processes like a sidecar
+	pub app_socket: Option<String>,
+	pub meta_socket: Option<String>,
+
+	/// Port of the gRPC server exposing the CRUD operations of the collections, not started
+	/// when unset
+	pub grpc_port: Option<String>,
+	/// Address the gRPC server listens on, all interfaces by default like the API
+	pub grpc_host: Option<String>,
+
+	/// Secret used to verify API JWTs, authentication is disabled when unset
+	pub jwt_secret: Option<String>,
+
+	/// Role given to requests without credentials, unauthenticated requests are rejected when unset
+	pub anonymous_role: Option<String>,
+	/// Comma separated collections or operations the anonymous role can query
+	pub anonymous_access: Option<String>,
+	/// Comma separated roles that can read drafts with `preview: true`, every authenticated role
+	/// when unset
+	pub preview_roles: Option<String>,
+	/// Lifetime in seconds of the JWTs issued by Alchemy
+	pub jwt_expiration: Option<String>,
+
+	/// OpenID Connect provider used for the `/auth/oidc` login flow
+	pub oidc_issuer: Option<String>,
+	pub oidc_client_id: Option<String>,
+	pub oidc_client_secret: Option<String>,
+	pub oidc_redirect_url: Option<String>,
+	pub oidc_scopes: Option<String>,
+	pub oidc_default_role: Option<String>,
+
+	/// Comma separated `role=requests_per_minute` pairs for the API rate limiter
+	pub rate_limits: Option<String>,
+	/// Requests per minute for roles without their own limit, unlimited when unset
+	pub rate_limit_default: Option<String>,
+	/// Comma separated addresses of the proxies in front of the engine, the client address of
+	/// their requests is read from `X-Forwarded-For`. The header is ignored when unset as
+	/// clients can send it themselves
+	pub trusted_proxies: Option<String>,
+
+	/// Seconds the queries of an API operation can run, after which ArangoDB kills them and
+	/// the operation fails with a `TIMEOUT` error. Comma separated `role=seconds` pairs
+	/// override it for their roles, unlimited when unset
+	pub operation_timeout: Option<String>,
+	pub operation_timeouts: Option<String>,
+	/// Bytes of memory ArangoDB can use for a query of an API operation, overridden by the
+	/// `role=bytes` pairs of `OPERATION_MEMORY_LIMITS`, unlimited when unset
+	pub operation_memory_limit: Option<String>,
+	pub operation_memory_limits: Option<String>,
+
+	/// Limits checked on every API request before it's executed
+	pub max_query_depth: Option<String>,
+	pub max_query_complexity: Option<String>,
+	/// Maximum number of operations in a batch request
+	pub max_batch_size: Option<String>,
+	/// Assumed size of list fields without a `limit` argument when estimating complexity
+	pub query_default_list_size: Option<String>,
+	/// Maximum `depth` of recursive relationship traversals
+	pub max_traversal_depth: Option<String>,
+	/// Documents fetched from ArangoDB at once by list queries, converted into the response
+	/// before the next batch is fetched, 1000 by default
+	pub query_batch_size: Option<String>,
+	/// Documents fetched from ArangoDB at once by the internal queries of mutations and the
+	/// relationship lookups, 1000 by default
+	pub internal_batch_size: Option<String>,
+	/// Number of compiled AQL queries kept for repeated operations, `0` disables the cache
+	pub query_cache_size: Option<String>,
+	/// Milliseconds after which a query is logged as slow with its AQL, disabled when unset
+	pub slow_query_threshold: Option<String>,
+	/// Logs the deprecated operations and fields used by the requests when set to `true`
+	pub log_deprecated_usage: Option<String>,
+	/// Number of API responses cached in memory for collections with a cache max age
+	pub response_cache_size: Option<String>,
+	/// Former name of `REDIS_URL`, read when it's unset
+	pub response_cache_redis_url: Option<String>,
+	/// Redis server sharing state between the instances behind a load balancer: the response
+	/// cache, the invalidations of the cached query results and the rate limit counters
+	pub redis_url: Option<String>,
+
+	/// JSON file mapping SHA-256 hashes to registered queries
+	pub persisted_queries_file: Option<String>,
+	/// Only execute registered queries on the API when set to `true`
+	pub persisted_queries_only: Option<String>,
+
+	/// Introspection and the playground on `/api`, enabled by default outside production
+	pub api_introspection: Option<String>,
+	pub api_playground: Option<String>,
+	/// Messages of database errors in the `details` of API errors, enabled by default outside
+	/// production
+	pub api_debug_errors: Option<String>,
+	/// Comma separated collections left out of the API, whatever their `exposed` setting
+	pub api_excluded_collections: Option<String>,
+	/// Only collections set `exposed` through the meta API or the schema file are in the API
+	/// when set to `true`, otherwise every collection is unless set not to be
+	pub api_allowlist: Option<String>,
+
+	/// Maximum decoded size in bytes of `Bytes` values
+	pub bytes_max_size: Option<String>,
+
+	/// YAML or JSON file declaring the collections and relationships, its differences with the
+	/// database are printed on startup and applied when `SCHEMA_APPLY` is `true`
+	pub schema_file: Option<String>,
+	pub schema_apply: Option<String>,
+	/// Seconds between the checks of the schema version, reloading the schema once another
+	/// instance changed it. 30 by default, `0` disables them
+	pub schema_poll_interval: Option<String>,
+
+	/// Directory of seed files upserted on startup and by the `runSeeds` meta mutation, one
+	/// JSON or YAML file per collection
+	pub seeds_dir: Option<String>,
+
+	/// Documents inserted per request to ArangoDB by `/api/import`, 1000 by default
+	pub import_batch_size: Option<String>,
+
+	/// Directory the backups created by the `createBackup` meta mutation are written to
+	pub backup_dir: Option<String>,
+
+	/// Fuel a WASM plugin can consume per call, roughly its instructions, 100000000 by default
+	pub plugin_fuel: Option<String>,
+	/// Memory a WASM plugin can grow to per call in MB, 64 by default
+	pub plugin_memory_limit: Option<String>,
+
+	/// YAML or JSON file declaring the named storage backends of `File` properties, a local
+	/// `default` one storing to `uploads` is used when it isn't declared
+	pub storage_file: Option<String>,
+	/// Secret the URLs of local and private files are signed with, they can't be signed and
+	/// their signed URLs are refused when unset
+	pub file_signing_key: Option<String>,
+	/// Maximum size in bytes of each file uploaded to the API, 10 MB by default
+	pub upload_max_size: Option<String>,
+	/// Maximum number of files uploaded in a single request, 10 by default
+	pub upload_max_files: Option<String>,
+
+	/// Records every mutation with its changes into `alchemy_audit_log` when set to `true`
+	pub audit_log: Option<String>,
+	/// Days the audit log entries are kept for, forever when unset
+	pub audit_log_retention_days: Option<String>,
+
+	/// Comma separated origins allowed to call the APIs from a browser, any origin when unset
+	pub cors_origins: Option<String>,
+
+	/// PEM certificate chain and private key the HTTP servers are served with over HTTPS and
+	/// HTTP/2, reloaded when renewed e.g. by certbot
+	pub tls_cert_file: Option<String>,
+	pub tls_key_file: Option<String>,
+
+	/// Selects the database of each API request among `TENANTS`: `header` from `TENANT_HEADER`
+	/// (`x-alchemy-tenant` by default), `subdomain` from the subdomain of `TENANT_DOMAIN`, or
+	/// `claim` from the `TENANT_CLAIM` claim of the JWT (`tenant` by default). Requests without
+	/// a tenant, the meta API and the background jobs use `DB_NAME`
+	pub tenant_source: Option<String>,
+	pub tenant_header: Option<String>,
+	pub tenant_domain: Option<String>,
+	pub tenant_claim: Option<String>,
+	/// Comma separated databases of the tenants, required with `TENANT_SOURCE`
+	pub tenants: Option<String>,
+
+	/// Prefix of the generated type names, e.g. `Crm` for `CrmUser` and `getCrmUser`, so
+	/// several instances can be stitched into one gateway. Scalars and the shared types of the
+	/// API aren't prefixed, field resolvers are registered under the prefixed type names
+	pub schema_prefix: Option<String>,
+
+	/// Locale the values of translatable fields are written in and read from when a request
+	/// doesn't give one or has no translation, `en` by default
+	pub default_locale: Option<String>,
+
+	/// `camel` or `snake`, case of the generated operations, `camel` by default
+	pub operation_case: Option<String>,
+	/// Template of the generated operation names, `{verb}` and `{name}` being replaced by the
+	/// verb of the operation and the type it's for, e.g. `{verb}{name}Admin`. `{verb}{name}` by
+	/// default
+	pub operation_template: Option<String>,
+	/// Verb of the generated queries, `get` by default, `none` to name them after their type,
+	/// e.g. `user` and `users`
+	pub query_verb: Option<String>,
+	/// Suffix of the operations on lists of documents, e.g. `List` for `getUserList`, they're
+	/// named after the plural of the type when unset
+	pub list_suffix: Option<String>,
+	/// Whether the types of the collections are named after their singular, e.g. `User` for
+	/// `users`, true by default
+	pub singular_type_names: Option<String>,
+
+	/// Seconds given to the requests in flight and the due events on SIGTERM, 30 by default
+	pub shutdown_timeout: Option<String>,
 }
 
 impl Config {
@@ -24,39 +245,948 @@ impl Config {
 	pub fn is_development(&self) -> bool {
 		!self.is_production()
 	}
+
+	pub fn get_log_level(&self) -> &str {
+		self.log_level
+			.as_deref()
+			.filter(|l| !l.is_empty())
+			.unwrap_or("info")
+	}
+
+	pub fn is_log_json(&self) -> bool {
+		match self.log_format.as_deref() {
+			Some("json") => true,
+			Some("pretty") => false,
+			_ => self.is_production(),
+		}
+	}
+
+	pub fn get_otlp_endpoint(&self) -> Option<&String> {
+		self.otel_exporter_otlp_endpoint
+			.as_ref()
+			.filter(|e| !e.is_empty())
+	}
+
+	pub fn get_otel_service_name(&self) -> String {
+		self.otel_service_name
+			.clone()
+			.filter(|n| !n.is_empty())
+			.unwrap_or_else(|| "alchemy".to_string())
+	}
+
+	pub fn is_introspection_enabled(&self) -> bool {
+		parse_flag(&self.api_introspection).unwrap_or_else(|| self.is_development())
+	}
+
+	pub fn is_playground_enabled(&self) -> bool {
+		parse_flag(&self.api_playground).unwrap_or_else(|| self.is_development())
+	}
+
+	pub fn is_debug_errors_enabled(&self) -> bool {
+		parse_flag(&self.api_debug_errors).unwrap_or_else(|| self.is_development())
+	}
+
+	/// Whether the collection is in the API, given its `exposed` setting
+	pub fn is_collection_exposed(&self, collection: &str, exposed: Option<bool>) -> bool {
+		if split_list(&self.api_excluded_collections)
+			.iter()
+			.any(|c| c == collection)
+		{
+			return false;
+		}
+
+		exposed.unwrap_or_else(|| !parse_flag(&self.api_allowlist).unwrap_or(false))
+	}
+
+	pub fn get_db_pool_size(&self) -> usize {
+		self.db_pool_size
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.filter(|s| *s > 0)
+			.unwrap_or(8)
+	}
+
+	pub fn get_db_connect_timeout(&self) -> Duration {
+		Duration::from_secs(
+			self.db_connect_timeout
+				.as_ref()
+				.and_then(|t| t.parse::<u64>().ok())
+				.unwrap_or(10),
+		)
+	}
+
+	pub fn get_db_query_timeout(&self) -> Option<Duration> {
+		self.db_query_timeout
+			.as_ref()
+			.and_then(|t| t.parse::<u64>().ok())
+			.map(Duration::from_secs)
+	}
+
+	pub fn get_db_retries(&self) -> u32 {
+		self.db_retries
+			.as_ref()
+			.and_then(|r| r.parse::<u32>().ok())
+			.unwrap_or(3)
+	}
+
+	pub fn get_db_retry_backoff(&self) -> Duration {
+		Duration::from_millis(
+			self.db_retry_backoff
+				.as_ref()
+				.and_then(|b| b.parse::<u64>().ok())
+				.unwrap_or(100),
+		)
+	}
+
+	pub fn get_db_replica_hosts(&self) -> Vec<String> {
+		split_list(&self.db_replica_hosts)
+	}
+
+	pub fn get_bytes_max_size(&self) -> usize {
+		self.bytes_max_size
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.unwrap_or(65536)
+	}
+
+	pub fn get_meta_port(&self) -> Option<u16> {
+		self.meta_port.as_ref().and_then(|p| p.parse::<u16>().ok())
+	}
+
+	pub fn get_admin_ui_dir(&self) -> Option<&String> {
+		self.admin_ui_dir.as_ref().filter(|d| !d.is_empty())
+	}
+
+	pub fn get_app_socket(&self) -> Option<&String> {
+		self.app_socket.as_ref().filter(|s| !s.is_empty())
+	}
+
+	pub fn get_meta_socket(&self) -> Option<&String> {
+		self.meta_socket.as_ref().filter(|s| !s.is_empty())
+	}
+
+	pub fn get_grpc_port(&self) -> Option<u16> {
+		self.grpc_port.as_ref().and_then(|p| p.parse::<u16>().ok())
+	}
+
+	pub fn get_grpc_host(&self) -> String {
+		self.grpc_host
+			.clone()
+			.filter(|h| !h.is_empty())
+			.unwrap_or_else(|| "0.0.0.0".to_string())
+	}
+
+	pub fn get_jwt_secret(&self) -> Option<&String> {
+		self.jwt_secret.as_ref().filter(|s| !s.is_empty())
+	}
+
+	pub fn get_anonymous_role(&self) -> Option<String> {
+		self.anonymous_role.clone().filter(|r| !r.is_empty())
+	}
+
+	pub fn get_anonymous_access(&self) -> Vec<String> {
+		split_list(&self.anonymous_access)
+	}
+
+	pub fn get_preview_roles(&self) -> Vec<String> {
+		split_list(&self.preview_roles)
+	}
+
+	pub fn get_jwt_expiration(&self) -> usize {
+		self.jwt_expiration
+			.as_ref()
+			.and_then(|e| e.parse::<usize>().ok())
+			.unwrap_or(3600)
+	}
+
+	pub fn get_rate_limits(&self) -> HashMap<String, u32> {
+		split_role_pairs(&self.rate_limits)
+	}
+
+	pub fn get_rate_limit_default(&self) -> Option<u32> {
+		self.rate_limit_default
+			.as_ref()
+			.and_then(|l| l.parse::<u32>().ok())
+	}
+
+	pub fn get_trusted_proxies(&self) -> Vec<IpAddr> {
+		split_list(&self.trusted_proxies)
+			.iter()
+			.filter_map(|a| a.parse::<IpAddr>().ok())
+			.collect()
+	}
+
+	pub fn get_operation_timeout(&self, role: &str) -> Option<Duration> {
+		split_role_pairs::<u64>(&self.operation_timeouts)
+			.remove(role)
+			.or_else(|| {
+				self.operation_timeout
+					.as_ref()
+					.and_then(|t| t.parse::<u64>().ok())
+			})
+			.filter(|t| *t > 0)
+			.map(Duration::from_secs)
+	}
+
+	pub fn get_operation_memory_limit(&self, role: &str) -> Option<u64> {
+		split_role_pairs::<u64>(&self.operation_memory_limits)
+			.remove(role)
+			.or_else(|| {
+				self.operation_memory_limit
+					.as_ref()
+					.and_then(|l| l.parse::<u64>().ok())
+			})
+			.filter(|l| *l > 0)
+	}
+
+	pub fn get_max_query_depth(&self) -> Option<u32> {
+		self.max_query_depth
+			.as_ref()
+			.and_then(|d| d.parse::<u32>().ok())
+	}
+
+	pub fn get_max_query_complexity(&self) -> Option<u64> {
+		self.max_query_complexity
+			.as_ref()
+			.and_then(|c| c.parse::<u64>().ok())
+	}
+
+	pub fn get_max_batch_size(&self) -> Option<usize> {
+		self.max_batch_size
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+	}
+
+	pub fn get_query_default_list_size(&self) -> u64 {
+		self.query_default_list_size
+			.as_ref()
+			.and_then(|s| s.parse::<u64>().ok())
+			.unwrap_or(100)
+	}
+
+	pub fn get_max_traversal_depth(&self) -> u32 {
+		self.max_traversal_depth
+			.as_ref()
+			.and_then(|d| d.parse::<u32>().ok())
+			.unwrap_or(10)
+	}
+
+	pub fn get_query_batch_size(&self) -> u32 {
+		self.query_batch_size
+			.as_ref()
+			.and_then(|s| s.parse::<u32>().ok())
+			.filter(|s| *s > 0)
+			.unwrap_or(1000)
+	}
+
+	pub fn get_internal_batch_size(&self) -> u32 {
+		self.internal_batch_size
+			.as_ref()
+			.and_then(|s| s.parse::<u32>().ok())
+			.filter(|s| *s > 0)
+			.unwrap_or(1000)
+	}
+
+	pub fn get_slow_query_threshold(&self) -> Option<Duration> {
+		self.slow_query_threshold
+			.as_ref()
+			.and_then(|t| t.parse::<u64>().ok())
+			.map(Duration::from_millis)
+	}
+
+	pub fn is_deprecation_log_enabled(&self) -> bool {
+		parse_flag(&self.log_deprecated_usage).unwrap_or(false)
+	}
+
+	pub fn get_query_cache_size(&self) -> usize {
+		self.query_cache_size
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.unwrap_or(1000)
+	}
+
+	pub fn get_response_cache_size(&self) -> usize {
+		self.response_cache_size
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.unwrap_or(1000)
+	}
+
+	pub fn get_redis_url(&self) -> Option<&String> {
+		self.redis_url
+			.as_ref()
+			.filter(|u| !u.is_empty())
+			.or_else(|| {
+				self.response_cache_redis_url
+					.as_ref()
+					.filter(|u| !u.is_empty())
+			})
+	}
+
+	pub fn get_persisted_queries_file(&self) -> Option<&String> {
+		self.persisted_queries_file
+			.as_ref()
+			.filter(|f| !f.is_empty())
+	}
+
+	pub fn get_persisted_queries_only(&self) -> bool {
+		parse_flag(&self.persisted_queries_only).unwrap_or(false)
+	}
+
+	pub fn get_schema_file(&self) -> Option<&String> {
+		self.schema_file.as_ref().filter(|f| !f.is_empty())
+	}
+
+	pub fn is_schema_apply_enabled(&self) -> bool {
+		parse_flag(&self.schema_apply).unwrap_or(false)
+	}
+
+	pub fn get_schema_poll_interval(&self) -> Option<Duration> {
+		match self
+			.schema_poll_interval
+			.as_ref()
+			.and_then(|i| i.parse::<u64>().ok())
+			.unwrap_or(30)
+		{
+			0 => None,
+			seconds => Some(Duration::from_secs(seconds)),
+		}
+	}
+
+	pub fn get_seeds_dir(&self) -> Option<&String> {
+		self.seeds_dir.as_ref().filter(|d| !d.is_empty())
+	}
+
+	pub fn get_import_batch_size(&self) -> usize {
+		self.import_batch_size
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.filter(|s| *s > 0)
+			.unwrap_or(1000)
+	}
+
+	pub fn get_backup_dir(&self) -> Option<&String> {
+		self.backup_dir.as_ref().filter(|d| !d.is_empty())
+	}
+
+	pub fn get_plugin_fuel(&self) -> u64 {
+		self.plugin_fuel
+			.as_ref()
+			.and_then(|s| s.parse::<u64>().ok())
+			.unwrap_or(100_000_000)
+	}
+
+	/// Memory limit of WASM plugins in bytes
+	pub fn get_plugin_memory_limit(&self) -> usize {
+		self.plugin_memory_limit
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.unwrap_or(64)
+			* 1024 * 1024
+	}
+
+	pub fn get_storage_file(&self) -> Option<&String> {
+		self.storage_file.as_ref().filter(|f| !f.is_empty())
+	}
+
+	pub fn get_file_signing_key(&self) -> Option<&String> {
+		self.file_signing_key.as_ref().filter(|k| !k.is_empty())
+	}
+
+	pub fn get_upload_max_size(&self) -> usize {
+		self.upload_max_size
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.unwrap_or(10 * 1024 * 1024)
+	}
+
+	pub fn get_upload_max_files(&self) -> usize {
+		self.upload_max_files
+			.as_ref()
+			.and_then(|s| s.parse::<usize>().ok())
+			.unwrap_or(10)
+	}
+
+	pub fn is_audit_log_enabled(&self) -> bool {
+		parse_flag(&self.audit_log).unwrap_or(false)
+	}
+
+	pub fn get_audit_log_retention_days(&self) -> Option<u32> {
+		self.audit_log_retention_days
+			.as_ref()
+			.and_then(|d| d.parse::<u32>().ok())
+			.filter(|d| *d > 0)
+	}
+
+	pub fn get_cors_origins(&self) -> Vec<String> {
+		split_list(&self.cors_origins)
+	}
+
+	pub fn get_tenant_source(&self) -> Option<&str> {
+		self.tenant_source.as_deref().filter(|s| !s.is_empty())
+	}
+
+	pub fn get_tenant_header(&self) -> String {
+		self.tenant_header
+			.clone()
+			.filter(|h| !h.is_empty())
+			.unwrap_or_else(|| "x-alchemy-tenant".to_string())
+	}
+
+	pub fn get_tenant_domain(&self) -> Option<&String> {
+		self.tenant_domain.as_ref().filter(|d| !d.is_empty())
+	}
+
+	pub fn get_tenant_claim(&self) -> String {
+		self.tenant_claim
+			.clone()
+			.filter(|c| !c.is_empty())
+			.unwrap_or_else(|| "tenant".to_string())
+	}
+
+	pub fn get_tenants(&self) -> Vec<String> {
+		split_list(&self.tenants)
+	}
+
+	pub fn get_schema_prefix(&self) -> &str {
+		self.schema_prefix.as_deref().unwrap_or_default()
+	}
+
+	pub fn get_default_locale(&self) -> &str {
+		self.default_locale
+			.as_deref()
+			.filter(|l| !l.is_empty())
+			.unwrap_or("en")
+	}
+
+	pub fn get_operation_case(&self) -> Case {
+		match self.operation_case.as_deref() {
+			Some("snake") => Case::Snake,
+			_ => Case::Camel,
+		}
+	}
+
+	pub fn get_operation_template(&self) -> &str {
+		self.operation_template
+			.as_deref()
+			.filter(|t| !t.is_empty())
+			.unwrap_or("{verb}{name}")
+	}
+
+	pub fn get_query_verb(&self) -> &str {
+		match self.query_verb.as_deref() {
+			Some("none") => "",
+			Some(verb) if !verb.is_empty() => verb,
+			_ => "get",
+		}
+	}
+
+	pub fn get_list_suffix(&self) -> Option<&str> {
+		self.list_suffix.as_deref().filter(|s| !s.is_empty())
+	}
+
+	pub fn is_singular_type_names(&self) -> bool {
+		parse_flag(&self.singular_type_names).unwrap_or(true)
+	}
+
+	pub fn get_shutdown_timeout(&self) -> Duration {
+		Duration::from_secs(
+			self.shutdown_timeout
+				.as_ref()
+				.and_then(|t| t.parse::<u64>().ok())
+				.unwrap_or(30),
+		)
+	}
+
+	pub fn get_tls_files(&self) -> Option<(&String, &String)> {
+		let cert_file = self.tls_cert_file.as_ref().filter(|f| !f.is_empty())?;
+		let key_file = self.tls_key_file.as_ref().filter(|f| !f.is_empty())?;
+
+		Some((cert_file, key_file))
+	}
+
+	pub fn get_oidc_issuer(&self) -> Option<&String> {
+		self.oidc_issuer.as_ref().filter(|i| !i.is_empty())
+	}
+
+	pub fn get_oidc_scopes(&self) -> String {
+		self.oidc_scopes
+			.clone()
+			.filter(|s| !s.is_empty())
+			.unwrap_or_else(|| "openid email profile".to_string())
+	}
+}
+
+fn parse_flag(value: &Option<String>) -> Option<bool> {
+	match value.as_deref() {
+		Some("true") | Some("1") => Some(true),
+		Some("false") | Some("0") => Some(false),
+		_ => None,
+	}
 }
 
-fn load_config() -> Result<Config> {
-	let env = envy::from_env::<Config>();
+fn split_list(value: &Option<String>) -> Vec<String> {
+	value
+		.as_ref()
+		.map(|v| {
+			v.split(',')
+				.map(|i| i.trim().to_string())
+				.filter(|i| !i.is_empty())
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+/// Comma separated `role=value` pairs, e.g. `editor=100,viewer=20`
+fn split_role_pairs<T: FromStr>(value: &Option<String>) -> HashMap<String, T> {
+	split_list(value)
+		.iter()
+		.filter_map(|pair| {
+			let (role, value) = pair.split_once('=')?;
 
-	match env {
-		Ok(config) => Ok(config),
-		Err(_) => {
-			// Load from .env file
-			let mut file = File::open(".env")?;
-			let mut content = String::new();
+			Some((role.trim().to_string(), value.trim().parse::<T>().ok()?))
+		})
+		.collect()
+}
 
-			file.read_to_string(&mut content)?;
+/// Settings read as `CONFIG_FILE` when it isn't set, ignored when missing
+const DEFAULT_CONFIG_FILE: &str = "alchemy.toml";
 
-			for line in content.lines() {
-				let pair = line.split('=').collect::<Vec<&str>>();
+/// Settings Alchemy can't start without
+const REQUIRED_SETTINGS: [&str; 7] = [
+	"DB_HOST",
+	"DB_USER",
+	"DB_PASS",
+	"DB_NAME",
+	"RUST_ENV",
+	"APP_PORT",
+	"ADMIN_SECRET",
+];
 
-				let (key, value) = match &pair[..] {
-					&[key, value] => (key, value),
-					_ => panic!("Expected env variable pairs, got {}", content),
+/// Flattens a TOML value into settings, the keys of nested tables being joined with `_`, e.g.
+/// `port` of `[db]` into `DB_PORT`. Arrays are joined with commas like the list settings
+fn flatten_toml(
+	prefix: &str,
+	value: &toml::Value,
+	settings: &mut HashMap<String, String>,
+	errors: &mut Vec<String>,
+) {
+	let setting = match value {
+		toml::Value::Table(table) => {
+			for (key, value) in table {
+				let key = match prefix.is_empty() {
+					true => key.to_uppercase(),
+					false => format!("{}_{}", prefix, key.to_uppercase()),
 				};
 
-				std::env::set_var(key, value);
+				flatten_toml(&key, value, settings, errors);
+			}
+
+			return;
+		}
+		toml::Value::String(s) => s.clone(),
+		toml::Value::Array(items) => {
+			let mut values = Vec::new();
+
+			for item in items {
+				match item {
+					toml::Value::Table(_) | toml::Value::Array(_) => {
+						errors.push(format!("{}: expected a list of values", prefix));
+						return;
+					}
+					toml::Value::String(s) => values.push(s.clone()),
+					item => values.push(item.to_string()),
+				}
+			}
+
+			values.join(",")
+		}
+		value => value.to_string(),
+	};
+
+	settings.insert(prefix.to_string(), setting);
+}
+
+fn read_config_file(path: &str, settings: &mut HashMap<String, String>, errors: &mut Vec<String>) {
+	let content = match std::fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(e) => {
+			errors.push(format!("{}: {}", path, e));
+			return;
+		}
+	};
+
+	match content.parse::<toml::Value>() {
+		Ok(value) => flatten_toml("", &value, settings, errors),
+		Err(e) => errors.push(format!("{}: {}", path, e)),
+	}
+}
+
+fn read_env_file(path: &str, settings: &mut HashMap<String, String>, errors: &mut Vec<String>) {
+	let content = match std::fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(_) => return,
+	};
+
+	for line in content.lines().map(str::trim) {
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		// Values can contain `=` themselves, e.g. `RATE_LIMITS=user=600`
+		match line.split_once('=') {
+			Some((key, value)) => {
+				settings.insert(key.trim().to_uppercase(), value.to_string());
+			}
+			None => errors.push(format!("{}: expected a KEY=value pair, got {}", path, line)),
+		}
+	}
+}
+
+/// Position of the command in the arguments, the settings flags coming before it. A flag taking
+/// the next argument as its value, `--schema-apply serve` has to be written `--schema-apply=true
+/// serve`
+fn get_command_index(args: &[String]) -> usize {
+	let mut i = 0;
+
+	while i < args.len() {
+		match args[i].strip_prefix("--") {
+			None | Some("help") | Some("version") => break,
+			Some(flag) if flag.contains('=') => i += 1,
+			Some(_) => match args.get(i + 1) {
+				Some(value) if !value.starts_with("--") => i += 2,
+				_ => i += 1,
+			},
+		}
+	}
+
+	i
+}
+
+/// Arguments of the command, with the program name, for the CLI to parse
+pub fn get_command_args() -> Vec<String> {
+	let args: Vec<String> = std::env::args().collect();
+	let index = get_command_index(&args[1..]) + 1;
+
+	args[..1].iter().chain(&args[index..]).cloned().collect()
+}
+
+/// Settings given as `--db-host=localhost` or `--db-host localhost`, a flag without a value
+/// being `true`
+fn read_args(args: &[String], errors: &mut Vec<String>) -> HashMap<String, String> {
+	let mut settings = HashMap::new();
+	let mut args = args.iter().peekable();
+
+	while let Some(arg) = args.next() {
+		let flag = match arg.strip_prefix("--") {
+			Some(flag) if !flag.is_empty() => flag,
+			_ => {
+				errors.push(format!("Unexpected argument {}", arg));
+				continue;
+			}
+		};
+
+		let (key, value) = match flag.split_once('=') {
+			Some((key, value)) => (key, value.to_string()),
+			None => match args.peek() {
+				Some(value) if !value.starts_with("--") => (flag, args.next().unwrap().clone()),
+				_ => (flag, "true".to_string()),
+			},
+		};
+
+		settings.insert(key.replace('-', "_").to_uppercase(), value);
+	}
+
+	settings
+}
+
+fn check_number<T: FromStr>(errors: &mut Vec<String>, name: &str, value: &Option<String>) {
+	if let Some(value) = value.as_ref().filter(|v| !v.is_empty()) {
+		if value.parse::<T>().is_err() {
+			errors.push(format!(
+				"{}: expected a positive number, got {}",
+				name, value
+			));
+		}
+	}
+}
+
+fn check_role_pairs<T: FromStr>(
+	errors: &mut Vec<String>,
+	name: &str,
+	value: &Option<String>,
+	expected: &str,
+) {
+	for pair in split_list(value) {
+		let parsed = pair.split_once('=').map(|(_, v)| v.trim().parse::<T>());
+
+		if !matches!(parsed, Some(Ok(_))) {
+			errors.push(format!("{}: expected {}, got {}", name, expected, pair));
+		}
+	}
+}
+
+fn check_flag(errors: &mut Vec<String>, name: &str, value: &Option<String>) {
+	if let Some(value) = value.as_ref().filter(|v| !v.is_empty()) {
+		if parse_flag(&Some(value.clone())).is_none() {
+			errors.push(format!("{}: expected true or false, got {}", name, value));
+		}
+	}
+}
+
+impl Config {
+	/// Settings whose values can't be parsed, which would otherwise silently fall back to
+	/// their defaults
+	fn validate(&self) -> Vec<String> {
+		let mut errors = Vec::new();
+
+		if self.app_port.parse::<u16>().is_err() {
+			errors.push(format!("APP_PORT: expected a port, got {}", self.app_port));
+		}
+
+		check_number::<u16>(&mut errors, "META_PORT", &self.meta_port);
+		check_number::<u16>(&mut errors, "GRPC_PORT", &self.grpc_port);
+		check_number::<usize>(&mut errors, "DB_POOL_SIZE", &self.db_pool_size);
+		check_number::<u64>(&mut errors, "DB_CONNECT_TIMEOUT", &self.db_connect_timeout);
+		check_number::<u64>(&mut errors, "DB_QUERY_TIMEOUT", &self.db_query_timeout);
+		check_number::<u32>(&mut errors, "DB_RETRIES", &self.db_retries);
+		check_number::<u64>(&mut errors, "DB_RETRY_BACKOFF", &self.db_retry_backoff);
+		check_number::<usize>(&mut errors, "JWT_EXPIRATION", &self.jwt_expiration);
+		check_number::<u32>(&mut errors, "RATE_LIMIT_DEFAULT", &self.rate_limit_default);
+
+		for address in split_list(&self.trusted_proxies) {
+			if address.parse::<IpAddr>().is_err() {
+				errors.push(format!(
+					"TRUSTED_PROXIES: expected an IP address, got {}",
+					address
+				));
+			}
+		}
+
+		check_number::<u32>(&mut errors, "MAX_QUERY_DEPTH", &self.max_query_depth);
+		check_number::<u64>(
+			&mut errors,
+			"MAX_QUERY_COMPLEXITY",
+			&self.max_query_complexity,
+		);
+		check_number::<usize>(&mut errors, "MAX_BATCH_SIZE", &self.max_batch_size);
+		check_number::<u64>(
+			&mut errors,
+			"QUERY_DEFAULT_LIST_SIZE",
+			&self.query_default_list_size,
+		);
+		check_number::<u32>(
+			&mut errors,
+			"MAX_TRAVERSAL_DEPTH",
+			&self.max_traversal_depth,
+		);
+		check_number::<u32>(&mut errors, "QUERY_BATCH_SIZE", &self.query_batch_size);
+		check_number::<u32>(
+			&mut errors,
+			"INTERNAL_BATCH_SIZE",
+			&self.internal_batch_size,
+		);
+		check_number::<usize>(&mut errors, "QUERY_CACHE_SIZE", &self.query_cache_size);
+		check_number::<u64>(
+			&mut errors,
+			"SLOW_QUERY_THRESHOLD",
+			&self.slow_query_threshold,
+		);
+		check_number::<usize>(
+			&mut errors,
+			"RESPONSE_CACHE_SIZE",
+			&self.response_cache_size,
+		);
+		check_number::<usize>(&mut errors, "BYTES_MAX_SIZE", &self.bytes_max_size);
+		check_number::<usize>(&mut errors, "IMPORT_BATCH_SIZE", &self.import_batch_size);
+		check_number::<u64>(&mut errors, "PLUGIN_FUEL", &self.plugin_fuel);
+		check_number::<usize>(
+			&mut errors,
+			"PLUGIN_MEMORY_LIMIT",
+			&self.plugin_memory_limit,
+		);
+		check_number::<usize>(&mut errors, "UPLOAD_MAX_SIZE", &self.upload_max_size);
+		check_number::<usize>(&mut errors, "UPLOAD_MAX_FILES", &self.upload_max_files);
+		check_number::<u64>(&mut errors, "SHUTDOWN_TIMEOUT", &self.shutdown_timeout);
+		check_number::<u64>(
+			&mut errors,
+			"SCHEMA_POLL_INTERVAL",
+			&self.schema_poll_interval,
+		);
+		check_number::<u32>(
+			&mut errors,
+			"AUDIT_LOG_RETENTION_DAYS",
+			&self.audit_log_retention_days,
+		);
+
+		check_flag(&mut errors, "API_INTROSPECTION", &self.api_introspection);
+		check_flag(&mut errors, "API_PLAYGROUND", &self.api_playground);
+		check_flag(&mut errors, "API_DEBUG_ERRORS", &self.api_debug_errors);
+		check_flag(&mut errors, "API_ALLOWLIST", &self.api_allowlist);
+		check_flag(
+			&mut errors,
+			"PERSISTED_QUERIES_ONLY",
+			&self.persisted_queries_only,
+		);
+		check_flag(&mut errors, "SCHEMA_APPLY", &self.schema_apply);
+		check_flag(&mut errors, "AUDIT_LOG", &self.audit_log);
+
+		check_role_pairs::<u32>(
+			&mut errors,
+			"RATE_LIMITS",
+			&self.rate_limits,
+			"role=requests_per_minute",
+		);
+		check_number::<u64>(&mut errors, "OPERATION_TIMEOUT", &self.operation_timeout);
+		check_role_pairs::<u64>(
+			&mut errors,
+			"OPERATION_TIMEOUTS",
+			&self.operation_timeouts,
+			"role=seconds",
+		);
+		check_number::<u64>(
+			&mut errors,
+			"OPERATION_MEMORY_LIMIT",
+			&self.operation_memory_limit,
+		);
+		check_role_pairs::<u64>(
+			&mut errors,
+			"OPERATION_MEMORY_LIMITS",
+			&self.operation_memory_limits,
+			"role=bytes",
+		);
+
+		let tls_cert_file = self.tls_cert_file.as_ref().filter(|f| !f.is_empty());
+		let tls_key_file = self.tls_key_file.as_ref().filter(|f| !f.is_empty());
+
+		if tls_cert_file.is_some() != tls_key_file.is_some() {
+			errors.push("TLS_CERT_FILE and TLS_KEY_FILE: expected both to be set".to_string());
+		}
+
+		match self.get_tenant_source() {
+			None => (),
+			// Clients could otherwise select any database, `_system` included
+			Some("header") | Some("subdomain") | Some("claim") if self.get_tenants().is_empty() => {
+				errors.push("TENANTS: expected the databases of the tenants".to_string())
+			}
+			Some("subdomain") if self.get_tenant_domain().is_none() => {
+				errors.push("TENANT_DOMAIN: expected the domain of the tenants".to_string())
 			}
+			Some("header") | Some("subdomain") | Some("claim") => (),
+			Some(source) => errors.push(format!(
+				"TENANT_SOURCE: expected header, subdomain or claim, got {}",
+				source
+			)),
+		}
+
+		let prefix = self.get_schema_prefix();
+
+		if !prefix.is_empty()
+			&& !(prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+				&& prefix.chars().all(|c| c.is_ascii_alphanumeric()))
+		{
+			errors.push(format!(
+				"SCHEMA_PREFIX: expected letters and digits starting with a letter, got {}",
+				prefix
+			));
+		}
 
-			match envy::from_env::<Config>() {
-				Ok(config) => Ok(config),
-				Err(e) => panic!("Failed to read the config from env: {}", e),
+		if let Some(case) = self.operation_case.as_deref().filter(|c| !c.is_empty()) {
+			if case != "camel" && case != "snake" {
+				errors.push(format!(
+					"OPERATION_CASE: expected camel or snake, got {}",
+					case
+				));
 			}
 		}
+
+		if !self.get_operation_template().contains("{name}") {
+			errors.push(format!(
+				"OPERATION_TEMPLATE: expected a {{name}} placeholder, got {}",
+				self.get_operation_template()
+			));
+		}
+
+		if let Some(dir) = self.get_admin_ui_dir() {
+			if !Path::new(dir).join("index.html").is_file() {
+				errors.push(format!("ADMIN_UI_DIR: no index.html in {}", dir));
+			}
+		}
+
+		if let Some(format) = self.log_format.as_deref().filter(|f| !f.is_empty()) {
+			if format != "json" && format != "pretty" {
+				errors.push(format!(
+					"LOG_FORMAT: expected json or pretty, got {}",
+					format
+				));
+			}
+		}
+
+		errors
 	}
 }
 
+/// Layers the settings of the config file, the `.env` file, the env variables and the command
+/// line flags, each one overriding the previous ones. Every missing or invalid setting is
+/// reported at once
+fn load_config() -> Result<Config, Vec<String>> {
+	let mut errors = Vec::new();
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	let flags = read_args(&args[..get_command_index(&args)], &mut errors);
+
+	let config_file = flags
+		.get("CONFIG")
+		.cloned()
+		.or_else(|| std::env::var("CONFIG_FILE").ok())
+		.filter(|f| !f.is_empty());
+
+	let mut settings = HashMap::new();
+
+	match config_file {
+		Some(path) => read_config_file(&path, &mut settings, &mut errors),
+		None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+			read_config_file(DEFAULT_CONFIG_FILE, &mut settings, &mut errors)
+		}
+		None => (),
+	}
+
+	read_env_file(".env", &mut settings, &mut errors);
+	settings.extend(std::env::vars());
+	settings.extend(flags);
+
+	// Empty values, like the ones of `.env.example`, are left unset
+	settings.retain(|_, value| !value.is_empty());
+
+	for setting in REQUIRED_SETTINGS {
+		if !settings.contains_key(setting) {
+			errors.push(format!("{}: missing", setting));
+		}
+	}
+
+	if !errors.is_empty() {
+		return Err(errors);
+	}
+
+	let config = envy::from_iter::<_, Config>(settings).map_err(|e| vec![e.to_string()])?;
+	let errors = config.validate();
+
+	match errors.is_empty() {
+		true => Ok(config),
+		false => Err(errors),
+	}
+}
+
+/// Logging isn't initialized before the config is loaded, so the errors are printed as is
+fn exit_with_errors(errors: Vec<String>) -> ! {
+	eprintln!("Invalid configuration:");
+
+	for error in errors {
+		eprintln!("  {}", error);
+	}
+
+	std::process::exit(1)
+}
+
 lazy_static! {
-	pub static ref CONFIG: Config = load_config().unwrap();
+	pub static ref CONFIG: Config = load_config().unwrap_or_else(|e| exit_with_errors(e));
 }
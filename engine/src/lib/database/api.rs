@@ -4,7 +4,17 @@ use std::collections::HashMap;
 use std::fmt::Formatter;
 use std::sync::Arc;
 
-use crate::lib::schema::{get_all_collections, get_all_edges};
+use crate::lib::database::schema::Versioning;
+use crate::lib::events::EventOperation;
+use crate::lib::locales::{get_translatable_fields, unwrap_translatable_rule};
+use crate::lib::naming::get_type_name;
+use crate::lib::plugins::get_all_plugins;
+use crate::lib::schema::{
+	get_all_actions, get_all_collections, get_all_edges, get_all_graphs, get_all_hooks, HookStage,
+	SchemaActionKind,
+};
+use crate::lib::storage::DEFAULT_STORAGE;
+use crate::lib::CONFIG;
 
 const ERR_CHILD_NOT_DEFINED: &str = "ERROR: Child type not defined";
 const ERR_UNDEFINED_TYPE: &str = "ERROR: Undefined associated SDL type";
@@ -13,6 +23,8 @@ const ERR_UNDEFINED_TYPE: &str = "ERROR: Undefined associated SDL type";
 pub struct DbMap {
 	pub primitives: Vec<DbPrimitive>,
 	pub relationships: Vec<DbRelationship>,
+	pub graphs: Vec<DbGraph>,
+	pub actions: Vec<Arc<DbAction>>,
 }
 
 impl DbMap {
@@ -20,10 +32,29 @@ impl DbMap {
 		Self {
 			primitives: Vec::new(),
 			relationships: Vec::new(),
+			graphs: Vec::new(),
+			actions: Vec::new(),
 		}
 	}
 }
 
+/// A named graph of ArangoDB and the entities of its vertex collections
+#[derive(Clone, Debug)]
+pub struct DbGraph {
+	pub name: String,
+	pub entities: Vec<Arc<DbEntity>>,
+}
+
+impl DbGraph {
+	pub fn get_neighbors_field_name(&self) -> String {
+		format!("{}_neighbors", self.name.to_case(convert_case::Case::Snake))
+	}
+
+	pub fn has_entity(&self, name: &str) -> bool {
+		self.entities.iter().any(|e| e.name == name)
+	}
+}
+
 #[derive(Clone)]
 pub enum DbPrimitive {
 	Entity(Arc<DbEntity>),
@@ -66,6 +97,18 @@ impl DbRelationshipType {
 	}
 }
 
+impl DbRelationshipType {
+	/// Cardinality of the same relationship seen from the other collection
+	pub fn reverse(&self) -> Self {
+		match self {
+			DbRelationshipType::OneToOne => DbRelationshipType::OneToOne,
+			DbRelationshipType::OneToMany => DbRelationshipType::ManyToOne,
+			DbRelationshipType::ManyToOne => DbRelationshipType::OneToMany,
+			DbRelationshipType::ManyToMany => DbRelationshipType::ManyToMany,
+		}
+	}
+}
+
 impl From<&str> for DbRelationshipType {
 	fn from(value: &str) -> Self {
 		return match value {
@@ -96,6 +139,17 @@ impl ToString for DbRelationshipDirection {
 	}
 }
 
+impl DbRelationshipDirection {
+	/// Direction traversing the same edges from the other end
+	pub fn reverse(&self) -> Self {
+		match *self {
+			DbRelationshipDirection::Inbound => DbRelationshipDirection::Outbound,
+			DbRelationshipDirection::Outbound => DbRelationshipDirection::Inbound,
+			DbRelationshipDirection::Any => DbRelationshipDirection::Any,
+		}
+	}
+}
+
 impl From<&str> for DbRelationshipDirection {
 	fn from(value: &str) -> Self {
 		return match value {
@@ -107,6 +161,25 @@ impl From<&str> for DbRelationshipDirection {
 	}
 }
 
+/// Applied to the related documents when a `from` document of the relationship is removed
+#[derive(Clone, PartialEq, Debug)]
+pub enum DbOnDelete {
+	Cascade,
+	Restrict,
+	SetNull,
+}
+
+impl From<&str> for DbOnDelete {
+	fn from(value: &str) -> Self {
+		return match value {
+			"cascade" => Self::Cascade,
+			"restrict" => Self::Restrict,
+			"set_null" => Self::SetNull,
+			&_ => unreachable!(),
+		};
+	}
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct DbRelationship {
 	pub name: String,
@@ -115,6 +188,63 @@ pub struct DbRelationship {
 	pub to: Arc<DbEntity>,
 	pub relationship_type: DbRelationshipType,
 	pub direction: DbRelationshipDirection,
+	/// Attributes stored on the edges, exposed by the `{name}_edges` field
+	pub edge_properties: Option<Arc<DbEmbedded>>,
+	pub on_delete: DbOnDelete,
+	/// Collections of a polymorphic relationship, `to` is the first of them
+	pub union: Option<Arc<DbUnion>>,
+	pub description: Option<String>,
+}
+
+impl DbRelationship {
+	pub fn get_edges_field_name(&self) -> String {
+		format!("{}_edges", self.name)
+	}
+
+	/// Entities the relationship can relate to
+	pub fn get_targets(&self) -> Vec<&Arc<DbEntity>> {
+		match self.union {
+			Some(ref union) => union.entities.iter().collect(),
+			None => vec![&self.to],
+		}
+	}
+
+	/// Whether the relationship is a field of the API, its collections being in the API. Others
+	/// are still followed on delete
+	pub fn is_exposed(&self) -> bool {
+		self.from.exposed && self.get_targets().iter().all(|e| e.exposed)
+	}
+}
+
+/// Receives the actions and hooks calls
+#[derive(Clone, PartialEq, Debug)]
+pub enum DbHandler {
+	Http {
+		url: String,
+		headers: HashMap<String, String>,
+	},
+	/// WASM plugin, with the hash of its module when the schema was generated
+	Plugin { name: String, hash: String },
+}
+
+/// Custom field of the API resolved by an HTTP endpoint or a plugin
+#[derive(Clone, PartialEq, Debug)]
+pub struct DbAction {
+	pub name: String,
+	pub kind: SchemaActionKind,
+	pub handler: DbHandler,
+	pub arguments: Vec<DbProperty>,
+	/// Result of the action, the property is named after its root field
+	pub output: DbProperty,
+	/// Roles allowed to execute the action, every authenticated role when empty
+	pub roles: Vec<String>,
+}
+
+/// Union type of the entities a polymorphic relationship relates to
+#[derive(Clone, PartialEq, Debug)]
+pub struct DbUnion {
+	pub name: String,
+	pub entities: Vec<Arc<DbEntity>>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -122,6 +252,87 @@ pub struct DbEntity {
 	pub name: String,
 	pub collection_name: String,
 	pub properties: Vec<DbProperty>,
+	pub permissions: HashMap<String, DbPermission>,
+	/// Property holding the variant of each document in polymorphic collections
+	pub discriminator: Option<String>,
+	pub variants: Vec<DbVariant>,
+	/// Read-only properties computed from the document when selected
+	pub computed: Vec<DbProperty>,
+	pub ttl: Option<DbTtl>,
+	/// Seconds the API responses reading the documents are cached for, not cached when unset
+	pub cache_max_age: Option<u32>,
+	/// Revision history of the documents, recorded on update and delete when set
+	pub versioning: Option<Versioning>,
+	/// Only published documents are read unless previewing, new documents being drafts
+	pub publication: bool,
+	/// Key of the only document of singleton collections, read and updated as a whole
+	pub singleton: Option<String>,
+	/// The operations of the collection are marked `@deprecated` with the reason
+	pub deprecation_reason: Option<String>,
+	pub description: Option<String>,
+	/// Hooks of the mutations, in the order they run
+	pub hooks: Vec<DbHook>,
+	/// Collections left out of the API keep their entity for the relationships to them
+	pub exposed: bool,
+}
+
+impl DbEntity {
+	/// Properties of all variants, the first variant declaring a property wins
+	pub fn get_variant_properties(&self) -> Vec<&DbProperty> {
+		let mut properties: Vec<&DbProperty> = Vec::new();
+
+		for property in self.variants.iter().flat_map(|v| v.properties.iter()) {
+			let exists = self.properties.iter().any(|p| p.name == property.name)
+				|| properties.iter().any(|p| p.name == property.name);
+
+			if !exists {
+				properties.push(property);
+			}
+		}
+
+		properties
+	}
+
+	/// Hooks of the stage running on the operation
+	pub fn get_hooks(&self, stage: HookStage, operation: EventOperation) -> Vec<&DbHook> {
+		self.hooks
+			.iter()
+			.filter(|h| h.stage == stage && h.operations.contains(&operation))
+			.collect()
+	}
+}
+
+/// Handler called synchronously around the mutations of an entity
+#[derive(Clone, PartialEq, Debug)]
+pub struct DbHook {
+	pub name: String,
+	pub stage: HookStage,
+	pub operations: Vec<EventOperation>,
+	pub handler: DbHandler,
+}
+
+/// Documents expire at the date in `field`, set `expire_after` seconds past their creation
+#[derive(Clone, PartialEq, Debug)]
+pub struct DbTtl {
+	pub field: String,
+	pub expire_after: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct DbVariant {
+	pub name: String,
+	/// Value of the discriminator for documents of this variant
+	pub value: String,
+	pub properties: Vec<DbProperty>,
+}
+
+/// Role permissions, both maps go from an attribute to a session variable name
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct DbPermission {
+	/// Documents must match every session variable to be accessible by the role
+	pub filter: HashMap<String, String>,
+	/// Attributes set from the session variables when the role creates a document
+	pub presets: HashMap<String, String>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug)]
@@ -130,18 +341,111 @@ pub struct DbProperty {
 	pub associated_type: Option<String>,
 	pub scalar_type: DbScalarType,
 	pub required: bool,
+	/// Value generated on create when omitted
+	pub generator: Option<DbGenerator>,
+	/// The value is also stored as the document `_key`
+	pub is_key: bool,
+	/// AQL expression of computed properties, the document is available as `doc`
+	pub expression: Option<String>,
+	/// The document holds a value per locale, the one of the requested locale being returned
+	pub translatable: bool,
+	/// The field is marked `@deprecated` with the reason
+	pub deprecation_reason: Option<String>,
+	pub description: Option<String>,
+	/// Descriptions of the values of enum properties, by value
+	pub enum_descriptions: HashMap<String, String>,
+	pub directives: DbFieldDirectives,
+}
+
+/// Directives of a field, declared by the `directives` keyword of its rule. They are emitted
+/// in the SDL and applied by the API
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct DbFieldDirectives {
+	/// `@auth`, the field is only read by these roles and the admin
+	pub auth_roles: Option<Vec<String>>,
+	/// `@cache`, max age of the cached responses reading the field, 0 when they aren't cached
+	pub cache_max_age: Option<u32>,
+	/// `@cost`, complexity of the field in the query cost analysis instead of 1
+	pub cost: Option<u64>,
+}
+
+impl DbFieldDirectives {
+	fn parse(rule: &Value) -> Self {
+		Self {
+			auth_roles: rule["auth"]["roles"].as_array().map(|roles| {
+				roles
+					.iter()
+					.filter_map(|r| r.as_str())
+					.map(str::to_string)
+					.collect()
+			}),
+			cache_max_age: rule["cache"]["maxAge"].as_u64().map(|m| m as u32),
+			cost: rule["cost"]["complexity"].as_u64(),
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DbGenerator {
+	UuidV4,
+	UuidV7,
+}
+
+impl DbGenerator {
+	pub fn generate(&self) -> String {
+		match self {
+			DbGenerator::UuidV4 => uuid::Uuid::new_v4(),
+			DbGenerator::UuidV7 => uuid::Uuid::now_v7(),
+		}
+		.hyphenated()
+		.to_string()
+	}
+
+	fn parse(value: &str) -> Option<Self> {
+		match value {
+			"uuid_v4" => Some(Self::UuidV4),
+			"uuid_v7" => Some(Self::UuidV7),
+			_ => None,
+		}
+	}
+}
+
+/// Structured sub-document stored inside an entity
+#[derive(Clone, PartialEq, Debug)]
+pub struct DbEmbedded {
+	pub name: String,
+	pub properties: Vec<DbProperty>,
 }
 
 #[derive(PartialEq, Default, Clone, Debug)]
 pub enum DbScalarType {
 	Array(Box<DbScalarType>),
 	Enum(Vec<String>),
+	Embedded(Arc<DbEmbedded>),
 	#[default]
 	String,
 	Object,
 	Float,
 	Int,
+	BigInt,
 	Boolean,
+	Bytes,
+	DateTime,
+	Decimal,
+	Uuid,
+	/// Id of an uploaded file, stored in the named storage
+	File(String),
+}
+
+impl DbScalarType {
+	/// Structured sub-document type, also when it's the item type of a list
+	pub fn get_embedded(&self) -> Option<&DbEmbedded> {
+		match self {
+			DbScalarType::Embedded(embedded) => Some(embedded),
+			DbScalarType::Array(t) => t.get_embedded(),
+			_ => None,
+		}
+	}
 }
 
 impl From<JsonType> for DbScalarType {
@@ -151,9 +455,15 @@ impl From<JsonType> for DbScalarType {
 			JsonType::Enum(values) => DbScalarType::Enum(values),
 			JsonType::Boolean => DbScalarType::Boolean,
 			JsonType::Integer => DbScalarType::Int,
+			JsonType::BigInteger => DbScalarType::BigInt,
 			JsonType::Number => DbScalarType::Float,
 			JsonType::Object => DbScalarType::Object,
 			JsonType::String => DbScalarType::String,
+			JsonType::Bytes => DbScalarType::Bytes,
+			JsonType::DateTime => DbScalarType::DateTime,
+			JsonType::Decimal => DbScalarType::Decimal,
+			JsonType::Uuid => DbScalarType::Uuid,
+			JsonType::File(storage) => DbScalarType::File(storage),
 		}
 	}
 }
@@ -164,9 +474,44 @@ pub enum JsonType {
 	Enum(Vec<String>),
 	Boolean,
 	Integer,
+	BigInteger,
 	Number,
 	Object,
 	String,
+	Bytes,
+	DateTime,
+	Decimal,
+	Uuid,
+	File(String),
+}
+
+impl JsonType {
+	/// Values of an enum, also when it's the item type of an array
+	fn get_enum_values(&self) -> Option<&Vec<String>> {
+		match self {
+			JsonType::Enum(values) => Some(values),
+			JsonType::Array(t) => t.get_enum_values(),
+			_ => None,
+		}
+	}
+}
+
+fn build_handler(entry: &Value, plugins: &[Value]) -> DbHandler {
+	match entry["plugin"].as_str() {
+		Some(name) => DbHandler::Plugin {
+			name: name.to_string(),
+			hash: plugins
+				.iter()
+				.find(|p| p["name"] == name)
+				.and_then(|p| p["hash"].as_str())
+				.unwrap_or_default()
+				.to_string(),
+		},
+		None => DbHandler::Http {
+			url: entry["url"].as_str().unwrap_or_default().to_string(),
+			headers: serde_json::from_value(entry["headers"].clone()).unwrap_or_default(),
+		},
+	}
 }
 
 pub async fn generate_sdl() -> DbMap {
@@ -174,6 +519,8 @@ pub async fn generate_sdl() -> DbMap {
 	let edges = get_all_edges().await;
 
 	let mut sdl: DbMap = DbMap::new();
+	let hooks = get_all_hooks().await;
+	let plugins = get_all_plugins().await;
 	let mut collections_by_keys: HashMap<String, Arc<DbEntity>> = HashMap::new();
 
 	let time = std::time::Instant::now();
@@ -181,20 +528,23 @@ pub async fn generate_sdl() -> DbMap {
 	for entry in collections.clone().iter() {
 		let collection_name = entry["name"].as_str().unwrap().to_string();
 
-		let type_name = pluralizer::pluralize(
-			collection_name.to_case(convert_case::Case::Pascal).as_str(),
-			1,
-			false,
-		);
-		let entry_properties = entry["schema"].get("properties").unwrap();
-		let entry_required_properties: Vec<String> = entry["schema"]
-			.get("required")
-			.unwrap()
-			.as_array()
-			.unwrap()
-			.iter()
-			.map(|v| v.as_str().unwrap().to_string())
-			.collect();
+		// Relationships from and to the collections left out are left out of the API too, but
+		// they're still followed when removing documents
+		let exposed = CONFIG.is_collection_exposed(&collection_name, entry["exposed"].as_bool());
+
+		let type_name = get_type_name(&collection_name);
+		let translatable = get_translatable_fields(entry);
+
+		// Translatable fields are typed by the rule of their values
+		let mut entry_properties = entry["schema"].get("properties").unwrap().clone();
+
+		for field in &translatable {
+			if let Some(rule) = entry_properties.get_mut(field) {
+				*rule = unwrap_translatable_rule(rule);
+			}
+		}
+
+		let entry_required_properties = entry["schema"].get("required").unwrap();
 
 		let mut props: Vec<DbProperty> = Vec::new();
 
@@ -206,39 +556,88 @@ pub async fn generate_sdl() -> DbMap {
 			..Default::default()
 		});
 
-		for prop in entry_properties.as_object().unwrap().iter() {
-			let prop_name = prop.0.clone();
+		props.extend(
+			build_properties(
+				&type_name,
+				&entry_properties,
+				entry_required_properties,
+				&mut sdl,
+			)
+			.into_iter()
+			.map(|p| DbProperty {
+				translatable: translatable.contains(&p.name),
+				..p
+			}),
+		);
 
-			let json_type = build_json_type(prop.1);
-			let scalar_type: DbScalarType = json_type.clone().into();
+		let variants: Vec<DbVariant> = entry["variants"]
+			.as_array()
+			.map(|variants| {
+				variants
+					.iter()
+					.map(|v| {
+						let name = format!(
+							"{}{}",
+							CONFIG.get_schema_prefix(),
+							v["name"].as_str().unwrap()
+						);
 
-			let mut associated_type: Option<String> = None;
+						DbVariant {
+							properties: build_properties(
+								&name,
+								&v["properties"],
+								&v["required"],
+								&mut sdl,
+							),
+							value: v["value"].as_str().unwrap().to_string(),
+							name,
+						}
+					})
+					.collect()
+			})
+			.unwrap_or_default();
 
-			if let JsonType::Enum(values) = json_type {
-				let enum_values: Vec<String> = values
+		let computed_schema: serde_json::Map<String, Value> = entry["computed"]
+			.as_array()
+			.map(|computed| {
+				computed
 					.iter()
-					.map(|v| format!("\t{}", v.to_case(convert_case::Case::UpperSnake)))
-					.collect();
+					.map(|c| (c["name"].as_str().unwrap().to_string(), c["schema"].clone()))
+					.collect()
+			})
+			.unwrap_or_default();
 
-				let enum_name = format!(
-					"{}{}Enum",
-					type_name,
-					prop_name.to_case(convert_case::Case::Pascal)
-				);
+		let mut computed = build_properties(
+			&type_name,
+			&Value::Object(computed_schema),
+			&Value::Null,
+			&mut sdl,
+		);
 
-				associated_type = Some(enum_name.clone());
+		for property in computed.iter_mut() {
+			property.expression = entry["computed"]
+				.as_array()
+				.and_then(|c| c.iter().find(|c| c["name"] == property.name.as_str()))
+				.and_then(|c| c["expression"].as_str())
+				.map(str::to_string);
+		}
 
-				sdl.primitives.push(DbPrimitive::Enum(Arc::new(DbEnum {
-					name: enum_name,
-					properties: enum_values,
-				})));
-			}
+		let ttl = entry["ttl"]["field"].as_str().map(|field| DbTtl {
+			field: field.to_string(),
+			expire_after: entry["ttl"]["expire_after"].as_u64().unwrap_or(0),
+		});
+
+		// The TTL field is set on create only, so it's exposed like a computed property
+		if let Some(ttl) = &ttl {
+			props.retain(|p| p.name != ttl.field);
 
-			props.push(DbProperty {
-				name: prop_name.clone(),
-				associated_type,
-				scalar_type,
-				required: entry_required_properties.contains(&prop_name),
+			computed.push(DbProperty {
+				name: ttl.field.clone(),
+				scalar_type: DbScalarType::DateTime,
+				expression: Some(format!(
+					"doc[{}]",
+					serde_json::to_string(&ttl.field).unwrap()
+				)),
 				..Default::default()
 			});
 		}
@@ -247,14 +646,40 @@ pub async fn generate_sdl() -> DbMap {
 			name: type_name,
 			collection_name: collection_name.clone(),
 			properties: props,
+			permissions: build_permissions(&entry["permissions"]),
+			discriminator: entry["discriminator"].as_str().map(str::to_string),
+			variants,
+			computed,
+			ttl,
+			cache_max_age: entry["cache_max_age"].as_u64().map(|a| a as u32),
+			versioning: serde_json::from_value(entry["versioning"].clone()).ok(),
+			publication: entry["publication"].as_bool().unwrap_or(false),
+			singleton: entry["singleton"].as_str().map(str::to_string),
+			deprecation_reason: entry["deprecation_reason"].as_str().map(str::to_string),
+			description: entry["description"].as_str().map(str::to_string),
+			hooks: hooks
+				.iter()
+				.filter(|h| h["collection"] == collection_name.as_str())
+				.map(|h| DbHook {
+					name: h["name"].as_str().unwrap().to_string(),
+					stage: serde_json::from_value(h["stage"].clone()).unwrap(),
+					operations: serde_json::from_value(h["operations"].clone()).unwrap_or_default(),
+					handler: build_handler(h, &plugins),
+				})
+				.collect(),
+			exposed,
 		});
 
 		// We insert it on this hash map for future use of relationships
 		collections_by_keys.insert(collection_name, entity.clone());
 
-		sdl.primitives.push(DbPrimitive::Entity(entity.clone()))
+		if exposed {
+			sdl.primitives.push(DbPrimitive::Entity(entity.clone()))
+		}
 	}
 
+	let mut inverses = Vec::new();
+
 	for entry in edges.clone().iter() {
 		let prop_name = entry["name"].as_str().unwrap();
 		let edge = entry["edge"].as_str().unwrap();
@@ -263,10 +688,94 @@ pub async fn generate_sdl() -> DbMap {
 		let relationship_type: DbRelationshipType = entry["type"].as_str().unwrap().into();
 		let relationship_direction: DbRelationshipDirection =
 			entry["direction"].as_str().unwrap().into();
+		let on_delete: DbOnDelete = entry["on_delete"].as_str().unwrap_or("set_null").into();
+
+		// Polymorphic relationships list their collections in `targets`, `to` names their union
+		let targets: Vec<Arc<DbEntity>> = match entry["targets"].as_array() {
+			Some(targets) => targets
+				.iter()
+				.filter_map(|t| t.as_str().and_then(|t| collections_by_keys.get(t)))
+				.cloned()
+				.collect(),
+			None => collections_by_keys.get(to).cloned().into_iter().collect(),
+		};
+
+		let union = entry["targets"].as_array().map(|_| {
+			Arc::new(DbUnion {
+				name: format!("{}{}", CONFIG.get_schema_prefix(), to),
+				entities: targets.clone(),
+			})
+		});
 
 		if let (Some(from_entity), Some(to_entity)) =
-			(collections_by_keys.get(from), collections_by_keys.get(to))
+			(collections_by_keys.get(from), targets.first())
 		{
+			let edge_name = format!(
+				"{}{}Edge",
+				from_entity.name,
+				prop_name.to_case(convert_case::Case::Pascal)
+			);
+
+			let edge_properties = entry["schema"]["properties"]
+				.as_object()
+				.filter(|p| !p.is_empty())
+				.map(|_| {
+					Arc::new(DbEmbedded {
+						properties: build_properties(
+							&edge_name,
+							&entry["schema"]["properties"],
+							&entry["schema"]["required"],
+							&mut sdl,
+						),
+						name: edge_name,
+					})
+				});
+
+			// Relationships are also traversed from the other collection, unless opted out
+			if entry["generate_inverse"].as_bool().unwrap_or(true) {
+				let inverse_type = relationship_type.reverse();
+
+				let inverse_name = entry["inverse"]
+					.as_str()
+					.map(str::to_string)
+					.unwrap_or_else(|| {
+						pluralizer::pluralize(
+							from_entity.name.to_case(convert_case::Case::Snake).as_str(),
+							if inverse_type.returns_array() { 2 } else { 1 },
+							false,
+						)
+					});
+
+				// Each collection of a polymorphic relationship gets the inverse field
+				for target in &targets {
+					// The edge type also names its `node`, so each direction has its own
+					let inverse_edge_properties = edge_properties.as_ref().map(|e| {
+						Arc::new(DbEmbedded {
+							name: format!(
+								"{}{}Edge",
+								target.name,
+								inverse_name.to_case(convert_case::Case::Pascal)
+							),
+							properties: e.properties.clone(),
+						})
+					});
+
+					inverses.push(DbRelationship {
+						name: inverse_name.clone(),
+						edge: edge.to_string(),
+						from: target.clone(),
+						to: from_entity.clone(),
+						relationship_type: inverse_type.clone(),
+						direction: relationship_direction.reverse(),
+						edge_properties: inverse_edge_properties,
+						// Removing a `to` document only removes its edges
+						on_delete: DbOnDelete::SetNull,
+						union: None,
+						description: None,
+					});
+				}
+			}
+
 			sdl.relationships.push(DbRelationship {
 				name: prop_name.to_string(),
 				edge: edge.to_string(),
@@ -274,12 +783,106 @@ pub async fn generate_sdl() -> DbMap {
 				to: to_entity.clone(),
 				relationship_type,
 				direction: relationship_direction,
+				edge_properties,
+				on_delete,
+				union,
+				description: entry["description"].as_str().map(str::to_string),
 			})
 		}
 	}
 
-	println!("SDL generated in {:?}", time.elapsed());
-	println!(
+	for graph in get_all_graphs().await {
+		let definitions = graph["edgeDefinitions"]
+			.as_array()
+			.cloned()
+			.unwrap_or_default();
+
+		let mut collections: Vec<&str> = definitions
+			.iter()
+			.flat_map(|d| {
+				d["from"]
+					.as_array()
+					.into_iter()
+					.chain(d["to"].as_array())
+					.flatten()
+			})
+			.chain(graph["orphanCollections"].as_array().into_iter().flatten())
+			.filter_map(|c| c.as_str())
+			.collect();
+
+		collections.sort_unstable();
+		collections.dedup();
+
+		sdl.graphs.push(DbGraph {
+			name: graph["_key"].as_str().unwrap().to_string(),
+			entities: collections
+				.into_iter()
+				.filter_map(|c| collections_by_keys.get(c).filter(|e| e.exposed).cloned())
+				.collect(),
+		});
+	}
+
+	for entry in get_all_actions().await {
+		let name = entry["name"].as_str().unwrap().to_string();
+		let type_name = format!(
+			"{}{}",
+			CONFIG.get_schema_prefix(),
+			name.to_case(convert_case::Case::Pascal)
+		);
+
+		let arguments = match entry["arguments"]["properties"].is_object() {
+			true => build_properties(
+				&type_name,
+				&entry["arguments"]["properties"],
+				&entry["arguments"]["required"],
+				&mut sdl,
+			),
+			false => Vec::new(),
+		};
+
+		let mut output = build_properties(
+			&type_name,
+			&serde_json::json!({ "output": entry["output"] }),
+			&Value::Null,
+			&mut sdl,
+		)
+		.remove(0);
+
+		// Root field of the action, e.g. `crmSendInvoice` for `sendInvoice`
+		output.name = match CONFIG.get_schema_prefix() {
+			"" => name.clone(),
+			_ => type_name.to_case(convert_case::Case::Camel),
+		};
+
+		sdl.actions.push(Arc::new(DbAction {
+			name,
+			kind: serde_json::from_value(entry["kind"].clone()).unwrap(),
+			handler: build_handler(&entry, &plugins),
+			arguments,
+			output,
+			roles: serde_json::from_value(entry["roles"].clone()).unwrap_or_default(),
+		}));
+	}
+
+	// Declared relationships and properties take precedence over generated inverses
+	for inverse in inverses {
+		let exists = inverse
+			.from
+			.properties
+			.iter()
+			.any(|p| p.name == inverse.name)
+			|| sdl
+				.relationships
+				.iter()
+				.any(|r| r.from.name == inverse.from.name && r.name == inverse.name);
+
+		if !exists {
+			sdl.relationships.push(inverse);
+		}
+	}
+
+	tracing::debug!(elapsed = ?time.elapsed(), "Generated the SDL");
+	tracing::info!(
 		"Found [{}] entities and [{}] relationships",
 		sdl.primitives
 			.iter()
@@ -296,6 +899,150 @@ pub async fn generate_sdl() -> DbMap {
 	sdl
 }
 
+fn build_permissions(data: &Value) -> HashMap<String, DbPermission> {
+	fn build_variables_map(data: &Value) -> HashMap<String, String> {
+		data.as_object()
+			.map(|o| {
+				o.iter()
+					.filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+					.collect()
+			})
+			.unwrap_or_default()
+	}
+
+	let mut permissions = HashMap::new();
+
+	if let Some(roles) = data.as_object() {
+		for (role, permission) in roles {
+			permissions.insert(
+				role.clone(),
+				DbPermission {
+					filter: build_variables_map(&permission["filter"]),
+					presets: build_variables_map(&permission["presets"]),
+				},
+			);
+		}
+	}
+
+	permissions
+}
+
+/// Builds the properties of an entity or embedded object from its JSON schema
+fn build_properties(
+	type_name: &str,
+	properties: &Value,
+	required: &Value,
+	sdl: &mut DbMap,
+) -> Vec<DbProperty> {
+	let required_properties: Vec<String> = required
+		.as_array()
+		.map(|r| r.iter().map(|v| v.as_str().unwrap().to_string()).collect())
+		.unwrap_or_default();
+
+	let mut props: Vec<DbProperty> = Vec::new();
+
+	for prop in properties.as_object().unwrap().iter() {
+		let prop_name = prop.0.clone();
+
+		// Names are used as GraphQL fields and AQL attributes, others are left out of the API
+		if !is_field_name(&prop_name) {
+			tracing::warn!(
+				type_name,
+				"Ignoring the property {:?}, not a valid name",
+				prop_name
+			);
+
+			continue;
+		}
+
+		let json_type = build_json_type(prop.1);
+		let mut scalar_type: DbScalarType = json_type.clone().into();
+
+		let mut associated_type: Option<String> = None;
+
+		if let Some(values) = json_type.get_enum_values() {
+			let enum_name = format!(
+				"{}{}Enum",
+				type_name,
+				prop_name.to_case(convert_case::Case::Pascal)
+			);
+
+			associated_type = Some(enum_name.clone());
+
+			sdl.primitives.push(DbPrimitive::Enum(Arc::new(DbEnum {
+				name: enum_name,
+				properties: values.clone(),
+			})));
+		}
+
+		let is_list = prop.1["type"] == "array";
+		let object_schema = if is_list { &prop.1["items"] } else { prop.1 };
+
+		// Objects with their own properties are structured, anything else is schemaless
+		if object_schema["type"] == "object" && object_schema["properties"].is_object() {
+			let mut embedded_name = format!(
+				"{}{}",
+				type_name,
+				prop_name.to_case(convert_case::Case::Pascal)
+			);
+
+			// Lists are named after a single item, e.g. `line_items` holds `OrderLineItem`
+			if is_list {
+				embedded_name = pluralizer::pluralize(embedded_name.as_str(), 1, false);
+			}
+
+			let embedded = DbScalarType::Embedded(Arc::new(DbEmbedded {
+				properties: build_properties(
+					&embedded_name,
+					&object_schema["properties"],
+					&object_schema["required"],
+					sdl,
+				),
+				name: embedded_name,
+			}));
+
+			scalar_type = if is_list {
+				DbScalarType::Array(Box::new(embedded))
+			} else {
+				embedded
+			};
+		}
+
+		props.push(DbProperty {
+			name: prop_name.clone(),
+			associated_type,
+			scalar_type,
+			required: required_properties.contains(&prop_name),
+			generator: prop.1["generate"].as_str().and_then(DbGenerator::parse),
+			is_key: prop.1["useAsKey"].as_bool().unwrap_or(false),
+			expression: None,
+			translatable: false,
+			deprecation_reason: prop.1["deprecationReason"].as_str().map(str::to_string),
+			description: prop.1["description"].as_str().map(str::to_string),
+			enum_descriptions: prop.1["enumDescriptions"]
+				.as_object()
+				.into_iter()
+				.flatten()
+				.filter_map(|(value, d)| d.as_str().map(|d| (value.clone(), d.to_string())))
+				.collect(),
+			directives: DbFieldDirectives::parse(&prop.1["directives"]),
+		});
+	}
+
+	props
+}
+
+/// GraphQL names, reserved ones starting with `__` excluded
+fn is_field_name(name: &str) -> bool {
+	let mut chars = name.chars();
+
+	chars
+		.next()
+		.map_or(false, |c| c == '_' || c.is_ascii_alphabetic())
+		&& chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+		&& !name.starts_with("__")
+}
+
 fn build_json_type(json_data: &Value) -> JsonType {
 	if let Some(enum_data) = json_data["enum"].as_array() {
 		return JsonType::Enum(
@@ -311,10 +1058,25 @@ fn build_json_type(json_data: &Value) -> JsonType {
 	match data_type {
 		"array" => JsonType::Array(Box::new(build_json_type(&json_data["items"]))),
 		"boolean" => JsonType::Boolean,
-		"integer" => JsonType::Integer,
+		"integer" => match json_data["format"].as_str() {
+			Some("int64") => JsonType::BigInteger,
+			_ => JsonType::Integer,
+		},
 		"number" => JsonType::Number,
 		"object" => JsonType::Object,
-		"string" => JsonType::String,
+		"string" => match json_data["format"].as_str() {
+			Some("date-time") => JsonType::DateTime,
+			Some("uuid") => JsonType::Uuid,
+			Some("byte") => JsonType::Bytes,
+			Some("decimal") => JsonType::Decimal,
+			Some("file") => JsonType::File(
+				json_data["storage"]
+					.as_str()
+					.unwrap_or(DEFAULT_STORAGE)
+					.to_string(),
+			),
+			_ => JsonType::String,
+		},
 		_ => JsonType::String, // This is an unreachable condition
 	}
 }
@@ -1,4 +1,7 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::OnceCell;
 
 use crate::lib::database::api::{DbRelationshipDirection, DbRelationshipType};
 
@@ -7,6 +10,16 @@ pub struct AQLQueryRelationship {
 	pub direction: DbRelationshipDirection,
 	pub relationship_type: DbRelationshipType,
 	pub variable_name: String,
+	/// Returns the traversed edges instead of the documents
+	pub edge_projection: Option<AQLEdgeProjection>,
+	/// Traverses `1..depth` edges, with the path available as `p_{id}`
+	pub depth: Option<u32>,
+}
+
+/// Attributes returned for each traversed edge, with the document under `node`
+pub struct AQLEdgeProjection {
+	pub properties: Vec<AQLProperty>,
+	pub node: Option<String>,
 }
 
 pub enum AQLQueryMethod {
@@ -14,7 +27,15 @@ pub enum AQLQueryMethod {
 	Update(String),
 	Remove,
 	Create,
-	CreateRelationship(Option<Box<AQLQuery>>),
+	/// Inserts an edge from `@__from`, inverse relationships traverse it `INBOUND`. Nothing
+	/// is inserted when it would break the cardinality of the relationship with the
+	/// given collections
+	CreateRelationship(
+		Option<Box<AQLQuery>>,
+		DbRelationshipDirection,
+		DbRelationshipType,
+		Vec<String>,
+	),
 }
 
 pub struct AQLQuery {
@@ -22,9 +43,16 @@ pub struct AQLQuery {
 	pub method: AQLQueryMethod,
 	pub filter: Option<Box<dyn AQLNode>>,
 	pub relations: HashMap<String, AQLQuery>,
+	/// Properties only returned for documents of a specific variant
+	pub fragments: Vec<AQLFragment>,
 	pub creates: String,
 	pub limit: Option<i32>,
 	pub relationship: Option<AQLQueryRelationship>,
+	/// Mutations also return the documents before and after the change, as `old` and `new`
+	pub returns_changes: bool,
+	/// AQL compiled by an identical operation with the session variables it uses, returned
+	/// instead of describing the query
+	pub compiled: Option<Arc<OnceCell<(String, Vec<String>)>>>,
 
 	pub id: u32,
 }
@@ -36,15 +64,60 @@ impl AQLQuery {
 			method: AQLQueryMethod::Get,
 			filter: None,
 			relations: HashMap::new(),
+			fragments: Vec::new(),
 			creates: "null".to_string(),
 			limit: None,
 			relationship: None,
+			returns_changes: false,
+			compiled: None,
 			id,
 		}
 	}
 
 	pub fn to_aql(&self) -> String {
-		self.to_aql_with_collection("@@collection")
+		let compiled = match self.compiled {
+			Some(ref compiled) => compiled,
+			None => return self.to_aql_with_collection("@@collection"),
+		};
+
+		if let Some((aql, _)) = compiled.get() {
+			return aql.clone();
+		}
+
+		let aql = self.to_aql_with_collection("@@collection");
+		let _ = compiled.set((aql.clone(), self.get_session_variables()));
+
+		aql
+	}
+
+	/// Session variables used by the filters of the query and its subqueries, bound as
+	/// `@session_{name}`
+	pub fn get_session_variables(&self) -> Vec<String> {
+		if let Some((_, variables)) = self.compiled.as_ref().and_then(|c| c.get()) {
+			return variables.clone();
+		}
+
+		let mut variables = Vec::new();
+
+		self.collect_session_variables(&mut variables);
+
+		variables
+	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		if let Some(filter) = &self.filter {
+			filter.collect_session_variables(variables);
+		}
+
+		let fragment_relations = self.fragments.iter().flat_map(|f| f.relations.values());
+
+		for query in self.relations.values().chain(fragment_relations) {
+			query.collect_session_variables(variables);
+		}
+
+		if let AQLQueryMethod::CreateRelationship(Some(ref inner), ..) = self.method {
+			inner.collect_session_variables(variables);
+		}
 	}
 
 	pub fn to_aql_with_collection(&self, inner: &str) -> String {
@@ -53,39 +126,99 @@ impl AQLQuery {
 			AQLQueryMethod::Update(ref data) => self.to_update_aql(inner, data),
 			AQLQueryMethod::Remove => self.to_remove_aql(inner),
 			AQLQueryMethod::Create => self.to_create_aql(inner),
-			AQLQueryMethod::CreateRelationship(ref q) => self.to_create_relationship_aql(q),
+			AQLQueryMethod::CreateRelationship(
+				ref q,
+				ref direction,
+				ref relationship_type,
+				ref targets,
+			) => self.to_create_relationship_aql(q, direction, relationship_type, targets),
 		}
 	}
 
+	/// Subqueries of the relations, run once per document before it's returned
+	pub fn describe_relations(&self) -> String {
+		self.relations
+			.iter()
+			.map(|(key, query)| {
+				format!(
+					"LET {} = {}",
+					self.get_relation_variable_name(key),
+					query.to_aql()
+				)
+			})
+			.collect::<Vec<String>>()
+			.join(" ")
+	}
+
 	pub fn describe_parameters(&self) -> String {
 		let variable = self.get_variable_name();
 
-		format!(
+		let parameters = format!(
 			"{{{}}}",
 			self.properties
 				.iter()
-				.map(|p| format!("\"{name}\": {}.`{name}`", variable, name = p.name))
-				.chain(self.relations.iter().map(|(key, query)| format!(
+				.map(|p| p.describe(&variable))
+				.chain(self.relations.keys().map(|key| format!(
 					"\"{}\": {}",
 					key,
-					query.to_aql()
+					self.get_relation_variable_name(key)
 				)))
 				.collect::<Vec<String>>()
 				.join(",")
+		);
+
+		if self.fragments.is_empty() {
+			return parameters;
+		}
+
+		format!(
+			"MERGE({}, {})",
+			parameters,
+			self.fragments
+				.iter()
+				.map(|f| f.describe(&variable))
+				.collect::<Vec<String>>()
+				.join(", ")
 		)
 	}
 
 	fn to_get_aql(&self, inner: &str) -> String {
 		if let Some(ref r) = self.relationship {
+			let (mut variables, parameters) = match r.edge_projection {
+				Some(ref projection) => (
+					format!(
+						"{}, {}",
+						self.get_variable_name(),
+						self.get_edge_variable_name()
+					),
+					projection.describe(&self.get_edge_variable_name(), || {
+						self.describe_parameters()
+					}),
+				),
+				None => (self.get_variable_name(), self.describe_parameters()),
+			};
+
+			let mut direction = r.direction.to_string();
+
+			if let Some(depth) = r.depth {
+				if r.edge_projection.is_none() {
+					variables = format!("{}, {}", variables, self.get_edge_variable_name());
+				}
+
+				variables = format!("{}, {}", variables, self.get_path_variable_name());
+				direction = format!("1..{} {}", depth, direction);
+			}
+
 			format!(
-				"(FOR {} IN {} {} {} {} {} RETURN {}){}",
-				self.get_variable_name(),
-				r.direction.to_string(),
+				"(FOR {} IN {} {} {} {} {} {} RETURN {}){}",
+				variables,
+				direction,
 				r.variable_name,
 				r.edge,
 				self.describe_filter(),
 				self.describe_limit(),
-				self.describe_parameters(),
+				self.describe_relations(),
+				parameters,
 				if !r.relationship_type.returns_array() {
 					"[0]"
 				} else {
@@ -94,11 +227,12 @@ impl AQLQuery {
 			)
 		} else {
 			format!(
-				"FOR {} IN {} {} {} RETURN {}",
+				"FOR {} IN {} {} {} {} RETURN {}",
 				self.get_variable_name(),
 				inner,
 				self.describe_filter(),
 				self.describe_limit(),
+				self.describe_relations(),
 				self.describe_parameters()
 			)
 		}
@@ -106,10 +240,14 @@ impl AQLQuery {
 
 	fn to_update_aql(&self, inner: &str, data: &str) -> String {
 		format!(
-			"FOR {var} IN {col} {} UPDATE {var}.`_key` WITH {} IN {col} {} RETURN {{ _key: NEW._key }}",
+			"FOR {var} IN {col} {} UPDATE {var}.`_key` WITH {} IN {col} {} RETURN {}",
 			self.describe_filter(),
 			data,
 			self.describe_limit(),
+			self.describe_changes(
+				"{ _key: NEW._key, old: OLD, new: NEW }",
+				"{ _key: NEW._key }"
+			),
 			var = self.get_variable_name(),
 			col = inner
 		)
@@ -117,9 +255,10 @@ impl AQLQuery {
 
 	fn to_remove_aql(&self, inner: &str) -> String {
 		format!(
-			"FOR {var} IN {col} {} REMOVE {var}.`_key` IN {col} {} RETURN {{ _key: OLD._key }}",
+			"FOR {var} IN {col} {} REMOVE {var}.`_key` IN {col} {} RETURN {}",
 			self.describe_filter(),
 			self.describe_limit(),
+			self.describe_changes("{ _key: OLD._key, old: OLD }", "{ _key: OLD._key }"),
 			var = self.get_variable_name(),
 			col = inner
 		)
@@ -127,22 +266,86 @@ impl AQLQuery {
 
 	fn to_create_aql(&self, inner: &str) -> String {
 		format!(
-			"INSERT {} INTO {} RETURN {{ _key: NEW._key }}",
-			self.creates, inner
+			"INSERT {} INTO {} RETURN {}",
+			self.creates,
+			inner,
+			self.describe_changes("{ _key: NEW._key, new: NEW }", "{ _key: NEW._key }")
 		)
 	}
 
-	fn to_create_relationship_aql(&self, inner: &Option<Box<AQLQuery>>) -> String {
+	fn describe_changes<'a>(&self, changes: &'a str, key: &'a str) -> &'a str {
+		match self.returns_changes {
+			true => changes,
+			false => key,
+		}
+	}
+
+	fn to_create_relationship_aql(
+		&self,
+		inner: &Option<Box<AQLQuery>>,
+		direction: &DbRelationshipDirection,
+		relationship_type: &DbRelationshipType,
+		targets: &[String],
+	) -> String {
+		let to = if let Some(inner_query) = inner {
+			format!(
+				"({})[0][\"_id\"]",
+				inner_query.to_aql_with_collection("@@inner_collection")
+			)
+		} else {
+			"@__to".to_string()
+		};
+
+		let (from_side, to_side) = match direction {
+			DbRelationshipDirection::Inbound => ("_to", "_from"),
+			_ => ("_from", "_to"),
+		};
+
+		let document = format!("{{{}: @__from, {}: __to}}", from_side, to_side);
+
+		// Edges of the same endpoint in this relationship, which can share its edge collection
+		let describe_unique = |side: &str, other_side: &str, id: &str, collections: &str| {
+			format!(
+				"FILTER LENGTH(FOR e IN @@collection FILTER e.{side} == {id} AND PARSE_IDENTIFIER(e.{other_side}).collection IN {collections} LIMIT 1 RETURN 1) == 0",
+				side = side,
+				other_side = other_side,
+				id = id,
+				collections = collections
+			)
+		};
+
+		let mut filters = Vec::new();
+
+		if !relationship_type.returns_array() {
+			filters.push(describe_unique(
+				from_side,
+				to_side,
+				"@__from",
+				&serde_json::to_string(targets).unwrap(),
+			));
+		}
+
+		if !relationship_type.reverse().returns_array() {
+			filters.push(describe_unique(
+				to_side,
+				from_side,
+				"__to",
+				"[PARSE_IDENTIFIER(@__from).collection]",
+			));
+		}
+
+		// Attributes of the edge are given in `creates`
+		let edge = if self.creates == "null" {
+			document
+		} else {
+			format!("MERGE({}, {})", self.creates, document)
+		};
+
 		format!(
-			"INSERT {{_from: @__from, _to: {}}} INTO @@collection",
-			if let Some(inner_query) = inner {
-				format!(
-					"({})[0][\"_id\"]",
-					inner_query.to_aql_with_collection("@@inner_collection")
-				)
-			} else {
-				"@__to".to_string()
-			},
+			"LET __to = {} {} INSERT {} INTO @@collection RETURN {{ _key: NEW._key }}",
+			to,
+			filters.join(" "),
+			edge
 		)
 	}
 
@@ -162,6 +365,17 @@ impl AQLQuery {
 		}
 	}
 
+	/// Adds a node to the query filter joining it with AND to any existing filter
+	pub fn add_filter(&mut self, node: Box<dyn AQLNode>) {
+		self.filter = Some(match self.filter.take() {
+			Some(filter) => Box::new(AQLLogicalFilter {
+				nodes: vec![filter, node],
+				operation: AQLLogicalOperator::AND,
+			}),
+			None => node,
+		});
+	}
+
 	pub fn get_argument_key(&self, name: &str) -> String {
 		format!("arg_{}_{}", self.id, name)
 	}
@@ -169,13 +383,177 @@ impl AQLQuery {
 	pub fn get_variable_name(&self) -> String {
 		format!("i_{}", self.id)
 	}
+
+	pub fn get_edge_variable_name(&self) -> String {
+		format!("e_{}", self.id)
+	}
+
+	pub fn get_path_variable_name(&self) -> String {
+		format!("p_{}", self.id)
+	}
+
+	/// Variable holding the result of the subquery of a relation
+	pub fn get_relation_variable_name(&self, key: &str) -> String {
+		format!("r_{}_{}", self.id, key)
+	}
 }
 
 unsafe impl Send for AQLQuery {}
 
+impl AQLEdgeProjection {
+	fn describe(&self, variable: &str, describe_node: impl Fn() -> String) -> String {
+		let properties = self
+			.properties
+			.iter()
+			.map(|p| p.describe(variable))
+			.chain(self.node.iter().map(|node| {
+				format!(
+					"{}: {}",
+					serde_json::to_string(node).unwrap(),
+					describe_node()
+				)
+			}))
+			.collect::<Vec<String>>()
+			.join(",");
+
+		format!("{{{}}}", properties)
+	}
+}
+
+/// Documents a fragment applies to
+pub enum AQLFragmentCondition {
+	/// Discriminator property and the value documents of the variant have
+	Variant(String, String),
+	/// Collection of the documents, for the members of a union
+	Collection(String),
+}
+
+pub struct AQLFragment {
+	pub condition: AQLFragmentCondition,
+	pub properties: Vec<AQLProperty>,
+	pub relations: HashMap<String, AQLQuery>,
+}
+
+impl AQLFragment {
+	fn describe(&self, variable: &str) -> String {
+		let condition = match self.condition {
+			AQLFragmentCondition::Variant(ref attribute, ref value) => format!(
+				"{} == {}",
+				get_attribute_accessor(variable, attribute),
+				serde_json::to_string(value).unwrap()
+			),
+			AQLFragmentCondition::Collection(ref collection) => format!(
+				"IS_SAME_COLLECTION({}, {})",
+				serde_json::to_string(collection).unwrap(),
+				variable
+			),
+		};
+
+		format!(
+			"({} ? {{{}}} : {{}})",
+			condition,
+			self.properties
+				.iter()
+				.map(|p| p.describe(variable))
+				.chain(self.relations.iter().map(|(key, query)| format!(
+					"\"{}\": {}",
+					key,
+					query.to_aql()
+				)))
+				.collect::<Vec<String>>()
+				.join(",")
+		)
+	}
+}
+
 #[derive(Debug)]
 pub struct AQLProperty {
 	pub name: String,
+	/// AQL expression returned instead of the attribute, e.g. for `__typename`
+	pub expression: Option<String>,
+	/// Pairs of stored value and returned value, used to return enum value names
+	pub translations: Option<Vec<(String, String)>>,
+	/// Selected attributes when the property is a sub-document
+	pub fields: Option<Vec<AQLProperty>>,
+	/// The property holds a list of sub-documents
+	pub is_list: bool,
+	/// Collection of the document whose key the attribute holds, returned in its place
+	pub lookup: Option<String>,
+}
+
+impl AQLProperty {
+	pub fn new(name: String) -> Self {
+		Self {
+			name,
+			expression: None,
+			translations: None,
+			fields: None,
+			is_list: false,
+			lookup: None,
+		}
+	}
+
+	pub fn describe(&self, variable: &str) -> String {
+		let key = serde_json::to_string(&self.name).unwrap();
+
+		if let Some(ref expression) = self.expression {
+			return format!("{}: {}", key, expression);
+		}
+
+		let value = match self.lookup {
+			Some(ref collection) => format!(
+				"DOCUMENT({}, {})",
+				serde_json::to_string(collection).unwrap(),
+				get_attribute_accessor(variable, &self.name)
+			),
+			None => get_attribute_accessor(variable, &self.name),
+		};
+
+		if let Some(ref fields) = self.fields {
+			let describe_fields = |variable: &str| {
+				fields
+					.iter()
+					.map(|f| f.describe(variable))
+					.collect::<Vec<String>>()
+					.join(",")
+			};
+
+			// Items of a list are projected inline through `CURRENT`
+			let object = if self.is_list {
+				format!("{}[* RETURN {{{}}}]", value, describe_fields("CURRENT"))
+			} else {
+				format!("{{{}}}", describe_fields(&value))
+			};
+
+			return format!(
+				"{}: ({value} == null ? null : {})",
+				key,
+				object,
+				value = value
+			);
+		}
+
+		let value = match self.translations {
+			Some(ref translations) => {
+				let map = serde_json::to_string(
+					&translations
+						.iter()
+						.map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+						.collect::<serde_json::Map<String, serde_json::Value>>(),
+				)
+				.unwrap();
+
+				format!(
+					"(IS_ARRAY({value}) ? (FOR t IN {value} RETURN TRANSLATE(t, {map}, t)) : TRANSLATE({value}, {map}, {value}))",
+					value = value,
+					map = map
+				)
+			}
+			None => value,
+		};
+
+		format!("{}: {}", key, value)
+	}
 }
 
 pub struct AQLFilterOperation {
@@ -196,6 +574,19 @@ pub struct AQLFunctionCall {
 
 pub struct AQLNotFilter(pub Box<dyn AQLNode>);
 
+pub enum AQLArrayQuantifier {
+	Any,
+	All,
+	None,
+}
+
+/// Filters on the items of a list of sub-documents, the items are iterated as the next query variable
+pub struct AQLArrayFilter {
+	pub attribute: String,
+	pub quantifier: AQLArrayQuantifier,
+	pub filter: Box<dyn AQLNode>,
+}
+
 pub struct AQLFilter {
 	pub attr_node: Box<dyn AQLNode>,
 	pub and_node: Option<Box<dyn AQLNode>>,
@@ -241,8 +632,8 @@ impl ToString for AQLOperation {
 			AQLOperation::LessThan => "<",
 			AQLOperation::LessOrEqualThan => "<=",
 			AQLOperation::NotEqual => "!=",
-			AQLOperation::NotRegex => "=~",
-			AQLOperation::Regex => "!~",
+			AQLOperation::NotRegex => "!~",
+			AQLOperation::Regex => "=~",
 		}
 		.to_string();
 	}
@@ -273,6 +664,19 @@ pub struct AQLQueryBind(pub String);
 pub struct AQLQueryParameter(pub String);
 pub struct AQLQueryValue(pub String);
 pub struct AQLQueryRaw(pub String);
+pub struct AQLSessionVariable(pub String);
+/// Matches documents of the collection, when a query returns documents of several of them
+pub struct AQLCollectionFilter(pub String);
+
+/// Attribute of a variable in bracket notation, the name being a string literal so names coming
+/// from the schema can't break out of the query
+pub fn get_attribute_accessor(variable: &str, attribute: &str) -> String {
+	format!(
+		"{}[{}]",
+		variable,
+		serde_json::to_string(attribute).unwrap()
+	)
+}
 
 pub trait AQLNode {
 	fn describe(&self, id: u32) -> String;
@@ -280,6 +684,20 @@ pub trait AQLNode {
 	fn valid(&self) -> bool {
 		true
 	}
+
+	/// Adds the names of the session variables of the node, they have to be bound
+	fn collect_session_variables(&self, _variables: &mut Vec<String>) {}
+}
+
+/// Session variables used by the nodes, bound as `@session_{name}`
+pub fn get_session_variables<'a>(nodes: impl IntoIterator<Item = &'a dyn AQLNode>) -> Vec<String> {
+	let mut variables = Vec::new();
+
+	for node in nodes {
+		node.collect_session_variables(&mut variables);
+	}
+
+	variables
 }
 
 impl AQLNode for AQLFilterOperation {
@@ -291,6 +709,11 @@ impl AQLNode for AQLFilterOperation {
 			self.right_node.describe(id)
 		)
 	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		self.left_node.collect_session_variables(variables);
+		self.right_node.collect_session_variables(variables);
+	}
 }
 
 impl AQLNode for AQLFilterInOperation {
@@ -310,6 +733,14 @@ impl AQLNode for AQLFilterInOperation {
 	fn valid(&self) -> bool {
 		self.vec.len() > 0
 	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		self.left_node.collect_session_variables(variables);
+
+		for node in &self.vec {
+			node.collect_session_variables(variables);
+		}
+	}
 }
 
 impl AQLNode for AQLFunctionCall {
@@ -325,12 +756,46 @@ impl AQLNode for AQLFunctionCall {
 				.as_str()
 		)
 	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		for node in &self.parameters {
+			node.collect_session_variables(variables);
+		}
+	}
+}
+
+impl AQLNode for AQLArrayFilter {
+	fn describe(&self, id: u32) -> String {
+		let item_id = id + 1;
+
+		let (filter, comparison) = match self.quantifier {
+			AQLArrayQuantifier::Any => (self.filter.describe(item_id), "> 0"),
+			AQLArrayQuantifier::All => (format!("NOT ({})", self.filter.describe(item_id)), "== 0"),
+			AQLArrayQuantifier::None => (self.filter.describe(item_id), "== 0"),
+		};
+
+		format!(
+			"(LENGTH(FOR i_{} IN ({} || []) FILTER {} LIMIT 1 RETURN true) {})",
+			item_id,
+			AQLQueryParameter(self.attribute.clone()).describe(id),
+			filter,
+			comparison
+		)
+	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		self.filter.collect_session_variables(variables);
+	}
 }
 
 impl AQLNode for AQLNotFilter {
 	fn describe(&self, id: u32) -> String {
 		format!("(NOT {})", self.0.describe(id))
 	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		self.0.collect_session_variables(variables);
+	}
 }
 
 impl AQLNode for AQLFilter {
@@ -363,6 +828,17 @@ impl AQLNode for AQLFilter {
 	fn valid(&self) -> bool {
 		self.attr_node.valid()
 	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		self.attr_node.collect_session_variables(variables);
+
+		for node in [&self.and_node, &self.or_node, &self.not_node]
+			.into_iter()
+			.flatten()
+		{
+			node.collect_session_variables(variables);
+		}
+	}
 }
 
 impl AQLNode for AQLLogicalFilter {
@@ -392,6 +868,12 @@ impl AQLNode for AQLLogicalFilter {
 	fn valid(&self) -> bool {
 		self.nodes.len() > 0
 	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		for node in &self.nodes {
+			node.collect_session_variables(variables);
+		}
+	}
 }
 
 impl AQLNode for AQLQueryBind {
@@ -402,7 +884,12 @@ impl AQLNode for AQLQueryBind {
 
 impl AQLNode for AQLQueryParameter {
 	fn describe(&self, id: u32) -> String {
-		format!("i_{}.`{}`", id, self.0)
+		// Dot separated paths point to attributes of sub-documents
+		self.0
+			.split('.')
+			.fold(format!("i_{}", id), |variable, attribute| {
+				get_attribute_accessor(&variable, attribute)
+			})
 	}
 }
 
@@ -412,6 +899,26 @@ impl AQLNode for AQLQueryValue {
 	}
 }
 
+impl AQLNode for AQLSessionVariable {
+	fn describe(&self, _: u32) -> String {
+		format!("@session_{}", self.0)
+	}
+
+	fn collect_session_variables(&self, variables: &mut Vec<String>) {
+		variables.push(self.0.clone());
+	}
+}
+
+impl AQLNode for AQLCollectionFilter {
+	fn describe(&self, id: u32) -> String {
+		format!(
+			"IS_SAME_COLLECTION({}, i_{})",
+			serde_json::to_string(&self.0).unwrap(),
+			id
+		)
+	}
+}
+
 impl AQLNode for AQLQueryRaw {
 	fn describe(&self, _: u32) -> String {
 		self.0.clone()
@@ -1,65 +1,898 @@
-use rust_arango::collection::options::{CreateOptions as CollectionOptions, CreateParameters};
+use rust_arango::collection::options::{
+	CreateOptions as CollectionOptions, CreateParameters, KeyOptions, PropertiesOptions,
+};
+use rust_arango::collection::CollectionType;
+use rust_arango::graph::{EdgeDefinition, Graph};
+use rust_arango::index::{Index, IndexSettings};
+use rust_arango::AqlQuery;
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 
 use serde_json::{to_value as toJsonValue, value::Value as JsonValue};
 
-use crate::lib::database::schema::{DatabaseSchema, Rule, SchemaProperty};
+use crate::lib::database::schema::{
+	get_validation_level, CollectionSettings, ComputedProperty, DatabaseSchema, Rule,
+	SchemaOnDelete, SchemaRelationshipType, SchemaValidationLevel, Ttl, Variant, Versioning,
+};
 use crate::lib::database::DATABASE;
-use crate::lib::schema::{create_entry, delete_entry, SchemaDocumentProperty};
+use crate::lib::locales::{
+	get_translatable_fields, unwrap_translatable_rule, wrap_translatable_rule,
+};
+use crate::lib::publication::get_status_property;
+use crate::lib::revisions::{get_revisions_collection, init_revisions};
+use crate::lib::schema::entries::{AlchemyCollectionEntry, AlchemyEdgeEntry};
+use crate::lib::schema::properties::build_object_rule;
+use crate::lib::schema::{
+	create_edge_entry, create_entry, delete_edge_entries, delete_entry, get_all_edges,
+	get_all_graphs, get_entry, rename_edge_entries, replace_entry, strip_document_attributes,
+	update_entry, SchemaCollectionSettings, SchemaComputedProperty, SchemaDocumentProperty,
+	SchemaTtl, SchemaVariant, SchemaVersioning,
+};
+
+/// Options to create a collection validated by the schema with. The builder only takes set
+/// values, so the replication factor is the only one left to the server default when unset
+pub fn get_collection_options<'a>(
+	name: &'a str,
+	collection_type: CollectionType,
+	schema: &DatabaseSchema,
+	settings: &CollectionSettings,
+) -> Result<CollectionOptions<'a>, Error> {
+	let key_options: KeyOptions = serde_json::from_value(serde_json::json!({
+		"type": settings.key_type.as_deref().unwrap_or("traditional"),
+	}))?;
+
+	let wait_for_sync = settings.wait_for_sync.unwrap_or(false);
+	let number_of_shards = settings.number_of_shards.unwrap_or(1);
+
+	Ok(match settings.replication_factor {
+		Some(replication_factor) => CollectionOptions::builder()
+			.name(name)
+			.collection_type(collection_type)
+			.schema(toJsonValue(schema)?)
+			.key_options(key_options)
+			.wait_for_sync(wait_for_sync)
+			.number_of_shards(number_of_shards)
+			.replication_factor(replication_factor)
+			.build(),
+		None => CollectionOptions::builder()
+			.name(name)
+			.collection_type(collection_type)
+			.schema(toJsonValue(schema)?)
+			.key_options(key_options)
+			.wait_for_sync(wait_for_sync)
+			.number_of_shards(number_of_shards)
+			.build(),
+	})
+}
 
 pub async fn create_collection(
 	name: String,
 	properties: Vec<SchemaDocumentProperty>,
+	discriminator: Option<String>,
+	variants: Vec<SchemaVariant>,
+	computed: Vec<SchemaComputedProperty>,
+	ttl: Option<SchemaTtl>,
+	settings: Option<SchemaCollectionSettings>,
+	level: Option<SchemaValidationLevel>,
 ) -> Result<(), Error> {
-	let db = DATABASE.get().await.database.clone();
+	let db = DATABASE.get().await.database();
 
-	// Create a schema struct to be populated with an empty JSON Map for properties
-	let mut schema = DatabaseSchema {
+	let settings = settings.map(CollectionSettings::from);
+	let level = level.map(|l| l.as_str());
+
+	let (mut rule_properties, mut required) = build_object_rule(properties);
+
+	let variants: Vec<Variant> = variants.into_iter().map(Variant::from).collect();
+
+	// Documents of every variant are stored in the same collection, so the variant
+	// properties are allowed but only required by the API
+	for variant in &variants {
+		for (key, value) in variant.properties.as_object().unwrap() {
+			rule_properties
+				.entry(key.clone())
+				.or_insert_with(|| value.clone());
+		}
+	}
+
+	// An undeclared discriminator becomes an enum of the variant values, which gives
+	// queries a typed filter on the variant
+	if let Some(discriminator) = &discriminator {
+		let values: Vec<&String> = variants.iter().map(|v| &v.value).collect();
+
+		rule_properties
+			.entry(discriminator.clone())
+			.or_insert_with(|| match values.is_empty() {
+				true => serde_json::json!({ "type": "string" }),
+				false => serde_json::json!({ "enum": values }),
+			});
+
+		if !required.contains(discriminator) {
+			required.push(discriminator.clone());
+		}
+	}
+
+	let schema = DatabaseSchema {
 		message: String::from("Schema validation failed"),
-		level: String::from("strict"),
+		level: level.clone().unwrap_or_else(|| String::from("strict")),
 		rule: Rule {
 			r#type: String::from("object"),
-			properties: JsonValue::Object(serde_json::Map::new()),
-			required: Vec::new(),
+			properties: JsonValue::Object(rule_properties),
+			required,
 			additional_properties: false,
 		},
 	};
 
-	// Iterate over the properties and add them to the schema rules
-	for property in properties {
-		schema.rule.properties.as_object_mut().unwrap().insert(
-			property.name.clone(),
-			toJsonValue(SchemaProperty::from(property.values)).unwrap(),
-		);
-
-		if property.required {
-			schema.rule.required.push(property.name);
-		}
-	}
-
 	// println!("{:?}", toJsonValue(schema.clone()).unwrap().to_string());
 
 	// Create the collection with the schema
-	let collection_options = CollectionOptions::builder()
-		.name(name.as_str())
-		.schema(toJsonValue(&schema).unwrap())
-		.build();
+	let collection_options = get_collection_options(
+		name.as_str(),
+		CollectionType::Document,
+		&schema,
+		&settings.clone().unwrap_or_default(),
+	)?;
 
 	db.create_collection_with_options(collection_options, CreateParameters::default())
 		.await?;
 
-	create_entry(name, schema.rule).await;
+	create_entry(AlchemyCollectionEntry {
+		name: name.clone(),
+		schema: toJsonValue(&schema.rule).unwrap(),
+		discriminator,
+		variants,
+		computed: computed.into_iter().map(ComputedProperty::from).collect(),
+		settings,
+		level,
+		..Default::default()
+	})
+	.await;
+
+	if ttl.is_some() {
+		set_collection_ttl(name, ttl).await?;
+	}
+
+	Ok(())
+}
+
+/// Sets or removes the expiry of the documents of a collection, replacing its TTL index.
+/// The TTL field is added to the schema when missing, and kept when the expiry is removed
+pub async fn set_collection_ttl(name: String, ttl: Option<SchemaTtl>) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	let ttl = ttl.map(Ttl::from);
+
+	if let Some(ttl) = &ttl {
+		alter_collection_rule(&name, |rule| {
+			rule.properties
+				.as_object_mut()
+				.unwrap()
+				.entry(ttl.field.clone())
+				.or_insert_with(|| serde_json::json!({ "type": "string", "format": "date-time" }));
+
+			Ok(())
+		})
+		.await?;
+	}
+
+	for index in db.indexes(&name).await?.indexes {
+		if let IndexSettings::Ttl { .. } = index.settings {
+			db.delete_index(&index.id).await?;
+		}
+	}
+
+	// The field holds the expiry date itself, so documents expire as soon as it's reached
+	if let Some(ttl) = &ttl {
+		let index = Index::builder()
+			.fields(vec![ttl.field.clone()])
+			.settings(IndexSettings::Ttl { expire_after: 0 })
+			.build();
+
+		db.create_index(&name, &index).await?;
+	}
+
+	update_entry(&name, serde_json::json!({ "ttl": ttl })).await;
+
+	Ok(())
+}
+
+pub async fn create_relationship(
+	name: String,
+	from: String,
+	to: String,
+	relationship_type: SchemaRelationshipType,
+	edge: String,
+	properties: Vec<SchemaDocumentProperty>,
+	inverse: Option<String>,
+	generate_inverse: Option<bool>,
+	on_delete: Option<SchemaOnDelete>,
+	targets: Option<Vec<String>>,
+	description: Option<String>,
+) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	let (rule_properties, required) = build_object_rule(properties);
+
+	let rule = Rule {
+		r#type: String::from("object"),
+		properties: JsonValue::Object(rule_properties),
+		required,
+		additional_properties: false,
+	};
+
+	// Several relationships can share an edge collection
+	if db.collection(edge.as_str()).await.is_err() {
+		let schema = DatabaseSchema {
+			message: String::from("Schema validation failed"),
+			level: String::from("strict"),
+			rule: rule.clone(),
+		};
+
+		let collection_options = CollectionOptions::builder()
+			.name(edge.as_str())
+			.collection_type(CollectionType::Edge)
+			.schema(toJsonValue(&schema).unwrap())
+			.build();
+
+		db.create_collection_with_options(collection_options, CreateParameters::default())
+			.await?;
+	}
+
+	create_edge_entry(AlchemyEdgeEntry {
+		name,
+		edge,
+		from,
+		to,
+		r#type: relationship_type.as_str(),
+		// Edges are inserted from `from` documents, which are traversed outbound
+		direction: String::from("inbound"),
+		schema: Some(toJsonValue(&rule).unwrap()),
+		inverse,
+		generate_inverse,
+		on_delete: on_delete.map(|o| o.as_str()),
+		targets,
+		description,
+	})
+	.await;
+
+	Ok(())
+}
+
+/// Creates a named graph from the edge collections of relationships, for traversals
+pub async fn create_graph(name: String, edges: Vec<String>) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	let mut edge_definitions: Vec<EdgeDefinition> = Vec::new();
+
+	for entry in get_all_edges().await {
+		let edge = entry["edge"].as_str().unwrap_or_default();
+
+		if !edges.iter().any(|e| e == edge) {
+			continue;
+		}
+
+		let from = entry["from"].as_str().unwrap_or_default().to_string();
+		let to = entry["to"].as_str().unwrap_or_default().to_string();
+
+		// Relationships sharing an edge collection are merged in one definition
+		match edge_definitions.iter_mut().find(|d| d.collection == edge) {
+			Some(definition) => {
+				if !definition.from.contains(&from) {
+					definition.from.push(from);
+				}
+
+				if !definition.to.contains(&to) {
+					definition.to.push(to);
+				}
+			}
+			None => edge_definitions.push(EdgeDefinition {
+				collection: edge.to_string(),
+				from: vec![from],
+				to: vec![to],
+			}),
+		}
+	}
+
+	let graph = Graph::builder()
+		.name(name)
+		.edge_definitions(edge_definitions)
+		.build();
+
+	db.create_graph(graph, true).await?;
 
 	Ok(())
 }
 
 pub async fn delete_collection(name: String) -> Result<(), Error> {
-	let db = DATABASE.get().await.database.clone();
+	let db = DATABASE.get().await.database();
 
 	db.drop_collection(name.as_str()).await?;
 
+	let revisions = get_revisions_collection(&name);
+
+	if db.collection(&revisions).await.is_ok() {
+		db.drop_collection(&revisions).await?;
+	}
+
+	// Relationships of the collection would point to documents that no longer exist
+	delete_edge_entries(&name, None).await;
 	delete_entry(name).await;
 
 	Ok(())
 }
+
+/// Renames the ArangoDB collection, rewriting the edges and graphs referring to it. Entries
+/// are left as they are
+pub async fn move_collection(name: &str, new_name: &str) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	// Graphs are recreated with the new name since their definitions can't be renamed
+	let mut graphs = Vec::new();
+
+	for graph in get_all_graphs().await {
+		let mut definitions: Vec<EdgeDefinition> =
+			serde_json::from_value(graph["edgeDefinitions"].clone())?;
+
+		let mut renamed = false;
+
+		for definition in definitions.iter_mut() {
+			for collection in definition.from.iter_mut().chain(definition.to.iter_mut()) {
+				if collection == name {
+					*collection = new_name.to_string();
+					renamed = true;
+				}
+			}
+		}
+
+		if renamed {
+			let graph_name = graph["_key"].as_str().unwrap_or_default().to_string();
+
+			db.drop_graph(&graph_name, false).await?;
+
+			graphs.push(
+				Graph::builder()
+					.name(graph_name)
+					.edge_definitions(definitions)
+					.build(),
+			);
+		}
+	}
+
+	db.collection(name).await?.rename(new_name).await?;
+
+	let revisions = get_revisions_collection(name);
+
+	if db.collection(&revisions).await.is_ok() {
+		db.collection(&revisions)
+			.await?
+			.rename(&get_revisions_collection(new_name))
+			.await?;
+	}
+
+	let mut edges: Vec<String> = get_all_edges()
+		.await
+		.iter()
+		.filter_map(|e| e["edge"].as_str().map(String::from))
+		.collect();
+
+	edges.sort();
+	edges.dedup();
+
+	for edge in edges {
+		update_documents(
+			&edge,
+			"FOR e IN @@collection
+				LET from = PARSE_IDENTIFIER(e._from)
+				LET to = PARSE_IDENTIFIER(e._to)
+				FILTER from.collection == @name OR to.collection == @name
+				LIMIT @batch_size
+				UPDATE e WITH {
+					_from: from.collection == @name ? CONCAT(@new_name, '/', from.key) : e._from,
+					_to: to.collection == @name ? CONCAT(@new_name, '/', to.key) : e._to
+				} IN @@collection
+				RETURN 1",
+			vec![
+				("name", JsonValue::String(name.to_string())),
+				("new_name", JsonValue::String(new_name.to_string())),
+			],
+		)
+		.await?;
+	}
+
+	for graph in graphs {
+		db.create_graph(graph, true).await?;
+	}
+
+	Ok(())
+}
+
+/// Renames a collection along with its documents and the relationships and graphs using it
+pub async fn rename_collection(name: String, new_name: String) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	if get_entry(&new_name).await.is_some() || db.collection(&new_name).await.is_ok() {
+		return Err(anyhow!("Collection {} already exists", new_name));
+	}
+
+	move_collection(&name, &new_name).await?;
+
+	update_entry(&name, serde_json::json!({ "name": new_name })).await;
+	rename_edge_entries(&name, &new_name).await;
+
+	Ok(())
+}
+
+/// Deletes the relationship `name` of the collection `from`, the edges are kept since their
+/// collection can be shared
+pub async fn delete_relationship(name: String, from: String) -> Result<(), Error> {
+	delete_edge_entries(&from, Some(&name)).await;
+
+	Ok(())
+}
+
+/// Changes the rule of a collection, updating both its ArangoDB schema and its entry
+async fn alter_collection_rule(
+	name: &str,
+	alter: impl FnOnce(&mut Rule) -> Result<(), Error>,
+) -> Result<(), Error> {
+	alter_collection_schema(name, |schema| alter(&mut schema.rule)).await
+}
+
+/// Changes the schema of a collection, updating both ArangoDB and its entry
+async fn alter_collection_schema(
+	name: &str,
+	alter: impl FnOnce(&mut DatabaseSchema) -> Result<(), Error>,
+) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	let entry = get_entry(name)
+		.await
+		.ok_or_else(|| anyhow!("Collection {} not found", name))?;
+
+	let mut schema = DatabaseSchema {
+		message: String::from("Schema validation failed"),
+		level: get_validation_level(&entry),
+		rule: serde_json::from_value(entry["schema"].clone())?,
+	};
+
+	alter(&mut schema)?;
+
+	db.collection(name)
+		.await?
+		.change_properties(
+			PropertiesOptions::builder()
+				.schema(toJsonValue(&schema)?)
+				.build(),
+		)
+		.await?;
+
+	update_entry(
+		name,
+		serde_json::json!({ "schema": schema.rule, "level": schema.level }),
+	)
+	.await;
+
+	Ok(())
+}
+
+/// Sets for how many seconds the API responses reading the collection are cached, or stops
+/// caching them when `max_age` is null
+pub async fn set_collection_cache(name: String, max_age: Option<i32>) -> Result<(), Error> {
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	let max_age = max_age.map(|a| a.max(0) as u32);
+
+	update_entry(&name, serde_json::json!({ "cache_max_age": max_age })).await;
+
+	Ok(())
+}
+
+/// Keeps the prior versions of the documents of a collection on update and delete, or stops
+/// when `versioning` is null. The revisions recorded so far are kept either way
+pub async fn set_collection_versioning(
+	name: String,
+	versioning: Option<SchemaVersioning>,
+) -> Result<(), Error> {
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	let versioning = versioning.map(Versioning::from);
+
+	if let Some(versioning) = &versioning {
+		init_revisions(&name, versioning).await?;
+	}
+
+	update_entry(&name, serde_json::json!({ "versioning": versioning })).await;
+
+	Ok(())
+}
+
+/// Gives the documents of a collection a draft, published or archived `status`, the API only
+/// reading published ones unless previewing. The `status` field is added when missing and kept
+/// when disabling it
+pub async fn set_collection_publication(name: String, enabled: bool) -> Result<(), Error> {
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	if enabled {
+		alter_collection_rule(&name, |rule| {
+			let property = get_status_property();
+
+			if rule.properties.get(&property.name).is_none() {
+				let (properties, _) = build_object_rule(vec![property]);
+
+				rule.properties.as_object_mut().unwrap().extend(properties);
+			}
+
+			Ok(())
+		})
+		.await?;
+	}
+
+	update_entry(&name, serde_json::json!({ "publication": enabled })).await;
+
+	Ok(())
+}
+
+/// Key of the document of singleton collections that were empty
+const SINGLETON_KEY: &str = "singleton";
+
+/// Makes a collection hold a single document, read and updated as a whole by the API, or a
+/// regular collection again when `enabled` is false. Collections holding several documents
+/// can't be singletons
+pub async fn set_collection_singleton(name: String, enabled: bool) -> Result<(), Error> {
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	let key = match enabled {
+		true => {
+			let query = AqlQuery::builder()
+				.query("FOR d IN @@collection LIMIT 2 RETURN d._key")
+				.bind_var("@collection", name.as_str())
+				.build();
+
+			let keys: Vec<String> = DATABASE.get().await.database().aql_query(query).await?;
+
+			if keys.len() > 1 {
+				return Err(anyhow!("Collection {} holds several documents", name));
+			}
+
+			Some(
+				keys.into_iter()
+					.next()
+					.unwrap_or_else(|| SINGLETON_KEY.to_string()),
+			)
+		}
+		false => None,
+	};
+
+	update_entry(&name, serde_json::json!({ "singleton": key })).await;
+
+	Ok(())
+}
+
+/// Documents the type of a collection in the API, or removes its description when there's none
+pub async fn set_collection_description(
+	name: String,
+	description: Option<String>,
+) -> Result<(), Error> {
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	update_entry(&name, serde_json::json!({ "description": description })).await;
+
+	Ok(())
+}
+
+/// Adds a collection to the API or leaves it out, without types nor operations. It's in the
+/// API unless `API_ALLOWLIST` is set when `exposed` is null
+pub async fn set_collection_exposure(name: String, exposed: Option<bool>) -> Result<(), Error> {
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	update_entry(&name, serde_json::json!({ "exposed": exposed })).await;
+
+	Ok(())
+}
+
+/// Marks the operations of a collection `@deprecated` with the reason, or no longer deprecated
+/// when there's none
+pub async fn set_collection_deprecation(name: String, reason: Option<String>) -> Result<(), Error> {
+	if get_entry(&name).await.is_none() {
+		return Err(anyhow!("Collection {} not found", name));
+	}
+
+	update_entry(&name, serde_json::json!({ "deprecation_reason": reason })).await;
+
+	Ok(())
+}
+
+/// Makes the fields of a collection hold a value per locale, the fields no longer listed holding
+/// a single value again. Values stored so far are read as they are until they're translated
+pub async fn set_translatable_fields(collection: String, fields: Vec<String>) -> Result<(), Error> {
+	let entry = get_entry(&collection)
+		.await
+		.ok_or_else(|| anyhow!("Collection {} not found", collection))?;
+
+	let translatable = get_translatable_fields(&entry);
+
+	alter_collection_rule(&collection, |rule| {
+		let properties = rule.properties.as_object_mut().unwrap();
+
+		if let Some(field) = fields.iter().find(|f| !properties.contains_key(*f)) {
+			return Err(anyhow!("Field {} not found", field));
+		}
+
+		for (name, values) in properties.iter_mut() {
+			match (translatable.contains(name), fields.contains(name)) {
+				(false, true) => *values = wrap_translatable_rule(values.take()),
+				(true, false) => *values = unwrap_translatable_rule(values),
+				_ => {}
+			}
+		}
+
+		Ok(())
+	})
+	.await?;
+
+	update_entry(&collection, serde_json::json!({ "translatable": fields })).await;
+
+	Ok(())
+}
+
+/// Documents updated by each query of a data migration, keeping transactions small
+const UPDATE_BATCH_SIZE: usize = 1000;
+
+/// Changes which documents are validated against the schema of a collection, e.g. `moderate`
+/// while invalid documents are migrated
+pub async fn set_validation_level(name: String, level: SchemaValidationLevel) -> Result<(), Error> {
+	alter_collection_schema(&name, |schema| {
+		schema.level = level.as_str();
+
+		Ok(())
+	})
+	.await
+}
+
+/// Applies an AQL update to the documents of the collection in batches, `@@collection` being
+/// bound to it. The query must skip the documents already updated, limit them to
+/// `@batch_size` and return one value per updated document
+async fn update_documents(
+	collection: &str,
+	aql: &str,
+	bind_vars: Vec<(&str, JsonValue)>,
+) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	loop {
+		let mut query = AqlQuery::builder()
+			.query(aql)
+			.bind_var("@collection", collection)
+			.bind_var("batch_size", UPDATE_BATCH_SIZE);
+
+		for (k, v) in &bind_vars {
+			query = query.bind_var(*k, v.clone());
+		}
+
+		let updated: Vec<JsonValue> = db.aql_query(query.build()).await?;
+
+		if updated.len() < UPDATE_BATCH_SIZE {
+			return Ok(());
+		}
+	}
+}
+
+pub async fn add_field(collection: String, property: SchemaDocumentProperty) -> Result<(), Error> {
+	alter_collection_rule(&collection, |rule| {
+		if rule.properties.get(&property.name).is_some() {
+			return Err(anyhow!("Field {} already exists", property.name));
+		}
+
+		let (properties, required) = build_object_rule(vec![property]);
+
+		rule.properties.as_object_mut().unwrap().extend(properties);
+		rule.required.extend(required);
+
+		Ok(())
+	})
+	.await
+}
+
+/// Replaces the type and constraints of a field, existing documents aren't validated again.
+/// Translatable fields keep a value of the new type per locale
+pub async fn alter_field(
+	collection: String,
+	property: SchemaDocumentProperty,
+) -> Result<(), Error> {
+	let translatable = get_entry(&collection)
+		.await
+		.map(|entry| get_translatable_fields(&entry).contains(&property.name))
+		.unwrap_or(false);
+
+	alter_collection_rule(&collection, |rule| {
+		if rule.properties.get(&property.name).is_none() {
+			return Err(anyhow!("Field {} not found", property.name));
+		}
+
+		let name = property.name.clone();
+		let (mut properties, required) = build_object_rule(vec![property]);
+
+		if translatable {
+			if let Some(values) = properties.remove(&name) {
+				properties.insert(name.clone(), wrap_translatable_rule(values));
+			}
+		}
+
+		rule.properties.as_object_mut().unwrap().extend(properties);
+		rule.required.retain(|r| *r != name);
+		rule.required.extend(required);
+
+		Ok(())
+	})
+	.await
+}
+
+/// Renames the attribute in the variants, permissions, TTL and translatable fields of a
+/// collection entry
+fn rename_entry_field(entry: &mut JsonValue, name: &str, new_name: &str) {
+	fn rename_key(object: &mut JsonValue, name: &str, new_name: &str) {
+		if let Some(value) = object.as_object_mut().and_then(|o| o.remove(name)) {
+			object[new_name] = value;
+		}
+	}
+
+	fn rename_value(value: &mut JsonValue, name: &str, new_name: &str) {
+		if value.as_str() == Some(name) {
+			*value = JsonValue::String(new_name.to_string());
+		}
+	}
+
+	rename_value(&mut entry["discriminator"], name, new_name);
+
+	if entry["ttl"].is_object() {
+		rename_value(&mut entry["ttl"]["field"], name, new_name);
+	}
+
+	for field in entry["translatable"].as_array_mut().into_iter().flatten() {
+		rename_value(field, name, new_name);
+	}
+
+	if let Some(variants) = entry["variants"].as_array_mut() {
+		for variant in variants {
+			rename_key(&mut variant["properties"], name, new_name);
+
+			for required in variant["required"].as_array_mut().into_iter().flatten() {
+				rename_value(required, name, new_name);
+			}
+		}
+	}
+
+	if let Some(roles) = entry["permissions"].as_object_mut() {
+		for permission in roles.values_mut() {
+			rename_key(&mut permission["filter"], name, new_name);
+			rename_key(&mut permission["presets"], name, new_name);
+		}
+	}
+}
+
+/// Renames a field, moving its value in the existing documents. Variants, permissions and the
+/// TTL refer to the new name, while computed expressions have to be altered separately
+pub async fn rename_field(collection: String, name: String, new_name: String) -> Result<(), Error> {
+	alter_collection_rule(&collection, |rule| {
+		let properties = rule.properties.as_object_mut().unwrap();
+
+		if properties.contains_key(&new_name) {
+			return Err(anyhow!("Field {} already exists", new_name));
+		}
+
+		let property = properties
+			.remove(&name)
+			.ok_or_else(|| anyhow!("Field {} not found", name))?;
+
+		properties.insert(new_name.clone(), property);
+
+		for required in rule.required.iter_mut().filter(|r| **r == name) {
+			*required = new_name.clone();
+		}
+
+		Ok(())
+	})
+	.await?;
+
+	let mut entry = get_entry(&collection)
+		.await
+		.ok_or_else(|| anyhow!("Collection {} not found", collection))?;
+
+	let is_ttl_field = entry["ttl"]["field"].as_str() == Some(name.as_str());
+
+	rename_entry_field(&mut entry, &name, &new_name);
+
+	// Updating would merge the renamed attributes with the previous ones
+	replace_entry(&collection, strip_document_attributes(entry.clone())).await;
+
+	move_field_values(&collection, &name, &new_name).await?;
+
+	// The TTL index is on the attribute itself
+	if is_ttl_field {
+		set_collection_ttl(collection, Some(ttl_from_entry(&entry)?)).await?;
+	}
+
+	Ok(())
+}
+
+/// TTL of a collection entry, as it's given to `set_collection_ttl`
+pub fn ttl_from_entry(entry: &JsonValue) -> Result<SchemaTtl, Error> {
+	let ttl: Ttl = serde_json::from_value(entry["ttl"].clone())?;
+
+	Ok(SchemaTtl {
+		field: ttl.field,
+		expire_after: ttl.expire_after as i32,
+	})
+}
+
+/// Moves the values of a field to another one in the existing documents
+pub async fn move_field_values(collection: &str, name: &str, new_name: &str) -> Result<(), Error> {
+	update_documents(
+		collection,
+		"FOR d IN @@collection
+			FILTER HAS(d, @name)
+			LIMIT @batch_size
+			UPDATE d WITH { [@new_name]: d[@name], [@name]: null } IN @@collection OPTIONS { keepNull: false }
+			RETURN 1",
+		vec![
+			("name", JsonValue::String(name.to_string())),
+			("new_name", JsonValue::String(new_name.to_string())),
+		],
+	)
+	.await
+}
+
+/// Removes a field along with its value in the existing documents
+pub async fn remove_field(collection: String, name: String) -> Result<(), Error> {
+	alter_collection_rule(&collection, |rule| {
+		rule.properties
+			.as_object_mut()
+			.unwrap()
+			.remove(&name)
+			.ok_or_else(|| anyhow!("Field {} not found", name))?;
+		rule.required.retain(|r| *r != name);
+
+		Ok(())
+	})
+	.await?;
+
+	if let Some(entry) = get_entry(&collection).await {
+		let mut translatable = get_translatable_fields(&entry);
+
+		if translatable.contains(&name) {
+			translatable.retain(|f| *f != name);
+
+			update_entry(
+				&collection,
+				serde_json::json!({ "translatable": translatable }),
+			)
+			.await;
+		}
+	}
+
+	update_documents(
+		&collection,
+		"FOR d IN @@collection
+			FILTER HAS(d, @name)
+			LIMIT @batch_size
+			UPDATE d WITH { [@name]: null } IN @@collection OPTIONS { keepNull: false }
+			RETURN 1",
+		vec![("name", JsonValue::String(name))],
+	)
+	.await
+}
@@ -1,30 +1,64 @@
 use async_once::AsyncOnce;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use crate::lib::database::reads::is_leader_read;
+use crate::lib::database::tenants::get_current_database_name;
 use crate::lib::CONFIG;
 
-use rust_arango::{Connection as ArangoConnection, Database as ArangoDatabase};
+use rust_arango::{
+	AqlQuery, ClientError, Connection as ArangoConnection, Database as ArangoDatabase,
+};
+use serde::de::DeserializeOwned;
+
+/// Server errors reported while a cluster is failing over, the request can be sent again
+const TRANSIENT_ERROR_NUMS: [u16; 2] = [
+	1495, // Leadership challenge ongoing
+	1496, // Not a leader
+];
 
 pub struct ArangoDB {
+	pub host: String,
 	pub connection: ArangoConnection,
 	pub database: ArangoDatabase,
+	/// Databases of the tenants opened on the connection, by name
+	tenants: RwLock<HashMap<String, ArangoDatabase>>,
 }
 
 impl ArangoDB {
-	pub async fn new() -> ArangoDB {
+	pub async fn new(host: &str) -> Result<ArangoDB, ClientError> {
 		let connection = ArangoConnection::establish_basic_auth(
-			&CONFIG.db_host.as_str(),
+			host,
 			&CONFIG.db_user.as_str(),
 			&CONFIG.db_pass.as_str(),
 		)
-		.await
-		.unwrap();
+		.await?;
 
-		let database = connection.db(&CONFIG.db_name.as_str()).await.unwrap();
+		let database = connection.db(&CONFIG.db_name.as_str()).await?;
 
-		ArangoDB {
+		Ok(ArangoDB {
+			host: host.to_string(),
 			connection,
 			database,
+			tenants: RwLock::new(HashMap::new()),
+		})
+	}
+
+	/// Handle of the database of the current task
+	fn get_database(&self) -> ArangoDatabase {
+		let name = get_current_database_name();
+
+		if name == CONFIG.db_name {
+			return self.database.clone();
+		}
+
+		// Falling back to the default database would leak the data of the tenants
+		match self.tenants.read().unwrap().get(&name) {
+			Some(database) => database.clone(),
+			None => panic!("Database {} isn't open", name),
 		}
 	}
 
@@ -47,8 +81,227 @@ impl ArangoDB {
 	// }
 }
 
+/// Counters of the requests sent through the pool since startup
+#[derive(Default)]
+pub struct PoolMetrics {
+	pub requests: AtomicUsize,
+	pub in_flight: AtomicUsize,
+	pub retries: AtomicUsize,
+	pub failures: AtomicUsize,
+	pub timeouts: AtomicUsize,
+}
+
+/// Connections to ArangoDB handed out in turns, so concurrent requests don't queue behind a
+/// single one. They are spread over the hosts of `DB_HOST`, a connection to the next host
+/// being made when one can't be reached. The connections to `DB_REPLICA_HOSTS` are handed out
+/// the same way for the reads
+pub struct ArangoPool {
+	pub connections: Vec<ArangoDB>,
+	pub replicas: Vec<ArangoDB>,
+	pub metrics: PoolMetrics,
+	next: AtomicUsize,
+	next_replica: AtomicUsize,
+}
+
+impl ArangoPool {
+	pub async fn new() -> ArangoPool {
+		let hosts: Vec<&str> = CONFIG
+			.db_host
+			.split(',')
+			.map(|h| h.trim())
+			.filter(|h| !h.is_empty())
+			.collect();
+
+		let replica_hosts = CONFIG.get_db_replica_hosts();
+		let replica_hosts: Vec<&str> = replica_hosts.iter().map(|h| h.as_str()).collect();
+
+		let mut connections = Vec::new();
+		let mut replicas = Vec::new();
+
+		for i in 0..CONFIG.get_db_pool_size() {
+			connections.push(connect(&hosts, i).await);
+
+			if !replica_hosts.is_empty() {
+				replicas.push(connect(&replica_hosts, i).await);
+			}
+		}
+
+		ArangoPool {
+			connections,
+			replicas,
+			metrics: PoolMetrics::default(),
+			next: AtomicUsize::new(0),
+			next_replica: AtomicUsize::new(0),
+		}
+	}
+
+	/// Database handle of the next connection of the pool, to the database of the tenant of
+	/// the current request if any
+	pub fn database(&self) -> ArangoDatabase {
+		self.next_connection().get_database()
+	}
+
+	/// Database handle of the next read connection
+	fn read_database(&self) -> ArangoDatabase {
+		self.next_read_connection().get_database()
+	}
+
+	/// Opens the database of a tenant on every connection, its queries can be sent once it is
+	pub async fn open_database(&self, name: &str) -> Result<(), ClientError> {
+		for connection in self.connections.iter().chain(&self.replicas) {
+			if connection.tenants.read().unwrap().contains_key(name) {
+				continue;
+			}
+
+			let database = connection.connection.db(name).await?;
+
+			connection
+				.tenants
+				.write()
+				.unwrap()
+				.insert(name.to_string(), database);
+		}
+
+		Ok(())
+	}
+
+	/// Host of the next connection of the pool, for the requests sent without the driver
+	pub fn host(&self) -> &str {
+		&self.next_connection().host
+	}
+
+	fn next_connection(&self) -> &ArangoDB {
+		let i = self.next.fetch_add(1, Ordering::Relaxed);
+
+		&self.connections[i % self.connections.len()]
+	}
+
+	/// Next connection to a replica, or to the leader when there are none or the current
+	/// request reads from it
+	fn next_read_connection(&self) -> &ArangoDB {
+		if self.replicas.is_empty() || is_leader_read() {
+			return self.next_connection();
+		}
+
+		let i = self.next_replica.fetch_add(1, Ordering::Relaxed);
+
+		&self.replicas[i % self.replicas.len()]
+	}
+
+	/// Runs a read with the next read connection, running it again with the following ones
+	/// after a backoff when it fails with a transient error. Writes shouldn't go through here
+	/// since a request failing on the way back may still have been applied, nor reach the
+	/// replicas
+	pub async fn run<T, F, Fut>(&self, operation: F) -> Result<T, ClientError>
+	where
+		F: Fn(ArangoDatabase) -> Fut,
+		Fut: Future<Output = Result<T, ClientError>>,
+	{
+		let retries = CONFIG.get_db_retries();
+		let mut backoff = CONFIG.get_db_retry_backoff();
+		let mut attempt = 0;
+
+		loop {
+			self.metrics.requests.fetch_add(1, Ordering::Relaxed);
+			self.metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+
+			let result = match CONFIG.get_db_query_timeout() {
+				Some(timeout) => tokio::time::timeout(timeout, operation(self.read_database()))
+					.await
+					.map_err(|_| timeout),
+				None => Ok(operation(self.read_database()).await),
+			};
+
+			self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+
+			match result {
+				Ok(Ok(value)) => return Ok(value),
+				Ok(Err(e)) if attempt < retries && is_transient(&e) => {
+					tracing::warn!("Retrying ArangoDB request after error: {}", e);
+
+					self.metrics.retries.fetch_add(1, Ordering::Relaxed);
+				}
+				Ok(Err(e)) => {
+					self.metrics.failures.fetch_add(1, Ordering::Relaxed);
+
+					return Err(e);
+				}
+				Err(timeout) => {
+					self.metrics.timeouts.fetch_add(1, Ordering::Relaxed);
+
+					return Err(ClientError::HttpClient(format!(
+						"Request timed out after {:?}",
+						timeout
+					)));
+				}
+			}
+
+			tokio::time::sleep(backoff).await;
+
+			attempt += 1;
+			backoff *= 2;
+		}
+	}
+}
+
+/// Connects to the hosts in turns starting with the `i`th, so the connections of the pool are
+/// spread over them. Only panics when none of them can be reached
+async fn connect(hosts: &[&str], i: usize) -> ArangoDB {
+	let timeout = CONFIG.get_db_connect_timeout();
+
+	for n in 0..hosts.len() {
+		let host = hosts[(i + n) % hosts.len()];
+
+		match tokio::time::timeout(timeout, ArangoDB::new(host)).await {
+			Ok(Ok(db)) => return db,
+			Ok(Err(e)) => tracing::error!("Error connecting to ArangoDB at {}: {}", host, e),
+			Err(_) => tracing::error!("Timed out connecting to ArangoDB at {}", host),
+		}
+	}
+
+	panic!(
+		"Could not connect to any ArangoDB host of {}",
+		hosts.join(",")
+	);
+}
+
+/// Executes the query with a cursor, each batch being handed to `on_batch` before the next one
+/// is fetched so the JSON of only one batch is held at once. The batch size is the one of the
+/// query
+pub async fn fetch_batches<R, F>(
+	database: &ArangoDatabase,
+	query: AqlQuery<'_>,
+	mut on_batch: F,
+) -> Result<(), ClientError>
+where
+	R: DeserializeOwned,
+	F: FnMut(Vec<R>),
+{
+	let cursor = database.aql_query_batch(query).await?;
+	let mut cursor_id = cursor.id.filter(|_| cursor.more);
+
+	on_batch(cursor.result);
+
+	while let Some(id) = cursor_id.take() {
+		let cursor = database.aql_next_batch(id.as_str()).await?;
+
+		cursor_id = cursor.id.filter(|_| cursor.more);
+		on_batch(cursor.result);
+	}
+
+	Ok(())
+}
+
+fn is_transient(error: &ClientError) -> bool {
+	match error {
+		ClientError::HttpClient(_) => true,
+		ClientError::Arango(e) => e.code() == 503 || TRANSIENT_ERROR_NUMS.contains(&e.error_num()),
+		_ => false,
+	}
+}
+
 lazy_static::lazy_static! {
-	pub static ref DATABASE: AsyncOnce<Arc<ArangoDB>> = AsyncOnce::new(async {
-		   Arc::new(ArangoDB::new().await)
+	pub static ref DATABASE: AsyncOnce<Arc<ArangoPool>> = AsyncOnce::new(async {
+		   Arc::new(ArangoPool::new().await)
 	   });
 }
@@ -8,6 +8,9 @@ pub use api::generate_sdl;
 
 pub mod aql;
 pub mod database;
+pub mod reads;
+pub mod tenants;
 
+pub use database::fetch_batches;
 pub use database::ArangoDB;
 pub use database::DATABASE;
@@ -0,0 +1,27 @@
+use std::cell::Cell;
+use std::future::Future;
+
+tokio::task_local! {
+	/// Whether the reads of a request are sent to the leader instead of the replicas, so they
+	/// see what it has written
+	static LEADER_READS: Cell<bool>;
+}
+
+/// Runs the future with its reads sent to the leader when `leader` is set, to the replicas
+/// until it writes otherwise
+pub fn scope_reads<F: Future>(leader: bool, future: F) -> impl Future<Output = F::Output> {
+	LEADER_READS.scope(Cell::new(leader), future)
+}
+
+/// Sends the next reads of the current request to the leader, e.g. once it has written
+pub fn read_from_leader() {
+	let _ = LEADER_READS.try_with(|leader| leader.set(true));
+}
+
+/// Whether the reads of the current task go to the leader, the tasks outside of a request
+/// read from the replicas
+pub fn is_leader_read() -> bool {
+	LEADER_READS
+		.try_with(|leader| leader.get())
+		.unwrap_or(false)
+}
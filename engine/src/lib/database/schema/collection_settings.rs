@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// ArangoDB options a collection was created with, unset ones use the server defaults
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CollectionSettings {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub key_type: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub number_of_shards: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub replication_factor: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub wait_for_sync: Option<bool>,
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// A read-only property computed with an AQL expression, the document is available as `doc`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ComputedProperty {
+	pub name: String,
+	pub expression: String,
+	pub schema: serde_json::Value,
+}
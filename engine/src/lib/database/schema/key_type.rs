@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// How ArangoDB generates the `_key` of new documents
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, GraphQLEnum)]
+pub enum SchemaKeyType {
+	Traditional,
+	Autoincrement,
+	Uuid,
+	Padded,
+}
+
+impl SchemaKeyType {
+	pub fn as_str(&self) -> String {
+		match self {
+			SchemaKeyType::Traditional => String::from("traditional"),
+			SchemaKeyType::Autoincrement => String::from("autoincrement"),
+			SchemaKeyType::Uuid => String::from("uuid"),
+			SchemaKeyType::Padded => String::from("padded"),
+		}
+	}
+}
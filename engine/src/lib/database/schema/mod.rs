@@ -15,3 +15,33 @@ pub use property_type::SchemaPropertyType;
 
 pub mod native_type_array;
 pub use native_type_array::SchemaNativeTypeArray;
+
+pub mod uuid_version;
+pub use uuid_version::SchemaUuidVersion;
+
+pub mod variant;
+pub use variant::Variant;
+
+pub mod computed;
+pub use computed::ComputedProperty;
+
+pub mod relationship_type;
+pub use relationship_type::SchemaRelationshipType;
+
+pub mod on_delete;
+pub use on_delete::SchemaOnDelete;
+
+pub mod ttl;
+pub use ttl::Ttl;
+
+pub mod versioning;
+pub use versioning::Versioning;
+
+pub mod key_type;
+pub use key_type::SchemaKeyType;
+
+pub mod collection_settings;
+pub use collection_settings::CollectionSettings;
+
+pub mod validation_level;
+pub use validation_level::{get_validation_level, SchemaValidationLevel};
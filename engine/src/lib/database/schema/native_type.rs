@@ -1,12 +1,13 @@
 use serde::{Deserialize, Serialize};
 
 /// The native types for Arango to store
-#[derive(Serialize, Deserialize, PartialEq, Default, GraphQLEnum)]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, GraphQLEnum)]
 pub enum SchemaNativeType {
 	#[default]
 	String,
 	Integer,
 	Boolean,
+	Object,
 }
 
 impl SchemaNativeType {
@@ -15,6 +16,7 @@ impl SchemaNativeType {
 			SchemaNativeType::String => String::from("string"),
 			SchemaNativeType::Integer => String::from("integer"),
 			SchemaNativeType::Boolean => String::from("boolean"),
+			SchemaNativeType::Object => String::from("object"),
 		}
 	}
 }
@@ -2,7 +2,15 @@ use serde::{Deserialize, Serialize};
 
 /// Schema native array for Arango
 #[derive(Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct SchemaNativeTypeArray {
 	pub r#type: String,
 	pub maximum: Option<i32>,
+	/// Only set if the items are structured objects
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub properties: Option<serde_json::Map<String, serde_json::Value>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub required: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub additional_properties: Option<bool>,
 }
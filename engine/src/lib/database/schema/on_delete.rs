@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// What happens to the related documents of a relationship when a document is removed
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, GraphQLEnum)]
+pub enum SchemaOnDelete {
+	/// Related documents are removed as well
+	Cascade,
+	/// The removal fails while there are related documents
+	Restrict,
+	/// Only the edges are removed
+	SetNull,
+}
+
+impl SchemaOnDelete {
+	pub fn as_str(&self) -> String {
+		match self {
+			SchemaOnDelete::Cascade => String::from("cascade"),
+			SchemaOnDelete::Restrict => String::from("restrict"),
+			SchemaOnDelete::SetNull => String::from("set_null"),
+		}
+	}
+}
@@ -14,9 +14,38 @@ pub struct SchemaProperty {
 	pub max_length: Option<i32>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub r#enum: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub format: Option<String>,
+	/// Generator used by the API when the value is omitted on create
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub generate: Option<String>,
+	/// Whether the value is also used as the document `_key`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub use_as_key: Option<bool>,
+	/// Storage backend of the uploads of `File` properties
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub storage: Option<String>,
+	/// Reason the field is deprecated for in the API
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub deprecation_reason: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Descriptions of the values of enums, by value
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub enum_descriptions: Option<serde_json::Map<String, serde_json::Value>>,
+	/// Directives of the field by name, e.g. `{ "cost": { "complexity": 5 } }`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub directives: Option<serde_json::Map<String, serde_json::Value>>,
 	/// Only set if the type is an Array
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub items: Option<SchemaNativeTypeArray>,
+	/// Only set if the type is a structured Object
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub properties: Option<serde_json::Map<String, serde_json::Value>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub required: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub additional_properties: Option<bool>,
 }
 
 impl SchemaProperty {
@@ -3,14 +3,22 @@ use serde::{Deserialize, Serialize};
 use super::SchemaNativeType;
 
 /// The schema property type of the collection
-#[derive(Serialize, Deserialize, PartialEq, Default, GraphQLEnum)]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, GraphQLEnum)]
 pub enum SchemaPropertyType {
 	#[default]
 	String,
 	Integer,
+	BigInt,
 	Array,
 	Boolean,
 	Enum,
+	Bytes,
+	DateTime,
+	Decimal,
+	File,
+	Json,
+	Object,
+	Uuid,
 }
 
 impl SchemaPropertyType {
@@ -18,9 +26,17 @@ impl SchemaPropertyType {
 		match self {
 			SchemaPropertyType::String => String::from("string"),
 			SchemaPropertyType::Integer => String::from("integer"),
+			SchemaPropertyType::BigInt => String::from("integer"),
 			SchemaPropertyType::Array => String::from("array"),
 			SchemaPropertyType::Boolean => String::from("boolean"),
 			SchemaPropertyType::Enum => String::from("enum"),
+			SchemaPropertyType::Bytes => String::from("string"),
+			SchemaPropertyType::DateTime => String::from("string"),
+			SchemaPropertyType::Decimal => String::from("string"),
+			SchemaPropertyType::File => String::from("string"),
+			SchemaPropertyType::Json => String::from("object"),
+			SchemaPropertyType::Object => String::from("object"),
+			SchemaPropertyType::Uuid => String::from("string"),
 		}
 	}
 }
@@ -30,9 +46,17 @@ impl From<SchemaPropertyType> for SchemaNativeType {
 		match property_type {
 			SchemaPropertyType::String => SchemaNativeType::String,
 			SchemaPropertyType::Integer => SchemaNativeType::Integer,
+			SchemaPropertyType::BigInt => SchemaNativeType::Integer,
 			SchemaPropertyType::Array => SchemaNativeType::String,
 			SchemaPropertyType::Boolean => SchemaNativeType::Boolean,
 			SchemaPropertyType::Enum => SchemaNativeType::String,
+			SchemaPropertyType::Bytes => SchemaNativeType::String,
+			SchemaPropertyType::DateTime => SchemaNativeType::String,
+			SchemaPropertyType::Decimal => SchemaNativeType::String,
+			SchemaPropertyType::File => SchemaNativeType::String,
+			SchemaPropertyType::Json => SchemaNativeType::String,
+			SchemaPropertyType::Object => SchemaNativeType::String,
+			SchemaPropertyType::Uuid => SchemaNativeType::String,
 		}
 	}
 }
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// The cardinality of a relationship between two collections
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, GraphQLEnum)]
+pub enum SchemaRelationshipType {
+	OneToOne,
+	OneToMany,
+	ManyToOne,
+	ManyToMany,
+}
+
+impl SchemaRelationshipType {
+	pub fn as_str(&self) -> String {
+		match self {
+			SchemaRelationshipType::OneToOne => String::from("one_to_one"),
+			SchemaRelationshipType::OneToMany => String::from("one_to_many"),
+			SchemaRelationshipType::ManyToOne => String::from("many_to_one"),
+			SchemaRelationshipType::ManyToMany => String::from("many_to_many"),
+		}
+	}
+}
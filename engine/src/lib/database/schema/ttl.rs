@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// Documents expire at the date stored in `field`, set `expire_after` seconds past their creation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ttl {
+	pub field: String,
+	pub expire_after: u32,
+}
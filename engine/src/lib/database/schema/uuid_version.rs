@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// The UUID version generated by the server when a value is omitted
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, GraphQLEnum)]
+pub enum SchemaUuidVersion {
+	V4,
+	V7,
+}
+
+impl SchemaUuidVersion {
+	pub fn as_str(&self) -> String {
+		match self {
+			SchemaUuidVersion::V4 => String::from("uuid_v4"),
+			SchemaUuidVersion::V7 => String::from("uuid_v7"),
+		}
+	}
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// Documents ArangoDB validates against the schema of a collection
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, GraphQLEnum)]
+pub enum SchemaValidationLevel {
+	/// Validation is turned off
+	None,
+	/// Only new documents are validated
+	New,
+	/// New documents and changes of valid documents are validated, invalid documents can still
+	/// be changed, e.g. while they're migrated
+	Moderate,
+	/// Every inserted or changed document is validated
+	Strict,
+}
+
+impl SchemaValidationLevel {
+	pub fn as_str(&self) -> String {
+		match self {
+			SchemaValidationLevel::None => String::from("none"),
+			SchemaValidationLevel::New => String::from("new"),
+			SchemaValidationLevel::Moderate => String::from("moderate"),
+			SchemaValidationLevel::Strict => String::from("strict"),
+		}
+	}
+}
+
+/// Validation level of a collection entry, collections are strict unless set otherwise
+pub fn get_validation_level(entry: &serde_json::Value) -> String {
+	entry["level"].as_str().unwrap_or("strict").to_string()
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// A variant of a polymorphic collection, selected by the value of the discriminator
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Variant {
+	pub name: String,
+	pub value: String,
+	pub properties: serde_json::Value,
+	pub required: Vec<String>,
+}
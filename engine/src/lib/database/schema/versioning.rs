@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Prior versions of the documents are kept in `{collection}_revisions`, pruned past
+/// `max_revisions` per document or `max_age_days` when set
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Versioning {
+	pub max_revisions: Option<u32>,
+	pub max_age_days: Option<u32>,
+}
@@ -0,0 +1,36 @@
+use std::future::Future;
+
+use crate::lib::database::reads::{is_leader_read, scope_reads};
+use crate::lib::CONFIG;
+
+tokio::task_local! {
+	/// Database of the tenant a request is for, `DB_NAME` outside of the requests
+	static TENANT_DATABASE: String;
+}
+
+/// Database the queries of the current task are sent to
+pub fn get_current_database_name() -> String {
+	TENANT_DATABASE
+		.try_with(|name| name.clone())
+		.unwrap_or_else(|_| CONFIG.db_name.clone())
+}
+
+pub fn is_default_database() -> bool {
+	TENANT_DATABASE
+		.try_with(|name| *name == CONFIG.db_name)
+		.unwrap_or(true)
+}
+
+/// Runs the future with its queries sent to the database
+pub fn scope_database<F: Future>(name: String, future: F) -> impl Future<Output = F::Output> {
+	TENANT_DATABASE.scope(name, future)
+}
+
+/// Keeps the database of the current task and where its reads are sent for a future run
+/// later, e.g. spawned or streamed in a response body once the request is handled
+pub fn in_current_database<F: Future>(future: F) -> impl Future<Output = F::Output> {
+	scope_database(
+		get_current_database_name(),
+		scope_reads(is_leader_read(), future),
+	)
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Error};
+use chrono::{SecondsFormat, Utc};
+use lazy_static::lazy_static;
+use rust_arango::AqlQuery;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, to_value as toJsonValue, value::Value as JsonValue};
+
+use crate::lib::database::tenants::{get_current_database_name, scope_database};
+use crate::lib::database::DATABASE;
+use crate::lib::schema::get_entry;
+use crate::lib::CONFIG;
+
+pub mod worker;
+pub use worker::{flush_events, run_event_worker};
+
+const WEBHOOKS_COLLECTION: &str = "alchemy_webhooks";
+const EVENTS_COLLECTION: &str = "alchemy_events";
+const DEAD_EVENTS_COLLECTION: &str = "alchemy_dead_events";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, GraphQLEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum EventOperation {
+	Create,
+	Update,
+	Delete,
+}
+
+/// Header sent with the deliveries of a webhook
+#[derive(Clone, GraphQLInputObject)]
+pub struct WebhookHeader {
+	pub name: String,
+	pub value: String,
+}
+
+/// Subscription of an URL to the changes of a collection
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlchemyWebhookEntry {
+	pub name: String,
+	pub collection: String,
+	pub url: String,
+	pub events: Vec<EventOperation>,
+	/// Sent with every delivery, e.g. a secret checked by the receiver
+	#[serde(default)]
+	pub headers: HashMap<String, String>,
+	/// Deliveries attempted before the event is moved to the dead events
+	pub max_attempts: u32,
+	/// Delay before the first retry, doubled after each failed attempt
+	pub backoff_seconds: u32,
+}
+
+impl AlchemyWebhookEntry {
+	fn matches(&self, collection: &str, operation: EventOperation) -> bool {
+		self.collection == collection && self.events.contains(&operation)
+	}
+}
+
+lazy_static! {
+	/// Webhooks of every collection, refreshed by the event worker and on meta API changes
+	static ref WEBHOOKS: RwLock<Vec<AlchemyWebhookEntry>> = RwLock::new(Vec::new());
+}
+
+fn now() -> String {
+	Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+async fn ensure_collection(name: &str) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(name).await.is_err() {
+		db.create_collection(name).await?;
+	}
+
+	Ok(())
+}
+
+pub async fn get_webhooks() -> Vec<AlchemyWebhookEntry> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(WEBHOOKS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query("FOR w IN @@collection SORT w.name RETURN UNSET(w, '_id', '_key', '_rev')")
+		.bind_var("@collection", WEBHOOKS_COLLECTION)
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+/// Reads the webhooks from the database into the ones mutations are checked against
+pub async fn refresh_webhooks() {
+	let webhooks = get_webhooks().await;
+
+	*WEBHOOKS.write().unwrap() = webhooks;
+}
+
+/// Whether changes of the collection are delivered anywhere, so mutations only return the
+/// changed documents when needed
+pub fn has_webhooks(collection: &str, operation: EventOperation) -> bool {
+	WEBHOOKS
+		.read()
+		.unwrap()
+		.iter()
+		.any(|w| w.matches(collection, operation))
+}
+
+fn get_webhook(name: &str) -> Option<AlchemyWebhookEntry> {
+	WEBHOOKS
+		.read()
+		.unwrap()
+		.iter()
+		.find(|w| w.name == name)
+		.cloned()
+}
+
+/// Creates the webhook, replacing the one with the same name
+pub async fn save_webhook(webhook: AlchemyWebhookEntry) -> Result<(), Error> {
+	if webhook.events.is_empty() {
+		return Err(anyhow!("Webhook {} has no events", webhook.name));
+	}
+
+	if url::Url::parse(&webhook.url).is_err() {
+		return Err(anyhow!("Invalid webhook URL {}", webhook.url));
+	}
+
+	if get_entry(&webhook.collection).await.is_none() {
+		return Err(anyhow!("Collection {} not found", webhook.collection));
+	}
+
+	ensure_collection(WEBHOOKS_COLLECTION).await?;
+
+	let query = AqlQuery::builder()
+		.query(
+			"UPSERT { name: @webhook.name }
+				INSERT @webhook
+				REPLACE @webhook
+				IN @@collection",
+		)
+		.bind_var("@collection", WEBHOOKS_COLLECTION)
+		.bind_var("webhook", toJsonValue(&webhook)?)
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	refresh_webhooks().await;
+
+	Ok(())
+}
+
+/// Deletes the webhook, its pending events are dropped by the worker
+pub async fn delete_webhook(name: &str) -> Result<(), Error> {
+	ensure_collection(WEBHOOKS_COLLECTION).await?;
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR w IN @@collection
+				FILTER w.name == @name
+				REMOVE w IN @@collection
+				RETURN 1",
+		)
+		.bind_var("@collection", WEBHOOKS_COLLECTION)
+		.bind_var("name", name)
+		.build();
+
+	let removed: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	if removed.is_empty() {
+		return Err(anyhow!("Webhook {} not found", name));
+	}
+
+	refresh_webhooks().await;
+
+	Ok(())
+}
+
+/// Queues an event for every webhook of the collection subscribed to the operation, `changes`
+/// being the `old` and `new` documents returned by the mutation
+pub async fn enqueue_events(
+	collection: &str,
+	operation: EventOperation,
+	changes: &[JsonValue],
+) -> Result<(), Error> {
+	let webhooks: Vec<String> = WEBHOOKS
+		.read()
+		.unwrap()
+		.iter()
+		.filter(|w| w.matches(collection, operation))
+		.map(|w| w.name.clone())
+		.collect();
+
+	if webhooks.is_empty() || changes.is_empty() {
+		return Ok(());
+	}
+
+	let now = now();
+	let database = get_current_database_name();
+	let mut events = Vec::new();
+
+	for webhook in &webhooks {
+		for change in changes {
+			events.push(json!({
+				"webhook": webhook,
+				"database": database,
+				"collection": collection,
+				"operation": operation,
+				"old": change.get("old"),
+				"new": change.get("new"),
+				"createdAt": now,
+				"attempts": 0,
+				"nextAttemptAt": now,
+			}));
+		}
+	}
+
+	// The events of every tenant are queued in the default database, where the worker reads them
+	scope_database(CONFIG.db_name.clone(), async {
+		ensure_collection(EVENTS_COLLECTION).await?;
+
+		let query = AqlQuery::builder()
+			.query("FOR e IN @events INSERT e INTO @@collection")
+			.bind_var("@collection", EVENTS_COLLECTION)
+			.bind_var("events", JsonValue::Array(events))
+			.build();
+
+		let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+		Ok(())
+	})
+	.await
+}
+
+/// Events whose deliveries failed every attempt, the latest first
+pub async fn get_dead_events(limit: Option<u32>) -> Vec<JsonValue> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(DEAD_EVENTS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR e IN @@collection
+				SORT e.failedAt DESC
+				LIMIT @limit
+				RETURN e",
+		)
+		.bind_var("@collection", DEAD_EVENTS_COLLECTION)
+		.bind_var("limit", limit.unwrap_or(u32::MAX))
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+/// Queues dead events again with their attempts reset, all of them when no ids are given.
+/// Returns how many were queued
+pub async fn retry_dead_events(ids: Option<Vec<String>>) -> Result<usize, Error> {
+	ensure_collection(DEAD_EVENTS_COLLECTION).await?;
+	ensure_collection(EVENTS_COLLECTION).await?;
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR e IN @@dead
+				FILTER @ids == null OR e._key IN @ids
+				REMOVE e IN @@dead
+				INSERT MERGE(UNSET(e, '_id', '_key', '_rev', 'failedAt', 'error'), {
+					attempts: 0,
+					nextAttemptAt: @now
+				}) INTO @@events
+				RETURN 1",
+		)
+		.bind_var("@dead", DEAD_EVENTS_COLLECTION)
+		.bind_var("@events", EVENTS_COLLECTION)
+		.bind_var("ids", ids)
+		.bind_var("now", now())
+		.build();
+
+	let retried: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(retried.len())
+}
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Error};
+use chrono::Utc;
+use rust_arango::AqlQuery;
+use serde_json::{json, value::Value as JsonValue};
+
+use crate::lib::database::DATABASE;
+use crate::lib::events::{
+	ensure_collection, get_webhook, now, refresh_webhooks, AlchemyWebhookEntry,
+	DEAD_EVENTS_COLLECTION, EVENTS_COLLECTION,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Events delivered per poll, the rest wait for the next one
+const DELIVERY_BATCH_SIZE: u32 = 100;
+/// Claimed events are due again after this long, in case their instance stopped delivering
+const CLAIM_DURATION: Duration = Duration::from_secs(60);
+/// Longest wait before retrying an event, however many attempts failed
+const MAX_RETRY_DELAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Claims the due events, postponing them so the other instances don't deliver them too. An
+/// event is only claimed if it's still due as read, like the cron jobs
+async fn claim_due_events() -> Result<Vec<JsonValue>, Error> {
+	let db = DATABASE.get().await.database();
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR e IN @@collection
+				FILTER e.nextAttemptAt <= @now
+				SORT e.nextAttemptAt
+				LIMIT @limit
+				RETURN e",
+		)
+		.bind_var("@collection", EVENTS_COLLECTION)
+		.bind_var("now", now())
+		.bind_var("limit", DELIVERY_BATCH_SIZE)
+		.build();
+
+	let due: Vec<JsonValue> = db.aql_query(query).await?;
+
+	let claimed_until = (Utc::now() + chrono::Duration::from_std(CLAIM_DURATION).unwrap())
+		.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+	let mut claimed = Vec::new();
+
+	for event in due {
+		let query = AqlQuery::builder()
+			.query(
+				"FOR e IN @@collection
+					FILTER e._key == @key AND e.nextAttemptAt == @nextAttemptAt
+					UPDATE e WITH { nextAttemptAt: @claimedUntil } IN @@collection
+					RETURN 1",
+			)
+			.bind_var("@collection", EVENTS_COLLECTION)
+			.bind_var("key", event["_key"].clone())
+			.bind_var("nextAttemptAt", event["nextAttemptAt"].clone())
+			.bind_var("claimedUntil", claimed_until.as_str())
+			.build();
+
+		// Conflicting with the claim of another instance leaves the event to it
+		match db.aql_query::<JsonValue>(query).await {
+			Ok(updated) if !updated.is_empty() => claimed.push(event),
+			Ok(_) => {}
+			Err(e) => tracing::debug!("Event claimed by another instance: {}", e),
+		}
+	}
+
+	Ok(claimed)
+}
+
+async fn deliver(
+	client: &reqwest::Client,
+	webhook: &AlchemyWebhookEntry,
+	event: &JsonValue,
+) -> Result<(), Error> {
+	let mut request = client
+		.post(&webhook.url)
+		.timeout(DELIVERY_TIMEOUT)
+		.json(&json!({
+			"id": event["_key"],
+			"webhook": webhook.name,
+			"database": event["database"],
+			"collection": event["collection"],
+			"operation": event["operation"],
+			"old": event["old"],
+			"new": event["new"],
+			"createdAt": event["createdAt"],
+		}));
+
+	for (name, value) in &webhook.headers {
+		request = request.header(name, value);
+	}
+
+	let response = request.send().await?;
+
+	match response.status().is_success() {
+		true => Ok(()),
+		false => Err(anyhow!("Webhook responded with {}", response.status())),
+	}
+}
+
+async fn run_query(query: AqlQuery<'_>) -> Result<(), Error> {
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(())
+}
+
+async fn remove_event(key: &str) -> Result<(), Error> {
+	run_query(
+		AqlQuery::builder()
+			.query("REMOVE @key IN @@collection")
+			.bind_var("@collection", EVENTS_COLLECTION)
+			.bind_var("key", key)
+			.build(),
+	)
+	.await
+}
+
+/// Retries the event later, waiting twice as long after each failed attempt up to a day, or
+/// moves it to the dead events once it ran out of attempts
+async fn fail_event(
+	webhook: &AlchemyWebhookEntry,
+	event: &JsonValue,
+	error: String,
+) -> Result<(), Error> {
+	let key = event["_key"].as_str().unwrap_or_default();
+	let attempts = event["attempts"].as_u64().unwrap_or_default() + 1;
+
+	if attempts >= webhook.max_attempts as u64 {
+		ensure_collection(DEAD_EVENTS_COLLECTION).await?;
+
+		return run_query(
+			AqlQuery::builder()
+				.query(
+					"FOR e IN @@events
+						FILTER e._key == @key
+						REMOVE e IN @@events
+						INSERT MERGE(UNSET(e, '_id', '_rev'), {
+							attempts: @attempts,
+							error: @error,
+							failedAt: @now
+						}) INTO @@dead",
+				)
+				.bind_var("@events", EVENTS_COLLECTION)
+				.bind_var("@dead", DEAD_EVENTS_COLLECTION)
+				.bind_var("key", key)
+				.bind_var("attempts", attempts)
+				.bind_var("error", error)
+				.bind_var("now", now())
+				.build(),
+		)
+		.await;
+	}
+
+	let delay = (webhook.backoff_seconds as i64)
+		.saturating_mul(2i64.saturating_pow(attempts.min(62) as u32 - 1))
+		.min(MAX_RETRY_DELAY_SECONDS);
+	let next_attempt_at = Utc::now() + chrono::Duration::seconds(delay);
+
+	run_query(
+		AqlQuery::builder()
+			.query("UPDATE @key WITH @changes IN @@collection")
+			.bind_var("@collection", EVENTS_COLLECTION)
+			.bind_var("key", key)
+			.bind_var(
+				"changes",
+				json!({
+					"attempts": attempts,
+					"error": error,
+					"nextAttemptAt": next_attempt_at
+						.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+				}),
+			)
+			.build(),
+	)
+	.await
+}
+
+async fn deliver_due_events(client: &reqwest::Client) -> Result<(), Error> {
+	for event in claim_due_events().await? {
+		let key = event["_key"].as_str().unwrap_or_default();
+
+		// Events of deleted webhooks have nowhere to go
+		let webhook = match get_webhook(event["webhook"].as_str().unwrap_or_default()) {
+			Some(webhook) => webhook,
+			None => {
+				remove_event(key).await?;
+
+				continue;
+			}
+		};
+
+		match deliver(client, &webhook, &event).await {
+			Ok(_) => remove_event(key).await?,
+			Err(e) => fail_event(&webhook, &event, e.to_string()).await?,
+		}
+	}
+
+	Ok(())
+}
+
+/// Delivers the queued events to their webhooks, polling for due ones
+pub async fn run_event_worker() {
+	let client = reqwest::Client::new();
+
+	loop {
+		refresh_webhooks().await;
+
+		if let Err(e) = ensure_collection(EVENTS_COLLECTION).await {
+			tracing::error!("Error creating the events collection: {}", e);
+		} else if let Err(e) = deliver_due_events(&client).await {
+			tracing::error!("Error delivering events: {}", e);
+		}
+
+		tokio::time::sleep(POLL_INTERVAL).await;
+	}
+}
+
+/// Delivers the due events once, when shutting down
+pub async fn flush_events() {
+	if let Err(e) = deliver_due_events(&reqwest::Client::new()).await {
+		tracing::error!("Error delivering events: {}", e);
+	}
+}
@@ -0,0 +1,134 @@
+use std::io::Cursor;
+
+use anyhow::{anyhow, Error};
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageOutputFormat};
+use serde::Deserialize;
+
+/// Largest width or height of the transformed images
+const MAX_DIMENSION: u32 = 4096;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFit {
+	/// Fits the image inside the size, keeping its aspect ratio
+	Contain,
+	/// Covers the whole size, cropping what overflows
+	Cover,
+	/// Stretches the image to the size
+	Fill,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageTransformFormat {
+	Jpeg,
+	Png,
+}
+
+/// Resizing and conversion of a stored image, read from the query string of its URL
+#[derive(Deserialize, Clone, Debug)]
+pub struct ImageTransform {
+	pub w: Option<u32>,
+	pub h: Option<u32>,
+	pub fit: Option<ImageFit>,
+	pub format: Option<ImageTransformFormat>,
+}
+
+impl ImageTransform {
+	pub fn is_empty(&self) -> bool {
+		self.w.is_none() && self.h.is_none() && self.format.is_none()
+	}
+
+	/// Format of the derivative, PNG for source images other than JPEG
+	fn get_format(&self, mime: &str) -> ImageTransformFormat {
+		self.format.unwrap_or(match mime {
+			"image/jpeg" => ImageTransformFormat::Jpeg,
+			_ => ImageTransformFormat::Png,
+		})
+	}
+
+	pub fn get_mime(&self, mime: &str) -> &'static str {
+		match self.get_format(mime) {
+			ImageTransformFormat::Jpeg => "image/jpeg",
+			ImageTransformFormat::Png => "image/png",
+		}
+	}
+
+	/// Name of the derivative, e.g. `200x0-cover.jpeg`
+	pub fn get_variant(&self, mime: &str) -> String {
+		format!(
+			"{}x{}-{}.{}",
+			self.w.unwrap_or(0),
+			self.h.unwrap_or(0),
+			match self.fit.unwrap_or(ImageFit::Contain) {
+				ImageFit::Contain => "contain",
+				ImageFit::Cover => "cover",
+				ImageFit::Fill => "fill",
+			},
+			match self.get_format(mime) {
+				ImageTransformFormat::Jpeg => "jpeg",
+				ImageTransformFormat::Png => "png",
+			}
+		)
+	}
+
+	/// Path the derivative is cached at in the storage of the file
+	pub fn get_derivative_path(&self, id: &str, mime: &str) -> String {
+		format!("derivatives/{}/{}", id, self.get_variant(mime))
+	}
+
+	pub fn validate(&self) -> Result<(), Error> {
+		let valid_dimensions = [self.w, self.h]
+			.iter()
+			.flatten()
+			.all(|d| (1..=MAX_DIMENSION).contains(d));
+
+		if !valid_dimensions {
+			return Err(anyhow!(
+				"Image dimensions must be between 1 and {}",
+				MAX_DIMENSION
+			));
+		}
+
+		Ok(())
+	}
+
+	/// Resizes and converts the image, a missing width or height follows the aspect ratio
+	pub fn apply(&self, content: &[u8], mime: &str) -> Result<Vec<u8>, Error> {
+		let source_format =
+			ImageFormat::from_mime_type(mime).ok_or_else(|| anyhow!("{} isn't an image", mime))?;
+
+		let mut image = image::load_from_memory_with_format(content, source_format)?;
+
+		if self.w.is_some() || self.h.is_some() {
+			let scale = |size: u32, from: u32, to: u32| {
+				(size as u64 * to as u64 / from.max(1) as u64).clamp(1, MAX_DIMENSION as u64) as u32
+			};
+
+			let width = self
+				.w
+				.unwrap_or_else(|| scale(self.h.unwrap(), image.height(), image.width()));
+			let height = self
+				.h
+				.unwrap_or_else(|| scale(self.w.unwrap(), image.width(), image.height()));
+
+			image = match self.fit.unwrap_or(ImageFit::Contain) {
+				ImageFit::Contain => image.resize(width, height, FilterType::Lanczos3),
+				ImageFit::Cover => image.resize_to_fill(width, height, FilterType::Lanczos3),
+				ImageFit::Fill => image.resize_exact(width, height, FilterType::Lanczos3),
+			};
+		}
+
+		let output_format = match self.get_format(mime) {
+			ImageTransformFormat::Jpeg => ImageOutputFormat::Jpeg(85),
+			ImageTransformFormat::Png => ImageOutputFormat::Png,
+		};
+
+		let mut output = Cursor::new(Vec::new());
+
+		image.write_to(&mut output, output_format)?;
+
+		Ok(output.into_inner())
+	}
+}
@@ -0,0 +1,73 @@
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::lib::CONFIG;
+
+/// Locales a translatable value is looked up in: the requested locale, its language and the
+/// default locale
+pub fn get_locale_chain(locale: Option<&str>) -> Vec<String> {
+	let mut chain: Vec<String> = Vec::new();
+
+	if let Some(locale) = locale.filter(|l| !l.is_empty()) {
+		chain.push(locale.to_string());
+
+		if let Some((language, _)) = locale.split_once('-') {
+			chain.push(language.to_string());
+		}
+	}
+
+	chain.push(CONFIG.get_default_locale().to_string());
+	chain.dedup();
+
+	chain
+}
+
+/// Translatable fields of a collection entry
+pub fn get_translatable_fields(entry: &JsonValue) -> Vec<String> {
+	entry["translatable"]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|f| f.as_str())
+		.map(str::to_string)
+		.collect()
+}
+
+/// Rule of a translatable field, an object holding a value of the field rule per locale
+pub fn wrap_translatable_rule(rule: JsonValue) -> JsonValue {
+	json!({
+		"type": "object",
+		"additionalProperties": rule,
+	})
+}
+
+/// Rule of the values of a translatable field
+pub fn unwrap_translatable_rule(rule: &JsonValue) -> JsonValue {
+	match rule.get("additionalProperties") {
+		Some(values) if values.is_object() => values.clone(),
+		_ => rule.clone(),
+	}
+}
+
+/// AQL expression of the translation of `attribute` in the first locale of the chain having
+/// one, falling back to any translation. Values stored before the field was translatable are
+/// returned as they are
+pub fn get_translation_expression(variable: &str, attribute: &str, chain: &[String]) -> String {
+	format!(
+		"FIRST(LET t = {}[{}] RETURN IS_OBJECT(t) ? FIRST(APPEND((FOR l IN {} FILTER t[l] != null RETURN t[l]), VALUES(t))) : t)",
+		variable,
+		serde_json::to_string(attribute).unwrap(),
+		serde_json::to_string(chain).unwrap()
+	)
+}
+
+/// Stores the values given for the translatable fields under the locale, the other locales of
+/// the field being kept on update
+pub fn localize_values(object: &mut JsonMap<String, JsonValue>, fields: &[&str], locale: &str) {
+	for field in fields {
+		if let Some(value) = object.get_mut(*field) {
+			if !value.is_null() {
+				*value = json!({ locale: value.take() });
+			}
+		}
+	}
+}
@@ -0,0 +1,195 @@
+use actix_web::http::header::HeaderMap;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::time::Duration;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+use crate::lib::CONFIG;
+
+/// Exports the spans to the OTLP endpoint, when one is configured
+fn get_otel_tracer() -> Option<trace::Tracer> {
+	let endpoint = CONFIG.get_otlp_endpoint()?;
+
+	opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+	let tracer = opentelemetry_otlp::new_pipeline()
+		.tracing()
+		.with_exporter(
+			opentelemetry_otlp::new_exporter()
+				.tonic()
+				.with_endpoint(endpoint),
+		)
+		.with_trace_config(
+			trace::config().with_resource(Resource::new(vec![KeyValue::new(
+				"service.name",
+				CONFIG.get_otel_service_name(),
+			)])),
+		)
+		.install_batch(runtime::Tokio);
+
+	match tracer {
+		Ok(tracer) => Some(tracer),
+		Err(e) => {
+			eprintln!("Error exporting traces to {}: {}", endpoint, e);
+
+			None
+		}
+	}
+}
+
+/// Prints the events of the levels enabled by `LOG_LEVEL` with the spans they happened in, as
+/// JSON lines when `LOG_FORMAT` is `json`. The spans are exported as traces as well when
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+pub fn init_logging() {
+	let filter = EnvFilter::try_new(CONFIG.get_log_level()).unwrap_or_else(|e| {
+		eprintln!("Invalid LOG_LEVEL {}: {}", CONFIG.get_log_level(), e);
+
+		EnvFilter::new("info")
+	});
+
+	let registry = tracing_subscriber::registry()
+		.with(filter)
+		.with(get_otel_tracer().map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)));
+
+	if CONFIG.is_log_json() {
+		registry
+			.with(
+				fmt::layer()
+					.json()
+					.with_current_span(true)
+					.with_span_list(true),
+			)
+			.init();
+	} else {
+		registry.with(fmt::layer()).init();
+	}
+}
+
+/// Exports the spans left before exiting
+pub fn shutdown_logging() {
+	if CONFIG.get_otlp_endpoint().is_some() {
+		opentelemetry::global::shutdown_tracer_provider();
+	}
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+	fn get(&self, key: &str) -> Option<&str> {
+		self.0.get(key).and_then(|v| v.to_str().ok())
+	}
+
+	fn keys(&self) -> Vec<&str> {
+		self.0.keys().map(|k| k.as_str()).collect()
+	}
+}
+
+/// Continues the trace of the `traceparent` header of the request in the span
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+	let context = opentelemetry::global::get_text_map_propagator(|propagator| {
+		propagator.extract(&HeaderExtractor(headers))
+	});
+
+	span.set_parent(context);
+}
+
+/// Bind variables as they are logged, only the collection names are kept since the values can
+/// hold user data or the claims of the session
+fn redact_bind_vars(bind_vars: &[(String, JsonValue)]) -> String {
+	let redacted: JsonMap<String, JsonValue> = bind_vars
+		.iter()
+		.map(|(k, v)| match k.starts_with('@') {
+			true => (k.clone(), v.clone()),
+			false => (k.clone(), JsonValue::String("<redacted>".to_string())),
+		})
+		.collect();
+
+	JsonValue::Object(redacted).to_string()
+}
+
+/// AQL with its string and number literals replaced by `?`, the values of filters are written
+/// in the query itself
+fn sanitize_aql(aql: &str) -> String {
+	let mut sanitized = String::with_capacity(aql.len());
+	let mut chars = aql.chars().peekable();
+	let mut previous: Option<char> = None;
+
+	while let Some(c) = chars.next() {
+		match c {
+			'"' | '\'' => {
+				let mut escaped = false;
+
+				for next in chars.by_ref() {
+					match next {
+						_ if escaped => escaped = false,
+						'\\' => escaped = true,
+						_ if next == c => break,
+						_ => (),
+					}
+				}
+
+				sanitized.push('?');
+			}
+			'0'..='9' if !previous.map_or(false, |p| p.is_alphanumeric() || p == '_') => {
+				while chars
+					.peek()
+					.map_or(false, |n| n.is_ascii_digit() || *n == '.')
+				{
+					chars.next();
+				}
+
+				sanitized.push('?');
+			}
+			_ => sanitized.push(c),
+		}
+
+		previous = Some(c);
+	}
+
+	sanitized
+}
+
+/// Logs a query that took longer than `SLOW_QUERY_THRESHOLD` with its AQL, so it can be
+/// explained offline
+pub fn log_slow_query(aql: &str, bind_vars: &[(String, JsonValue)], elapsed: Duration) {
+	if CONFIG
+		.get_slow_query_threshold()
+		.map_or(false, |threshold| elapsed >= threshold)
+	{
+		tracing::warn!(
+			aql,
+			bind_vars = %redact_bind_vars(bind_vars),
+			elapsed = ?elapsed,
+			"Slow query"
+		);
+	}
+}
+
+/// Logs the use of a deprecated operation or field by an operation, so its removal can be
+/// planned once it's no longer used
+pub fn log_deprecated_usage(operation: &str, member: &str, reason: &str, role: &str) {
+	tracing::warn!(operation, member, reason, role, "Deprecated member used");
+}
+
+/// Span of an AQL query sent to ArangoDB, the query is logged at the debug level and its
+/// sanitized statement recorded on the span
+pub fn query_span(aql: &str, bind_vars: &[(String, JsonValue)]) -> Span {
+	let span = tracing::info_span!(
+		"aql",
+		otel.kind = "client",
+		db.system = "arangodb",
+		db.statement = sanitize_aql(aql).as_str(),
+	);
+
+	span.in_scope(|| tracing::debug!(aql, bind_vars = %redact_bind_vars(bind_vars), "AQL query"));
+
+	span
+}
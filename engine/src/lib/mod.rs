@@ -1,5 +1,17 @@
+pub mod audit;
 pub mod database;
+pub mod events;
+pub mod images;
+pub mod locales;
+pub mod logging;
+pub mod naming;
+pub mod plugins;
+pub mod publication;
+pub mod revisions;
+pub mod scheduler;
 pub mod schema;
+pub mod storage;
+pub mod tls;
 
 pub mod config;
 pub use config::CONFIG;
@@ -0,0 +1,41 @@
+use convert_case::{Case, Casing};
+
+use crate::lib::CONFIG;
+
+/// Name of the type of a collection, e.g. `User` for `users`, prefixed by `SCHEMA_PREFIX`
+pub fn get_type_name(collection: &str) -> String {
+	let name = collection.to_case(Case::Pascal);
+
+	format!(
+		"{}{}",
+		CONFIG.get_schema_prefix(),
+		match CONFIG.is_singular_type_names() {
+			true => pluralizer::pluralize(name.as_str(), 1, false),
+			false => name,
+		}
+	)
+}
+
+/// Name of an operation on one or several documents of an entity, e.g. `getUser` and
+/// `getUsers` by default. Lists are named after the plural of the entity unless `LIST_SUFFIX`
+/// is set, e.g. `getUserList`
+pub fn get_operation_name(verb: &str, entity: &str, list: bool) -> String {
+	let name = match (list, CONFIG.get_list_suffix()) {
+		(true, Some(suffix)) => format!("{}{}", entity, suffix),
+		(true, None) => pluralizer::pluralize(entity, 2, false),
+		(false, _) => entity.to_string(),
+	};
+
+	format_operation_name(verb, &name)
+}
+
+/// Name of an operation in the `OPERATION_CASE`, from the `OPERATION_TEMPLATE` filled with
+/// its verb and the name it's for
+pub fn format_operation_name(verb: &str, name: &str) -> String {
+	let name = CONFIG
+		.get_operation_template()
+		.replace("{verb}", verb)
+		.replace("{name}", &name.to_case(Case::Pascal));
+
+	name.to_case(CONFIG.get_operation_case())
+}
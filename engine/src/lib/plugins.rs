@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Error};
+use chrono::{SecondsFormat, Utc};
+use lazy_static::lazy_static;
+use rust_arango::AqlQuery;
+use serde_json::{json, value::Value as JsonValue};
+use sha2::{Digest, Sha256};
+use wasmtime::{
+	Config as WasmConfig, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+use crate::lib::database::DATABASE;
+use crate::lib::CONFIG;
+
+const PLUGINS_COLLECTION: &str = "alchemy_plugins";
+
+lazy_static! {
+	/// Plugins run out of fuel once they executed about as many instructions as allowed
+	static ref ENGINE: Engine =
+		Engine::new(WasmConfig::new().consume_fuel(true)).expect("Error creating the WASM engine");
+
+	/// Compiled plugins by name, with the hash of the module they were compiled from
+	static ref MODULES: RwLock<HashMap<String, (String, Module)>> = RwLock::new(HashMap::new());
+}
+
+/// Get the plugins without their modules
+pub async fn get_all_plugins() -> Vec<JsonValue> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(PLUGINS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query("FOR p IN @@collection SORT p.name RETURN UNSET(p, '_id', '_key', '_rev', 'wasm')")
+		.bind_var("@collection", PLUGINS_COLLECTION)
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+fn compile(wasm: &[u8]) -> Result<Module, Error> {
+	let module = Module::new(&ENGINE, wasm)?;
+
+	for export in ["memory", "alloc", "handle"] {
+		if module.get_export(export).is_none() {
+			return Err(anyhow!("Plugin module doesn't export {}", export));
+		}
+	}
+
+	Ok(module)
+}
+
+/// Creates the plugin from a WASM module, replacing the one with the same name. The module
+/// exports its `memory`, `alloc(len: i32) -> i32` for the input and `handle(ptr: i32,
+/// len: i32) -> i64` returning the pointer and length of the output in the high and low bits
+pub async fn save_plugin(name: &str, wasm: Vec<u8>) -> Result<(), Error> {
+	let valid_name = !name.is_empty()
+		&& name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+
+	if !valid_name {
+		return Err(anyhow!("Invalid plugin name {}", name));
+	}
+
+	let hash = format!("{:x}", Sha256::digest(&wasm));
+	let size = wasm.len();
+
+	let module = actix_web::rt::task::spawn_blocking({
+		let wasm = wasm.clone();
+
+		move || compile(&wasm)
+	})
+	.await??;
+
+	let db = DATABASE.get().await.database();
+
+	if db.collection(PLUGINS_COLLECTION).await.is_err() {
+		db.create_collection(PLUGINS_COLLECTION).await?;
+	}
+
+	let plugin = json!({
+		"name": name,
+		"hash": hash,
+		"size": size,
+		"updatedAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+		"wasm": base64::encode(&wasm),
+	});
+
+	let query = AqlQuery::builder()
+		.query(
+			"UPSERT { name: @plugin.name }
+				INSERT @plugin
+				REPLACE @plugin
+				IN @@collection",
+		)
+		.bind_var("@collection", PLUGINS_COLLECTION)
+		.bind_var("plugin", plugin)
+		.build();
+
+	let _: Vec<JsonValue> = db.aql_query(query).await?;
+
+	MODULES
+		.write()
+		.unwrap()
+		.insert(name.to_string(), (hash, module));
+
+	Ok(())
+}
+
+pub async fn delete_plugin(name: &str) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(PLUGINS_COLLECTION).await.is_err() {
+		return Err(anyhow!("Plugin {} not found", name));
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR p IN @@collection
+				FILTER p.name == @name
+				REMOVE p IN @@collection
+				RETURN 1",
+		)
+		.bind_var("@collection", PLUGINS_COLLECTION)
+		.bind_var("name", name)
+		.build();
+
+	let removed: Vec<JsonValue> = db.aql_query(query).await?;
+
+	MODULES.write().unwrap().remove(name);
+
+	match removed.is_empty() {
+		true => Err(anyhow!("Plugin {} not found", name)),
+		false => Ok(()),
+	}
+}
+
+/// Compiled module of the plugin, compiling it again when it was replaced since
+async fn get_module(name: &str, hash: &str) -> Result<Module, Error> {
+	if let Some((cached_hash, module)) = MODULES.read().unwrap().get(name) {
+		if cached_hash == hash {
+			return Ok(module.clone());
+		}
+	}
+
+	let query = AqlQuery::builder()
+		.query("FOR p IN @@collection FILTER p.name == @name LIMIT 1 RETURN p")
+		.bind_var("@collection", PLUGINS_COLLECTION)
+		.bind_var("name", name)
+		.build();
+
+	let plugins: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	let plugin = plugins
+		.into_iter()
+		.next()
+		.ok_or_else(|| anyhow!("Plugin {} not found", name))?;
+
+	let wasm = base64::decode(plugin["wasm"].as_str().unwrap_or_default())?;
+	let module = actix_web::rt::task::spawn_blocking(move || compile(&wasm)).await??;
+
+	MODULES.write().unwrap().insert(
+		name.to_string(),
+		(
+			plugin["hash"].as_str().unwrap_or_default().to_string(),
+			module.clone(),
+		),
+	);
+
+	Ok(module)
+}
+
+/// Runs the module in a new instance limited in fuel and memory, returning its output
+fn execute(module: &Module, input: &[u8]) -> Result<Vec<u8>, Error> {
+	let limits = StoreLimitsBuilder::new()
+		.memory_size(CONFIG.get_plugin_memory_limit())
+		.instances(1)
+		.build();
+
+	let mut store = Store::new(&ENGINE, limits);
+
+	store.limiter(|limits: &mut StoreLimits| limits);
+	store.set_fuel(CONFIG.get_plugin_fuel())?;
+
+	// Plugins can't import anything, they only transform their input
+	let instance = Linker::<StoreLimits>::new(&ENGINE).instantiate(&mut store, module)?;
+
+	let memory = instance
+		.get_memory(&mut store, "memory")
+		.ok_or_else(|| anyhow!("Plugin doesn't export its memory"))?;
+	let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+	let handle = instance.get_typed_func::<(i32, i32), i64>(&mut store, "handle")?;
+
+	let input_ptr = alloc.call(&mut store, input.len() as i32)?;
+
+	memory.write(&mut store, input_ptr as u32 as usize, input)?;
+
+	let result = handle.call(&mut store, (input_ptr, input.len() as i32))?;
+
+	let mut output = vec![0; result as u32 as usize];
+
+	memory.read(&store, (result >> 32) as u32 as usize, &mut output)?;
+
+	Ok(output)
+}
+
+/// Calls the `handle` function of the plugin with the JSON input, returning its JSON output
+pub async fn call_plugin(name: &str, hash: &str, input: &JsonValue) -> Result<JsonValue, Error> {
+	let module = get_module(name, hash).await?;
+	let input = serde_json::to_vec(input)?;
+
+	let output = actix_web::rt::task::spawn_blocking(move || execute(&module, &input))
+		.await?
+		.map_err(|e| anyhow!("Plugin {} failed: {}", name, e))?;
+
+	serde_json::from_slice(&output).map_err(|e| anyhow!("Invalid output of plugin {}: {}", name, e))
+}
+
+/// Checks that exactly one of the URL or the plugin handles an action or a hook
+pub async fn check_handler(url: &Option<String>, plugin: &Option<String>) -> Result<(), Error> {
+	match (url, plugin) {
+		(Some(url), None) => match url::Url::parse(url) {
+			Ok(_) => Ok(()),
+			Err(_) => Err(anyhow!("Invalid handler URL {}", url)),
+		},
+		(None, Some(plugin)) => {
+			let exists = get_all_plugins()
+				.await
+				.iter()
+				.any(|p| p["name"] == plugin.as_str());
+
+			match exists {
+				true => Ok(()),
+				false => Err(anyhow!("Plugin {} not found", plugin)),
+			}
+		}
+		_ => Err(anyhow!("Set either a URL or a plugin as handler")),
+	}
+}
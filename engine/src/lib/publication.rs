@@ -0,0 +1,29 @@
+use crate::lib::database::schema::SchemaPropertyType;
+use crate::lib::schema::{SchemaDocumentProperty, SchemaDocumentPropertyValues};
+
+/// Attribute holding the publication status of the documents of collections with publication
+pub const STATUS_FIELD: &str = "status";
+
+/// Status of new documents, only read with `preview: true`
+pub const DRAFT_STATUS: &str = "draft";
+/// Status of the documents read by default
+pub const PUBLISHED_STATUS: &str = "published";
+pub const ARCHIVED_STATUS: &str = "archived";
+
+/// Enum field added to collections when their publication is enabled
+pub fn get_status_property() -> SchemaDocumentProperty {
+	SchemaDocumentProperty {
+		name: STATUS_FIELD.to_string(),
+		values: SchemaDocumentPropertyValues {
+			r#type: SchemaPropertyType::Enum,
+			r#enum: Some(
+				[DRAFT_STATUS, PUBLISHED_STATUS, ARCHIVED_STATUS]
+					.iter()
+					.map(|s| s.to_string())
+					.collect(),
+			),
+			..Default::default()
+		},
+		required: false,
+	}
+}
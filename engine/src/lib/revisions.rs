@@ -0,0 +1,169 @@
+use anyhow::Error;
+use chrono::{SecondsFormat, Utc};
+use rust_arango::index::{Index, IndexSettings};
+use rust_arango::{AqlQuery, ClientError};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::lib::database::schema::Versioning;
+use crate::lib::database::DATABASE;
+use crate::lib::events::EventOperation;
+
+/// Collection the prior versions of the documents of `collection` are kept in
+pub fn get_revisions_collection(collection: &str) -> String {
+	format!("{}_revisions", collection)
+}
+
+/// Creates the revisions collection of a versioned collection, with an expiry index removing
+/// the revisions older than `max_age_days` when it's set
+pub async fn init_revisions(collection: &str, versioning: &Versioning) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+	let name = get_revisions_collection(collection);
+
+	if db.collection(&name).await.is_err() {
+		db.create_collection(&name).await?;
+
+		let index = Index::builder()
+			.fields(vec!["document".to_string(), "timestamp".to_string()])
+			.settings(IndexSettings::Persistent {
+				unique: false,
+				sparse: false,
+				deduplicate: false,
+			})
+			.build();
+
+		db.create_index(&name, &index).await?;
+	}
+
+	for index in db.indexes(&name).await?.indexes {
+		if let IndexSettings::Ttl { .. } = index.settings {
+			db.delete_index(&index.id).await?;
+		}
+	}
+
+	if let Some(days) = versioning.max_age_days {
+		let index = Index::builder()
+			.fields(vec!["timestamp".to_string()])
+			.settings(IndexSettings::Ttl {
+				expire_after: days * 24 * 60 * 60,
+			})
+			.build();
+
+		db.create_index(&name, &index).await?;
+	}
+
+	Ok(())
+}
+
+/// Records the documents as they were before an update or a delete, `changes` being the `old`
+/// documents returned by it. Revisions past `max_revisions` are removed, the oldest first
+pub async fn record_revisions(
+	collection: &str,
+	versioning: &Versioning,
+	operation: EventOperation,
+	changes: &[JsonValue],
+	user: Option<&str>,
+) -> Result<(), Error> {
+	let revisions: Vec<JsonValue> = changes
+		.iter()
+		.filter(|change| change["old"].is_object())
+		.map(|change| {
+			json!({
+				"document": change["_key"],
+				"operation": operation,
+				"user": user,
+				"timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+				"data": strip_system_attributes(&change["old"]),
+			})
+		})
+		.collect();
+
+	if revisions.is_empty() {
+		return Ok(());
+	}
+
+	let keys: Vec<JsonValue> = revisions.iter().map(|r| r["document"].clone()).collect();
+
+	let query = AqlQuery::builder()
+		.query("FOR r IN @revisions INSERT r INTO @@collection")
+		.bind_var("@collection", get_revisions_collection(collection))
+		.bind_var("revisions", JsonValue::Array(revisions))
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	if let Some(max_revisions) = versioning.max_revisions {
+		let query = AqlQuery::builder()
+			.query(
+				"FOR key IN @keys
+					FOR r IN @@collection
+						FILTER r.document == key
+						SORT r.timestamp DESC
+						LIMIT @max_revisions, 1000000
+						REMOVE r IN @@collection",
+			)
+			.bind_var("@collection", get_revisions_collection(collection))
+			.bind_var("keys", keys)
+			.bind_var("max_revisions", max_revisions)
+			.build();
+
+		let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+	}
+
+	Ok(())
+}
+
+/// Changes bringing a document back to one of its revisions
+pub enum RevertChanges {
+	/// The document exists, the attributes added since the revision being set to null
+	Update(String, JsonValue),
+	/// The document was removed since, it's inserted again with the attributes of the revision
+	Insert(String, JsonValue),
+}
+
+/// Changes bringing the document back to the revision, with the key of the document. None
+/// when the revision isn't found
+pub async fn get_revert_changes(
+	collection: &str,
+	revision: &str,
+) -> Result<Option<RevertChanges>, ClientError> {
+	let query = AqlQuery::builder()
+		.query(
+			"FOR r IN @@revisions
+				FILTER r._key == @revision
+				LET doc = DOCUMENT(@@collection, r.document)
+				LET added = doc == null ? [] : ATTRIBUTES(doc, true)
+				RETURN {
+					document: r.document,
+					removed: doc == null,
+					changes: doc == null
+						? MERGE(r.data, { _key: r.document })
+						: MERGE(ZIP(added, added[* RETURN null]), r.data)
+				}",
+		)
+		.bind_var("@revisions", get_revisions_collection(collection))
+		.bind_var("@collection", collection)
+		.bind_var("revision", revision)
+		.build();
+
+	let reverts: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(reverts.into_iter().next().and_then(|r| {
+		let key = r["document"].as_str()?.to_string();
+		let changes = r["changes"].clone();
+
+		Some(match r["removed"].as_bool().unwrap_or_default() {
+			true => RevertChanges::Insert(key, changes),
+			false => RevertChanges::Update(key, changes),
+		})
+	}))
+}
+
+fn strip_system_attributes(document: &JsonValue) -> JsonValue {
+	let mut data = document.as_object().cloned().unwrap_or_else(JsonMap::new);
+
+	for attribute in ["_key", "_id", "_rev"] {
+		data.remove(attribute);
+	}
+
+	JsonValue::Object(data)
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use chrono::{DateTime, SecondsFormat, Utc};
+use cron::Schedule;
+use rust_arango::AqlQuery;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, to_value as toJsonValue, value::Value as JsonValue, Map as JsonMap};
+
+use crate::lib::database::DATABASE;
+
+const JOBS_COLLECTION: &str = "alchemy_cron_jobs";
+const RUNS_COLLECTION: &str = "alchemy_cron_runs";
+
+/// What a job does when it is due
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, GraphQLEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum CronAction {
+	/// POSTs the job name and the scheduled time to the URL
+	Webhook,
+	/// Executes the AQL query on the database
+	Aql,
+	/// Executes the GraphQL operation on the API as an admin
+	Graphql,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, GraphQLEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum CronRunStatus {
+	Running,
+	Succeeded,
+	Failed,
+}
+
+/// A job run on a cron schedule
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlchemyCronJob {
+	pub name: String,
+	/// Cron expression, with or without the leading seconds field
+	pub schedule: String,
+	pub action: CronAction,
+	/// URL of the webhook, or the AQL or GraphQL query
+	pub target: String,
+	/// Sent with the webhook requests
+	#[serde(default)]
+	pub headers: HashMap<String, String>,
+	/// Bind variables of the AQL query or variables of the GraphQL operation
+	#[serde(default)]
+	pub variables: JsonMap<String, JsonValue>,
+	pub enabled: bool,
+	/// When the job was last due, the next run is scheduled after it
+	#[serde(default)]
+	pub last_run_at: Option<String>,
+}
+
+fn now() -> String {
+	Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+async fn ensure_collection(name: &str) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(name).await.is_err() {
+		db.create_collection(name).await?;
+	}
+
+	Ok(())
+}
+
+/// Parses a cron expression, the classic five fields expression runs at the start of the minute
+pub fn parse_schedule(expression: &str) -> Result<Schedule, Error> {
+	let expression = match expression.split_whitespace().count() {
+		5 => format!("0 {}", expression),
+		_ => expression.to_string(),
+	};
+
+	Schedule::from_str(&expression).map_err(|e| anyhow!("Invalid schedule {}: {}", expression, e))
+}
+
+impl AlchemyCronJob {
+	/// When the job is due next, after its last run
+	pub fn get_next_run(&self) -> Option<DateTime<Utc>> {
+		let last_run = self
+			.last_run_at
+			.as_deref()
+			.and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+			.map(|t| t.with_timezone(&Utc))
+			.unwrap_or_else(Utc::now);
+
+		parse_schedule(&self.schedule).ok()?.after(&last_run).next()
+	}
+}
+
+pub async fn get_cron_jobs() -> Vec<AlchemyCronJob> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(JOBS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query("FOR j IN @@collection SORT j.name RETURN UNSET(j, '_id', '_key', '_rev')")
+		.bind_var("@collection", JOBS_COLLECTION)
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+pub async fn get_cron_job(name: &str) -> Option<AlchemyCronJob> {
+	get_cron_jobs().await.into_iter().find(|j| j.name == name)
+}
+
+/// Creates the job, replacing the one with the same name. New jobs are first due after
+/// they're created, replaced ones keep their last run
+pub async fn save_cron_job(job: AlchemyCronJob) -> Result<(), Error> {
+	parse_schedule(&job.schedule)?;
+
+	if job.action == CronAction::Webhook && url::Url::parse(&job.target).is_err() {
+		return Err(anyhow!("Invalid webhook URL {}", job.target));
+	}
+
+	ensure_collection(JOBS_COLLECTION).await?;
+
+	let query = AqlQuery::builder()
+		.query(
+			"UPSERT { name: @job.name }
+				INSERT MERGE(@job, { lastRunAt: @now })
+				REPLACE MERGE(@job, { lastRunAt: OLD.lastRunAt })
+				IN @@collection",
+		)
+		.bind_var("@collection", JOBS_COLLECTION)
+		.bind_var("job", toJsonValue(&job)?)
+		.bind_var("now", now())
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(())
+}
+
+pub async fn delete_cron_job(name: &str) -> Result<(), Error> {
+	ensure_collection(JOBS_COLLECTION).await?;
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR j IN @@collection
+				FILTER j.name == @name
+				REMOVE j IN @@collection
+				RETURN 1",
+		)
+		.bind_var("@collection", JOBS_COLLECTION)
+		.bind_var("name", name)
+		.build();
+
+	let removed: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	match removed.is_empty() {
+		true => Err(anyhow!("Cron job {} not found", name)),
+		false => Ok(()),
+	}
+}
+
+/// Enabled jobs that are due, with the time they were due. Each one is claimed by moving its
+/// last run, so it only runs once when several servers share the database
+pub async fn claim_due_cron_jobs() -> Result<Vec<(AlchemyCronJob, String)>, Error> {
+	let db = DATABASE.get().await.database();
+	let now = Utc::now();
+
+	let mut due = Vec::new();
+
+	for job in get_cron_jobs().await.into_iter().filter(|j| j.enabled) {
+		let scheduled_at = match job.get_next_run() {
+			Some(next_run) if next_run <= now => next_run,
+			_ => continue,
+		};
+
+		// Missed runs are skipped, the job runs once and is next due after now
+		let query = AqlQuery::builder()
+			.query(
+				"FOR j IN @@collection
+					FILTER j.name == @name AND j.lastRunAt == @lastRunAt
+					UPDATE j WITH { lastRunAt: @now } IN @@collection
+					RETURN 1",
+			)
+			.bind_var("@collection", JOBS_COLLECTION)
+			.bind_var("name", job.name.as_str())
+			.bind_var("lastRunAt", job.last_run_at.clone())
+			.bind_var("now", now.to_rfc3339_opts(SecondsFormat::Millis, true))
+			.build();
+
+		let claimed: Vec<JsonValue> = db.aql_query(query).await?;
+
+		if !claimed.is_empty() {
+			due.push((
+				job,
+				scheduled_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+			));
+		}
+	}
+
+	Ok(due)
+}
+
+/// Records a run of the job as running, returning its key
+pub async fn start_cron_run(job: &str, scheduled_at: &str) -> Result<String, Error> {
+	ensure_collection(RUNS_COLLECTION).await?;
+
+	let query = AqlQuery::builder()
+		.query("INSERT @run INTO @@collection RETURN NEW._key")
+		.bind_var("@collection", RUNS_COLLECTION)
+		.bind_var(
+			"run",
+			json!({
+				"job": job,
+				"status": CronRunStatus::Running,
+				"scheduledAt": scheduled_at,
+				"startedAt": now(),
+			}),
+		)
+		.build();
+
+	let keys: Vec<String> = DATABASE.get().await.database().aql_query(query).await?;
+
+	keys.into_iter()
+		.next()
+		.ok_or_else(|| anyhow!("Error creating the cron run"))
+}
+
+/// Records the output of the run, or why it failed
+pub async fn finish_cron_run(key: &str, result: Result<String, Error>) {
+	let changes = match result {
+		Ok(output) => json!({
+			"status": CronRunStatus::Succeeded,
+			"finishedAt": now(),
+			"output": output,
+		}),
+		Err(e) => json!({
+			"status": CronRunStatus::Failed,
+			"finishedAt": now(),
+			"error": e.to_string(),
+		}),
+	};
+
+	let query = AqlQuery::builder()
+		.query("UPDATE @key WITH @changes IN @@collection")
+		.bind_var("@collection", RUNS_COLLECTION)
+		.bind_var("key", key)
+		.bind_var("changes", changes)
+		.build();
+
+	let _: Result<Vec<JsonValue>, _> = DATABASE.get().await.database().aql_query(query).await;
+}
+
+/// Runs of every job or of the given one, the latest first
+pub async fn get_cron_runs(job: Option<String>, limit: Option<u32>) -> Vec<JsonValue> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(RUNS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR r IN @@collection
+				FILTER @job == null OR r.job == @job
+				SORT r.startedAt DESC
+				LIMIT @limit
+				RETURN r",
+		)
+		.bind_var("@collection", RUNS_COLLECTION)
+		.bind_var("job", job)
+		.bind_var("limit", limit.unwrap_or(u32::MAX))
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Error};
+use rust_arango::AqlQuery;
+use serde::{Deserialize, Serialize};
+use serde_json::{to_value as toJsonValue, value::Value as JsonValue};
+
+use crate::lib::database::DATABASE;
+use crate::lib::plugins::check_handler;
+
+const ACTIONS_COLLECTION: &str = "alchemy_actions";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, GraphQLEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum SchemaActionKind {
+	Query,
+	Mutation,
+}
+
+/// Custom field of the API resolved by an HTTP endpoint or a plugin, e.g. `placeOrder`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlchemyActionEntry {
+	pub name: String,
+	pub kind: SchemaActionKind,
+	/// Receives the arguments and the session as a JSON POST, and responds with the result
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub url: Option<String>,
+	/// Plugin resolving the action in-process instead of the URL
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub plugin: Option<String>,
+	#[serde(default)]
+	pub headers: HashMap<String, String>,
+	/// JSON schema `properties` and `required` of the arguments
+	pub arguments: JsonValue,
+	/// JSON schema of the result
+	pub output: JsonValue,
+	/// Roles allowed to execute the action, every authenticated role when empty
+	#[serde(default)]
+	pub roles: Vec<String>,
+}
+
+/// Get the entries of all of the actions
+pub async fn get_all_actions() -> Vec<JsonValue> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(ACTIONS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query("FOR a IN @@collection SORT a.name RETURN UNSET(a, '_id', '_key', '_rev')")
+		.bind_var("@collection", ACTIONS_COLLECTION)
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+/// Creates the action, replacing the one with the same name
+pub async fn save_action(action: AlchemyActionEntry) -> Result<(), Error> {
+	let valid_name = action
+		.name
+		.chars()
+		.next()
+		.map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+		&& action
+			.name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+	if !valid_name || action.name.starts_with("__") {
+		return Err(anyhow!("Invalid action name {}", action.name));
+	}
+
+	check_handler(&action.url, &action.plugin).await?;
+
+	let db = DATABASE.get().await.database();
+
+	if db.collection(ACTIONS_COLLECTION).await.is_err() {
+		db.create_collection(ACTIONS_COLLECTION).await?;
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"UPSERT { name: @action.name }
+				INSERT @action
+				REPLACE @action
+				IN @@collection",
+		)
+		.bind_var("@collection", ACTIONS_COLLECTION)
+		.bind_var("action", toJsonValue(&action)?)
+		.build();
+
+	let _: Vec<JsonValue> = db.aql_query(query).await?;
+
+	Ok(())
+}
+
+pub async fn delete_action(name: &str) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(ACTIONS_COLLECTION).await.is_err() {
+		return Err(anyhow!("Action {} not found", name));
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR a IN @@collection
+				FILTER a.name == @name
+				REMOVE a IN @@collection
+				RETURN 1",
+		)
+		.bind_var("@collection", ACTIONS_COLLECTION)
+		.bind_var("name", name)
+		.build();
+
+	let removed: Vec<JsonValue> = db.aql_query(query).await?;
+
+	match removed.is_empty() {
+		true => Err(anyhow!("Action {} not found", name)),
+		false => Ok(()),
+	}
+}
@@ -0,0 +1,419 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Error};
+use chrono::{SecondsFormat, Utc};
+use rust_arango::AqlQuery;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, to_value as toJsonValue, value::Value as JsonValue};
+
+use crate::lib::database::DATABASE;
+use crate::lib::schema::{
+	delete_edge_entries, delete_entry, export_schema, get_all_graphs, import_schema, migrate,
+	SchemaExport,
+};
+
+const BACKUPS_COLLECTION: &str = "alchemy_backups";
+const SCHEMA_FILE: &str = "schema.json";
+const BACKUP_BATCH_SIZE: u32 = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, GraphQLEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupOperation {
+	Backup,
+	Restore,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, GraphQLEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupStatus {
+	Running,
+	Completed,
+	Failed,
+}
+
+/// A backup or restore running in the background, updated as collections are processed
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlchemyBackupJob {
+	pub backup: String,
+	pub operation: BackupOperation,
+	pub status: BackupStatus,
+	pub started_at: String,
+	#[serde(default)]
+	pub finished_at: Option<String>,
+	#[serde(default)]
+	pub total_collections: usize,
+	#[serde(default)]
+	pub processed_collections: usize,
+	#[serde(default)]
+	pub documents: usize,
+	#[serde(default)]
+	pub error: Option<String>,
+}
+
+fn now() -> String {
+	Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Backups are directories of the backup directory, names can't point outside of it
+fn get_backup_path(directory: &str, backup: &str) -> Result<PathBuf, Error> {
+	let valid = !backup.is_empty()
+		&& !backup.starts_with('.')
+		&& backup
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+	if !valid {
+		return Err(anyhow!("Invalid backup name {}", backup));
+	}
+
+	Ok(Path::new(directory).join(backup))
+}
+
+/// Names of the backups in the directory, the latest first
+pub fn list_backups(directory: &str) -> Result<Vec<String>, Error> {
+	let mut backups = Vec::new();
+
+	for entry in std::fs::read_dir(directory)? {
+		let path = entry?.path();
+
+		if path.join(SCHEMA_FILE).is_file() {
+			if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+				backups.push(name.to_string());
+			}
+		}
+	}
+
+	backups.sort();
+	backups.reverse();
+
+	Ok(backups)
+}
+
+/// Get the backup and restore jobs, the latest first
+pub async fn get_backup_jobs(limit: Option<u32>) -> Vec<JsonValue> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(BACKUPS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR j IN @@collection
+				SORT j.startedAt DESC
+				LIMIT @limit
+				RETURN j",
+		)
+		.bind_var("@collection", BACKUPS_COLLECTION)
+		.bind_var("limit", limit.unwrap_or(u32::MAX))
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+/// Records a job as running, returning its key
+pub async fn create_backup_job(backup: &str, operation: BackupOperation) -> Result<String, Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(BACKUPS_COLLECTION).await.is_err() {
+		db.create_collection(BACKUPS_COLLECTION).await?;
+	}
+
+	let job = AlchemyBackupJob {
+		backup: backup.to_string(),
+		operation,
+		status: BackupStatus::Running,
+		started_at: now(),
+		finished_at: None,
+		total_collections: 0,
+		processed_collections: 0,
+		documents: 0,
+		error: None,
+	};
+
+	let query = AqlQuery::builder()
+		.query("INSERT @job INTO @@collection RETURN NEW._key")
+		.bind_var("@collection", BACKUPS_COLLECTION)
+		.bind_var("job", toJsonValue(&job)?)
+		.build();
+
+	let keys: Vec<String> = db.aql_query(query).await?;
+
+	keys.into_iter()
+		.next()
+		.ok_or_else(|| anyhow!("Error creating the backup job"))
+}
+
+async fn update_job(key: &str, changes: JsonValue) {
+	let query = AqlQuery::builder()
+		.query("UPDATE @key WITH @changes IN @@collection")
+		.bind_var("@collection", BACKUPS_COLLECTION)
+		.bind_var("key", key)
+		.bind_var("changes", changes)
+		.build();
+
+	let _: Result<Vec<JsonValue>, _> = DATABASE.get().await.database().aql_query(query).await;
+}
+
+async fn finish_job(key: &str, result: Result<(), Error>) {
+	let changes = match result {
+		Ok(_) => json!({ "status": BackupStatus::Completed, "finishedAt": now() }),
+		Err(e) => json!({
+			"status": BackupStatus::Failed,
+			"finishedAt": now(),
+			"error": e.to_string(),
+		}),
+	};
+
+	update_job(key, changes).await;
+}
+
+/// Collections holding the documents of the schema, edge collections last
+fn get_backup_collections(export: &SchemaExport) -> Vec<String> {
+	let names = export
+		.collections
+		.iter()
+		.filter_map(|c| c["name"].as_str())
+		.chain(
+			export
+				.relationships
+				.iter()
+				.filter_map(|r| r["edge"].as_str()),
+		);
+
+	let mut collections: Vec<String> = Vec::new();
+
+	for name in names {
+		if !collections.iter().any(|c| c == name) {
+			collections.push(name.to_string());
+		}
+	}
+
+	collections
+}
+
+/// Writes the documents of the collection as JSON lines, a batch at a time
+async fn dump_collection(collection: &str, path: &Path) -> Result<usize, Error> {
+	let db = DATABASE.get().await.database();
+
+	let mut writer = BufWriter::new(File::create(path)?);
+	let mut count = 0;
+
+	let query = AqlQuery::builder()
+		.query("FOR d IN @@collection RETURN UNSET(d, '_id', '_rev')")
+		.bind_var("@collection", collection)
+		.batch_size(BACKUP_BATCH_SIZE)
+		.build();
+
+	let mut cursor = db.aql_query_batch::<JsonValue>(query).await?;
+
+	loop {
+		for document in &cursor.result {
+			writeln!(writer, "{}", document)?;
+		}
+
+		count += cursor.result.len();
+
+		match cursor.id.filter(|_| cursor.more) {
+			Some(id) => cursor = db.aql_next_batch(id.as_str()).await?,
+			None => break,
+		}
+	}
+
+	writer.flush()?;
+
+	Ok(count)
+}
+
+async fn insert_documents(collection: &str, documents: Vec<JsonValue>) -> Result<(), Error> {
+	let query = AqlQuery::builder()
+		.query("FOR d IN @documents INSERT d INTO @@collection")
+		.bind_var("@collection", collection)
+		.bind_var("documents", JsonValue::Array(documents))
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(())
+}
+
+/// Inserts the documents of a collection file in batches
+async fn load_collection(collection: &str, path: &Path) -> Result<usize, Error> {
+	let reader = BufReader::new(File::open(path)?);
+
+	let mut documents = Vec::new();
+	let mut count = 0;
+
+	for line in reader.lines() {
+		let line = line?;
+
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		documents.push(serde_json::from_str(&line)?);
+
+		if documents.len() >= BACKUP_BATCH_SIZE as usize {
+			count += documents.len();
+			insert_documents(collection, std::mem::take(&mut documents)).await?;
+		}
+	}
+
+	if !documents.is_empty() {
+		count += documents.len();
+		insert_documents(collection, documents).await?;
+	}
+
+	Ok(count)
+}
+
+async fn backup(key: &str, path: PathBuf) -> Result<(), Error> {
+	let export = export_schema().await?;
+	let collections = get_backup_collections(&export);
+
+	std::fs::create_dir_all(&path)?;
+	std::fs::write(
+		path.join(SCHEMA_FILE),
+		serde_json::to_string_pretty(&export)?,
+	)?;
+
+	update_job(key, json!({ "totalCollections": collections.len() })).await;
+
+	let mut documents = 0;
+
+	for (i, collection) in collections.iter().enumerate() {
+		documents +=
+			dump_collection(collection, &path.join(format!("{}.jsonl", collection))).await?;
+
+		update_job(
+			key,
+			json!({ "processedCollections": i + 1, "documents": documents }),
+		)
+		.await;
+	}
+
+	Ok(())
+}
+
+/// Dumps the schema and every document of the collections it declares into a new directory
+/// of the backup directory, tracking its progress in the job
+pub async fn run_backup(directory: String, backup_name: String, key: String) {
+	let result = match get_backup_path(&directory, &backup_name) {
+		Ok(path) if path.exists() => Err(anyhow!("Backup {} already exists", backup_name)),
+		Ok(path) => backup(&key, path).await,
+		Err(e) => Err(e),
+	};
+
+	finish_job(&key, result).await;
+}
+
+/// Drops the graphs, collections and entries of the backup, so it can be imported again
+async fn drop_backup_schema(export: &SchemaExport) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	let graphs = get_all_graphs().await;
+
+	for graph in &export.graphs {
+		if graphs
+			.iter()
+			.any(|g| g["_key"].as_str() == Some(graph.name.as_str()))
+		{
+			db.drop_graph(&graph.name, false).await?;
+		}
+	}
+
+	for collection in get_backup_collections(export) {
+		if db.collection(&collection).await.is_ok() {
+			db.drop_collection(&collection).await?;
+		}
+	}
+
+	for entry in &export.collections {
+		if let Some(name) = entry["name"].as_str() {
+			delete_entry(name.to_string()).await;
+		}
+	}
+
+	for entry in &export.relationships {
+		if let (Some(from), Some(name)) = (entry["from"].as_str(), entry["name"].as_str()) {
+			delete_edge_entries(from, Some(name)).await;
+		}
+	}
+
+	Ok(())
+}
+
+async fn restore(
+	key: &str,
+	path: PathBuf,
+	drop_existing: bool,
+	author: Option<String>,
+) -> Result<(), Error> {
+	let export: SchemaExport =
+		serde_json::from_str(&std::fs::read_to_string(path.join(SCHEMA_FILE))?)?;
+
+	let collections = get_backup_collections(&export);
+
+	update_job(key, json!({ "totalCollections": collections.len() })).await;
+
+	let name = path
+		.file_name()
+		.and_then(|n| n.to_str())
+		.unwrap_or_default()
+		.to_string();
+
+	migrate(
+		format!("restore backup {}", name),
+		author,
+		json!({ "restoreBackup": { "name": name, "dropExisting": drop_existing } }),
+		async {
+			if drop_existing {
+				drop_backup_schema(&export).await?;
+			}
+
+			import_schema(export).await
+		},
+	)
+	.await?;
+
+	let mut documents = 0;
+
+	for (i, collection) in collections.iter().enumerate() {
+		let file = path.join(format!("{}.jsonl", collection));
+
+		if file.is_file() {
+			documents += load_collection(collection, &file).await?;
+		}
+
+		update_job(
+			key,
+			json!({ "processedCollections": i + 1, "documents": documents }),
+		)
+		.await;
+	}
+
+	Ok(())
+}
+
+/// Recreates the schema of a backup and inserts its documents. Its collections must not
+/// exist unless `drop_existing` is set, which drops them with their documents first
+pub async fn run_restore(
+	directory: String,
+	backup_name: String,
+	key: String,
+	drop_existing: bool,
+	author: Option<String>,
+) {
+	let result = match get_backup_path(&directory, &backup_name) {
+		Ok(path) if !path.join(SCHEMA_FILE).is_file() => {
+			Err(anyhow!("Backup {} not found", backup_name))
+		}
+		Ok(path) => restore(&key, path, drop_existing, author).await,
+		Err(e) => Err(e),
+	};
+
+	finish_job(&key, result).await;
+}
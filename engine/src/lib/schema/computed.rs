@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lib::database::schema::{ComputedProperty, SchemaProperty};
+use crate::lib::schema::properties::SchemaDocumentPropertyValues;
+
+/// A read-only property computed when selected, e.g. `CONCAT(doc.first_name, " ", doc.last_name)`
+#[derive(Serialize, Deserialize, PartialEq, Clone, GraphQLInputObject)]
+pub struct SchemaComputedProperty {
+	pub name: String,
+	/// AQL expression, the document is available as `doc`
+	pub expression: String,
+	pub values: SchemaDocumentPropertyValues,
+}
+
+impl From<SchemaComputedProperty> for ComputedProperty {
+	fn from(property: SchemaComputedProperty) -> Self {
+		ComputedProperty {
+			name: property.name,
+			expression: property.expression,
+			schema: serde_json::to_value(SchemaProperty::from(property.values)).unwrap(),
+		}
+	}
+}
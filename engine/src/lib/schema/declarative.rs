@@ -0,0 +1,551 @@
+use std::fmt;
+
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use serde_json::{to_value as toJsonValue, value::Value as JsonValue};
+
+use crate::lib::database::arango::{
+	add_field, alter_field, create_collection, create_relationship, delete_collection,
+	delete_relationship, remove_field, set_collection_cache, set_collection_deprecation,
+	set_collection_description, set_collection_exposure, set_collection_publication,
+	set_collection_singleton, set_collection_ttl, set_collection_versioning,
+	set_translatable_fields, set_validation_level,
+};
+use crate::lib::database::schema::{
+	get_validation_level, ComputedProperty, Rule, SchemaOnDelete, SchemaProperty,
+	SchemaRelationshipType, SchemaValidationLevel, Ttl, Versioning,
+};
+use crate::lib::locales::{get_translatable_fields, unwrap_translatable_rule};
+use crate::lib::publication::STATUS_FIELD;
+use crate::lib::schema::properties::build_object_rule;
+use crate::lib::schema::{
+	get_all_collections, get_all_edges, get_entry, migrate, update_entry, SchemaCollectionSettings,
+	SchemaComputedProperty, SchemaDocumentProperty, SchemaTtl, SchemaVariant, SchemaVersioning,
+};
+
+/// The whole schema of the database, as declared in a YAML or JSON file
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct DeclaredSchema {
+	pub collections: Vec<DeclaredCollection>,
+	pub relationships: Vec<DeclaredRelationship>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclaredCollection {
+	pub name: String,
+	#[serde(default)]
+	pub properties: Vec<SchemaDocumentProperty>,
+	pub discriminator: Option<String>,
+	#[serde(default)]
+	pub variants: Vec<SchemaVariant>,
+	#[serde(default)]
+	pub computed: Vec<SchemaComputedProperty>,
+	pub ttl: Option<SchemaTtl>,
+	/// Seconds the API responses reading the collection are cached for
+	pub cache_max_age: Option<i32>,
+	pub versioning: Option<SchemaVersioning>,
+	/// Adds the `status` field and only reads published documents by default
+	#[serde(default)]
+	pub publication: bool,
+	/// Fields holding a value per locale
+	#[serde(default)]
+	pub translatable: Vec<String>,
+	/// Holds a single document, read and updated as a whole
+	#[serde(default)]
+	pub singleton: bool,
+	/// Marks the operations of the collection `@deprecated` with the reason
+	pub deprecation_reason: Option<String>,
+	pub description: Option<String>,
+	/// Whether the collection is in the API, it is unless `API_ALLOWLIST` is set
+	pub exposed: Option<bool>,
+	/// Only applied when creating the collection
+	pub settings: Option<SchemaCollectionSettings>,
+	pub validation_level: Option<SchemaValidationLevel>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclaredRelationship {
+	pub name: String,
+	pub from: String,
+	pub to: String,
+	pub relationship_type: SchemaRelationshipType,
+	pub edge: String,
+	#[serde(default)]
+	pub properties: Vec<SchemaDocumentProperty>,
+	pub inverse: Option<String>,
+	pub generate_inverse: Option<bool>,
+	pub on_delete: Option<SchemaOnDelete>,
+	pub targets: Option<Vec<String>>,
+	pub description: Option<String>,
+}
+
+/// A change bringing the stored schema to the declared one
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SchemaChange {
+	CreateCollection(DeclaredCollection),
+	DropCollection(String),
+	AddField(String, SchemaDocumentProperty),
+	AlterField(String, SchemaDocumentProperty),
+	RemoveField(String, String),
+	UpdateComputed(String, Vec<SchemaComputedProperty>),
+	SetTtl(String, Option<SchemaTtl>),
+	SetCache(String, Option<i32>),
+	SetVersioning(String, Option<SchemaVersioning>),
+	SetPublication(String, bool),
+	SetTranslatable(String, Vec<String>),
+	SetSingleton(String, bool),
+	SetDeprecation(String, Option<String>),
+	SetDescription(String, Option<String>),
+	SetExposure(String, Option<bool>),
+	SetValidationLevel(String, SchemaValidationLevel),
+	CreateRelationship(DeclaredRelationship),
+	DeleteRelationship(String, String),
+}
+
+impl fmt::Display for SchemaChange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SchemaChange::CreateCollection(c) => write!(f, "+ collection {}", c.name),
+			SchemaChange::DropCollection(name) => write!(f, "- collection {}", name),
+			SchemaChange::AddField(c, p) => write!(f, "+ field {}.{}", c, p.name),
+			SchemaChange::AlterField(c, p) => write!(f, "~ field {}.{}", c, p.name),
+			SchemaChange::RemoveField(c, name) => write!(f, "- field {}.{}", c, name),
+			SchemaChange::UpdateComputed(c, _) => write!(f, "~ computed properties of {}", c),
+			SchemaChange::SetTtl(c, _) => write!(f, "~ ttl of {}", c),
+			SchemaChange::SetCache(c, _) => write!(f, "~ cache of {}", c),
+			SchemaChange::SetVersioning(c, _) => write!(f, "~ versioning of {}", c),
+			SchemaChange::SetPublication(c, _) => write!(f, "~ publication of {}", c),
+			SchemaChange::SetTranslatable(c, _) => write!(f, "~ translatable fields of {}", c),
+			SchemaChange::SetSingleton(c, _) => write!(f, "~ singleton of {}", c),
+			SchemaChange::SetDeprecation(c, _) => write!(f, "~ deprecation of {}", c),
+			SchemaChange::SetDescription(c, _) => write!(f, "~ description of {}", c),
+			SchemaChange::SetExposure(c, _) => write!(f, "~ exposure of {}", c),
+			SchemaChange::SetValidationLevel(c, level) => {
+				write!(f, "~ validation level of {} to {}", c, level.as_str())
+			}
+			SchemaChange::CreateRelationship(r) => {
+				write!(f, "+ relationship {}.{}", r.from, r.name)
+			}
+			SchemaChange::DeleteRelationship(name, from) => {
+				write!(f, "- relationship {}.{}", from, name)
+			}
+		}
+	}
+}
+
+/// Parses a schema definition, JSON being valid YAML either format is accepted
+pub fn parse_schema(definition: &str) -> Result<DeclaredSchema, Error> {
+	Ok(serde_yaml::from_str(definition)?)
+}
+
+/// Properties added to the rule by the variants, the discriminator and the TTL of a collection
+fn get_variant_properties(entry: &JsonValue) -> Vec<String> {
+	let mut names: Vec<String> = entry["variants"]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|v| v["properties"].as_object())
+		.flat_map(|p| p.keys().cloned())
+		.collect();
+
+	if let Some(discriminator) = entry["discriminator"].as_str() {
+		names.push(discriminator.to_string());
+	}
+
+	// The TTL field is managed along with the TTL
+	if let Some(field) = entry["ttl"]["field"].as_str() {
+		names.push(field.to_string());
+	}
+
+	names
+}
+
+fn diff_collection(
+	collection: &DeclaredCollection,
+	entry: &JsonValue,
+	prune: bool,
+	changes: &mut Vec<SchemaChange>,
+) {
+	let name = &collection.name;
+	let stored = &entry["schema"]["properties"];
+	let required: Vec<&str> = entry["schema"]["required"]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|r| r.as_str())
+		.collect();
+	let translatable = get_translatable_fields(entry);
+
+	for property in &collection.properties {
+		match stored.get(&property.name) {
+			None => changes.push(SchemaChange::AddField(name.clone(), property.clone())),
+			Some(value) => {
+				let declared = toJsonValue(SchemaProperty::from(property.values.clone())).unwrap();
+
+				// Translatable fields are declared by the rule of their values
+				let value = if translatable.contains(&property.name) {
+					unwrap_translatable_rule(value)
+				} else {
+					value.clone()
+				};
+
+				if value != declared
+					|| required.contains(&property.name.as_str()) != property.required
+				{
+					changes.push(SchemaChange::AlterField(name.clone(), property.clone()));
+				}
+			}
+		}
+	}
+
+	if prune {
+		let variant_properties = get_variant_properties(entry);
+
+		for key in stored.as_object().into_iter().flat_map(|p| p.keys()) {
+			let is_status = collection.publication && key == STATUS_FIELD;
+
+			if !variant_properties.contains(key)
+				&& !is_status
+				&& !collection.properties.iter().any(|p| p.name == *key)
+			{
+				changes.push(SchemaChange::RemoveField(name.clone(), key.clone()));
+			}
+		}
+	}
+
+	let computed: Vec<ComputedProperty> = collection
+		.computed
+		.iter()
+		.cloned()
+		.map(ComputedProperty::from)
+		.collect();
+
+	let stored_computed = match &entry["computed"] {
+		JsonValue::Null => JsonValue::Array(vec![]),
+		computed => computed.clone(),
+	};
+
+	if toJsonValue(computed).unwrap() != stored_computed {
+		changes.push(SchemaChange::UpdateComputed(
+			name.clone(),
+			collection.computed.clone(),
+		));
+	}
+
+	let ttl = collection.ttl.clone().map(Ttl::from);
+
+	if toJsonValue(ttl).unwrap() != entry["ttl"] {
+		changes.push(SchemaChange::SetTtl(name.clone(), collection.ttl.clone()));
+	}
+
+	let cache_max_age = collection.cache_max_age.map(|a| a.max(0) as u64);
+
+	if cache_max_age != entry["cache_max_age"].as_u64() {
+		changes.push(SchemaChange::SetCache(
+			name.clone(),
+			collection.cache_max_age,
+		));
+	}
+
+	let versioning = collection.versioning.clone().map(Versioning::from);
+
+	if toJsonValue(versioning).unwrap() != entry["versioning"] {
+		changes.push(SchemaChange::SetVersioning(
+			name.clone(),
+			collection.versioning.clone(),
+		));
+	}
+
+	if collection.translatable != translatable {
+		changes.push(SchemaChange::SetTranslatable(
+			name.clone(),
+			collection.translatable.clone(),
+		));
+	}
+
+	if collection.singleton != entry["singleton"].is_string() {
+		changes.push(SchemaChange::SetSingleton(
+			name.clone(),
+			collection.singleton,
+		));
+	}
+
+	if collection.deprecation_reason.as_deref() != entry["deprecation_reason"].as_str() {
+		changes.push(SchemaChange::SetDeprecation(
+			name.clone(),
+			collection.deprecation_reason.clone(),
+		));
+	}
+
+	if collection.description.as_deref() != entry["description"].as_str() {
+		changes.push(SchemaChange::SetDescription(
+			name.clone(),
+			collection.description.clone(),
+		));
+	}
+
+	if collection.exposed != entry["exposed"].as_bool() {
+		changes.push(SchemaChange::SetExposure(name.clone(), collection.exposed));
+	}
+
+	if collection.publication != entry["publication"].as_bool().unwrap_or(false) {
+		changes.push(SchemaChange::SetPublication(
+			name.clone(),
+			collection.publication,
+		));
+	}
+
+	let level = collection
+		.validation_level
+		.clone()
+		.unwrap_or(SchemaValidationLevel::Strict);
+
+	if level.as_str() != get_validation_level(entry) {
+		changes.push(SchemaChange::SetValidationLevel(name.clone(), level));
+	}
+}
+
+/// Whether the stored relationship entry differs from the declared relationship
+fn is_relationship_changed(relationship: &DeclaredRelationship, entry: &JsonValue) -> bool {
+	let (properties, required) = build_object_rule(relationship.properties.clone());
+
+	let rule = Rule {
+		r#type: String::from("object"),
+		properties: JsonValue::Object(properties),
+		required,
+		additional_properties: false,
+	};
+
+	entry["edge"].as_str() != Some(relationship.edge.as_str())
+		|| entry["to"].as_str() != Some(relationship.to.as_str())
+		|| entry["type"].as_str() != Some(relationship.relationship_type.as_str().as_str())
+		|| entry["schema"] != toJsonValue(&rule).unwrap()
+		|| entry["inverse"].as_str() != relationship.inverse.as_deref()
+		|| entry["generate_inverse"].as_bool() != relationship.generate_inverse
+		|| entry["on_delete"].as_str().map(String::from)
+			!= relationship.on_delete.as_ref().map(|o| o.as_str())
+		|| entry["targets"] != toJsonValue(&relationship.targets).unwrap()
+		|| entry["description"].as_str() != relationship.description.as_deref()
+}
+
+/// Compares the declared schema with the stored one. Collections, fields and relationships
+/// missing from the declaration are only removed when `prune` is set. Changes are ordered so
+/// they can be applied one after the other
+pub async fn diff_schema(declared: &DeclaredSchema, prune: bool) -> Vec<SchemaChange> {
+	let collections = get_all_collections().await;
+	let edges = get_all_edges().await;
+
+	let mut changes = Vec::new();
+
+	for collection in &declared.collections {
+		match collections
+			.iter()
+			.find(|c| c["name"].as_str() == Some(collection.name.as_str()))
+		{
+			Some(entry) => diff_collection(collection, entry, prune, &mut changes),
+			None => changes.push(SchemaChange::CreateCollection(collection.clone())),
+		}
+	}
+
+	let mut created = Vec::new();
+
+	for relationship in &declared.relationships {
+		let entry = edges.iter().find(|e| {
+			e["name"].as_str() == Some(relationship.name.as_str())
+				&& e["from"].as_str() == Some(relationship.from.as_str())
+		});
+
+		match entry {
+			Some(entry) if !is_relationship_changed(relationship, entry) => {}
+			Some(_) => {
+				// Entries of relationships are replaced, the edges themselves are kept
+				changes.push(SchemaChange::DeleteRelationship(
+					relationship.name.clone(),
+					relationship.from.clone(),
+				));
+				created.push(SchemaChange::CreateRelationship(relationship.clone()));
+			}
+			None => created.push(SchemaChange::CreateRelationship(relationship.clone())),
+		}
+	}
+
+	if prune {
+		for entry in &edges {
+			let (name, from) = (
+				entry["name"].as_str().unwrap_or_default(),
+				entry["from"].as_str().unwrap_or_default(),
+			);
+
+			if !declared
+				.relationships
+				.iter()
+				.any(|r| r.name == name && r.from == from)
+			{
+				changes.push(SchemaChange::DeleteRelationship(
+					name.to_string(),
+					from.to_string(),
+				));
+			}
+		}
+	}
+
+	changes.append(&mut created);
+
+	if prune {
+		for entry in &collections {
+			let name = entry["name"].as_str().unwrap_or_default();
+
+			if !declared.collections.iter().any(|c| c.name == name) {
+				changes.push(SchemaChange::DropCollection(name.to_string()));
+			}
+		}
+	}
+
+	changes
+}
+
+async fn apply_schema_change(change: SchemaChange) -> Result<(), Error> {
+	match change {
+		SchemaChange::CreateCollection(c) => {
+			let name = c.name.clone();
+			let cache_max_age = c.cache_max_age;
+			let versioning = c.versioning;
+			let publication = c.publication;
+			let translatable = c.translatable;
+			let singleton = c.singleton;
+			let deprecation_reason = c.deprecation_reason;
+			let description = c.description;
+			let exposed = c.exposed;
+
+			create_collection(
+				c.name,
+				c.properties,
+				c.discriminator,
+				c.variants,
+				c.computed,
+				c.ttl,
+				c.settings,
+				c.validation_level,
+			)
+			.await?;
+
+			if cache_max_age.is_some() {
+				set_collection_cache(name.clone(), cache_max_age).await?;
+			}
+
+			if versioning.is_some() {
+				set_collection_versioning(name.clone(), versioning).await?;
+			}
+
+			if publication {
+				set_collection_publication(name.clone(), publication).await?;
+			}
+
+			if !translatable.is_empty() {
+				set_translatable_fields(name.clone(), translatable).await?;
+			}
+
+			if singleton {
+				set_collection_singleton(name.clone(), singleton).await?;
+			}
+
+			if deprecation_reason.is_some() {
+				set_collection_deprecation(name.clone(), deprecation_reason).await?;
+			}
+
+			if description.is_some() {
+				set_collection_description(name.clone(), description).await?;
+			}
+
+			if exposed.is_some() {
+				set_collection_exposure(name, exposed).await?;
+			}
+		}
+		SchemaChange::DropCollection(name) => delete_collection(name).await?,
+		SchemaChange::AddField(collection, property) => add_field(collection, property).await?,
+		SchemaChange::AlterField(collection, property) => alter_field(collection, property).await?,
+		SchemaChange::RemoveField(collection, name) => remove_field(collection, name).await?,
+		SchemaChange::UpdateComputed(collection, computed) => {
+			if get_entry(&collection).await.is_none() {
+				return Err(anyhow!("Collection {} not found", collection));
+			}
+
+			let computed: Vec<ComputedProperty> =
+				computed.into_iter().map(ComputedProperty::from).collect();
+
+			update_entry(&collection, serde_json::json!({ "computed": computed })).await;
+		}
+		SchemaChange::SetTtl(collection, ttl) => set_collection_ttl(collection, ttl).await?,
+		SchemaChange::SetCache(collection, max_age) => {
+			set_collection_cache(collection, max_age).await?
+		}
+		SchemaChange::SetVersioning(collection, versioning) => {
+			set_collection_versioning(collection, versioning).await?
+		}
+		SchemaChange::SetPublication(collection, enabled) => {
+			set_collection_publication(collection, enabled).await?
+		}
+		SchemaChange::SetTranslatable(collection, fields) => {
+			set_translatable_fields(collection, fields).await?
+		}
+		SchemaChange::SetSingleton(collection, enabled) => {
+			set_collection_singleton(collection, enabled).await?
+		}
+		SchemaChange::SetDeprecation(collection, reason) => {
+			set_collection_deprecation(collection, reason).await?
+		}
+		SchemaChange::SetDescription(collection, description) => {
+			set_collection_description(collection, description).await?
+		}
+		SchemaChange::SetExposure(collection, exposed) => {
+			set_collection_exposure(collection, exposed).await?
+		}
+		SchemaChange::SetValidationLevel(collection, level) => {
+			set_validation_level(collection, level).await?
+		}
+		SchemaChange::CreateRelationship(r) => {
+			create_relationship(
+				r.name,
+				r.from,
+				r.to,
+				r.relationship_type,
+				r.edge,
+				r.properties,
+				r.inverse,
+				r.generate_inverse,
+				r.on_delete,
+				r.targets,
+				r.description,
+			)
+			.await?
+		}
+		SchemaChange::DeleteRelationship(name, from) => delete_relationship(name, from).await?,
+	}
+
+	Ok(())
+}
+
+/// Applies the changes in order, stopping at the first one failing. Each change is recorded
+/// as a migration
+pub async fn apply_schema_changes(
+	changes: Vec<SchemaChange>,
+	author: Option<String>,
+) -> Result<(), Error> {
+	for change in changes {
+		tracing::info!("Applying schema change: {}", change);
+
+		migrate(
+			change.to_string(),
+			author.clone(),
+			toJsonValue(&change)?,
+			apply_schema_change(change),
+		)
+		.await?;
+	}
+
+	Ok(())
+}
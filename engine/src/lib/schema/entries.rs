@@ -5,7 +5,9 @@ use serde_json::{to_value as toJsonValue, value::Value as JsonValue};
 
 use rust_arango::AqlQuery;
 
-use crate::lib::database::schema::rule::Rule;
+use crate::lib::database::schema::{
+	CollectionSettings, ComputedProperty, Ttl, Variant, Versioning,
+};
 
 #[derive(Serialize, Deserialize, Default, Derivative)]
 pub struct AlchemyCollectionEntry {
@@ -13,6 +15,87 @@ pub struct AlchemyCollectionEntry {
 	pub schema: JsonValue,
 	#[derivative(Default(value = "0"))]
 	pub count: u64,
+	/// Property holding the variant of polymorphic collections
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub discriminator: Option<String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub variants: Vec<Variant>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub computed: Vec<ComputedProperty>,
+	/// Expiry of the documents, enforced by a TTL index
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ttl: Option<Ttl>,
+	/// Seconds the API responses reading the collection are cached for
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cache_max_age: Option<u32>,
+	/// Revision history of the documents, recorded on update and delete
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub versioning: Option<Versioning>,
+	/// Documents have a draft, published or archived `status`, only published ones being read
+	/// by default
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub publication: bool,
+	/// Fields holding a value per locale, their rule wrapping the rule of the values
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub translatable: Vec<String>,
+	/// Key of the only document of singleton collections
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub singleton: Option<String>,
+	/// Reason the operations of the collection are deprecated for
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub deprecation_reason: Option<String>,
+	/// Documentation of the type of the collection in the API
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Whether the collection is in the API, it is unless `API_ALLOWLIST` is set
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub exposed: Option<bool>,
+	/// Options the collection was created with, to create it alike elsewhere
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub settings: Option<CollectionSettings>,
+	/// Schema validation level, `strict` when unset
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub level: Option<String>,
+}
+
+/// A relationship between two collections stored in an edge collection
+#[derive(Serialize, Deserialize)]
+pub struct AlchemyEdgeEntry {
+	pub name: String,
+	pub edge: String,
+	pub from: String,
+	pub to: String,
+	pub r#type: String,
+	pub direction: String,
+	/// Schema of the attributes stored on the edges
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub schema: Option<JsonValue>,
+	/// Name of the field traversing the relationship from `to`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub inverse: Option<String>,
+	/// Whether the inverse field is generated, it is unless set to false
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub generate_inverse: Option<bool>,
+	/// Behavior of the relationship when a `from` document is removed
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub on_delete: Option<String>,
+	/// Collections of a polymorphic relationship, `to` is then the name of their union
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub targets: Option<Vec<String>>,
+	/// Documentation of the field of the relationship in the API
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+}
+
+/// Drops the document attributes set by ArangoDB, which change on every write
+pub fn strip_document_attributes(mut entry: JsonValue) -> JsonValue {
+	if let Some(object) = entry.as_object_mut() {
+		object.remove("_id");
+		object.remove("_key");
+		object.remove("_rev");
+	}
+
+	entry
 }
 
 /// Get all of the entries in the database
@@ -27,7 +110,7 @@ pub async fn get_all_collections() -> Vec<JsonValue> {
 	let entries: Vec<JsonValue> = DATABASE
 		.get()
 		.await
-		.database
+		.database()
 		.aql_query(entries_query)
 		.await
 		.unwrap();
@@ -47,7 +130,7 @@ pub async fn get_all_edges() -> Vec<JsonValue> {
 	let entries: Vec<JsonValue> = DATABASE
 		.get()
 		.await
-		.database
+		.database()
 		.aql_query(entries_query)
 		.await
 		.unwrap();
@@ -55,14 +138,50 @@ pub async fn get_all_edges() -> Vec<JsonValue> {
 	return entries;
 }
 
-pub async fn create_entry(name: String, schema_rule: Rule) {
-	/* Collection entry */
-	let alchemy_collection_entry = AlchemyCollectionEntry {
-		name,
-		schema: toJsonValue(&schema_rule).unwrap(),
-		..Default::default()
-	};
+/// Get the entry of a collection
+pub async fn get_entry(name: &str) -> Option<JsonValue> {
+	let entries_query = AqlQuery::builder()
+		.query(
+			"FOR entry in alchemy_collections
+				FILTER entry.name == @name
+				LIMIT 1
+				RETURN entry",
+		)
+		.bind_var("name", name)
+		.build();
+
+	let entries: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(entries_query)
+		.await
+		.unwrap();
+
+	entries.into_iter().next()
+}
+
+/// Get all of the named graphs in the database
+pub async fn get_all_graphs() -> Vec<JsonValue> {
+	let entries_query = AqlQuery::builder()
+		.query(
+			"FOR entry in _graphs
+				RETURN entry",
+		)
+		.build();
 
+	let entries: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(entries_query)
+		.await
+		.unwrap();
+
+	return entries;
+}
+
+pub async fn create_entry(alchemy_collection_entry: AlchemyCollectionEntry) {
 	// Create an entry in the alchemy collections
 	let alchemy_entry = AqlQuery::builder()
 		.query("INSERT @document  INTO @@collection")
@@ -73,7 +192,41 @@ pub async fn create_entry(name: String, schema_rule: Rule) {
 	let _alchemy_entry_document: Vec<JsonValue> = DATABASE
 		.get()
 		.await
-		.database
+		.database()
+		.aql_query(alchemy_entry)
+		.await
+		.unwrap();
+}
+
+/// Inserts entries as they are into `alchemy_collections` or `alchemy_edges`
+pub async fn insert_entries(collection: &str, entries: Vec<JsonValue>) {
+	let alchemy_entries = AqlQuery::builder()
+		.query("FOR e IN @entries INSERT e INTO @@collection")
+		.bind_var("@collection", collection)
+		.bind_var("entries", JsonValue::Array(entries))
+		.build();
+
+	let _alchemy_entry_documents: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(alchemy_entries)
+		.await
+		.unwrap();
+}
+
+pub async fn create_edge_entry(alchemy_edge_entry: AlchemyEdgeEntry) {
+	// Create an entry in the alchemy edges
+	let alchemy_entry = AqlQuery::builder()
+		.query("INSERT @document INTO @@collection")
+		.bind_var("@collection", "alchemy_edges")
+		.bind_var("document", toJsonValue(&alchemy_edge_entry).unwrap())
+		.build();
+
+	let _alchemy_entry_document: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
 		.aql_query(alchemy_entry)
 		.await
 		.unwrap();
@@ -95,7 +248,106 @@ pub async fn delete_entry(name: String) {
 	let _alchemy_entry_document: Vec<JsonValue> = DATABASE
 		.get()
 		.await
-		.database
+		.database()
+		.aql_query(alchemy_entry)
+		.await
+		.unwrap();
+}
+
+pub async fn update_entry(name: &str, patch: JsonValue) {
+	// Update the entry of the collection with the given attributes
+	let alchemy_entry = AqlQuery::builder()
+		.query(
+			"FOR e IN @@collection
+				FILTER e.name == @name
+				UPDATE e WITH @patch IN @@collection
+		",
+		)
+		.bind_var("@collection", "alchemy_collections")
+		.bind_var("name", name)
+		.bind_var("patch", patch)
+		.build();
+
+	let _alchemy_entry_document: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(alchemy_entry)
+		.await
+		.unwrap();
+}
+
+/// Replaces the entry of the collection as a whole, unlike `update_entry`
+pub async fn replace_entry(name: &str, entry: JsonValue) {
+	let alchemy_entry = AqlQuery::builder()
+		.query(
+			"FOR e IN @@collection
+				FILTER e.name == @name
+				REPLACE e WITH @entry IN @@collection
+		",
+		)
+		.bind_var("@collection", "alchemy_collections")
+		.bind_var("name", name)
+		.bind_var("entry", entry)
+		.build();
+
+	let _alchemy_entry_document: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(alchemy_entry)
+		.await
+		.unwrap();
+}
+
+/// Points the relationships of the collection `name` to `new_name`
+pub async fn rename_edge_entries(name: &str, new_name: &str) {
+	let alchemy_entry = AqlQuery::builder()
+		.query(
+			"FOR e IN @@collection
+				FILTER e.from == @name OR e.to == @name OR @name IN (e.targets || [])
+				UPDATE e WITH MERGE(
+					{
+						from: e.from == @name ? @new_name : e.from,
+						to: e.to == @name ? @new_name : e.to
+					},
+					e.targets == null ? {} : { targets: (FOR t IN e.targets RETURN t == @name ? @new_name : t) }
+				) IN @@collection
+		",
+		)
+		.bind_var("@collection", "alchemy_edges")
+		.bind_var("name", name)
+		.bind_var("new_name", new_name)
+		.build();
+
+	let _alchemy_entry_document: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(alchemy_entry)
+		.await
+		.unwrap();
+}
+
+/// Deletes the relationship `name` of the collection `from`, or every relationship of the
+/// collection when no name is given
+pub async fn delete_edge_entries(from: &str, name: Option<&str>) {
+	let alchemy_entry = AqlQuery::builder()
+		.query(
+			"FOR e IN @@collection
+				FILTER @name == null ? (e.from == @from OR e.to == @from OR @from IN (e.targets || [])) : (e.from == @from AND e.name == @name)
+				REMOVE { _key: e._key } IN @@collection
+		",
+		)
+		.bind_var("@collection", "alchemy_edges")
+		.bind_var("from", from)
+		.bind_var("name", name)
+		.build();
+
+	let _alchemy_entry_document: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
 		.aql_query(alchemy_entry)
 		.await
 		.unwrap();
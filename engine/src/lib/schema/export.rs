@@ -0,0 +1,163 @@
+use anyhow::{anyhow, Error};
+use rust_arango::collection::options::{CreateOptions as CollectionOptions, CreateParameters};
+use rust_arango::collection::CollectionType;
+use rust_arango::graph::{EdgeDefinition, Graph};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value as fromJsonValue, value::Value as JsonValue};
+
+use crate::lib::database::arango::get_collection_options;
+use crate::lib::database::schema::{get_validation_level, CollectionSettings, DatabaseSchema};
+use crate::lib::database::DATABASE;
+use crate::lib::schema::{
+	get_all_collections, get_all_edges, get_all_graphs, insert_entries, strip_document_attributes,
+};
+
+/// The complete schema of an instance, with the entries as they are stored so nothing like
+/// permissions is left out
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaExport {
+	pub alchemy_version: String,
+	pub collections: Vec<JsonValue>,
+	#[serde(default)]
+	pub relationships: Vec<JsonValue>,
+	#[serde(default)]
+	pub graphs: Vec<ExportedGraph>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedGraph {
+	pub name: String,
+	pub edge_definitions: Vec<EdgeDefinition>,
+}
+
+pub async fn export_schema() -> Result<SchemaExport, Error> {
+	let mut graphs = Vec::new();
+
+	for graph in get_all_graphs().await {
+		graphs.push(ExportedGraph {
+			name: graph["_key"].as_str().unwrap_or_default().to_string(),
+			edge_definitions: fromJsonValue(graph["edgeDefinitions"].clone())?,
+		});
+	}
+
+	Ok(SchemaExport {
+		alchemy_version: env!("CARGO_PKG_VERSION").to_string(),
+		collections: get_all_collections()
+			.await
+			.into_iter()
+			.map(strip_document_attributes)
+			.collect(),
+		relationships: get_all_edges()
+			.await
+			.into_iter()
+			.map(strip_document_attributes)
+			.collect(),
+		graphs,
+	})
+}
+
+/// Creates the collection validated by the stored rule of its entry, when it doesn't exist
+async fn create_collection_from_entry(
+	name: &str,
+	entry: &JsonValue,
+	collection_type: CollectionType,
+) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(name).await.is_ok() {
+		return Ok(());
+	}
+
+	let collection_options = match entry["schema"].is_null() {
+		true => CollectionOptions::builder()
+			.name(name)
+			.collection_type(collection_type)
+			.build(),
+		false => {
+			let schema = DatabaseSchema {
+				message: String::from("Schema validation failed"),
+				level: get_validation_level(entry),
+				rule: fromJsonValue(entry["schema"].clone())?,
+			};
+
+			let settings: CollectionSettings =
+				fromJsonValue(entry["settings"].clone()).unwrap_or_default();
+
+			get_collection_options(name, collection_type, &schema, &settings)?
+		}
+	};
+
+	db.create_collection_with_options(collection_options, CreateParameters::default())
+		.await?;
+
+	Ok(())
+}
+
+/// Imports an exported schema into an instance without any of its collections or
+/// relationships, documents aren't part of it
+pub async fn import_schema(export: SchemaExport) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	let collections = get_all_collections().await;
+	let edges = get_all_edges().await;
+
+	for entry in &export.collections {
+		if collections.iter().any(|c| c["name"] == entry["name"]) {
+			return Err(anyhow!("Collection {} already exists", entry["name"]));
+		}
+	}
+
+	for entry in &export.relationships {
+		if edges
+			.iter()
+			.any(|e| e["name"] == entry["name"] && e["from"] == entry["from"])
+		{
+			return Err(anyhow!(
+				"Relationship {} of {} already exists",
+				entry["name"],
+				entry["from"]
+			));
+		}
+	}
+
+	for entry in &export.collections {
+		let name = entry["name"]
+			.as_str()
+			.ok_or_else(|| anyhow!("Collection without a name"))?;
+
+		create_collection_from_entry(name, entry, CollectionType::Document).await?;
+	}
+
+	for entry in &export.relationships {
+		let edge = entry["edge"]
+			.as_str()
+			.ok_or_else(|| anyhow!("Relationship without an edge collection"))?;
+
+		create_collection_from_entry(edge, entry, CollectionType::Edge).await?;
+	}
+
+	insert_entries("alchemy_collections", export.collections).await;
+	insert_entries("alchemy_edges", export.relationships).await;
+
+	let existing_graphs = get_all_graphs().await;
+
+	for graph in export.graphs {
+		if existing_graphs
+			.iter()
+			.any(|g| g["_key"].as_str() == Some(graph.name.as_str()))
+		{
+			continue;
+		}
+
+		let graph = Graph::builder()
+			.name(graph.name)
+			.edge_definitions(graph.edge_definitions)
+			.build();
+
+		db.create_graph(graph, true).await?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use anyhow::Error;
+use rust_arango::AqlQuery;
+use serde_json::Value as JsonValue;
+
+use crate::lib::database::DATABASE;
+
+use super::version::SCHEMA_STATE_COLLECTION;
+
+const FIELD_NUMBERS_KEY: &str = "field_numbers";
+
+/// Numbers reserved by the protobuf implementations, skipped when numbering the fields
+const RESERVED_NUMBERS: std::ops::RangeInclusive<i32> = 19000..=19999;
+
+/// Attempts at saving the numbers of new fields while other instances number them too
+const SAVE_ATTEMPTS: usize = 3;
+
+/// Numbers of the fields of each message, by the name of the property each field holds
+pub type FieldNumbers = HashMap<String, HashMap<String, i32>>;
+
+/// Numbers of the fields of the messages, given with the properties they hold. A field keeps
+/// its number for good and new fields are numbered after every number the message ever had,
+/// so the numbers of removed fields aren't reused by the clients generated before
+pub async fn get_field_numbers(messages: &[(String, Vec<String>)]) -> Result<FieldNumbers, Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(SCHEMA_STATE_COLLECTION).await.is_err() {
+		db.create_collection(SCHEMA_STATE_COLLECTION).await?;
+	}
+
+	let mut attempt = 0;
+
+	loop {
+		attempt += 1;
+
+		let query = AqlQuery::builder()
+			.query("RETURN DOCUMENT(@@collection, @key)")
+			.bind_var("@collection", SCHEMA_STATE_COLLECTION)
+			.bind_var("key", FIELD_NUMBERS_KEY)
+			.build();
+
+		let stored: Vec<JsonValue> = db.aql_query(query).await?;
+		let stored = stored.into_iter().next().unwrap_or_default();
+
+		let mut numbers: FieldNumbers =
+			serde_json::from_value(stored["messages"].clone()).unwrap_or_default();
+		let mut changed = false;
+
+		for (message, fields) in messages {
+			let numbers = numbers.entry(message.clone()).or_default();
+			let mut last = numbers.values().max().copied().unwrap_or_default();
+
+			for field in fields {
+				if numbers.contains_key(field) {
+					continue;
+				}
+
+				last += 1;
+
+				if RESERVED_NUMBERS.contains(&last) {
+					last = RESERVED_NUMBERS.end() + 1;
+				}
+
+				numbers.insert(field.clone(), last);
+				changed = true;
+			}
+		}
+
+		if !changed {
+			return Ok(numbers);
+		}
+
+		// Saved only if no other instance saved numbers meanwhile, they're read again otherwise
+		let query = match stored["_rev"].as_str() {
+			Some(rev) => AqlQuery::builder()
+				.query(
+					"UPDATE { _key: @key, _rev: @rev } WITH { messages: @messages } IN @@collection
+						OPTIONS { ignoreRevs: false, mergeObjects: false }",
+				)
+				.bind_var("rev", rev),
+			None => AqlQuery::builder()
+				.query("INSERT { _key: @key, messages: @messages } INTO @@collection"),
+		}
+		.bind_var("@collection", SCHEMA_STATE_COLLECTION)
+		.bind_var("key", FIELD_NUMBERS_KEY)
+		.bind_var("messages", serde_json::to_value(&numbers)?)
+		.build();
+
+		match db.aql_query::<JsonValue>(query).await {
+			Ok(_) => return Ok(numbers),
+			Err(e) if attempt >= SAVE_ATTEMPTS => return Err(e.into()),
+			Err(e) => tracing::debug!("Numbering the fields again: {}", e),
+		}
+	}
+}
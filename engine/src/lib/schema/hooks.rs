@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Error};
+use rust_arango::AqlQuery;
+use serde::{Deserialize, Serialize};
+use serde_json::{to_value as toJsonValue, value::Value as JsonValue};
+
+use crate::lib::database::DATABASE;
+use crate::lib::events::EventOperation;
+use crate::lib::plugins::check_handler;
+use crate::lib::schema::get_entry;
+
+const HOOKS_COLLECTION: &str = "alchemy_hooks";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, GraphQLEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum HookStage {
+	/// Runs before the mutation, it can change its input or reject it
+	Before,
+	/// Runs once the mutation is applied, with the changed documents
+	After,
+}
+
+/// Handler called synchronously around the mutations of a collection
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlchemyHookEntry {
+	pub name: String,
+	pub collection: String,
+	pub stage: HookStage,
+	pub operations: Vec<EventOperation>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub url: Option<String>,
+	/// Plugin handling the hook in-process instead of the URL
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub plugin: Option<String>,
+	#[serde(default)]
+	pub headers: HashMap<String, String>,
+}
+
+/// Get the entries of all of the hooks, hooks of the same stage run in the order of their names
+pub async fn get_all_hooks() -> Vec<JsonValue> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(HOOKS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query("FOR h IN @@collection SORT h.name RETURN UNSET(h, '_id', '_key', '_rev')")
+		.bind_var("@collection", HOOKS_COLLECTION)
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+/// Creates the hook, replacing the one with the same name
+pub async fn save_hook(hook: AlchemyHookEntry) -> Result<(), Error> {
+	if hook.operations.is_empty() {
+		return Err(anyhow!("Hook {} has no operations", hook.name));
+	}
+
+	check_handler(&hook.url, &hook.plugin).await?;
+
+	if get_entry(&hook.collection).await.is_none() {
+		return Err(anyhow!("Collection {} not found", hook.collection));
+	}
+
+	let db = DATABASE.get().await.database();
+
+	if db.collection(HOOKS_COLLECTION).await.is_err() {
+		db.create_collection(HOOKS_COLLECTION).await?;
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"UPSERT { name: @hook.name }
+				INSERT @hook
+				REPLACE @hook
+				IN @@collection",
+		)
+		.bind_var("@collection", HOOKS_COLLECTION)
+		.bind_var("hook", toJsonValue(&hook)?)
+		.build();
+
+	let _: Vec<JsonValue> = db.aql_query(query).await?;
+
+	Ok(())
+}
+
+pub async fn delete_hook(name: &str) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(HOOKS_COLLECTION).await.is_err() {
+		return Err(anyhow!("Hook {} not found", name));
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR h IN @@collection
+				FILTER h.name == @name
+				REMOVE h IN @@collection
+				RETURN 1",
+		)
+		.bind_var("@collection", HOOKS_COLLECTION)
+		.bind_var("name", name)
+		.build();
+
+	let removed: Vec<JsonValue> = db.aql_query(query).await?;
+
+	match removed.is_empty() {
+		true => Err(anyhow!("Hook {} not found", name)),
+		false => Ok(()),
+	}
+}
@@ -0,0 +1,404 @@
+use std::future::Future;
+
+use anyhow::{anyhow, Error};
+use chrono::{SecondsFormat, Utc};
+use rust_arango::collection::options::{CreateParameters, PropertiesOptions};
+use rust_arango::collection::CollectionType;
+use rust_arango::AqlQuery;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value as fromJsonValue, to_value as toJsonValue, value::Value as JsonValue};
+
+use crate::lib::database::arango::{
+	get_collection_options, move_collection, move_field_values, set_collection_ttl, ttl_from_entry,
+};
+use crate::lib::database::schema::{get_validation_level, CollectionSettings, DatabaseSchema};
+use crate::lib::database::DATABASE;
+use crate::lib::schema::{
+	delete_edge_entries, delete_entry, get_all_collections, get_all_edges, get_all_graphs,
+	get_entry, insert_entries, replace_entry, strip_document_attributes,
+};
+
+const MIGRATIONS_COLLECTION: &str = "alchemy_migrations";
+
+/// An applied schema change, with what is needed to roll it back
+#[derive(Serialize, Deserialize)]
+pub struct AlchemyMigrationEntry {
+	pub name: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub author: Option<String>,
+	/// RFC 3339 time the change was applied at, migrations are ordered by it
+	pub timestamp: String,
+	/// The change as requested, e.g. `{ "addField": { ... } }`
+	pub up: JsonValue,
+	pub down: MigrationDown,
+}
+
+/// State of the schema entries a migration changed, from before it was applied
+#[derive(Serialize, Deserialize, Default)]
+pub struct MigrationDown {
+	#[serde(default)]
+	pub entries: Vec<EntrySnapshot>,
+	#[serde(default)]
+	pub edges: Vec<EdgeSnapshot>,
+	/// Collections created by the migration, including edge collections
+	#[serde(default)]
+	pub created_collections: Vec<String>,
+	#[serde(default)]
+	pub created_graphs: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EntrySnapshot {
+	pub name: String,
+	/// `None` when the migration created the entry
+	pub entry: Option<JsonValue>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EdgeSnapshot {
+	pub from: String,
+	pub name: String,
+	/// `None` when the migration created the relationship
+	pub entry: Option<JsonValue>,
+}
+
+impl MigrationDown {
+	fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+			&& self.edges.is_empty()
+			&& self.created_collections.is_empty()
+			&& self.created_graphs.is_empty()
+	}
+}
+
+struct SchemaSnapshot {
+	entries: Vec<JsonValue>,
+	edges: Vec<JsonValue>,
+	collections: Vec<String>,
+	graphs: Vec<String>,
+}
+
+async fn take_snapshot() -> Result<SchemaSnapshot, Error> {
+	let db = DATABASE.get().await.database();
+
+	let collections = db
+		.accessible_collections()
+		.await?
+		.into_iter()
+		.filter(|c| !c.is_system)
+		.map(|c| c.name)
+		.collect();
+
+	Ok(SchemaSnapshot {
+		entries: get_all_collections()
+			.await
+			.into_iter()
+			.map(strip_document_attributes)
+			.collect(),
+		edges: get_all_edges()
+			.await
+			.into_iter()
+			.map(strip_document_attributes)
+			.collect(),
+		collections,
+		graphs: get_all_graphs()
+			.await
+			.iter()
+			.filter_map(|g| g["_key"].as_str().map(String::from))
+			.collect(),
+	})
+}
+
+fn find_edge<'a>(edges: &'a [JsonValue], from: &str, name: &str) -> Option<&'a JsonValue> {
+	edges
+		.iter()
+		.find(|e| e["from"].as_str() == Some(from) && e["name"].as_str() == Some(name))
+}
+
+/// What changed between the two snapshots, as the state to restore
+fn get_down(before: SchemaSnapshot, after: &SchemaSnapshot) -> MigrationDown {
+	let mut down = MigrationDown::default();
+
+	let mut names: Vec<&str> = before
+		.entries
+		.iter()
+		.chain(after.entries.iter())
+		.filter_map(|e| e["name"].as_str())
+		.collect();
+
+	names.sort_unstable();
+	names.dedup();
+
+	for name in names {
+		let find = |entries: &[JsonValue]| {
+			entries
+				.iter()
+				.find(|e| e["name"].as_str() == Some(name))
+				.cloned()
+		};
+
+		let entry = find(&before.entries);
+
+		if entry != find(&after.entries) {
+			down.entries.push(EntrySnapshot {
+				name: name.to_string(),
+				entry,
+			});
+		}
+	}
+
+	let mut keys: Vec<(&str, &str)> = before
+		.edges
+		.iter()
+		.chain(after.edges.iter())
+		.map(|e| {
+			(
+				e["from"].as_str().unwrap_or_default(),
+				e["name"].as_str().unwrap_or_default(),
+			)
+		})
+		.collect();
+
+	keys.sort_unstable();
+	keys.dedup();
+
+	for (from, name) in keys {
+		let entry = find_edge(&before.edges, from, name);
+
+		if entry != find_edge(&after.edges, from, name) {
+			down.edges.push(EdgeSnapshot {
+				from: from.to_string(),
+				name: name.to_string(),
+				entry: entry.cloned(),
+			});
+		}
+	}
+
+	down.created_collections = after
+		.collections
+		.iter()
+		.filter(|c| !before.collections.contains(c) && *c != MIGRATIONS_COLLECTION)
+		.cloned()
+		.collect();
+
+	down.created_graphs = after
+		.graphs
+		.iter()
+		.filter(|g| !before.graphs.contains(g))
+		.cloned()
+		.collect();
+
+	down
+}
+
+async fn insert_migration(migration: AlchemyMigrationEntry) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(MIGRATIONS_COLLECTION).await.is_err() {
+		db.create_collection(MIGRATIONS_COLLECTION).await?;
+	}
+
+	let query = AqlQuery::builder()
+		.query("INSERT @document INTO @@collection")
+		.bind_var("@collection", MIGRATIONS_COLLECTION)
+		.bind_var("document", toJsonValue(&migration)?)
+		.build();
+
+	let _: Vec<JsonValue> = db.aql_query(query).await?;
+
+	Ok(())
+}
+
+/// Applies a schema change, recording it as a migration when it changed anything. A failing
+/// change is recorded as well since it can have been partially applied
+pub async fn migrate(
+	name: String,
+	author: Option<String>,
+	up: JsonValue,
+	change: impl Future<Output = Result<(), Error>>,
+) -> Result<(), Error> {
+	let before = take_snapshot().await?;
+	let result = change.await;
+	let down = get_down(before, &take_snapshot().await?);
+
+	if !down.is_empty() {
+		insert_migration(AlchemyMigrationEntry {
+			name,
+			author,
+			timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+			up,
+			down,
+		})
+		.await?;
+	}
+
+	result
+}
+
+/// Get the applied migrations, the latest first
+pub async fn get_migrations(limit: Option<u32>) -> Vec<JsonValue> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(MIGRATIONS_COLLECTION).await.is_err() {
+		return Vec::new();
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR m IN @@collection
+				SORT m.timestamp DESC
+				LIMIT @limit
+				RETURN m",
+		)
+		.bind_var("@collection", MIGRATIONS_COLLECTION)
+		.bind_var("limit", limit.unwrap_or(u32::MAX))
+		.build();
+
+	db.aql_query(query).await.unwrap_or_default()
+}
+
+/// Restores the collection entries, recreating the collections dropped by the migration.
+/// Their documents are lost, only the schema comes back
+async fn restore_entries(entries: Vec<EntrySnapshot>) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	for snapshot in entries {
+		let entry = match snapshot.entry {
+			Some(entry) => entry,
+			None => {
+				delete_entry(snapshot.name).await;
+
+				continue;
+			}
+		};
+
+		let schema = DatabaseSchema {
+			message: String::from("Schema validation failed"),
+			level: get_validation_level(&entry),
+			rule: fromJsonValue(entry["schema"].clone())?,
+		};
+
+		match db.collection(&snapshot.name).await {
+			Ok(collection) => {
+				collection
+					.change_properties(
+						PropertiesOptions::builder()
+							.schema(toJsonValue(&schema)?)
+							.build(),
+					)
+					.await?;
+			}
+			Err(_) => {
+				let settings: CollectionSettings =
+					fromJsonValue(entry["settings"].clone()).unwrap_or_default();
+
+				let collection_options = get_collection_options(
+					snapshot.name.as_str(),
+					CollectionType::Document,
+					&schema,
+					&settings,
+				)?;
+
+				db.create_collection_with_options(collection_options, CreateParameters::default())
+					.await?;
+			}
+		}
+
+		match get_entry(&snapshot.name).await {
+			Some(_) => replace_entry(&snapshot.name, entry).await,
+			None => insert_entries("alchemy_collections", vec![entry]).await,
+		}
+	}
+
+	Ok(())
+}
+
+/// Reverts a migration. Values of renamed fields are moved back, removed fields and the
+/// documents of dropped collections can't be brought back
+async fn rollback_migration(migration: &JsonValue) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	let down: MigrationDown = fromJsonValue(migration["down"].clone())?;
+
+	for graph in &down.created_graphs {
+		db.drop_graph(graph, false).await?;
+	}
+
+	// The renamed collection is moved back before its entry is restored, which would
+	// otherwise create an empty collection
+	if let Some(rename) = migration["up"].get("renameCollection") {
+		move_collection(
+			rename["newName"].as_str().unwrap_or_default(),
+			rename["name"].as_str().unwrap_or_default(),
+		)
+		.await?;
+	}
+
+	restore_entries(down.entries).await?;
+
+	if let Some(rename) = migration["up"].get("renameField") {
+		let collection = rename["collection"].as_str().unwrap_or_default();
+
+		if let Some(entry) = get_entry(collection).await {
+			let name = rename["name"].as_str().unwrap_or_default();
+
+			move_field_values(
+				collection,
+				rename["newName"].as_str().unwrap_or_default(),
+				name,
+			)
+			.await?;
+
+			// The TTL index has to be on the restored attribute as well
+			if entry["ttl"]["field"].as_str() == Some(name) {
+				set_collection_ttl(collection.to_string(), Some(ttl_from_entry(&entry)?)).await?;
+			}
+		}
+	}
+
+	for snapshot in down.edges {
+		delete_edge_entries(&snapshot.from, Some(&snapshot.name)).await;
+
+		if let Some(entry) = snapshot.entry {
+			insert_entries("alchemy_edges", vec![entry]).await;
+		}
+	}
+
+	for collection in &down.created_collections {
+		if db.collection(collection).await.is_ok() {
+			db.drop_collection(collection).await?;
+		}
+	}
+
+	let query = AqlQuery::builder()
+		.query("REMOVE @key IN @@collection")
+		.bind_var("@collection", MIGRATIONS_COLLECTION)
+		.bind_var("key", migration["_key"].clone())
+		.build();
+
+	let _: Vec<JsonValue> = db.aql_query(query).await?;
+
+	Ok(())
+}
+
+/// Rolls back the last `count` migrations, the latest first, returning their names
+pub async fn rollback_migrations(count: u32) -> Result<Vec<String>, Error> {
+	let migrations = get_migrations(Some(count)).await;
+
+	if migrations.len() < count as usize {
+		return Err(anyhow!(
+			"Only {} migrations can be rolled back",
+			migrations.len()
+		));
+	}
+
+	let mut names = Vec::new();
+
+	for migration in &migrations {
+		rollback_migration(migration).await?;
+
+		names.push(migration["name"].as_str().unwrap_or_default().to_string());
+	}
+
+	Ok(names)
+}
@@ -1,6 +1,57 @@
 pub mod properties;
 pub use properties::{SchemaDocumentProperty, SchemaDocumentPropertyValues};
 
+pub mod variants;
+pub use variants::SchemaVariant;
+
+pub mod computed;
+pub use computed::SchemaComputedProperty;
+
+pub mod ttl;
+pub use ttl::SchemaTtl;
+
+pub mod versioning;
+pub use versioning::SchemaVersioning;
+
+pub mod settings;
+pub use settings::SchemaCollectionSettings;
+
 // pub mod generation;
 pub mod entries;
-pub use entries::{create_entry, delete_entry, get_all_collections, get_all_edges};
+pub use entries::{
+	create_edge_entry, create_entry, delete_edge_entries, delete_entry, get_all_collections,
+	get_all_edges, get_all_graphs, get_entry, insert_entries, rename_edge_entries, replace_entry,
+	strip_document_attributes, update_entry,
+};
+
+pub mod declarative;
+pub use declarative::{apply_schema_changes, diff_schema, parse_schema};
+
+pub mod export;
+pub use export::{export_schema, import_schema, SchemaExport};
+
+pub mod seeds;
+pub use seeds::run_seeds;
+
+pub mod backup;
+pub use backup::{
+	create_backup_job, get_backup_jobs, list_backups, run_backup, run_restore, BackupOperation,
+	BackupStatus,
+};
+
+pub mod actions;
+pub use actions::{
+	delete_action, get_all_actions, save_action, AlchemyActionEntry, SchemaActionKind,
+};
+
+pub mod hooks;
+pub use hooks::{delete_hook, get_all_hooks, save_hook, AlchemyHookEntry, HookStage};
+
+pub mod migrations;
+pub use migrations::{get_migrations, migrate, rollback_migrations};
+
+pub mod version;
+pub use version::{bump_schema_version, get_schema_version};
+
+pub mod field_numbers;
+pub use field_numbers::{get_field_numbers, FieldNumbers};
@@ -1,19 +1,69 @@
 use serde::{Deserialize, Serialize};
 
 use crate::lib::database::schema::{
-	SchemaNativeType, SchemaNativeTypeArray, SchemaProperty, SchemaPropertyType,
+	SchemaNativeType, SchemaNativeTypeArray, SchemaProperty, SchemaPropertyType, SchemaUuidVersion,
 };
 
 /// The property for the collection property
-#[derive(Serialize, Deserialize, PartialEq, GraphQLInputObject)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, GraphQLInputObject)]
 pub struct SchemaDocumentProperty {
 	pub name: String,
 	pub values: SchemaDocumentPropertyValues,
 	pub required: bool,
 }
 
+/// Description of a value of an enum property
+#[derive(Serialize, Deserialize, PartialEq, Clone, GraphQLInputObject)]
+pub struct SchemaEnumValueDescription {
+	pub value: String,
+	pub description: String,
+}
+
+/// Directives of a field, emitted in the SDL and applied by the API
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, GraphQLInputObject)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaFieldDirectives {
+	/// `@auth`, roles allowed to read the field besides the admin
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub auth_roles: Option<Vec<String>>,
+	/// `@cache`, max age in seconds of the cached responses reading the field, 0 to never
+	/// cache them
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cache_max_age: Option<i32>,
+	/// `@cost`, complexity of the field in the query cost analysis
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cost: Option<i32>,
+}
+
+impl SchemaFieldDirectives {
+	/// Directives keyword of the field rule, each directive with its arguments
+	fn to_rule(&self) -> serde_json::Map<String, serde_json::Value> {
+		let mut rule = serde_json::Map::new();
+
+		if let Some(roles) = &self.auth_roles {
+			rule.insert("auth".to_string(), serde_json::json!({ "roles": roles }));
+		}
+
+		if let Some(max_age) = self.cache_max_age {
+			rule.insert(
+				"cache".to_string(),
+				serde_json::json!({ "maxAge": max_age.max(0) }),
+			);
+		}
+
+		if let Some(cost) = self.cost {
+			rule.insert(
+				"cost".to_string(),
+				serde_json::json!({ "complexity": cost.max(0) }),
+			);
+		}
+
+		rule
+	}
+}
+
 /// The property values for the collection property
-#[derive(Serialize, Deserialize, PartialEq, Default, GraphQLInputObject)]
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, GraphQLInputObject)]
 #[serde(rename_all = "camelCase")]
 pub struct SchemaDocumentPropertyValues {
 	pub r#type: SchemaPropertyType,
@@ -25,6 +75,50 @@ pub struct SchemaDocumentPropertyValues {
 	pub r#enum: Option<Vec<String>>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub array_type: Option<SchemaNativeType>,
+	/// Only used by UUID properties
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub generate: Option<SchemaUuidVersion>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub use_as_key: Option<bool>,
+	/// Only used by File properties, the default storage when unset
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub storage: Option<String>,
+	/// Only used by Object properties and Arrays of objects
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub properties: Option<Vec<SchemaDocumentProperty>>,
+	/// Marks the field `@deprecated` in the API with the reason
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub deprecation_reason: Option<String>,
+	/// Documentation of the field in the API
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Only used by Enum properties, documentation of their values
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub enum_descriptions: Option<Vec<SchemaEnumValueDescription>>,
+	/// `@auth`, `@cache` and `@cost` directives of the field
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub directives: Option<SchemaFieldDirectives>,
+}
+
+/// Builds the JSON schema properties and the required property names of an object
+pub fn build_object_rule(
+	properties: Vec<SchemaDocumentProperty>,
+) -> (serde_json::Map<String, serde_json::Value>, Vec<String>) {
+	let mut rule_properties = serde_json::Map::new();
+	let mut required = Vec::new();
+
+	for property in properties {
+		rule_properties.insert(
+			property.name.clone(),
+			serde_json::to_value(SchemaProperty::from(property.values)).unwrap(),
+		);
+
+		if property.required {
+			required.push(property.name);
+		}
+	}
+
+	(rule_properties, required)
 }
 
 // Convert `SchemaDocumentProperty` to `SchemaProperty` ready for Arango
@@ -33,6 +127,12 @@ impl From<SchemaDocumentPropertyValues> for SchemaProperty {
 		// Initialize the schema property with the default type
 		let mut property = SchemaProperty::new();
 		property.r#type = Some(values.r#type.as_str());
+		property.deprecation_reason = values.deprecation_reason;
+		property.description = values.description;
+		property.directives = values
+			.directives
+			.as_ref()
+			.map(SchemaFieldDirectives::to_rule);
 
 		// TODO: guard to ensure that the values are of either default, array or enum
 		// perhaps use https://graphql-rust.github.io/juniper/master/types/unions.html
@@ -40,17 +140,69 @@ impl From<SchemaDocumentPropertyValues> for SchemaProperty {
 		// Match through the types based on Array, Enum or default scalar values
 		match values.r#type {
 			SchemaPropertyType::Array => {
-				property.items = Some(SchemaNativeTypeArray {
-					r#type: values
-						.array_type
-						.unwrap_or(SchemaNativeType::String)
-						.as_str(),
+				let array_type = values.array_type.unwrap_or(SchemaNativeType::String);
+
+				let mut items = SchemaNativeTypeArray {
+					r#type: array_type.as_str(),
 					maximum: values.max_length,
-				});
+					properties: None,
+					required: None,
+					additional_properties: None,
+				};
+
+				// Lists of structured objects
+				if array_type == SchemaNativeType::Object {
+					let (properties, required) =
+						build_object_rule(values.properties.unwrap_or_default());
+
+					items.properties = Some(properties);
+					items.required = Some(required);
+					items.additional_properties = Some(false);
+				}
+
+				property.items = Some(items);
 			}
 			SchemaPropertyType::Enum => {
 				property.r#type = None;
 				property.r#enum = values.r#enum;
+				property.enum_descriptions = values.enum_descriptions.map(|descriptions| {
+					descriptions
+						.into_iter()
+						.map(|d| (d.value, serde_json::Value::String(d.description)))
+						.collect()
+				});
+			}
+			SchemaPropertyType::BigInt => {
+				property.format = Some(String::from("int64"));
+			}
+			SchemaPropertyType::DateTime => {
+				property.format = Some(String::from("date-time"));
+			}
+			SchemaPropertyType::Bytes => {
+				property.format = Some(String::from("byte"));
+			}
+			SchemaPropertyType::Decimal => {
+				property.format = Some(String::from("decimal"));
+			}
+			// The document holds the id of the uploaded file
+			SchemaPropertyType::File => {
+				property.format = Some(String::from("file"));
+				property.storage = values.storage;
+			}
+			// Schemaless, any nested structure is accepted
+			SchemaPropertyType::Json => {}
+			SchemaPropertyType::Object => {
+				let (properties, required) =
+					build_object_rule(values.properties.unwrap_or_default());
+
+				property.properties = Some(properties);
+				property.required = Some(required);
+				property.additional_properties = Some(false);
+			}
+			SchemaPropertyType::Uuid => {
+				property.format = Some(String::from("uuid"));
+				property.generate = values.generate.map(|v| v.as_str());
+				property.use_as_key = values.use_as_key;
 			}
 			// Default for string, integer and boolean
 			_ => {
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use anyhow::{anyhow, Error};
+use rust_arango::AqlQuery;
+use serde::Deserialize;
+use serde_json::value::Value as JsonValue;
+
+use crate::lib::database::DATABASE;
+use crate::lib::schema::get_entry;
+
+/// Documents of a seed file, matched with the existing ones by `_key` unless attributes to
+/// match them by are given, e.g. `code` for countries
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SeedFile {
+	Documents(Vec<JsonValue>),
+	Fixture(SeedFixture),
+}
+
+#[derive(Deserialize)]
+struct SeedFixture {
+	#[serde(default)]
+	r#match: Vec<String>,
+	documents: Vec<JsonValue>,
+}
+
+pub struct SeedReport {
+	pub collection: String,
+	pub inserted: usize,
+	pub updated: usize,
+}
+
+impl fmt::Display for SeedReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{}: {} inserted, {} updated",
+			self.collection, self.inserted, self.updated
+		)
+	}
+}
+
+/// Seed files of the directory by collection name, `countries.yaml` seeding `countries`
+fn get_seed_files(directory: &str) -> Result<Vec<(String, String)>, Error> {
+	let mut files = Vec::new();
+
+	for entry in std::fs::read_dir(directory)? {
+		let path = entry?.path();
+
+		let extension = path
+			.extension()
+			.and_then(|e| e.to_str())
+			.unwrap_or_default();
+
+		if !["json", "yaml", "yml"].contains(&extension) {
+			continue;
+		}
+
+		if let Some(collection) = path.file_stem().and_then(|s| s.to_str()) {
+			files.push((collection.to_string(), path.to_string_lossy().to_string()));
+		}
+	}
+
+	files.sort();
+
+	Ok(files)
+}
+
+/// Sets the `_key` of the documents matching an existing one, so they're updated
+async fn resolve_keys(
+	collection: &str,
+	documents: &mut [JsonValue],
+	attributes: &[String],
+) -> Result<(), Error> {
+	let query = AqlQuery::builder()
+		.query(
+			"FOR doc IN @documents
+				RETURN FIRST(
+					FOR d IN @@collection
+						FILTER MATCHES(d, KEEP(doc, @attributes))
+						LIMIT 1
+						RETURN d._key
+				)",
+		)
+		.bind_var("@collection", collection)
+		.bind_var("documents", JsonValue::Array(documents.to_vec()))
+		.bind_var("attributes", attributes.to_vec())
+		.build();
+
+	let keys: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	for (document, key) in documents.iter_mut().zip(keys) {
+		if let (Some(document), JsonValue::String(_)) = (document.as_object_mut(), &key) {
+			document.insert("_key".to_string(), key);
+		}
+	}
+
+	Ok(())
+}
+
+async fn seed_collection(collection: &str, file: SeedFile) -> Result<SeedReport, Error> {
+	if get_entry(collection).await.is_none() {
+		return Err(anyhow!("Collection {} not found", collection));
+	}
+
+	let (attributes, mut documents) = match file {
+		SeedFile::Documents(documents) => (Vec::new(), documents),
+		SeedFile::Fixture(fixture) => (fixture.r#match, fixture.documents),
+	};
+
+	if attributes.iter().any(|a| a != "_key") {
+		resolve_keys(collection, &mut documents, &attributes).await?;
+	}
+
+	// Documents without a key never match, so they're inserted
+	let query = AqlQuery::builder()
+		.query(
+			"FOR doc IN @documents
+				UPSERT { _key: doc._key }
+				INSERT doc
+				UPDATE doc
+				IN @@collection
+				RETURN OLD != null",
+		)
+		.bind_var("@collection", collection)
+		.bind_var("documents", JsonValue::Array(documents))
+		.build();
+
+	let results: Vec<bool> = DATABASE.get().await.database().aql_query(query).await?;
+
+	let updated = results.iter().filter(|u| **u).count();
+
+	Ok(SeedReport {
+		collection: collection.to_string(),
+		inserted: results.len() - updated,
+		updated,
+	})
+}
+
+/// Upserts the seed files of the directory, or only those of the given collections. Running
+/// them again updates the seeded documents instead of duplicating them
+pub async fn run_seeds(
+	directory: &str,
+	collections: Option<&[String]>,
+) -> Result<Vec<SeedReport>, Error> {
+	let mut reports = Vec::new();
+
+	for (collection, path) in get_seed_files(directory)? {
+		if let Some(collections) = collections {
+			if !collections.contains(&collection) {
+				continue;
+			}
+		}
+
+		let file: SeedFile = serde_yaml::from_str(&std::fs::read_to_string(&path)?)
+			.map_err(|e| anyhow!("Error parsing seed file {}: {}", path, e))?;
+
+		reports.push(seed_collection(&collection, file).await?);
+	}
+
+	Ok(reports)
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lib::database::schema::{CollectionSettings, SchemaKeyType};
+
+/// Options of the ArangoDB collection, only set on creation
+#[derive(Serialize, Deserialize, PartialEq, Clone, Default, GraphQLInputObject)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaCollectionSettings {
+	pub key_type: Option<SchemaKeyType>,
+	/// Only used by clusters
+	pub number_of_shards: Option<i32>,
+	/// Only used by clusters, the server default applies when omitted
+	pub replication_factor: Option<i32>,
+	pub wait_for_sync: Option<bool>,
+}
+
+impl From<SchemaCollectionSettings> for CollectionSettings {
+	fn from(settings: SchemaCollectionSettings) -> Self {
+		CollectionSettings {
+			key_type: settings.key_type.map(|k| k.as_str()),
+			number_of_shards: settings.number_of_shards.map(|n| n.max(1) as u32),
+			replication_factor: settings.replication_factor.map(|n| n.max(1) as u32),
+			wait_for_sync: settings.wait_for_sync,
+		}
+	}
+}
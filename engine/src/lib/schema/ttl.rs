@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lib::database::schema::Ttl;
+
+/// Expiry of the documents of a collection, e.g. sessions or tokens
+#[derive(Serialize, Deserialize, PartialEq, Clone, GraphQLInputObject)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaTtl {
+	/// Read-only property holding when the document expires, set on create
+	pub field: String,
+	/// Lifetime of the documents in seconds
+	pub expire_after: i32,
+}
+
+impl From<SchemaTtl> for Ttl {
+	fn from(ttl: SchemaTtl) -> Self {
+		Ttl {
+			field: ttl.field,
+			expire_after: ttl.expire_after.max(0) as u32,
+		}
+	}
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lib::database::schema::Variant;
+use crate::lib::schema::properties::{build_object_rule, SchemaDocumentProperty};
+
+/// A variant of a polymorphic collection, e.g. `TextBlock` when `type` is `text`
+#[derive(Serialize, Deserialize, PartialEq, Clone, GraphQLInputObject)]
+pub struct SchemaVariant {
+	/// GraphQL type name of the variant
+	pub name: String,
+	/// Value of the discriminator property for documents of this variant
+	pub value: String,
+	/// Properties only documents of this variant have
+	pub properties: Vec<SchemaDocumentProperty>,
+}
+
+impl From<SchemaVariant> for Variant {
+	fn from(variant: SchemaVariant) -> Self {
+		let (properties, required) = build_object_rule(variant.properties);
+
+		Variant {
+			name: variant.name,
+			value: variant.value,
+			properties: serde_json::Value::Object(properties),
+			required,
+		}
+	}
+}
@@ -0,0 +1,57 @@
+use anyhow::Error;
+use rust_arango::AqlQuery;
+use serde_json::Value as JsonValue;
+
+use crate::lib::database::DATABASE;
+
+/// Holds the state shared by the instances, like the version of the schema
+pub const SCHEMA_STATE_COLLECTION: &str = "alchemy_schema_state";
+const VERSION_KEY: &str = "version";
+
+/// Version of the schema, bumped by the changes of the meta API so the instances sharing the
+/// database know theirs is outdated. `0` until the first change
+pub async fn get_schema_version() -> Result<u64, Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(SCHEMA_STATE_COLLECTION).await.is_err() {
+		return Ok(0);
+	}
+
+	let query = AqlQuery::builder()
+		.query("RETURN DOCUMENT(@@collection, @key).version")
+		.bind_var("@collection", SCHEMA_STATE_COLLECTION)
+		.bind_var("key", VERSION_KEY)
+		.build();
+
+	let versions: Vec<JsonValue> = db.aql_query(query).await?;
+
+	Ok(versions
+		.first()
+		.and_then(JsonValue::as_u64)
+		.unwrap_or_default())
+}
+
+/// Bumps the version of the schema once it was changed, returning the new version
+pub async fn bump_schema_version() -> Result<u64, Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(SCHEMA_STATE_COLLECTION).await.is_err() {
+		db.create_collection(SCHEMA_STATE_COLLECTION).await?;
+	}
+
+	let query = AqlQuery::builder()
+		.query(
+			"UPSERT { _key: @key }
+				INSERT { _key: @key, version: 1 }
+				UPDATE { version: OLD.version + 1 }
+				IN @@collection
+				RETURN NEW.version",
+		)
+		.bind_var("@collection", SCHEMA_STATE_COLLECTION)
+		.bind_var("key", VERSION_KEY)
+		.build();
+
+	let versions: Vec<u64> = db.aql_query(query).await?;
+
+	Ok(versions.first().copied().unwrap_or_default())
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lib::database::schema::Versioning;
+
+/// Revision history of the documents of a collection, so editors can revert their changes
+#[derive(Serialize, Deserialize, PartialEq, Clone, GraphQLInputObject)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaVersioning {
+	/// Revisions kept per document, the oldest being removed first
+	pub max_revisions: Option<i32>,
+	/// Days the revisions are kept for
+	pub max_age_days: Option<i32>,
+}
+
+impl From<SchemaVersioning> for Versioning {
+	fn from(versioning: SchemaVersioning) -> Self {
+		Versioning {
+			max_revisions: versioning.max_revisions.map(|r| r.max(1) as u32),
+			max_age_days: versioning.max_age_days.map(|d| d.max(1) as u32),
+		}
+	}
+}
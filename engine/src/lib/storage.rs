@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use actix_web::web::Bytes;
+use anyhow::{anyhow, Error};
+use chrono::{SecondsFormat, Utc};
+use futures_util::{Stream, StreamExt};
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use rust_arango::AqlQuery;
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use serde::Deserialize;
+use serde_json::{json, value::Value as JsonValue};
+use sha2::Sha256;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::lib::database::DATABASE;
+use crate::lib::images::ImageTransform;
+use crate::lib::CONFIG;
+
+pub const FILES_COLLECTION: &str = "alchemy_files";
+
+/// Backend of the `File` properties not choosing one
+pub const DEFAULT_STORAGE: &str = "default";
+
+/// Prefix of the URLs stored for the files of private storages, they're signed when resolved
+pub const PRIVATE_URL_PREFIX: &str = "private:";
+
+/// Size of the chunks local files are streamed in
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Storage backend as declared in the storage file
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum StorageBackendEntry {
+	/// Directory on the local disk, its files are served by `/api/files/{path}`
+	Local { path: String },
+	/// Any S3-compatible bucket, `endpoint` being the URL of the service when it isn't AWS
+	#[serde(rename_all = "camelCase")]
+	S3 {
+		bucket: String,
+		region: String,
+		endpoint: Option<String>,
+		access_key: String,
+		secret_key: String,
+	},
+	/// Backblaze B2 bucket, accessed through its S3-compatible API
+	#[serde(rename_all = "camelCase")]
+	B2 {
+		bucket: String,
+		region: String,
+		key_id: String,
+		application_key: String,
+	},
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct StorageEntry {
+	#[serde(flatten)]
+	backend: StorageBackendEntry,
+	public_url: Option<String>,
+	/// Files are only accessible through signed URLs, valid for `url_expiration` seconds
+	#[serde(default)]
+	private: bool,
+	url_expiration: Option<u32>,
+}
+
+enum StorageBackend {
+	Local(PathBuf),
+	S3(Box<Bucket>),
+}
+
+/// Chunks of a file streamed from its storage
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, Error>>>>;
+
+pub struct Storage {
+	name: String,
+	backend: StorageBackend,
+	public_url: Option<String>,
+	private: bool,
+	/// Lifetime in seconds of the signed URLs
+	url_expiration: u32,
+}
+
+fn build_bucket(
+	name: &str,
+	region: String,
+	endpoint: Option<String>,
+	access_key: &str,
+	secret_key: &str,
+) -> Result<Box<Bucket>, Error> {
+	let region = match endpoint {
+		Some(endpoint) => Region::Custom { region, endpoint },
+		None => region.parse()?,
+	};
+
+	let credentials = Credentials::new(Some(access_key), Some(secret_key), None, None, None)?;
+
+	Ok(Box::new(
+		Bucket::new(name, region, credentials)?.with_path_style(),
+	))
+}
+
+impl Storage {
+	fn from_entry(name: String, entry: StorageEntry) -> Result<Self, Error> {
+		let backend = match entry.backend {
+			StorageBackendEntry::Local { path } => StorageBackend::Local(PathBuf::from(path)),
+			StorageBackendEntry::S3 {
+				bucket,
+				region,
+				endpoint,
+				access_key,
+				secret_key,
+			} => StorageBackend::S3(build_bucket(
+				&bucket,
+				region,
+				endpoint,
+				&access_key,
+				&secret_key,
+			)?),
+			StorageBackendEntry::B2 {
+				bucket,
+				region,
+				key_id,
+				application_key,
+			} => StorageBackend::S3(build_bucket(
+				&bucket,
+				region.clone(),
+				Some(format!("https://s3.{}.backblazeb2.com", region)),
+				&key_id,
+				&application_key,
+			)?),
+		};
+
+		Ok(Self {
+			name,
+			backend,
+			public_url: entry.public_url,
+			private: entry.private,
+			url_expiration: entry.url_expiration.unwrap_or(3600),
+		})
+	}
+
+	pub fn is_private(&self) -> bool {
+		self.private
+	}
+
+	pub async fn put(&self, path: &str, content: &[u8], mime: &str) -> Result<(), Error> {
+		match &self.backend {
+			StorageBackend::Local(dir) => {
+				let file = dir.join(path);
+
+				if let Some(parent) = file.parent() {
+					tokio::fs::create_dir_all(parent).await?;
+				}
+
+				tokio::fs::write(file, content).await?;
+			}
+			StorageBackend::S3(bucket) => {
+				let response = bucket
+					.put_object_with_content_type(path, content, mime)
+					.await?;
+
+				if response.status_code() >= 300 {
+					return Err(anyhow!(
+						"Storage responded with {} uploading {}",
+						response.status_code(),
+						path
+					));
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	pub async fn get(&self, path: &str) -> Result<Vec<u8>, Error> {
+		match &self.backend {
+			StorageBackend::Local(dir) => Ok(tokio::fs::read(dir.join(path)).await?),
+			StorageBackend::S3(bucket) => {
+				let response = bucket.get_object(path).await?;
+
+				match response.status_code() {
+					200 => Ok(response.bytes().to_vec()),
+					status => Err(anyhow!(
+						"Storage responded with {} reading {}",
+						status,
+						path
+					)),
+				}
+			}
+		}
+	}
+
+	/// Size in bytes of the file as stored
+	pub async fn size(&self, path: &str) -> Result<u64, Error> {
+		match &self.backend {
+			StorageBackend::Local(dir) => Ok(tokio::fs::metadata(dir.join(path)).await?.len()),
+			StorageBackend::S3(bucket) => {
+				let (head, status) = bucket.head_object(path).await?;
+
+				match (status, head.content_length) {
+					(200, Some(length)) => Ok(length as u64),
+					_ => Err(anyhow!(
+						"Storage responded with {} reading {}",
+						status,
+						path
+					)),
+				}
+			}
+		}
+	}
+
+	/// Streams the bytes of the file from `start` to `end`, both included, without
+	/// holding it in memory
+	pub async fn stream(&self, path: &str, start: u64, end: u64) -> Result<ByteStream, Error> {
+		match &self.backend {
+			StorageBackend::Local(dir) => {
+				let mut file = tokio::fs::File::open(dir.join(path)).await?;
+
+				file.seek(SeekFrom::Start(start)).await?;
+
+				let reader = file.take(end + 1 - start);
+
+				Ok(Box::pin(futures_util::stream::unfold(
+					reader,
+					|mut reader| async move {
+						let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+
+						match reader.read(&mut chunk).await {
+							Ok(0) => None,
+							Ok(read) => {
+								chunk.truncate(read);
+
+								Some((Ok(Bytes::from(chunk)), reader))
+							}
+							Err(e) => Some((Err(e.into()), reader)),
+						}
+					},
+				)))
+			}
+			StorageBackend::S3(bucket) => {
+				// The bucket client buffers the objects, their presigned URL streams them
+				let url = bucket.presign_get(path, 60, None)?;
+
+				let response = reqwest::Client::new()
+					.get(url)
+					.header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+					.send()
+					.await?;
+
+				if !response.status().is_success() {
+					return Err(anyhow!(
+						"Storage responded with {} reading {}",
+						response.status(),
+						path
+					));
+				}
+
+				Ok(Box::pin(
+					response
+						.bytes_stream()
+						.map(|chunk| chunk.map_err(Error::from)),
+				))
+			}
+		}
+	}
+
+	pub async fn delete(&self, path: &str) -> Result<(), Error> {
+		match &self.backend {
+			StorageBackend::Local(dir) => Ok(tokio::fs::remove_file(dir.join(path)).await?),
+			StorageBackend::S3(bucket) => {
+				bucket.delete_object(path).await?;
+
+				Ok(())
+			}
+		}
+	}
+
+	/// URL stored with the file, local files without a public URL are served by the API
+	fn get_url(&self, path: &str) -> String {
+		if self.private {
+			return format!("{}{}:{}", PRIVATE_URL_PREFIX, self.name, path);
+		}
+
+		match (&self.public_url, &self.backend) {
+			(Some(public_url), _) => format!("{}/{}", public_url.trim_end_matches('/'), path),
+			(None, StorageBackend::Local(_)) => format!("/api/files/{}", path),
+			(None, StorageBackend::S3(bucket)) => format!("{}/{}", bucket.url(), path),
+		}
+	}
+
+	/// Time-limited URL to download the file with `GET` or to upload it with `PUT`
+	fn sign_url(&self, path: &str, method: &str) -> Result<String, Error> {
+		match &self.backend {
+			StorageBackend::Local(_) => {
+				let expires = Utc::now().timestamp() + self.url_expiration as i64;
+
+				Ok(format!(
+					"/api/files/{}?expires={}&signature={}",
+					path,
+					expires,
+					sign_local_path(path, method, expires)?
+				))
+			}
+			StorageBackend::S3(bucket) => match method {
+				"PUT" => Ok(bucket.presign_put(path, self.url_expiration, None)?),
+				_ => Ok(bucket.presign_get(path, self.url_expiration, None)?),
+			},
+		}
+	}
+}
+
+/// HMAC of the local file URL, keyed with `FILE_SIGNING_KEY`. Without a key anyone could sign
+/// them, so there's none
+fn get_local_mac(path: &str, method: &str, expires: i64) -> Option<Hmac<Sha256>> {
+	let key = CONFIG.get_file_signing_key()?;
+	let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).ok()?;
+
+	mac.update(format!("{}:{}:{}", method, path, expires).as_bytes());
+
+	Some(mac)
+}
+
+/// Signature of the local file URLs
+fn sign_local_path(path: &str, method: &str, expires: i64) -> Result<String, Error> {
+	let mac = get_local_mac(path, method, expires)
+		.ok_or_else(|| anyhow!("FILE_SIGNING_KEY is required to sign file URLs"))?;
+
+	Ok(base64::encode_config(
+		mac.finalize().into_bytes(),
+		base64::URL_SAFE_NO_PAD,
+	))
+}
+
+/// Checks a signed local file URL, it must not be expired
+pub fn verify_local_signature(path: &str, method: &str, expires: i64, signature: &str) -> bool {
+	if expires < Utc::now().timestamp() {
+		return false;
+	}
+
+	let signature = match base64::decode_config(signature, base64::URL_SAFE_NO_PAD) {
+		Ok(signature) => signature,
+		Err(_) => return false,
+	};
+
+	get_local_mac(path, method, expires).map_or(false, |mac| mac.verify_slice(&signature).is_ok())
+}
+
+/// Signed download URL of a file of a private storage, from the URL stored with it
+pub fn sign_private_url(url: &str) -> Option<String> {
+	let (storage, path) = url.strip_prefix(PRIVATE_URL_PREFIX)?.split_once(':')?;
+
+	get_storage(storage).ok()?.sign_url(path, "GET").ok()
+}
+
+fn load_storages() -> Result<HashMap<String, Storage>, Error> {
+	let entries: HashMap<String, StorageEntry> = match CONFIG.get_storage_file() {
+		Some(path) => {
+			let contents = std::fs::read_to_string(path)
+				.map_err(|e| anyhow!("Error reading storage file {}: {}", path, e))?;
+
+			serde_yaml::from_str(&contents)
+				.map_err(|e| anyhow!("Invalid storage file {}: {}", path, e))?
+		}
+		None => HashMap::new(),
+	};
+
+	let mut storages = HashMap::new();
+
+	for (name, entry) in entries {
+		let storage = Storage::from_entry(name.clone(), entry)
+			.map_err(|e| anyhow!("Invalid storage {}: {}", name, e))?;
+
+		storages.insert(name, storage);
+	}
+
+	// Uploads work out of the box, on the local disk
+	storages
+		.entry(DEFAULT_STORAGE.to_string())
+		.or_insert_with(|| Storage {
+			name: DEFAULT_STORAGE.to_string(),
+			backend: StorageBackend::Local(PathBuf::from("uploads")),
+			public_url: None,
+			private: false,
+			url_expiration: 3600,
+		});
+
+	Ok(storages)
+}
+
+lazy_static! {
+	/// Storage backends by name, declared in `STORAGE_FILE`. The error of an invalid file is
+	/// kept to fail the startup, see `check_storages`
+	static ref STORAGES: Result<HashMap<String, Storage>, String> =
+		load_storages().map_err(|e| e.to_string());
+}
+
+/// Loads the storages declared in `STORAGE_FILE`, called at startup so an invalid file stops
+/// the server instead of failing the uploads
+pub fn check_storages() -> Result<(), Error> {
+	match STORAGES.as_ref() {
+		Ok(_) => Ok(()),
+		Err(e) => Err(anyhow!("{}", e)),
+	}
+}
+
+pub fn get_storage(name: &str) -> Result<&'static Storage, Error> {
+	STORAGES
+		.as_ref()
+		.map_err(|e| anyhow!("{}", e))?
+		.get(name)
+		.ok_or_else(|| anyhow!("Storage {} not found", name))
+}
+
+/// Path of a new file in its storage, the name is only kept for its extension as it's sent
+/// by the client
+fn get_file_path(id: &str, name: &str) -> String {
+	match name.rsplit_once('.') {
+		Some((_, extension)) if extension.chars().all(|c| c.is_ascii_alphanumeric()) => {
+			format!("{}.{}", id, extension.to_lowercase())
+		}
+		_ => id.to_string(),
+	}
+}
+
+async fn insert_file(
+	storage: &Storage,
+	collection: &str,
+	id: &str,
+	name: &str,
+	mime: &str,
+	size: Option<usize>,
+	path: &str,
+) -> Result<(), Error> {
+	let db = DATABASE.get().await.database();
+
+	if db.collection(FILES_COLLECTION).await.is_err() {
+		db.create_collection(FILES_COLLECTION).await?;
+	}
+
+	let file = json!({
+		"_key": id,
+		"id": id,
+		"name": name,
+		"url": storage.get_url(path),
+		"mime": mime,
+		"size": size,
+		"storage": storage.name,
+		"collection": collection,
+		"path": path,
+		"createdAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+	});
+
+	let query = AqlQuery::builder()
+		.query("INSERT @file IN @@collection")
+		.bind_var("@collection", FILES_COLLECTION)
+		.bind_var("file", file)
+		.build();
+
+	let _: Vec<JsonValue> = db.aql_query(query).await?;
+
+	Ok(())
+}
+
+/// Uploads the file of a collection to the storage and saves its metadata, returning its id
+pub async fn save_file(
+	storage_name: &str,
+	collection: &str,
+	name: &str,
+	mime: &str,
+	content: &[u8],
+) -> Result<String, Error> {
+	let storage = get_storage(storage_name)?;
+
+	let id = uuid::Uuid::new_v4().simple().to_string();
+	let path = get_file_path(&id, name);
+
+	storage.put(&path, content, mime).await?;
+
+	insert_file(
+		storage,
+		collection,
+		&id,
+		name,
+		mime,
+		Some(content.len()),
+		&path,
+	)
+	.await?;
+
+	Ok(id)
+}
+
+/// Saves the metadata of a file the client uploads itself, returning its id and the signed
+/// URL to `PUT` its content to. Its size is unknown until then
+pub async fn create_upload_url(
+	storage_name: &str,
+	collection: &str,
+	name: &str,
+	mime: &str,
+) -> Result<(String, String), Error> {
+	let storage = get_storage(storage_name)?;
+
+	let id = uuid::Uuid::new_v4().simple().to_string();
+	let path = get_file_path(&id, name);
+
+	let url = storage.sign_url(&path, "PUT")?;
+
+	insert_file(storage, collection, &id, name, mime, None, &path).await?;
+
+	Ok((id, url))
+}
+
+pub async fn get_file(id: &str) -> Option<JsonValue> {
+	let query = AqlQuery::builder()
+		.query("RETURN DOCUMENT(@@collection, @id)")
+		.bind_var("@collection", FILES_COLLECTION)
+		.bind_var("id", id)
+		.build();
+
+	let files: Vec<JsonValue> = DATABASE
+		.get()
+		.await
+		.database()
+		.aql_query(query)
+		.await
+		.unwrap_or_default();
+
+	files.into_iter().next().filter(|f| !f.is_null())
+}
+
+/// Reads the content of the file from its storage, along with its metadata
+pub async fn read_file(file: &JsonValue) -> Result<Vec<u8>, Error> {
+	get_storage(file["storage"].as_str().unwrap_or(DEFAULT_STORAGE))?
+		.get(file["path"].as_str().unwrap_or_default())
+		.await
+}
+
+/// Removes the transformed images cached for the file
+async fn delete_derivatives(storage: &Storage, file: &JsonValue) -> Result<(), Error> {
+	for path in file["derivatives"].as_array().into_iter().flatten() {
+		storage.delete(path.as_str().unwrap_or_default()).await?;
+	}
+
+	Ok(())
+}
+
+/// Transformed image of the file, cached in its storage the first time it's requested.
+/// Returns its content and MIME type
+pub async fn get_derivative(
+	file: &JsonValue,
+	transform: &ImageTransform,
+) -> Result<(Vec<u8>, &'static str), Error> {
+	let storage = get_storage(file["storage"].as_str().unwrap_or(DEFAULT_STORAGE))?;
+	let id = file["id"].as_str().unwrap_or_default();
+	let mime = file["mime"].as_str().unwrap_or_default().to_string();
+
+	let path = transform.get_derivative_path(id, &mime);
+	let derivative_mime = transform.get_mime(&mime);
+
+	let is_cached = file["derivatives"]
+		.as_array()
+		.map_or(false, |d| d.iter().any(|p| p == path.as_str()));
+
+	if is_cached {
+		if let Ok(content) = storage.get(&path).await {
+			return Ok((content, derivative_mime));
+		}
+	}
+
+	let source = storage
+		.get(file["path"].as_str().unwrap_or_default())
+		.await?;
+
+	let content = actix_web::rt::task::spawn_blocking({
+		let transform = transform.clone();
+
+		move || transform.apply(&source, &mime)
+	})
+	.await??;
+
+	storage.put(&path, &content, derivative_mime).await?;
+
+	let query = AqlQuery::builder()
+		.query(
+			"FOR f IN @@collection
+				FILTER f._key == @id
+				UPDATE f WITH { derivatives: APPEND(f.derivatives, [@path], true) } IN @@collection",
+		)
+		.bind_var("@collection", FILES_COLLECTION)
+		.bind_var("id", id)
+		.bind_var("path", path)
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok((content, derivative_mime))
+}
+
+/// Stores the content of a file created with a signed upload URL
+pub async fn write_file(file: &JsonValue, content: &[u8]) -> Result<(), Error> {
+	let storage = get_storage(file["storage"].as_str().unwrap_or(DEFAULT_STORAGE))?;
+
+	delete_derivatives(storage, file).await?;
+
+	storage
+		.put(
+			file["path"].as_str().unwrap_or_default(),
+			content,
+			file["mime"].as_str().unwrap_or("application/octet-stream"),
+		)
+		.await?;
+
+	let query = AqlQuery::builder()
+		.query("UPDATE @id WITH { size: @size, derivatives: [] } IN @@collection")
+		.bind_var("@collection", FILES_COLLECTION)
+		.bind_var("id", file["id"].clone())
+		.bind_var("size", content.len())
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(())
+}
+
+/// Removes the file and its transformed images from its storage along with its metadata,
+/// documents keep referencing it
+pub async fn delete_file(id: &str) -> Result<(), Error> {
+	let file = get_file(id)
+		.await
+		.ok_or_else(|| anyhow!("File {} not found", id))?;
+
+	let storage = get_storage(file["storage"].as_str().unwrap_or(DEFAULT_STORAGE))?;
+
+	delete_derivatives(storage, &file).await?;
+
+	storage
+		.delete(file["path"].as_str().unwrap_or_default())
+		.await?;
+
+	let query = AqlQuery::builder()
+		.query("REMOVE @id IN @@collection")
+		.bind_var("@collection", FILES_COLLECTION)
+		.bind_var("id", id)
+		.build();
+
+	let _: Vec<JsonValue> = DATABASE.get().await.database().aql_query(query).await?;
+
+	Ok(())
+}
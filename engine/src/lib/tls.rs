@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Error};
+use arc_swap::ArcSwap;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::{self, CertifiedKey};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+
+/// How often the certificate files are checked for renewals, e.g. by certbot
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+fn read_certified_key(cert_file: &str, key_file: &str) -> Result<CertifiedKey, Error> {
+	let certs: Vec<Certificate> =
+		rustls_pemfile::certs(&mut BufReader::new(File::open(cert_file)?))?
+			.into_iter()
+			.map(Certificate)
+			.collect();
+
+	if certs.is_empty() {
+		return Err(anyhow!("No certificate found in {}", cert_file));
+	}
+
+	let key = rustls_pemfile::read_all(&mut BufReader::new(File::open(key_file)?))?
+		.into_iter()
+		.find_map(|item| match item {
+			Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+			_ => None,
+		})
+		.ok_or_else(|| anyhow!("No private key found in {}", key_file))?;
+
+	let key = sign::any_supported_type(&key)
+		.map_err(|_| anyhow!("Unsupported private key in {}", key_file))?;
+
+	Ok(CertifiedKey::new(certs, key))
+}
+
+fn get_modified(file: &str) -> Option<SystemTime> {
+	std::fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+/// Certificate served to every client, swapped when its files change so renewed certificates
+/// are used without restarting
+pub struct CertificateResolver {
+	cert_file: String,
+	key_file: String,
+	key: ArcSwap<CertifiedKey>,
+}
+
+impl CertificateResolver {
+	pub fn new(cert_file: &str, key_file: &str) -> Result<Self, Error> {
+		Ok(Self {
+			cert_file: cert_file.to_string(),
+			key_file: key_file.to_string(),
+			key: ArcSwap::from_pointee(read_certified_key(cert_file, key_file)?),
+		})
+	}
+
+	/// Reloads the certificate whenever its files are modified, a renewal written halfway is
+	/// retried on the next check
+	pub async fn watch(self: Arc<Self>) {
+		let mut modified = (get_modified(&self.cert_file), get_modified(&self.key_file));
+
+		loop {
+			tokio::time::sleep(RELOAD_INTERVAL).await;
+
+			let current = (get_modified(&self.cert_file), get_modified(&self.key_file));
+
+			if current == modified {
+				continue;
+			}
+
+			match read_certified_key(&self.cert_file, &self.key_file) {
+				Ok(key) => {
+					self.key.store(Arc::new(key));
+					modified = current;
+
+					tracing::info!("Reloaded TLS certificate {}", self.cert_file);
+				}
+				Err(e) => tracing::error!("Error reloading TLS certificate: {}", e),
+			}
+		}
+	}
+}
+
+impl ResolvesServerCert for CertificateResolver {
+	fn resolve(&self, _: ClientHello) -> Option<Arc<CertifiedKey>> {
+		Some(self.key.load_full())
+	}
+}
+
+/// Config of the HTTPS servers, HTTP/2 being negotiated with the clients supporting it
+pub fn get_server_config(resolver: Arc<CertificateResolver>) -> ServerConfig {
+	ServerConfig::builder()
+		.with_safe_defaults()
+		.with_no_client_auth()
+		.with_cert_resolver(resolver)
+}
@@ -10,7 +10,7 @@ extern crate derivative;
 extern crate juniper_codegen;
 
 use actix_cors::Cors;
-use actix_web::dev::Server;
+use actix_web::dev::{Server, ServerHandle};
 use actix_web::{
 	http::header,
 	middleware,
@@ -23,9 +23,18 @@ use arangodb_events_rs::{
 	TriggerAuthentication,
 };
 
-use std::sync::{Arc, Mutex};
+use arc_swap::ArcSwap;
+use clap::Parser;
+use futures_util::future::join_all;
+use rustls::ServerConfig;
+use std::fmt;
+use std::os::unix::fs::FileTypeExt;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
 
 mod api;
+mod cli;
 mod lib;
 mod meta;
 
@@ -34,91 +43,460 @@ use lib::CONFIG;
 
 #[tokio::main]
 async fn main() {
+	// Parsed before the config is loaded so `--help` works without it
+	let cli = cli::Cli::parse_from(lib::config::get_command_args());
+
+	lib::logging::init_logging();
+
 	pluralizer::initialize();
 
+	match cli.command.unwrap_or(cli::Command::Serve) {
+		cli::Command::Serve => serve().await,
+		command => cli::run(command).await,
+	}
+}
+
+async fn serve() {
+	lib::storage::check_storages().expect("Error loading the storages");
+
 	let app_port = CONFIG.app_port.parse::<u16>().unwrap_or(8080);
 
-	println!("Starting Alchemy on port {:?}", app_port);
+	let tls = CONFIG.get_tls_files().map(|(cert_file, key_file)| {
+		let resolver = Arc::new(
+			lib::tls::CertificateResolver::new(cert_file, key_file)
+				.expect("Error loading TLS certificate"),
+		);
+
+		tokio::spawn(resolver.clone().watch());
+
+		lib::tls::get_server_config(resolver)
+	});
+
+	let listener = match CONFIG.get_app_socket() {
+		Some(path) => Listener::Unix(path.clone()),
+		None => Listener::Tcp("0.0.0.0".to_string(), app_port),
+	};
+
+	tracing::info!(
+		"Starting Alchemy on {}{}",
+		listener,
+		if tls.is_some() { " over HTTPS" } else { "" }
+	);
+
+	if let Some(schema_file) = CONFIG.get_schema_file() {
+		apply_schema_file(schema_file)
+			.await
+			.expect("Error applying schema file");
+	}
+
+	if let Some(seeds_dir) = CONFIG.get_seeds_dir() {
+		for report in lib::schema::run_seeds(seeds_dir, None)
+			.await
+			.expect("Error running seeds")
+		{
+			tracing::info!("Seeded {}", report);
+		}
+	}
+
+	lib::events::refresh_webhooks().await;
+
+	if CONFIG.is_audit_log_enabled() {
+		lib::audit::init_audit_log()
+			.await
+			.expect("Error setting up the audit log");
+	}
+
+	api::schema::load_schema_version().await;
 
 	let map = generate_sdl().await;
-	let api_schema = Data::new(Mutex::new(api::schema::schema(map.clone())));
-
-	let (http, _) = tokio::join!(
-		get_http_server(
-			app_port,
-			api_schema.clone(),
-			Data::new(meta::graphql::schema())
-		),
-		run_arangodb_listener(api_schema)
+	let api_schema = Data::new(ArcSwap::from_pointee(api::schema::schema(map.clone())));
+	let meta_schema = Data::new(meta::graphql::schema());
+
+	if let Some(grpc_port) = CONFIG.get_grpc_port() {
+		let grpc_host = CONFIG.get_grpc_host();
+
+		tracing::info!(
+			"Starting Alchemy gRPC server on {}:{}",
+			grpc_host,
+			grpc_port
+		);
+
+		tokio::spawn(api::grpc::run_grpc_server(
+			grpc_host,
+			grpc_port,
+			api_schema.clone().into_inner(),
+		));
+	}
+
+	let meta_listener = match (CONFIG.get_meta_socket(), CONFIG.get_meta_port()) {
+		(Some(path), _) => Some(Listener::Unix(path.clone())),
+		(None, Some(meta_port)) => {
+			let meta_host = CONFIG
+				.meta_host
+				.clone()
+				.filter(|h| !h.is_empty())
+				.unwrap_or_else(|| "127.0.0.1".to_string());
+
+			Some(Listener::Tcp(meta_host, meta_port))
+		}
+		(None, None) => None,
+	};
+
+	let http = get_http_server(
+		&listener,
+		tls.clone(),
+		api_schema.clone(),
+		meta_listener.is_none().then(|| meta_schema.clone()),
 	);
 
-	http.expect("Error running HTTP Server");
+	let meta_http = meta_listener.map(|meta_listener| {
+		tracing::info!("Starting Alchemy meta API on {}", meta_listener);
+
+		get_meta_http_server(&meta_listener, tls, meta_schema, api_schema.clone())
+	});
+
+	let mut handles = vec![http.handle()];
+
+	if let Some(meta_http) = &meta_http {
+		handles.push(meta_http.handle());
+	}
+
+	tokio::spawn(handle_signals(handles, api_schema.clone()));
+
+	let scheduler = api::scheduler::run_scheduler(api_schema.clone().into_inner());
+
+	let servers = async {
+		let (http, meta_http) = tokio::join!(http, async {
+			match meta_http {
+				Some(meta_http) => meta_http.await,
+				None => Ok(()),
+			}
+		});
+
+		http.expect("Error running HTTP Server");
+		meta_http.expect("Error running meta HTTP Server");
+	};
+
+	// The background tasks are stopped once the servers are drained
+	tokio::select! {
+		_ = servers => (),
+		_ = async {
+			tokio::join!(
+				api::cluster::run_cluster_subscriber(api_schema.clone().into_inner()),
+				api::cluster::run_schema_watcher(api_schema.clone().into_inner()),
+				run_arangodb_listener(api_schema),
+				lib::events::run_event_worker(),
+				scheduler
+			)
+		} => (),
+	}
+
+	shutdown().await;
+}
+
+/// Reloads the schema on SIGHUP, and stops the HTTP servers on SIGTERM or SIGINT once the
+/// requests in flight are answered
+async fn handle_signals(servers: Vec<ServerHandle>, schema: Data<ArcSwap<api::schema::Schema>>) {
+	let mut terminate = signal(SignalKind::terminate()).expect("Error listening to SIGTERM");
+	let mut interrupt = signal(SignalKind::interrupt()).expect("Error listening to SIGINT");
+	let mut hangup = signal(SignalKind::hangup()).expect("Error listening to SIGHUP");
+
+	loop {
+		tokio::select! {
+			_ = hangup.recv() => reload(&schema).await,
+			_ = terminate.recv() => break,
+			_ = interrupt.recv() => break,
+		}
+	}
+
+	tracing::info!("Shutting down, waiting for the requests in flight");
+
+	join_all(servers.iter().map(|server| server.stop(true))).await;
+}
+
+/// Reloads what can change without a restart: the schema file, the API schemas, the webhooks
+/// and the persisted queries file. Other settings are only read on startup
+async fn reload(schema: &ArcSwap<api::schema::Schema>) {
+	tracing::info!("Reloading the schema");
+
+	if let Some(schema_file) = CONFIG.get_schema_file() {
+		if let Err(e) = apply_schema_file(schema_file).await {
+			tracing::error!("Error applying schema file {}: {}", schema_file, e);
+		}
+	}
+
+	api::schema::reload_schema(schema).await;
+	api::tenants::TENANT_SCHEMAS.clear().await;
+	lib::events::refresh_webhooks().await;
+	api::persisted::PERSISTED_QUERIES.reload();
+}
+
+/// Delivers the due events before exiting, the ones left are delivered by the next instance
+async fn shutdown() {
+	if tokio::time::timeout(CONFIG.get_shutdown_timeout(), lib::events::flush_events())
+		.await
+		.is_err()
+	{
+		tracing::warn!("Timed out delivering the due events");
+	}
+
+	tracing::info!("Alchemy stopped");
+
+	lib::logging::shutdown_logging();
+}
+
+/// Prints the differences between the schema file and the database, applying them when enabled
+async fn apply_schema_file(path: &str) -> Result<(), anyhow::Error> {
+	let definition = std::fs::read_to_string(path)?;
+	let declared = lib::schema::parse_schema(&definition)?;
+	let changes = lib::schema::diff_schema(&declared, false).await;
+
+	if changes.is_empty() {
+		tracing::info!("Schema is up to date with {}", path);
+
+		api::health::PENDING_SCHEMA_CHANGES.store(0, Ordering::Relaxed);
+
+		return Ok(());
+	}
+
+	for change in &changes {
+		tracing::info!("Schema change: {}", change);
+	}
+
+	if CONFIG.is_schema_apply_enabled() {
+		lib::schema::apply_schema_changes(changes, Some(path.to_string())).await?;
+
+		api::health::PENDING_SCHEMA_CHANGES.store(0, Ordering::Relaxed);
+	} else {
+		// Reported by the readiness route until the changes are applied
+		api::health::PENDING_SCHEMA_CHANGES.store(changes.len(), Ordering::Relaxed);
+	}
+
+	Ok(())
+}
+
+fn get_cors() -> Cors {
+	let origins = CONFIG.get_cors_origins();
+
+	let cors = match origins.is_empty() {
+		true => Cors::default().allow_any_origin(),
+		false => origins
+			.iter()
+			.fold(Cors::default(), |cors, origin| cors.allowed_origin(origin)),
+	};
+
+	let cors = match CONFIG.get_tenant_source() {
+		Some("header") => cors.allowed_header(CONFIG.get_tenant_header().as_str()),
+		_ => cors,
+	};
+
+	cors.allowed_methods(vec!["POST", "GET", "PUT", "PATCH", "DELETE"])
+		.allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
+		.allowed_header(header::CONTENT_TYPE)
+		.allowed_header(meta::graphql::server::ADMIN_SECRET_HEADER)
+		.allowed_header(meta::graphql::server::AUTHOR_HEADER)
+		.allowed_header(api::server::READ_LEADER_HEADER)
+		.supports_credentials()
+		.max_age(3600)
+}
+
+fn configure_meta(cfg: &mut web::ServiceConfig) {
+	cfg.service(
+		web::resource("/meta/graphql")
+			.route(web::post().to(meta::graphql::server::graphql_meta_route))
+			.route(web::get().to(meta::graphql::server::graphql_meta_route)),
+	)
+	.service(
+		web::resource("/meta/playground")
+			.route(web::get().to(meta::graphql::server::playground_meta_route)),
+	)
+	.configure(meta::admin::configure_admin_ui);
+}
+
+fn configure_api_playground(cfg: &mut web::ServiceConfig) {
+	if CONFIG.is_playground_enabled() {
+		cfg.service(
+			web::resource("/api/playground")
+				.route(web::get().to(api::server::playground_api_route)),
+		);
+	}
+}
+
+/// Address a server listens on
+enum Listener {
+	Tcp(String, u16),
+	/// Path of a unix socket, restricting the server to the processes allowed to open it
+	Unix(String),
+}
+
+impl fmt::Display for Listener {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Listener::Tcp(host, port) => write!(f, "{}:{}", host, port),
+			Listener::Unix(path) => write!(f, "unix:{}", path),
+		}
+	}
+}
+
+/// Removes the socket file left by a previous run, binding fails when it exists
+fn remove_stale_socket(path: &str) {
+	let is_socket = std::fs::metadata(path)
+		.map(|m| m.file_type().is_socket())
+		.unwrap_or(false);
+
+	if is_socket {
+		if let Err(e) = std::fs::remove_file(path) {
+			tracing::warn!("Error removing socket {}: {}", path, e);
+		}
+	}
 }
 
 fn get_http_server(
-	port: u16,
-	api_schema: Data<Mutex<api::schema::Schema>>,
-	meta_schema: Data<meta::graphql::Schema>,
+	listener: &Listener,
+	tls: Option<ServerConfig>,
+	api_schema: Data<ArcSwap<api::schema::Schema>>,
+	meta_schema: Option<Data<meta::graphql::Schema>>,
 ) -> Server {
-	HttpServer::new(move || {
-		App::new()
-			.app_data(meta_schema.clone())
+	let server = HttpServer::new(move || {
+		let app = App::new()
 			.app_data(api_schema.clone())
-			.wrap(
-				Cors::default()
-					.allow_any_origin()
-					.allowed_methods(vec!["POST", "GET"])
-					.allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
-					.allowed_header(header::CONTENT_TYPE)
-					.supports_credentials()
-					.max_age(3600),
-			)
+			.wrap_fn(api::tenants::scope_request)
+			.wrap_fn(api::server::scope_request_reads)
+			.wrap(get_cors())
 			.wrap(middleware::Compress::default())
 			.wrap(middleware::Logger::default())
+			.service(web::resource("/healthz").route(web::get().to(api::health::healthz_route)))
+			.service(web::resource("/readyz").route(web::get().to(api::health::readyz_route)))
 			.service(
 				web::resource("/api/graphql")
 					.route(web::post().to(api::server::graphql_api_route))
 					.route(web::get().to(api::server::graphql_api_route)),
 			)
 			.service(
-				web::resource("/api/playground")
-					.route(web::get().to(api::server::playground_api_route)),
+				web::resource("/api/graphql/upload")
+					.route(web::post().to(api::files::graphql_upload_route)),
+			)
+			.service(
+				web::resource("/api/files/upload-url")
+					.route(web::post().to(api::files::upload_url_route)),
+			)
+			.service(
+				web::resource("/api/files/{id}/download")
+					.route(web::get().to(api::files::file_download_route)),
+			)
+			.service(
+				web::resource("/api/files/{path}")
+					.app_data(web::PayloadConfig::new(CONFIG.get_upload_max_size()))
+					.route(web::get().to(api::files::file_route))
+					.route(web::put().to(api::files::file_upload_route)),
+			)
+			.service(
+				web::resource("/api/schema.graphql").route(web::get().to(api::server::sdl_route)),
+			)
+			.service(
+				web::resource("/api/openapi.json")
+					.route(web::get().to(api::openapi::openapi_route)),
+			)
+			.service(
+				web::resource("/api/grpc/alchemy.proto")
+					.route(web::get().to(api::grpc::proto_route)),
+			)
+			.service(
+				web::resource("/api/rest/{collection}")
+					.route(web::get().to(api::rest::rest_list_route))
+					.route(web::post().to(api::rest::rest_create_route)),
+			)
+			.service(
+				web::resource("/api/rest/{collection}/{key}")
+					.route(web::get().to(api::rest::rest_get_route))
+					.route(web::patch().to(api::rest::rest_update_route))
+					.route(web::delete().to(api::rest::rest_delete_route)),
 			)
 			.service(
-				web::resource("/meta/graphql")
-					.route(web::post().to(meta::graphql::server::graphql_meta_route))
-					.route(web::get().to(meta::graphql::server::graphql_meta_route)),
+				web::resource("/api/export/{collection}")
+					.route(web::get().to(api::export::export_route)),
 			)
 			.service(
-				web::resource("/meta/playground")
-					.route(web::get().to(meta::graphql::server::playground_meta_route)),
+				web::resource("/api/import/{collection}")
+					.route(web::post().to(api::import::import_route)),
 			)
+			.configure(configure_api_playground)
+			.configure(api::auth::oidc::configure_oidc);
+
+		// The meta API is only mounted here when it isn't served on its own address
+		if let Some(meta_schema) = &meta_schema {
+			app.app_data(meta_schema.clone()).configure(configure_meta)
+		} else {
+			app
+		}
 	})
-	.bind(("0.0.0.0", port))
+	.disable_signals()
+	.shutdown_timeout(CONFIG.get_shutdown_timeout().as_secs());
+
+	// HTTP/2 is negotiated over TLS with the clients supporting it, local sockets are plain
+	match (listener, tls) {
+		(Listener::Tcp(host, port), Some(config)) => {
+			server.bind_rustls((host.as_str(), *port), config)
+		}
+		(Listener::Tcp(host, port), None) => server.bind((host.as_str(), *port)),
+		(Listener::Unix(path), _) => {
+			remove_stale_socket(path);
+
+			server.bind_uds(path)
+		}
+	}
 	.expect("Error binding HTTP server address")
 	.run()
 }
 
+fn get_meta_http_server(
+	listener: &Listener,
+	tls: Option<ServerConfig>,
+	meta_schema: Data<meta::graphql::Schema>,
+	api_schema: Data<ArcSwap<api::schema::Schema>>,
+) -> Server {
+	let server = HttpServer::new(move || {
+		App::new()
+			.app_data(meta_schema.clone())
+			.app_data(api_schema.clone())
+			.wrap(get_cors())
+			.wrap(middleware::Compress::default())
+			.wrap(middleware::Logger::default())
+			.configure(configure_meta)
+	})
+	.disable_signals()
+	.shutdown_timeout(CONFIG.get_shutdown_timeout().as_secs());
+
+	match (listener, tls) {
+		(Listener::Tcp(host, port), Some(config)) => {
+			server.bind_rustls((host.as_str(), *port), config)
+		}
+		(Listener::Tcp(host, port), None) => server.bind((host.as_str(), *port)),
+		(Listener::Unix(path), _) => {
+			remove_stale_socket(path);
+
+			server.bind_uds(path)
+		}
+	}
+	.expect("Error binding meta HTTP server address")
+	.run()
+}
+
 pub struct ArangoDBListener;
 
 impl Handler for ArangoDBListener {
-	type Context = Arc<Mutex<api::schema::Schema>>;
+	type Context = Arc<ArcSwap<api::schema::Schema>>;
 
 	fn call<'a>(ctx: &'a Self::Context, _: &'a DocumentOperation) -> AsyncHandlerOutput<'a> {
 		Box::pin(async move {
-			println!("Schema update requested");
-
-			let mut schema = ctx.lock().unwrap();
-
-			let map = generate_sdl().await;
-			let api_schema = api::schema::schema(map.clone());
+			tracing::info!("Schema update requested");
 
-			*schema = api_schema;
+			api::schema::reload_schema(ctx).await;
 		})
 	}
 }
 
-async fn run_arangodb_listener(schema: Data<Mutex<api::schema::Schema>>) {
+async fn run_arangodb_listener(schema: Data<ArcSwap<api::schema::Schema>>) {
 	let mut trigger = Trigger::new_auth(
 		CONFIG.db_host.as_str(),
 		CONFIG.db_name.as_str(),
@@ -136,9 +514,37 @@ async fn run_arangodb_listener(schema: Data<Mutex<api::schema::Schema>>) {
 	trigger.subscribe_to::<ArangoDBListener>(
 		HandlerEvent::Remove,
 		"alchemy_collections",
-		context_data,
+		context_data.clone(),
+	);
+
+	// Relationships are part of the schema as well
+	trigger.subscribe_to::<ArangoDBListener>(
+		HandlerEvent::InsertOrReplace,
+		"alchemy_edges",
+		context_data.clone(),
 	);
 
+	trigger.subscribe_to::<ArangoDBListener>(
+		HandlerEvent::Remove,
+		"alchemy_edges",
+		context_data.clone(),
+	);
+
+	// So are the actions, hooks and the plugins they run
+	for collection in ["alchemy_actions", "alchemy_hooks", "alchemy_plugins"] {
+		trigger.subscribe_to::<ArangoDBListener>(
+			HandlerEvent::InsertOrReplace,
+			collection,
+			context_data.clone(),
+		);
+
+		trigger.subscribe_to::<ArangoDBListener>(
+			HandlerEvent::Remove,
+			collection,
+			context_data.clone(),
+		);
+	}
+
 	trigger.init().await.unwrap();
 
 	loop {
@@ -0,0 +1,28 @@
+use actix_files::{Files, NamedFile};
+use actix_web::dev::{fn_service, ServiceRequest, ServiceResponse};
+use actix_web::web;
+use std::path::Path;
+
+use crate::lib::CONFIG;
+
+/// Serves the built admin console from `ADMIN_UI_DIR` under `/admin`, next to the meta API it
+/// calls. Paths without a file are answered with `index.html` so the console handles its
+/// own routes
+pub fn configure_admin_ui(cfg: &mut web::ServiceConfig) {
+	let dir = match CONFIG.get_admin_ui_dir() {
+		Some(dir) => dir,
+		None => return,
+	};
+
+	cfg.service(
+		Files::new("/admin", dir)
+			.index_file("index.html")
+			.default_handler(fn_service(|req: ServiceRequest| async {
+				let (req, _) = req.into_parts();
+				let index = Path::new(CONFIG.get_admin_ui_dir().unwrap()).join("index.html");
+				let response = NamedFile::open_async(index).await?.into_response(&req);
+
+				Ok(ServiceResponse::new(req, response))
+			})),
+	);
+}
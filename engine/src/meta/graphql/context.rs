@@ -1,18 +1,56 @@
-use crate::lib::database::DATABASE;
+use anyhow::Error;
+use arc_swap::ArcSwap;
 use rust_arango::Database as ArangoDatabase;
+use serde_json::Value as JsonValue;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::api::schema::{apply_schema_change, Schema as ApiSchema};
+use crate::lib::database::DATABASE;
+use crate::lib::schema::migrate;
 
 pub struct Context {
 	pub authenticated: bool,
 	pub database: ArangoDatabase,
+	/// Schema of the API, reloaded by the mutations changing it
+	pub api_schema: Arc<ArcSwap<ApiSchema>>,
+	/// Recorded on the migrations of the schema changes
+	pub author: Option<String>,
 }
 
 impl Context {
-	pub async fn new() -> Context {
+	pub async fn new(api_schema: Arc<ArcSwap<ApiSchema>>) -> Context {
 		Context {
 			authenticated: false,
-			database: DATABASE.get().await.database.clone(),
+			database: DATABASE.get().await.database(),
+			api_schema,
+			author: None,
 		}
 	}
+
+	/// Reloads the API schema of every instance when a change succeeded, so its types are served
+	/// right away
+	pub async fn apply_change(&self, result: Result<(), Error>) -> bool {
+		match result {
+			Ok(_) => {
+				apply_schema_change(&self.api_schema).await;
+
+				true
+			}
+			Err(_) => false,
+		}
+	}
+
+	/// Applies a schema change recorded as the migration `name`, `up` holding its arguments
+	pub async fn migrate(
+		&self,
+		name: String,
+		up: JsonValue,
+		change: impl Future<Output = Result<(), Error>>,
+	) -> bool {
+		self.apply_change(migrate(name, self.author.clone(), up, change).await)
+			.await
+	}
 }
 
 impl juniper::Context for Context {}
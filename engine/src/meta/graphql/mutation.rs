@@ -1,29 +1,773 @@
 use super::Context;
 
-use crate::lib::database::arango::{create_collection, delete_collection};
-use crate::lib::schema::SchemaDocumentProperty;
+use chrono::Utc;
+use juniper::FieldResult;
+use serde_json::json;
+
+use crate::api::persisted::PERSISTED_QUERIES;
+use crate::api::scheduler::execute_cron_run;
+use crate::api::schema::apply_schema_change;
+use crate::lib::database::arango::{
+	add_field, alter_field, create_collection, create_graph, create_relationship,
+	delete_collection, delete_relationship, remove_field, rename_collection, rename_field,
+	set_collection_cache, set_collection_deprecation, set_collection_description,
+	set_collection_exposure, set_collection_publication, set_collection_singleton,
+	set_collection_ttl, set_collection_versioning, set_translatable_fields, set_validation_level,
+};
+use crate::lib::database::schema::{
+	SchemaOnDelete, SchemaProperty, SchemaRelationshipType, SchemaValidationLevel,
+};
+use crate::lib::events::{
+	delete_webhook, retry_dead_events, save_webhook, AlchemyWebhookEntry, EventOperation,
+	WebhookHeader,
+};
+use crate::lib::plugins::{delete_plugin, save_plugin};
+use crate::lib::scheduler::{
+	delete_cron_job, get_cron_job, save_cron_job, start_cron_run, AlchemyCronJob, CronAction,
+};
+use crate::lib::schema::properties::build_object_rule;
+use crate::lib::schema::{
+	apply_schema_changes, create_backup_job, delete_action, delete_hook, diff_schema,
+	import_schema, parse_schema, rollback_migrations, run_backup, run_restore, run_seeds,
+	save_action, save_hook, AlchemyActionEntry, AlchemyHookEntry, BackupOperation, HookStage,
+	SchemaActionKind, SchemaCollectionSettings, SchemaComputedProperty, SchemaDocumentProperty,
+	SchemaDocumentPropertyValues, SchemaExport, SchemaTtl, SchemaVariant, SchemaVersioning,
+};
+use crate::lib::storage::delete_file;
+use crate::lib::CONFIG;
 
 pub struct Mutation;
 
 #[juniper::graphql_object(context = Context)]
 impl Mutation {
 	pub async fn create_collection(
-		_context: &Context,
+		context: &Context,
 		#[graphql] name: String,
 		#[graphql] properties: Vec<SchemaDocumentProperty>,
+		#[graphql] discriminator: Option<String>,
+		#[graphql] variants: Option<Vec<SchemaVariant>>,
+		#[graphql] computed: Option<Vec<SchemaComputedProperty>>,
+		#[graphql] ttl: Option<SchemaTtl>,
+		#[graphql] settings: Option<SchemaCollectionSettings>,
+		#[graphql] validation_level: Option<SchemaValidationLevel>,
 	) -> bool {
-		return if let Ok(_) = create_collection(name, properties).await {
-			true
-		} else {
-			false
-		};
+		let variants = variants.unwrap_or_default();
+		let computed = computed.unwrap_or_default();
+
+		context
+			.migrate(
+				format!("create collection {}", name),
+				json!({ "createCollection": {
+					"name": name,
+					"properties": properties,
+					"discriminator": discriminator,
+					"variants": variants,
+					"computed": computed,
+					"ttl": ttl,
+					"settings": settings,
+					"validationLevel": validation_level,
+				} }),
+				create_collection(
+					name,
+					properties,
+					discriminator,
+					variants,
+					computed,
+					ttl,
+					settings,
+					validation_level,
+				),
+			)
+			.await
+	}
+
+	/// Relates two collections through the `edge` collection, whose documents can hold
+	/// attributes of their own. The `inverse` field is generated on `to` unless
+	/// `generateInverse` is false. `onDelete` applies to the `to` documents when a `from`
+	/// document is removed, edges are removed either way. Polymorphic relationships relate to
+	/// any of the `targets` collections, `to` being the name of their union type
+	pub async fn create_relationship(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] from: String,
+		#[graphql] to: String,
+		#[graphql] relationship_type: SchemaRelationshipType,
+		#[graphql] edge: String,
+		#[graphql] properties: Option<Vec<SchemaDocumentProperty>>,
+		#[graphql] inverse: Option<String>,
+		#[graphql] generate_inverse: Option<bool>,
+		#[graphql] on_delete: Option<SchemaOnDelete>,
+		#[graphql] targets: Option<Vec<String>>,
+		#[graphql] description: Option<String>,
+	) -> bool {
+		let properties = properties.unwrap_or_default();
+
+		context
+			.migrate(
+				format!("create relationship {}.{}", from, name),
+				json!({ "createRelationship": {
+					"name": name,
+					"from": from,
+					"to": to,
+					"relationshipType": relationship_type,
+					"edge": edge,
+					"properties": properties,
+					"inverse": inverse,
+					"generateInverse": generate_inverse,
+					"onDelete": on_delete,
+					"targets": targets,
+					"description": description,
+				} }),
+				create_relationship(
+					name,
+					from,
+					to,
+					relationship_type,
+					edge,
+					properties,
+					inverse,
+					generate_inverse,
+					on_delete,
+					targets,
+					description,
+				),
+			)
+			.await
+	}
+
+	/// Creates a named graph from the edge collections of relationships, enabling the
+	/// neighbors and shortest path queries for its collections
+	pub async fn create_graph(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] edges: Vec<String>,
+	) -> bool {
+		context
+			.migrate(
+				format!("create graph {}", name),
+				json!({ "createGraph": { "name": name, "edges": edges } }),
+				create_graph(name, edges),
+			)
+			.await
+	}
+
+	/// Drops the collection along with its relationships
+	pub async fn delete_collection(context: &Context, #[graphql] name: String) -> bool {
+		context
+			.migrate(
+				format!("delete collection {}", name),
+				json!({ "deleteCollection": { "name": name } }),
+				delete_collection(name),
+			)
+			.await
+	}
+
+	/// Renames a collection, its documents, relationships and graphs follow. Its type in the
+	/// API is named after the new name
+	pub async fn rename_collection(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] new_name: String,
+	) -> bool {
+		context
+			.migrate(
+				format!("rename collection {} to {}", name, new_name),
+				json!({ "renameCollection": { "name": name, "newName": new_name } }),
+				rename_collection(name, new_name),
+			)
+			.await
+	}
+
+	pub async fn delete_relationship(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] from: String,
+	) -> bool {
+		context
+			.migrate(
+				format!("delete relationship {}.{}", from, name),
+				json!({ "deleteRelationship": { "name": name, "from": from } }),
+				delete_relationship(name, from),
+			)
+			.await
+	}
+
+	/// Sets the expiry of the documents of a collection, or removes it when `ttl` is null.
+	/// The TTL field is read-only and set on create to when the document expires
+	pub async fn set_collection_ttl(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] ttl: Option<SchemaTtl>,
+	) -> bool {
+		context
+			.migrate(
+				format!("set ttl of {}", collection),
+				json!({ "setCollectionTtl": { "collection": collection, "ttl": ttl } }),
+				set_collection_ttl(collection, ttl),
+			)
+			.await
+	}
+
+	/// Caches the API responses and the query operation results reading a collection for
+	/// `maxAge` seconds, or stops caching them when it's null. They are invalidated once the API
+	/// writes to the collection
+	pub async fn set_collection_cache(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] max_age: Option<i32>,
+	) -> bool {
+		context
+			.migrate(
+				format!("set cache of {}", collection),
+				json!({ "setCollectionCache": { "collection": collection, "maxAge": max_age } }),
+				set_collection_cache(collection, max_age),
+			)
+			.await
+	}
+
+	/// Keeps the prior versions of the documents of a collection in `{collection}_revisions`
+	/// on update and delete, exposed by their `revisions` field and the `get{Entity}Revisions`
+	/// query and restored by the `revert{Entity}` mutation, removed documents being created
+	/// again. Stops recording them when `versioning` is null
+	pub async fn set_collection_versioning(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] versioning: Option<SchemaVersioning>,
+	) -> bool {
+		context
+			.migrate(
+				format!("set versioning of {}", collection),
+				json!({ "setCollectionVersioning": { "collection": collection, "versioning": versioning } }),
+				set_collection_versioning(collection, versioning),
+			)
+			.await
+	}
+
+	/// Gives the documents of a collection a draft, published or archived `status`, the API
+	/// only reading published ones unless a query sets `preview: true`. New documents are
+	/// drafts, published by the `publish{Entity}` mutation
+	pub async fn set_collection_publication(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] enabled: bool,
+	) -> bool {
+		context
+			.migrate(
+				format!("set publication of {}", collection),
+				json!({ "setCollectionPublication": { "collection": collection, "enabled": enabled } }),
+				set_collection_publication(collection, enabled),
+			)
+			.await
+	}
+
+	/// Makes a collection hold a single document, e.g. global settings, exposed by the API as
+	/// `{collection}` and `update{Collection}` instead of the operations on documents. Fails
+	/// when the collection holds several documents
+	pub async fn set_collection_singleton(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] enabled: bool,
+	) -> bool {
+		context
+			.migrate(
+				format!("set singleton of {}", collection),
+				json!({ "setCollectionSingleton": { "collection": collection, "enabled": enabled } }),
+				set_collection_singleton(collection, enabled),
+			)
+			.await
+	}
+
+	/// Documents the type of a collection in the API, or removes its description when there's
+	/// none. Fields and enum values are documented by their `description` and
+	/// `enumDescriptions`
+	pub async fn set_collection_description(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] description: Option<String>,
+	) -> bool {
+		context
+			.migrate(
+				format!("set description of {}", collection),
+				json!({ "setCollectionDescription": { "collection": collection, "description": description } }),
+				set_collection_description(collection, description),
+			)
+			.await
+	}
+
+	/// Adds a collection to the API or leaves it out, without its types, operations and the
+	/// relationships to it. Collections are in the API unless `API_ALLOWLIST` is set when
+	/// `exposed` is null, while `API_EXCLUDED_COLLECTIONS` are always left out
+	pub async fn set_collection_exposure(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] exposed: Option<bool>,
+	) -> bool {
+		context
+			.migrate(
+				format!("set exposure of {}", collection),
+				json!({ "setCollectionExposure": { "collection": collection, "exposed": exposed } }),
+				set_collection_exposure(collection, exposed),
+			)
+			.await
+	}
+
+	/// Marks the operations of a collection `@deprecated` in the API with the reason, or no
+	/// longer deprecated when there's none. Fields are deprecated by their `deprecationReason`
+	pub async fn set_collection_deprecation(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] reason: Option<String>,
+	) -> bool {
+		context
+			.migrate(
+				format!("set deprecation of {}", collection),
+				json!({ "setCollectionDeprecation": { "collection": collection, "reason": reason } }),
+				set_collection_deprecation(collection, reason),
+			)
+			.await
+	}
+
+	/// Makes the fields of a collection hold a value per locale, read and written in the locale
+	/// given by the `locale` argument of the API operations. Fields left out of `fields` hold
+	/// a single value again
+	pub async fn set_translatable_fields(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] fields: Vec<String>,
+	) -> bool {
+		context
+			.migrate(
+				format!("set translatable fields of {}", collection),
+				json!({ "setTranslatableFields": { "collection": collection, "fields": fields } }),
+				set_translatable_fields(collection, fields),
+			)
+			.await
+	}
+
+	/// Changes which documents are validated against the schema of a collection, so existing
+	/// documents can be migrated gradually with a `MODERATE` or `NEW` level
+	pub async fn set_validation_level(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] level: SchemaValidationLevel,
+	) -> bool {
+		context
+			.migrate(
+				format!("set validation level of {}", collection),
+				json!({ "setValidationLevel": { "collection": collection, "level": level } }),
+				set_validation_level(collection, level),
+			)
+			.await
+	}
+
+	pub async fn add_field(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] property: SchemaDocumentProperty,
+	) -> bool {
+		context
+			.migrate(
+				format!("add field {}.{}", collection, property.name),
+				json!({ "addField": { "collection": collection, "property": property } }),
+				add_field(collection, property),
+			)
+			.await
+	}
+
+	/// Replaces the type and constraints of a field
+	pub async fn alter_field(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] property: SchemaDocumentProperty,
+	) -> bool {
+		context
+			.migrate(
+				format!("alter field {}.{}", collection, property.name),
+				json!({ "alterField": { "collection": collection, "property": property } }),
+				alter_field(collection, property),
+			)
+			.await
 	}
 
-	pub async fn delete_collection(_context: &Context, #[graphql] name: String) -> bool {
-		return if let Ok(_) = delete_collection(name).await {
-			true
-		} else {
-			false
+	/// Renames a field, existing documents keep their value under the new name. Variants,
+	/// permissions and the TTL refer to the new name
+	pub async fn rename_field(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] name: String,
+		#[graphql] new_name: String,
+	) -> bool {
+		context
+			.migrate(
+				format!("rename field {}.{} to {}", collection, name, new_name),
+				json!({ "renameField": {
+					"collection": collection,
+					"name": name,
+					"newName": new_name,
+				} }),
+				rename_field(collection, name, new_name),
+			)
+			.await
+	}
+
+	/// Removes a field, its value is removed from the existing documents
+	pub async fn remove_field(
+		context: &Context,
+		#[graphql] collection: String,
+		#[graphql] name: String,
+	) -> bool {
+		context
+			.migrate(
+				format!("remove field {}.{}", collection, name),
+				json!({ "removeField": { "collection": collection, "name": name } }),
+				remove_field(collection, name),
+			)
+			.await
+	}
+
+	/// Brings the schema to the YAML or JSON schema `definition`, returning the applied
+	/// changes. Collections, fields and relationships it doesn't declare are removed when
+	/// `prune` is set
+	pub async fn apply_schema(
+		context: &Context,
+		#[graphql] definition: String,
+		#[graphql] prune: Option<bool>,
+	) -> FieldResult<Vec<String>> {
+		let declared = parse_schema(&definition)?;
+		let changes = diff_schema(&declared, prune.unwrap_or(false)).await;
+		let applied: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+
+		let result = apply_schema_changes(changes, context.author.clone()).await;
+
+		// Changes applied before a failing one are kept
+		apply_schema_change(&context.api_schema).await;
+
+		result?;
+
+		Ok(applied)
+	}
+
+	/// Imports a document from `exportSchema` into an instance that has none of its
+	/// collections and relationships, e.g. to promote a schema from staging to production
+	pub async fn import_schema(
+		context: &Context,
+		#[graphql] document: String,
+	) -> FieldResult<bool> {
+		let export: SchemaExport = serde_json::from_str(&document)?;
+
+		Ok(context
+			.migrate(
+				format!("import schema of {} collections", export.collections.len()),
+				json!({ "importSchema": { "alchemyVersion": export.alchemy_version } }),
+				import_schema(export),
+			)
+			.await)
+	}
+
+	/// Rolls back the last `count` schema migrations, returning their names. Collections and
+	/// graphs they created are dropped, while removed fields and the documents of deleted
+	/// collections can't be restored
+	pub async fn rollback_migrations(
+		context: &Context,
+		#[graphql] count: i32,
+	) -> FieldResult<Vec<String>> {
+		let result = rollback_migrations(count.max(0) as u32).await;
+
+		apply_schema_change(&context.api_schema).await;
+
+		Ok(result?)
+	}
+
+	/// Upserts the seed files of `SEEDS_DIR`, or only those of the given collections, returning
+	/// how many documents were inserted and updated by collection
+	pub async fn run_seeds(
+		_context: &Context,
+		#[graphql] collections: Option<Vec<String>>,
+	) -> FieldResult<Vec<String>> {
+		let directory = CONFIG
+			.get_seeds_dir()
+			.ok_or("SEEDS_DIR is not configured")?;
+
+		Ok(run_seeds(directory, collections.as_deref())
+			.await?
+			.iter()
+			.map(|r| r.to_string())
+			.collect())
+	}
+
+	/// Starts a backup of the schema and documents into a new directory of `BACKUP_DIR`,
+	/// returning the id of its job in `backupJobs`
+	pub async fn create_backup(_context: &Context) -> FieldResult<String> {
+		let directory = CONFIG
+			.get_backup_dir()
+			.ok_or("BACKUP_DIR is not configured")?
+			.clone();
+
+		let name = Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string();
+		let job = create_backup_job(&name, BackupOperation::Backup).await?;
+
+		actix_web::rt::spawn(run_backup(directory, name, job.clone()));
+
+		Ok(job)
+	}
+
+	/// Starts restoring a backup of `BACKUP_DIR`, returning the id of its job in `backupJobs`.
+	/// Its collections are dropped first when `dropExisting` is set, otherwise none of them
+	/// can exist
+	pub async fn restore_backup(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] drop_existing: Option<bool>,
+	) -> FieldResult<String> {
+		let directory = CONFIG
+			.get_backup_dir()
+			.ok_or("BACKUP_DIR is not configured")?
+			.clone();
+
+		let job = create_backup_job(&name, BackupOperation::Restore).await?;
+
+		let api_schema = context.api_schema.clone();
+		let author = context.author.clone();
+		let key = job.clone();
+
+		actix_web::rt::spawn(async move {
+			run_restore(directory, name, key, drop_existing.unwrap_or(false), author).await;
+
+			apply_schema_change(&api_schema).await;
+		});
+
+		Ok(job)
+	}
+
+	/// Creates a webhook receiving the `events` of the collection as JSON POST requests, or
+	/// replaces the one with the same name. Failed deliveries are retried `maxAttempts` times,
+	/// waiting `backoffSeconds` doubled after each attempt
+	pub async fn save_webhook(
+		_context: &Context,
+		#[graphql] name: String,
+		#[graphql] collection: String,
+		#[graphql] url: String,
+		#[graphql] events: Vec<EventOperation>,
+		#[graphql] headers: Option<Vec<WebhookHeader>>,
+		#[graphql] max_attempts: Option<i32>,
+		#[graphql] backoff_seconds: Option<i32>,
+	) -> FieldResult<bool> {
+		save_webhook(AlchemyWebhookEntry {
+			name,
+			collection,
+			url,
+			events,
+			headers: headers
+				.unwrap_or_default()
+				.into_iter()
+				.map(|h| (h.name, h.value))
+				.collect(),
+			max_attempts: max_attempts.unwrap_or(5).max(1) as u32,
+			backoff_seconds: backoff_seconds.unwrap_or(10).max(0) as u32,
+		})
+		.await?;
+
+		Ok(true)
+	}
+
+	pub async fn delete_webhook(_context: &Context, #[graphql] name: String) -> FieldResult<bool> {
+		delete_webhook(&name).await?;
+
+		Ok(true)
+	}
+
+	/// Queues the dead events again, all of them when no ids are given, returning how many were
+	/// queued
+	pub async fn retry_dead_events(
+		_context: &Context,
+		#[graphql] ids: Option<Vec<String>>,
+	) -> FieldResult<i32> {
+		Ok(retry_dead_events(ids).await? as i32)
+	}
+
+	/// Adds the query or mutation field `name` to the API, resolved by POSTing its arguments
+	/// and the session to `url` or by calling the `plugin` with them. It replaces the action
+	/// with the same name
+	pub async fn save_action(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] kind: SchemaActionKind,
+		#[graphql] url: Option<String>,
+		#[graphql] plugin: Option<String>,
+		#[graphql] arguments: Option<Vec<SchemaDocumentProperty>>,
+		#[graphql] output: SchemaDocumentPropertyValues,
+		#[graphql] headers: Option<Vec<WebhookHeader>>,
+		#[graphql] roles: Option<Vec<String>>,
+	) -> FieldResult<bool> {
+		let (properties, required) = build_object_rule(arguments.unwrap_or_default());
+
+		save_action(AlchemyActionEntry {
+			name,
+			kind,
+			url,
+			plugin,
+			headers: headers
+				.unwrap_or_default()
+				.into_iter()
+				.map(|h| (h.name, h.value))
+				.collect(),
+			arguments: json!({ "properties": properties, "required": required }),
+			output: serde_json::to_value(SchemaProperty::from(output))?,
+			roles: roles.unwrap_or_default(),
+		})
+		.await?;
+
+		apply_schema_change(&context.api_schema).await;
+
+		Ok(true)
+	}
+
+	pub async fn delete_action(context: &Context, #[graphql] name: String) -> FieldResult<bool> {
+		delete_action(&name).await?;
+
+		apply_schema_change(&context.api_schema).await;
+
+		Ok(true)
+	}
+
+	/// Calls `url` or the `plugin` around the `operations` of the collection, or replaces the
+	/// hook with the same name. `BEFORE` hooks receive the input and can respond with a new `input`, or reject the
+	/// mutation with an error status and `message`. `AFTER` hooks receive the changed documents
+	pub async fn save_hook(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] collection: String,
+		#[graphql] stage: HookStage,
+		#[graphql] operations: Vec<EventOperation>,
+		#[graphql] url: Option<String>,
+		#[graphql] plugin: Option<String>,
+		#[graphql] headers: Option<Vec<WebhookHeader>>,
+	) -> FieldResult<bool> {
+		save_hook(AlchemyHookEntry {
+			name,
+			collection,
+			stage,
+			operations,
+			url,
+			plugin,
+			headers: headers
+				.unwrap_or_default()
+				.into_iter()
+				.map(|h| (h.name, h.value))
+				.collect(),
+		})
+		.await?;
+
+		apply_schema_change(&context.api_schema).await;
+
+		Ok(true)
+	}
+
+	pub async fn delete_hook(context: &Context, #[graphql] name: String) -> FieldResult<bool> {
+		delete_hook(&name).await?;
+
+		apply_schema_change(&context.api_schema).await;
+
+		Ok(true)
+	}
+
+	/// Uploads the base64 encoded WASM module of the plugin, or replaces the one with the same
+	/// name. Hooks and actions using it run the new module right away
+	pub async fn save_plugin(
+		context: &Context,
+		#[graphql] name: String,
+		#[graphql] wasm: String,
+	) -> FieldResult<bool> {
+		save_plugin(&name, base64::decode(wasm)?).await?;
+
+		apply_schema_change(&context.api_schema).await;
+
+		Ok(true)
+	}
+
+	pub async fn delete_plugin(context: &Context, #[graphql] name: String) -> FieldResult<bool> {
+		delete_plugin(&name).await?;
+
+		apply_schema_change(&context.api_schema).await;
+
+		Ok(true)
+	}
+
+	/// Removes the uploaded file from its storage, documents referencing it return no file
+	pub async fn delete_file(_context: &Context, #[graphql] id: String) -> FieldResult<bool> {
+		delete_file(&id).await?;
+
+		Ok(true)
+	}
+
+	/// Creates a job run on the cron `schedule`, or replaces the one with the same name. `target`
+	/// is the URL of a webhook or the AQL or GraphQL query, `variables` being a JSON object of
+	/// their variables
+	pub async fn save_cron_job(
+		_context: &Context,
+		#[graphql] name: String,
+		#[graphql] schedule: String,
+		#[graphql] action: CronAction,
+		#[graphql] target: String,
+		#[graphql] headers: Option<Vec<WebhookHeader>>,
+		#[graphql] variables: Option<String>,
+		#[graphql] enabled: Option<bool>,
+	) -> FieldResult<bool> {
+		let variables = match variables {
+			Some(variables) => serde_json::from_str(&variables)?,
+			None => serde_json::Map::new(),
 		};
+
+		save_cron_job(AlchemyCronJob {
+			name,
+			schedule,
+			action,
+			target,
+			headers: headers
+				.unwrap_or_default()
+				.into_iter()
+				.map(|h| (h.name, h.value))
+				.collect(),
+			variables,
+			enabled: enabled.unwrap_or(true),
+			last_run_at: None,
+		})
+		.await?;
+
+		Ok(true)
+	}
+
+	pub async fn delete_cron_job(_context: &Context, #[graphql] name: String) -> FieldResult<bool> {
+		delete_cron_job(&name).await?;
+
+		Ok(true)
+	}
+
+	/// Runs the cron job now without changing its schedule, returning the id of the run in
+	/// `cronRuns`
+	pub async fn run_cron_job(context: &Context, #[graphql] name: String) -> FieldResult<String> {
+		let job = get_cron_job(&name)
+			.await
+			.ok_or(format!("Cron job {} not found", name))?;
+
+		let scheduled_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+		let key = start_cron_run(&job.name, &scheduled_at).await?;
+
+		actix_web::rt::spawn(execute_cron_run(
+			context.api_schema.clone(),
+			job,
+			scheduled_at,
+			key.clone(),
+		));
+
+		Ok(key)
+	}
+
+	pub fn register_persisted_query(_context: &Context, #[graphql] query: String) -> String {
+		PERSISTED_QUERIES.register(query)
+	}
+
+	pub fn delete_persisted_query(_context: &Context, #[graphql] hash: String) -> bool {
+		PERSISTED_QUERIES.remove(&hash)
 	}
 }
@@ -1,10 +1,603 @@
 use super::Context;
 
+use juniper::FieldResult;
+use serde_json::Value as JsonValue;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::api::explain::explain_operation;
+use crate::api::persisted::PERSISTED_QUERIES;
+use crate::api::schema::directives::print_schema;
+use crate::api::stats::OPERATION_STATS;
+use crate::lib::audit::{get_audit_log, AuditLogFilter};
+use crate::lib::database::DATABASE;
+use crate::lib::events::{get_dead_events, get_webhooks, EventOperation};
+use crate::lib::plugins::get_all_plugins;
+use crate::lib::scheduler::{get_cron_jobs, get_cron_runs, CronAction, CronRunStatus};
+use crate::lib::schema::{
+	diff_schema, export_schema, get_all_actions, get_all_hooks, get_backup_jobs, get_migrations,
+	list_backups, parse_schema, AlchemyActionEntry, AlchemyHookEntry, BackupOperation,
+	BackupStatus, HookStage, SchemaActionKind,
+};
+use crate::lib::CONFIG;
+
 pub struct Query;
 
+#[derive(GraphQLObject)]
+pub struct PersistedQuery {
+	pub hash: String,
+	pub query: String,
+}
+
+/// Connections of the ArangoDB pool and the requests sent through it since startup
+#[derive(GraphQLObject)]
+pub struct DatabasePool {
+	pub size: i32,
+	pub hosts: Vec<String>,
+	/// Hosts of the connections to the read replicas
+	pub replica_hosts: Vec<String>,
+	pub requests: i32,
+	pub in_flight: i32,
+	pub retries: i32,
+	pub failures: i32,
+	pub timeouts: i32,
+}
+
+/// Executions of an API operation since startup, the latencies in milliseconds being of the
+/// latest ones
+#[derive(GraphQLObject)]
+pub struct OperationStats {
+	pub name: String,
+	pub count: i32,
+	pub errors: i32,
+	pub rows: i32,
+	pub p50: f64,
+	pub p95: f64,
+}
+
+/// Index used by a query plan
+#[derive(GraphQLObject)]
+pub struct ExplainIndex {
+	pub collection: String,
+	pub name: String,
+	#[graphql(name = "type")]
+	pub index_type: String,
+	pub fields: Vec<String>,
+}
+
+/// AQL generated by an API operation with ArangoDB's plan for it, `bindVars` and `plan` being
+/// JSON
+#[derive(GraphQLObject)]
+pub struct ExplainedQuery {
+	pub aql: String,
+	pub bind_vars: String,
+	pub plan: String,
+	pub estimated_cost: f64,
+	pub estimated_items: f64,
+	pub indexes: Vec<ExplainIndex>,
+	pub rules: Vec<String>,
+	pub warnings: Vec<String>,
+}
+
+/// An applied schema change, `up` and `down` being JSON
+#[derive(GraphQLObject)]
+pub struct Migration {
+	pub id: String,
+	pub name: String,
+	pub author: Option<String>,
+	pub timestamp: String,
+	pub up: String,
+	pub down: String,
+}
+
+/// Subscription of an URL to the changes of a collection, header values are left out as they
+/// usually hold secrets
+#[derive(GraphQLObject)]
+pub struct Webhook {
+	pub name: String,
+	pub collection: String,
+	pub url: String,
+	pub events: Vec<EventOperation>,
+	pub headers: Vec<String>,
+	pub max_attempts: i32,
+	pub backoff_seconds: i32,
+}
+
+/// A document changed by a mutation, `diff`, `old` and `new` being JSON. `diff` holds the
+/// old and new values of the changed attributes
+#[derive(GraphQLObject)]
+pub struct AuditLogEntry {
+	pub id: String,
+	pub collection: String,
+	pub key: String,
+	pub operation: EventOperation,
+	pub user: Option<String>,
+	pub role: String,
+	pub timestamp: String,
+	pub diff: String,
+	pub old: Option<String>,
+	pub new: Option<String>,
+}
+
+/// An event whose deliveries failed every attempt, `old` and `new` being JSON
+#[derive(GraphQLObject)]
+pub struct DeadEvent {
+	pub id: String,
+	pub webhook: String,
+	pub collection: String,
+	pub operation: EventOperation,
+	pub old: Option<String>,
+	pub new: Option<String>,
+	pub attempts: i32,
+	pub error: Option<String>,
+	pub created_at: String,
+	pub failed_at: String,
+}
+
+/// Custom field of the API resolved by an HTTP endpoint or a plugin, `arguments` and `output`
+/// being their JSON schemas. Header values are left out
+#[derive(GraphQLObject)]
+pub struct Action {
+	pub name: String,
+	pub kind: SchemaActionKind,
+	pub url: Option<String>,
+	pub plugin: Option<String>,
+	pub headers: Vec<String>,
+	pub arguments: String,
+	pub output: String,
+	pub roles: Vec<String>,
+}
+
+/// Handler called synchronously around the mutations of a collection. Header values are left
+/// out
+#[derive(GraphQLObject)]
+pub struct Hook {
+	pub name: String,
+	pub collection: String,
+	pub stage: HookStage,
+	pub operations: Vec<EventOperation>,
+	pub url: Option<String>,
+	pub plugin: Option<String>,
+	pub headers: Vec<String>,
+}
+
+/// WASM module running in-process as the handler of hooks and actions, `size` being in bytes
+#[derive(GraphQLObject)]
+pub struct Plugin {
+	pub name: String,
+	pub hash: String,
+	pub size: i32,
+	pub updated_at: String,
+}
+
+/// A job run on a cron schedule, `variables` being JSON
+#[derive(GraphQLObject)]
+pub struct CronJob {
+	pub name: String,
+	pub schedule: String,
+	pub action: CronAction,
+	pub target: String,
+	pub headers: Vec<String>,
+	pub variables: String,
+	pub enabled: bool,
+	pub last_run_at: Option<String>,
+	pub next_run_at: Option<String>,
+}
+
+#[derive(GraphQLObject)]
+pub struct CronRun {
+	pub id: String,
+	pub job: String,
+	pub status: CronRunStatus,
+	pub scheduled_at: String,
+	pub started_at: String,
+	pub finished_at: Option<String>,
+	pub output: Option<String>,
+	pub error: Option<String>,
+}
+
+/// Progress of a backup or restore running in the background
+#[derive(GraphQLObject)]
+pub struct BackupJob {
+	pub id: String,
+	pub backup: String,
+	pub operation: BackupOperation,
+	pub status: BackupStatus,
+	pub started_at: String,
+	pub finished_at: Option<String>,
+	pub total_collections: i32,
+	pub processed_collections: i32,
+	pub documents: i32,
+	pub error: Option<String>,
+}
+
 #[juniper::graphql_object(context = Context)]
 impl Query {
 	fn alchemy_version() -> &'static str {
 		return env!("CARGO_PKG_VERSION");
 	}
+
+	fn persisted_queries(_context: &Context) -> Vec<PersistedQuery> {
+		PERSISTED_QUERIES
+			.get_all()
+			.into_iter()
+			.map(|(hash, query)| PersistedQuery { hash, query })
+			.collect()
+	}
+
+	async fn database_pool(_context: &Context) -> DatabasePool {
+		let pool = DATABASE.get().await;
+		let count = |c: &AtomicUsize| c.load(Ordering::Relaxed) as i32;
+
+		DatabasePool {
+			size: pool.connections.len() as i32,
+			hosts: pool.connections.iter().map(|c| c.host.clone()).collect(),
+			replica_hosts: pool.replicas.iter().map(|c| c.host.clone()).collect(),
+			requests: count(&pool.metrics.requests),
+			in_flight: count(&pool.metrics.in_flight),
+			retries: count(&pool.metrics.retries),
+			failures: count(&pool.metrics.failures),
+			timeouts: count(&pool.metrics.timeouts),
+		}
+	}
+
+	/// The complete schema as a JSON document, to be imported with `importSchema`
+	async fn export_schema(_context: &Context) -> FieldResult<String> {
+		Ok(serde_json::to_string_pretty(&export_schema().await?)?)
+	}
+
+	/// GraphQL SDL of the API generated from the schema
+	fn export_sdl(context: &Context) -> String {
+		print_schema(&context.api_schema.load())
+	}
+
+	/// Execution stats of the API operations, sorted by name
+	fn operation_stats(_context: &Context) -> Vec<OperationStats> {
+		OPERATION_STATS
+			.get_snapshots()
+			.into_iter()
+			.map(|s| OperationStats {
+				name: s.name,
+				count: s.count as i32,
+				errors: s.errors as i32,
+				rows: s.rows as i32,
+				p50: s.p50.as_secs_f64() * 1000.0,
+				p95: s.p95.as_secs_f64() * 1000.0,
+			})
+			.collect()
+	}
+
+	/// Plans of the AQL queries the query operation generates for the arguments and selection,
+	/// without executing them. `args` and `selection` are written as in a GraphQL document,
+	/// e.g. `where: { age: { gt: 18 } }` and `{ name posts { title } }`
+	async fn explain_operation(
+		context: &Context,
+		#[graphql] operation: String,
+		#[graphql] args: Option<String>,
+		#[graphql] selection: Option<String>,
+	) -> FieldResult<Vec<ExplainedQuery>> {
+		let schema = context.api_schema.load_full();
+
+		let explained =
+			explain_operation(&schema, &operation, args.as_deref(), selection.as_deref()).await?;
+
+		Ok(explained
+			.into_iter()
+			.map(|q| {
+				let plan = &q.explain["plan"];
+				let strings = |value: &JsonValue| -> Vec<String> {
+					value
+						.as_array()
+						.into_iter()
+						.flatten()
+						.filter_map(|v| v.as_str().map(String::from))
+						.collect()
+				};
+
+				let indexes = plan["nodes"]
+					.as_array()
+					.into_iter()
+					.flatten()
+					.flat_map(|node| {
+						node["indexes"]
+							.as_array()
+							.into_iter()
+							.flatten()
+							.map(move |index| ExplainIndex {
+								collection: node["collection"]
+									.as_str()
+									.unwrap_or_default()
+									.to_string(),
+								name: index["name"].as_str().unwrap_or_default().to_string(),
+								index_type: index["type"].as_str().unwrap_or_default().to_string(),
+								fields: strings(&index["fields"]),
+							})
+					})
+					.collect();
+
+				ExplainedQuery {
+					aql: q.aql,
+					bind_vars: q.bind_vars.to_string(),
+					plan: plan.to_string(),
+					estimated_cost: plan["estimatedCost"].as_f64().unwrap_or_default(),
+					estimated_items: plan["estimatedNrItems"].as_f64().unwrap_or_default(),
+					indexes,
+					rules: strings(&plan["rules"]),
+					warnings: q.explain["warnings"]
+						.as_array()
+						.into_iter()
+						.flatten()
+						.filter_map(|w| w["message"].as_str().map(String::from))
+						.collect(),
+				}
+			})
+			.collect())
+	}
+
+	/// Applied schema migrations, the latest first
+	async fn migrations(_context: &Context, #[graphql] limit: Option<i32>) -> Vec<Migration> {
+		get_migrations(limit.map(|l| l.max(0) as u32))
+			.await
+			.into_iter()
+			.map(|m| Migration {
+				id: m["_key"].as_str().unwrap_or_default().to_string(),
+				name: m["name"].as_str().unwrap_or_default().to_string(),
+				author: m["author"].as_str().map(String::from),
+				timestamp: m["timestamp"].as_str().unwrap_or_default().to_string(),
+				up: m["up"].to_string(),
+				down: m["down"].to_string(),
+			})
+			.collect()
+	}
+
+	/// Backups of `BACKUP_DIR` that can be restored, the latest first
+	fn backups(_context: &Context) -> FieldResult<Vec<String>> {
+		let directory = CONFIG
+			.get_backup_dir()
+			.ok_or("BACKUP_DIR is not configured")?;
+
+		Ok(list_backups(directory)?)
+	}
+
+	/// Backup and restore jobs, the latest first
+	async fn backup_jobs(
+		_context: &Context,
+		#[graphql] limit: Option<i32>,
+	) -> FieldResult<Vec<BackupJob>> {
+		let mut jobs = Vec::new();
+
+		for job in get_backup_jobs(limit.map(|l| l.max(0) as u32)).await {
+			let count = |name: &str| job[name].as_i64().unwrap_or_default() as i32;
+
+			jobs.push(BackupJob {
+				id: job["_key"].as_str().unwrap_or_default().to_string(),
+				backup: job["backup"].as_str().unwrap_or_default().to_string(),
+				operation: serde_json::from_value(job["operation"].clone())?,
+				status: serde_json::from_value(job["status"].clone())?,
+				started_at: job["startedAt"].as_str().unwrap_or_default().to_string(),
+				finished_at: job["finishedAt"].as_str().map(String::from),
+				total_collections: count("totalCollections"),
+				processed_collections: count("processedCollections"),
+				documents: count("documents"),
+				error: job["error"].as_str().map(String::from),
+			});
+		}
+
+		Ok(jobs)
+	}
+
+	async fn webhooks(_context: &Context) -> Vec<Webhook> {
+		get_webhooks()
+			.await
+			.into_iter()
+			.map(|w| Webhook {
+				name: w.name,
+				collection: w.collection,
+				url: w.url,
+				events: w.events,
+				headers: w.headers.into_keys().collect(),
+				max_attempts: w.max_attempts as i32,
+				backoff_seconds: w.backoff_seconds as i32,
+			})
+			.collect()
+	}
+
+	/// Events whose deliveries failed every attempt, the latest first
+	async fn dead_events(
+		_context: &Context,
+		#[graphql] limit: Option<i32>,
+	) -> FieldResult<Vec<DeadEvent>> {
+		let mut events = Vec::new();
+
+		for event in get_dead_events(limit.map(|l| l.max(0) as u32)).await {
+			let string = |name: &str| event[name].as_str().unwrap_or_default().to_string();
+			let document = |name: &str| {
+				Some(&event[name])
+					.filter(|d| !d.is_null())
+					.map(|d| d.to_string())
+			};
+
+			events.push(DeadEvent {
+				id: string("_key"),
+				webhook: string("webhook"),
+				collection: string("collection"),
+				operation: serde_json::from_value(event["operation"].clone())?,
+				old: document("old"),
+				new: document("new"),
+				attempts: event["attempts"].as_i64().unwrap_or_default() as i32,
+				error: event["error"].as_str().map(String::from),
+				created_at: string("createdAt"),
+				failed_at: string("failedAt"),
+			});
+		}
+
+		Ok(events)
+	}
+
+	/// Mutations recorded in the audit log, the latest first. `since` and `until` are RFC 3339
+	/// times
+	async fn audit_log(
+		_context: &Context,
+		#[graphql] collection: Option<String>,
+		#[graphql] key: Option<String>,
+		#[graphql] user: Option<String>,
+		#[graphql] operation: Option<EventOperation>,
+		#[graphql] since: Option<String>,
+		#[graphql] until: Option<String>,
+		#[graphql] limit: Option<i32>,
+	) -> FieldResult<Vec<AuditLogEntry>> {
+		let filter = AuditLogFilter {
+			collection,
+			key,
+			user,
+			operation,
+			since,
+			until,
+			limit: limit.map(|l| l.max(0) as u32),
+		};
+
+		let mut entries = Vec::new();
+
+		for entry in get_audit_log(filter).await? {
+			let string = |name: &str| entry[name].as_str().unwrap_or_default().to_string();
+			let document = |name: &str| {
+				Some(&entry[name])
+					.filter(|d| !d.is_null())
+					.map(|d| d.to_string())
+			};
+
+			entries.push(AuditLogEntry {
+				id: string("_key"),
+				collection: string("collection"),
+				key: string("key"),
+				operation: serde_json::from_value(entry["operation"].clone())?,
+				user: entry["user"].as_str().map(String::from),
+				role: string("role"),
+				timestamp: string("timestamp"),
+				diff: entry["diff"].to_string(),
+				old: document("old"),
+				new: document("new"),
+			});
+		}
+
+		Ok(entries)
+	}
+
+	async fn actions(_context: &Context) -> FieldResult<Vec<Action>> {
+		let mut actions = Vec::new();
+
+		for entry in get_all_actions().await {
+			let action: AlchemyActionEntry = serde_json::from_value(entry)?;
+
+			actions.push(Action {
+				name: action.name,
+				kind: action.kind,
+				url: action.url,
+				plugin: action.plugin,
+				headers: action.headers.into_keys().collect(),
+				arguments: action.arguments.to_string(),
+				output: action.output.to_string(),
+				roles: action.roles,
+			});
+		}
+
+		Ok(actions)
+	}
+
+	async fn hooks(_context: &Context) -> FieldResult<Vec<Hook>> {
+		let mut hooks = Vec::new();
+
+		for entry in get_all_hooks().await {
+			let hook: AlchemyHookEntry = serde_json::from_value(entry)?;
+
+			hooks.push(Hook {
+				name: hook.name,
+				collection: hook.collection,
+				stage: hook.stage,
+				operations: hook.operations,
+				url: hook.url,
+				plugin: hook.plugin,
+				headers: hook.headers.into_keys().collect(),
+			});
+		}
+
+		Ok(hooks)
+	}
+
+	async fn plugins(_context: &Context) -> Vec<Plugin> {
+		get_all_plugins()
+			.await
+			.into_iter()
+			.map(|p| Plugin {
+				name: p["name"].as_str().unwrap_or_default().to_string(),
+				hash: p["hash"].as_str().unwrap_or_default().to_string(),
+				size: p["size"].as_i64().unwrap_or_default() as i32,
+				updated_at: p["updatedAt"].as_str().unwrap_or_default().to_string(),
+			})
+			.collect()
+	}
+
+	async fn cron_jobs(_context: &Context) -> Vec<CronJob> {
+		get_cron_jobs()
+			.await
+			.into_iter()
+			.map(|j| CronJob {
+				next_run_at: j
+					.enabled
+					.then(|| j.get_next_run())
+					.flatten()
+					.map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+				name: j.name,
+				schedule: j.schedule,
+				action: j.action,
+				target: j.target,
+				headers: j.headers.into_keys().collect(),
+				variables: serde_json::Value::Object(j.variables).to_string(),
+				enabled: j.enabled,
+				last_run_at: j.last_run_at,
+			})
+			.collect()
+	}
+
+	/// Runs of every cron job or of the given one, the latest first
+	async fn cron_runs(
+		_context: &Context,
+		#[graphql] job: Option<String>,
+		#[graphql] limit: Option<i32>,
+	) -> FieldResult<Vec<CronRun>> {
+		let mut runs = Vec::new();
+
+		for run in get_cron_runs(job, limit.map(|l| l.max(0) as u32)).await {
+			let string = |name: &str| run[name].as_str().unwrap_or_default().to_string();
+			let optional = |name: &str| run[name].as_str().map(String::from);
+
+			runs.push(CronRun {
+				id: string("_key"),
+				job: string("job"),
+				status: serde_json::from_value(run["status"].clone())?,
+				scheduled_at: string("scheduledAt"),
+				started_at: string("startedAt"),
+				finished_at: optional("finishedAt"),
+				output: optional("output"),
+				error: optional("error"),
+			});
+		}
+
+		Ok(runs)
+	}
+
+	/// Changes that applying the YAML or JSON schema `definition` would make, removals only
+	/// being listed when `prune` is set
+	async fn schema_diff(
+		_context: &Context,
+		#[graphql] definition: String,
+		#[graphql] prune: Option<bool>,
+	) -> FieldResult<Vec<String>> {
+		let declared = parse_schema(&definition)?;
+
+		Ok(diff_schema(&declared, prune.unwrap_or(false))
+			.await
+			.iter()
+			.map(|c| c.to_string())
+			.collect())
+	}
 }
@@ -4,18 +4,63 @@ use actix_web::{
 	web::{Data, Payload as ActixPayload},
 	Error as ActixError, HttpRequest as ActixRequest, HttpResponse as ActixResponse,
 };
+use arc_swap::ArcSwap;
 
 use juniper_actix::{graphql_handler, playground_handler};
 
+use crate::api::schema::Schema as ApiSchema;
+use crate::lib::CONFIG;
+
+pub const ADMIN_SECRET_HEADER: &str = "x-alchemy-admin-secret";
+pub const AUTHOR_HEADER: &str = "x-alchemy-author";
+
 pub async fn graphql_meta_route(
 	req: ActixRequest,
 	payload: ActixPayload,
 	schema: Data<Schema>,
+	api_schema: Data<ArcSwap<ApiSchema>>,
 ) -> Result<ActixResponse, ActixError> {
-	let context = Context::new().await;
+	if !has_admin_secret(&req) {
+		return Ok(ActixResponse::Unauthorized().json(serde_json::json!({
+			"errors": [{ "message": "Invalid or missing admin secret" }]
+		})));
+	}
+
+	let mut context = Context::new(api_schema.into_inner()).await;
+	context.authenticated = true;
+	context.author = req
+		.headers()
+		.get(AUTHOR_HEADER)
+		.and_then(|v| v.to_str().ok())
+		.map(String::from);
+
 	graphql_handler(&schema, &context, req, payload).await
 }
 
 pub async fn playground_meta_route() -> Result<ActixResponse, ActixError> {
 	playground_handler("/meta/graphql", Some("/meta/graphql_subscriptions")).await
 }
+
+fn has_admin_secret(req: &ActixRequest) -> bool {
+	let secret = CONFIG.admin_secret.as_bytes();
+
+	// An empty secret would leave the meta API open, so it never matches
+	if secret.is_empty() {
+		return false;
+	}
+
+	match req.headers().get(ADMIN_SECRET_HEADER) {
+		Some(value) => {
+			let value = value.as_bytes();
+
+			// Compare in constant time to avoid leaking the secret through timing
+			value.len() == secret.len()
+				&& value
+					.iter()
+					.zip(secret.iter())
+					.fold(0u8, |acc, (a, b)| acc | (a ^ b))
+					== 0
+		}
+		None => false,
+	}
+}